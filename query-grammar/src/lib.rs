@@ -8,6 +8,7 @@ mod user_input_ast;
 pub use crate::infallible::LenientError;
 pub use crate::occur::Occur;
 use crate::query_grammar::{parse_to_ast, parse_to_ast_lenient};
+pub use crate::query_grammar::{DEFAULT_FUZZY_DISTANCE, DEFAULT_NEAR_DISTANCE};
 pub use crate::user_input_ast::{
     Delimiter, UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral,
 };
@@ -5,7 +5,7 @@ use nom::bytes::complete::tag;
 use nom::character::complete::{
     anychar, char, digit1, none_of, one_of, satisfy, space0, space1, u32,
 };
-use nom::combinator::{eof, map, map_res, opt, peek, recognize, value, verify};
+use nom::combinator::{eof, map, map_opt, map_res, not, opt, peek, recognize, value, verify};
 use nom::error::{Error, ErrorKind};
 use nom::multi::{many0, many1, separated_list0, separated_list1};
 use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
@@ -22,6 +22,12 @@ const SPECIAL_CHARS: &[char] = &[
     '+', '^', '`', ':', '{', '}', '"', '[', ']', '(', ')', '!', '\\', '*', ' ',
 ];
 
+/// Distance used by a `NEAR` operator which does not specify one, as in `a NEAR b`.
+pub const DEFAULT_NEAR_DISTANCE: u32 = 10;
+
+/// Edit distance used by a fuzzy term which does not specify one, as in `japon~`.
+pub const DEFAULT_FUZZY_DISTANCE: u8 = 2;
+
 /// consume a field name followed by colon. Return the field name with escape sequence
 /// already interpreted
 fn field_name(i: &str) -> IResult<&str, String> {
@@ -56,6 +62,7 @@ fn word(i: &str) -> IResult<&str, &str> {
         ))),
         |s| match s {
             "OR" | "AND" | "NOT" | "IN" => Err(Error::new(i, ErrorKind::Tag)),
+            _ if is_near_operator(s) => Err(Error::new(i, ErrorKind::Tag)),
             _ => Ok(s),
         },
     )(i)
@@ -166,8 +173,97 @@ fn simple_term_infallible(
     }
 }
 
-fn term_or_phrase(i: &str) -> IResult<&str, UserInputLeaf> {
+/// Returns true if the word contains a `*` or `?` wildcard which is not escaped.
+fn contains_wildcard(word: &str) -> bool {
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => return true,
+            '\\' => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Consume a word containing wildcards, such as `te?t*`.
+fn wildcard(i: &str) -> IResult<&str, UserInputLeaf> {
     map(
+        verify(word, |word: &str| contains_wildcard(word)),
+        |pattern| UserInputLeaf::Wildcard {
+            field: None,
+            pattern: pattern.to_string(),
+        },
+    )(i)
+}
+
+/// Consume a regex delimited by slashes, such as `/jap[ao]n/`. Slashes inside the regex are
+/// escaped with a `\`.
+fn regex(i: &str) -> IResult<&str, UserInputLeaf> {
+    map(
+        terminated(
+            delimited(
+                char('/'),
+                many0(alt((
+                    value("/", tag("\\/")),
+                    // the other escape sequences are interpreted by the regex itself
+                    recognize(preceded(char('\\'), anychar)),
+                    recognize(none_of("\\/")),
+                ))),
+                char('/'),
+            ),
+            not(word),
+        ),
+        |parts| UserInputLeaf::Regex {
+            field: None,
+            pattern: parts.concat(),
+        },
+    )(i)
+}
+
+/// Consume a word followed by a `~` and an optional edit distance, such as `japon~1`.
+fn fuzzy(i: &str) -> IResult<&str, UserInputLeaf> {
+    map_opt(word, |word| {
+        let (term, distance) = word.rsplit_once('~')?;
+        if term.is_empty() {
+            return None;
+        }
+        let distance = if distance.is_empty() {
+            DEFAULT_FUZZY_DISTANCE
+        } else if distance.bytes().all(|byte| byte.is_ascii_digit()) {
+            distance.parse().ok()?
+        } else {
+            return None;
+        };
+        Some(UserInputLeaf::Fuzzy {
+            field: None,
+            term: term.to_string(),
+            distance,
+        })
+    })(i)
+}
+
+// turns a leaf parser into a precondition for `alt_infallible`. It does not consume its input.
+fn leaf_precond<'a>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, UserInputLeaf>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (), ()> {
+    move |i| value((), peek(&mut parser))(i).map_err(|e| e.map(|_| ()))
+}
+
+// runs a leaf parser whose precondition, built with `leaf_precond`, succeeded.
+fn leaf_after_precond<'a>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, UserInputLeaf>,
+) -> impl FnMut(&'a str) -> JResult<&'a str, Option<UserInputLeaf>> {
+    move |i| {
+        let (rest, leaf) = parser(i).expect("precondition failed");
+        Ok((rest, (Some(leaf), Vec::new())))
+    }
+}
+
+fn term_or_phrase(i: &str) -> IResult<&str, UserInputLeaf> {
+    let term_or_phrase = map(
         tuple((simple_term, fallible(slop_or_prefix_val))),
         |((delimiter, phrase), (slop, prefix))| {
             UserInputLiteral {
@@ -179,11 +275,12 @@ fn term_or_phrase(i: &str) -> IResult<&str, UserInputLeaf> {
             }
             .into()
         },
-    )(i)
+    );
+    alt((regex, wildcard, fuzzy, term_or_phrase))(i)
 }
 
 fn term_or_phrase_infallible(i: &str) -> JResult<&str, Option<UserInputLeaf>> {
-    map(
+    let term_or_phrase = map(
         // ~* for slop/prefix, ) inside group or ast tree, ^ if boost
         tuple_infallible((simple_term_infallible("*)^"), slop_or_prefix_val)),
         |((delimiter_phrase, (slop, prefix)), errors)| {
@@ -214,9 +311,88 @@ fn term_or_phrase_infallible(i: &str) -> JResult<&str, Option<UserInputLeaf>> {
             };
             (leaf, errors)
         },
+    );
+    alt_infallible(
+        (
+            (leaf_precond(regex), leaf_after_precond(regex)),
+            (leaf_precond(wildcard), leaf_after_precond(wildcard)),
+            (leaf_precond(fuzzy), leaf_after_precond(fuzzy)),
+        ),
+        term_or_phrase,
     )(i)
 }
 
+/// Consume a `NEAR` or `NEAR/N` operator, returning its distance.
+fn near_operator(i: &str) -> IResult<&str, u32> {
+    map(
+        preceded(tag("NEAR"), opt(preceded(char('/'), u32))),
+        |distance_opt| distance_opt.unwrap_or(DEFAULT_NEAR_DISTANCE),
+    )(i)
+}
+
+fn is_near_operator(i: &str) -> bool {
+    terminated(near_operator, eof)(i).is_ok()
+}
+
+/// Consume a chain of terms separated by `NEAR` operators, returning the terms and the distance
+/// of each operator.
+fn near_operands(i: &str) -> IResult<&str, (Vec<String>, Vec<u32>)> {
+    map(
+        tuple((
+            simple_term,
+            many1(tuple((
+                delimited(space1, near_operator, space1),
+                simple_term,
+            ))),
+        )),
+        |((_, first_term), rest)| {
+            let mut terms = vec![first_term];
+            let mut distances = Vec::with_capacity(rest.len());
+            for (distance, (_, term)) in rest {
+                distances.push(distance);
+                terms.push(term);
+            }
+            (terms, distances)
+        },
+    )(i)
+}
+
+fn near(i: &str) -> IResult<&str, UserInputLeaf> {
+    map_res(near_operands, |(terms, distances)| {
+        // all the operators of a chain must agree on the distance
+        if distances.windows(2).any(|pair| pair[0] != pair[1]) {
+            return Err(Error::new(i, ErrorKind::Verify));
+        }
+        Ok(UserInputLeaf::Near {
+            field: None,
+            terms,
+            distance: distances[0],
+        })
+    })(i)
+}
+
+// this is a precondition for near_infallible. It does not consume its input.
+fn near_precond(i: &str) -> IResult<&str, (), ()> {
+    value((), peek(near_operands))(i).map_err(|e| e.map(|_| ()))
+}
+
+fn near_infallible(i: &str) -> JResult<&str, UserInputLeaf> {
+    let (rest, (terms, distances)) = near_operands(i).expect("precondition failed");
+    let mut errors = Vec::new();
+    if distances.windows(2).any(|pair| pair[0] != pair[1]) {
+        errors.push(LenientErrorInternal {
+            pos: i.len(),
+            message: "NEAR operators of a same chain should have the same distance".to_string(),
+        });
+    }
+    let leaf = UserInputLeaf::Near {
+        field: None,
+        terms,
+        distance: distances.into_iter().max().unwrap_or(DEFAULT_NEAR_DISTANCE),
+    };
+    Ok((rest, (leaf, errors)))
+}
+
 fn term_group(i: &str) -> IResult<&str, UserInputAst> {
     let occur_symbol = alt((
         value(Occur::MustNot, char('-')),
@@ -300,10 +476,25 @@ fn term_group_infallible(i: &str) -> JResult<&str, UserInputAst> {
     }
 }
 
+/// Consume a query for the documents having a value in a field: `field:*` or `_exists_:field`.
+fn exists(i: &str) -> IResult<&str, UserInputLeaf> {
+    alt((
+        map(preceded(tag("_exists_:"), word), |field| {
+            UserInputLeaf::Exists {
+                field: field.to_string(),
+            }
+        }),
+        map(terminated(field_name, all_docs), |field| {
+            UserInputLeaf::Exists { field }
+        }),
+    ))(i)
+}
+
 fn literal(i: &str) -> IResult<&str, UserInputAst> {
     alt((
+        map(exists, UserInputAst::from),
         map(
-            tuple((opt(field_name), alt((range, set, term_or_phrase)))),
+            tuple((opt(field_name), alt((range, set, near, term_or_phrase)))),
             |(field_name, leaf): (Option<String>, UserInputLeaf)| leaf.set_field(field_name).into(),
         ),
         term_group,
@@ -325,6 +516,10 @@ fn literal_no_group_infallible(i: &str) -> JResult<&str, Option<UserInputAst>> {
                         value((), peek(one_of("{[><"))),
                         map(range_infallible, |(range, errs)| (Some(range), errs)),
                     ),
+                    (
+                        near_precond,
+                        map(near_infallible, |(near, errs)| (Some(near), errs)),
+                    ),
                 ),
                 delimited_infallible(space0_infallible, term_or_phrase_infallible, nothing),
             ),
@@ -341,14 +536,19 @@ fn literal_no_group_infallible(i: &str) -> JResult<&str, Option<UserInputAst>> {
                             message: "parsed possible invalid field as term".to_string(),
                         });
                     }
-                    if matches!(&leaf, UserInputLeaf::Literal(literal)
-                            if literal.phrase == "NOT" && literal.delimiter == Delimiter::None)
-                        && field_name.is_none()
-                    {
-                        errors.push(LenientErrorInternal {
-                            pos: i.len(),
-                            message: "parsed keyword NOT as term. It should be quoted".to_string(),
-                        });
+                    if let UserInputLeaf::Literal(literal) = &leaf {
+                        if (literal.phrase == "NOT" || is_near_operator(&literal.phrase))
+                            && literal.delimiter == Delimiter::None
+                            && field_name.is_none()
+                        {
+                            errors.push(LenientErrorInternal {
+                                pos: i.len(),
+                                message: format!(
+                                    "parsed keyword {} as term. It should be quoted",
+                                    literal.phrase
+                                ),
+                            });
+                        }
                     }
                     leaf.set_field(field_name).into()
                 }),
@@ -360,10 +560,18 @@ fn literal_no_group_infallible(i: &str) -> JResult<&str, Option<UserInputAst>> {
 
 fn literal_infallible(i: &str) -> JResult<&str, Option<UserInputAst>> {
     alt_infallible(
-        ((
-            term_group_precond,
-            map(term_group_infallible, |(group, errs)| (Some(group), errs)),
-        ),),
+        (
+            (
+                leaf_precond(exists),
+                map(leaf_after_precond(exists), |(leaf, errs)| {
+                    (leaf.map(UserInputAst::from), errs)
+                }),
+            ),
+            (
+                term_group_precond,
+                map(term_group_infallible, |(group, errs)| (Some(group), errs)),
+            ),
+        ),
         literal_no_group_infallible,
     )(i)
 }
@@ -626,10 +834,20 @@ fn negate(expr: UserInputAst) -> UserInputAst {
     expr.unary(Occur::MustNot)
 }
 
+/// Consume a `*` matching all documents. A `*` followed by a word is a wildcard.
+fn all_docs(i: &str) -> IResult<&str, UserInputLeaf> {
+    value(UserInputLeaf::All, terminated(char('*'), not(word)))(i)
+}
+
+// this is a precondition for the lenient parsing of `*`. It consumes the `*`.
+fn all_docs_precond(i: &str) -> IResult<&str, (), ()> {
+    value((), all_docs)(i).map_err(|e| e.map(|_| ()))
+}
+
 fn leaf(i: &str) -> IResult<&str, UserInputAst> {
     alt((
         delimited(char('('), ast, char(')')),
-        map(char('*'), |_| UserInputAst::from(UserInputLeaf::All)),
+        map(all_docs, UserInputAst::from),
         map(preceded(tuple((tag("NOT"), space1)), leaf), negate),
         literal,
     ))(i)
@@ -650,7 +868,7 @@ fn leaf_infallible(i: &str) -> JResult<&str, Option<UserInputAst>> {
                 ),
             ),
             (
-                value((), char('*')),
+                all_docs_precond,
                 map(nothing, |_| {
                     (Some(UserInputAst::from(UserInputLeaf::All)), Vec::new())
                 }),
@@ -1528,6 +1746,101 @@ mod test {
         test_parse_query_to_ast_helper("\"a b\"~300^2", "(\"a b\"~300)^2");
     }
 
+    #[test]
+    fn test_near() {
+        test_parse_query_to_ast_helper("a NEAR/3 b", "\"a\" NEAR/3 \"b\"");
+        test_parse_query_to_ast_helper("a NEAR b", "\"a\" NEAR/10 \"b\"");
+        test_parse_query_to_ast_helper(
+            "title:a NEAR/2 \"b c\" NEAR/2 d",
+            "\"title\":\"a\" NEAR/2 \"b c\" NEAR/2 \"d\"",
+        );
+        test_parse_query_to_ast_helper("+a NEAR/1 b c^2", "(+\"a\" NEAR/1 \"b\" *(c)^2)");
+        test_parse_query_to_ast_helper("(a NEAR/1 b)^2", "(\"a\" NEAR/1 \"b\")^2");
+        test_parse_query_to_ast_helper("a NEARBY b", "(*a *NEARBY *b)");
+        test_parse_query_to_ast_helper("a \"NEAR\" b", "(*a *\"NEAR\" *b)");
+        test_is_parse_err("NEAR", "NEAR");
+        test_is_parse_err("NEAR/2", "NEAR/2");
+        test_is_parse_err("a NEAR", "(*a *NEAR)");
+        test_is_parse_err("a NEAR/2", "(*a *NEAR/2)");
+        test_is_parse_err("a NEAR/1 b NEAR/2 c", "\"a\" NEAR/2 \"b\" NEAR/2 \"c\"");
+    }
+
+    #[test]
+    fn test_wildcard() {
+        test_parse_query_to_ast_helper("foo*", "foo*");
+        test_parse_query_to_ast_helper("title:te?t", "\"title\":te?t");
+        test_parse_query_to_ast_helper("foo*bar baz", "(*foo*bar *baz)");
+        test_parse_query_to_ast_helper("*foo", "*foo");
+        test_parse_query_to_ast_helper("title:*t", "\"title\":*t");
+        test_parse_query_to_ast_helper("a * b", "(*a ** *b)");
+        test_parse_query_to_ast_helper("-a?c^2", "(-(a?c)^2)");
+        test_parse_query_to_ast_helper("title:(a* b)", "(*\"title\":a* *\"title\":b)");
+        test_parse_query_to_ast_helper("(*oo)", "*oo");
+        let assert_is_wildcard = |query: &str, expected: bool| {
+            let ast = parse_to_ast(query).unwrap().1;
+            let is_wildcard = matches!(&ast, UserInputAst::Leaf(leaf)
+                if matches!(**leaf, UserInputLeaf::Wildcard { .. }));
+            assert_eq!(is_wildcard, expected, "{query}");
+        };
+        assert_is_wildcard("f?o*", true);
+        assert_is_wildcard(r"f\?o\*", false);
+        assert_is_wildcard("\"foo*\"", false);
+        assert_is_wildcard("foo", false);
+    }
+
+    #[test]
+    fn test_regex() {
+        test_parse_query_to_ast_helper("/jap[ao]n/", "/jap[ao]n/");
+        test_parse_query_to_ast_helper("country:/k.*a/", "\"country\":/k.*a/");
+        test_parse_query_to_ast_helper(r"/a\/b\d/", r"/a\/b\d/");
+        test_parse_query_to_ast_helper("/a b/^2 c", "(*(/a b/)^2 *c)");
+        test_parse_query_to_ast_helper("-(/ab/)", "(-/ab/)");
+        test_parse_query_to_ast_helper("/usr/bin", "/usr/bin");
+        let ast = parse_to_ast(r"/a\/b/").unwrap().1;
+        assert!(
+            matches!(&ast, UserInputAst::Leaf(leaf) if **leaf == UserInputLeaf::Regex {
+                field: None,
+                pattern: "a/b".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        test_parse_query_to_ast_helper("japon~1", "japon~1");
+        test_parse_query_to_ast_helper("japon~", "japon~2");
+        test_parse_query_to_ast_helper("country:japon~1^2", "(\"country\":japon~1)^2");
+        test_parse_query_to_ast_helper("~japon", "~japon");
+        test_parse_query_to_ast_helper("a~b", "a~b");
+        test_parse_query_to_ast_helper("\"a b\"~1", "\"a b\"~1");
+        let ast = parse_to_ast("a~b~1").unwrap().1;
+        assert!(
+            matches!(&ast, UserInputAst::Leaf(leaf) if **leaf == UserInputLeaf::Fuzzy {
+                field: None,
+                term: "a~b".to_string(),
+                distance: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_exists() {
+        test_parse_query_to_ast_helper("title:*", "\"title\":*");
+        test_parse_query_to_ast_helper("_exists_:title", "\"title\":*");
+        test_parse_query_to_ast_helper("attributes.color:*^2", "(\"attributes.color\":*)^2");
+        test_parse_query_to_ast_helper("-_exists_:a.b c", "(-\"a.b\":* *c)");
+        test_parse_query_to_ast_helper("(title:* AND NOT body:*)", "(+\"title\":* +(-\"body\":*))");
+        let ast = parse_to_ast("_exists_:title").unwrap().1;
+        assert!(
+            matches!(&ast, UserInputAst::Leaf(leaf) if **leaf == UserInputLeaf::Exists {
+                field: "title".to_string(),
+            })
+        );
+        let ast = parse_to_ast("title:*t").unwrap().1;
+        assert!(matches!(&ast, UserInputAst::Leaf(leaf)
+            if matches!(**leaf, UserInputLeaf::Wildcard { .. })));
+    }
+
     #[test]
     fn test_phrase_prefix() {
         test_parse_query_to_ast_helper("\"a b\"*", "\"a b\"*");
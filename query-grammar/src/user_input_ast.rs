@@ -16,6 +16,37 @@ pub enum UserInputLeaf {
         field: Option<String>,
         elements: Vec<String>,
     },
+    /// Unordered proximity search, e.g. `a NEAR/3 b`.
+    ///
+    /// Matches when all the terms appear in any order, with at most `distance` positions in
+    /// between.
+    Near {
+        field: Option<String>,
+        terms: Vec<String>,
+        distance: u32,
+    },
+    /// Term pattern with `*` and `?` wildcards, e.g. `te?t*`.
+    ///
+    /// The pattern is kept as typed, `\` escape sequences included.
+    Wildcard {
+        field: Option<String>,
+        pattern: String,
+    },
+    /// Regex matched against the terms, e.g. `/jap[ao]n/`.
+    Regex {
+        field: Option<String>,
+        pattern: String,
+    },
+    /// Term matched with at most `distance` edits, e.g. `japon~1`.
+    Fuzzy {
+        field: Option<String>,
+        term: String,
+        distance: u8,
+    },
+    /// Documents having a value in a field, e.g. `title:*` or `_exists_:title`.
+    Exists {
+        field: String,
+    },
 }
 
 impl UserInputLeaf {
@@ -36,6 +67,30 @@ impl UserInputLeaf {
                 upper,
             },
             UserInputLeaf::Set { field: _, elements } => UserInputLeaf::Set { field, elements },
+            UserInputLeaf::Near {
+                field: _,
+                terms,
+                distance,
+            } => UserInputLeaf::Near {
+                field,
+                terms,
+                distance,
+            },
+            UserInputLeaf::Wildcard { field: _, pattern } => {
+                UserInputLeaf::Wildcard { field, pattern }
+            }
+            UserInputLeaf::Regex { field: _, pattern } => UserInputLeaf::Regex { field, pattern },
+            UserInputLeaf::Fuzzy {
+                field: _,
+                term,
+                distance,
+            } => UserInputLeaf::Fuzzy {
+                field,
+                term,
+                distance,
+            },
+            // the field is part of the syntax of the leaf.
+            UserInputLeaf::Exists { field } => UserInputLeaf::Exists { field },
         }
     }
 }
@@ -73,6 +128,54 @@ impl Debug for UserInputLeaf {
                 }
                 write!(formatter, "]")
             }
+            UserInputLeaf::Near {
+                field,
+                terms,
+                distance,
+            } => {
+                if let Some(ref field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                for (i, text) in terms.iter().enumerate() {
+                    if i != 0 {
+                        write!(formatter, " NEAR/{distance} ")?;
+                    }
+                    // TODO properly escape element
+                    write!(formatter, "\"{text}\"")?;
+                }
+                Ok(())
+            }
+            UserInputLeaf::Wildcard { field, pattern } => {
+                if let Some(ref field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                write!(formatter, "{pattern}")
+            }
+            UserInputLeaf::Regex { field, pattern } => {
+                if let Some(ref field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                write!(formatter, "/{}/", pattern.replace('/', "\\/"))
+            }
+            UserInputLeaf::Fuzzy {
+                field,
+                term,
+                distance,
+            } => {
+                if let Some(ref field) = field {
+                    // TODO properly escape field (in case of \")
+                    write!(formatter, "\"{field}\":")?;
+                }
+                // TODO properly escape element
+                write!(formatter, "{term}~{distance}")
+            }
+            UserInputLeaf::Exists { field } => {
+                // TODO properly escape field (in case of \")
+                write!(formatter, "\"{field}\":*")
+            }
             UserInputLeaf::All => write!(formatter, "*"),
         }
     }
@@ -31,11 +31,17 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use super::bucket::{
-    DateHistogramAggregationReq, HistogramAggregation, RangeAggregation, TermsAggregation,
+    CompositeAggregation, DateHistogramAggregationReq, FilterAggregation, FiltersAggregation,
+    GeohashGridAggregation, HistogramAggregation, RangeAggregation, TermsAggregation,
 };
 use super::metric::{
-    AverageAggregation, CountAggregation, MaxAggregation, MinAggregation,
-    PercentilesAggregationReq, StatsAggregation, SumAggregation,
+    AverageAggregation, CardinalityAggregationReq, CountAggregation, MaxAggregation,
+    MinAggregation, PercentilesAggregationReq, StatsAggregation, SumAggregation,
+    TopHitsAggregation,
+};
+use super::pipeline::{
+    BucketScriptAggregation, BucketSelectorAggregation, BucketSortAggregation,
+    CumulativeSumAggregation, DerivativeAggregation, MovingAvgAggregation,
 };
 
 /// The top-level aggregation request structure, which contains [`Aggregation`] and their user
@@ -93,9 +99,22 @@ impl Aggregation {
     }
 
     fn get_fast_field_names(&self, fast_field_names: &mut HashSet<String>) {
-        fast_field_names.insert(self.agg.get_fast_field_name().to_string());
+        fast_field_names.extend(
+            self.agg
+                .get_fast_field_names()
+                .into_iter()
+                .map(ToString::to_string),
+        );
         fast_field_names.extend(get_fast_field_names(&self.sub_aggregation));
     }
+
+    fn requires_scoring(&self) -> bool {
+        let agg_requires_scoring = match &self.agg {
+            AggregationVariants::TopHits(top_hits) => top_hits.requires_scoring(),
+            _ => false,
+        };
+        agg_requires_scoring || aggregations_require_scoring(&self.sub_aggregation)
+    }
 }
 
 /// Extract all fast field names used in the tree.
@@ -107,6 +126,11 @@ pub fn get_fast_field_names(aggs: &Aggregations) -> HashSet<String> {
     fast_field_names
 }
 
+/// Returns true if an aggregation of the tree needs the score of the documents.
+pub(crate) fn aggregations_require_scoring(aggs: &Aggregations) -> bool {
+    aggs.values().any(|agg| agg.requires_scoring())
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// All aggregation types.
 pub enum AggregationVariants {
@@ -123,6 +147,18 @@ pub enum AggregationVariants {
     /// Put data into buckets of terms.
     #[serde(rename = "terms")]
     Terms(TermsAggregation),
+    /// Put geo points into the cells of a geohash grid.
+    #[serde(rename = "geohash_grid")]
+    GeohashGrid(GeohashGridAggregation),
+    /// Put the documents matching a query into a single bucket.
+    #[serde(rename = "filter")]
+    Filter(FilterAggregation),
+    /// Put the documents matching each of the named queries into a bucket.
+    #[serde(rename = "filters")]
+    Filters(FiltersAggregation),
+    /// Put data into buckets of combinations of values, one page at a time.
+    #[serde(rename = "composite")]
+    Composite(CompositeAggregation),
 
     // Metric aggregation types
     /// Computes the average of the extracted values.
@@ -147,23 +183,76 @@ pub enum AggregationVariants {
     /// Computes the sum of the extracted values.
     #[serde(rename = "percentiles")]
     Percentiles(PercentilesAggregationReq),
+    /// Approximates the number of distinct values.
+    #[serde(rename = "cardinality")]
+    Cardinality(CardinalityAggregationReq),
+    /// Keeps the best documents.
+    #[serde(rename = "top_hits")]
+    TopHits(TopHitsAggregation),
+
+    // Pipeline aggregation types
+    /// Computes the difference of a value between consecutive buckets.
+    #[serde(rename = "derivative")]
+    Derivative(DerivativeAggregation),
+    /// Computes the running sum of a value over the buckets.
+    #[serde(rename = "cumulative_sum")]
+    CumulativeSum(CumulativeSumAggregation),
+    /// Computes the average of a value over a sliding window of buckets.
+    #[serde(rename = "moving_avg")]
+    MovingAvg(MovingAvgAggregation),
+    /// Computes a script over values of each bucket.
+    #[serde(rename = "bucket_script")]
+    BucketScript(BucketScriptAggregation),
+    /// Keeps the buckets for which a script is true.
+    #[serde(rename = "bucket_selector")]
+    BucketSelector(BucketSelectorAggregation),
+    /// Sorts and truncates the buckets.
+    #[serde(rename = "bucket_sort")]
+    BucketSort(BucketSortAggregation),
 }
 
 impl AggregationVariants {
     /// Returns the name of the field used by the aggregation.
+    ///
+    /// For aggregations using several fields, e.g. `composite` or `top_hits`, this is the first
+    /// of them, see [`get_fast_field_names`](Self::get_fast_field_names). Returns an empty string
+    /// for aggregations without a field, e.g. `filter` or the pipeline aggregations.
     pub fn get_fast_field_name(&self) -> &str {
+        self.get_fast_field_names()
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of the fields used by the aggregation.
+    pub fn get_fast_field_names(&self) -> Vec<&str> {
         match self {
-            AggregationVariants::Terms(terms) => terms.field.as_str(),
-            AggregationVariants::Range(range) => range.field.as_str(),
-            AggregationVariants::Histogram(histogram) => histogram.field.as_str(),
-            AggregationVariants::DateHistogram(histogram) => histogram.field.as_str(),
-            AggregationVariants::Average(avg) => avg.field_name(),
-            AggregationVariants::Count(count) => count.field_name(),
-            AggregationVariants::Max(max) => max.field_name(),
-            AggregationVariants::Min(min) => min.field_name(),
-            AggregationVariants::Stats(stats) => stats.field_name(),
-            AggregationVariants::Sum(sum) => sum.field_name(),
-            AggregationVariants::Percentiles(per) => per.field_name(),
+            AggregationVariants::Terms(terms) => vec![terms.field.as_str()],
+            AggregationVariants::Range(range) => vec![range.field.as_str()],
+            AggregationVariants::Histogram(histogram) => vec![histogram.field.as_str()],
+            AggregationVariants::DateHistogram(histogram) => vec![histogram.field.as_str()],
+            AggregationVariants::GeohashGrid(geohash_grid) => vec![geohash_grid.field.as_str()],
+            AggregationVariants::Filter(_) | AggregationVariants::Filters(_) => Vec::new(),
+            AggregationVariants::Composite(composite) => composite
+                .sources
+                .iter()
+                .map(|source| source.source.field())
+                .collect(),
+            AggregationVariants::Average(avg) => vec![avg.field_name()],
+            AggregationVariants::Count(count) => vec![count.field_name()],
+            AggregationVariants::Max(max) => vec![max.field_name()],
+            AggregationVariants::Min(min) => vec![min.field_name()],
+            AggregationVariants::Stats(stats) => vec![stats.field_name()],
+            AggregationVariants::Sum(sum) => vec![sum.field_name()],
+            AggregationVariants::Percentiles(per) => vec![per.field_name()],
+            AggregationVariants::Cardinality(cardinality) => vec![cardinality.field_name()],
+            AggregationVariants::TopHits(top_hits) => top_hits.field_names(),
+            AggregationVariants::Derivative(_)
+            | AggregationVariants::CumulativeSum(_)
+            | AggregationVariants::MovingAvg(_)
+            | AggregationVariants::BucketScript(_)
+            | AggregationVariants::BucketSelector(_)
+            | AggregationVariants::BucketSort(_) => Vec::new(),
         }
     }
 
@@ -189,12 +278,40 @@ impl AggregationVariants {
         }
     }
 
+    pub(crate) fn as_geohash_grid(&self) -> Option<&GeohashGridAggregation> {
+        match &self {
+            AggregationVariants::GeohashGrid(geohash_grid) => Some(geohash_grid),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_composite(&self) -> Option<&CompositeAggregation> {
+        match &self {
+            AggregationVariants::Composite(composite) => Some(composite),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_filters(&self) -> Option<&FiltersAggregation> {
+        match &self {
+            AggregationVariants::Filters(filters) => Some(filters),
+            _ => None,
+        }
+    }
+
     pub(crate) fn as_percentile(&self) -> Option<&PercentilesAggregationReq> {
         match &self {
             AggregationVariants::Percentiles(percentile_req) => Some(percentile_req),
             _ => None,
         }
     }
+
+    pub(crate) fn as_top_hits(&self) -> Option<&TopHitsAggregation> {
+        match &self {
+            AggregationVariants::TopHits(top_hits) => Some(top_hits),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -329,6 +446,7 @@ mod tests {
             }))
             .unwrap()
         };
+        assert_eq!(range_agg.agg.get_fast_field_name(), "score");
 
         let agg_req1: Aggregations = {
             serde_json::from_value(json!({
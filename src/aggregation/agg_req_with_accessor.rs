@@ -1,29 +1,60 @@
 //! This will enhance the request tree with access to the fastfield and metadata.
 
+use std::net::Ipv6Addr;
+
 use columnar::{Column, ColumnBlockAccessor, ColumnType, StrColumn};
 
 use super::agg_limits::ResourceLimitGuard;
 use super::agg_req::{Aggregation, AggregationVariants, Aggregations};
 use super::bucket::{
-    DateHistogramAggregationReq, HistogramAggregation, RangeAggregation, TermsAggregation,
+    CompositeSourceAccessor, DateHistogramAggregationReq, FilterWeight, GeohashGridAggregation,
+    HistogramAggregation, RangeAggregation, TermsAggregation,
 };
 use super::metric::{
     AverageAggregation, CountAggregation, MaxAggregation, MinAggregation, StatsAggregation,
-    SumAggregation,
+    SumAggregation, TopHitsAccessor,
 };
 use super::segment_agg_result::AggregationLimits;
 use super::VecWithNames;
-use crate::aggregation::{f64_to_fastfield_u64, Key};
-use crate::SegmentReader;
+use crate::aggregation::{f64_to_fastfield_u64, AggregationError, Key};
+use crate::schema::FieldType;
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
 #[derive(Default)]
 pub(crate) struct AggregationsWithAccessor {
     pub aggs: VecWithNames<AggregationWithAccessor>,
+    /// True if a top hits aggregation of the tree sorts by score.
+    requires_scoring: bool,
 }
 
 impl AggregationsWithAccessor {
     fn from_data(aggs: VecWithNames<AggregationWithAccessor>) -> Self {
-        Self { aggs }
+        let requires_scoring = aggs.values.iter().any(|agg| {
+            agg.sub_aggregation.requires_scoring
+                || agg
+                    .top_hits_accessor
+                    .as_ref()
+                    .map(TopHitsAccessor::requires_scoring)
+                    .unwrap_or(false)
+        });
+        Self {
+            aggs,
+            requires_scoring,
+        }
+    }
+
+    /// Sets the scores of the block of documents being collected. Only the top hits
+    /// aggregations that sort by score, and the aggregations above them, are visited.
+    pub(crate) fn set_block_scores(&mut self, docs: &[DocId], scores: &[Score]) {
+        if !self.requires_scoring {
+            return;
+        }
+        for agg in self.aggs.values_mut() {
+            if let Some(top_hits_accessor) = agg.top_hits_accessor.as_mut() {
+                top_hits_accessor.set_block_scores(docs, scores);
+            }
+            agg.sub_aggregation.set_block_scores(docs, scores);
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -39,6 +70,16 @@ pub struct AggregationWithAccessor {
     /// Load insert u64 for missing use case
     pub(crate) missing_value_for_accessor: Option<u64>,
     pub(crate) str_dict_column: Option<StrColumn>,
+    /// Ip address columns can't be read as u64. They are only used by the cardinality
+    /// aggregation, in which case `accessor` is empty.
+    pub(crate) ip_addr_column: Option<Column<Ipv6Addr>>,
+    /// Only set for the top hits aggregation.
+    pub(crate) top_hits_accessor: Option<TopHitsAccessor>,
+    /// The weights of the queries of the filter and filters aggregations, in the order of the
+    /// buckets.
+    pub(crate) filter_weights: Vec<FilterWeight>,
+    /// The columns of the sources of the composite aggregation, in the order of the sources.
+    pub(crate) composite_sources: Vec<CompositeSourceAccessor>,
     pub(crate) field_type: ColumnType,
     pub(crate) sub_aggregation: AggregationsWithAccessor,
     pub(crate) limits: ResourceLimitGuard,
@@ -57,6 +98,7 @@ impl AggregationWithAccessor {
         agg: &Aggregation,
         sub_aggregation: &Aggregations,
         reader: &SegmentReader,
+        segment_ordinal: SegmentOrdinal,
         limits: AggregationLimits,
     ) -> crate::Result<Vec<AggregationWithAccessor>> {
         let add_agg_with_accessor = |accessor: Column<u64>,
@@ -70,12 +112,17 @@ impl AggregationWithAccessor {
                 sub_aggregation: get_aggs_with_segment_accessor_and_validate(
                     sub_aggregation,
                     reader,
+                    segment_ordinal,
                     &limits,
                 )?,
                 agg: agg.clone(),
                 limits: limits.new_guard(),
                 missing_value_for_accessor: None,
                 str_dict_column: None,
+                ip_addr_column: None,
+                top_hits_accessor: None,
+                filter_weights: Vec::new(),
+                composite_sources: Vec::new(),
                 column_block_accessor: Default::default(),
             };
             aggs.push(res);
@@ -106,6 +153,81 @@ impl AggregationWithAccessor {
                     get_ff_reader(reader, field_name, Some(get_numeric_or_date_column_types()))?;
                 add_agg_with_accessor(accessor, column_type, &mut res)?;
             }
+            GeohashGrid(GeohashGridAggregation {
+                field: field_name, ..
+            }) => {
+                let schema = reader.schema();
+                if let Ok(field) = schema.get_field(field_name) {
+                    if !matches!(
+                        schema.get_field_entry(field).field_type(),
+                        FieldType::GeoPoint(_)
+                    ) {
+                        return Err(TantivyError::AggregationError(
+                            AggregationError::InvalidRequest(format!(
+                                "geohash_grid requires a geo point field, {field_name:?} is not \
+                                 one"
+                            )),
+                        ));
+                    }
+                }
+                let (accessor, column_type) =
+                    get_ff_reader(reader, field_name, Some(&[ColumnType::U64]))?;
+                add_agg_with_accessor(accessor, column_type, &mut res)?;
+            }
+            Filter(filter) => {
+                add_agg_with_accessor(
+                    Column::build_empty_column(reader.num_docs()),
+                    ColumnType::U64,
+                    &mut res,
+                )?;
+                if let Some(agg) = res.last_mut() {
+                    agg.filter_weights = vec![FilterWeight::for_segment(&filter.query, reader)?];
+                }
+            }
+            Filters(filters) => {
+                add_agg_with_accessor(
+                    Column::build_empty_column(reader.num_docs()),
+                    ColumnType::U64,
+                    &mut res,
+                )?;
+                if let Some(agg) = res.last_mut() {
+                    agg.filter_weights = filters
+                        .filters
+                        .values()
+                        .map(|query| FilterWeight::for_segment(query, reader))
+                        .collect::<crate::Result<_>>()?;
+                }
+            }
+            Composite(composite) => {
+                composite.validate()?;
+                add_agg_with_accessor(
+                    Column::build_empty_column(reader.num_docs()),
+                    ColumnType::U64,
+                    &mut res,
+                )?;
+                let composite_sources = composite
+                    .sources
+                    .iter()
+                    .map(|source| {
+                        let field_name = source.source.field();
+                        let (column, column_type) = get_ff_reader(
+                            reader,
+                            field_name,
+                            Some(source.source.allowed_column_types()),
+                        )?;
+                        CompositeSourceAccessor::open(
+                            source,
+                            composite.after_value(&source.name),
+                            column,
+                            column_type,
+                            reader.fast_fields().str(field_name)?,
+                        )
+                    })
+                    .collect::<crate::Result<_>>()?;
+                if let Some(agg) = res.last_mut() {
+                    agg.composite_sources = composite_sources;
+                }
+            }
             Terms(TermsAggregation {
                 field: field_name,
                 missing,
@@ -160,10 +282,15 @@ impl AggregationWithAccessor {
                         sub_aggregation: get_aggs_with_segment_accessor_and_validate(
                             sub_aggregation,
                             reader,
+                            segment_ordinal,
                             &limits,
                         )?,
                         agg: agg.clone(),
                         str_dict_column: str_dict_column.clone(),
+                        ip_addr_column: None,
+                        top_hits_accessor: None,
+                        filter_weights: Vec::new(),
+                        composite_sources: Vec::new(),
                         limits: limits.new_guard(),
                         column_block_accessor: Default::default(),
                     };
@@ -179,7 +306,7 @@ impl AggregationWithAccessor {
 
                     let missing_value_for_accessor =
                         if let Some(missing) = missing_value_term_agg.as_ref() {
-                            get_missing_val(column_type, missing, agg.agg.get_fast_field_name())?
+                            get_missing_val(column_type, missing, field_name)?
                         } else {
                             None
                         };
@@ -192,10 +319,15 @@ impl AggregationWithAccessor {
                         sub_aggregation: get_aggs_with_segment_accessor_and_validate(
                             sub_aggregation,
                             reader,
+                            segment_ordinal,
                             &limits,
                         )?,
                         agg: agg.clone(),
                         str_dict_column: str_dict_column.clone(),
+                        ip_addr_column: None,
+                        top_hits_accessor: None,
+                        filter_weights: Vec::new(),
+                        composite_sources: Vec::new(),
                         limits: limits.new_guard(),
                         column_block_accessor: Default::default(),
                     };
@@ -232,6 +364,63 @@ impl AggregationWithAccessor {
                 )?;
                 add_agg_with_accessor(accessor, column_type, &mut res)?;
             }
+            Cardinality(cardinality) => {
+                let str_dict_column = reader.fast_fields().str(cardinality.field_name())?;
+                let allowed_column_types = [
+                    ColumnType::I64,
+                    ColumnType::U64,
+                    ColumnType::F64,
+                    ColumnType::Str,
+                    ColumnType::DateTime,
+                    ColumnType::Bool,
+                ];
+                let column_and_types = get_all_ff_reader_or_empty(
+                    reader,
+                    cardinality.field_name(),
+                    Some(&allowed_column_types),
+                    ColumnType::U64,
+                )?;
+                for (accessor, column_type) in column_and_types {
+                    add_agg_with_accessor(accessor, column_type, &mut res)?;
+                }
+                if let Some(str_dict_column) = str_dict_column {
+                    for agg in &mut res {
+                        if agg.field_type == ColumnType::Str {
+                            agg.str_dict_column = Some(str_dict_column.clone());
+                        }
+                    }
+                }
+                let ip_addr_column = reader
+                    .fast_fields()
+                    .column_opt::<Ipv6Addr>(cardinality.field_name())?;
+                if let Some(ip_addr_column) = ip_addr_column {
+                    add_agg_with_accessor(
+                        Column::build_empty_column(reader.num_docs()),
+                        ColumnType::IpAddr,
+                        &mut res,
+                    )?;
+                    if let Some(agg) = res.last_mut() {
+                        agg.ip_addr_column = Some(ip_addr_column);
+                    }
+                }
+            }
+            TopHits(top_hits) => {
+                add_agg_with_accessor(
+                    Column::build_empty_column(reader.num_docs()),
+                    ColumnType::U64,
+                    &mut res,
+                )?;
+                if let Some(agg) = res.last_mut() {
+                    agg.top_hits_accessor =
+                        Some(TopHitsAccessor::open(top_hits, reader, segment_ordinal)?);
+                }
+            }
+            Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketScript(_)
+            | BucketSelector(_) | BucketSort(_) => {
+                return Err(TantivyError::InternalError(
+                    "pipeline aggregations don't collect documents".to_string(),
+                ));
+            }
         };
 
         Ok(res)
@@ -272,14 +461,17 @@ fn get_numeric_or_date_column_types() -> &'static [ColumnType] {
 pub(crate) fn get_aggs_with_segment_accessor_and_validate(
     aggs: &Aggregations,
     reader: &SegmentReader,
+    segment_ordinal: SegmentOrdinal,
     limits: &AggregationLimits,
 ) -> crate::Result<AggregationsWithAccessor> {
     let mut aggss = Vec::new();
-    for (key, agg) in aggs.iter() {
+    // Pipeline aggregations are computed on the final result of their parent.
+    for (key, agg) in aggs.iter().filter(|(_, agg)| !agg.agg.is_pipeline()) {
         let aggs = AggregationWithAccessor::try_from_agg(
             agg,
             agg.sub_aggregation(),
             reader,
+            segment_ordinal,
             limits.clone(),
         )?;
         for agg in aggs {
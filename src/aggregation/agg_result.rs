@@ -7,8 +7,10 @@
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
-use super::bucket::GetDocCount;
-use super::metric::{PercentilesMetricResult, SingleMetricResult, Stats};
+use super::agg_req::Aggregations;
+use super::bucket::{CompositeKey, GetDocCount};
+use super::metric::{PercentilesMetricResult, SingleMetricResult, Stats, TopHitsMetricResult};
+use super::pipeline::{apply_pipeline_aggregations, PipelineBucket};
 use super::{AggregationError, Key};
 use crate::TantivyError;
 
@@ -92,10 +94,18 @@ pub enum MetricResult {
     Sum(SingleMetricResult),
     /// Sum metric result.
     Percentiles(PercentilesMetricResult),
+    /// Cardinality metric result.
+    Cardinality(SingleMetricResult),
+    /// Top hits metric result.
+    TopHits(TopHitsMetricResult),
+    /// Value computed by a pipeline aggregation.
+    ///
+    /// See [`pipeline`](super::pipeline)
+    Pipeline(SingleMetricResult),
 }
 
 impl MetricResult {
-    fn get_value(&self, agg_property: &str) -> crate::Result<Option<f64>> {
+    pub(crate) fn get_value(&self, agg_property: &str) -> crate::Result<Option<f64>> {
         match self {
             MetricResult::Average(avg) => Ok(avg.value),
             MetricResult::Count(count) => Ok(count.value),
@@ -103,6 +113,11 @@ impl MetricResult {
             MetricResult::Min(min) => Ok(min.value),
             MetricResult::Stats(stats) => stats.get_value(agg_property),
             MetricResult::Sum(sum) => Ok(sum.value),
+            MetricResult::Cardinality(cardinality) => Ok(cardinality.value),
+            MetricResult::Pipeline(pipeline) => Ok(pipeline.value),
+            MetricResult::TopHits(_) => Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest("top_hits can't be used to order".to_string()),
+            )),
             MetricResult::Percentiles(_) => Err(TantivyError::AggregationError(
                 AggregationError::InvalidRequest("percentiles can't be used to order".to_string()),
             )),
@@ -142,6 +157,35 @@ pub enum BucketResult {
         /// The upper bound error for the doc count of each term.
         doc_count_error_upper_bound: Option<u64>,
     },
+    /// This is the geohash grid result
+    GeohashGrid {
+        /// The buckets, sorted by decreasing `doc_count`.
+        ///
+        /// See [`GeohashGridAggregation`](super::bucket::GeohashGridAggregation)
+        buckets: Vec<BucketEntry>,
+    },
+    /// This is the filters result
+    Filters {
+        /// The buckets, keyed by filter name.
+        ///
+        /// See [`FiltersAggregation`](super::bucket::FiltersAggregation)
+        buckets: FxHashMap<String, FilterBucketEntry>,
+    },
+    /// This is the composite result
+    Composite {
+        /// The buckets, sorted by key.
+        ///
+        /// See [`CompositeAggregation`](super::bucket::CompositeAggregation)
+        buckets: Vec<CompositeBucketEntry>,
+        /// The key of the last bucket, to request the next page with. Not set when there are no
+        /// buckets.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        after_key: Option<CompositeKey>,
+    },
+    /// This is the filter result, which contains a count, and optionally sub-aggregations.
+    ///
+    /// See [`FilterAggregation`](super::bucket::FilterAggregation)
+    Filter(FilterBucketEntry),
 }
 
 impl BucketResult {
@@ -158,6 +202,50 @@ impl BucketResult {
                 sum_other_doc_count: _,
                 doc_count_error_upper_bound: _,
             } => buckets.iter().map(|bucket| bucket.get_bucket_count()).sum(),
+            BucketResult::GeohashGrid { buckets } => {
+                buckets.iter().map(|bucket| bucket.get_bucket_count()).sum()
+            }
+            BucketResult::Filters { buckets } => buckets
+                .values()
+                .map(|bucket| bucket.get_bucket_count())
+                .sum(),
+            BucketResult::Filter(bucket) => bucket.get_bucket_count(),
+            BucketResult::Composite {
+                buckets,
+                after_key: _,
+            } => buckets.iter().map(|bucket| bucket.get_bucket_count()).sum(),
+        }
+    }
+
+    /// Computes the pipeline aggregations among the sub-aggregations of the buckets.
+    pub(crate) fn apply_pipeline_aggregations(
+        &mut self,
+        sub_aggregations: &Aggregations,
+    ) -> crate::Result<()> {
+        if !sub_aggregations.values().any(|agg| agg.agg.is_pipeline()) {
+            return Ok(());
+        }
+        match self {
+            BucketResult::Range { buckets } => {
+                apply_pipeline_aggregations_to_entries(buckets, sub_aggregations)
+            }
+            BucketResult::Histogram { buckets } => {
+                apply_pipeline_aggregations_to_entries(buckets, sub_aggregations)
+            }
+            BucketResult::Terms { buckets, .. } | BucketResult::GeohashGrid { buckets } => {
+                apply_pipeline_aggregations(buckets, sub_aggregations)
+            }
+            BucketResult::Composite { buckets, .. } => {
+                apply_pipeline_aggregations(buckets, sub_aggregations)
+            }
+            BucketResult::Filters { buckets } => {
+                let mut entries: Vec<_> = std::mem::take(buckets).into_iter().collect();
+                entries.sort_by(|left, right| left.cmp_position(right));
+                apply_pipeline_aggregations(&mut entries, sub_aggregations)?;
+                *buckets = entries.into_iter().collect();
+                Ok(())
+            }
+            BucketResult::Filter(_) => Ok(()),
         }
     }
 }
@@ -182,6 +270,23 @@ impl<T> BucketEntries<T> {
     }
 }
 
+fn apply_pipeline_aggregations_to_entries<T: PipelineBucket>(
+    bucket_entries: &mut BucketEntries<T>,
+    sub_aggregations: &Aggregations,
+) -> crate::Result<()> {
+    match bucket_entries {
+        BucketEntries::Vec(buckets) => apply_pipeline_aggregations(buckets, sub_aggregations),
+        BucketEntries::HashMap(map) => {
+            // Keyed buckets are brought back into their order for the pipeline aggregations.
+            let mut entries: Vec<(String, T)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|left, right| left.cmp_position(right));
+            apply_pipeline_aggregations(&mut entries, sub_aggregations)?;
+            *map = entries.into_iter().collect();
+            Ok(())
+        }
+    }
+}
+
 /// This is the default entry for a bucket, which contains a key, count, and optionally
 /// sub-aggregations.
 ///
@@ -294,3 +399,68 @@ impl RangeBucketEntry {
         1 + self.sub_aggregation.get_bucket_count()
     }
 }
+
+/// This is the entry for a filter bucket, which contains a count, and optionally
+/// sub-aggregations.
+///
+/// # JSON Format
+/// ```json
+/// {
+///   ...
+///     "in_stock": {
+///       "doc_count": 5,
+///       "avg_price": {
+///         "value": 10.0
+///       }
+///     }
+///   ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterBucketEntry {
+    /// Number of documents in the bucket.
+    pub doc_count: u64,
+    #[serde(flatten)]
+    /// Sub-aggregations in this bucket.
+    pub sub_aggregation: AggregationResults,
+}
+impl FilterBucketEntry {
+    pub(crate) fn get_bucket_count(&self) -> u64 {
+        1 + self.sub_aggregation.get_bucket_count()
+    }
+}
+
+/// This is the entry for a composite bucket, which contains the key, a count, and optionally
+/// sub-aggregations.
+///
+/// # JSON Format
+/// ```json
+/// {
+///   ...
+///     "counts": {
+///       "buckets": [
+///         {
+///           "key": { "tenant": "acme", "status": 200.0 },
+///           "doc_count": 5
+///         }
+///       ],
+///       "after_key": { "tenant": "acme", "status": 200.0 }
+///     }
+///   ...
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompositeBucketEntry {
+    /// The values of the sources of the bucket, by source name.
+    pub key: CompositeKey,
+    /// Number of documents in the bucket.
+    pub doc_count: u64,
+    #[serde(flatten)]
+    /// Sub-aggregations in this bucket.
+    pub sub_aggregation: AggregationResults,
+}
+impl CompositeBucketEntry {
+    pub(crate) fn get_bucket_count(&self) -> u64 {
+        1 + self.sub_aggregation.get_bucket_count()
+    }
+}
@@ -0,0 +1,1116 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Debug;
+
+use columnar::{Column, ColumnType, StrColumn};
+use rustc_hash::FxHashMap;
+use serde::ser::SerializeMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{parse_into_milliseconds, Order};
+use crate::aggregation::agg_req_with_accessor::AggregationsWithAccessor;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateCompositeBucketResult, IntermediateKey, IntermediateTermBucketEntry,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::aggregation::{f64_from_fastfield_u64, AggregationError, Key};
+use crate::{DocId, TantivyError};
+
+/// The key of a composite bucket, with the value of each source by source name.
+///
+/// A `None` value stands for documents without a value for the source, which only have a bucket
+/// with `missing_bucket`.
+pub type CompositeKey = FxHashMap<String, Option<Key>>;
+
+/// Groups documents by the combination of the values of several sources, e.g. terms and date
+/// histogram buckets.
+///
+/// The buckets are sorted by key, comparing the values of the sources in their order. Each source
+/// can be sorted ascending (the default) or descending. Documents without a value for a source
+/// are skipped, unless the source has `missing_bucket` set, in which case they are sorted before
+/// the other values in ascending order and after them in descending order.
+///
+/// Only the `size` first buckets are returned, along with an `after_key`, the key of the last
+/// bucket. Passing it as `after` in the next request returns the next page. Pages are computed
+/// exactly, and only `size` buckets per segment are held in memory while collecting, so that
+/// groupings with more buckets than the bucket limit of the
+/// [`AggregationLimits`](crate::aggregation::AggregationLimits) can be paginated through. The
+/// last page is reached when fewer than `size` buckets are returned.
+///
+/// If a document has several values for a source, it is counted in the bucket of each
+/// combination of values.
+///
+/// Supported sources are:
+/// - `terms`, on text, `u64`, `i64` and `f64` fast fields. The key is the term or the number.
+/// - `histogram`, on numeric and date fast fields, with a numeric `interval`. The key is the lower
+///   bound of the interval.
+/// - `date_histogram`, on date fast fields, with a `fixed_interval` like `1d`. The key is the start
+///   of the interval, in milliseconds since the epoch.
+///
+/// Result type is [`BucketResult::Composite`](crate::aggregation::agg_result::BucketResult)
+/// with [`CompositeBucketEntry`](crate::aggregation::agg_result::CompositeBucketEntry) on the
+/// `AggregationCollector`.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "counts": {
+///         "composite": {
+///             "size": 1000,
+///             "sources": [
+///                 { "tenant": { "terms": { "field": "tenant" } } },
+///                 { "day": { "date_histogram": { "field": "timestamp", "fixed_interval": "1d" } } },
+///                 { "status": { "terms": { "field": "status", "order": "desc" } } }
+///             ],
+///             "after": { "tenant": "acme", "day": 1546300800000.0, "status": 404 }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompositeAggregation {
+    /// The sources of the values of the keys.
+    pub sources: Vec<CompositeSource>,
+    /// The maximum number of buckets returned. Defaults to 10.
+    #[serde(default = "default_size")]
+    pub size: u32,
+    /// Only the buckets whose key sorts after this key are returned. Usually set to the
+    /// `after_key` of the previous page.
+    ///
+    /// Keys are numbers in JSON, so the values of terms sources on `u64` and `i64` fields must be
+    /// within +/-2^53 to be exact, larger values are rejected.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub after: Option<CompositeKey>,
+}
+
+fn default_size() -> u32 {
+    10
+}
+
+impl CompositeAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        let invalid_request =
+            |msg: String| TantivyError::AggregationError(AggregationError::InvalidRequest(msg));
+        if self.sources.is_empty() {
+            return Err(invalid_request(
+                "composite aggregation requires at least one source".to_string(),
+            ));
+        }
+        if self.size == 0 {
+            return Err(invalid_request(
+                "composite size must be at least 1".to_string(),
+            ));
+        }
+        let mut source_names = HashSet::new();
+        for source in &self.sources {
+            if !source_names.insert(source.name.as_str()) {
+                return Err(invalid_request(format!(
+                    "composite source name {:?} is used more than once",
+                    source.name
+                )));
+            }
+            source.source.validate()?;
+        }
+        if let Some(after) = self.after.as_ref() {
+            let after_names: HashSet<&str> = after.keys().map(String::as_str).collect();
+            if after_names != source_names {
+                return Err(invalid_request(format!(
+                    "composite after key must have a value for each of the sources {:?}",
+                    self.sources
+                        .iter()
+                        .map(|source| source.name.as_str())
+                        .collect::<Vec<_>>()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the `after` key for a source, if `after` is set.
+    pub(crate) fn after_value(&self, source_name: &str) -> Option<Option<&Key>> {
+        self.after
+            .as_ref()
+            .map(|after| after.get(source_name).and_then(Option::as_ref))
+    }
+
+    /// Compares two keys, given as the values of the sources in their order.
+    pub(crate) fn compare_keys(
+        &self,
+        left: &[Option<IntermediateKey>],
+        right: &[Option<IntermediateKey>],
+    ) -> Ordering {
+        for ((source, left), right) in self.sources.iter().zip(left).zip(right) {
+            let ordering = match (left, right) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Less,
+                (Some(_), None) => Ordering::Greater,
+                (Some(IntermediateKey::F64(left)), Some(IntermediateKey::F64(right))) => {
+                    left.total_cmp(right)
+                }
+                (Some(IntermediateKey::Str(left)), Some(IntermediateKey::Str(right))) => {
+                    left.cmp(right)
+                }
+                (Some(IntermediateKey::F64(_)), Some(IntermediateKey::Str(_))) => Ordering::Less,
+                (Some(IntermediateKey::Str(_)), Some(IntermediateKey::F64(_))) => Ordering::Greater,
+            };
+            let ordering = match source.source.order() {
+                Order::Asc => ordering,
+                Order::Desc => ordering.reverse(),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// A named source of the values of the keys of a [`CompositeAggregation`].
+///
+/// In JSON, a source is an object with the name of the source as its single key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompositeSource {
+    /// The name of the source in the keys.
+    pub name: String,
+    /// How the values are extracted.
+    pub source: CompositeSourceVariants,
+}
+
+impl Serialize for CompositeSource {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(&self.name, &self.source)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CompositeSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let sources = BTreeMap::<String, CompositeSourceVariants>::deserialize(deserializer)?;
+        if sources.len() != 1 {
+            return Err(de::Error::custom(format!(
+                "a composite source must have exactly one name, got {:?}",
+                sources.keys().collect::<Vec<_>>()
+            )));
+        }
+        let (name, source) = sources.into_iter().next().expect("one source");
+        Ok(CompositeSource { name, source })
+    }
+}
+
+/// The types of sources of a [`CompositeAggregation`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CompositeSourceVariants {
+    /// The values of a field.
+    #[serde(rename = "terms")]
+    Terms(TermsCompositeSource),
+    /// The histogram buckets of the values of a field.
+    #[serde(rename = "histogram")]
+    Histogram(HistogramCompositeSource),
+    /// The date histogram buckets of the values of a date field.
+    #[serde(rename = "date_histogram")]
+    DateHistogram(DateHistogramCompositeSource),
+}
+
+impl CompositeSourceVariants {
+    /// Returns the field of the source.
+    pub fn field(&self) -> &str {
+        match self {
+            CompositeSourceVariants::Terms(terms) => &terms.field,
+            CompositeSourceVariants::Histogram(histogram) => &histogram.field,
+            CompositeSourceVariants::DateHistogram(date_histogram) => &date_histogram.field,
+        }
+    }
+
+    fn order(&self) -> Order {
+        match self {
+            CompositeSourceVariants::Terms(terms) => terms.order,
+            CompositeSourceVariants::Histogram(histogram) => histogram.order,
+            CompositeSourceVariants::DateHistogram(date_histogram) => date_histogram.order,
+        }
+    }
+
+    fn missing_bucket(&self) -> bool {
+        match self {
+            CompositeSourceVariants::Terms(terms) => terms.missing_bucket,
+            CompositeSourceVariants::Histogram(histogram) => histogram.missing_bucket,
+            CompositeSourceVariants::DateHistogram(date_histogram) => date_histogram.missing_bucket,
+        }
+    }
+
+    /// The column types the source can read.
+    pub(crate) fn allowed_column_types(&self) -> &'static [ColumnType] {
+        match self {
+            CompositeSourceVariants::Terms(_) => &[
+                ColumnType::I64,
+                ColumnType::U64,
+                ColumnType::F64,
+                ColumnType::Str,
+            ],
+            CompositeSourceVariants::Histogram(_) => &[
+                ColumnType::I64,
+                ColumnType::U64,
+                ColumnType::F64,
+                ColumnType::DateTime,
+            ],
+            CompositeSourceVariants::DateHistogram(_) => &[ColumnType::DateTime],
+        }
+    }
+
+    fn validate(&self) -> crate::Result<()> {
+        match self {
+            CompositeSourceVariants::Terms(_) => {}
+            CompositeSourceVariants::Histogram(histogram) => {
+                if histogram.interval <= 0.0 || !histogram.interval.is_finite() {
+                    return Err(TantivyError::AggregationError(
+                        AggregationError::InvalidRequest(format!(
+                            "composite histogram interval must be positive, got {}",
+                            histogram.interval
+                        )),
+                    ));
+                }
+            }
+            CompositeSourceVariants::DateHistogram(date_histogram) => {
+                date_histogram.interval_in_milliseconds()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A source of a [`CompositeAggregation`] with the values of a field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TermsCompositeSource {
+    /// The field to read the values from.
+    pub field: String,
+    /// The order of the values. Defaults to ascending.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether to put documents without a value into buckets.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+/// A source of a [`CompositeAggregation`] with the histogram buckets of the values of a field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistogramCompositeSource {
+    /// The field to read the values from.
+    pub field: String,
+    /// The width of the buckets. For date fields, it is in nanoseconds.
+    pub interval: f64,
+    /// The order of the buckets. Defaults to ascending.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether to put documents without a value into buckets.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+/// A source of a [`CompositeAggregation`] with the date histogram buckets of the values of a
+/// date field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DateHistogramCompositeSource {
+    /// The field to read the values from.
+    pub field: String,
+    /// The width of the buckets, e.g. `30d`. See
+    /// [`DateHistogramAggregationReq::fixed_interval`](super::DateHistogramAggregationReq) for
+    /// the supported units.
+    pub fixed_interval: String,
+    /// The order of the buckets. Defaults to ascending.
+    #[serde(default = "default_order")]
+    pub order: Order,
+    /// Whether to put documents without a value into buckets.
+    #[serde(default)]
+    pub missing_bucket: bool,
+}
+
+impl DateHistogramCompositeSource {
+    fn interval_in_milliseconds(&self) -> crate::Result<f64> {
+        Ok(parse_into_milliseconds(&self.fixed_interval)? as f64)
+    }
+}
+
+fn default_order() -> Order {
+    Order::Asc
+}
+
+/// The largest magnitude up to which all integers are exactly representable as `f64`.
+const MAX_EXACT_INTEGER: f64 = (1u64 << 53) as f64;
+
+/// The `after` value of a source, translated for a segment.
+#[derive(Clone, Debug)]
+enum AfterValue {
+    /// Documents without a value.
+    Missing,
+    /// A term, with the ordinal of the first term of the segment that is greater or equal.
+    TermOrd { term_ord: u64, exact: bool },
+    /// An integer, with the first column value that is greater or equal.
+    ColumnValue { value: u64, exact: bool },
+    /// A number, or the key of a histogram bucket.
+    F64(f64),
+    /// A value of a type the segment has no values of.
+    NotInSegment,
+}
+
+#[derive(Clone, Debug)]
+enum SourceKind {
+    Terms,
+    Histogram { interval: f64 },
+    DateHistogram { interval_ms: f64 },
+}
+
+/// The column of a composite source for a segment.
+pub(crate) struct CompositeSourceAccessor {
+    column: Column<u64>,
+    column_type: ColumnType,
+    str_dict_column: Option<StrColumn>,
+    kind: SourceKind,
+    order: Order,
+    missing_bucket: bool,
+    after: Option<AfterValue>,
+}
+
+impl CompositeSourceAccessor {
+    pub(crate) fn open(
+        source: &CompositeSource,
+        after: Option<Option<&Key>>,
+        column: Column<u64>,
+        column_type: ColumnType,
+        str_dict_column: Option<StrColumn>,
+    ) -> crate::Result<Self> {
+        let kind = match &source.source {
+            CompositeSourceVariants::Terms(_) => SourceKind::Terms,
+            CompositeSourceVariants::Histogram(histogram) => SourceKind::Histogram {
+                interval: histogram.interval,
+            },
+            CompositeSourceVariants::DateHistogram(date_histogram) => SourceKind::DateHistogram {
+                interval_ms: date_histogram.interval_in_milliseconds()?,
+            },
+        };
+        let str_dict_column = if column_type == ColumnType::Str {
+            str_dict_column
+        } else {
+            None
+        };
+        let mut accessor = CompositeSourceAccessor {
+            column,
+            column_type,
+            str_dict_column,
+            kind,
+            order: source.source.order(),
+            missing_bucket: source.source.missing_bucket(),
+            after: None,
+        };
+        accessor.after = after
+            .map(|after| accessor.translate_after_value(&source.name, after))
+            .transpose()?;
+        Ok(accessor)
+    }
+
+    fn translate_after_value(
+        &self,
+        source_name: &str,
+        after: Option<&Key>,
+    ) -> crate::Result<AfterValue> {
+        let after = match after {
+            Some(after) => after,
+            None => return Ok(AfterValue::Missing),
+        };
+        if self.column.values.num_vals() == 0 {
+            return Ok(AfterValue::NotInSegment);
+        }
+        match (after, &self.str_dict_column) {
+            (Key::Str(term), Some(str_dict_column)) => {
+                let dictionary = str_dict_column.dictionary();
+                if let Some(term_ord) = dictionary.term_ord(term)? {
+                    return Ok(AfterValue::TermOrd {
+                        term_ord,
+                        exact: true,
+                    });
+                }
+                let mut stream = dictionary.range().ge(term).into_stream()?;
+                let term_ord = if stream.advance() {
+                    stream.term_ord()
+                } else {
+                    dictionary.num_terms() as u64
+                };
+                Ok(AfterValue::TermOrd {
+                    term_ord,
+                    exact: false,
+                })
+            }
+            (Key::F64(val), None) => match (&self.kind, self.column_type) {
+                (SourceKind::Terms, ColumnType::U64 | ColumnType::I64) => {
+                    self.translate_integer_after_value(source_name, *val)
+                }
+                _ => Ok(AfterValue::F64(*val)),
+            },
+            _ => Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest(format!(
+                    "composite after value {after:?} of source {source_name:?} doesn't match the \
+                     type of the field"
+                )),
+            )),
+        }
+    }
+
+    /// Translates an `after` value into the space of the column values, so that integers are
+    /// compared without going through `f64`.
+    fn translate_integer_after_value(
+        &self,
+        source_name: &str,
+        after: f64,
+    ) -> crate::Result<AfterValue> {
+        if after.is_nan() || after.abs() > MAX_EXACT_INTEGER {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest(format!(
+                    "composite after value {after} of source {source_name:?} is outside of \
+                     +/-2^53 and can't be compared exactly with the values of the field"
+                )),
+            ));
+        }
+        let ceil = after.ceil();
+        let exact = ceil == after;
+        let after_value = if self.column_type == ColumnType::I64 {
+            AfterValue::ColumnValue {
+                value: common::i64_to_u64(ceil as i64),
+                exact,
+            }
+        } else if ceil < 0.0 {
+            AfterValue::ColumnValue {
+                value: 0,
+                exact: false,
+            }
+        } else {
+            AfterValue::ColumnValue {
+                value: ceil as u64,
+                exact,
+            }
+        };
+        Ok(after_value)
+    }
+
+    /// Returns the value of the source for a value of the column. The values keep the order of
+    /// the keys.
+    #[inline]
+    fn source_value(&self, val: u64) -> u64 {
+        match self.kind {
+            SourceKind::Terms => val,
+            SourceKind::Histogram { interval } => {
+                let val = f64_from_fastfield_u64(val, &self.column_type);
+                common::f64_to_u64((val / interval).floor() * interval)
+            }
+            SourceKind::DateHistogram { interval_ms } => {
+                let val_ms = f64_from_fastfield_u64(val, &self.column_type) / 1_000_000.0;
+                common::f64_to_u64((val_ms / interval_ms).floor() * interval_ms)
+            }
+        }
+    }
+
+    fn to_f64(&self, source_value: u64) -> f64 {
+        match self.kind {
+            SourceKind::Terms => f64_from_fastfield_u64(source_value, &self.column_type),
+            SourceKind::Histogram { .. } | SourceKind::DateHistogram { .. } => {
+                common::u64_to_f64(source_value)
+            }
+        }
+    }
+
+    fn to_intermediate_key(&self, source_value: u64) -> crate::Result<IntermediateKey> {
+        if let Some(str_dict_column) = self.str_dict_column.as_ref() {
+            let mut term = String::new();
+            str_dict_column.ord_to_str(source_value, &mut term)?;
+            Ok(IntermediateKey::Str(term))
+        } else {
+            Ok(IntermediateKey::F64(self.to_f64(source_value)))
+        }
+    }
+
+    /// Compares a value of the source with its `after` value, in ascending order.
+    fn compare_with_after(&self, source_value: Option<u64>, after: &AfterValue) -> Ordering {
+        match (source_value, after) {
+            (None, AfterValue::Missing) => Ordering::Equal,
+            (None, _) => Ordering::Less,
+            (Some(_), AfterValue::Missing) => Ordering::Greater,
+            (
+                Some(source_value),
+                AfterValue::TermOrd {
+                    term_ord: after_value,
+                    exact,
+                }
+                | AfterValue::ColumnValue {
+                    value: after_value,
+                    exact,
+                },
+            ) => match source_value.cmp(after_value) {
+                Ordering::Equal if !exact => Ordering::Greater,
+                ordering => ordering,
+            },
+            (Some(source_value), AfterValue::F64(after)) => {
+                self.to_f64(source_value).total_cmp(after)
+            }
+            // The segment has no values for the source.
+            (Some(_), AfterValue::NotInSegment) => Ordering::Equal,
+        }
+    }
+}
+
+/// A segment key part, encoded so that the natural order of the encoded values is the order of
+/// the source.
+type EncodedSourceValue = (u8, u64);
+
+fn encode_source_value(source_value: Option<u64>, order: Order) -> EncodedSourceValue {
+    match (order, source_value) {
+        (Order::Asc, None) => (0, 0),
+        (Order::Asc, Some(val)) => (1, val),
+        (Order::Desc, Some(val)) => (0, !val),
+        (Order::Desc, None) => (1, 0),
+    }
+}
+
+fn decode_source_value(encoded: EncodedSourceValue, order: Order) -> Option<u64> {
+    match (order, encoded) {
+        (Order::Asc, (0, _)) | (Order::Desc, (1, _)) => None,
+        (Order::Asc, (_, val)) => Some(val),
+        (Order::Desc, (_, val)) => Some(!val),
+    }
+}
+
+#[derive(Clone)]
+struct CompositeBucket {
+    doc_count: u32,
+    sub_aggregation: Option<Box<dyn SegmentAggregationCollector>>,
+}
+
+/// The collector keeps the `size` first buckets after the `after` key of the segment.
+///
+/// Keys are made of term ordinals and column values, and only converted into terms and numbers
+/// when the intermediate result is built. As term ordinals and column values keep the order of
+/// the values, the page of a segment can be computed without the conversion.
+#[derive(Clone)]
+pub(crate) struct SegmentCompositeCollector {
+    buckets: BTreeMap<Vec<EncodedSourceValue>, CompositeBucket>,
+    blueprint: Option<Box<dyn SegmentAggregationCollector>>,
+    size: usize,
+    accessor_idx: usize,
+    /// The values of each source for the document being collected.
+    doc_values: Vec<Vec<Option<u64>>>,
+    /// The index in `doc_values` of the value of each source, for the combination being
+    /// collected.
+    value_idxs: Vec<usize>,
+}
+
+impl Debug for SegmentCompositeCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SegmentCompositeCollector")
+            .field("num_buckets", &self.buckets.len())
+            .field("size", &self.size)
+            .field("accessor_idx", &self.accessor_idx)
+            .finish()
+    }
+}
+
+impl SegmentCompositeCollector {
+    pub(crate) fn from_req_and_validate(
+        req: &CompositeAggregation,
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        let blueprint = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+        Ok(SegmentCompositeCollector {
+            buckets: BTreeMap::new(),
+            blueprint,
+            size: req.size as usize,
+            accessor_idx,
+            doc_values: vec![Vec::new(); req.sources.len()],
+            value_idxs: vec![0; req.sources.len()],
+        })
+    }
+
+    /// Returns true if the key sorts after the `after` key, or if there is no `after` key.
+    fn is_after(sources: &[CompositeSourceAccessor], key: &[Option<u64>]) -> bool {
+        for (source, source_value) in sources.iter().zip(key) {
+            let after = match source.after.as_ref() {
+                Some(after) => after,
+                None => return true,
+            };
+            let ordering = match source.order {
+                Order::Asc => source.compare_with_after(*source_value, after),
+                Order::Desc => source.compare_with_after(*source_value, after).reverse(),
+            };
+            if ordering != Ordering::Equal {
+                return ordering == Ordering::Greater;
+            }
+        }
+        false
+    }
+}
+
+impl SegmentAggregationCollector for SegmentCompositeCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let bucket_agg_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+        let sources = &bucket_agg_accessor.composite_sources;
+        let sub_agg = &bucket_agg_accessor.sub_aggregation;
+
+        let buckets = self
+            .buckets
+            .into_iter()
+            .map(|(encoded_key, bucket)| {
+                let key = sources
+                    .iter()
+                    .zip(encoded_key)
+                    .map(|(source, encoded)| {
+                        decode_source_value(encoded, source.order)
+                            .map(|source_value| source.to_intermediate_key(source_value))
+                            .transpose()
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?;
+                let mut sub_aggregation_res = IntermediateAggregationResults::default();
+                if let Some(sub_aggregation) = bucket.sub_aggregation {
+                    sub_aggregation
+                        .add_intermediate_aggregation_result(sub_agg, &mut sub_aggregation_res)?;
+                }
+                Ok((
+                    key,
+                    IntermediateTermBucketEntry {
+                        doc_count: bucket.doc_count,
+                        sub_aggregation: sub_aggregation_res,
+                    },
+                ))
+            })
+            .collect::<crate::Result<_>>()?;
+
+        let bucket =
+            IntermediateBucketResult::Composite(IntermediateCompositeBucketResult { buckets });
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+        let sources = &bucket_agg_accessor.composite_sources;
+        let sub_aggregation_accessor = &mut bucket_agg_accessor.sub_aggregation;
+
+        let mut key = Vec::with_capacity(sources.len());
+        let mut encoded_key = Vec::with_capacity(sources.len());
+        'docs: for &doc in docs {
+            for (source, values) in sources.iter().zip(self.doc_values.iter_mut()) {
+                values.clear();
+                values.extend(
+                    source
+                        .column
+                        .values_for_doc(doc)
+                        .map(|val| Some(source.source_value(val))),
+                );
+                values.sort_unstable();
+                values.dedup();
+                if values.is_empty() {
+                    if !source.missing_bucket {
+                        continue 'docs;
+                    }
+                    values.push(None);
+                }
+            }
+
+            // Visits the combinations of the values of the sources.
+            self.value_idxs.fill(0);
+            loop {
+                key.clear();
+                key.extend(
+                    self.doc_values
+                        .iter()
+                        .zip(&self.value_idxs)
+                        .map(|(values, &value_idx)| values[value_idx]),
+                );
+                encoded_key.clear();
+                encoded_key.extend(sources.iter().zip(&key).map(|(source, &source_value)| {
+                    encode_source_value(source_value, source.order)
+                }));
+
+                let mut is_in_page = self.buckets.contains_key(&encoded_key);
+                if !is_in_page {
+                    let last_key = self.buckets.keys().next_back();
+                    let is_full = self.buckets.len() >= self.size;
+                    let is_past_page = is_full
+                        && last_key
+                            .map(|last_key| encoded_key > *last_key)
+                            .unwrap_or(false);
+                    if !is_past_page && Self::is_after(sources, &key) {
+                        if is_full {
+                            // The new bucket pushes the last one out of the page.
+                            let last_key = last_key.cloned();
+                            if let Some(last_key) = last_key {
+                                self.buckets.remove(&last_key);
+                            }
+                        } else {
+                            bucket_agg_accessor.limits.add_memory_consumed(
+                                (std::mem::size_of::<CompositeBucket>()
+                                    + encoded_key.len() * std::mem::size_of::<EncodedSourceValue>())
+                                    as u64,
+                            )?;
+                        }
+                        self.buckets.insert(
+                            encoded_key.clone(),
+                            CompositeBucket {
+                                doc_count: 0,
+                                sub_aggregation: self.blueprint.clone(),
+                            },
+                        );
+                        is_in_page = true;
+                    }
+                }
+                if is_in_page {
+                    let bucket = self
+                        .buckets
+                        .get_mut(&encoded_key)
+                        .expect("the bucket is in the page");
+                    bucket.doc_count += 1;
+                    if let Some(sub_aggregation) = bucket.sub_aggregation.as_mut() {
+                        sub_aggregation.collect(doc, sub_aggregation_accessor)?;
+                    }
+                }
+
+                // Moves to the next combination, the last source varying fastest.
+                let mut source_idx = sources.len();
+                loop {
+                    if source_idx == 0 {
+                        continue 'docs;
+                    }
+                    source_idx -= 1;
+                    self.value_idxs[source_idx] += 1;
+                    if self.value_idxs[source_idx] < self.doc_values[source_idx].len() {
+                        break;
+                    }
+                    self.value_idxs[source_idx] = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+
+        for bucket in self.buckets.values_mut() {
+            if let Some(sub_agg) = bucket.sub_aggregation.as_mut() {
+                sub_agg.flush(sub_aggregation_accessor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::{
+        exec_request_with_query, exec_request_with_query_and_memory_limit,
+    };
+    use crate::aggregation::AggregationLimits;
+    use crate::schema::{Schema, FAST, STRING};
+    use crate::{DateTime, Index};
+
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+    fn get_test_index_with_requests(merge_segments: bool) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_text_field("tenant", STRING | FAST);
+        let timestamp = schema_builder.add_date_field("timestamp", FAST);
+        let status = schema_builder.add_u64_field("status", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let day = |day: i64| DateTime::from_timestamp_millis(day * DAY_MS + 1000);
+        index_writer.add_document(doc!(tenant => "acme", timestamp => day(0), status => 200u64))?;
+        index_writer.add_document(doc!(tenant => "acme", timestamp => day(0), status => 404u64))?;
+        index_writer.add_document(doc!(tenant => "beta", timestamp => day(1), status => 200u64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(tenant => "acme", timestamp => day(0), status => 200u64))?;
+        index_writer.add_document(doc!(tenant => "acme", timestamp => day(1), status => 500u64))?;
+        index_writer.add_document(doc!(tenant => "zeta", timestamp => day(1), status => 200u64))?;
+        index_writer.add_document(doc!(timestamp => day(2), status => 200u64))?;
+        index_writer.commit()?;
+        if merge_segments {
+            let segment_ids = index.searchable_segment_ids()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        Ok(index)
+    }
+
+    fn composite_req(size: u32, after: Option<Value>) -> Aggregations {
+        let mut composite = json!({
+            "size": size,
+            "sources": [
+                { "tenant": { "terms": { "field": "tenant" } } },
+                { "day": { "date_histogram": { "field": "timestamp", "fixed_interval": "1d" } } },
+                { "status": { "terms": { "field": "status", "order": "desc" } } }
+            ]
+        });
+        if let Some(after) = after {
+            composite["after"] = after;
+        }
+        serde_json::from_value(json!({ "counts": { "composite": composite } })).unwrap()
+    }
+
+    #[test]
+    fn test_composite_aggregation() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let index = get_test_index_with_requests(merge_segments)?;
+            let res = exec_request_with_query(composite_req(10, None), &index, None)?;
+            assert_eq!(
+                res["counts"],
+                json!({
+                    "buckets": [
+                        { "key": { "tenant": "acme", "day": 0.0, "status": 404.0 }, "doc_count": 1 },
+                        { "key": { "tenant": "acme", "day": 0.0, "status": 200.0 }, "doc_count": 2 },
+                        { "key": { "tenant": "acme", "day": DAY_MS as f64, "status": 500.0 }, "doc_count": 1 },
+                        { "key": { "tenant": "beta", "day": DAY_MS as f64, "status": 200.0 }, "doc_count": 1 },
+                        { "key": { "tenant": "zeta", "day": DAY_MS as f64, "status": 200.0 }, "doc_count": 1 },
+                    ],
+                    "after_key": { "tenant": "zeta", "day": DAY_MS as f64, "status": 200.0 }
+                })
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_missing_bucket() -> crate::Result<()> {
+        let index = get_test_index_with_requests(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "counts": {
+                "composite": {
+                    "sources": [
+                        { "tenant": { "terms": { "field": "tenant", "missing_bucket": true } } }
+                    ]
+                },
+                "aggs": { "max_status": { "max": { "field": "status" } } }
+            }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(
+            res["counts"]["buckets"],
+            json!([
+                { "key": { "tenant": null }, "doc_count": 1, "max_status": { "value": 200.0 } },
+                { "key": { "tenant": "acme" }, "doc_count": 4, "max_status": { "value": 500.0 } },
+                { "key": { "tenant": "beta" }, "doc_count": 1, "max_status": { "value": 200.0 } },
+                { "key": { "tenant": "zeta" }, "doc_count": 1, "max_status": { "value": 200.0 } },
+            ])
+        );
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "counts": {
+                "composite": {
+                    "sources": [
+                        { "tenant": { "terms": { "field": "tenant", "missing_bucket": true } } }
+                    ],
+                    "after": { "tenant": null },
+                    "size": 1
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(
+            res["counts"],
+            json!({
+                "buckets": [{ "key": { "tenant": "acme" }, "doc_count": 4 }],
+                "after_key": { "tenant": "acme" }
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_pagination() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let index = get_test_index_with_requests(merge_segments)?;
+            let all_buckets = exec_request_with_query(composite_req(10, None), &index, None)?
+                ["counts"]["buckets"]
+                .clone();
+
+            let mut buckets = Vec::new();
+            let mut after = None;
+            loop {
+                let res = exec_request_with_query(composite_req(2, after), &index, None)?;
+                let page = res["counts"]["buckets"].as_array().unwrap().clone();
+                assert!(page.len() <= 2);
+                buckets.extend(page);
+                if res["counts"].get("after_key").is_none() {
+                    break;
+                }
+                after = Some(res["counts"]["after_key"].clone());
+            }
+            assert_eq!(Value::Array(buckets), all_buckets);
+        }
+
+        // The after key doesn't need to be the key of a bucket.
+        let index = get_test_index_with_requests(false)?;
+        let after = json!({ "tenant": "alpha", "day": 0.0, "status": 1000.0 });
+        let res = exec_request_with_query(composite_req(1, Some(after)), &index, None)?;
+        assert_eq!(
+            res["counts"]["after_key"],
+            json!({ "tenant": "beta", "day": DAY_MS as f64, "status": 200.0 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_pages_under_bucket_limit() -> crate::Result<()> {
+        let index = get_test_index_with_requests(false)?;
+        let limits = AggregationLimits::new(None, Some(3));
+        let err = exec_request_with_query_and_memory_limit(
+            composite_req(10, None),
+            &index,
+            None,
+            limits.clone(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("bucket limit was exceeded"));
+
+        let mut num_buckets = 0;
+        let mut after = None;
+        loop {
+            let res = exec_request_with_query_and_memory_limit(
+                composite_req(3, after),
+                &index,
+                None,
+                limits.clone(),
+            )?;
+            num_buckets += res["counts"]["buckets"].as_array().unwrap().len();
+            if res["counts"].get("after_key").is_none() {
+                break;
+            }
+            after = Some(res["counts"]["after_key"].clone());
+        }
+        assert_eq!(num_buckets, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_histogram_multivalued() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let price = schema_builder.add_f64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(price => 1.5f64, price => 2.5f64, price => 12.0f64))?;
+        index_writer.add_document(doc!(price => -3.0f64))?;
+        index_writer.commit()?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "prices": {
+                "composite": {
+                    "sources": [
+                        { "price": { "histogram": { "field": "price", "interval": 10.0 } } }
+                    ]
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(
+            res["prices"]["buckets"],
+            json!([
+                { "key": { "price": -10.0 }, "doc_count": 1 },
+                { "key": { "price": 0.0 }, "doc_count": 1 },
+                { "key": { "price": 10.0 }, "doc_count": 1 },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_integer_after() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for val in [3u64, 7, (1 << 53) + 1] {
+            index_writer.add_document(doc!(id => val))?;
+        }
+        index_writer.commit()?;
+        let agg_req = |after: Value| -> Aggregations {
+            serde_json::from_value(json!({
+                "ids": {
+                    "composite": {
+                        "sources": [{ "id": { "terms": { "field": "id" } } }],
+                        "after": { "id": after }
+                    }
+                }
+            }))
+            .unwrap()
+        };
+
+        let res = exec_request_with_query(agg_req(json!(3.5)), &index, None)?;
+        assert_eq!(res["ids"]["buckets"].as_array().unwrap().len(), 2);
+        assert_eq!(res["ids"]["buckets"][0]["key"]["id"], json!(7.0));
+        let res = exec_request_with_query(agg_req(json!(-1.0)), &index, None)?;
+        assert_eq!(res["ids"]["buckets"].as_array().unwrap().len(), 3);
+        // The value is compared as an integer, not after a conversion to f64.
+        let res = exec_request_with_query(agg_req(json!((1u64 << 53) as f64)), &index, None)?;
+        assert_eq!(res["ids"]["buckets"].as_array().unwrap().len(), 1);
+
+        let err = exec_request_with_query(agg_req(json!(((1u64 << 53) + 2) as f64)), &index, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("outside of +/-2^53"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_composite_aggregation_invalid_request() -> crate::Result<()> {
+        let index = get_test_index_with_requests(false)?;
+        let after = json!({ "tenant": "acme" });
+        let err =
+            exec_request_with_query(composite_req(10, Some(after)), &index, None).unwrap_err();
+        assert!(err.to_string().contains("after key must have a value"));
+
+        let after = json!({ "tenant": 3.0, "day": 0.0, "status": 200.0 });
+        let err =
+            exec_request_with_query(composite_req(10, Some(after)), &index, None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("doesn't match the type of the field"));
+
+        let agg_req: Result<Aggregations, _> = serde_json::from_value(json!({
+            "counts": {
+                "composite": {
+                    "sources": [{ "a": { "terms": { "field": "tenant" } }, "b": { "terms": { "field": "tenant" } } }]
+                }
+            }
+        }));
+        assert!(agg_req.is_err());
+        Ok(())
+    }
+}
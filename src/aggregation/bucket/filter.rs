@@ -0,0 +1,666 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use common::BitSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::aggregation::agg_req_with_accessor::AggregationsWithAccessor;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateFiltersBucketResult, IntermediateTermBucketEntry,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::aggregation::AggregationError;
+use crate::query::{EnableScoring, Query, QueryParser, Weight};
+use crate::{DocId, SegmentReader, TantivyError};
+
+/// The query of a [`FilterAggregation`] or of a bucket of a [`FiltersAggregation`].
+///
+/// In JSON, the query is a query string, parsed with the
+/// [`QueryParser`] and the tokenizers of the index. As there are no default fields, the fields
+/// have to be explicit, e.g. `in_stock:true`. For queries that can't be expressed as query
+/// strings, a boxed [`Query`] can be used instead. Such requests can't be serialized.
+///
+/// Boxed queries are compared through their `Debug` representation.
+#[derive(Debug)]
+pub enum FilterQuery {
+    /// A query string.
+    QueryString(String),
+    /// A query.
+    Query(Box<dyn Query>),
+}
+
+impl FilterQuery {
+    fn weight(&self, reader: &SegmentReader) -> crate::Result<Box<dyn Weight>> {
+        let schema = reader.schema();
+        match self {
+            FilterQuery::QueryString(query_string) => {
+                let query_parser =
+                    QueryParser::new(schema.clone(), Vec::new(), reader.tokenizers().clone());
+                let query = query_parser.parse_query(query_string).map_err(|err| {
+                    TantivyError::AggregationError(AggregationError::InvalidRequest(format!(
+                        "Invalid filter query {query_string:?}: {err}"
+                    )))
+                })?;
+                query.weight(EnableScoring::disabled_from_schema(schema))
+            }
+            FilterQuery::Query(query) => query.weight(EnableScoring::disabled_from_schema(schema)),
+        }
+    }
+}
+
+impl Clone for FilterQuery {
+    fn clone(&self) -> Self {
+        match self {
+            FilterQuery::QueryString(query_string) => {
+                FilterQuery::QueryString(query_string.clone())
+            }
+            FilterQuery::Query(query) => FilterQuery::Query(query.box_clone()),
+        }
+    }
+}
+
+impl PartialEq for FilterQuery {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FilterQuery::QueryString(left), FilterQuery::QueryString(right)) => left == right,
+            (FilterQuery::Query(left), FilterQuery::Query(right)) => {
+                format!("{left:?}") == format!("{right:?}")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Serialize for FilterQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        match self {
+            FilterQuery::QueryString(query_string) => serializer.serialize_str(query_string),
+            FilterQuery::Query(_) => Err(serde::ser::Error::custom(
+                "filter queries can only be serialized as query strings",
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FilterQuery {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        Ok(FilterQuery::QueryString(String::deserialize(deserializer)?))
+    }
+}
+
+impl From<String> for FilterQuery {
+    fn from(query_string: String) -> Self {
+        FilterQuery::QueryString(query_string)
+    }
+}
+
+impl From<&str> for FilterQuery {
+    fn from(query_string: &str) -> Self {
+        FilterQuery::QueryString(query_string.to_string())
+    }
+}
+
+impl From<Box<dyn Query>> for FilterQuery {
+    fn from(query: Box<dyn Query>) -> Self {
+        FilterQuery::Query(query)
+    }
+}
+
+/// A single bucket containing the documents matching a query.
+///
+/// The result has the `doc_count` of the bucket, and its sub-aggregations, e.g. the average
+/// price of the products in stock.
+///
+/// Result type is [`BucketResult::Filter`](crate::aggregation::agg_result::BucketResult).
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "in_stock": {
+///         "filter": { "query": "in_stock:true" },
+///         "aggs": {
+///             "avg_price": { "avg": { "field": "price" } }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterAggregation {
+    /// The query the documents of the bucket match.
+    pub query: FilterQuery,
+}
+
+impl FilterAggregation {
+    /// Creates a filter aggregation from a query string or a boxed query.
+    pub fn new(query: impl Into<FilterQuery>) -> Self {
+        FilterAggregation {
+            query: query.into(),
+        }
+    }
+}
+
+/// The default key of the bucket of the documents matching none of the filters.
+pub const DEFAULT_OTHER_BUCKET_KEY: &str = "_other_";
+
+/// One bucket per named query, containing the documents matching the query.
+///
+/// Buckets may overlap, a document is counted in the bucket of each query it matches. With
+/// `other_bucket`, an additional bucket contains the documents matching none of the queries.
+/// Its key defaults to `_other_`, and can be set with `other_bucket_key`.
+///
+/// Result type is [`BucketResult::Filters`](crate::aggregation::agg_result::BucketResult), with
+/// the buckets keyed by name.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "slices": {
+///         "filters": {
+///             "filters": {
+///                 "in_stock": "in_stock:true",
+///                 "on_sale": "on_sale:true"
+///             },
+///             "other_bucket": true
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FiltersAggregation {
+    /// The queries of the buckets, by name.
+    pub filters: BTreeMap<String, FilterQuery>,
+    /// Whether to add a bucket for the documents matching none of the queries.
+    #[serde(default)]
+    pub other_bucket: bool,
+    /// The key of the bucket for the documents matching none of the queries. Setting it
+    /// enables the bucket.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub other_bucket_key: Option<String>,
+}
+
+impl FiltersAggregation {
+    /// Creates a filters aggregation from named query strings or boxed queries.
+    pub fn new<K: ToString, Q: Into<FilterQuery>>(
+        filters: impl IntoIterator<Item = (K, Q)>,
+    ) -> Self {
+        FiltersAggregation {
+            filters: filters
+                .into_iter()
+                .map(|(name, query)| (name.to_string(), query.into()))
+                .collect(),
+            other_bucket: false,
+            other_bucket_key: None,
+        }
+    }
+
+    /// Returns the key of the other bucket, if it is enabled.
+    pub(crate) fn other_bucket_key(&self) -> Option<&str> {
+        match self.other_bucket_key.as_deref() {
+            Some(key) => Some(key),
+            None if self.other_bucket => Some(DEFAULT_OTHER_BUCKET_KEY),
+            None => None,
+        }
+    }
+
+    fn validate(&self) -> crate::Result<()> {
+        if let Some(other_bucket_key) = self.other_bucket_key() {
+            if self.filters.contains_key(other_bucket_key) {
+                return Err(TantivyError::AggregationError(
+                    AggregationError::InvalidRequest(format!(
+                        "filters other_bucket_key {other_bucket_key:?} collides with a filter name"
+                    )),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The documents of a segment matching a filter query.
+///
+/// The query is run once per segment. Documents may then be checked in any order, e.g. when they
+/// come grouped by the buckets of a parent aggregation.
+pub(crate) struct FilterWeight {
+    doc_bitset: BitSet,
+}
+
+impl FilterWeight {
+    pub(crate) fn for_segment(query: &FilterQuery, reader: &SegmentReader) -> crate::Result<Self> {
+        let weight = query.weight(reader)?;
+        let mut doc_bitset = BitSet::with_max_value(reader.max_doc());
+        weight.for_each_no_score(reader, &mut |docs| {
+            for &doc in docs {
+                doc_bitset.insert(doc);
+            }
+        })?;
+        Ok(FilterWeight { doc_bitset })
+    }
+
+    /// Returns true if the document matches the query.
+    #[inline]
+    fn matches(&self, doc: DocId) -> bool {
+        self.doc_bitset.contains(doc)
+    }
+}
+
+#[derive(Clone)]
+struct FilterBucket {
+    doc_count: u32,
+    sub_aggregation: Option<Box<dyn SegmentAggregationCollector>>,
+}
+
+impl FilterBucket {
+    fn collect(
+        &mut self,
+        doc: DocId,
+        sub_aggregation_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.doc_count += 1;
+        if let Some(sub_aggregation) = self.sub_aggregation.as_mut() {
+            sub_aggregation.collect(doc, sub_aggregation_accessor)?;
+        }
+        Ok(())
+    }
+
+    fn into_intermediate_bucket_entry(
+        self,
+        sub_aggregation_accessor: &AggregationsWithAccessor,
+    ) -> crate::Result<IntermediateTermBucketEntry> {
+        let mut sub_aggregation_res = IntermediateAggregationResults::default();
+        if let Some(sub_aggregation) = self.sub_aggregation {
+            sub_aggregation.add_intermediate_aggregation_result(
+                sub_aggregation_accessor,
+                &mut sub_aggregation_res,
+            )?;
+        }
+        Ok(IntermediateTermBucketEntry {
+            doc_count: self.doc_count,
+            sub_aggregation: sub_aggregation_res,
+        })
+    }
+}
+
+/// The collector of the `filter` and `filters` aggregations.
+///
+/// Its buckets are in the order of the filter weights of the accessor, followed by the other
+/// bucket. The names of the buckets are `None` for the `filter` aggregation.
+#[derive(Clone)]
+pub(crate) struct SegmentFiltersCollector {
+    bucket_names: Option<Vec<String>>,
+    buckets: Vec<FilterBucket>,
+    other_bucket: Option<(String, FilterBucket)>,
+    accessor_idx: usize,
+}
+
+impl Debug for SegmentFiltersCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SegmentFiltersCollector")
+            .field("bucket_names", &self.bucket_names)
+            .field("num_buckets", &self.buckets.len())
+            .field("accessor_idx", &self.accessor_idx)
+            .finish()
+    }
+}
+
+impl SegmentFiltersCollector {
+    pub(crate) fn from_filter_req(
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        let bucket = Self::empty_bucket(sub_aggregation)?;
+        Ok(SegmentFiltersCollector {
+            bucket_names: None,
+            buckets: vec![bucket],
+            other_bucket: None,
+            accessor_idx,
+        })
+    }
+
+    pub(crate) fn from_filters_req_and_validate(
+        req: &FiltersAggregation,
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        let bucket = Self::empty_bucket(sub_aggregation)?;
+        Ok(SegmentFiltersCollector {
+            bucket_names: Some(req.filters.keys().cloned().collect()),
+            buckets: vec![bucket.clone(); req.filters.len()],
+            other_bucket: req.other_bucket_key().map(|key| (key.to_string(), bucket)),
+            accessor_idx,
+        })
+    }
+
+    fn empty_bucket(sub_aggregation: &mut AggregationsWithAccessor) -> crate::Result<FilterBucket> {
+        let sub_aggregation = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+        Ok(FilterBucket {
+            doc_count: 0,
+            sub_aggregation,
+        })
+    }
+}
+
+impl SegmentAggregationCollector for SegmentFiltersCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let sub_agg = &agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+
+        let bucket = if let Some(bucket_names) = self.bucket_names {
+            let mut buckets = bucket_names
+                .into_iter()
+                .zip(self.buckets)
+                .map(|(bucket_name, bucket)| {
+                    Ok((bucket_name, bucket.into_intermediate_bucket_entry(sub_agg)?))
+                })
+                .collect::<crate::Result<rustc_hash::FxHashMap<_, _>>>()?;
+            if let Some((other_bucket_key, other_bucket)) = self.other_bucket {
+                buckets.insert(
+                    other_bucket_key,
+                    other_bucket.into_intermediate_bucket_entry(sub_agg)?,
+                );
+            }
+            IntermediateBucketResult::Filters(IntermediateFiltersBucketResult { buckets })
+        } else {
+            let bucket = self
+                .buckets
+                .into_iter()
+                .next()
+                .expect("the filter aggregation has a single bucket");
+            IntermediateBucketResult::Filter(bucket.into_intermediate_bucket_entry(sub_agg)?)
+        };
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+        for &doc in docs {
+            let mut matched_any = false;
+            for (filter_weight, bucket) in bucket_agg_accessor
+                .filter_weights
+                .iter()
+                .zip(self.buckets.iter_mut())
+            {
+                if filter_weight.matches(doc) {
+                    matched_any = true;
+                    bucket.collect(doc, &mut bucket_agg_accessor.sub_aggregation)?;
+                }
+            }
+            if !matched_any {
+                if let Some((_, other_bucket)) = self.other_bucket.as_mut() {
+                    other_bucket.collect(doc, &mut bucket_agg_accessor.sub_aggregation)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+        let other_bucket = self.other_bucket.as_mut().map(|(_, bucket)| bucket);
+        for bucket in self.buckets.iter_mut().chain(other_bucket) {
+            if let Some(sub_aggregation) = bucket.sub_aggregation.as_mut() {
+                sub_aggregation.flush(sub_aggregation_accessor)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{FilterQuery, FilterWeight, FiltersAggregation};
+    use crate::aggregation::agg_req::{Aggregation, AggregationVariants, Aggregations};
+    use crate::aggregation::tests::exec_request_with_query;
+    use crate::aggregation::AggregationCollector;
+    use crate::query::{AllQuery, Query, TermQuery};
+    use crate::schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STRING,
+    };
+    use crate::tokenizer::{LowerCaser, RawTokenizer, TextAnalyzer};
+    use crate::{Index, Term};
+
+    fn get_test_index_with_products(merge_segments: bool) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let category = schema_builder.add_text_field("category", STRING | FAST);
+        let price = schema_builder.add_u64_field("price", INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(category => "book", price => 10u64))?;
+        index_writer.add_document(doc!(category => "book", price => 30u64))?;
+        index_writer.add_document(doc!(category => "music", price => 5u64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(category => "book", price => 20u64))?;
+        index_writer.add_document(doc!(category => "movie", price => 15u64))?;
+        index_writer.add_document(doc!(price => 100u64))?;
+        index_writer.commit()?;
+        if merge_segments {
+            let segment_ids = index.searchable_segment_ids()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        Ok(index)
+    }
+
+    #[test]
+    fn test_filter_aggregation() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let index = get_test_index_with_products(merge_segments)?;
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "books": {
+                    "filter": { "query": "category:book" },
+                    "aggs": { "avg_price": { "avg": { "field": "price" } } }
+                }
+            }))
+            .unwrap();
+            let res = exec_request_with_query(agg_req, &index, None)?;
+            assert_eq!(
+                res["books"],
+                json!({ "doc_count": 3, "avg_price": { "value": 20.0 } })
+            );
+
+            // Only the documents matching the search query are considered.
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "cheap": { "filter": { "query": "price:[0 TO 15]" } }
+            }))
+            .unwrap();
+            let res = exec_request_with_query(agg_req, &index, Some(("category", "book")))?;
+            assert_eq!(res["cheap"], json!({ "doc_count": 1 }));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters_aggregation() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let index = get_test_index_with_products(merge_segments)?;
+            let category = index.schema().get_field("category").unwrap();
+            let music_query: Box<dyn Query> = Box::new(TermQuery::new(
+                Term::from_field_text(category, "music"),
+                IndexRecordOption::Basic,
+            ));
+            let mut filters = FiltersAggregation::new([
+                ("books", FilterQuery::from("category:book")),
+                ("cheap", FilterQuery::from("price:[0 TO 15]")),
+                ("music", FilterQuery::from(music_query)),
+            ]);
+            filters.other_bucket = true;
+            assert_eq!(filters, filters.clone());
+            let agg_req: Aggregations = vec![(
+                "slices".to_string(),
+                Aggregation {
+                    agg: AggregationVariants::Filters(filters),
+                    sub_aggregation: serde_json::from_value(json!({
+                        "max_price": { "max": { "field": "price" } }
+                    }))
+                    .unwrap(),
+                },
+            )]
+            .into_iter()
+            .collect();
+
+            let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+            let searcher = index.reader()?.searcher();
+            let res = searcher.search(&AllQuery, &collector)?;
+            let res: Value = serde_json::to_value(res)?;
+            assert_eq!(
+                res["slices"]["buckets"],
+                json!({
+                    "books": { "doc_count": 3, "max_price": { "value": 30.0 } },
+                    "cheap": { "doc_count": 3, "max_price": { "value": 15.0 } },
+                    "music": { "doc_count": 1, "max_price": { "value": 5.0 } },
+                    "_other_": { "doc_count": 1, "max_price": { "value": 100.0 } },
+                })
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters_aggregation_under_terms() -> crate::Result<()> {
+        let index = get_test_index_with_products(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "categories": {
+                "terms": { "field": "category" },
+                "aggs": {
+                    "slices": {
+                        "filters": {
+                            "filters": { "cheap": "price:[0 TO 15]" },
+                            "other_bucket_key": "expensive"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(
+            res["categories"]["buckets"][0],
+            json!({
+                "key": "book",
+                "doc_count": 3,
+                "slices": {
+                    "buckets": {
+                        "cheap": { "doc_count": 1 },
+                        "expensive": { "doc_count": 2 },
+                    }
+                }
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_query_eq() {
+        let category = Field::from_field_id(0);
+        let term_query = |text: &str| -> FilterQuery {
+            let query: Box<dyn Query> = Box::new(TermQuery::new(
+                Term::from_field_text(category, text),
+                IndexRecordOption::Basic,
+            ));
+            FilterQuery::from(query)
+        };
+        assert_eq!(term_query("book"), term_query("book"));
+        assert_ne!(term_query("book"), term_query("music"));
+        assert_ne!(term_query("book"), FilterQuery::from("category:book"));
+    }
+
+    #[test]
+    fn test_filter_aggregation_custom_tokenizer() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let genre = schema_builder.add_text_field(
+            "genre",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("lowercased_raw")),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "lowercased_raw",
+            TextAnalyzer::builder(RawTokenizer::default())
+                .filter(LowerCaser)
+                .build(),
+        );
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(genre => "Science Fiction"))?;
+        index_writer.add_document(doc!(genre => "science fiction"))?;
+        index_writer.add_document(doc!(genre => "Science"))?;
+        index_writer.commit()?;
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "sf": { "filter": { "query": "genre:\"SCIENCE FICTION\"" } }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(res["sf"]["doc_count"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_weight_out_of_order() -> crate::Result<()> {
+        let index = get_test_index_with_products(false)?;
+        let searcher = index.reader()?.searcher();
+        let query = FilterQuery::QueryString("category:book".to_string());
+        // Both segments start with a book, and have no book after their second document.
+        let filter_weight = FilterWeight::for_segment(&query, searcher.segment_reader(0))?;
+        assert!(filter_weight.matches(1));
+        assert!(!filter_weight.matches(2));
+        // Going back to a previous document still matches it.
+        assert!(filter_weight.matches(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_aggregation_invalid_request() -> crate::Result<()> {
+        let index = get_test_index_with_products(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "books": { "filter": { "query": "unknown_field:book" } }
+        }))
+        .unwrap();
+        let err = exec_request_with_query(agg_req, &index, None).unwrap_err();
+        assert!(err.to_string().contains("Invalid filter query"));
+
+        let mut filters = FiltersAggregation::new([("_other_", "category:book")]);
+        filters.other_bucket = true;
+        let agg_req: Aggregations = vec![(
+            "slices".to_string(),
+            Aggregation {
+                agg: AggregationVariants::Filters(filters),
+                sub_aggregation: Default::default(),
+            },
+        )]
+        .into_iter()
+        .collect();
+        let err = exec_request_with_query(agg_req, &index, None).unwrap_err();
+        assert!(err.to_string().contains("collides with a filter name"));
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt::Debug;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::agg_req_with_accessor::AggregationsWithAccessor;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateBucketResult,
+    IntermediateGeohashGridBucketResult, IntermediateTermBucketEntry,
+};
+use crate::aggregation::segment_agg_result::{
+    build_segment_agg_collector, SegmentAggregationCollector,
+};
+use crate::aggregation::AggregationError;
+use crate::schema::{geohash_from_cell_bits, GeoPoint, MAX_GEOHASH_PRECISION};
+use crate::TantivyError;
+
+/// Groups the points of a geo point field in the cells of a geohash grid.
+///
+/// Each bucket corresponds to a cell, and its key is the geohash of the cell. Precision is the
+/// number of characters of the geohashes, between 1 and 12: a cell of precision 1 covers about
+/// 5000km x 5000km, while a cell of precision 12 covers a few centimeters.
+///
+/// The buckets are sorted by decreasing `doc_count`, then by key. Only the `size` largest buckets
+/// are returned.
+///
+/// The field needs to be a fast geo point field.
+/// If a document has several points, each of its points is counted.
+///
+/// Result type is [`BucketResult`](crate::aggregation::agg_result::BucketResult) with
+/// [`BucketEntry`](crate::aggregation::agg_result::BucketEntry) on the
+/// `AggregationCollector`.
+///
+/// # Request JSON Format
+/// ```json
+/// {
+///     "locations": {
+///         "geohash_grid": {
+///             "field": "location",
+///             "precision": 3
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeohashGridAggregation {
+    /// The field to aggregate on.
+    pub field: String,
+    /// The number of characters of the geohashes of the cells. Defaults to 5.
+    #[serde(default = "default_precision")]
+    pub precision: usize,
+    /// The maximum number of buckets returned. Defaults to 10000.
+    #[serde(default = "default_size")]
+    pub size: u32,
+}
+
+fn default_precision() -> usize {
+    5
+}
+
+fn default_size() -> u32 {
+    10_000
+}
+
+impl GeohashGridAggregation {
+    fn validate(&self) -> crate::Result<()> {
+        if !(1..=MAX_GEOHASH_PRECISION).contains(&self.precision) {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest(format!(
+                    "geohash_grid precision must be between 1 and {MAX_GEOHASH_PRECISION}, got {}",
+                    self.precision
+                )),
+            ));
+        }
+        if self.size == 0 {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest(
+                    "geohash_grid size must be at least 1".to_string(),
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct GeohashGridBucket {
+    doc_count: u32,
+    sub_aggregation: Option<Box<dyn SegmentAggregationCollector>>,
+}
+
+/// The collector puts the points of the fast field into the cells of the geohash grid.
+///
+/// Cells are identified by the interleaved bits of their geohash, and only converted into
+/// geohashes when the intermediate result is built.
+#[derive(Clone)]
+pub(crate) struct SegmentGeohashGridCollector {
+    buckets: FxHashMap<u64, GeohashGridBucket>,
+    blueprint: Option<Box<dyn SegmentAggregationCollector>>,
+    precision: usize,
+    accessor_idx: usize,
+}
+
+impl Debug for SegmentGeohashGridCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SegmentGeohashGridCollector")
+            .field("num_buckets", &self.buckets.len())
+            .field("precision", &self.precision)
+            .field("accessor_idx", &self.accessor_idx)
+            .finish()
+    }
+}
+
+impl SegmentGeohashGridCollector {
+    pub(crate) fn from_req_and_validate(
+        req: &GeohashGridAggregation,
+        sub_aggregation: &mut AggregationsWithAccessor,
+        accessor_idx: usize,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        let blueprint = if sub_aggregation.is_empty() {
+            None
+        } else {
+            Some(build_segment_agg_collector(sub_aggregation)?)
+        };
+        Ok(SegmentGeohashGridCollector {
+            buckets: FxHashMap::default(),
+            blueprint,
+            precision: req.precision,
+            accessor_idx,
+        })
+    }
+}
+
+impl SegmentAggregationCollector for SegmentGeohashGridCollector {
+    fn add_intermediate_aggregation_result(
+        self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let sub_agg = &agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+        let precision = self.precision;
+
+        let buckets = self
+            .buckets
+            .into_iter()
+            .map(|(cell, bucket)| {
+                let mut sub_aggregation_res = IntermediateAggregationResults::default();
+                if let Some(sub_aggregation) = bucket.sub_aggregation {
+                    sub_aggregation
+                        .add_intermediate_aggregation_result(sub_agg, &mut sub_aggregation_res)?;
+                }
+                Ok((
+                    geohash_from_cell_bits(cell, precision),
+                    IntermediateTermBucketEntry {
+                        doc_count: bucket.doc_count,
+                        sub_aggregation: sub_aggregation_res,
+                    },
+                ))
+            })
+            .collect::<crate::Result<_>>()?;
+
+        let bucket =
+            IntermediateBucketResult::GeohashGrid(IntermediateGeohashGridBucketResult { buckets });
+        results.push(name, IntermediateAggregationResult::Bucket(bucket))?;
+        Ok(())
+    }
+
+    #[inline]
+    fn collect(
+        &mut self,
+        doc: crate::DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    #[inline]
+    fn collect_block(
+        &mut self,
+        docs: &[crate::DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let bucket_agg_accessor = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+
+        bucket_agg_accessor
+            .column_block_accessor
+            .fetch_block(docs, &bucket_agg_accessor.accessor);
+
+        let mem_pre = self.buckets.capacity();
+        for (doc, val) in bucket_agg_accessor.column_block_accessor.iter_docid_vals() {
+            let cell = GeoPoint::from_u64(val).geohash_cell(self.precision);
+            let blueprint = &self.blueprint;
+            let bucket = self
+                .buckets
+                .entry(cell)
+                .or_insert_with(|| GeohashGridBucket {
+                    doc_count: 0,
+                    sub_aggregation: blueprint.clone(),
+                });
+            bucket.doc_count += 1;
+            if let Some(sub_aggregation) = &mut bucket.sub_aggregation {
+                sub_aggregation.collect(doc, &mut bucket_agg_accessor.sub_aggregation)?;
+            }
+        }
+        let mem_delta =
+            (self.buckets.capacity() - mem_pre) * std::mem::size_of::<(u64, GeohashGridBucket)>();
+        bucket_agg_accessor
+            .limits
+            .add_memory_consumed(mem_delta as u64)?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
+        let sub_aggregation_accessor =
+            &mut agg_with_accessor.aggs.values[self.accessor_idx].sub_aggregation;
+
+        for bucket in self.buckets.values_mut() {
+            if let Some(sub_agg) = bucket.sub_aggregation.as_mut() {
+                sub_agg.flush(sub_aggregation_accessor)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::exec_request_with_query;
+    use crate::aggregation::AggregationCollector;
+    use crate::query::AllQuery;
+    use crate::schema::{GeoPoint, Schema, FAST};
+    use crate::Index;
+
+    fn get_test_index_with_geo_points(merge_segments: bool) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let location = schema_builder.add_geo_point_field("location", FAST);
+        let price = schema_builder.add_u64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // Paris, geohash "u09tv"
+        index_writer
+            .add_document(doc!(location => GeoPoint::new(48.8566, 2.3522), price => 10u64))?;
+        index_writer
+            .add_document(doc!(location => GeoPoint::new(48.8600, 2.3500), price => 20u64))?;
+        index_writer.commit()?;
+        // London, geohash "gcpvj"
+        index_writer
+            .add_document(doc!(location => GeoPoint::new(51.5074, -0.1278), price => 5u64))?;
+        // Paris again
+        index_writer
+            .add_document(doc!(location => GeoPoint::new(48.8570, 2.3530), price => 30u64))?;
+        index_writer.add_document(doc!(price => 100u64))?;
+        index_writer.commit()?;
+        if merge_segments {
+            let segment_ids = index.searchable_segment_ids()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        Ok(index)
+    }
+
+    #[test]
+    fn test_geohash_grid_aggregation() -> crate::Result<()> {
+        for merge_segments in [false, true] {
+            let index = get_test_index_with_geo_points(merge_segments)?;
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "cells": {
+                    "geohash_grid": { "field": "location", "precision": 5 },
+                    "aggs": { "avg_price": { "avg": { "field": "price" } } }
+                }
+            }))
+            .unwrap();
+            let res = exec_request_with_query(agg_req, &index, None)?;
+            assert_eq!(
+                res["cells"]["buckets"],
+                json!([
+                    { "key": "u09tv", "doc_count": 3, "avg_price": { "value": 20.0 } },
+                    { "key": "gcpvj", "doc_count": 1, "avg_price": { "value": 5.0 } },
+                ])
+            );
+
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "cells": { "geohash_grid": { "field": "location", "precision": 1, "size": 1 } }
+            }))
+            .unwrap();
+            let res = exec_request_with_query(agg_req, &index, None)?;
+            assert_eq!(
+                res["cells"]["buckets"],
+                json!([{ "key": "u", "doc_count": 3 }])
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_geohash_grid_aggregation_invalid_precision() -> crate::Result<()> {
+        let index = get_test_index_with_geo_points(false)?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "cells": { "geohash_grid": { "field": "location", "precision": 13 } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let searcher = index.reader()?.searcher();
+        let err = searcher.search(&AllQuery, &collector).unwrap_err();
+        assert!(err.to_string().contains("precision"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_geohash_grid_aggregation_empty_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_geo_point_field("location", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "cells": { "geohash_grid": { "field": "location" } }
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req, &index, None)?;
+        assert_eq!(res["cells"]["buckets"], Value::Array(Vec::new()));
+        Ok(())
+    }
+}
@@ -21,7 +21,14 @@
 //! - [DateHistogram](DateHistogramAggregationReq)
 //! - [Range](RangeAggregation)
 //! - [Terms](TermsAggregation)
+//! - [GeohashGrid](GeohashGridAggregation)
+//! - [Filter](FilterAggregation)
+//! - [Filters](FiltersAggregation)
+//! - [Composite](CompositeAggregation)
 
+mod composite;
+mod filter;
+mod geohash_grid;
 mod histogram;
 mod range;
 mod term_agg;
@@ -29,6 +36,15 @@ mod term_missing_agg;
 
 use std::collections::HashMap;
 
+pub use composite::{
+    CompositeAggregation, CompositeKey, CompositeSource, CompositeSourceVariants,
+    DateHistogramCompositeSource, HistogramCompositeSource, TermsCompositeSource,
+};
+pub(crate) use composite::{CompositeSourceAccessor, SegmentCompositeCollector};
+pub use filter::{FilterAggregation, FilterQuery, FiltersAggregation, DEFAULT_OTHER_BUCKET_KEY};
+pub(crate) use filter::{FilterWeight, SegmentFiltersCollector};
+pub use geohash_grid::GeohashGridAggregation;
+pub(crate) use geohash_grid::SegmentGeohashGridCollector;
 pub use histogram::*;
 pub use range::*;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
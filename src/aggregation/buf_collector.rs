@@ -1,16 +1,20 @@
 use super::agg_req_with_accessor::AggregationsWithAccessor;
 use super::intermediate_agg_result::IntermediateAggregationResults;
 use super::segment_agg_result::SegmentAggregationCollector;
-use crate::DocId;
+use crate::{DocId, Score};
 
 pub(crate) const DOC_BLOCK_SIZE: usize = 64;
 pub(crate) type DocBlock = [DocId; DOC_BLOCK_SIZE];
 
 /// BufAggregationCollector buffers documents before calling collect_block().
+///
+/// The scores of the documents are buffered along with them, and set on the aggregations that
+/// need them before each block is collected.
 #[derive(Clone)]
 pub(crate) struct BufAggregationCollector {
     pub(crate) collector: Box<dyn SegmentAggregationCollector>,
     staged_docs: DocBlock,
+    staged_scores: [Score; DOC_BLOCK_SIZE],
     num_staged_docs: usize,
 }
 
@@ -29,8 +33,32 @@ impl BufAggregationCollector {
             collector,
             num_staged_docs: 0,
             staged_docs: [0; DOC_BLOCK_SIZE],
+            staged_scores: [0.0; DOC_BLOCK_SIZE],
         }
     }
+
+    /// Collects a document along with its score.
+    #[inline]
+    pub(crate) fn collect_with_score(
+        &mut self,
+        doc: DocId,
+        score: Score,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.staged_scores[self.num_staged_docs] = score;
+        self.collect(doc, agg_with_accessor)
+    }
+
+    fn collect_staged_docs(
+        &mut self,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let docs = &self.staged_docs[..self.num_staged_docs];
+        agg_with_accessor.set_block_scores(docs, &self.staged_scores[..self.num_staged_docs]);
+        self.collector.collect_block(docs, agg_with_accessor)?;
+        self.num_staged_docs = 0;
+        Ok(())
+    }
 }
 
 impl SegmentAggregationCollector for BufAggregationCollector {
@@ -52,9 +80,7 @@ impl SegmentAggregationCollector for BufAggregationCollector {
         self.staged_docs[self.num_staged_docs] = doc;
         self.num_staged_docs += 1;
         if self.num_staged_docs == self.staged_docs.len() {
-            self.collector
-                .collect_block(&self.staged_docs[..self.num_staged_docs], agg_with_accessor)?;
-            self.num_staged_docs = 0;
+            self.collect_staged_docs(agg_with_accessor)?;
         }
         Ok(())
     }
@@ -72,9 +98,7 @@ impl SegmentAggregationCollector for BufAggregationCollector {
 
     #[inline]
     fn flush(&mut self, agg_with_accessor: &mut AggregationsWithAccessor) -> crate::Result<()> {
-        self.collector
-            .collect_block(&self.staged_docs[..self.num_staged_docs], agg_with_accessor)?;
-        self.num_staged_docs = 0;
+        self.collect_staged_docs(agg_with_accessor)?;
 
         self.collector.flush(agg_with_accessor)?;
 
@@ -1,4 +1,4 @@
-use super::agg_req::Aggregations;
+use super::agg_req::{aggregations_require_scoring, Aggregations};
 use super::agg_req_with_accessor::AggregationsWithAccessor;
 use super::agg_result::AggregationResults;
 use super::buf_collector::BufAggregationCollector;
@@ -7,8 +7,9 @@ use super::segment_agg_result::{
     build_segment_agg_collector, AggregationLimits, SegmentAggregationCollector,
 };
 use crate::aggregation::agg_req_with_accessor::get_aggs_with_segment_accessor_and_validate;
+use crate::aggregation::pipeline::validate_pipeline_aggregations;
 use crate::collector::{Collector, SegmentCollector};
-use crate::{DocId, SegmentReader, TantivyError};
+use crate::{DocId, SegmentOrdinal, SegmentReader, TantivyError};
 
 /// The default max bucket count, before the aggregation fails.
 pub const DEFAULT_BUCKET_LIMIT: u32 = 65000;
@@ -64,14 +65,19 @@ impl Collector for DistributedAggregationCollector {
 
     fn for_segment(
         &self,
-        _segment_local_id: crate::SegmentOrdinal,
+        segment_local_id: crate::SegmentOrdinal,
         reader: &crate::SegmentReader,
     ) -> crate::Result<Self::Child> {
-        AggregationSegmentCollector::from_agg_req_and_reader(&self.agg, reader, &self.limits)
+        AggregationSegmentCollector::from_agg_req_and_reader(
+            &self.agg,
+            reader,
+            segment_local_id,
+            &self.limits,
+        )
     }
 
     fn requires_scoring(&self) -> bool {
-        false
+        aggregations_require_scoring(&self.agg)
     }
 
     fn merge_fruits(
@@ -89,14 +95,19 @@ impl Collector for AggregationCollector {
 
     fn for_segment(
         &self,
-        _segment_local_id: crate::SegmentOrdinal,
+        segment_local_id: crate::SegmentOrdinal,
         reader: &crate::SegmentReader,
     ) -> crate::Result<Self::Child> {
-        AggregationSegmentCollector::from_agg_req_and_reader(&self.agg, reader, &self.limits)
+        AggregationSegmentCollector::from_agg_req_and_reader(
+            &self.agg,
+            reader,
+            segment_local_id,
+            &self.limits,
+        )
     }
 
     fn requires_scoring(&self) -> bool {
-        false
+        aggregations_require_scoring(&self.agg)
     }
 
     fn merge_fruits(
@@ -126,6 +137,7 @@ fn merge_fruits(
 pub struct AggregationSegmentCollector {
     aggs_with_accessor: AggregationsWithAccessor,
     agg_collector: BufAggregationCollector,
+    requires_scoring: bool,
     error: Option<TantivyError>,
 }
 
@@ -135,15 +147,18 @@ impl AggregationSegmentCollector {
     pub fn from_agg_req_and_reader(
         agg: &Aggregations,
         reader: &SegmentReader,
+        segment_ordinal: SegmentOrdinal,
         limits: &AggregationLimits,
     ) -> crate::Result<Self> {
+        validate_pipeline_aggregations(agg)?;
         let mut aggs_with_accessor =
-            get_aggs_with_segment_accessor_and_validate(agg, reader, limits)?;
+            get_aggs_with_segment_accessor_and_validate(agg, reader, segment_ordinal, limits)?;
         let result =
             BufAggregationCollector::new(build_segment_agg_collector(&mut aggs_with_accessor)?);
         Ok(AggregationSegmentCollector {
             aggs_with_accessor,
             agg_collector: result,
+            requires_scoring: aggregations_require_scoring(agg),
             error: None,
         })
     }
@@ -153,14 +168,18 @@ impl SegmentCollector for AggregationSegmentCollector {
     type Fruit = crate::Result<IntermediateAggregationResults>;
 
     #[inline]
-    fn collect(&mut self, doc: DocId, _score: crate::Score) {
+    fn collect(&mut self, doc: DocId, score: crate::Score) {
         if self.error.is_some() {
             return;
         }
-        if let Err(err) = self
-            .agg_collector
-            .collect(doc, &mut self.aggs_with_accessor)
-        {
+        let res = if self.requires_scoring {
+            self.agg_collector
+                .collect_with_score(doc, score, &mut self.aggs_with_accessor)
+        } else {
+            self.agg_collector
+                .collect(doc, &mut self.aggs_with_accessor)
+        };
+        if let Err(err) = res {
             self.error = Some(err);
         }
     }
@@ -12,15 +12,20 @@ use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 
 use super::agg_req::{Aggregation, AggregationVariants, Aggregations};
-use super::agg_result::{AggregationResult, BucketResult, MetricResult, RangeBucketEntry};
+use super::agg_result::{
+    AggregationResult, BucketResult, CompositeBucketEntry, FilterBucketEntry, MetricResult,
+    RangeBucketEntry,
+};
 use super::bucket::{
     cut_off_buckets, get_agg_name_and_property, intermediate_histogram_buckets_to_final_buckets,
-    GetDocCount, Order, OrderTarget, RangeAggregation, TermsAggregation,
+    CompositeAggregation, FiltersAggregation, GeohashGridAggregation, GetDocCount, Order,
+    OrderTarget, RangeAggregation, TermsAggregation,
 };
 use super::metric::{
-    IntermediateAverage, IntermediateCount, IntermediateMax, IntermediateMin, IntermediateStats,
-    IntermediateSum, PercentilesCollector,
+    CardinalityCollector, IntermediateAverage, IntermediateCount, IntermediateMax, IntermediateMin,
+    IntermediateStats, IntermediateSum, PercentilesCollector, TopHitsCollector,
 };
+use super::pipeline::validate_pipeline_aggregations;
 use super::segment_agg_result::AggregationLimits;
 use super::{format_date, AggregationError, Key, SerializedKey};
 use crate::aggregation::agg_result::{AggregationResults, BucketEntries, BucketEntry};
@@ -97,6 +102,8 @@ impl IntermediateAggregationResults {
         req: Aggregations,
         limits: &AggregationLimits,
     ) -> crate::Result<AggregationResults> {
+        // The request is also validated when collecting a segment, but there may be none.
+        validate_pipeline_aggregations(&req)?;
         let res = self.into_final_result_internal(&req, limits)?;
         let bucket_count = res.get_bucket_count() as u32;
         if bucket_count > limits.get_bucket_limit() {
@@ -131,7 +138,8 @@ impl IntermediateAggregationResults {
         // Handle empty results
         if results.len() != req.len() {
             for (key, req) in req.iter() {
-                if !results.contains_key(key) {
+                // Pipeline aggregations are computed by their parent bucket aggregation.
+                if !results.contains_key(key) && !req.agg.is_pipeline() {
                     let empty_res = empty_from_req(req);
                     results.insert(key.to_string(), empty_res.into_final_result(req, limits)?);
                 }
@@ -143,7 +151,7 @@ impl IntermediateAggregationResults {
 
     pub(crate) fn empty_from_req(req: &Aggregations) -> Self {
         let mut aggs_res: FxHashMap<String, IntermediateAggregationResult> = FxHashMap::default();
-        for (key, req) in req.iter() {
+        for (key, req) in req.iter().filter(|(_, req)| !req.agg.is_pipeline()) {
             let empty_res = empty_from_req(req);
             aggs_res.insert(key.to_string(), empty_res);
         }
@@ -172,6 +180,18 @@ pub(crate) fn empty_from_req(req: &Aggregation) -> IntermediateAggregationResult
         Range(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Range(
             Default::default(),
         )),
+        GeohashGrid(_) => IntermediateAggregationResult::Bucket(
+            IntermediateBucketResult::GeohashGrid(Default::default()),
+        ),
+        Composite(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Composite(
+            Default::default(),
+        )),
+        Filter(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Filter(
+            Default::default(),
+        )),
+        Filters(_) => IntermediateAggregationResult::Bucket(IntermediateBucketResult::Filters(
+            Default::default(),
+        )),
         Histogram(_) | DateHistogram(_) => {
             IntermediateAggregationResult::Bucket(IntermediateBucketResult::Histogram {
                 buckets: Vec::new(),
@@ -199,6 +219,16 @@ pub(crate) fn empty_from_req(req: &Aggregation) -> IntermediateAggregationResult
         Percentiles(_) => IntermediateAggregationResult::Metric(
             IntermediateMetricResult::Percentiles(PercentilesCollector::default()),
         ),
+        Cardinality(ref cardinality_req) => IntermediateAggregationResult::Metric(
+            IntermediateMetricResult::Cardinality(CardinalityCollector::from_req(cardinality_req)),
+        ),
+        TopHits(ref top_hits_req) => IntermediateAggregationResult::Metric(
+            IntermediateMetricResult::TopHits(TopHitsCollector::from_req(top_hits_req)),
+        ),
+        Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketScript(_) | BucketSelector(_)
+        | BucketSort(_) => {
+            panic!("pipeline aggregations have no intermediate result")
+        }
     }
 }
 
@@ -219,7 +249,9 @@ impl IntermediateAggregationResult {
     ) -> crate::Result<AggregationResult> {
         let res = match self {
             IntermediateAggregationResult::Bucket(bucket) => {
-                AggregationResult::BucketResult(bucket.into_final_bucket_result(req, limits)?)
+                let mut bucket = bucket.into_final_bucket_result(req, limits)?;
+                bucket.apply_pipeline_aggregations(req.sub_aggregation())?;
+                AggregationResult::BucketResult(bucket)
             }
             IntermediateAggregationResult::Metric(metric) => {
                 AggregationResult::MetricResult(metric.into_final_metric_result(req))
@@ -259,6 +291,10 @@ pub enum IntermediateMetricResult {
     Stats(IntermediateStats),
     /// Intermediate sum result.
     Sum(IntermediateSum),
+    /// Intermediate cardinality result.
+    Cardinality(CardinalityCollector),
+    /// Intermediate top hits result.
+    TopHits(TopHitsCollector),
 }
 
 impl IntermediateMetricResult {
@@ -286,6 +322,12 @@ impl IntermediateMetricResult {
                 percentiles
                     .into_final_result(req.agg.as_percentile().expect("unexpected metric type")),
             ),
+            IntermediateMetricResult::Cardinality(cardinality) => {
+                MetricResult::Cardinality((cardinality.estimate() as f64).into())
+            }
+            IntermediateMetricResult::TopHits(top_hits) => MetricResult::TopHits(
+                top_hits.into_final_result(req.agg.as_top_hits().expect("unexpected metric type")),
+            ),
         }
     }
 
@@ -324,6 +366,15 @@ impl IntermediateMetricResult {
             ) => {
                 left.merge_fruits(right)?;
             }
+            (
+                IntermediateMetricResult::Cardinality(left),
+                IntermediateMetricResult::Cardinality(right),
+            ) => {
+                left.merge_fruits(right)?;
+            }
+            (IntermediateMetricResult::TopHits(left), IntermediateMetricResult::TopHits(right)) => {
+                left.merge_fruits(right)?;
+            }
             _ => {
                 panic!("incompatible fruit types in tree or missing merge_fruits handler");
             }
@@ -350,6 +401,14 @@ pub enum IntermediateBucketResult {
     },
     /// Term aggregation
     Terms(IntermediateTermBucketResult),
+    /// Geohash grid aggregation
+    GeohashGrid(IntermediateGeohashGridBucketResult),
+    /// Filter aggregation
+    Filter(IntermediateTermBucketEntry),
+    /// Filters aggregation
+    Filters(IntermediateFiltersBucketResult),
+    /// Composite aggregation
+    Composite(IntermediateCompositeBucketResult),
 }
 
 impl IntermediateBucketResult {
@@ -433,6 +492,30 @@ impl IntermediateBucketResult {
                 req.sub_aggregation(),
                 limits,
             ),
+            IntermediateBucketResult::GeohashGrid(geohash_grid) => geohash_grid.into_final_result(
+                req.agg
+                    .as_geohash_grid()
+                    .expect("unexpected aggregation, expected geohash_grid aggregation"),
+                req.sub_aggregation(),
+                limits,
+            ),
+            IntermediateBucketResult::Composite(composite) => composite.into_final_result(
+                req.agg
+                    .as_composite()
+                    .expect("unexpected aggregation, expected composite aggregation"),
+                req.sub_aggregation(),
+                limits,
+            ),
+            IntermediateBucketResult::Filter(entry) => Ok(BucketResult::Filter(
+                entry.into_final_filter_bucket_entry(req.sub_aggregation(), limits)?,
+            )),
+            IntermediateBucketResult::Filters(filters) => filters.into_final_result(
+                req.agg
+                    .as_filters()
+                    .expect("unexpected aggregation, expected filters aggregation"),
+                req.sub_aggregation(),
+                limits,
+            ),
         }
     }
 
@@ -454,6 +537,30 @@ impl IntermediateBucketResult {
             ) => {
                 merge_maps(&mut range_res_left.buckets, range_res_right.buckets)?;
             }
+            (
+                IntermediateBucketResult::GeohashGrid(geohash_grid_left),
+                IntermediateBucketResult::GeohashGrid(geohash_grid_right),
+            ) => {
+                merge_maps(&mut geohash_grid_left.buckets, geohash_grid_right.buckets)?;
+            }
+            (
+                IntermediateBucketResult::Composite(composite_left),
+                IntermediateBucketResult::Composite(composite_right),
+            ) => {
+                merge_maps(&mut composite_left.buckets, composite_right.buckets)?;
+            }
+            (
+                IntermediateBucketResult::Filter(filter_left),
+                IntermediateBucketResult::Filter(filter_right),
+            ) => {
+                filter_left.merge_fruits(filter_right)?;
+            }
+            (
+                IntermediateBucketResult::Filters(filters_left),
+                IntermediateBucketResult::Filters(filters_right),
+            ) => {
+                merge_maps(&mut filters_left.buckets, filters_right.buckets)?;
+            }
             (
                 IntermediateBucketResult::Histogram {
                     buckets: buckets_left,
@@ -491,6 +598,18 @@ impl IntermediateBucketResult {
             (IntermediateBucketResult::Terms { .. }, _) => {
                 panic!("try merge on different types")
             }
+            (IntermediateBucketResult::GeohashGrid(_), _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::Composite(_), _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::Filter(_), _) => {
+                panic!("try merge on different types")
+            }
+            (IntermediateBucketResult::Filters(_), _) => {
+                panic!("try merge on different types")
+            }
         }
         Ok(())
     }
@@ -503,6 +622,125 @@ pub struct IntermediateRangeBucketResult {
     pub(crate) column_type: Option<ColumnType>,
 }
 
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Geohash grid aggregation, with the buckets keyed by geohash
+pub struct IntermediateGeohashGridBucketResult {
+    pub(crate) buckets: FxHashMap<String, IntermediateTermBucketEntry>,
+}
+
+impl IntermediateGeohashGridBucketResult {
+    pub(crate) fn into_final_result(
+        self,
+        req: &GeohashGridAggregation,
+        sub_aggregation_req: &Aggregations,
+        limits: &AggregationLimits,
+    ) -> crate::Result<BucketResult> {
+        let mut buckets: Vec<(String, IntermediateTermBucketEntry)> =
+            self.buckets.into_iter().collect();
+        buckets.sort_unstable_by(|(left_key, left), (right_key, right)| {
+            right
+                .doc_count
+                .cmp(&left.doc_count)
+                .then_with(|| left_key.cmp(right_key))
+        });
+        buckets.truncate(req.size as usize);
+        let buckets = buckets
+            .into_iter()
+            .map(|(key, entry)| {
+                Ok(BucketEntry {
+                    key_as_string: None,
+                    key: Key::Str(key),
+                    doc_count: entry.doc_count as u64,
+                    sub_aggregation: entry
+                        .sub_aggregation
+                        .into_final_result_internal(sub_aggregation_req, limits)?,
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(BucketResult::GeohashGrid { buckets })
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Composite aggregation, with the buckets keyed by the values of the sources
+pub struct IntermediateCompositeBucketResult {
+    pub(crate) buckets: FxHashMap<Vec<Option<IntermediateKey>>, IntermediateTermBucketEntry>,
+}
+
+impl IntermediateCompositeBucketResult {
+    pub(crate) fn into_final_result(
+        self,
+        req: &CompositeAggregation,
+        sub_aggregation_req: &Aggregations,
+        limits: &AggregationLimits,
+    ) -> crate::Result<BucketResult> {
+        // Each segment returns its own page, the page of the index is the beginning of their
+        // union.
+        let mut buckets: Vec<(Vec<Option<IntermediateKey>>, IntermediateTermBucketEntry)> =
+            self.buckets.into_iter().collect();
+        buckets.sort_unstable_by(|(left_key, _), (right_key, _)| {
+            req.compare_keys(left_key, right_key)
+        });
+        buckets.truncate(req.size as usize);
+        let buckets = buckets
+            .into_iter()
+            .map(|(key, entry)| {
+                let key = req
+                    .sources
+                    .iter()
+                    .zip(key)
+                    .map(|(source, value)| (source.name.to_string(), value.map(Key::from)))
+                    .collect();
+                Ok(CompositeBucketEntry {
+                    key,
+                    doc_count: entry.doc_count as u64,
+                    sub_aggregation: entry
+                        .sub_aggregation
+                        .into_final_result_internal(sub_aggregation_req, limits)?,
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let after_key = buckets.last().map(|bucket| bucket.key.clone());
+        Ok(BucketResult::Composite { buckets, after_key })
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// Filters aggregation, with the buckets keyed by filter name
+pub struct IntermediateFiltersBucketResult {
+    pub(crate) buckets: FxHashMap<String, IntermediateTermBucketEntry>,
+}
+
+impl IntermediateFiltersBucketResult {
+    pub(crate) fn into_final_result(
+        mut self,
+        req: &FiltersAggregation,
+        sub_aggregation_req: &Aggregations,
+        limits: &AggregationLimits,
+    ) -> crate::Result<BucketResult> {
+        // Segments without any documents don't report their buckets.
+        let bucket_keys = req
+            .filters
+            .keys()
+            .map(String::as_str)
+            .chain(req.other_bucket_key());
+        for key in bucket_keys {
+            self.buckets.entry(key.to_string()).or_default();
+        }
+        let buckets = self
+            .buckets
+            .into_iter()
+            .map(|(key, entry)| {
+                Ok((
+                    key,
+                    entry.into_final_filter_bucket_entry(sub_aggregation_req, limits)?,
+                ))
+            })
+            .collect::<crate::Result<FxHashMap<_, _>>>()?;
+        Ok(BucketResult::Filters { buckets })
+    }
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// Term aggregation including error counts
 pub struct IntermediateTermBucketResult {
@@ -712,6 +950,21 @@ pub struct IntermediateTermBucketEntry {
     pub sub_aggregation: IntermediateAggregationResults,
 }
 
+impl IntermediateTermBucketEntry {
+    pub(crate) fn into_final_filter_bucket_entry(
+        self,
+        sub_aggregation_req: &Aggregations,
+        limits: &AggregationLimits,
+    ) -> crate::Result<FilterBucketEntry> {
+        Ok(FilterBucketEntry {
+            doc_count: self.doc_count as u64,
+            sub_aggregation: self
+                .sub_aggregation
+                .into_final_result_internal(sub_aggregation_req, limits)?,
+        })
+    }
+}
+
 impl MergeFruits for IntermediateTermBucketEntry {
     fn merge_fruits(&mut self, other: IntermediateTermBucketEntry) -> crate::Result<()> {
         self.doc_count += other.doc_count;
@@ -0,0 +1,585 @@
+use std::net::Ipv6Addr;
+
+use columnar::{ColumnType, MonotonicallyMappableToU64};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::agg_req_with_accessor::{
+    AggregationWithAccessor, AggregationsWithAccessor,
+};
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateMetricResult,
+};
+use crate::aggregation::segment_agg_result::SegmentAggregationCollector;
+use crate::aggregation::AggregationError;
+use crate::{DocId, TantivyError};
+
+/// # Cardinality
+///
+/// The cardinality aggregation approximates the number of distinct values of a field, e.g. the
+/// number of unique users that visited a website.
+///
+/// It works on numeric, date, bool, ip and text fast fields.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "cardinality": {
+///         "field": "user_id",
+///         "precision_threshold": 3000
+///     }
+/// }
+/// ```
+///
+/// # Precision
+///
+/// Values are hashed into a [HyperLogLog](https://en.wikipedia.org/wiki/HyperLogLog) sketch,
+/// using linear counting for small cardinalities. Unlike HyperLogLog++, the raw estimate is not
+/// bias corrected. Up to `precision_threshold` distinct values, the count is expected to be exact. Above it,
+/// the count is estimated, with a relative error decreasing as `precision_threshold` grows:
+/// about 2% for the default threshold of 3000.
+///
+/// The memory used by the sketch is about `8 * precision_threshold` bytes, and is accounted
+/// against the memory limit of the aggregation. `precision_threshold` is capped to 40000.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CardinalityAggregationReq {
+    /// The field name to compute the cardinality on.
+    pub field: String,
+    /// Number of distinct values below which the count is expected to be exact.
+    /// Defaults to 3000.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub precision_threshold: Option<u32>,
+}
+
+const DEFAULT_PRECISION_THRESHOLD: u32 = 3_000;
+const MAX_PRECISION_THRESHOLD: u32 = 40_000;
+
+impl CardinalityAggregationReq {
+    /// Creates a new [`CardinalityAggregationReq`] instance from a field name.
+    pub fn from_field_name(field_name: String) -> Self {
+        CardinalityAggregationReq {
+            field: field_name,
+            precision_threshold: None,
+        }
+    }
+    /// Returns the field name the aggregation is computed on.
+    pub fn field_name(&self) -> &str {
+        &self.field
+    }
+
+    fn precision_threshold(&self) -> u32 {
+        self.precision_threshold
+            .unwrap_or(DEFAULT_PRECISION_THRESHOLD)
+            .min(MAX_PRECISION_THRESHOLD)
+    }
+}
+
+/// MurmurHash64A, used to hash the values into the sketch.
+///
+/// The hash has to be stable, as sketches computed on different indexes may be merged.
+/// `seed` is used to give values of different types different hashes.
+fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut hash = seed ^ (key.len() as u64).wrapping_mul(M);
+    let mut chunks = key.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        hash ^= k;
+        hash = hash.wrapping_mul(M);
+    }
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        for (i, &byte) in remainder.iter().enumerate() {
+            hash ^= u64::from(byte) << (8 * i);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> R;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> R;
+    hash
+}
+
+const SEED_INTEGER: u64 = 0;
+const SEED_LARGE_U64: u64 = 1;
+const SEED_F64: u64 = 2;
+const SEED_DATE: u64 = 3;
+const SEED_BOOL: u64 = 4;
+const SEED_STR: u64 = 5;
+const SEED_IP_ADDR: u64 = 6;
+
+/// Hashes a numerical value, so that a number has the same hash whatever the type of its
+/// column. The numerical type of a JSON field may differ from one segment to another.
+fn hash_numerical_value(val: u64, column_type: ColumnType) -> u64 {
+    match column_type {
+        ColumnType::U64 if val > i64::MAX as u64 => {
+            murmurhash64a(&val.to_le_bytes(), SEED_LARGE_U64)
+        }
+        ColumnType::U64 => murmurhash64a(&val.to_le_bytes(), SEED_INTEGER),
+        ColumnType::I64 => murmurhash64a(&i64::from_u64(val).to_le_bytes(), SEED_INTEGER),
+        ColumnType::F64 => {
+            let f64_val = f64::from_u64(val);
+            if f64_val.fract() == 0.0 && f64_val >= i64::MIN as f64 && f64_val < i64::MAX as f64 {
+                murmurhash64a(&(f64_val as i64).to_le_bytes(), SEED_INTEGER)
+            } else {
+                murmurhash64a(&f64_val.to_bits().to_le_bytes(), SEED_F64)
+            }
+        }
+        ColumnType::DateTime => murmurhash64a(&val.to_le_bytes(), SEED_DATE),
+        _ => murmurhash64a(&val.to_le_bytes(), SEED_BOOL),
+    }
+}
+
+/// Linear counting is used below these estimates, as recommended by the HyperLogLog++ paper,
+/// for precisions 4 to 18.
+const LINEAR_COUNTING_THRESHOLDS: [f64; 15] = [
+    10.0, 20.0, 40.0, 80.0, 220.0, 400.0, 900.0, 1800.0, 3100.0, 6500.0, 11500.0, 20000.0, 50000.0,
+    120000.0, 350000.0,
+];
+
+/// Returns the precision, i.e. the log2 of the number of registers, used above the given
+/// precision threshold. The registers take about as much memory as the hash set of the exact
+/// phase.
+fn precision_from_threshold(precision_threshold: u32) -> u8 {
+    let hash_table_entries = (u64::from(precision_threshold.max(1)) * 4 + 2) / 3;
+    let precision = 64 - (hash_table_entries * 4 - 1).leading_zeros();
+    precision.clamp(4, 18) as u8
+}
+
+/// The cardinality collector, a HyperLogLog sketch with linear counting used during segment collection and for
+/// merging results.
+///
+/// The hashes of the values are kept as is until there are more than `precision_threshold`
+/// of them. They are then folded into `2^precision` registers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CardinalityCollector {
+    precision_threshold: u32,
+    precision: u8,
+    hashes: FxHashSet<u64>,
+    registers: Vec<u8>,
+}
+
+impl CardinalityCollector {
+    pub(crate) fn from_req(req: &CardinalityAggregationReq) -> Self {
+        let precision_threshold = req.precision_threshold();
+        CardinalityCollector {
+            precision_threshold,
+            precision: precision_from_threshold(precision_threshold),
+            hashes: FxHashSet::default(),
+            registers: Vec::new(),
+        }
+    }
+
+    /// Returns the memory used by the hash set or the registers.
+    fn memory_consumption(&self) -> usize {
+        self.hashes.capacity() * std::mem::size_of::<u64>() + self.registers.capacity()
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        if self.registers.is_empty() {
+            self.hashes.insert(hash);
+            if self.hashes.len() > self.precision_threshold as usize {
+                self.fold_into_registers();
+            }
+        } else {
+            self.insert_into_registers(hash);
+        }
+    }
+
+    fn insert_into_registers(&mut self, hash: u64) {
+        let register = (hash >> (64 - self.precision)) as usize;
+        // The sentinel bit bounds the number of leading zeros.
+        let remaining_bits = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = remaining_bits.leading_zeros() as u8 + 1;
+        if self.registers[register] < rank {
+            self.registers[register] = rank;
+        }
+    }
+
+    fn fold_into_registers(&mut self) {
+        if !self.registers.is_empty() {
+            return;
+        }
+        self.registers = vec![0; 1 << self.precision];
+        for hash in std::mem::take(&mut self.hashes) {
+            self.insert_into_registers(hash);
+        }
+    }
+
+    /// Returns the estimated number of distinct values.
+    pub fn estimate(&self) -> u64 {
+        if self.registers.is_empty() {
+            return self.hashes.len() as u64;
+        }
+        let num_registers = self.registers.len() as f64;
+        let mut sum = 0.0;
+        let mut num_zero_registers = 0;
+        for &rank in &self.registers {
+            sum += 1.0 / (1u64 << rank) as f64;
+            if rank == 0 {
+                num_zero_registers += 1;
+            }
+        }
+        if num_zero_registers > 0 {
+            let linear_counting_estimate =
+                num_registers * (num_registers / num_zero_registers as f64).ln();
+            if linear_counting_estimate <= LINEAR_COUNTING_THRESHOLDS[self.precision as usize - 4] {
+                return linear_counting_estimate.round() as u64;
+            }
+        }
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / num_registers),
+        };
+        (alpha * num_registers * num_registers / sum).round() as u64
+    }
+
+    pub(crate) fn merge_fruits(&mut self, right: CardinalityCollector) -> crate::Result<()> {
+        if self.precision != right.precision {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InternalError(format!(
+                    "Error while merging cardinalities of different precisions {} and {}",
+                    self.precision, right.precision
+                )),
+            ));
+        }
+        if right.registers.is_empty() {
+            for hash in right.hashes {
+                self.insert_hash(hash);
+            }
+        } else {
+            self.fold_into_registers();
+            for (left_rank, right_rank) in self.registers.iter_mut().zip(right.registers) {
+                *left_rank = (*left_rank).max(right_rank);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SegmentCardinalityCollector {
+    cardinality: CardinalityCollector,
+    column_type: ColumnType,
+    /// Term ordinals of a text column. They are only resolved and hashed once the segment is
+    /// collected, so that the same term has the same hash in every segment.
+    term_ords: FxHashSet<u64>,
+    accessor_idx: usize,
+}
+
+impl SegmentCardinalityCollector {
+    pub fn from_req(
+        req: &CardinalityAggregationReq,
+        column_type: ColumnType,
+        accessor_idx: usize,
+    ) -> Self {
+        SegmentCardinalityCollector {
+            cardinality: CardinalityCollector::from_req(req),
+            column_type,
+            term_ords: FxHashSet::default(),
+            accessor_idx,
+        }
+    }
+
+    fn memory_consumption(&self) -> usize {
+        self.cardinality.memory_consumption()
+            + self.term_ords.capacity() * std::mem::size_of::<u64>()
+    }
+
+    fn collect_ip_addr(&mut self, ip_addr: Ipv6Addr) {
+        self.cardinality
+            .insert_hash(murmurhash64a(&ip_addr.octets(), SEED_IP_ADDR));
+    }
+
+    fn collect_val(&mut self, val: u64) {
+        if self.column_type == ColumnType::Str {
+            self.term_ords.insert(val);
+        } else {
+            self.cardinality
+                .insert_hash(hash_numerical_value(val, self.column_type));
+        }
+    }
+
+    #[inline]
+    fn collect_block_with_field(
+        &mut self,
+        docs: &[DocId],
+        agg_accessor: &mut AggregationWithAccessor,
+    ) {
+        if let Some(ip_addr_column) = agg_accessor.ip_addr_column.as_ref() {
+            for &doc in docs {
+                for ip_addr in ip_addr_column.values_for_doc(doc) {
+                    self.collect_ip_addr(ip_addr);
+                }
+            }
+            return;
+        }
+        agg_accessor
+            .column_block_accessor
+            .fetch_block(docs, &agg_accessor.accessor);
+        for val in agg_accessor.column_block_accessor.iter_vals() {
+            self.collect_val(val);
+        }
+    }
+}
+
+impl SegmentAggregationCollector for SegmentCardinalityCollector {
+    fn add_intermediate_aggregation_result(
+        mut self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        if !self.term_ords.is_empty() {
+            let str_dict_column = agg_with_accessor.aggs.values[self.accessor_idx]
+                .str_dict_column
+                .as_ref()
+                .ok_or_else(|| {
+                    TantivyError::InternalError(format!(
+                        "Missing dictionary for text column of cardinality aggregation {name:?}"
+                    ))
+                })?;
+            let mem_pre = self.cardinality.memory_consumption();
+            let mut term = Vec::new();
+            for &term_ord in &self.term_ords {
+                if !str_dict_column
+                    .dictionary()
+                    .ord_to_term(term_ord, &mut term)?
+                {
+                    return Err(TantivyError::InternalError(format!(
+                        "Couldn't find term_id {term_ord} in dict"
+                    )));
+                }
+                self.cardinality.insert_hash(murmurhash64a(&term, SEED_STR));
+            }
+            let mem_delta = self
+                .cardinality
+                .memory_consumption()
+                .saturating_sub(mem_pre);
+            agg_with_accessor.aggs.values[self.accessor_idx]
+                .limits
+                .add_memory_consumed(mem_delta as u64)?;
+        }
+        results.push(
+            name,
+            IntermediateAggregationResult::Metric(IntermediateMetricResult::Cardinality(
+                self.cardinality,
+            )),
+        )?;
+        Ok(())
+    }
+
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let field = &mut agg_with_accessor.aggs.values[self.accessor_idx];
+        let mem_pre = self.memory_consumption();
+        self.collect_block_with_field(docs, field);
+        // Folding the hashes into the registers releases the hash set, the released memory is
+        // not given back to the limits.
+        let mem_delta = self.memory_consumption().saturating_sub(mem_pre);
+        field.limits.add_memory_consumed(mem_delta as u64)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use serde_json::Value;
+
+    use super::{murmurhash64a, precision_from_threshold, CardinalityCollector};
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+    use crate::aggregation::tests::{
+        exec_request_with_query, exec_request_with_query_and_memory_limit,
+    };
+    use crate::aggregation::{
+        AggregationCollector, AggregationLimits, DistributedAggregationCollector,
+    };
+    use crate::indexer::NoMergePolicy;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, FAST, STRING};
+    use crate::Index;
+
+    #[test]
+    fn test_precision_from_threshold() {
+        assert_eq!(precision_from_threshold(0), 4);
+        assert_eq!(precision_from_threshold(100), 10);
+        assert_eq!(precision_from_threshold(3_000), 14);
+        assert_eq!(precision_from_threshold(40_000), 18);
+    }
+
+    #[test]
+    fn test_murmurhash64a() {
+        assert_eq!(murmurhash64a(b"", 0), 0);
+        assert_ne!(murmurhash64a(b"abc", 0), murmurhash64a(b"abd", 0));
+        assert_ne!(murmurhash64a(b"abc", 0), murmurhash64a(b"abc", 1));
+        assert_ne!(
+            murmurhash64a(b"abcdefghi", 0),
+            murmurhash64a(b"abcdefgh", 0)
+        );
+    }
+
+    #[test]
+    fn test_cardinality_collector_estimate() {
+        let req = super::CardinalityAggregationReq {
+            field: "field".to_string(),
+            precision_threshold: Some(100),
+        };
+        let mut left = CardinalityCollector::from_req(&req);
+        let mut right = CardinalityCollector::from_req(&req);
+        for val in 0u64..50 {
+            left.insert_hash(murmurhash64a(&val.to_le_bytes(), 0));
+            right.insert_hash(murmurhash64a(&(val + 25).to_le_bytes(), 0));
+        }
+        assert!(left.registers.is_empty());
+        let mut exact = left.clone();
+        exact.merge_fruits(right).unwrap();
+        assert_eq!(exact.estimate(), 75);
+
+        for val in 0u64..100_000 {
+            left.insert_hash(murmurhash64a(&val.to_le_bytes(), 0));
+        }
+        assert!(!left.registers.is_empty());
+        let estimate = left.estimate() as f64;
+        assert!((estimate - 100_000.0).abs() / 100_000.0 < 0.1, "{estimate}");
+
+        // Merging a sketch with itself does not change its estimate.
+        let mut merged = left.clone();
+        merged.merge_fruits(left.clone()).unwrap();
+        assert_eq!(merged.estimate(), left.estimate());
+        merged.merge_fruits(exact).unwrap();
+        assert_eq!(merged.estimate(), left.estimate());
+    }
+
+    #[test]
+    fn test_aggregation_cardinality() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING | FAST);
+        let score = schema_builder.add_u64_field("score", FAST);
+        let score_f64 = schema_builder.add_f64_field("score_f64", FAST);
+        let ip = schema_builder.add_ip_addr_field("ip", FAST);
+        let json = schema_builder.add_json_field("json", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0u64..300 {
+            let json_value: Value = if i % 2 == 0 {
+                serde_json::json!({ "mixed": i % 10 })
+            } else {
+                serde_json::json!({ "mixed": (i % 10) as f64 + 0.5 })
+            };
+            index_writer.add_document(doc!(
+                text => format!("term{}", i % 7),
+                score => i % 20,
+                score_f64 => (i % 20) as f64,
+                ip => Ipv6Addr::from((i % 5) as u128),
+                json => json_value.as_object().unwrap().clone(),
+            ))?;
+            // The terms of the segments have different ordinals.
+            if i % 100 == 99 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "text": { "cardinality": { "field": "text" } },
+            "score": { "cardinality": { "field": "score" } },
+            "score_f64": { "cardinality": { "field": "score_f64", "precision_threshold": 10 } },
+            "ip": { "cardinality": { "field": "ip" } },
+            "mixed": { "cardinality": { "field": "json.mixed" } },
+            "missing_field": { "cardinality": { "field": "not_a_field" } },
+        }))
+        .unwrap();
+        let res = exec_request_with_query(agg_req.clone(), &index, None)?;
+        assert_eq!(res["text"]["value"], 7.0);
+        assert_eq!(res["score"]["value"], 20.0);
+        // Above the precision threshold, the count is only estimated.
+        let score_f64 = res["score_f64"]["value"].as_f64().unwrap();
+        assert!((16.0..=24.0).contains(&score_f64), "{score_f64}");
+        assert_eq!(res["ip"]["value"], 5.0);
+        // The even values are integers, the odd values are not.
+        assert_eq!(res["mixed"]["value"], 10.0);
+        assert_eq!(res["missing_field"]["value"], 0.0);
+
+        // Intermediate results merge across indexes.
+        let collector =
+            DistributedAggregationCollector::from_aggs(agg_req.clone(), Default::default());
+        let searcher = index.reader()?.searcher();
+        let mut intermediate_res: IntermediateAggregationResults =
+            searcher.search(&AllQuery, &collector)?;
+        intermediate_res.merge_fruits(searcher.search(&AllQuery, &collector)?)?;
+        let res: Value = serde_json::to_value(
+            intermediate_res.into_final_result(agg_req.clone(), &Default::default())?,
+        )?;
+        assert_eq!(res["text"]["value"], 7.0);
+        assert_eq!(res["score"]["value"], 20.0);
+
+        // Cardinality as a sub aggregation
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "by_text": {
+                "terms": { "field": "text", "order": { "distinct_scores": "desc" } },
+                "aggs": { "distinct_scores": { "cardinality": { "field": "score" } } }
+            }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let res: Value = serde_json::to_value(searcher.search(&AllQuery, &collector)?)?;
+        assert_eq!(
+            res["by_text"]["buckets"][0]["distinct_scores"]["value"],
+            20.0
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregation_cardinality_memory_limit() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING | FAST);
+        let score = schema_builder.add_u64_field("score", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for i in 0u64..1_000 {
+            index_writer.add_document(doc!(text => format!("term{i}"), score => i))?;
+        }
+        index_writer.commit()?;
+
+        for field in ["text", "score"] {
+            let agg_req: Aggregations = serde_json::from_value(json!({
+                "distinct": { "cardinality": { "field": field } },
+            }))
+            .unwrap();
+            let err = exec_request_with_query_and_memory_limit(
+                agg_req.clone(),
+                &index,
+                None,
+                AggregationLimits::new(Some(1_000), None),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("memory limit"), "{err}");
+            let res = exec_request_with_query(agg_req, &index, None)?;
+            assert_eq!(res["distinct"]["value"], 1_000.0);
+        }
+        Ok(())
+    }
+}
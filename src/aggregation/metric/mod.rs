@@ -15,15 +15,20 @@
 //! - [Sum](SumAggregation)
 //! - [Count](CountAggregation)
 //! - [Percentiles](PercentilesAggregationReq)
+//! - [Cardinality](CardinalityAggregationReq)
+//! - [Top Hits](TopHitsAggregation)
 
 mod average;
+mod cardinality;
 mod count;
 mod max;
 mod min;
 mod percentiles;
 mod stats;
 mod sum;
+mod top_hits;
 pub use average::*;
+pub use cardinality::*;
 pub use count::*;
 pub use max::*;
 pub use min::*;
@@ -32,6 +37,7 @@ use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 pub use stats::*;
 pub use sum::*;
+pub use top_hits::*;
 
 /// Single-metric aggregations use this common result structure.
 ///
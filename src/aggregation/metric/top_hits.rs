@@ -0,0 +1,959 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use columnar::{Column, ColumnType, DynamicColumn, MonotonicallyMappableToU64};
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::aggregation::agg_req_with_accessor::{
+    AggregationWithAccessor, AggregationsWithAccessor,
+};
+use crate::aggregation::bucket::Order;
+use crate::aggregation::intermediate_agg_result::{
+    IntermediateAggregationResult, IntermediateAggregationResults, IntermediateMetricResult,
+};
+use crate::aggregation::segment_agg_result::SegmentAggregationCollector;
+use crate::aggregation::{f64_from_fastfield_u64, AggregationError};
+use crate::schema::{Field, Schema, Value};
+use crate::store::StoreReader;
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+/// Name of the pseudo field used to sort the hits by score.
+pub const SCORE_SORT_FIELD: &str = "_score";
+
+/// # Top Hits
+///
+/// The top hits aggregation keeps the best documents of each bucket. It is meant to be used as
+/// a sub-aggregation, e.g. to get the latest events of each host with a `terms` aggregation on
+/// the host.
+///
+/// Documents are sorted by the fast fields listed in `sort`, or by [`SCORE_SORT_FIELD`]. Without
+/// `sort`, they are sorted by decreasing score. Sort fields need to be numerical or date fast
+/// fields. Documents without a value for a sort field come last. Ties are broken by increasing
+/// [`DocAddress`].
+///
+/// For each hit, the aggregation returns its `DocAddress`, its sort values, and the values of
+/// the fast fields listed in `docvalue_fields` and of the stored fields listed in
+/// `stored_fields`.
+///
+/// As each bucket keeps its own hits, `from + size` can't exceed 100.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "top_hits": {
+///         "size": 3,
+///         "sort": [{ "timestamp": "desc" }],
+///         "docvalue_fields": ["host", "timestamp"],
+///         "stored_fields": ["message"]
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct TopHitsAggregation {
+    /// The number of hits to return. Defaults to 3.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<usize>,
+    /// The number of hits to skip. Defaults to 0.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub from: Option<usize>,
+    /// The keys to sort the hits by, by order of priority.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub sort: Vec<KeyOrder>,
+    /// The fast fields to return the values of.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub docvalue_fields: Vec<String>,
+    /// The stored fields to return the values of.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub stored_fields: Vec<String>,
+}
+
+const DEFAULT_SIZE: usize = 3;
+
+/// The maximum number of hits a bucket keeps, i.e. the maximum `from + size`.
+const MAX_RESULT_WINDOW: usize = 100;
+
+impl TopHitsAggregation {
+    fn size(&self) -> usize {
+        self.size.unwrap_or(DEFAULT_SIZE)
+    }
+
+    fn from(&self) -> usize {
+        self.from.unwrap_or(0)
+    }
+
+    fn validate(&self) -> crate::Result<()> {
+        let result_window = self.from().checked_add(self.size());
+        if result_window.map_or(true, |result_window| result_window > MAX_RESULT_WINDOW) {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InvalidRequest(format!(
+                    "top_hits from + size must be at most {MAX_RESULT_WINDOW}"
+                )),
+            ));
+        }
+        Ok(())
+    }
+
+    fn sort_keys(&self) -> Vec<KeyOrder> {
+        if self.sort.is_empty() {
+            vec![KeyOrder {
+                field: SCORE_SORT_FIELD.to_string(),
+                order: Order::Desc,
+            }]
+        } else {
+            self.sort.clone()
+        }
+    }
+
+    /// Returns the fast fields used by the aggregation.
+    pub fn field_names(&self) -> Vec<&str> {
+        self.sort
+            .iter()
+            .map(|key_order| key_order.field.as_str())
+            .filter(|field| *field != SCORE_SORT_FIELD)
+            .chain(self.docvalue_fields.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Returns true if the hits are sorted by score, i.e. if `_score` is one of the sort keys,
+    /// or if there is no sort key.
+    pub fn requires_scoring(&self) -> bool {
+        self.sort.is_empty()
+            || self
+                .sort
+                .iter()
+                .any(|key_order| key_order.field == SCORE_SORT_FIELD)
+    }
+}
+
+/// A sort key of the [`TopHitsAggregation`]: a field and an order.
+///
+/// It is serialized as `{ "field": "asc" }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyOrder {
+    /// The field to sort by, or [`SCORE_SORT_FIELD`].
+    pub field: String,
+    /// The order of the sort.
+    pub order: Order,
+}
+
+impl Serialize for KeyOrder {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(&self.field, &self.order)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyOrder {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        struct KeyOrderVisitor;
+
+        impl<'de> Visitor<'de> for KeyOrderVisitor {
+            type Value = KeyOrder;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object with a single field and its order")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<KeyOrder, A::Error>
+            where A: MapAccess<'de> {
+                let (field, order) = map
+                    .next_entry::<String, Order>()?
+                    .ok_or_else(|| de::Error::custom("expected a field and its order"))?;
+                if map.next_key::<String>()?.is_some() {
+                    return Err(de::Error::custom(
+                        "expected a single field per sort key, use several sort keys to sort by \
+                         several fields",
+                    ));
+                }
+                Ok(KeyOrder { field, order })
+            }
+        }
+
+        deserializer.deserialize_map(KeyOrderVisitor)
+    }
+}
+
+/// A hit of the [`TopHitsAggregation`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TopHitsEntry {
+    /// The address of the document.
+    pub doc_address: DocAddress,
+    /// The values of the sort keys, `None` if the document has no value for the key.
+    pub sort: Vec<Option<f64>>,
+    /// The values of the requested fast fields.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub docvalue_fields: BTreeMap<String, Vec<Value>>,
+    /// The values of the requested stored fields.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub stored_fields: BTreeMap<String, Vec<Value>>,
+}
+
+fn value_memory_consumption(value: &Value) -> usize {
+    std::mem::size_of::<Value>()
+        + match value {
+            Value::Str(text) => text.len(),
+            Value::Bytes(bytes) => bytes.len(),
+            _ => 0,
+        }
+}
+
+/// The value of a sort key, in the monotonic `u64` representation of its column.
+///
+/// Hits are compared without the precision lost by a conversion to `f64`, e.g. for dates in
+/// nanoseconds or integers above 2^53. Values are only converted to `f64` in the final result.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct SortValue {
+    val: u64,
+    column_type: ColumnType,
+}
+
+impl SortValue {
+    fn from_score(score: Score) -> Self {
+        SortValue {
+            val: f64::from(score).to_u64(),
+            column_type: ColumnType::F64,
+        }
+    }
+
+    fn to_f64(self) -> f64 {
+        f64_from_fastfield_u64(self.val, &self.column_type)
+    }
+
+    fn to_i128(self) -> Option<i128> {
+        match self.column_type {
+            ColumnType::U64 => Some(i128::from(self.val)),
+            ColumnType::I64 | ColumnType::DateTime => Some(i128::from(i64::from_u64(self.val))),
+            _ => None,
+        }
+    }
+
+    fn compare(&self, other: &SortValue) -> Ordering {
+        if self.column_type == other.column_type {
+            return self.val.cmp(&other.val);
+        }
+        // The numerical values of a JSON field may have a different type in each segment.
+        match (self.to_i128(), other.to_i128()) {
+            (Some(left), Some(right)) => left.cmp(&right),
+            _ => self
+                .to_f64()
+                .partial_cmp(&other.to_f64())
+                .unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+/// A hit of the [`TopHitsCollector`], with exact sort values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct IntermediateTopHitsEntry {
+    doc_address: DocAddress,
+    sort: Vec<Option<SortValue>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    docvalue_fields: BTreeMap<String, Vec<Value>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    stored_fields: BTreeMap<String, Vec<Value>>,
+}
+
+impl IntermediateTopHitsEntry {
+    /// Returns the memory used by the values of the requested fields.
+    fn fields_memory_consumption(&self) -> usize {
+        self.docvalue_fields
+            .iter()
+            .chain(&self.stored_fields)
+            .map(|(field_name, values)| {
+                field_name.len() + values.iter().map(value_memory_consumption).sum::<usize>()
+            })
+            .sum()
+    }
+
+    fn into_final_result(self) -> TopHitsEntry {
+        TopHitsEntry {
+            doc_address: self.doc_address,
+            sort: self
+                .sort
+                .into_iter()
+                .map(|sort_value| sort_value.map(SortValue::to_f64))
+                .collect(),
+            docvalue_fields: self.docvalue_fields,
+            stored_fields: self.stored_fields,
+        }
+    }
+}
+
+/// The top hits aggregation result.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TopHitsMetricResult {
+    /// The hits, best first.
+    pub hits: Vec<TopHitsEntry>,
+}
+
+/// Compares the sort values, the best first. Missing values come last, whatever the order.
+fn compare_sort_values(
+    orders: &[Order],
+    left: &[Option<SortValue>],
+    right: &[Option<SortValue>],
+) -> Ordering {
+    for ((order, left_val), right_val) in orders.iter().zip(left).zip(right) {
+        let ordering = match (left_val, right_val) {
+            (Some(left_val), Some(right_val)) => {
+                let ordering = left_val.compare(right_val);
+                match order {
+                    Order::Asc => ordering,
+                    Order::Desc => ordering.reverse(),
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// The top hits collector, used during segment collection and for merging results.
+///
+/// It keeps the `from + size` best hits.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TopHitsCollector {
+    orders: Vec<Order>,
+    limit: usize,
+    hits: Vec<IntermediateTopHitsEntry>,
+}
+
+impl TopHitsCollector {
+    pub(crate) fn from_req(req: &TopHitsAggregation) -> Self {
+        TopHitsCollector {
+            orders: req
+                .sort_keys()
+                .iter()
+                .map(|key_order| key_order.order)
+                .collect(),
+            limit: req.from() + req.size(),
+            hits: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, hit: IntermediateTopHitsEntry) {
+        self.hits.push(hit);
+        // Pruning only once twice the limit is reached keeps the cost of sorting low.
+        if self.hits.len() >= self.limit.max(1) * 2 {
+            self.sort_and_truncate();
+        }
+    }
+
+    /// Returns the memory used by the hits, without the values of their fields.
+    fn memory_consumption(&self) -> usize {
+        let entry_size = std::mem::size_of::<IntermediateTopHitsEntry>()
+            + self.orders.len() * std::mem::size_of::<Option<SortValue>>();
+        self.hits.capacity() * entry_size
+    }
+
+    fn sort_and_truncate(&mut self) {
+        let orders = &self.orders;
+        self.hits.sort_unstable_by(|left, right| {
+            compare_sort_values(orders, &left.sort, &right.sort)
+                .then_with(|| left.doc_address.cmp(&right.doc_address))
+        });
+        self.hits.truncate(self.limit);
+    }
+
+    pub(crate) fn merge_fruits(&mut self, right: TopHitsCollector) -> crate::Result<()> {
+        if self.orders != right.orders || self.limit != right.limit {
+            return Err(TantivyError::AggregationError(
+                AggregationError::InternalError(
+                    "Error while merging top hits with different sort keys or sizes".to_string(),
+                ),
+            ));
+        }
+        self.hits.extend(right.hits);
+        self.sort_and_truncate();
+        Ok(())
+    }
+
+    pub(crate) fn into_final_result(mut self, req: &TopHitsAggregation) -> TopHitsMetricResult {
+        self.sort_and_truncate();
+        TopHitsMetricResult {
+            hits: self
+                .hits
+                .into_iter()
+                .skip(req.from())
+                .map(IntermediateTopHitsEntry::into_final_result)
+                .collect(),
+        }
+    }
+}
+
+/// Where the value of a sort key is read from.
+enum SortValueSource {
+    Score,
+    Column(Option<(Column<u64>, ColumnType)>),
+}
+
+/// The columns and the store of a segment, used to fetch the sort values and the requested
+/// fields of the hits.
+pub(crate) struct TopHitsAccessor {
+    segment_ordinal: SegmentOrdinal,
+    sort_value_sources: Vec<(SortValueSource, Order)>,
+    docvalue_columns: Vec<(String, Vec<DynamicColumn>)>,
+    stored_fields: Vec<Field>,
+    store_reader: Option<StoreReader>,
+    schema: Schema,
+    /// The scores of the block of documents being collected, by increasing doc id. Only set if
+    /// the hits are sorted by score.
+    block_scores: Vec<(DocId, Score)>,
+}
+
+impl TopHitsAccessor {
+    pub(crate) fn open(
+        req: &TopHitsAggregation,
+        reader: &SegmentReader,
+        segment_ordinal: SegmentOrdinal,
+    ) -> crate::Result<Self> {
+        req.validate()?;
+        let fast_fields = reader.fast_fields();
+        let mut sort_value_sources = Vec::new();
+        for key_order in req.sort_keys() {
+            let source = if key_order.field == SCORE_SORT_FIELD {
+                SortValueSource::Score
+            } else {
+                let column_and_type = fast_fields.u64_lenient_for_type(
+                    Some(&[
+                        ColumnType::F64,
+                        ColumnType::U64,
+                        ColumnType::I64,
+                        ColumnType::DateTime,
+                    ]),
+                    &key_order.field,
+                )?;
+                SortValueSource::Column(column_and_type)
+            };
+            sort_value_sources.push((source, key_order.order));
+        }
+        let mut docvalue_columns = Vec::new();
+        for field_name in &req.docvalue_fields {
+            let columns = fast_fields
+                .dynamic_column_handles(field_name)?
+                .iter()
+                .map(|handle| handle.open())
+                .collect::<std::io::Result<Vec<_>>>()?;
+            docvalue_columns.push((field_name.clone(), columns));
+        }
+        let schema = reader.schema().clone();
+        let mut stored_fields = Vec::new();
+        for field_name in &req.stored_fields {
+            let field = schema.get_field(field_name)?;
+            if !schema.get_field_entry(field).is_stored() {
+                return Err(TantivyError::AggregationError(
+                    AggregationError::InvalidRequest(format!(
+                        "top_hits requires stored fields, {field_name:?} is not stored"
+                    )),
+                ));
+            }
+            stored_fields.push(field);
+        }
+        let store_reader = if stored_fields.is_empty() {
+            None
+        } else {
+            Some(reader.get_store_reader(1)?)
+        };
+        Ok(TopHitsAccessor {
+            segment_ordinal,
+            sort_value_sources,
+            docvalue_columns,
+            stored_fields,
+            store_reader,
+            schema,
+            block_scores: Vec::new(),
+        })
+    }
+
+    pub(crate) fn requires_scoring(&self) -> bool {
+        self.sort_value_sources
+            .iter()
+            .any(|(source, _)| matches!(source, SortValueSource::Score))
+    }
+
+    pub(crate) fn set_block_scores(&mut self, docs: &[DocId], scores: &[Score]) {
+        self.block_scores.clear();
+        self.block_scores
+            .extend(docs.iter().copied().zip(scores.iter().copied()));
+    }
+
+    fn score(&self, doc: DocId) -> Option<Score> {
+        self.block_scores
+            .binary_search_by_key(&doc, |(block_doc, _)| *block_doc)
+            .ok()
+            .map(|idx| self.block_scores[idx].1)
+    }
+
+    fn sort_values(&self, doc: DocId) -> Vec<Option<SortValue>> {
+        self.sort_value_sources
+            .iter()
+            .map(|(source, order)| match source {
+                SortValueSource::Score => self.score(doc).map(SortValue::from_score),
+                SortValueSource::Column(None) => None,
+                // With several values, the best one is used.
+                SortValueSource::Column(Some((column, column_type))) => {
+                    let vals = column.values_for_doc(doc);
+                    let val = match order {
+                        Order::Asc => vals.min(),
+                        Order::Desc => vals.max(),
+                    };
+                    val.map(|val| SortValue {
+                        val,
+                        column_type: *column_type,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    fn fill_fields(&self, hit: &mut IntermediateTopHitsEntry) -> crate::Result<()> {
+        let doc = hit.doc_address.doc_id;
+        for (field_name, columns) in &self.docvalue_columns {
+            let mut values = Vec::new();
+            for column in columns {
+                push_column_values(column, doc, &mut values)?;
+            }
+            if !values.is_empty() {
+                hit.docvalue_fields.insert(field_name.clone(), values);
+            }
+        }
+        if let Some(store_reader) = self.store_reader.as_ref() {
+            let document = store_reader.get(doc)?;
+            for field_value in document.field_values() {
+                if self.stored_fields.contains(&field_value.field()) {
+                    let field_name = self.schema.get_field_name(field_value.field());
+                    hit.stored_fields
+                        .entry(field_name.to_string())
+                        .or_default()
+                        .push(field_value.value().clone());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn push_column_values(
+    column: &DynamicColumn,
+    doc: DocId,
+    values: &mut Vec<Value>,
+) -> crate::Result<()> {
+    match column {
+        DynamicColumn::Bool(column) => values.extend(column.values_for_doc(doc).map(Value::Bool)),
+        DynamicColumn::I64(column) => values.extend(column.values_for_doc(doc).map(Value::I64)),
+        DynamicColumn::U64(column) => values.extend(column.values_for_doc(doc).map(Value::U64)),
+        DynamicColumn::F64(column) => values.extend(column.values_for_doc(doc).map(Value::F64)),
+        DynamicColumn::IpAddr(column) => {
+            values.extend(column.values_for_doc(doc).map(Value::IpAddr))
+        }
+        DynamicColumn::DateTime(column) => {
+            values.extend(column.values_for_doc(doc).map(Value::Date))
+        }
+        DynamicColumn::Bytes(column) => {
+            for term_ord in column.term_ords(doc) {
+                let mut bytes = Vec::new();
+                column.ord_to_bytes(term_ord, &mut bytes)?;
+                values.push(Value::Bytes(bytes));
+            }
+        }
+        DynamicColumn::Str(column) => {
+            for term_ord in column.term_ords(doc) {
+                let mut text = String::new();
+                column.ord_to_str(term_ord, &mut text)?;
+                values.push(Value::Str(text));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SegmentTopHitsCollector {
+    top_hits: TopHitsCollector,
+    accessor_idx: usize,
+}
+
+impl SegmentTopHitsCollector {
+    pub fn from_req(req: &TopHitsAggregation, accessor_idx: usize) -> Self {
+        SegmentTopHitsCollector {
+            top_hits: TopHitsCollector::from_req(req),
+            accessor_idx,
+        }
+    }
+}
+
+impl SegmentAggregationCollector for SegmentTopHitsCollector {
+    fn add_intermediate_aggregation_result(
+        mut self: Box<Self>,
+        agg_with_accessor: &AggregationsWithAccessor,
+        results: &mut IntermediateAggregationResults,
+    ) -> crate::Result<()> {
+        let name = agg_with_accessor.aggs.keys[self.accessor_idx].to_string();
+        let top_hits_agg_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+        let top_hits_accessor = top_hits_accessor(top_hits_agg_accessor)?;
+        // The fields are only fetched for the hits that may be returned.
+        self.top_hits.sort_and_truncate();
+        for hit in &mut self.top_hits.hits {
+            top_hits_accessor.fill_fields(hit)?;
+            top_hits_agg_accessor
+                .limits
+                .add_memory_consumed(hit.fields_memory_consumption() as u64)?;
+        }
+        results.push(
+            name,
+            IntermediateAggregationResult::Metric(IntermediateMetricResult::TopHits(self.top_hits)),
+        )?;
+        Ok(())
+    }
+
+    fn collect(
+        &mut self,
+        doc: DocId,
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        self.collect_block(&[doc], agg_with_accessor)
+    }
+
+    fn collect_block(
+        &mut self,
+        docs: &[DocId],
+        agg_with_accessor: &mut AggregationsWithAccessor,
+    ) -> crate::Result<()> {
+        let top_hits_agg_accessor = &agg_with_accessor.aggs.values[self.accessor_idx];
+        let top_hits_accessor = top_hits_accessor(top_hits_agg_accessor)?;
+        let mem_pre = self.top_hits.memory_consumption();
+        for &doc in docs {
+            self.top_hits.push(IntermediateTopHitsEntry {
+                doc_address: DocAddress::new(top_hits_accessor.segment_ordinal, doc),
+                sort: top_hits_accessor.sort_values(doc),
+                docvalue_fields: BTreeMap::new(),
+                stored_fields: BTreeMap::new(),
+            });
+        }
+        let mem_delta = self.top_hits.memory_consumption() - mem_pre;
+        top_hits_agg_accessor
+            .limits
+            .add_memory_consumed(mem_delta as u64)?;
+        Ok(())
+    }
+}
+
+fn top_hits_accessor(
+    agg_with_accessor: &AggregationWithAccessor,
+) -> crate::Result<&TopHitsAccessor> {
+    agg_with_accessor.top_hits_accessor.as_ref().ok_or_else(|| {
+        TantivyError::InternalError("Missing accessor for top_hits aggregation".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::agg_result::AggregationResults;
+    use crate::aggregation::{AggregationCollector, AggregationLimits};
+    use crate::collector::TopDocs;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{AllQuery, QueryParser};
+    use crate::schema::{Field, Schema, FAST, STORED, STRING, TEXT};
+    use crate::{DocAddress, Index};
+
+    fn create_index() -> crate::Result<(Index, Field)> {
+        let mut schema_builder = Schema::builder();
+        let host = schema_builder.add_text_field("host", STRING | FAST);
+        let timestamp = schema_builder.add_i64_field("timestamp", FAST);
+        let message = schema_builder.add_text_field("message", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(host => "a", timestamp => 1i64, message => "error"))?;
+        index_writer.add_document(doc!(host => "b", timestamp => 2i64, message => "ok"))?;
+        index_writer
+            .add_document(doc!(host => "a", timestamp => 5i64, message => "error error"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(host => "a", timestamp => 3i64, message => "ok"))?;
+        index_writer.add_document(doc!(host => "b", message => "error"))?;
+        index_writer.add_document(doc!(host => "a", timestamp => 4i64, message => "warn"))?;
+        index_writer.commit()?;
+        Ok((index, message))
+    }
+
+    fn hit_doc_addresses(res: &Value) -> Vec<DocAddress> {
+        res["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| serde_json::from_value(hit["doc_address"].clone()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_top_hits_sort_by_field() -> crate::Result<()> {
+        let (index, message) = create_index()?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "hosts": {
+                "terms": { "field": "host" },
+                "aggs": {
+                    "latest": {
+                        "top_hits": {
+                            "size": 2,
+                            "sort": [{ "timestamp": "desc" }],
+                            "docvalue_fields": ["host", "timestamp"],
+                            "stored_fields": ["message"]
+                        }
+                    }
+                }
+            },
+            "oldest": {
+                "top_hits": {
+                    "size": 2,
+                    "from": 1,
+                    "sort": [{ "timestamp": "asc" }]
+                }
+            }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let searcher = index.reader()?.searcher();
+        let agg_res: AggregationResults = searcher.search(&AllQuery, &collector)?;
+        let res: Value = serde_json::to_value(agg_res)?;
+
+        let host_a = &res["hosts"]["buckets"][0];
+        assert_eq!(host_a["key"], "a");
+        let hits = host_a["latest"]["hits"].as_array().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0]["sort"], json!([5.0]));
+        assert_eq!(
+            hits[0]["docvalue_fields"],
+            json!({ "host": ["a"], "timestamp": [5] })
+        );
+        assert_eq!(
+            hits[0]["stored_fields"],
+            json!({ "message": ["error error"] })
+        );
+        assert_eq!(hits[1]["sort"], json!([4.0]));
+        assert_eq!(hits[1]["stored_fields"], json!({ "message": ["warn"] }));
+        // The hits point to the documents they were built from.
+        for (hit, doc_address) in hits.iter().zip(hit_doc_addresses(&host_a["latest"])) {
+            let doc = searcher.doc(doc_address)?;
+            assert_eq!(
+                doc.get_first(message).and_then(|value| value.as_text()),
+                hit["stored_fields"]["message"][0].as_str()
+            );
+        }
+
+        // Documents without a sort value come last.
+        let host_b = &res["hosts"]["buckets"][1];
+        assert_eq!(host_b["key"], "b");
+        assert_eq!(host_b["latest"]["hits"][0]["sort"], json!([2.0]));
+        assert_eq!(host_b["latest"]["hits"][1]["sort"], json!([null]));
+        assert_eq!(
+            host_b["latest"]["hits"][1]["docvalue_fields"],
+            json!({ "host": ["b"] })
+        );
+
+        let oldest_sort_values: Vec<&Value> = res["oldest"]["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| &hit["sort"])
+            .collect();
+        assert_eq!(oldest_sort_values, vec![&json!([2.0]), &json!([3.0])]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_sort_by_score() -> crate::Result<()> {
+        let (index, _) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![]).parse_query("message:error")?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "best": { "top_hits": { "size": 10 } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let (top_docs, agg_res) = searcher.search(&query, &(TopDocs::with_limit(10), collector))?;
+        let res: Value = serde_json::to_value(agg_res)?;
+
+        let doc_addresses: Vec<DocAddress> = top_docs
+            .iter()
+            .map(|(_, doc_address)| *doc_address)
+            .collect();
+        assert_eq!(doc_addresses.len(), 3);
+        assert_eq!(hit_doc_addresses(&res["best"]), doc_addresses);
+        let scores: Vec<Value> = top_docs
+            .iter()
+            .map(|(score, _)| json!([f64::from(*score)]))
+            .collect();
+        let sort_values: Vec<Value> = res["best"]["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["sort"].clone())
+            .collect();
+        assert_eq!(sort_values, scores);
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_sort_by_score_in_buckets() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let host = schema_builder.add_text_field("host", STRING | FAST);
+        let message = schema_builder.add_text_field("message", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // More documents than a block of the aggregation collector.
+        for i in 0..200 {
+            let host_name = if i % 2 == 0 { "a" } else { "b" };
+            let text = format!("{}{}", "error ".repeat(i % 7 + 1), "ok ".repeat(i % 5));
+            index_writer.add_document(doc!(host => host_name, message => text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![]).parse_query("message:error")?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "hosts": {
+                "terms": { "field": "host" },
+                "aggs": {
+                    "best": { "top_hits": { "size": 5, "sort": [{ "_score": "desc" }] } }
+                }
+            }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let (mut top_docs, agg_res) =
+            searcher.search(&query, &(TopDocs::with_limit(200), collector))?;
+        let res: Value = serde_json::to_value(agg_res)?;
+
+        top_docs.sort_by(|left, right| right.0.total_cmp(&left.0).then(left.1.cmp(&right.1)));
+        for bucket in res["hosts"]["buckets"].as_array().unwrap() {
+            let parity = if bucket["key"] == "a" { 0 } else { 1 };
+            let expected: Vec<(Value, DocAddress)> = top_docs
+                .iter()
+                .filter(|(_, doc_address)| doc_address.doc_id % 2 == parity)
+                .take(5)
+                .map(|(score, doc_address)| (json!([f64::from(*score)]), *doc_address))
+                .collect();
+            let hits = &bucket["best"];
+            let sort_values: Vec<Value> = hits["hits"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|hit| hit["sort"].clone())
+                .collect();
+            let actual: Vec<(Value, DocAddress)> = sort_values
+                .into_iter()
+                .zip(hit_doc_addresses(hits))
+                .collect();
+            assert_eq!(actual, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_invalid_request() -> crate::Result<()> {
+        let (index, _) = create_index()?;
+        let searcher = index.reader()?.searcher();
+
+        let res: Result<Aggregations, _> = serde_json::from_value(json!({
+            "latest": {
+                "top_hits": { "sort": [{ "timestamp": "desc", "host": "asc" }] }
+            }
+        }));
+        assert!(res.is_err());
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "latest": { "top_hits": { "stored_fields": ["host"] } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        assert!(searcher.search(&AllQuery, &collector).is_err());
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "latest": { "top_hits": { "from": 95, "size": 10 } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let err = searcher.search(&AllQuery, &collector).unwrap_err();
+        assert!(err.to_string().contains("from + size"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_memory_limit() -> crate::Result<()> {
+        let (index, _) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "latest": { "top_hits": { "stored_fields": ["message"] } }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(
+            agg_req.clone(),
+            AggregationLimits::new(Some(100), None),
+        );
+        let err = searcher.search(&AllQuery, &collector).unwrap_err();
+        assert!(err.to_string().contains("memory limit"), "{err}");
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        assert!(searcher.search(&AllQuery, &collector).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_hits_sort_values_keep_their_precision() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", FAST);
+        let json = schema_builder.add_json_field("json", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        // Both pairs of values are equal once converted to f64.
+        index_writer.add_document(doc!(id => 1u64 << 60))?;
+        index_writer.add_document(doc!(id => (1u64 << 60) + 1))?;
+        index_writer.add_document(doc!(
+            json => json!({ "val": i64::MAX }).as_object().unwrap().clone()
+        ))?;
+        index_writer.commit()?;
+        // The JSON values are a u64 column in this segment, an i64 column in the other one.
+        index_writer.add_document(doc!(
+            json => json!({ "val": (1u64 << 63) + 1 }).as_object().unwrap().clone()
+        ))?;
+        index_writer.commit()?;
+
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "by_id": {
+                "top_hits": { "size": 1, "sort": [{ "id": "desc" }], "docvalue_fields": ["id"] }
+            },
+            "by_json": {
+                "top_hits": {
+                    "size": 1,
+                    "sort": [{ "json.val": "desc" }],
+                    "docvalue_fields": ["json.val"]
+                }
+            }
+        }))
+        .unwrap();
+        let collector = AggregationCollector::from_aggs(agg_req, Default::default());
+        let searcher = index.reader()?.searcher();
+        let res: Value = serde_json::to_value(searcher.search(&AllQuery, &collector)?)?;
+        assert_eq!(
+            res["by_id"]["hits"][0]["docvalue_fields"],
+            json!({ "id": [(1u64 << 60) + 1] })
+        );
+        assert_eq!(
+            res["by_json"]["hits"][0]["docvalue_fields"],
+            json!({ "json.val": [(1u64 << 63) + 1] })
+        );
+        Ok(())
+    }
+}
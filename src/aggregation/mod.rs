@@ -41,6 +41,9 @@
 //!     - [DateHistogram](bucket::DateHistogramAggregationReq)
 //!     - [Range](bucket::RangeAggregation)
 //!     - [Terms](bucket::TermsAggregation)
+//!     - [Filter](bucket::FilterAggregation)
+//!     - [Filters](bucket::FiltersAggregation)
+//!     - [Composite](bucket::CompositeAggregation)
 //! - [Metric](metric)
 //!     - [Average](metric::AverageAggregation)
 //!     - [Stats](metric::StatsAggregation)
@@ -49,6 +52,15 @@
 //!     - [Sum](metric::SumAggregation)
 //!     - [Count](metric::CountAggregation)
 //!     - [Percentiles](metric::PercentilesAggregationReq)
+//!     - [Cardinality](metric::CardinalityAggregationReq)
+//!     - [Top Hits](metric::TopHitsAggregation)
+//! - [Pipeline](pipeline)
+//!     - [Derivative](pipeline::DerivativeAggregation)
+//!     - [Cumulative Sum](pipeline::CumulativeSumAggregation)
+//!     - [Moving Average](pipeline::MovingAvgAggregation)
+//!     - [Bucket Script](pipeline::BucketScriptAggregation)
+//!     - [Bucket Selector](pipeline::BucketSelectorAggregation)
+//!     - [Bucket Sort](pipeline::BucketSortAggregation)
 //!
 //! # Example
 //! Compute the average metric, by building [`agg_req::Aggregations`], which is built from an
@@ -135,6 +147,7 @@ mod date;
 mod error;
 pub mod intermediate_agg_result;
 pub mod metric;
+pub mod pipeline;
 
 mod segment_agg_result;
 use std::collections::HashMap;
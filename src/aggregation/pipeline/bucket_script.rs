@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::script::Script;
+use super::{invalid_request, GapPolicy, PipelineBucket};
+
+/// # Bucket Script
+///
+/// The bucket script aggregation computes a script over values of each bucket, e.g. the share
+/// of the sales of a category in the monthly sales.
+///
+/// `buckets_path` maps the variables of the script to the paths of their values. The script is
+/// an arithmetic expression over these variables, written `name` or `params.name`, supporting
+/// `+ - * / %`, comparisons, `&& || !` and parentheses.
+///
+/// With the default `skip` gap policy, buckets where a variable has no value get no result.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "bucket_script": {
+///         "buckets_path": {
+///             "book_sales": "books>sales",
+///             "total_sales": "sales"
+///         },
+///         "script": "params.book_sales / params.total_sales * 100"
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BucketScriptAggregation {
+    /// The paths of the values of the variables of the script, by variable name.
+    pub buckets_path: BTreeMap<String, String>,
+    /// The script to compute.
+    pub script: String,
+    /// How buckets without a value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl BucketScriptAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        parse_script(&self.script, &self.buckets_path).map(|_| ())
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let script = parse_script(&self.script, &self.buckets_path)?;
+        for bucket in buckets.iter_mut() {
+            if let Some(params) = resolve_params(bucket, &self.buckets_path, self.gap_policy)? {
+                let value = script.eval(&params)?;
+                bucket.set_pipeline_value(name, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a script and checks all its variables are defined in `buckets_path`.
+pub(super) fn parse_script(
+    source: &str,
+    buckets_path: &BTreeMap<String, String>,
+) -> crate::Result<Script> {
+    let script = Script::parse(source)?;
+    if let Some(variable) = script
+        .variables()
+        .into_iter()
+        .find(|variable| !buckets_path.contains_key(*variable))
+    {
+        return Err(invalid_request(format!(
+            "script variable {variable:?} is not defined in buckets_path"
+        )));
+    }
+    Ok(script)
+}
+
+/// Resolves the values of the variables of a script for a bucket, or `None` if one of them is a
+/// skipped gap.
+pub(super) fn resolve_params<'a, B: PipelineBucket>(
+    bucket: &B,
+    buckets_path: &'a BTreeMap<String, String>,
+    gap_policy: GapPolicy,
+) -> crate::Result<Option<HashMap<&'a str, f64>>> {
+    let mut params = HashMap::with_capacity(buckets_path.len());
+    for (variable, path) in buckets_path {
+        match gap_policy.resolve(bucket, path)? {
+            Some(value) => {
+                params.insert(variable.as_str(), value);
+            }
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(params))
+}
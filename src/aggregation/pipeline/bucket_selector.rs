@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::bucket_script::{parse_script, resolve_params};
+use super::script::is_true;
+use super::{GapPolicy, PipelineBucket};
+
+/// # Bucket Selector
+///
+/// The bucket selector aggregation keeps the buckets for which a script is true, e.g. the months
+/// with more than 1000 sales. It adds no value to the buckets.
+///
+/// The script is written like the one of a
+/// [`BucketScriptAggregation`](super::BucketScriptAggregation). Non-zero values are true.
+///
+/// With the default `skip` gap policy, buckets where a variable has no value are removed.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "bucket_selector": {
+///         "buckets_path": {
+///             "total_sales": "sales"
+///         },
+///         "script": "params.total_sales > 1000"
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BucketSelectorAggregation {
+    /// The paths of the values of the variables of the script, by variable name.
+    pub buckets_path: BTreeMap<String, String>,
+    /// The condition for a bucket to be kept.
+    pub script: String,
+    /// How buckets without a value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl BucketSelectorAggregation {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        parse_script(&self.script, &self.buckets_path).map(|_| ())
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(&self, buckets: &mut Vec<B>) -> crate::Result<()> {
+        let script = parse_script(&self.script, &self.buckets_path)?;
+        let mut keep = Vec::with_capacity(buckets.len());
+        for bucket in buckets.iter() {
+            let params = resolve_params(bucket, &self.buckets_path, self.gap_policy)?;
+            keep.push(match params {
+                Some(params) => is_true(script.eval(&params)?),
+                None => false,
+            });
+        }
+        let mut keep = keep.into_iter();
+        buckets.retain(|_| keep.next().unwrap_or(false));
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::ser::SerializeMap;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{invalid_request, GapPolicy, PipelineBucket, KEY_PATH};
+use crate::aggregation::bucket::Order;
+use crate::aggregation::Key;
+
+/// # Bucket Sort
+///
+/// The bucket sort aggregation sorts the buckets by values of the buckets, and keeps a page of
+/// them, e.g. the 3 months with the most sales. It adds no value to the buckets.
+///
+/// `sort` is a list of paths, each with an optional order, `asc` by default. `_key` sorts by the
+/// key of the buckets. Without `sort`, the buckets keep their order and are only truncated to
+/// `size` buckets, after skipping the first `from` ones. Keyed buckets, e.g. of a `filters`
+/// aggregation or a keyed `histogram`, have no order and can't be sorted.
+///
+/// With the default `skip` gap policy, buckets where a sort value is missing are removed.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "bucket_sort": {
+///         "sort": [
+///             { "sales": { "order": "desc" } },
+///             "_key"
+///         ],
+///         "size": 3
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BucketSortAggregation {
+    /// The values to sort the buckets by, in order of priority.
+    #[serde(default)]
+    pub sort: Vec<BucketSortField>,
+    /// The number of buckets to skip after sorting. Defaults to 0.
+    #[serde(default)]
+    pub from: usize,
+    /// The number of buckets to keep. Defaults to all.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<usize>,
+    /// How buckets without a value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+/// A value to sort the buckets by in a [`BucketSortAggregation`].
+///
+/// Serialized as `{"<path>": {"order": "desc"}}`. A path alone is also accepted, and sorts in
+/// ascending order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketSortField {
+    /// The path of the value to sort by.
+    pub path: String,
+    /// The sort order.
+    pub order: Order,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BucketSortFieldOrder {
+    #[serde(default = "default_order")]
+    order: Order,
+}
+
+fn default_order() -> Order {
+    Order::Asc
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BucketSortFieldForDeserialization {
+    Path(String),
+    PathWithOrder(BTreeMap<String, BucketSortFieldOrder>),
+}
+
+impl Serialize for BucketSortField {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(&self.path, &BucketSortFieldOrder { order: self.order })?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BucketSortField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        match BucketSortFieldForDeserialization::deserialize(deserializer)? {
+            BucketSortFieldForDeserialization::Path(path) => Ok(BucketSortField {
+                path,
+                order: default_order(),
+            }),
+            BucketSortFieldForDeserialization::PathWithOrder(fields) => {
+                if fields.len() != 1 {
+                    return Err(de::Error::custom(format!(
+                        "a bucket_sort field must have exactly one path, got {:?}",
+                        fields.keys().collect::<Vec<_>>()
+                    )));
+                }
+                let (path, order) = fields.into_iter().next().expect("one field");
+                Ok(BucketSortField {
+                    path,
+                    order: order.order,
+                })
+            }
+        }
+    }
+}
+
+impl BucketSortAggregation {
+    pub(crate) fn buckets_paths(&self) -> Vec<&str> {
+        self.sort.iter().map(|field| field.path.as_str()).collect()
+    }
+
+    /// Returns the sort values of a bucket, or `None` if one of them is a skipped gap. Metric
+    /// values are compared as `f64` keys.
+    fn sort_values<B: PipelineBucket>(&self, bucket: &B) -> crate::Result<Option<Vec<Key>>> {
+        let mut sort_values = Vec::with_capacity(self.sort.len());
+        for field in &self.sort {
+            let sort_value = if field.path == KEY_PATH {
+                bucket.key().ok_or_else(|| {
+                    invalid_request(format!("buckets can't be sorted by {KEY_PATH:?}"))
+                })?
+            } else {
+                match self.gap_policy.resolve(bucket, &field.path)? {
+                    Some(value) => Key::F64(value),
+                    None => return Ok(None),
+                }
+            };
+            sort_values.push(sort_value);
+        }
+        Ok(Some(sort_values))
+    }
+
+    fn compare(&self, left: &[Key], right: &[Key]) -> Ordering {
+        self.sort
+            .iter()
+            .zip(left.iter().zip(right))
+            .map(|(field, (left, right))| {
+                let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+                match field.order {
+                    Order::Asc => ordering,
+                    Order::Desc => ordering.reverse(),
+                }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(&self, buckets: &mut Vec<B>) -> crate::Result<()> {
+        let mut sorted = Vec::with_capacity(buckets.len());
+        for bucket in buckets.drain(..) {
+            if let Some(sort_values) = self.sort_values(&bucket)? {
+                sorted.push((sort_values, bucket));
+            }
+        }
+        sorted.sort_by(|(left, _), (right, _)| self.compare(left, right));
+        let size = self.size.unwrap_or(usize::MAX);
+        buckets.extend(
+            sorted
+                .into_iter()
+                .skip(self.from)
+                .take(size)
+                .map(|(_, bucket)| bucket),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use super::{GapPolicy, PipelineBucket};
+
+/// # Cumulative Sum
+///
+/// The cumulative sum aggregation computes the running sum of a value over the buckets, e.g.
+/// the total sales up to each month in a `date_histogram`.
+///
+/// Buckets without a value count as zero.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "cumulative_sum": {
+///         "buckets_path": "sales"
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CumulativeSumAggregation {
+    /// The path of the value to sum.
+    pub buckets_path: String,
+}
+
+impl CumulativeSumAggregation {
+    /// Creates a new [`CumulativeSumAggregation`] of the value at `buckets_path`.
+    pub fn from_buckets_path(buckets_path: String) -> Self {
+        CumulativeSumAggregation { buckets_path }
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut sum = 0.0;
+        for bucket in buckets.iter_mut() {
+            sum += GapPolicy::InsertZeros
+                .resolve(bucket, &self.buckets_path)?
+                .unwrap_or(0.0);
+            bucket.set_pipeline_value(name, sum);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use super::{GapPolicy, PipelineBucket};
+
+/// # Derivative
+///
+/// The derivative aggregation computes the difference of a value between each bucket and the
+/// previous one, e.g. the change of the monthly sales in a `date_histogram`.
+///
+/// The first bucket has no derivative. With the default `skip` gap policy, buckets without a
+/// value have no derivative either, and the next bucket is compared with the last bucket which
+/// has a value.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "derivative": {
+///         "buckets_path": "sales"
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DerivativeAggregation {
+    /// The path of the value to compute the derivative of.
+    pub buckets_path: String,
+    /// How buckets without a value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl DerivativeAggregation {
+    /// Creates a new [`DerivativeAggregation`] of the value at `buckets_path`.
+    pub fn from_buckets_path(buckets_path: String) -> Self {
+        DerivativeAggregation {
+            buckets_path,
+            gap_policy: GapPolicy::default(),
+        }
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut previous = None;
+        for bucket in buckets.iter_mut() {
+            if let Some(value) = self.gap_policy.resolve(bucket, &self.buckets_path)? {
+                if let Some(previous) = previous {
+                    bucket.set_pipeline_value(name, value - previous);
+                }
+                previous = Some(value);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,762 @@
+//! Module for all pipeline aggregations.
+//!
+//! Pipeline aggregations work on the results of other aggregations instead of documents. They
+//! are computed on the final result tree, once the intermediate results of all the segments have
+//! been merged.
+//!
+//! A pipeline aggregation is a sub-aggregation of a multi-bucket aggregation, its parent. It is
+//! computed from the other sub-aggregations of the buckets of the parent, referenced by a
+//! `buckets_path`:
+//! - `_count`: the doc count of the bucket.
+//! - `_key`: the key of the bucket, for numeric keys.
+//! - `my_avg`: the value of the single-value metric `my_avg`, or of another pipeline aggregation.
+//! - `my_stats.avg`: a value of the multi-value metric `my_stats`.
+//! - `my_filter>my_avg`: a metric in the single-bucket aggregation `my_filter`. Its doc count is
+//!   `my_filter>_count`.
+//!
+//! Depending on the type of the pipeline aggregation, its value is added to each bucket as a
+//! [`MetricResult::Pipeline`](super::agg_result::MetricResult), or the buckets are filtered and
+//! sorted.
+//!
+//! ```json
+//! {
+//!     "sales_per_month": {
+//!         "date_histogram": { "field": "date", "fixed_interval": "30d" },
+//!         "aggs": {
+//!             "sales": { "sum": { "field": "price" } },
+//!             "sales_derivative": { "derivative": { "buckets_path": "sales" } }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Supported Pipeline Aggregations
+//! - [Derivative](DerivativeAggregation)
+//! - [CumulativeSum](CumulativeSumAggregation)
+//! - [MovingAvg](MovingAvgAggregation)
+//! - [BucketScript](BucketScriptAggregation)
+//! - [BucketSelector](BucketSelectorAggregation)
+//! - [BucketSort](BucketSortAggregation)
+
+mod bucket_script;
+mod bucket_selector;
+mod bucket_sort;
+mod cumulative_sum;
+mod derivative;
+mod moving_avg;
+mod script;
+
+use std::cmp::Ordering;
+
+pub use bucket_script::*;
+pub use bucket_selector::*;
+pub use bucket_sort::*;
+pub use cumulative_sum::*;
+pub use derivative::*;
+pub use moving_avg::*;
+use serde::{Deserialize, Serialize};
+
+use super::agg_req::{AggregationVariants, Aggregations};
+use super::agg_result::{
+    AggregationResult, AggregationResults, BucketEntry, BucketResult, CompositeBucketEntry,
+    FilterBucketEntry, MetricResult, RangeBucketEntry,
+};
+use super::bucket::get_agg_name_and_property;
+use super::{AggregationError, Key};
+use crate::TantivyError;
+
+/// The `buckets_path` of the doc count of a bucket.
+pub const COUNT_PATH: &str = "_count";
+/// The `buckets_path` of the key of a bucket.
+pub const KEY_PATH: &str = "_key";
+
+/// How buckets without a value for a `buckets_path` are handled, e.g. when an average is
+/// computed on an empty bucket.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GapPolicy {
+    /// Buckets without a value are skipped.
+    #[serde(rename = "skip")]
+    #[default]
+    Skip,
+    /// Missing values are replaced by zero.
+    #[serde(rename = "insert_zeros")]
+    InsertZeros,
+}
+
+impl GapPolicy {
+    /// Resolves the value of a `buckets_path` for a bucket, or `None` if it is a gap that is
+    /// skipped.
+    pub(crate) fn resolve<B: PipelineBucket>(
+        self,
+        bucket: &B,
+        buckets_path: &str,
+    ) -> crate::Result<Option<f64>> {
+        let value = resolve_buckets_path(bucket, buckets_path)?.filter(|value| !value.is_nan());
+        Ok(match (value, self) {
+            (None, GapPolicy::InsertZeros) => Some(0.0),
+            (value, _) => value,
+        })
+    }
+}
+
+/// A bucket of a multi-bucket aggregation result, as seen by the pipeline aggregations.
+pub(crate) trait PipelineBucket {
+    fn doc_count(&self) -> u64;
+    fn key(&self) -> Option<Key>;
+    fn sub_aggregation(&self) -> &AggregationResults;
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults;
+    /// The order of the buckets in the aggregation result, for results stored in maps.
+    fn cmp_position(&self, other: &Self) -> Ordering;
+
+    /// Adds the value computed by a pipeline aggregation to the bucket.
+    fn set_pipeline_value(&mut self, name: &str, value: f64) {
+        self.sub_aggregation_mut().0.insert(
+            name.to_string(),
+            AggregationResult::MetricResult(MetricResult::Pipeline(value.into())),
+        );
+    }
+}
+
+impl PipelineBucket for BucketEntry {
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn key(&self) -> Option<Key> {
+        Some(self.key.clone())
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+    fn cmp_position(&self, other: &Self) -> Ordering {
+        self.key.partial_cmp(&other.key).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PipelineBucket for RangeBucketEntry {
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn key(&self) -> Option<Key> {
+        Some(self.key.clone())
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+    fn cmp_position(&self, other: &Self) -> Ordering {
+        self.from
+            .unwrap_or(f64::MIN)
+            .total_cmp(&other.from.unwrap_or(f64::MIN))
+    }
+}
+
+impl PipelineBucket for CompositeBucketEntry {
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn key(&self) -> Option<Key> {
+        None
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+    fn cmp_position(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl PipelineBucket for FilterBucketEntry {
+    fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+    fn key(&self) -> Option<Key> {
+        None
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        &self.sub_aggregation
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        &mut self.sub_aggregation
+    }
+    fn cmp_position(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// A bucket of a keyed result, with its name. Buckets without a key of their own, like the ones
+/// of the `filters` aggregation, use their name as key.
+impl<T: PipelineBucket> PipelineBucket for (String, T) {
+    fn doc_count(&self) -> u64 {
+        self.1.doc_count()
+    }
+    fn key(&self) -> Option<Key> {
+        self.1.key().or_else(|| Some(Key::Str(self.0.clone())))
+    }
+    fn sub_aggregation(&self) -> &AggregationResults {
+        self.1.sub_aggregation()
+    }
+    fn sub_aggregation_mut(&mut self) -> &mut AggregationResults {
+        self.1.sub_aggregation_mut()
+    }
+    fn cmp_position(&self, other: &Self) -> Ordering {
+        self.1
+            .cmp_position(&other.1)
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+fn invalid_request(msg: String) -> TantivyError {
+    TantivyError::AggregationError(AggregationError::InvalidRequest(msg))
+}
+
+/// Resolves the value of a `buckets_path` for a bucket.
+pub(crate) fn resolve_buckets_path<B: PipelineBucket>(
+    bucket: &B,
+    buckets_path: &str,
+) -> crate::Result<Option<f64>> {
+    if buckets_path == COUNT_PATH {
+        return Ok(Some(bucket.doc_count() as f64));
+    }
+    if buckets_path == KEY_PATH {
+        return match bucket.key() {
+            Some(Key::F64(key)) => Ok(Some(key)),
+            _ => Err(invalid_request(format!(
+                "buckets_path {KEY_PATH:?} requires numeric bucket keys"
+            ))),
+        };
+    }
+    let (single_bucket_path, metric_path) = match buckets_path.rsplit_once('>') {
+        Some((single_bucket_path, metric_path)) => (Some(single_bucket_path), metric_path),
+        None => (None, buckets_path),
+    };
+    let mut results = bucket.sub_aggregation();
+    let mut doc_count = bucket.doc_count();
+    for agg_name in single_bucket_path
+        .into_iter()
+        .flat_map(|path| path.split('>'))
+    {
+        match results.0.get(agg_name) {
+            Some(AggregationResult::BucketResult(BucketResult::Filter(entry))) => {
+                results = &entry.sub_aggregation;
+                doc_count = entry.doc_count;
+            }
+            _ => {
+                return Err(invalid_request(format!(
+                    "buckets_path {buckets_path:?}: {agg_name:?} is not a single-bucket \
+                     aggregation"
+                )))
+            }
+        }
+    }
+    if metric_path == COUNT_PATH {
+        return Ok(Some(doc_count as f64));
+    }
+    let (agg_name, agg_property) = get_agg_name_and_property(metric_path);
+    match results.0.get(agg_name) {
+        Some(AggregationResult::MetricResult(metric)) => metric.get_value(agg_property),
+        Some(AggregationResult::BucketResult(_)) => Err(invalid_request(format!(
+            "buckets_path {buckets_path:?} needs to end with a metric aggregation"
+        ))),
+        None => Err(invalid_request(format!(
+            "buckets_path {buckets_path:?}: aggregation {agg_name:?} not found"
+        ))),
+    }
+}
+
+/// Returns the name of the sibling aggregation a `buckets_path` starts with, if any.
+fn referenced_aggregation(buckets_path: &str) -> Option<&str> {
+    if buckets_path == COUNT_PATH || buckets_path == KEY_PATH {
+        return None;
+    }
+    buckets_path.split(['>', '.']).next()
+}
+
+impl AggregationVariants {
+    /// Returns true for pipeline aggregations, which are computed from the results of other
+    /// aggregations.
+    pub fn is_pipeline(&self) -> bool {
+        matches!(
+            self,
+            AggregationVariants::Derivative(_)
+                | AggregationVariants::CumulativeSum(_)
+                | AggregationVariants::MovingAvg(_)
+                | AggregationVariants::BucketScript(_)
+                | AggregationVariants::BucketSelector(_)
+                | AggregationVariants::BucketSort(_)
+        )
+    }
+
+    /// Returns the paths of the values used by a pipeline aggregation.
+    pub(crate) fn buckets_paths(&self) -> Vec<&str> {
+        match self {
+            AggregationVariants::Derivative(derivative) => vec![derivative.buckets_path.as_str()],
+            AggregationVariants::CumulativeSum(cumulative_sum) => {
+                vec![cumulative_sum.buckets_path.as_str()]
+            }
+            AggregationVariants::MovingAvg(moving_avg) => vec![moving_avg.buckets_path.as_str()],
+            AggregationVariants::BucketScript(bucket_script) => bucket_script
+                .buckets_path
+                .values()
+                .map(String::as_str)
+                .collect(),
+            AggregationVariants::BucketSelector(bucket_selector) => bucket_selector
+                .buckets_path
+                .values()
+                .map(String::as_str)
+                .collect(),
+            AggregationVariants::BucketSort(bucket_sort) => bucket_sort.buckets_paths(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn is_multi_bucket(&self) -> bool {
+        matches!(
+            self,
+            AggregationVariants::Range(_)
+                | AggregationVariants::Histogram(_)
+                | AggregationVariants::DateHistogram(_)
+                | AggregationVariants::Terms(_)
+                | AggregationVariants::GeohashGrid(_)
+                | AggregationVariants::Filters(_)
+                | AggregationVariants::Composite(_)
+        )
+    }
+
+    /// Returns true if the buckets of the aggregation are returned as a map, which has no order.
+    fn has_keyed_buckets(&self) -> bool {
+        match self {
+            AggregationVariants::Range(range) => range.keyed,
+            AggregationVariants::Histogram(histogram) => histogram.keyed,
+            AggregationVariants::DateHistogram(histogram) => histogram.keyed,
+            AggregationVariants::Filters(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the pipeline aggregations of a level of the request, in the order they need to be
+/// computed: a pipeline aggregation referencing another one is computed after it.
+fn pipeline_aggregations_in_order(
+    aggs: &Aggregations,
+) -> crate::Result<Vec<(&str, &AggregationVariants)>> {
+    let mut pending: Vec<(&str, &AggregationVariants)> = aggs
+        .iter()
+        .filter(|(_, agg)| agg.agg.is_pipeline())
+        .map(|(name, agg)| (name.as_str(), &agg.agg))
+        .collect();
+    pending.sort_by_key(|(name, _)| *name);
+    let mut ordered = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let next_idx = pending
+            .iter()
+            .position(|(_, agg)| {
+                agg.buckets_paths().into_iter().all(|buckets_path| {
+                    let referenced = referenced_aggregation(buckets_path);
+                    !pending.iter().any(|(name, _)| Some(*name) == referenced)
+                })
+            })
+            .ok_or_else(|| {
+                invalid_request(format!(
+                    "pipeline aggregations {:?} reference each other",
+                    pending.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+                ))
+            })?;
+        ordered.push(pending.remove(next_idx));
+    }
+    Ok(ordered)
+}
+
+/// Validates the pipeline aggregations of a request: they need to be sub-aggregations of
+/// multi-bucket aggregations, and to reference sibling aggregations.
+pub(crate) fn validate_pipeline_aggregations(aggs: &Aggregations) -> crate::Result<()> {
+    if let Some(name) = aggs
+        .iter()
+        .find(|(_, agg)| agg.agg.is_pipeline())
+        .map(|(name, _)| name)
+    {
+        return Err(invalid_request(format!(
+            "pipeline aggregation {name:?} needs to be a sub-aggregation of a multi-bucket \
+             aggregation"
+        )));
+    }
+    validate_sub_aggregations(aggs)
+}
+
+fn validate_sub_aggregations(aggs: &Aggregations) -> crate::Result<()> {
+    for (parent_name, parent) in aggs {
+        let sub_aggregations = parent.sub_aggregation();
+        for (name, agg) in pipeline_aggregations_in_order(sub_aggregations)? {
+            if !parent.agg.is_multi_bucket() {
+                return Err(invalid_request(format!(
+                    "pipeline aggregation {name:?} needs a multi-bucket parent aggregation, \
+                     {parent_name:?} is not one"
+                )));
+            }
+            if let AggregationVariants::BucketSort(bucket_sort) = agg {
+                if !bucket_sort.sort.is_empty() && parent.agg.has_keyed_buckets() {
+                    return Err(invalid_request(format!(
+                        "bucket_sort {name:?} can't sort the keyed buckets of {parent_name:?}"
+                    )));
+                }
+            }
+            for buckets_path in agg.buckets_paths() {
+                if let Some(referenced) = referenced_aggregation(buckets_path) {
+                    if !sub_aggregations.contains_key(referenced) {
+                        return Err(invalid_request(format!(
+                            "buckets_path {buckets_path:?} of pipeline aggregation {name:?} \
+                             references the unknown aggregation {referenced:?}"
+                        )));
+                    }
+                }
+            }
+            match agg {
+                AggregationVariants::MovingAvg(moving_avg) => moving_avg.validate()?,
+                AggregationVariants::BucketScript(bucket_script) => bucket_script.validate()?,
+                AggregationVariants::BucketSelector(bucket_selector) => {
+                    bucket_selector.validate()?
+                }
+                _ => {}
+            }
+        }
+        validate_sub_aggregations(sub_aggregations)?;
+    }
+    Ok(())
+}
+
+/// Computes the pipeline aggregations of the sub-aggregations of a multi-bucket aggregation.
+pub(crate) fn apply_pipeline_aggregations<B: PipelineBucket>(
+    buckets: &mut Vec<B>,
+    sub_aggregations: &Aggregations,
+) -> crate::Result<()> {
+    for (name, agg) in pipeline_aggregations_in_order(sub_aggregations)? {
+        match agg {
+            AggregationVariants::Derivative(derivative) => derivative.apply(name, buckets)?,
+            AggregationVariants::CumulativeSum(cumulative_sum) => {
+                cumulative_sum.apply(name, buckets)?
+            }
+            AggregationVariants::MovingAvg(moving_avg) => moving_avg.apply(name, buckets)?,
+            AggregationVariants::BucketScript(bucket_script) => {
+                bucket_script.apply(name, buckets)?
+            }
+            AggregationVariants::BucketSelector(bucket_selector) => {
+                bucket_selector.apply(buckets)?
+            }
+            AggregationVariants::BucketSort(bucket_sort) => bucket_sort.apply(buckets)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::aggregation::agg_req::Aggregations;
+    use crate::aggregation::tests::{exec_request, get_test_index_from_values};
+    use crate::Index;
+
+    // Histogram buckets 0, 10, 20 and 30 with doc counts 3, 2, 0, 1, sums 6, 21, 0, 35 and
+    // averages 2, 10.5, none, 35.
+    fn get_test_index() -> crate::Result<Index> {
+        get_test_index_from_values(false, &[1.0, 2.0, 3.0, 10.0, 11.0, 35.0])
+    }
+
+    fn histogram_req(pipelines: Value) -> Aggregations {
+        let mut aggs = json!({
+            "sum": { "sum": { "field": "score_f64" } },
+            "avg": { "avg": { "field": "score_f64" } }
+        });
+        aggs.as_object_mut()
+            .unwrap()
+            .extend(pipelines.as_object().unwrap().clone());
+        serde_json::from_value(json!({
+            "hist": {
+                "histogram": { "field": "score_f64", "interval": 10.0 },
+                "aggs": aggs
+            }
+        }))
+        .unwrap()
+    }
+
+    fn bucket_values(res: &Value, name: &str) -> Value {
+        res["hist"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket[name]["value"].clone())
+            .collect()
+    }
+
+    fn bucket_keys(res: &Value) -> Value {
+        res["hist"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["key"].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_derivative_and_cumulative_sum() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let agg_req = histogram_req(json!({
+            "d_sum": { "derivative": { "buckets_path": "sum" } },
+            "d_avg": { "derivative": { "buckets_path": "avg" } },
+            "d_avg_zeros": {
+                "derivative": { "buckets_path": "avg", "gap_policy": "insert_zeros" }
+            },
+            "cum": { "cumulative_sum": { "buckets_path": "sum" } },
+            "d_cum": { "derivative": { "buckets_path": "cum" } },
+            "cum_count": { "cumulative_sum": { "buckets_path": "_count" } }
+        }));
+        let res = exec_request(agg_req, &index)?;
+
+        assert_eq!(
+            bucket_values(&res, "d_sum"),
+            json!([null, 15.0, -21.0, 35.0])
+        );
+        assert_eq!(bucket_values(&res, "d_avg"), json!([null, 8.5, null, 24.5]));
+        assert_eq!(
+            bucket_values(&res, "d_avg_zeros"),
+            json!([null, 8.5, -10.5, 35.0])
+        );
+        assert_eq!(bucket_values(&res, "cum"), json!([6.0, 27.0, 27.0, 62.0]));
+        assert_eq!(bucket_values(&res, "d_cum"), json!([null, 21.0, 0.0, 35.0]));
+        assert_eq!(
+            bucket_values(&res, "cum_count"),
+            json!([3.0, 5.0, 5.0, 6.0])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipeline_on_keyed_buckets() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "hist": {
+                "histogram": { "field": "score_f64", "interval": 10.0, "keyed": true },
+                "aggs": {
+                    "stats": { "stats": { "field": "score_f64" } },
+                    "cum_max": { "cumulative_sum": { "buckets_path": "stats.max" } }
+                }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index)?;
+
+        let buckets = &res["hist"]["buckets"];
+        assert_eq!(buckets["0"]["cum_max"]["value"], 3.0);
+        assert_eq!(buckets["10"]["cum_max"]["value"], 14.0);
+        assert_eq!(buckets["20"]["cum_max"]["value"], 14.0);
+        assert_eq!(buckets["30"]["cum_max"]["value"], 49.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_moving_avg() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let agg_req = histogram_req(json!({
+            "simple": { "moving_avg": { "buckets_path": "sum", "window": 2 } },
+            "linear": { "moving_avg": { "buckets_path": "sum", "window": 3, "model": "linear" } },
+            "ewma": {
+                "moving_avg": {
+                    "buckets_path": "avg",
+                    "model": "ewma",
+                    "settings": { "alpha": 0.5 }
+                }
+            }
+        }));
+        let res = exec_request(agg_req, &index)?;
+
+        assert_eq!(
+            bucket_values(&res, "simple"),
+            json!([null, 6.0, 13.5, 10.5])
+        );
+        assert_eq!(bucket_values(&res, "linear"), json!([null, 6.0, 16.0, 8.0]));
+        assert_eq!(bucket_values(&res, "ewma"), json!([null, 2.0, null, 6.25]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_script_and_selector() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let agg_req = histogram_req(json!({
+            "script": {
+                "bucket_script": {
+                    "buckets_path": { "k": "_key", "s": "sum" },
+                    "script": "params.s + k * 2"
+                }
+            },
+            "avg_per_doc": {
+                "bucket_script": {
+                    "buckets_path": { "s": "sum", "c": "_count" },
+                    "script": "s / c"
+                }
+            }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            bucket_values(&res, "script"),
+            json!([6.0, 41.0, 40.0, 95.0])
+        );
+        assert_eq!(bucket_values(&res, "avg_per_doc")[1], 10.5);
+
+        let agg_req = histogram_req(json!({
+            "at_least_two": {
+                "bucket_selector": {
+                    "buckets_path": { "c": "_count" },
+                    "script": "params.c >= 2"
+                }
+            }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(bucket_keys(&res), json!([0.0, 10.0]));
+
+        // The bucket without an average is a gap, and is removed.
+        let agg_req = histogram_req(json!({
+            "positive": {
+                "bucket_selector": {
+                    "buckets_path": { "a": "avg" },
+                    "script": "a > 0"
+                }
+            },
+            "cum": { "cumulative_sum": { "buckets_path": "_count" } }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(bucket_keys(&res), json!([0.0, 10.0, 30.0]));
+        assert_eq!(bucket_values(&res, "cum"), json!([3.0, 5.0, 6.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_sort() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let agg_req = histogram_req(json!({
+            "top": {
+                "bucket_sort": { "sort": [{ "sum": { "order": "desc" } }], "size": 2 }
+            }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(bucket_keys(&res), json!([30.0, 10.0]));
+
+        let agg_req = histogram_req(json!({
+            "page": {
+                "bucket_sort": {
+                    "sort": ["_count", { "_key": { "order": "desc" } }],
+                    "from": 1,
+                    "size": 2
+                }
+            }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(bucket_keys(&res), json!([30.0, 10.0]));
+
+        // Without sort, the buckets are only truncated.
+        let agg_req = histogram_req(json!({
+            "page": { "bucket_sort": { "from": 3 } }
+        }));
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(bucket_keys(&res), json!([30.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipeline_validation() -> crate::Result<()> {
+        let index = get_test_index()?;
+        let exec_err = |agg_req: Value| {
+            let agg_req: Aggregations = serde_json::from_value(agg_req).unwrap();
+            exec_request(agg_req, &index).unwrap_err().to_string()
+        };
+
+        let err = exec_err(json!({
+            "d": { "derivative": { "buckets_path": "_count" } }
+        }));
+        assert!(err.contains("needs to be a sub-aggregation"), "{err}");
+
+        let err = exec_err(json!({
+            "avg": {
+                "avg": { "field": "score_f64" },
+                "aggs": { "d": { "derivative": { "buckets_path": "_count" } } }
+            }
+        }));
+        assert!(err.contains("needs a multi-bucket parent"), "{err}");
+
+        for (pipelines, expected) in [
+            (
+                json!({ "d": { "derivative": { "buckets_path": "unknown" } } }),
+                "unknown aggregation",
+            ),
+            (
+                json!({
+                    "a": { "derivative": { "buckets_path": "b" } },
+                    "b": { "derivative": { "buckets_path": "a" } }
+                }),
+                "reference each other",
+            ),
+            (
+                json!({
+                    "s": {
+                        "bucket_script": { "buckets_path": { "s": "sum" }, "script": "params.x" }
+                    }
+                }),
+                "not defined in buckets_path",
+            ),
+            (
+                json!({ "s": { "bucket_script": { "buckets_path": {}, "script": "1 +" } } }),
+                "invalid script",
+            ),
+            (
+                json!({ "m": { "moving_avg": { "buckets_path": "sum", "window": 0 } } }),
+                "window",
+            ),
+        ] {
+            let agg_req = histogram_req(pipelines);
+            let err = exec_request(agg_req, &index).unwrap_err().to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+
+        // The order of keyed buckets is lost, they can only be truncated.
+        let err = exec_err(json!({
+            "hist": {
+                "histogram": { "field": "score_f64", "interval": 10.0, "keyed": true },
+                "aggs": {
+                    "sum": { "sum": { "field": "score_f64" } },
+                    "top": { "bucket_sort": { "sort": [{ "sum": { "order": "desc" } }] } }
+                }
+            }
+        }));
+        assert!(err.contains("can't sort the keyed buckets"), "{err}");
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "hist": {
+                "histogram": { "field": "score_f64", "interval": 10.0, "keyed": true },
+                "aggs": { "first": { "bucket_sort": { "size": 2 } } }
+            }
+        }))
+        .unwrap();
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(
+            res["hist"]["buckets"].as_object().unwrap().len(),
+            2,
+            "{res}"
+        );
+
+        // Requests are validated even if there is no segment to collect.
+        let empty_index = Index::create_in_ram(index.schema());
+        let agg_req: Aggregations = serde_json::from_value(json!({
+            "d": { "derivative": { "buckets_path": "_count" } }
+        }))
+        .unwrap();
+        let err = exec_request(agg_req, &empty_index).unwrap_err().to_string();
+        assert!(err.contains("needs to be a sub-aggregation"), "{err}");
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::{invalid_request, GapPolicy, PipelineBucket};
+
+/// # Moving Average
+///
+/// The moving average aggregation computes the average of a value over a sliding window of
+/// buckets, e.g. to smooth the monthly sales of a `date_histogram`.
+///
+/// The value of a bucket is the average of the values of the `window` buckets before it, so the
+/// first bucket has no moving average. With the default `skip` gap policy, buckets without a
+/// value are left out of the window.
+///
+/// # JSON Format
+/// ```json
+/// {
+///     "moving_avg": {
+///         "buckets_path": "sales",
+///         "window": 3,
+///         "model": "ewma",
+///         "settings": { "alpha": 0.5 }
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovingAvgAggregation {
+    /// The path of the value to average.
+    pub buckets_path: String,
+    /// The number of buckets in the window. Defaults to 5.
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// How the values of the window are weighted.
+    #[serde(default)]
+    pub model: MovingAvgModel,
+    /// The settings of the model.
+    #[serde(default)]
+    pub settings: MovingAvgSettings,
+    /// How buckets without a value are handled.
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+fn default_window() -> usize {
+    5
+}
+
+/// The weighting of the values of the window of a [`MovingAvgAggregation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovingAvgModel {
+    /// All the values have the same weight.
+    #[serde(rename = "simple")]
+    #[default]
+    Simple,
+    /// The weights grow linearly, the most recent value has the highest weight.
+    #[serde(rename = "linear")]
+    Linear,
+    /// Exponentially weighted, the weight of the older values decreases by a factor of
+    /// `1 - alpha` per bucket.
+    #[serde(rename = "ewma")]
+    Ewma,
+}
+
+/// The settings of the model of a [`MovingAvgAggregation`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovingAvgSettings {
+    /// The smoothing factor of the `ewma` model, between 0 (exclusive) and 1. Defaults to 0.3.
+    #[serde(default = "default_alpha")]
+    pub alpha: f64,
+}
+
+fn default_alpha() -> f64 {
+    0.3
+}
+
+impl Default for MovingAvgSettings {
+    fn default() -> Self {
+        MovingAvgSettings {
+            alpha: default_alpha(),
+        }
+    }
+}
+
+impl MovingAvgAggregation {
+    /// Creates a new simple [`MovingAvgAggregation`] of the value at `buckets_path`.
+    pub fn from_buckets_path(buckets_path: String) -> Self {
+        MovingAvgAggregation {
+            buckets_path,
+            window: default_window(),
+            model: MovingAvgModel::default(),
+            settings: MovingAvgSettings::default(),
+            gap_policy: GapPolicy::default(),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if self.window == 0 {
+            return Err(invalid_request(
+                "moving_avg window needs to be at least 1".to_string(),
+            ));
+        }
+        let alpha = self.settings.alpha;
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(invalid_request(format!(
+                "moving_avg alpha needs to be in (0, 1], got {alpha}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn average(&self, window: &VecDeque<f64>) -> f64 {
+        match self.model {
+            MovingAvgModel::Simple => window.iter().sum::<f64>() / window.len() as f64,
+            MovingAvgModel::Linear => {
+                let (weighted_sum, weights) = window.iter().zip(1..).fold(
+                    (0.0, 0.0),
+                    |(weighted_sum, weights), (value, weight)| {
+                        let weight = weight as f64;
+                        (weighted_sum + value * weight, weights + weight)
+                    },
+                );
+                weighted_sum / weights
+            }
+            MovingAvgModel::Ewma => {
+                let alpha = self.settings.alpha;
+                let mut values = window.iter();
+                let first = values.next().copied().unwrap_or(0.0);
+                values.fold(first, |avg, value| alpha * value + (1.0 - alpha) * avg)
+            }
+        }
+    }
+
+    pub(crate) fn apply<B: PipelineBucket>(
+        &self,
+        name: &str,
+        buckets: &mut [B],
+    ) -> crate::Result<()> {
+        let mut window = VecDeque::with_capacity(self.window);
+        for bucket in buckets.iter_mut() {
+            if let Some(value) = self.gap_policy.resolve(bucket, &self.buckets_path)? {
+                if !window.is_empty() {
+                    bucket.set_pipeline_value(name, self.average(&window));
+                }
+                if window.len() == self.window {
+                    window.pop_front();
+                }
+                window.push_back(value);
+            }
+        }
+        Ok(())
+    }
+}
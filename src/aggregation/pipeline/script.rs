@@ -0,0 +1,403 @@
+//! A small expression language for the scripts of `bucket_script` and `bucket_selector`.
+//!
+//! Expressions are made of numbers, `true` and `false`, variables, parentheses and the operators
+//! `+ - * / %`, `== != < <= > >=`, `&& || !`, with the usual precedence. Variables are the names
+//! of the `buckets_path` entries, and can be written `params.name` or `name`. Booleans are
+//! represented as `1.0` and `0.0`.
+//!
+//! Scripts can't be nested more than [`MAX_DEPTH`] levels deep, counting parentheses, unary
+//! operators and chained binary operators, so that parsing and evaluating them can't overflow
+//! the stack.
+
+use std::collections::HashMap;
+
+use crate::aggregation::AggregationError;
+use crate::TantivyError;
+
+/// Maximum nesting depth of a script.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// Binding power of the operator, higher binds tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+
+    fn apply(self, left: f64, right: f64) -> f64 {
+        let from_bool = |val: bool| if val { 1.0 } else { 0.0 };
+        match self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Sub => left - right,
+            BinaryOp::Mul => left * right,
+            BinaryOp::Div => left / right,
+            BinaryOp::Rem => left % right,
+            BinaryOp::Eq => from_bool(left == right),
+            BinaryOp::Ne => from_bool(left != right),
+            BinaryOp::Lt => from_bool(left < right),
+            BinaryOp::Le => from_bool(left <= right),
+            BinaryOp::Gt => from_bool(left > right),
+            BinaryOp::Ge => from_bool(left >= right),
+            BinaryOp::And => from_bool(is_true(left) && is_true(right)),
+            BinaryOp::Or => from_bool(is_true(left) || is_true(right)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    OpenParen,
+    CloseParen,
+}
+
+/// Returns true if the value of an expression is considered true.
+pub(crate) fn is_true(val: f64) -> bool {
+    val != 0.0 && !val.is_nan()
+}
+
+/// A parsed script.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Script {
+    expr: Expr,
+}
+
+impl Script {
+    pub(crate) fn parse(source: &str) -> crate::Result<Script> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            source,
+            tokens: &tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.parse_expr(0)?;
+        if parser.pos != tokens.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(Script { expr })
+    }
+
+    /// Returns the names of the variables used by the script.
+    pub(crate) fn variables(&self) -> Vec<&str> {
+        fn collect<'a>(expr: &'a Expr, variables: &mut Vec<&'a str>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Variable(name) => variables.push(name),
+                Expr::Unary(_, expr) => collect(expr, variables),
+                Expr::Binary(_, left, right) => {
+                    collect(left, variables);
+                    collect(right, variables);
+                }
+            }
+        }
+        let mut variables = Vec::new();
+        collect(&self.expr, &mut variables);
+        variables
+    }
+
+    /// Evaluates the script. All the variables of the script need to be defined.
+    pub(crate) fn eval(&self, params: &HashMap<&str, f64>) -> crate::Result<f64> {
+        fn eval(expr: &Expr, params: &HashMap<&str, f64>) -> crate::Result<f64> {
+            match expr {
+                Expr::Number(val) => Ok(*val),
+                Expr::Variable(name) => params.get(name.as_str()).copied().ok_or_else(|| {
+                    TantivyError::AggregationError(AggregationError::InvalidRequest(format!(
+                        "script variable {name:?} is not defined in buckets_path"
+                    )))
+                }),
+                Expr::Unary(UnaryOp::Neg, expr) => Ok(-eval(expr, params)?),
+                Expr::Unary(UnaryOp::Not, expr) => Ok(if is_true(eval(expr, params)?) {
+                    0.0
+                } else {
+                    1.0
+                }),
+                Expr::Binary(op, left, right) => {
+                    Ok(op.apply(eval(left, params)?, eval(right, params)?))
+                }
+            }
+        }
+        eval(&self.expr, params)
+    }
+}
+
+fn invalid_script(source: &str, msg: &str) -> TantivyError {
+    TantivyError::AggregationError(AggregationError::InvalidRequest(format!(
+        "invalid script {source:?}: {msg}"
+    )))
+}
+
+fn tokenize(source: &str) -> crate::Result<Vec<Token>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let byte = bytes[pos];
+        if byte.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if byte.is_ascii_digit() || byte == b'.' {
+            let start = pos;
+            while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                pos += 1;
+            }
+            if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+                pos += 1;
+                if pos < bytes.len() && (bytes[pos] == b'+' || bytes[pos] == b'-') {
+                    pos += 1;
+                }
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+            }
+            let number = source[start..pos]
+                .parse()
+                .map_err(|_| invalid_script(source, &format!("invalid number at {start}")))?;
+            tokens.push(Token::Number(number));
+            continue;
+        }
+        if byte.is_ascii_alphabetic() || byte == b'_' {
+            let start = pos;
+            while pos < bytes.len()
+                && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_' || bytes[pos] == b'.')
+            {
+                pos += 1;
+            }
+            let token = match &source[start..pos] {
+                "true" => Token::Number(1.0),
+                "false" => Token::Number(0.0),
+                identifier => Token::Identifier(identifier.to_string()),
+            };
+            tokens.push(token);
+            continue;
+        }
+        let next = bytes.get(pos + 1).copied();
+        let (token, len) = match (byte, next) {
+            (b'=', Some(b'=')) => (Token::Binary(BinaryOp::Eq), 2),
+            (b'!', Some(b'=')) => (Token::Binary(BinaryOp::Ne), 2),
+            (b'<', Some(b'=')) => (Token::Binary(BinaryOp::Le), 2),
+            (b'>', Some(b'=')) => (Token::Binary(BinaryOp::Ge), 2),
+            (b'&', Some(b'&')) => (Token::Binary(BinaryOp::And), 2),
+            (b'|', Some(b'|')) => (Token::Binary(BinaryOp::Or), 2),
+            (b'<', _) => (Token::Binary(BinaryOp::Lt), 1),
+            (b'>', _) => (Token::Binary(BinaryOp::Gt), 1),
+            (b'+', _) => (Token::Binary(BinaryOp::Add), 1),
+            (b'-', _) => (Token::Binary(BinaryOp::Sub), 1),
+            (b'*', _) => (Token::Binary(BinaryOp::Mul), 1),
+            (b'/', _) => (Token::Binary(BinaryOp::Div), 1),
+            (b'%', _) => (Token::Binary(BinaryOp::Rem), 1),
+            (b'!', _) => (Token::Unary(UnaryOp::Not), 1),
+            (b'(', _) => (Token::OpenParen, 1),
+            (b')', _) => (Token::CloseParen, 1),
+            _ => {
+                return Err(invalid_script(
+                    source,
+                    &format!("unexpected character at {pos}"),
+                ))
+            }
+        };
+        tokens.push(token);
+        pos += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    /// Nesting depth of the expression being parsed, which bounds the depth of the parsed tree.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> TantivyError {
+        invalid_script(self.source, msg)
+    }
+
+    fn enter(&mut self) -> crate::Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(TantivyError::InvalidArgument(format!(
+                "the script is nested more than {MAX_DEPTH} levels deep"
+            )));
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    /// Parses an expression whose binary operators bind tighter than `min_precedence`.
+    fn parse_expr(&mut self, min_precedence: u8) -> crate::Result<Expr> {
+        let depth = self.depth;
+        self.enter()?;
+        let mut left = self.parse_operand()?;
+        while let Some(Token::Binary(op)) = self.tokens.get(self.pos) {
+            if op.precedence() <= min_precedence {
+                break;
+            }
+            self.pos += 1;
+            // Each chained operator nests the expression parsed so far one level deeper.
+            self.enter()?;
+            let right = self.parse_expr(op.precedence())?;
+            left = Expr::Binary(*op, Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self, op: UnaryOp) -> crate::Result<Expr> {
+        self.enter()?;
+        let operand = self.parse_operand()?;
+        self.depth -= 1;
+        Ok(Expr::Unary(op, Box::new(operand)))
+    }
+
+    fn parse_operand(&mut self) -> crate::Result<Expr> {
+        match self.next() {
+            Some(Token::Number(val)) => Ok(Expr::Number(*val)),
+            Some(Token::Identifier(identifier)) => {
+                let name = identifier.strip_prefix("params.").unwrap_or(identifier);
+                if name.is_empty() || name.contains('.') {
+                    return Err(self.error(&format!("invalid variable {identifier:?}")));
+                }
+                Ok(Expr::Variable(name.to_string()))
+            }
+            Some(Token::Binary(BinaryOp::Sub)) => self.parse_unary(UnaryOp::Neg),
+            Some(Token::Unary(op)) => self.parse_unary(*op),
+            Some(Token::OpenParen) => {
+                let expr = self.parse_expr(0)?;
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(expr),
+                    _ => Err(self.error("missing closing parenthesis")),
+                }
+            }
+            Some(_) => Err(self.error("unexpected operator")),
+            None => Err(self.error("unexpected end of script")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Script, MAX_DEPTH};
+    use crate::TantivyError;
+
+    fn eval(source: &str, params: &[(&str, f64)]) -> f64 {
+        let params: HashMap<&str, f64> = params.iter().copied().collect();
+        Script::parse(source).unwrap().eval(&params).unwrap()
+    }
+
+    #[test]
+    fn test_script_eval() {
+        assert_eq!(eval("1 + 2 * 3", &[]), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", &[]), 9.0);
+        assert_eq!(eval("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(eval("-2 * -3 % 4", &[]), 2.0);
+        assert_eq!(eval("1.5e1 / 3", &[]), 5.0);
+        assert_eq!(
+            eval(
+                "params.sales / params.count",
+                &[("sales", 10.0), ("count", 4.0)]
+            ),
+            2.5
+        );
+        assert_eq!(
+            eval(
+                "sales > 5 && count <= 4",
+                &[("sales", 10.0), ("count", 4.0)]
+            ),
+            1.0
+        );
+        assert_eq!(eval("!(1 == 1) || 2 != 2", &[]), 0.0);
+        assert_eq!(eval("true && !false", &[]), 1.0);
+    }
+
+    #[test]
+    fn test_script_variables_and_errors() {
+        let script = Script::parse("params.a * b + a").unwrap();
+        assert_eq!(script.variables(), vec!["a", "b", "a"]);
+
+        for invalid in ["1 +", "(1", "1 2", "a.b", "1 # 2", "* 2", ""] {
+            assert!(
+                Script::parse(invalid).is_err(),
+                "{invalid:?} should be invalid"
+            );
+        }
+        let err = Script::parse("missing * 2")
+            .unwrap()
+            .eval(&HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("not defined in buckets_path"));
+    }
+
+    #[test]
+    fn test_script_max_depth() {
+        let nested = |prefix: &str, suffix: &str, depth: usize| {
+            format!("{}1{}", prefix.repeat(depth), suffix.repeat(depth))
+        };
+        assert_eq!(eval(&nested("(", ")", MAX_DEPTH - 1), &[]), 1.0);
+        assert_eq!(eval(&nested("-", "", MAX_DEPTH - 1), &[]), -1.0);
+        assert_eq!(eval(&nested("1 + ", "", MAX_DEPTH / 2 - 1), &[]), 64.0);
+        for too_deep in [
+            nested("(", ")", 10_000),
+            nested("(", "", 10_000),
+            nested("-", "", 10_000),
+            nested("!", "", 10_000),
+            nested("1 + ", "", 10_000),
+            nested("1 * (", ")", 10_000),
+        ] {
+            assert!(matches!(
+                Script::parse(&too_deep),
+                Err(TantivyError::InvalidArgument(_))
+            ));
+        }
+    }
+}
@@ -8,12 +8,15 @@ use std::fmt::Debug;
 pub(crate) use super::agg_limits::AggregationLimits;
 use super::agg_req::AggregationVariants;
 use super::agg_req_with_accessor::{AggregationWithAccessor, AggregationsWithAccessor};
-use super::bucket::{SegmentHistogramCollector, SegmentRangeCollector, SegmentTermCollector};
+use super::bucket::{
+    SegmentCompositeCollector, SegmentFiltersCollector, SegmentGeohashGridCollector,
+    SegmentHistogramCollector, SegmentRangeCollector, SegmentTermCollector,
+};
 use super::intermediate_agg_result::IntermediateAggregationResults;
 use super::metric::{
     AverageAggregation, CountAggregation, MaxAggregation, MinAggregation,
-    SegmentPercentilesCollector, SegmentStatsCollector, SegmentStatsType, StatsAggregation,
-    SumAggregation,
+    SegmentCardinalityCollector, SegmentPercentilesCollector, SegmentStatsCollector,
+    SegmentStatsType, SegmentTopHitsCollector, StatsAggregation, SumAggregation,
 };
 use crate::aggregation::bucket::TermMissingAgg;
 
@@ -103,6 +106,29 @@ pub(crate) fn build_single_agg_segment_collector(
             req.field_type,
             accessor_idx,
         )?)),
+        GeohashGrid(geohash_grid_req) => Ok(Box::new(
+            SegmentGeohashGridCollector::from_req_and_validate(
+                geohash_grid_req,
+                &mut req.sub_aggregation,
+                accessor_idx,
+            )?,
+        )),
+        Composite(composite_req) => Ok(Box::new(SegmentCompositeCollector::from_req_and_validate(
+            composite_req,
+            &mut req.sub_aggregation,
+            accessor_idx,
+        )?)),
+        Filter(_) => Ok(Box::new(SegmentFiltersCollector::from_filter_req(
+            &mut req.sub_aggregation,
+            accessor_idx,
+        )?)),
+        Filters(filters_req) => Ok(Box::new(
+            SegmentFiltersCollector::from_filters_req_and_validate(
+                filters_req,
+                &mut req.sub_aggregation,
+                accessor_idx,
+            )?,
+        )),
         Histogram(histogram) => Ok(Box::new(SegmentHistogramCollector::from_req_and_validate(
             histogram.clone(),
             &mut req.sub_aggregation,
@@ -160,6 +186,19 @@ pub(crate) fn build_single_agg_segment_collector(
                 accessor_idx,
             )?,
         )),
+        Cardinality(cardinality_req) => Ok(Box::new(SegmentCardinalityCollector::from_req(
+            cardinality_req,
+            req.field_type,
+            accessor_idx,
+        ))),
+        TopHits(top_hits_req) => Ok(Box::new(SegmentTopHitsCollector::from_req(
+            top_hits_req,
+            accessor_idx,
+        ))),
+        Derivative(_) | CumulativeSum(_) | MovingAvg(_) | BucketScript(_) | BucketSelector(_)
+        | BucketSort(_) => Err(crate::TantivyError::InternalError(
+            "pipeline aggregations don't collect documents".to_string(),
+        )),
     }
 }
 
@@ -0,0 +1,423 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use columnar::{Column, MonotonicallyMappableToU64, StrColumn};
+
+use crate::collector::top_collector::TopSegmentCollector;
+use crate::collector::{Collector, SegmentCollector};
+use crate::schema::FieldType;
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+/// Value of the field a [`CollapsingCollector`] groups documents by.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GroupKey {
+    /// Value of a `u64` field.
+    U64(u64),
+    /// Value of a `i64` field.
+    I64(i64),
+    /// Value of a `str` field.
+    Str(String),
+}
+
+/// The top documents of a group, as returned by a [`CollapsingCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollapsedGroup {
+    /// Value shared by the documents of the group, or `None` for the documents without any
+    /// value.
+    pub key: Option<GroupKey>,
+    /// Top documents of the group, sorted by decreasing score.
+    pub top_docs: Vec<(Score, DocAddress)>,
+}
+
+impl CollapsedGroup {
+    fn best_score(&self) -> Score {
+        self.top_docs
+            .first()
+            .map(|(score, _)| *score)
+            .unwrap_or(Score::MIN)
+    }
+}
+
+/// The groups returned by a [`CollapsingCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollapsedGroups {
+    /// Top groups, sorted by the decreasing score of their best document.
+    pub groups: Vec<CollapsedGroup>,
+    /// Number of distinct groups of matching documents, including the groups that did not make
+    /// it to the top.
+    pub total_groups: usize,
+}
+
+/// Collapses the matching documents by the value of a fast field, and returns the best
+/// documents of each group.
+///
+/// The field must be a `u64`, `i64` or `str` fast field. Documents are grouped by their first
+/// value in the field. Documents without any value form their own group, with a `None` key.
+///
+/// Groups are ranked by the score of their best document. `hits_per_group`, 1 by default,
+/// documents are returned for each of the top `limit` groups.
+///
+/// ```rust
+/// use tantivy::collector::{CollapsingCollector, GroupKey};
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, FAST, STRING, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let family = schema_builder.add_text_field("family", STRING | FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "red running shoes", family => "shoes"))?;
+/// index_writer.add_document(doc!(title => "blue running shoes", family => "shoes"))?;
+/// index_writer.add_document(doc!(title => "running shorts", family => "shorts"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("running")?;
+/// let collapsed = searcher.search(&query, &CollapsingCollector::for_field("family", 10))?;
+/// assert_eq!(collapsed.total_groups, 2);
+/// assert_eq!(collapsed.groups.len(), 2);
+/// assert!(collapsed.groups.iter().all(|group| group.top_docs.len() == 1));
+/// assert!(collapsed
+///     .groups
+///     .iter()
+///     .any(|group| group.key == Some(GroupKey::Str("shorts".to_string()))));
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CollapsingCollector {
+    field_name: String,
+    limit: usize,
+    hits_per_group: usize,
+}
+
+impl CollapsingCollector {
+    /// Creates a collector returning the top `limit` groups of documents sharing the same value
+    /// in the fast field `field_name`.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn for_field(field_name: impl ToString, limit: usize) -> CollapsingCollector {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        CollapsingCollector {
+            field_name: field_name.to_string(),
+            limit,
+            hits_per_group: 1,
+        }
+    }
+
+    /// Sets the number of documents returned for each group.
+    ///
+    /// # Panics
+    /// The method panics if hits_per_group is 0
+    #[must_use]
+    pub fn with_hits_per_group(mut self, hits_per_group: usize) -> CollapsingCollector {
+        assert!(
+            hits_per_group >= 1,
+            "The number of hits per group must be strictly greater than 0."
+        );
+        self.hits_per_group = hits_per_group;
+        self
+    }
+
+    fn open_group_column(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Option<GroupColumn>> {
+        let schema = segment_reader.schema();
+        let field = schema
+            .get_field(&self.field_name)
+            .map_err(|_| TantivyError::FieldNotFound(self.field_name.clone()))?;
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field.",
+                self.field_name
+            )));
+        }
+        let fast_fields = segment_reader.fast_fields();
+        let group_column_opt = match field_entry.field_type() {
+            FieldType::Str(_) => fast_fields.str(&self.field_name)?.map(GroupColumn::Str),
+            FieldType::U64(_) => fast_fields
+                .column_opt::<u64>(&self.field_name)?
+                .map(GroupColumn::U64),
+            FieldType::I64(_) => fast_fields
+                .column_opt::<i64>(&self.field_name)?
+                .map(GroupColumn::I64),
+            _ => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Documents can only be collapsed by a u64, i64 or str field, {:?} is not one.",
+                    self.field_name
+                )));
+            }
+        };
+        Ok(group_column_opt)
+    }
+}
+
+impl Collector for CollapsingCollector {
+    type Fruit = CollapsedGroups;
+
+    type Child = CollapsingSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<CollapsingSegmentCollector> {
+        Ok(CollapsingSegmentCollector {
+            group_column_opt: self.open_group_column(segment_reader)?,
+            groups: HashMap::new(),
+            segment_ord: segment_local_id,
+            hits_per_group: self.hits_per_group,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_groups: Vec<crate::Result<Vec<CollapsedGroup>>>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut merged_groups: HashMap<Option<GroupKey>, Vec<(Score, DocAddress)>> = HashMap::new();
+        for groups in segment_groups {
+            for group in groups? {
+                merged_groups
+                    .entry(group.key)
+                    .or_default()
+                    .extend(group.top_docs);
+            }
+        }
+        let total_groups = merged_groups.len();
+        let mut groups: Vec<CollapsedGroup> = merged_groups
+            .into_iter()
+            .map(|(key, mut top_docs)| {
+                top_docs.sort_by(compare_by_score);
+                top_docs.truncate(self.hits_per_group);
+                CollapsedGroup { key, top_docs }
+            })
+            .collect();
+        groups.sort_by(|left, right| {
+            right
+                .best_score()
+                .partial_cmp(&left.best_score())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.key.cmp(&right.key))
+        });
+        groups.truncate(self.limit);
+        Ok(CollapsedGroups {
+            groups,
+            total_groups,
+        })
+    }
+}
+
+/// Sorts documents by decreasing score, and then by increasing address.
+fn compare_by_score(left: &(Score, DocAddress), right: &(Score, DocAddress)) -> Ordering {
+    right
+        .0
+        .partial_cmp(&left.0)
+        .unwrap_or(Ordering::Equal)
+        .then(left.1.cmp(&right.1))
+}
+
+enum GroupColumn {
+    Str(StrColumn),
+    U64(Column<u64>),
+    I64(Column<i64>),
+}
+
+impl GroupColumn {
+    /// Returns the segment local representation of the group of a document.
+    fn group(&self, doc: DocId) -> Option<u64> {
+        match self {
+            GroupColumn::Str(str_column) => str_column.ords().first(doc),
+            GroupColumn::U64(column) => column.first(doc),
+            GroupColumn::I64(column) => column.first(doc).map(|val| val.to_u64()),
+        }
+    }
+
+    /// Converts the segment local representation of a group to its key.
+    ///
+    /// Str values are resolved through the dictionary of the segment, so that they can be
+    /// compared across segments.
+    fn key(&self, group: u64) -> crate::Result<GroupKey> {
+        match self {
+            GroupColumn::Str(str_column) => {
+                let mut value = String::new();
+                str_column.ord_to_str(group, &mut value)?;
+                Ok(GroupKey::Str(value))
+            }
+            GroupColumn::U64(_) => Ok(GroupKey::U64(group)),
+            GroupColumn::I64(_) => Ok(GroupKey::I64(i64::from_u64(group))),
+        }
+    }
+}
+
+/// Segment collector of the [`CollapsingCollector`].
+pub struct CollapsingSegmentCollector {
+    /// `None` if no document of the segment has a value.
+    group_column_opt: Option<GroupColumn>,
+    groups: HashMap<Option<u64>, TopSegmentCollector<Score>>,
+    segment_ord: SegmentOrdinal,
+    hits_per_group: usize,
+}
+
+impl SegmentCollector for CollapsingSegmentCollector {
+    type Fruit = crate::Result<Vec<CollapsedGroup>>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let group = self
+            .group_column_opt
+            .as_ref()
+            .and_then(|group_column| group_column.group(doc));
+        let (segment_ord, hits_per_group) = (self.segment_ord, self.hits_per_group);
+        self.groups
+            .entry(group)
+            .or_insert_with(|| TopSegmentCollector::new(segment_ord, hits_per_group))
+            .collect(doc, score);
+    }
+
+    fn harvest(self) -> crate::Result<Vec<CollapsedGroup>> {
+        let mut groups = Vec::with_capacity(self.groups.len());
+        for (group_opt, top_collector) in self.groups {
+            let key = match (group_opt, self.group_column_opt.as_ref()) {
+                (Some(group), Some(group_column)) => Some(group_column.key(group)?),
+                _ => None,
+            };
+            groups.push(CollapsedGroup {
+                key,
+                top_docs: top_collector.harvest(),
+            });
+        }
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollapsingCollector, GroupKey};
+    use crate::collector::TopDocs;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{AllQuery, ConstScoreQuery, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, STRING, TEXT};
+    use crate::{Index, Term};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let family = schema_builder.add_text_field("family", STRING | FAST);
+        let size = schema_builder.add_u64_field("size", FAST);
+        let delta = schema_builder.add_i64_field("delta", FAST);
+        schema_builder.add_f64_field("price", FAST);
+        schema_builder.add_text_field("description", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(
+            doc!(title => "shoe shoe shoe", family => "shoes", size => 42u64, delta => -1i64),
+        )?;
+        index_writer.add_document(doc!(title => "shoe", family => "shoes", size => 43u64))?;
+        index_writer
+            .add_document(doc!(title => "shoe shorts", family => "shorts", delta => 1i64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "shoe shoe", family => "shoes", size => 42u64))?;
+        index_writer.add_document(doc!(title => "shoe", family => "socks", delta => -1i64))?;
+        index_writer.add_document(doc!(title => "shoe", size => 44u64))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    #[test]
+    fn test_collapsing_collector_str() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let title = index.schema().get_field("title").unwrap();
+        let query = QueryParser::for_index(&index, vec![title]).parse_query("shoe")?;
+        let collapsing_collector =
+            CollapsingCollector::for_field("family", 2).with_hits_per_group(2);
+        let collapsed = searcher.search(&query, &collapsing_collector)?;
+        assert_eq!(collapsed.total_groups, 4);
+        assert_eq!(collapsed.groups.len(), 2);
+        // The documents of the "shoes" group come from both segments.
+        let shoes = &collapsed.groups[0];
+        assert_eq!(shoes.key, Some(GroupKey::Str("shoes".to_string())));
+        assert_eq!(shoes.top_docs.len(), 2);
+        assert!(shoes.top_docs[0].0 > shoes.top_docs[1].0);
+        assert_ne!(
+            shoes.top_docs[0].1.segment_ord,
+            shoes.top_docs[1].1.segment_ord
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+        assert_eq!(shoes.top_docs[0], top_docs[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapsing_collector_numerical() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let collapsed = searcher.search(
+            &ConstScoreQuery::new(Box::new(AllQuery), 1.0),
+            &CollapsingCollector::for_field("size", 10).with_hits_per_group(3),
+        )?;
+        let mut groups: Vec<(Option<GroupKey>, usize)> = collapsed
+            .groups
+            .into_iter()
+            .map(|group| (group.key, group.top_docs.len()))
+            .collect();
+        groups.sort();
+        assert_eq!(
+            groups,
+            vec![
+                (None, 2),
+                (Some(GroupKey::U64(42)), 2),
+                (Some(GroupKey::U64(43)), 1),
+                (Some(GroupKey::U64(44)), 1),
+            ]
+        );
+        let family = index.schema().get_field("family").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(family, "shoes"),
+            IndexRecordOption::Basic,
+        );
+        let collapsed = searcher.search(&query, &CollapsingCollector::for_field("delta", 1))?;
+        assert_eq!(collapsed.total_groups, 2);
+        assert_eq!(collapsed.groups.len(), 1);
+        let collapsed = searcher.search(
+            &ConstScoreQuery::new(Box::new(AllQuery), 1.0),
+            &CollapsingCollector::for_field("delta", 10),
+        )?;
+        let mut keys: Vec<Option<GroupKey>> = collapsed
+            .groups
+            .into_iter()
+            .map(|group| group.key)
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![None, Some(GroupKey::I64(-1)), Some(GroupKey::I64(1))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapsing_collector_unsupported_field() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        for field_name in ["price", "description", "unknown"] {
+            assert!(searcher
+                .search(&AllQuery, &CollapsingCollector::for_field(field_name, 1))
+                .is_err());
+        }
+        Ok(())
+    }
+}
@@ -114,6 +114,28 @@ pub use self::docset_collector::DocSetCollector;
 mod filter_collector_wrapper;
 pub use self::filter_collector_wrapper::{BytesFilterCollector, FilterCollector};
 
+mod reciprocal_rank_fusion;
+pub use self::reciprocal_rank_fusion::ReciprocalRankFusion;
+
+mod rescorer;
+pub use self::rescorer::Rescorer;
+
+mod search_after;
+pub use self::search_after::{Cursor, Page};
+
+mod total_hits;
+pub use self::total_hits::TotalHits;
+
+mod sort_by_collector;
+pub use self::sort_by_collector::{
+    MissingOrder, SortBy, SortBySegmentCollector, SortKey, SortValue,
+};
+
+mod collapsing_collector;
+pub use self::collapsing_collector::{
+    CollapsedGroup, CollapsedGroups, CollapsingCollector, CollapsingSegmentCollector, GroupKey,
+};
+
 /// `Fruit` is the type for the result of our collection.
 /// e.g. `usize` for the `Count` collector.
 pub trait Fruit: Send + downcast_rs::Downcast {}
@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::collector::TopDocs;
+use crate::query::Query;
+use crate::{DocAddress, Score, Searcher};
+
+const DEFAULT_RANK_CONSTANT: Score = 60.0;
+
+/// Combines several rankings of documents using Reciprocal Rank Fusion.
+///
+/// Each document gets the score `sum(1 / (rank_constant + rank))`, summed over the rankings
+/// it appears in, `rank` starting at 1. Only the rank of a document matters, which makes it
+/// possible to combine rankings whose scores are not comparable, typically a full-text
+/// query and a [`KnnQuery`](crate::query::KnnQuery).
+///
+/// Ranks are only meaningful over the entire index, so the fusion cannot be done
+/// segment by segment like a regular [`Collector`](super::Collector). Instead, the top documents
+/// of each query are collected first, and then fused.
+///
+/// ```rust
+/// use tantivy::collector::ReciprocalRankFusion;
+/// use tantivy::query::{KnnQuery, QueryParser};
+/// use tantivy::schema::{Schema, VectorMetric, VectorOptions, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let embedding = schema_builder
+///     .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Cosine));
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(title => "the old man", embedding => vec![1.0f32, 0.0]))?;
+/// index_writer.add_document(doc!(title => "the sea", embedding => vec![0.0f32, 1.0]))?;
+/// index_writer.add_document(doc!(title => "the old old sea", embedding => vec![0.7f32, 0.7]))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let text_query = QueryParser::for_index(&index, vec![title]).parse_query("old")?;
+/// let knn_query = KnnQuery::new(embedding, vec![0.1, 1.0], 3);
+/// let fused_docs = ReciprocalRankFusion::with_limit(3)
+///     .search(&searcher, &[text_query.as_ref(), &knn_query])?;
+/// assert_eq!(fused_docs.len(), 3);
+/// // "the old old sea" is ranked first by the text query and second by the knn query.
+/// assert_eq!(fused_docs[0].1.doc_id, 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReciprocalRankFusion {
+    limit: usize,
+    window_size: usize,
+    rank_constant: Score,
+}
+
+impl ReciprocalRankFusion {
+    /// Creates a fusion returning the top `limit` documents.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn with_limit(limit: usize) -> ReciprocalRankFusion {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        ReciprocalRankFusion {
+            limit,
+            window_size: limit,
+            rank_constant: DEFAULT_RANK_CONSTANT,
+        }
+    }
+
+    /// Sets the number of documents collected for each query by [`Self::search`].
+    ///
+    /// It defaults to the limit. Larger windows let documents ranked low by a single
+    /// query contribute to the fused ranking.
+    #[must_use]
+    pub fn with_window_size(mut self, window_size: usize) -> ReciprocalRankFusion {
+        self.window_size = window_size.max(1);
+        self
+    }
+
+    /// Sets the rank constant (default `60`).
+    ///
+    /// Higher values reduce the advantage of the documents at the top of each ranking.
+    #[must_use]
+    pub fn with_rank_constant(mut self, rank_constant: Score) -> ReciprocalRankFusion {
+        self.rank_constant = rank_constant;
+        self
+    }
+
+    /// Fuses rankings, each sorted by decreasing relevance.
+    ///
+    /// Scores of the input rankings are ignored. Documents with the same fused score are
+    /// ordered by `DocAddress`.
+    pub fn fuse(&self, rankings: &[Vec<(Score, DocAddress)>]) -> Vec<(Score, DocAddress)> {
+        let mut fused_scores: HashMap<DocAddress, Score> = HashMap::new();
+        for ranking in rankings {
+            for (rank, (_, doc_address)) in ranking.iter().enumerate() {
+                *fused_scores.entry(*doc_address).or_default() +=
+                    1.0 / (self.rank_constant + (rank + 1) as Score);
+            }
+        }
+        let mut fused_docs: Vec<(Score, DocAddress)> = fused_scores
+            .into_iter()
+            .map(|(doc_address, score)| (score, doc_address))
+            .collect();
+        fused_docs.sort_by(|left, right| {
+            right
+                .0
+                .partial_cmp(&left.0)
+                .unwrap_or(Ordering::Equal)
+                .then(left.1.cmp(&right.1))
+        });
+        fused_docs.truncate(self.limit);
+        fused_docs
+    }
+
+    /// Runs each query with [`TopDocs`] and fuses the resulting rankings.
+    pub fn search(
+        &self,
+        searcher: &Searcher,
+        queries: &[&dyn Query],
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let top_docs = TopDocs::with_limit(self.window_size);
+        let rankings = queries
+            .iter()
+            .map(|query| searcher.search(*query, &top_docs))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(self.fuse(&rankings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReciprocalRankFusion;
+    use crate::DocAddress;
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let doc = |doc_id| DocAddress::new(0, doc_id);
+        let rankings = vec![
+            vec![(10.0, doc(1)), (5.0, doc(2)), (1.0, doc(3))],
+            vec![(0.9, doc(3)), (0.8, doc(2)), (0.7, doc(4))],
+        ];
+        let fused = ReciprocalRankFusion::with_limit(3)
+            .with_rank_constant(1.0)
+            .fuse(&rankings);
+        assert_eq!(
+            fused,
+            vec![
+                (1.0 / 4.0 + 1.0 / 2.0, doc(3)),
+                (1.0 / 3.0 + 1.0 / 3.0, doc(2)),
+                (1.0 / 2.0, doc(1)),
+            ]
+        );
+        assert!(ReciprocalRankFusion::with_limit(2).fuse(&[]).is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::collector::top_collector::{TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector};
+use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::{DocAddress, DocId, Score, Searcher, SegmentOrdinal, SegmentReader};
+
+/// Re-scores the top documents of a first query with a second, more expensive, query.
+///
+/// Scoring every match with a query such as a [`PhraseQuery`](crate::query::PhraseQuery)
+/// with slop can be too slow. Instead, the documents can be ranked by a cheap query first,
+/// and only the best of them re-scored with the expensive query.
+///
+/// The final score of a document is
+/// `query_weight * first_pass_score + rescore_query_weight * rescore_score`, where
+/// `rescore_score` is `0` if the document does not match the rescore query. Both weights
+/// default to `1`.
+///
+/// The rescoring can happen:
+/// - per segment, by collecting with [`TopDocs::rescore`](super::TopDocs::rescore). The top
+///   `window_size` documents of each segment are re-scored.
+/// - globally, by calling [`Rescorer::rescore`] on the documents returned by
+///   [`TopDocs`](super::TopDocs).
+///
+/// ```rust
+/// use tantivy::collector::{Rescorer, TopDocs};
+/// use tantivy::query::{PhraseQuery, QueryParser};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(title => "the sea and the old fisherman, old and wise"))?;
+/// index_writer.add_document(doc!(title => "the old man and the sea"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("old sea")?;
+/// let mut phrase_query = PhraseQuery::new(vec![
+///     Term::from_field_text(title, "old"),
+///     Term::from_field_text(title, "sea"),
+/// ]);
+/// phrase_query.set_slop(4);
+/// let rescorer = Rescorer::new(&searcher, &phrase_query, 10)?.with_rescore_query_weight(2.0);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(2).rescore(rescorer.clone()))?;
+/// // Only the second document has "old" and "sea" close to each other.
+/// assert_eq!(top_docs[0].1.doc_id, 1);
+///
+/// let explanation = rescorer.explain(&searcher, &query, top_docs[0].1)?;
+/// assert_eq!(explanation.value(), top_docs[0].0);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone)]
+pub struct Rescorer {
+    weight: Arc<dyn Weight>,
+    window_size: usize,
+    query_weight: Score,
+    rescore_query_weight: Score,
+}
+
+impl Rescorer {
+    /// Creates a `Rescorer` re-scoring the top `window_size` documents with `rescore_query`.
+    ///
+    /// The searcher is used to compute the statistics of the rescore query, so it should be
+    /// the searcher the first query is run with.
+    pub fn new(
+        searcher: &Searcher,
+        rescore_query: &dyn Query,
+        window_size: usize,
+    ) -> crate::Result<Rescorer> {
+        let weight = rescore_query.weight(EnableScoring::enabled_from_searcher(searcher))?;
+        Ok(Rescorer {
+            weight: Arc::from(weight),
+            window_size: window_size.max(1),
+            query_weight: 1.0,
+            rescore_query_weight: 1.0,
+        })
+    }
+
+    /// Sets the weight of the score of the first query (default `1`).
+    #[must_use]
+    pub fn with_query_weight(mut self, query_weight: Score) -> Rescorer {
+        self.query_weight = query_weight;
+        self
+    }
+
+    /// Sets the weight of the score of the rescore query (default `1`).
+    #[must_use]
+    pub fn with_rescore_query_weight(mut self, rescore_query_weight: Score) -> Rescorer {
+        self.rescore_query_weight = rescore_query_weight;
+        self
+    }
+
+    /// Returns the number of documents re-scored per segment by
+    /// [`TopDocs::rescore`](super::TopDocs::rescore).
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Re-scores documents, typically the top documents returned by
+    /// [`TopDocs`](super::TopDocs) for the first query, and sorts them by decreasing
+    /// final score.
+    pub fn rescore(
+        &self,
+        searcher: &Searcher,
+        top_docs: Vec<(Score, DocAddress)>,
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let mut top_docs = top_docs;
+        // Scorers can only move forward, so documents are re-scored in the doc id order.
+        top_docs.sort_by_key(|(_, doc_address)| *doc_address);
+        let mut rescored_docs = Vec::with_capacity(top_docs.len());
+        let mut segment_rescorer_opt: Option<(SegmentOrdinal, SegmentRescorer)> = None;
+        for (score, doc_address) in top_docs {
+            let segment_ord = doc_address.segment_ord;
+            if segment_rescorer_opt.as_ref().map(|(ord, _)| *ord) != Some(segment_ord) {
+                let segment_reader = searcher.segment_reader(segment_ord);
+                segment_rescorer_opt = Some((segment_ord, self.segment_rescorer(segment_reader)?));
+            }
+            if let Some((_, segment_rescorer)) = segment_rescorer_opt.as_mut() {
+                rescored_docs.push((
+                    segment_rescorer.rescore(doc_address.doc_id, score),
+                    doc_address,
+                ));
+            }
+        }
+        sort_by_score(&mut rescored_docs);
+        Ok(rescored_docs)
+    }
+
+    /// Returns an [`Explanation`] of the final score of a document, combining the
+    /// explanations of the first query and of the rescore query.
+    pub fn explain(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        doc_address: DocAddress,
+    ) -> crate::Result<Explanation> {
+        let query_explanation = query.explain(searcher, doc_address)?;
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+        let mut scorer = self.weight.scorer(segment_reader, 1.0)?;
+        let rescore_explanation = if seek_doc(scorer.as_mut(), doc_address.doc_id) {
+            self.weight.explain(segment_reader, doc_address.doc_id)?
+        } else {
+            Explanation::new("Rescore query does not match", 0.0)
+        };
+        let score = self.query_weight * query_explanation.value()
+            + self.rescore_query_weight * rescore_explanation.value();
+        let mut explanation = Explanation::new(
+            "Rescore, query_weight * first pass + rescore_query_weight * second pass",
+            score,
+        );
+        explanation.add_const("query_weight", self.query_weight);
+        explanation.add_detail(query_explanation);
+        explanation.add_const("rescore_query_weight", self.rescore_query_weight);
+        explanation.add_detail(rescore_explanation);
+        Ok(explanation)
+    }
+
+    fn segment_rescorer(&self, segment_reader: &SegmentReader) -> crate::Result<SegmentRescorer> {
+        Ok(SegmentRescorer {
+            scorer: self.weight.scorer(segment_reader, 1.0)?,
+            query_weight: self.query_weight,
+            rescore_query_weight: self.rescore_query_weight,
+        })
+    }
+}
+
+impl std::fmt::Debug for Rescorer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Rescorer")
+            .field("window_size", &self.window_size)
+            .field("query_weight", &self.query_weight)
+            .field("rescore_query_weight", &self.rescore_query_weight)
+            .finish()
+    }
+}
+
+/// Re-scores the documents of a segment, which must be passed in increasing doc id order.
+struct SegmentRescorer {
+    scorer: Box<dyn Scorer>,
+    query_weight: Score,
+    rescore_query_weight: Score,
+}
+
+impl SegmentRescorer {
+    fn rescore(&mut self, doc: DocId, score: Score) -> Score {
+        let rescore_score = if seek_doc(self.scorer.as_mut(), doc) {
+            self.scorer.score()
+        } else {
+            0.0
+        };
+        self.query_weight * score + self.rescore_query_weight * rescore_score
+    }
+}
+
+/// Advances the scorer to `doc` if it is not past it yet, and returns true if `doc` matches.
+fn seek_doc(scorer: &mut dyn Scorer, doc: DocId) -> bool {
+    if scorer.doc() > doc {
+        return false;
+    }
+    scorer.seek(doc) == doc
+}
+
+fn sort_by_score(docs: &mut [(Score, DocAddress)]) {
+    docs.sort_by(|left, right| {
+        right
+            .0
+            .partial_cmp(&left.0)
+            .unwrap_or(Ordering::Equal)
+            .then(left.1.cmp(&right.1))
+    });
+}
+
+/// Collector returned by [`TopDocs::rescore`](super::TopDocs::rescore).
+pub(crate) struct RescoreTopCollector {
+    rescorer: Rescorer,
+    collector: TopCollector<Score>,
+}
+
+impl RescoreTopCollector {
+    pub fn new(rescorer: Rescorer, collector: TopCollector<Score>) -> RescoreTopCollector {
+        RescoreTopCollector {
+            rescorer,
+            collector,
+        }
+    }
+}
+
+impl Collector for RescoreTopCollector {
+    type Fruit = Vec<(Score, DocAddress)>;
+
+    type Child = RescoreSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        // All of the documents that can be returned are re-scored, even if the window
+        // is smaller.
+        let window_size = self
+            .rescorer
+            .window_size
+            .max(self.collector.limit + self.collector.offset);
+        let window_collector: TopCollector<Score> = TopCollector::with_limit(window_size);
+        Ok(RescoreSegmentCollector {
+            segment_collector: window_collector.for_segment(segment_local_id, segment_reader),
+            segment_rescorer: self.rescorer.segment_rescorer(segment_reader)?,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        child_fruits: Vec<Vec<(Score, DocAddress)>>,
+    ) -> crate::Result<Self::Fruit> {
+        self.collector.merge_fruits(child_fruits)
+    }
+}
+
+pub(crate) struct RescoreSegmentCollector {
+    segment_collector: TopSegmentCollector<Score>,
+    segment_rescorer: SegmentRescorer,
+}
+
+impl SegmentCollector for RescoreSegmentCollector {
+    type Fruit = Vec<(Score, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        self.segment_collector.collect(doc, score);
+    }
+
+    fn harvest(self) -> Vec<(Score, DocAddress)> {
+        let mut segment_rescorer = self.segment_rescorer;
+        let mut window = self.segment_collector.harvest();
+        window.sort_by_key(|(_, doc_address)| *doc_address);
+        let mut rescored_docs: Vec<(Score, DocAddress)> = window
+            .into_iter()
+            .map(|(score, doc_address)| {
+                (
+                    segment_rescorer.rescore(doc_address.doc_id, score),
+                    doc_address,
+                )
+            })
+            .collect();
+        sort_by_score(&mut rescored_docs);
+        rescored_docs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rescorer;
+    use crate::collector::TopDocs;
+    use crate::query::{PhraseQuery, QueryParser};
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{assert_nearly_equals, Index, Term};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "old old old sea sea sea"))?;
+        index_writer.add_document(doc!(title => "the old man and the sea"))?;
+        index_writer.add_document(doc!(title => "a sea of old books"))?;
+        index_writer.add_document(doc!(title => "the sea"))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn phrase_query(index: &Index, slop: u32) -> PhraseQuery {
+        let title = index.schema().get_field("title").unwrap();
+        let mut phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(title, "old"),
+            Term::from_field_text(title, "sea"),
+        ]);
+        phrase_query.set_slop(slop);
+        phrase_query
+    }
+
+    fn titles(index: &Index, top_docs: &[(f32, crate::DocAddress)]) -> Vec<String> {
+        let searcher = index.reader().unwrap().searcher();
+        let title = index.schema().get_field("title").unwrap();
+        top_docs
+            .iter()
+            .map(|(_, doc_address)| {
+                let doc = searcher.doc(*doc_address).unwrap();
+                doc.get_first(title).unwrap().as_text().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rescore_top_docs() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let title = index.schema().get_field("title").unwrap();
+        let query = QueryParser::for_index(&index, vec![title]).parse_query("old sea")?;
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(3))?;
+        assert_eq!(titles(&index, &top_docs)[0], "old old old sea sea sea");
+
+        // Only the first-pass score is kept.
+        let rescorer =
+            Rescorer::new(&searcher, &phrase_query(&index, 2), 10)?.with_rescore_query_weight(0.0);
+        let rescored_docs = searcher.search(&query, &TopDocs::with_limit(3).rescore(rescorer))?;
+        assert_eq!(rescored_docs, top_docs);
+
+        // Only the rescore score is kept, and only the first document contains "old sea".
+        let rescorer =
+            Rescorer::new(&searcher, &phrase_query(&index, 0), 10)?.with_query_weight(0.0);
+        let rescored_docs = searcher.search(&query, &TopDocs::with_limit(4).rescore(rescorer))?;
+        assert_eq!(rescored_docs.len(), 4);
+        assert_eq!(titles(&index, &rescored_docs)[0], "old old old sea sea sea");
+        assert!(rescored_docs[0].0 > 0.0);
+        assert_eq!(rescored_docs[1].0, 0.0);
+
+        // With a slop, every document but "the sea" matches the rescore query.
+        let rescorer =
+            Rescorer::new(&searcher, &phrase_query(&index, 4), 10)?.with_query_weight(0.0);
+        let rescored_docs = searcher.search(&query, &TopDocs::with_limit(4).rescore(rescorer))?;
+        assert_eq!(titles(&index, &rescored_docs)[3], "the sea");
+        assert!(rescored_docs[2].0 > 0.0);
+        assert_eq!(rescored_docs[3].0, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescore_globally() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let title = index.schema().get_field("title").unwrap();
+        let query = QueryParser::for_index(&index, vec![title]).parse_query("old sea")?;
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let rescorer = Rescorer::new(&searcher, &phrase_query(&index, 4), 10)?
+            .with_query_weight(0.5)
+            .with_rescore_query_weight(2.0);
+        let rescored_docs = rescorer.rescore(&searcher, top_docs.clone())?;
+        let collected_docs =
+            searcher.search(&query, &TopDocs::with_limit(10).rescore(rescorer.clone()))?;
+        assert_eq!(rescored_docs, collected_docs);
+        // "the sea" does not match the rescore query, and only keeps half of its score.
+        let the_sea = top_docs
+            .iter()
+            .find(|(_, doc_address)| titles(&index, &[(0.0, *doc_address)])[0] == "the sea")
+            .unwrap();
+        let rescored_the_sea = rescored_docs
+            .iter()
+            .find(|(_, doc_address)| *doc_address == the_sea.1)
+            .unwrap();
+        assert_nearly_equals!(rescored_the_sea.0, the_sea.0 * 0.5);
+        assert!(rescorer.rescore(&searcher, Vec::new())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescore_explain() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let title = index.schema().get_field("title").unwrap();
+        let query = QueryParser::for_index(&index, vec![title]).parse_query("old sea")?;
+        let rescorer = Rescorer::new(&searcher, &phrase_query(&index, 1), 2)?
+            .with_query_weight(0.3)
+            .with_rescore_query_weight(1.5);
+        let rescored_docs =
+            searcher.search(&query, &TopDocs::with_limit(4).rescore(rescorer.clone()))?;
+        assert_eq!(rescored_docs.len(), 4);
+        for (score, doc_address) in rescored_docs {
+            let explanation = rescorer.explain(&searcher, &query, doc_address)?;
+            assert_nearly_equals!(explanation.value(), score);
+        }
+        Ok(())
+    }
+}
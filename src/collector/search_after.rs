@@ -0,0 +1,432 @@
+use std::collections::BinaryHeap;
+use std::net::Ipv6Addr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::collector::top_collector::{ComparableDoc, TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector, SortValue};
+use crate::query::Weight;
+use crate::{DateTime, DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+/// Position of a document in a sorted list of documents, from which the next page of results
+/// starts.
+///
+/// Paginating with a cursor, as in
+/// [`TopDocs::search_after`](super::TopDocs::search_after) or
+/// [`SortBy::search_after`](super::SortBy::search_after), only costs as much as collecting
+/// a single page, however deep the page is.
+///
+/// A cursor refers to documents by their [`DocAddress`], so it should be used with the
+/// same [`Searcher`](crate::Searcher) as the previous page.
+///
+/// The cursor can be sent to a client as an opaque token, with [`Cursor::to_token`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    sort_values: Vec<Option<SortValue>>,
+    doc_address: DocAddress,
+}
+
+impl Cursor {
+    /// Creates a cursor positioned on a document, given its sort values and address.
+    pub fn new(sort_values: Vec<Option<SortValue>>, doc_address: DocAddress) -> Cursor {
+        Cursor {
+            sort_values,
+            doc_address,
+        }
+    }
+
+    /// Creates a cursor positioned on a document ranked by score.
+    pub fn for_score(score: Score, doc_address: DocAddress) -> Cursor {
+        Cursor::new(vec![Some(SortValue::Score(score))], doc_address)
+    }
+
+    /// Returns the sort values of the document the cursor is positioned on.
+    pub fn sort_values(&self) -> &[Option<SortValue>] {
+        &self.sort_values
+    }
+
+    /// Returns the address of the document the cursor is positioned on.
+    pub fn doc_address(&self) -> DocAddress {
+        self.doc_address
+    }
+
+    /// Serializes the cursor into a URL-safe token.
+    pub fn to_token(&self) -> String {
+        let serialized_cursor = SerializedCursor {
+            sort_values: self
+                .sort_values
+                .iter()
+                .map(|sort_value_opt| sort_value_opt.as_ref().map(SerializedSortValue::from))
+                .collect(),
+            segment_ord: self.doc_address.segment_ord,
+            doc_id: self.doc_address.doc_id,
+        };
+        // Serializing plain values to JSON cannot fail.
+        let json = serde_json::to_vec(&serialized_cursor).unwrap();
+        BASE64.encode(json)
+    }
+
+    /// Deserializes a cursor from a token created by [`Cursor::to_token`].
+    pub fn from_token(token: &str) -> crate::Result<Cursor> {
+        let invalid_token = || TantivyError::InvalidArgument(format!("Invalid cursor {token:?}"));
+        let json = BASE64.decode(token).map_err(|_| invalid_token())?;
+        let serialized_cursor: SerializedCursor =
+            serde_json::from_slice(&json).map_err(|_| invalid_token())?;
+        Ok(Cursor {
+            sort_values: serialized_cursor
+                .sort_values
+                .into_iter()
+                .map(|sort_value_opt| sort_value_opt.map(SortValue::from))
+                .collect(),
+            doc_address: DocAddress::new(serialized_cursor.segment_ord, serialized_cursor.doc_id),
+        })
+    }
+
+    fn score(&self) -> crate::Result<Score> {
+        match self.sort_values.as_slice() {
+            [Some(SortValue::Score(score))] => Ok(*score),
+            _ => Err(TantivyError::InvalidArgument(
+                "The cursor is not positioned on a document ranked by score.".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedCursor {
+    sort_values: Vec<Option<SerializedSortValue>>,
+    segment_ord: SegmentOrdinal,
+    doc_id: DocId,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SerializedSortValue {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    DateNanos(i64),
+    IpAddr(Ipv6Addr),
+    Str(String),
+    Score(Score),
+    Doc(SegmentOrdinal, DocId),
+}
+
+impl From<&SortValue> for SerializedSortValue {
+    fn from(sort_value: &SortValue) -> SerializedSortValue {
+        match sort_value {
+            SortValue::U64(val) => SerializedSortValue::U64(*val),
+            SortValue::I64(val) => SerializedSortValue::I64(*val),
+            SortValue::F64(val) => SerializedSortValue::F64(*val),
+            SortValue::Bool(val) => SerializedSortValue::Bool(*val),
+            SortValue::Date(val) => SerializedSortValue::DateNanos(val.into_timestamp_nanos()),
+            SortValue::IpAddr(val) => SerializedSortValue::IpAddr(*val),
+            SortValue::Str(val) => SerializedSortValue::Str(val.clone()),
+            SortValue::Score(val) => SerializedSortValue::Score(*val),
+            SortValue::Doc(val) => SerializedSortValue::Doc(val.segment_ord, val.doc_id),
+        }
+    }
+}
+
+impl From<SerializedSortValue> for SortValue {
+    fn from(sort_value: SerializedSortValue) -> SortValue {
+        match sort_value {
+            SerializedSortValue::U64(val) => SortValue::U64(val),
+            SerializedSortValue::I64(val) => SortValue::I64(val),
+            SerializedSortValue::F64(val) => SortValue::F64(val),
+            SerializedSortValue::Bool(val) => SortValue::Bool(val),
+            SerializedSortValue::DateNanos(val) => {
+                SortValue::Date(DateTime::from_timestamp_nanos(val))
+            }
+            SerializedSortValue::IpAddr(val) => SortValue::IpAddr(val),
+            SerializedSortValue::Str(val) => SortValue::Str(val),
+            SerializedSortValue::Score(val) => SortValue::Score(val),
+            SerializedSortValue::Doc(segment_ord, doc_id) => {
+                SortValue::Doc(DocAddress::new(segment_ord, doc_id))
+            }
+        }
+    }
+}
+
+/// A page of documents, collected after a [`Cursor`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    /// The documents of the page, with their score or sort values.
+    pub docs: Vec<(T, DocAddress)>,
+    /// The cursor to fetch the next page, or `None` if there are no more documents.
+    ///
+    /// The next page can be empty if the last page happens to be full.
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    pub(crate) fn new(
+        docs: Vec<(T, DocAddress)>,
+        limit: usize,
+        cursor_for_doc: impl Fn(&T, DocAddress) -> Cursor,
+    ) -> Page<T> {
+        let next_cursor = if docs.len() < limit {
+            None
+        } else {
+            docs.last()
+                .map(|(sort_values, doc_address)| cursor_for_doc(sort_values, *doc_address))
+        };
+        Page { docs, next_cursor }
+    }
+}
+
+/// Collector returned by [`TopDocs::search_after`](super::TopDocs::search_after).
+pub(crate) struct SearchAfterTopCollector {
+    collector: TopCollector<Score>,
+    cursor_opt: Option<Cursor>,
+}
+
+impl SearchAfterTopCollector {
+    pub fn new(collector: TopCollector<Score>, cursor_opt: Option<Cursor>) -> Self {
+        SearchAfterTopCollector {
+            collector,
+            cursor_opt,
+        }
+    }
+
+    fn after_opt(&self) -> crate::Result<Option<(Score, DocAddress)>> {
+        if let Some(cursor) = self.cursor_opt.as_ref() {
+            Ok(Some((cursor.score()?, cursor.doc_address)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Returns true if the document is ranked before the cursor, i.e. on a previous page.
+#[inline]
+fn is_before_cursor(
+    after_opt: Option<(Score, DocAddress)>,
+    segment_ord: SegmentOrdinal,
+    doc: DocId,
+    score: Score,
+) -> bool {
+    if let Some((after_score, after_doc_address)) = after_opt {
+        // Documents are sorted by decreasing score, and then by increasing address.
+        score > after_score
+            || (score == after_score && DocAddress::new(segment_ord, doc) <= after_doc_address)
+    } else {
+        false
+    }
+}
+
+impl Collector for SearchAfterTopCollector {
+    type Fruit = Page<Score>;
+
+    type Child = SearchAfterTopSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<SearchAfterTopSegmentCollector> {
+        Ok(SearchAfterTopSegmentCollector {
+            segment_collector: self.collector.for_segment(segment_local_id, segment_reader),
+            after_opt: self.after_opt()?,
+            segment_ord: segment_local_id,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        child_fruits: Vec<Vec<(Score, DocAddress)>>,
+    ) -> crate::Result<Page<Score>> {
+        let docs = self.collector.merge_fruits(child_fruits)?;
+        Ok(Page::new(
+            docs,
+            self.collector.limit,
+            |score, doc_address| Cursor::for_score(*score, doc_address),
+        ))
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: SegmentOrdinal,
+        reader: &SegmentReader,
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let after_opt = self.after_opt()?;
+        let alive_bitset_opt = reader.alive_bitset();
+        let heap_len = self.collector.limit + self.collector.offset;
+        let mut heap: BinaryHeap<ComparableDoc<Score, DocId>> = BinaryHeap::with_capacity(heap_len);
+
+        // As in `TopDocs`, the threshold is the score of the last document of the page once the
+        // page is full. The cursor only bounds the scores from above, so the documents ranked
+        // before it are skipped here rather than pruned.
+        let mut threshold = Score::MIN;
+        weight.for_each_pruning(threshold, reader, &mut |doc, score| {
+            let is_deleted = alive_bitset_opt
+                .map(|alive_bitset| alive_bitset.is_deleted(doc))
+                .unwrap_or(false);
+            if is_deleted || is_before_cursor(after_opt, segment_ord, doc, score) {
+                return threshold;
+            }
+            let heap_item = ComparableDoc {
+                feature: score,
+                doc,
+            };
+            if heap.len() < heap_len {
+                heap.push(heap_item);
+                if heap.len() == heap_len {
+                    threshold = heap.peek().map(|el| el.feature).unwrap_or(Score::MIN);
+                }
+                return threshold;
+            }
+            *heap.peek_mut().unwrap() = heap_item;
+            threshold = heap.peek().map(|el| el.feature).unwrap_or(Score::MIN);
+            threshold
+        })?;
+
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|cid| (cid.feature, DocAddress::new(segment_ord, cid.doc)))
+            .collect())
+    }
+}
+
+pub(crate) struct SearchAfterTopSegmentCollector {
+    segment_collector: TopSegmentCollector<Score>,
+    after_opt: Option<(Score, DocAddress)>,
+    segment_ord: SegmentOrdinal,
+}
+
+impl SegmentCollector for SearchAfterTopSegmentCollector {
+    type Fruit = Vec<(Score, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if is_before_cursor(self.after_opt, self.segment_ord, doc, score) {
+            return;
+        }
+        self.segment_collector.collect(doc, score);
+    }
+
+    fn harvest(self) -> Vec<(Score, DocAddress)> {
+        self.segment_collector.harvest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use super::Cursor;
+    use crate::collector::{SortValue, TopDocs};
+    use crate::indexer::NoMergePolicy;
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, TEXT};
+    use crate::{DateTime, DocAddress, Index, Term};
+
+    #[test]
+    fn test_cursor_token() -> crate::Result<()> {
+        let cursor = Cursor::new(
+            vec![
+                Some(SortValue::U64(3)),
+                Some(SortValue::I64(-3)),
+                Some(SortValue::F64(0.5)),
+                Some(SortValue::Bool(true)),
+                Some(SortValue::Date(DateTime::from_timestamp_nanos(12))),
+                Some(SortValue::IpAddr(Ipv6Addr::LOCALHOST)),
+                Some(SortValue::Str("a/b?".to_string())),
+                Some(SortValue::Score(1.5)),
+                Some(SortValue::Doc(DocAddress::new(1, 2))),
+                None,
+            ],
+            DocAddress::new(1, 2),
+        );
+        let token = cursor.to_token();
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Cursor::from_token(&token)?, cursor);
+        assert!(Cursor::from_token("not a token").is_err());
+        assert!(Cursor::from_token("bm90IGpzb24").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_docs_search_after() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0..30 {
+            // Documents with the same text have the same score.
+            let repeated_text = vec!["a"; 1 + i % 4].join(" ");
+            index_writer.add_document(doc!(text => repeated_text))?;
+            if i % 10 == 9 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("a")?;
+        let all_docs = searcher.search(&query, &TopDocs::with_limit(30))?;
+        let mut paginated_docs = Vec::new();
+        let mut cursor_opt = None;
+        loop {
+            let page = searcher.search(&query, &TopDocs::with_limit(7).search_after(cursor_opt))?;
+            paginated_docs.extend(page.docs);
+            cursor_opt = page.next_cursor;
+            if cursor_opt.is_none() {
+                break;
+            }
+        }
+        assert_eq!(paginated_docs, all_docs);
+
+        let cursor = Cursor::new(vec![Some(SortValue::U64(1))], DocAddress::new(0, 0));
+        assert!(searcher
+            .search(&query, &TopDocs::with_limit(7).search_after(Some(cursor)))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_docs_search_after_with_deletes_and_pruning() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // Enough documents for the block-wand pruning of the union to skip blocks.
+        for i in 0..1_000 {
+            let mut words = vec!["a"; 1 + i % 7];
+            words.extend(vec!["b"; i % 5]);
+            if i % 9 == 0 {
+                words.push("c");
+            }
+            index_writer.add_document(doc!(text => words.join(" ")))?;
+        }
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(text, "c"));
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("a b")?;
+        let all_docs = searcher.search(&query, &TopDocs::with_limit(1_000))?;
+        assert_eq!(all_docs.len(), 888);
+        let mut paginated_docs = Vec::new();
+        let mut cursor_opt = None;
+        loop {
+            let page =
+                searcher.search(&query, &TopDocs::with_limit(50).search_after(cursor_opt))?;
+            paginated_docs.extend(page.docs);
+            cursor_opt = page.next_cursor;
+            if cursor_opt.is_none() {
+                break;
+            }
+        }
+        assert_eq!(paginated_docs, all_docs);
+        Ok(())
+    }
+}
@@ -0,0 +1,978 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::mem;
+use std::net::Ipv6Addr;
+
+use columnar::{Column, ColumnType, MonotonicallyMappableToU64, StrColumn};
+
+use crate::collector::{Collector, Cursor, Page, SegmentCollector};
+use crate::schema::FieldType;
+use crate::{
+    DateTime, DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader, TantivyError,
+};
+
+/// Position of the documents without any value, when sorting by a field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingOrder {
+    /// Documents without any value come first, whatever the order.
+    First,
+    /// Documents without any value come last, whatever the order.
+    #[default]
+    Last,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum SortTarget {
+    Field(String),
+    Score,
+    DocId,
+}
+
+/// A sort criterion of a [`SortBy`] collector.
+#[derive(Clone, Debug)]
+pub struct SortKey {
+    target: SortTarget,
+    order: Order,
+    missing: MissingOrder,
+}
+
+impl SortKey {
+    /// Sorts by the value of a fast field.
+    ///
+    /// The field can be a `u64`, `i64`, `f64`, `bool`, date, ip address or `str` fast field.
+    /// For multivalued fields, the smallest value is used in ascending order, and the largest
+    /// value in descending order.
+    pub fn field(field_name: impl ToString, order: Order) -> SortKey {
+        SortKey {
+            target: SortTarget::Field(field_name.to_string()),
+            order,
+            missing: MissingOrder::default(),
+        }
+    }
+
+    /// Sorts by score.
+    pub fn score(order: Order) -> SortKey {
+        SortKey {
+            target: SortTarget::Score,
+            order,
+            missing: MissingOrder::default(),
+        }
+    }
+
+    /// Sorts by [`DocAddress`].
+    pub fn doc_id(order: Order) -> SortKey {
+        SortKey {
+            target: SortTarget::DocId,
+            order,
+            missing: MissingOrder::default(),
+        }
+    }
+
+    /// Sets the position of the documents without any value in the field, last by default.
+    #[must_use]
+    pub fn with_missing(mut self, missing: MissingOrder) -> SortKey {
+        self.missing = missing;
+        self
+    }
+
+    /// Compares two values of this key, `None` standing for a missing value.
+    ///
+    /// `Ordering::Less` means that `left` comes first.
+    fn compare<T: PartialOrd>(&self, left: Option<&T>, right: Option<&T>) -> Ordering {
+        let missing_first = if self.missing == MissingOrder::First {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                let ordering = left.partial_cmp(right).unwrap_or(Ordering::Equal);
+                if self.order.is_desc() {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => missing_first,
+            (Some(_), None) => missing_first.reverse(),
+        }
+    }
+
+    /// Encodes a segment local value, so that encoded values sort in the order of this key.
+    ///
+    /// The boolean puts the missing values before or after the other values.
+    fn encode(&self, value_opt: Option<u128>) -> (bool, u128) {
+        match value_opt {
+            Some(value) if self.order.is_desc() => (self.missing == MissingOrder::First, !value),
+            Some(value) => (self.missing == MissingOrder::First, value),
+            None => (self.missing == MissingOrder::Last, 0),
+        }
+    }
+
+    fn decode(&self, (flag, value): (bool, u128)) -> Option<u128> {
+        if flag == (self.missing == MissingOrder::Last) {
+            return None;
+        }
+        if self.order.is_desc() {
+            Some(!value)
+        } else {
+            Some(value)
+        }
+    }
+}
+
+/// The value of a document for a [`SortKey`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortValue {
+    /// Value of a `u64` field.
+    U64(u64),
+    /// Value of an `i64` field.
+    I64(i64),
+    /// Value of an `f64` field.
+    F64(f64),
+    /// Value of a `bool` field.
+    Bool(bool),
+    /// Value of a date field.
+    Date(DateTime),
+    /// Value of an ip address field.
+    IpAddr(Ipv6Addr),
+    /// Value of a `str` field.
+    Str(String),
+    /// Score of the document.
+    Score(Score),
+    /// Address of the document.
+    Doc(DocAddress),
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &SortValue) -> Option<Ordering> {
+        match (self, other) {
+            (SortValue::U64(left), SortValue::U64(right)) => left.partial_cmp(right),
+            (SortValue::I64(left), SortValue::I64(right)) => left.partial_cmp(right),
+            (SortValue::F64(left), SortValue::F64(right)) => left.partial_cmp(right),
+            (SortValue::Bool(left), SortValue::Bool(right)) => left.partial_cmp(right),
+            (SortValue::Date(left), SortValue::Date(right)) => left.partial_cmp(right),
+            (SortValue::IpAddr(left), SortValue::IpAddr(right)) => left.partial_cmp(right),
+            (SortValue::Str(left), SortValue::Str(right)) => left.partial_cmp(right),
+            (SortValue::Score(left), SortValue::Score(right)) => left.partial_cmp(right),
+            (SortValue::Doc(left), SortValue::Doc(right)) => left.partial_cmp(right),
+            _ => None,
+        }
+    }
+}
+
+/// Sorts the matching documents by several [`SortKey`]s, and returns the top documents with
+/// their sort values.
+///
+/// Each key breaks the ties of the previous ones. The remaining ties are broken by ascending
+/// [`DocAddress`]. The values of `str` fields are compared as strings, so that documents from
+/// different segments are merged correctly.
+///
+/// ```rust
+/// use tantivy::collector::{SortBy, SortKey, SortValue};
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, FAST, STRING};
+/// use tantivy::{doc, Index, Order};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let name = schema_builder.add_text_field("name", STRING | FAST);
+/// let price = schema_builder.add_u64_field("price", FAST);
+/// let rating = schema_builder.add_f64_field("rating", FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(name => "kettle", price => 30u64, rating => 4.5))?;
+/// index_writer.add_document(doc!(name => "toaster", price => 25u64, rating => 4.0))?;
+/// index_writer.add_document(doc!(name => "blender", price => 30u64, rating => 4.8))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let sort_by = SortBy::new(
+///     vec![
+///         SortKey::field("price", Order::Asc),
+///         SortKey::field("rating", Order::Desc),
+///         SortKey::field("name", Order::Asc),
+///     ],
+///     2,
+/// );
+/// let top_docs = searcher.search(&AllQuery, &sort_by)?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].0[2], Some(SortValue::Str("toaster".to_string())));
+/// assert_eq!(
+///     top_docs[1].0,
+///     vec![
+///         Some(SortValue::U64(30)),
+///         Some(SortValue::F64(4.8)),
+///         Some(SortValue::Str("blender".to_string())),
+///     ]
+/// );
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SortBy {
+    sort_keys: Vec<SortKey>,
+    limit: usize,
+    offset: usize,
+    cursor_opt: Option<Cursor>,
+}
+
+impl SortBy {
+    /// Creates a collector returning the top `limit` documents sorted by `sort_keys`.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0 or if there are no sort keys.
+    pub fn new(sort_keys: Vec<SortKey>, limit: usize) -> SortBy {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        assert!(!sort_keys.is_empty(), "At least one sort key is required.");
+        SortBy {
+            sort_keys,
+            limit,
+            offset: 0,
+            cursor_opt: None,
+        }
+    }
+
+    /// Skip the first "offset" documents when collecting.
+    #[must_use]
+    pub fn and_offset(mut self, offset: usize) -> SortBy {
+        self.offset = offset;
+        self
+    }
+
+    /// Only collects the documents sorted after a [`Cursor`], typically the
+    /// [`next_cursor`](Page::next_cursor) of the previous page, or from the first document if
+    /// `cursor_opt` is `None`.
+    ///
+    /// Unlike [`SortBy::and_offset`], the cost of collecting a page does not depend on its
+    /// depth.
+    ///
+    /// ```rust
+    /// use tantivy::collector::{SortBy, SortKey};
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{Schema, FAST};
+    /// use tantivy::{doc, Index, Order};
+    ///
+    /// # fn test() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let price = schema_builder.add_u64_field("price", FAST);
+    /// let schema = schema_builder.build();
+    /// let index = Index::create_in_ram(schema);
+    /// let mut index_writer = index.writer(15_000_000)?;
+    /// for val in 0..25u64 {
+    ///     index_writer.add_document(doc!(price => val % 10))?;
+    /// }
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let mut cursor_opt = None;
+    /// let mut num_docs = 0;
+    /// loop {
+    ///     let sort_by = SortBy::new(vec![SortKey::field("price", Order::Asc)], 10);
+    ///     let page = searcher.search(&AllQuery, &sort_by.search_after(cursor_opt))?;
+    ///     num_docs += page.docs.len();
+    ///     cursor_opt = page.next_cursor;
+    ///     if cursor_opt.is_none() {
+    ///         break;
+    ///     }
+    /// }
+    /// assert_eq!(num_docs, 25);
+    /// # Ok(())
+    /// # }
+    /// # assert!(test().is_ok());
+    /// ```
+    pub fn search_after(
+        mut self,
+        cursor_opt: Option<Cursor>,
+    ) -> impl Collector<Fruit = Page<Vec<Option<SortValue>>>> {
+        self.cursor_opt = cursor_opt;
+        SortByPage(self)
+    }
+
+    fn compare(
+        &self,
+        left: &(Vec<Option<SortValue>>, DocAddress),
+        right: &(Vec<Option<SortValue>>, DocAddress),
+    ) -> Ordering {
+        self.sort_keys
+            .iter()
+            .zip(left.0.iter().zip(right.0.iter()))
+            .map(|(sort_key, (left, right))| sort_key.compare(left.as_ref(), right.as_ref()))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then(left.1.cmp(&right.1))
+    }
+}
+
+impl Collector for SortBy {
+    type Fruit = Vec<(Vec<Option<SortValue>>, DocAddress)>;
+
+    type Child = SortBySegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<SortBySegmentCollector> {
+        let columns = self
+            .sort_keys
+            .iter()
+            .map(|sort_key| SortColumn::open(&sort_key.target, segment_reader))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let segment_cursor_opt = if let Some(cursor) = self.cursor_opt.as_ref() {
+            Some(SegmentCursor::new(cursor, &columns, segment_local_id)?)
+        } else {
+            None
+        };
+        Ok(SortBySegmentCollector {
+            sort_keys: self.sort_keys.clone(),
+            columns,
+            segment_cursor_opt,
+            heap: BinaryHeap::new(),
+            limit: self.limit + self.offset,
+            segment_ord: segment_local_id,
+            values: Vec::with_capacity(self.sort_keys.len()),
+            buffer: Vec::with_capacity(self.sort_keys.len()),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.sort_keys
+            .iter()
+            .any(|sort_key| sort_key.target == SortTarget::Score)
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<crate::Result<Self::Fruit>>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut docs = Vec::new();
+        for segment_fruit in segment_fruits {
+            docs.extend(segment_fruit?);
+        }
+        docs.sort_by(|left, right| self.compare(left, right));
+        Ok(docs
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .collect())
+    }
+}
+
+/// Collector returned by [`SortBy::search_after`].
+struct SortByPage(SortBy);
+
+impl Collector for SortByPage {
+    type Fruit = Page<Vec<Option<SortValue>>>;
+
+    type Child = SortBySegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<SortBySegmentCollector> {
+        self.0.for_segment(segment_local_id, segment_reader)
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.0.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<crate::Result<Vec<(Vec<Option<SortValue>>, DocAddress)>>>,
+    ) -> crate::Result<Self::Fruit> {
+        let docs = self.0.merge_fruits(segment_fruits)?;
+        Ok(Page::new(docs, self.0.limit, |sort_values, doc_address| {
+            Cursor::new(sort_values.clone(), doc_address)
+        }))
+    }
+}
+
+/// The values of a [`SortKey`] in a segment, represented by `u128` that sort in the same
+/// order as the values.
+enum SortColumn {
+    Numerical {
+        column: Column<u64>,
+        to_sort_value: fn(u64) -> SortValue,
+    },
+    IpAddr(Column<Ipv6Addr>),
+    /// Values are represented by their term ordinal.
+    Str(StrColumn),
+    /// No document of the segment has a value.
+    Empty,
+    Score,
+    DocId,
+}
+
+impl SortColumn {
+    fn open(target: &SortTarget, segment_reader: &SegmentReader) -> crate::Result<SortColumn> {
+        let field_name = match target {
+            SortTarget::Field(field_name) => field_name,
+            SortTarget::Score => return Ok(SortColumn::Score),
+            SortTarget::DocId => return Ok(SortColumn::DocId),
+        };
+        let schema = segment_reader.schema();
+        let field = schema
+            .get_field(field_name)
+            .map_err(|_| TantivyError::FieldNotFound(field_name.clone()))?;
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {field_name:?} is not a fast field."
+            )));
+        }
+        let fast_fields = segment_reader.fast_fields();
+        let (column_type, to_sort_value): (ColumnType, fn(u64) -> SortValue) = match field_entry
+            .field_type()
+        {
+            FieldType::U64(_) => (ColumnType::U64, SortValue::U64),
+            FieldType::I64(_) => (ColumnType::I64, |val| SortValue::I64(i64::from_u64(val))),
+            FieldType::F64(_) => (ColumnType::F64, |val| SortValue::F64(f64::from_u64(val))),
+            FieldType::Bool(_) => (ColumnType::Bool, |val| SortValue::Bool(bool::from_u64(val))),
+            FieldType::Date(_) => (ColumnType::DateTime, |val| {
+                SortValue::Date(DateTime::from_u64(val))
+            }),
+            FieldType::IpAddr(_) => {
+                let column_opt = fast_fields.column_opt::<Ipv6Addr>(field_name)?;
+                return Ok(column_opt
+                    .map(SortColumn::IpAddr)
+                    .unwrap_or(SortColumn::Empty));
+            }
+            FieldType::Str(_) => {
+                let column_opt = fast_fields.str(field_name)?;
+                return Ok(column_opt.map(SortColumn::Str).unwrap_or(SortColumn::Empty));
+            }
+            _ => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Documents can only be sorted by a numerical, date, bool, ip or str field, \
+                     {field_name:?} is not one."
+                )));
+            }
+        };
+        let column_opt = fast_fields.u64_lenient_for_type(Some(&[column_type]), field_name)?;
+        Ok(column_opt
+            .map(|(column, _)| SortColumn::Numerical {
+                column,
+                to_sort_value,
+            })
+            .unwrap_or(SortColumn::Empty))
+    }
+
+    fn value(&self, doc: DocId, score: Score, order: &Order) -> Option<u128> {
+        fn pick<T: Ord>(values: impl Iterator<Item = T>, order: &Order) -> Option<T> {
+            if order.is_asc() {
+                values.min()
+            } else {
+                values.max()
+            }
+        }
+        match self {
+            SortColumn::Numerical { column, .. } => {
+                pick(column.values_for_doc(doc), order).map(u128::from)
+            }
+            SortColumn::IpAddr(column) => pick(column.values_for_doc(doc), order).map(u128::from),
+            SortColumn::Str(column) => pick(column.term_ords(doc), order).map(u128::from),
+            SortColumn::Empty => None,
+            SortColumn::Score => Some(u128::from((score as f64).to_u64())),
+            SortColumn::DocId => Some(u128::from(doc)),
+        }
+    }
+
+    fn sort_value(&self, value: u128, segment_ord: SegmentOrdinal) -> crate::Result<SortValue> {
+        let sort_value = match self {
+            SortColumn::Numerical { to_sort_value, .. } => to_sort_value(value as u64),
+            SortColumn::IpAddr(_) => SortValue::IpAddr(Ipv6Addr::from(value)),
+            SortColumn::Str(column) => {
+                let mut term = String::new();
+                column.ord_to_str(value as u64, &mut term)?;
+                SortValue::Str(term)
+            }
+            SortColumn::Empty => {
+                return Err(TantivyError::InternalError(
+                    "A segment without any value cannot have a sort value.".to_string(),
+                ));
+            }
+            SortColumn::Score => SortValue::Score(f64::from_u64(value as u64) as Score),
+            SortColumn::DocId => SortValue::Doc(DocAddress::new(segment_ord, value as DocId)),
+        };
+        Ok(sort_value)
+    }
+
+    /// Returns the position of a sort value among the values of the segment.
+    ///
+    /// The position is `(value, true)` if the sort value is represented by `value` in the
+    /// segment, and `(value, false)` if it falls right before `value`.
+    /// Returns `None` if the sort value does not have the type of the column.
+    fn position(
+        &self,
+        sort_value: &SortValue,
+        segment_ord: SegmentOrdinal,
+    ) -> crate::Result<Option<(u128, bool)>> {
+        let position = match (self, sort_value) {
+            (SortColumn::Numerical { to_sort_value, .. }, sort_value) => {
+                // The cursor value must have the type of the column.
+                if mem::discriminant(&to_sort_value(0)) != mem::discriminant(sort_value) {
+                    return Ok(None);
+                }
+                let val = match sort_value {
+                    SortValue::U64(val) => *val,
+                    SortValue::I64(val) => val.to_u64(),
+                    SortValue::F64(val) => val.to_u64(),
+                    SortValue::Bool(val) => val.to_u64(),
+                    SortValue::Date(val) => val.to_u64(),
+                    _ => return Ok(None),
+                };
+                (u128::from(val), true)
+            }
+            (SortColumn::IpAddr(_), SortValue::IpAddr(ip_addr)) => (u128::from(*ip_addr), true),
+            (SortColumn::Str(column), SortValue::Str(term)) => {
+                let dictionary = column.dictionary();
+                if let Some(term_ord) = dictionary.term_ord(term)? {
+                    (u128::from(term_ord), true)
+                } else {
+                    let mut stream = dictionary.range().gt(term).into_stream()?;
+                    let next_term_ord = if stream.advance() {
+                        stream.term_ord()
+                    } else {
+                        dictionary.num_terms() as u64
+                    };
+                    (u128::from(next_term_ord), false)
+                }
+            }
+            (SortColumn::Empty, _) => (0, true),
+            (SortColumn::Score, SortValue::Score(score)) => {
+                (u128::from((*score as f64).to_u64()), true)
+            }
+            (SortColumn::DocId, SortValue::Doc(doc_address)) => {
+                match doc_address.segment_ord.cmp(&segment_ord) {
+                    Ordering::Less => (0, false),
+                    Ordering::Equal => (u128::from(doc_address.doc_id), true),
+                    Ordering::Greater => (u128::MAX, false),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(position))
+    }
+}
+
+/// A [`Cursor`], expressed with the values of a segment.
+struct SegmentCursor {
+    positions: Vec<Option<(u128, bool)>>,
+    doc_address: DocAddress,
+}
+
+impl SegmentCursor {
+    fn new(
+        cursor: &Cursor,
+        columns: &[SortColumn],
+        segment_ord: SegmentOrdinal,
+    ) -> crate::Result<SegmentCursor> {
+        let invalid_cursor = || {
+            TantivyError::InvalidArgument("The cursor does not match the sort keys.".to_string())
+        };
+        if cursor.sort_values().len() != columns.len() {
+            return Err(invalid_cursor());
+        }
+        let mut positions = Vec::with_capacity(columns.len());
+        for (column, sort_value_opt) in columns.iter().zip(cursor.sort_values()) {
+            let position_opt = match sort_value_opt {
+                Some(sort_value) => Some(
+                    column
+                        .position(sort_value, segment_ord)?
+                        .ok_or_else(invalid_cursor)?,
+                ),
+                None => None,
+            };
+            positions.push(position_opt);
+        }
+        Ok(SegmentCursor {
+            positions,
+            doc_address: cursor.doc_address(),
+        })
+    }
+
+    /// Returns true if the cursor comes before a document.
+    fn precedes(
+        &self,
+        sort_keys: &[SortKey],
+        values: &[Option<u128>],
+        doc_address: DocAddress,
+    ) -> bool {
+        sort_keys
+            .iter()
+            .zip(values.iter().zip(&self.positions))
+            .map(|(sort_key, (value_opt, position_opt))| {
+                let doc_position_opt = value_opt.map(|value| (value, true));
+                sort_key.compare(doc_position_opt.as_ref(), position_opt.as_ref())
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then(doc_address.cmp(&self.doc_address))
+            .is_gt()
+    }
+}
+
+/// A document with its encoded sort values, ordered from the best to the worst document.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct SortedDoc {
+    sort_key: Vec<(bool, u128)>,
+    doc: DocId,
+}
+
+/// Segment collector of the [`SortBy`] collector.
+pub struct SortBySegmentCollector {
+    sort_keys: Vec<SortKey>,
+    columns: Vec<SortColumn>,
+    segment_cursor_opt: Option<SegmentCursor>,
+    /// The root of the heap is the worst of the collected documents.
+    heap: BinaryHeap<SortedDoc>,
+    limit: usize,
+    segment_ord: SegmentOrdinal,
+    values: Vec<Option<u128>>,
+    buffer: Vec<(bool, u128)>,
+}
+
+impl SegmentCollector for SortBySegmentCollector {
+    type Fruit = crate::Result<Vec<(Vec<Option<SortValue>>, DocAddress)>>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        self.values.clear();
+        for (sort_key, column) in self.sort_keys.iter().zip(&self.columns) {
+            self.values.push(column.value(doc, score, &sort_key.order));
+        }
+        if let Some(segment_cursor) = self.segment_cursor_opt.as_ref() {
+            let doc_address = DocAddress::new(self.segment_ord, doc);
+            if !segment_cursor.precedes(&self.sort_keys, &self.values, doc_address) {
+                return;
+            }
+        }
+        self.buffer.clear();
+        for (sort_key, value_opt) in self.sort_keys.iter().zip(&self.values) {
+            self.buffer.push(sort_key.encode(*value_opt));
+        }
+        if self.heap.len() < self.limit {
+            self.heap.push(SortedDoc {
+                sort_key: self.buffer.clone(),
+                doc,
+            });
+        } else if let Some(mut head) = self.heap.peek_mut() {
+            // Documents are collected by increasing doc id, so on a tie, the document already
+            // collected comes first.
+            if self.buffer < head.sort_key {
+                head.sort_key.clone_from(&self.buffer);
+                head.doc = doc;
+            }
+        }
+    }
+
+    fn harvest(self) -> crate::Result<Vec<(Vec<Option<SortValue>>, DocAddress)>> {
+        let mut docs = Vec::with_capacity(self.heap.len());
+        for sorted_doc in self.heap.into_sorted_vec() {
+            let mut sort_values = Vec::with_capacity(self.sort_keys.len());
+            for ((sort_key, column), encoded) in self
+                .sort_keys
+                .iter()
+                .zip(&self.columns)
+                .zip(sorted_doc.sort_key)
+            {
+                let sort_value = match sort_key.decode(encoded) {
+                    Some(value) => Some(column.sort_value(value, self.segment_ord)?),
+                    None => None,
+                };
+                sort_values.push(sort_value);
+            }
+            docs.push((
+                sort_values,
+                DocAddress::new(self.segment_ord, sorted_doc.doc),
+            ));
+        }
+        Ok(docs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use super::{MissingOrder, SortBy, SortKey, SortValue};
+    use crate::collector::Cursor;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{AllQuery, QueryParser};
+    use crate::schema::{Schema, FAST, INDEXED, STORED, STRING, TEXT};
+    use crate::{DateTime, DocAddress, Index, Order, Searcher};
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let name = schema_builder.add_text_field("name", STRING | FAST | STORED);
+        let description = schema_builder.add_text_field("description", TEXT);
+        let price = schema_builder.add_u64_field("price", FAST);
+        let rating = schema_builder.add_f64_field("rating", FAST);
+        let stock = schema_builder.add_i64_field("stock", FAST);
+        let available = schema_builder.add_bool_field("available", FAST);
+        let release = schema_builder.add_date_field("release", FAST);
+        let ip = schema_builder.add_ip_addr_field("ip", FAST);
+        schema_builder.add_u64_field("not_fast", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        let date = DateTime::from_timestamp_secs;
+        let ip_addr = |val: u16| Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, val);
+        index_writer.add_document(doc!(
+            name => "kettle", description => "red kettle", price => 30u64, rating => 4.5,
+            stock => -2i64, available => true, release => date(300), ip => ip_addr(3)
+        ))?;
+        index_writer.add_document(doc!(
+            name => "toaster", description => "red red toaster", price => 25u64, rating => 4.0,
+            stock => 5i64, available => false, release => date(100), ip => ip_addr(1)
+        ))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(
+            name => "blender", description => "red blender", price => 30u64, rating => 4.8,
+            stock => 0i64, available => true, release => date(200), ip => ip_addr(2)
+        ))?;
+        index_writer.add_document(doc!(
+            name => "mixer", description => "red red mixer", price => 30u64, rating => 4.5
+        ))?;
+        index_writer.add_document(doc!(name => "grill", description => "grill"))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn names(
+        searcher: &Searcher,
+        top_docs: &[(Vec<Option<SortValue>>, DocAddress)],
+    ) -> Vec<String> {
+        let name = searcher.schema().get_field("name").unwrap();
+        top_docs
+            .iter()
+            .map(|(_, doc_address)| {
+                let doc = searcher.doc(*doc_address).unwrap();
+                doc.get_first(name).unwrap().as_text().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_by_several_keys() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let description = index.schema().get_field("description").unwrap();
+        let query = QueryParser::for_index(&index, vec![description]).parse_query("red")?;
+        let sort_by = SortBy::new(
+            vec![
+                SortKey::field("price", Order::Asc),
+                SortKey::field("rating", Order::Desc),
+                SortKey::score(Order::Desc),
+            ],
+            10,
+        );
+        let top_docs = searcher.search(&query, &sort_by)?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["toaster", "blender", "mixer", "kettle"]
+        );
+        // "mixer" and "kettle" have the same price and rating, but "red" appears twice in
+        // the description of "mixer".
+        match (&top_docs[2].0[2], &top_docs[3].0[2]) {
+            (Some(SortValue::Score(left)), Some(SortValue::Score(right))) => {
+                assert!(left > right)
+            }
+            _ => panic!("Expected scores"),
+        }
+        let top_docs = searcher.search(&query, &SortBy::new(sort_by.sort_keys, 2).and_offset(1))?;
+        assert_eq!(names(&searcher, &top_docs), vec!["blender", "mixer"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_str_across_segments() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let top_docs = searcher.search(
+            &AllQuery,
+            &SortBy::new(vec![SortKey::field("name", Order::Asc)], 10),
+        )?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["blender", "grill", "kettle", "mixer", "toaster"]
+        );
+        assert_eq!(
+            top_docs[0].0,
+            vec![Some(SortValue::Str("blender".to_string()))]
+        );
+        let top_docs = searcher.search(
+            &AllQuery,
+            &SortBy::new(vec![SortKey::field("name", Order::Desc)], 2),
+        )?;
+        assert_eq!(names(&searcher, &top_docs), vec!["toaster", "mixer"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_missing() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let sort_by = |order: Order, missing: MissingOrder| {
+            SortBy::new(
+                vec![
+                    SortKey::field("stock", order).with_missing(missing),
+                    SortKey::field("name", Order::Asc),
+                ],
+                10,
+            )
+        };
+        let top_docs = searcher.search(&AllQuery, &sort_by(Order::Asc, MissingOrder::Last))?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["kettle", "blender", "toaster", "grill", "mixer"]
+        );
+        assert_eq!(top_docs[0].0[0], Some(SortValue::I64(-2)));
+        assert_eq!(top_docs[4].0[0], None);
+        let top_docs = searcher.search(&AllQuery, &sort_by(Order::Desc, MissingOrder::Last))?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["toaster", "blender", "kettle", "grill", "mixer"]
+        );
+        let top_docs = searcher.search(&AllQuery, &sort_by(Order::Asc, MissingOrder::First))?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["grill", "mixer", "kettle", "blender", "toaster"]
+        );
+        let top_docs = searcher.search(&AllQuery, &sort_by(Order::Desc, MissingOrder::First))?;
+        assert_eq!(
+            names(&searcher, &top_docs),
+            vec!["grill", "mixer", "toaster", "blender", "kettle"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_value_types() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let sort = |sort_key: SortKey| {
+            let sort_keys = vec![sort_key, SortKey::field("name", Order::Asc)];
+            let top_docs = searcher.search(&AllQuery, &SortBy::new(sort_keys, 3))?;
+            let values: Vec<Option<SortValue>> = top_docs
+                .iter()
+                .map(|(sort_values, _)| sort_values[0].clone())
+                .collect();
+            crate::Result::Ok((names(&searcher, &top_docs), values))
+        };
+        let (top_names, values) = sort(SortKey::field("available", Order::Asc))?;
+        assert_eq!(top_names, vec!["toaster", "blender", "kettle"]);
+        assert_eq!(values[0], Some(SortValue::Bool(false)));
+        let (top_names, values) = sort(SortKey::field("release", Order::Desc))?;
+        assert_eq!(top_names, vec!["kettle", "blender", "toaster"]);
+        assert_eq!(
+            values[0],
+            Some(SortValue::Date(DateTime::from_timestamp_secs(300)))
+        );
+        let (top_names, values) = sort(SortKey::field("ip", Order::Asc))?;
+        assert_eq!(top_names, vec!["toaster", "blender", "kettle"]);
+        assert_eq!(
+            values[0],
+            Some(SortValue::IpAddr(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)))
+        );
+        let (_, values) = sort(SortKey::doc_id(Order::Desc))?;
+        let mut expected: Vec<DocAddress> = Vec::new();
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            for doc_id in 0..segment_reader.max_doc() {
+                expected.push(DocAddress::new(segment_ord as u32, doc_id));
+            }
+        }
+        expected.reverse();
+        expected.truncate(3);
+        assert_eq!(
+            values,
+            expected
+                .into_iter()
+                .map(|doc_address| Some(SortValue::Doc(doc_address)))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_search_after() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let sort_keys_list = vec![
+            vec![SortKey::field("name", Order::Desc)],
+            vec![
+                SortKey::field("price", Order::Asc).with_missing(MissingOrder::First),
+                SortKey::field("name", Order::Asc),
+            ],
+            vec![
+                SortKey::field("stock", Order::Desc),
+                SortKey::doc_id(Order::Desc),
+            ],
+            vec![
+                SortKey::field("ip", Order::Asc),
+                SortKey::score(Order::Desc),
+            ],
+        ];
+        for sort_keys in sort_keys_list {
+            let all_docs = searcher.search(&AllQuery, &SortBy::new(sort_keys.clone(), 10))?;
+            let mut paginated_docs = Vec::new();
+            let mut cursor_opt = None;
+            loop {
+                let sort_by = SortBy::new(sort_keys.clone(), 2).search_after(cursor_opt);
+                let page = searcher.search(&AllQuery, &sort_by)?;
+                paginated_docs.extend(page.docs);
+                cursor_opt = page.next_cursor;
+                if cursor_opt.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(paginated_docs, all_docs);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_search_after_term_not_in_segment() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let sort_keys = vec![SortKey::field("name", Order::Asc)];
+        let cursor = Cursor::new(
+            vec![Some(SortValue::Str("hammer".to_string()))],
+            DocAddress::new(0, 0),
+        );
+        let page = searcher.search(
+            &AllQuery,
+            &SortBy::new(sort_keys.clone(), 10).search_after(Some(cursor)),
+        )?;
+        assert_eq!(
+            names(&searcher, &page.docs),
+            vec!["kettle", "mixer", "toaster"]
+        );
+        assert!(page.next_cursor.is_none());
+        let cursor = Cursor::new(vec![Some(SortValue::U64(3))], DocAddress::new(0, 0));
+        assert!(searcher
+            .search(
+                &AllQuery,
+                &SortBy::new(sort_keys, 10).search_after(Some(cursor))
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_unsupported_field() -> crate::Result<()> {
+        let index = create_index()?;
+        let searcher = index.reader()?.searcher();
+        for field_name in ["description", "not_fast", "unknown"] {
+            let sort_by = SortBy::new(vec![SortKey::field(field_name, Order::Asc)], 1);
+            assert!(searcher.search(&AllQuery, &sort_by).is_err());
+        }
+        Ok(())
+    }
+}
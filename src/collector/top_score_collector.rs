@@ -3,17 +3,22 @@ use std::fmt;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use columnar::ColumnValues;
+use columnar::{Column, ColumnValues};
 
 use super::Collector;
 use crate::collector::custom_score_top_collector::CustomScoreTopCollector;
+use crate::collector::rescorer::RescoreTopCollector;
+use crate::collector::search_after::SearchAfterTopCollector;
 use crate::collector::top_collector::{ComparableDoc, TopCollector, TopSegmentCollector};
+use crate::collector::total_hits::TotalHitsTopCollector;
 use crate::collector::tweak_score_top_collector::TweakedScoreTopCollector;
 use crate::collector::{
-    CustomScorer, CustomSegmentScorer, ScoreSegmentTweaker, ScoreTweaker, SegmentCollector,
+    Cursor, CustomScorer, CustomSegmentScorer, Page, Rescorer, ScoreSegmentTweaker, ScoreTweaker,
+    SegmentCollector, TotalHits,
 };
 use crate::fastfield::{FastFieldNotAvailableError, FastValue};
 use crate::query::Weight;
+use crate::schema::{FieldType, GeoPoint};
 use crate::{DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
 struct FastFieldConvertCollector<
@@ -83,6 +88,59 @@ where
     }
 }
 
+/// Converts the negated distances used to rank documents by geo distance back into
+/// distances.
+struct GeoDistanceConvertCollector<TCollector: Collector<Fruit = Vec<(f64, DocAddress)>>> {
+    collector: TCollector,
+    field: String,
+}
+
+impl<TCollector> Collector for GeoDistanceConvertCollector<TCollector>
+where TCollector: Collector<Fruit = Vec<(f64, DocAddress)>>
+{
+    type Fruit = Vec<(f64, DocAddress)>;
+
+    type Child = TCollector::Child;
+
+    fn for_segment(
+        &self,
+        segment_local_id: crate::SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let schema = segment.schema();
+        let field = schema.get_field(&self.field)?;
+        let field_entry = schema.get_field_entry(field);
+        if !matches!(field_entry.field_type(), FieldType::GeoPoint(_)) {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a geo point field.",
+                field_entry.name()
+            )));
+        }
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field.",
+                field_entry.name()
+            )));
+        }
+        self.collector.for_segment(segment_local_id, segment)
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> crate::Result<Self::Fruit> {
+        let raw_result = self.collector.merge_fruits(segment_fruits)?;
+        Ok(raw_result
+            .into_iter()
+            .map(|(negated_distance, doc_address)| (-negated_distance, doc_address))
+            .collect())
+    }
+}
+
 /// The `TopDocs` collector keeps track of the top `K` documents
 /// sorted by their score.
 ///
@@ -182,6 +240,42 @@ impl CustomScorer<u64> for ScorerByField {
     }
 }
 
+struct GeoDistanceSegmentScorer {
+    column_opt: Option<Column<u64>>,
+    origin: GeoPoint,
+}
+
+impl CustomSegmentScorer<f64> for GeoDistanceSegmentScorer {
+    fn score(&mut self, doc: DocId) -> f64 {
+        // The closest point ranks first, documents without any point rank last.
+        let min_distance = self.column_opt.as_ref().and_then(|column| {
+            column
+                .values_for_doc(doc)
+                .map(|val| self.origin.distance(&GeoPoint::from_u64(val)))
+                .min_by(f64::total_cmp)
+        });
+        -min_distance.unwrap_or(f64::INFINITY)
+    }
+}
+
+struct ScorerByGeoDistance {
+    field: String,
+    origin: GeoPoint,
+}
+
+impl CustomScorer<f64> for ScorerByGeoDistance {
+    type Child = GeoDistanceSegmentScorer;
+
+    fn segment_scorer(&self, segment_reader: &SegmentReader) -> crate::Result<Self::Child> {
+        Ok(GeoDistanceSegmentScorer {
+            column_opt: segment_reader
+                .fast_fields()
+                .column_opt::<u64>(&self.field)?,
+            origin: self.origin,
+        })
+    }
+}
+
 impl TopDocs {
     /// Creates a top score collector, with a number of documents equal to "limit".
     ///
@@ -407,6 +501,53 @@ impl TopDocs {
         }
     }
 
+    /// Set top-K to rank documents by their distance to `origin`, closest first.
+    ///
+    /// `geo_point_field` must be a fast geo point field. Distances are expressed in meters,
+    /// and computed with the haversine formula. If a document has several points, its
+    /// closest point is used. Documents without any point are ranked last, with an
+    /// infinite distance.
+    ///
+    /// ```rust
+    /// use tantivy::collector::TopDocs;
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{GeoPoint, Schema, FAST};
+    /// use tantivy::{doc, DocAddress, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let location = schema_builder.add_geo_point_field("location", FAST);
+    /// let schema = schema_builder.build();
+    /// let index = Index::create_in_ram(schema);
+    /// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+    /// index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+    /// index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let versailles = GeoPoint::new(48.8049, 2.1204);
+    /// let top_docs = TopDocs::with_limit(2).order_by_geo_distance("location", versailles);
+    /// let closest_docs: Vec<(f64, DocAddress)> = searcher.search(&AllQuery, &top_docs)?;
+    /// assert_eq!(closest_docs[0].1, DocAddress::new(0, 1));
+    /// assert!(closest_docs[0].0 < 20_000.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn order_by_geo_distance(
+        self,
+        geo_point_field: impl ToString,
+        origin: GeoPoint,
+    ) -> impl Collector<Fruit = Vec<(f64, DocAddress)>> {
+        let scorer_by_geo_distance = ScorerByGeoDistance {
+            field: geo_point_field.to_string(),
+            origin,
+        };
+        GeoDistanceConvertCollector {
+            collector: self.custom_score(scorer_by_geo_distance),
+            field: geo_point_field.to_string(),
+        }
+    }
+
     /// Ranks the documents using a custom score.
     ///
     /// This method offers a convenient way to tweak or replace
@@ -627,6 +768,114 @@ impl TopDocs {
     {
         CustomScoreTopCollector::new(custom_score, self.0.into_tscore())
     }
+
+    /// Re-scores the top documents of each segment with a [`Rescorer`].
+    ///
+    /// The documents are first ranked by the score of the query being searched. The top
+    /// [`window_size`](Rescorer::window_size) documents of each segment, and at least as many
+    /// documents as this collector returns, are then re-scored by the rescore query.
+    ///
+    /// The returned documents are sorted by their final score. See [`Rescorer`] for an example.
+    pub fn rescore(self, rescorer: Rescorer) -> impl Collector<Fruit = Vec<(Score, DocAddress)>> {
+        RescoreTopCollector::new(rescorer, self.0)
+    }
+
+    /// Only collects the documents ranked after a [`Cursor`], typically the
+    /// [`next_cursor`](Page::next_cursor) of the previous page, or from the top document if
+    /// `cursor_opt` is `None`.
+    ///
+    /// Unlike [`TopDocs::and_offset`], the cost of collecting a page does not depend on its
+    /// depth.
+    ///
+    /// Only the ranking by score is supported. The scores of
+    /// [`tweak_score`](TopDocs::tweak_score) and [`custom_score`](TopDocs::custom_score) are of
+    /// any type, which a [`Cursor`] can't hold, and the documents ranked by
+    /// [`order_by_fast_field`](TopDocs::order_by_fast_field) can be paginated with
+    /// [`SortBy::search_after`](super::SortBy::search_after).
+    ///
+    /// ```rust
+    /// use tantivy::collector::{Cursor, TopDocs};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn test() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let schema = schema_builder.build();
+    /// let index = Index::create_in_ram(schema);
+    /// let mut index_writer = index.writer(15_000_000)?;
+    /// for _ in 0..25 {
+    ///     index_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+    /// }
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("sea")?;
+    /// let first_page = searcher.search(&query, &TopDocs::with_limit(10).search_after(None))?;
+    /// assert_eq!(first_page.docs.len(), 10);
+    ///
+    /// // The cursor can be sent to the client as a token, to fetch the next page.
+    /// let token = first_page.next_cursor.unwrap().to_token();
+    /// let cursor = Cursor::from_token(&token)?;
+    /// let second_page =
+    ///     searcher.search(&query, &TopDocs::with_limit(10).search_after(Some(cursor)))?;
+    /// assert_eq!(second_page.docs.len(), 10);
+    /// assert!(second_page.docs.iter().all(|doc| !first_page.docs.contains(doc)));
+    /// # Ok(())
+    /// # }
+    /// # assert!(test().is_ok());
+    /// ```
+    pub fn search_after(self, cursor_opt: Option<Cursor>) -> impl Collector<Fruit = Page<Score>> {
+        SearchAfterTopCollector::new(self.0, cursor_opt)
+    }
+
+    /// Also counts the documents matching the query, up to `total_hits_threshold`.
+    ///
+    /// Combining `TopDocs` with [`Count`](super::Count) disables the pruning of the documents
+    /// that cannot make it to the top documents. Here, documents are only pruned once more than
+    /// `total_hits_threshold` documents have been counted: the count is exact if at most
+    /// `total_hits_threshold` documents match, and it is
+    /// [`TotalHits::GreaterThanOrEqual(total_hits_threshold)`](TotalHits::GreaterThanOrEqual)
+    /// otherwise.
+    ///
+    /// ```rust
+    /// use tantivy::collector::{TopDocs, TotalHits};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn test() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let schema = schema_builder.build();
+    /// let index = Index::create_in_ram(schema);
+    /// let mut index_writer = index.writer(15_000_000)?;
+    /// for _ in 0..25 {
+    ///     index_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+    /// }
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("sea")?;
+    /// let (top_docs, total_hits) =
+    ///     searcher.search(&query, &TopDocs::with_limit(10).track_total_hits_up_to(100))?;
+    /// assert_eq!(top_docs.len(), 10);
+    /// assert_eq!(total_hits, TotalHits::Exact(25));
+    ///
+    /// let (_, total_hits) =
+    ///     searcher.search(&query, &TopDocs::with_limit(10).track_total_hits_up_to(20))?;
+    /// assert_eq!(total_hits, TotalHits::GreaterThanOrEqual(20));
+    /// # Ok(())
+    /// # }
+    /// # assert!(test().is_ok());
+    /// ```
+    pub fn track_total_hits_up_to(
+        self,
+        total_hits_threshold: usize,
+    ) -> impl Collector<Fruit = (Vec<(Score, DocAddress)>, TotalHits)> {
+        TotalHitsTopCollector::new(self.0, total_hits_threshold)
+    }
 }
 
 impl Collector for TopDocs {
@@ -741,7 +990,7 @@ mod tests {
     use super::TopDocs;
     use crate::collector::Collector;
     use crate::query::{AllQuery, Query, QueryParser};
-    use crate::schema::{Field, Schema, FAST, STORED, TEXT};
+    use crate::schema::{Field, GeoPoint, Schema, FAST, STORED, TEXT};
     use crate::time::format_description::well_known::Rfc3339;
     use crate::time::OffsetDateTime;
     use crate::{DateTime, DocAddress, DocId, Index, IndexWriter, Order, Score, SegmentReader};
@@ -1016,6 +1265,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_top_field_collector_geo_distance() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let city = schema_builder.add_text_field("city", TEXT | STORED);
+        let location = schema_builder.add_geo_point_field("location", FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            city => "london",
+            location => GeoPoint::new(51.5074, -0.1278),
+        ))?;
+        index_writer.add_document(doc!(city => "nowhere"))?;
+        // The documents are sorted across two segments.
+        index_writer.commit()?;
+        index_writer.add_document(doc!(
+            city => "paris",
+            location => GeoPoint::new(48.8566, 2.3522),
+        ))?;
+        index_writer.add_document(doc!(
+            city => "berlin and tokyo",
+            location => GeoPoint::new(35.6762, 139.6503),
+            location => GeoPoint::new(52.5200, 13.4050),
+        ))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let origin = GeoPoint::new(48.8566, 2.3522);
+        let top_collector = TopDocs::with_limit(4).order_by_geo_distance("location", origin);
+        let top_docs: Vec<(f64, DocAddress)> = searcher.search(&AllQuery, &top_collector)?;
+        let distances: Vec<f64> = top_docs.iter().map(|(distance, _)| *distance).collect();
+        assert!(distances[0] < 0.01);
+        assert!((distances[1] - 343_500.0).abs() < 1_000.0);
+        assert!((distances[2] - 878_000.0).abs() < 1_000.0);
+        assert_eq!(distances[3], f64::INFINITY);
+        let cities: Vec<String> = top_docs
+            .iter()
+            .map(|(_, doc_address)| {
+                let doc = searcher.doc(*doc_address)?;
+                Ok(doc.get_first(city).unwrap().as_text().unwrap().to_string())
+            })
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(cities, ["paris", "london", "berlin and tokyo", "nowhere"]);
+
+        let top_collector = TopDocs::with_limit(4).order_by_geo_distance("city", origin);
+        assert!(searcher.search(&AllQuery, &top_collector).is_err());
+        Ok(())
+    }
+
     #[test]
     #[should_panic]
     fn test_field_does_not_exist() {
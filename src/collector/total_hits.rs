@@ -0,0 +1,228 @@
+use std::collections::BinaryHeap;
+
+use crate::collector::top_collector::{ComparableDoc, TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector};
+use crate::query::Weight;
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+
+/// Number of documents matching a query, as counted by
+/// [`TopDocs::track_total_hits_up_to`](super::TopDocs::track_total_hits_up_to).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TotalHits {
+    /// Exactly this number of documents match the query.
+    Exact(usize),
+    /// At least this number of documents match the query.
+    GreaterThanOrEqual(usize),
+}
+
+impl TotalHits {
+    /// Returns the number of matching documents, or its lower bound.
+    pub fn value(&self) -> usize {
+        match *self {
+            TotalHits::Exact(value) | TotalHits::GreaterThanOrEqual(value) => value,
+        }
+    }
+
+    /// Returns true if the number of matching documents is exact.
+    pub fn is_exact(&self) -> bool {
+        matches!(self, TotalHits::Exact(_))
+    }
+}
+
+/// Collector returned by
+/// [`TopDocs::track_total_hits_up_to`](super::TopDocs::track_total_hits_up_to).
+pub(crate) struct TotalHitsTopCollector {
+    collector: TopCollector<Score>,
+    total_hits_threshold: usize,
+}
+
+impl TotalHitsTopCollector {
+    pub fn new(collector: TopCollector<Score>, total_hits_threshold: usize) -> Self {
+        TotalHitsTopCollector {
+            collector,
+            total_hits_threshold,
+        }
+    }
+}
+
+impl Collector for TotalHitsTopCollector {
+    type Fruit = (Vec<(Score, DocAddress)>, TotalHits);
+
+    type Child = TotalHitsTopSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<TotalHitsTopSegmentCollector> {
+        Ok(TotalHitsTopSegmentCollector {
+            segment_collector: self.collector.for_segment(segment_local_id, segment_reader),
+            max_num_hits: self.total_hits_threshold + 1,
+            num_hits: 0,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        child_fruits: Vec<(Vec<(Score, DocAddress)>, usize)>,
+    ) -> crate::Result<(Vec<(Score, DocAddress)>, TotalHits)> {
+        let mut num_hits = 0;
+        let mut top_docs = Vec::with_capacity(child_fruits.len());
+        for (segment_top_docs, segment_num_hits) in child_fruits {
+            top_docs.push(segment_top_docs);
+            num_hits += segment_num_hits;
+        }
+        let total_hits = if num_hits > self.total_hits_threshold {
+            TotalHits::GreaterThanOrEqual(self.total_hits_threshold)
+        } else {
+            TotalHits::Exact(num_hits)
+        };
+        Ok((self.collector.merge_fruits(top_docs)?, total_hits))
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<(Vec<(Score, DocAddress)>, usize)> {
+        let heap_len = self.collector.limit + self.collector.offset;
+        let mut heap: BinaryHeap<ComparableDoc<Score, DocId>> = BinaryHeap::with_capacity(heap_len);
+        let max_num_hits = self.total_hits_threshold + 1;
+        let mut num_hits = 0;
+        let alive_bitset_opt = reader.alive_bitset();
+        let mut threshold = Score::MIN;
+
+        // Pruning only starts once more than `total_hits_threshold` documents were counted,
+        // as documents skipped by block-WAND cannot be counted.
+        weight.for_each_pruning(Score::MIN, reader, &mut |doc, score| {
+            if let Some(alive_bitset) = alive_bitset_opt {
+                if alive_bitset.is_deleted(doc) {
+                    return threshold;
+                }
+            }
+            if num_hits < max_num_hits {
+                num_hits += 1;
+            }
+            let heap_item = ComparableDoc {
+                feature: score,
+                doc,
+            };
+            if heap.len() < heap_len {
+                heap.push(heap_item);
+            } else if score > heap.peek().unwrap().feature {
+                *heap.peek_mut().unwrap() = heap_item;
+            }
+            if num_hits == max_num_hits && heap.len() == heap_len {
+                threshold = heap.peek().unwrap().feature;
+            }
+            threshold
+        })?;
+
+        let top_docs = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|cid| (cid.feature, DocAddress::new(segment_ord, cid.doc)))
+            .collect();
+        Ok((top_docs, num_hits))
+    }
+}
+
+pub(crate) struct TotalHitsTopSegmentCollector {
+    segment_collector: TopSegmentCollector<Score>,
+    max_num_hits: usize,
+    num_hits: usize,
+}
+
+impl SegmentCollector for TotalHitsTopSegmentCollector {
+    type Fruit = (Vec<(Score, DocAddress)>, usize);
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if self.num_hits < self.max_num_hits {
+            self.num_hits += 1;
+        }
+        self.segment_collector.collect(doc, score);
+    }
+
+    fn harvest(self) -> (Vec<(Score, DocAddress)>, usize) {
+        (self.segment_collector.harvest(), self.num_hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TotalHits;
+    use crate::collector::{Count, TopDocs};
+    use crate::indexer::NoMergePolicy;
+    use crate::query::{QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{Index, Term};
+
+    #[test]
+    fn test_track_total_hits_up_to() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0..300 {
+            let text_value = match i % 3 {
+                0 => "a b",
+                1 => "a a a c",
+                _ => "b c",
+            };
+            index_writer.add_document(doc!(text => text_value))?;
+            if i % 100 == 99 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_text(text, "c"));
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text]).parse_query("a b")?;
+        let (expected_top_docs, count) =
+            searcher.search(&query, &(TopDocs::with_limit(5), Count))?;
+        assert_eq!(count, 101);
+
+        for (threshold, expected_total_hits) in [
+            (0, TotalHits::GreaterThanOrEqual(0)),
+            (20, TotalHits::GreaterThanOrEqual(20)),
+            (100, TotalHits::GreaterThanOrEqual(100)),
+            (101, TotalHits::Exact(101)),
+            (1_000, TotalHits::Exact(101)),
+        ] {
+            let (top_docs, total_hits) = searcher.search(
+                &query,
+                &TopDocs::with_limit(5).track_total_hits_up_to(threshold),
+            )?;
+            assert_eq!(top_docs, expected_top_docs);
+            assert_eq!(total_hits, expected_total_hits);
+        }
+
+        // The segment collectors count the documents as well.
+        let ((top_docs, total_hits), _) = searcher.search(
+            &query,
+            &(TopDocs::with_limit(5).track_total_hits_up_to(20), Count),
+        )?;
+        assert_eq!(top_docs, expected_top_docs);
+        assert_eq!(total_hits, TotalHits::GreaterThanOrEqual(20));
+
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let (_, total_hits) = searcher.search(
+            &term_query,
+            &TopDocs::with_limit(5).track_total_hits_up_to(1_000),
+        )?;
+        assert_eq!(total_hits, TotalHits::Exact(101));
+        assert_eq!(total_hits.value(), 101);
+        assert!(total_hits.is_exact());
+        Ok(())
+    }
+}
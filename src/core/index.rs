@@ -18,6 +18,7 @@ use crate::directory::{Directory, ManagedDirectory, RamDirectory, INDEX_WRITER_L
 use crate::error::{DataCorruption, TantivyError};
 use crate::indexer::index_writer::{MAX_NUM_THREAD, MEMORY_BUDGET_NUM_BYTES_MIN};
 use crate::indexer::segment_updater::save_metas;
+use crate::query::{Similarity, SimilarityManager};
 use crate::reader::{IndexReader, IndexReaderBuilder};
 use crate::schema::{Field, FieldType, Schema};
 use crate::tokenizer::{TextAnalyzer, TokenizerManager};
@@ -108,6 +109,7 @@ pub struct IndexBuilder {
     index_settings: IndexSettings,
     tokenizer_manager: TokenizerManager,
     fast_field_tokenizer_manager: TokenizerManager,
+    similarity_manager: SimilarityManager,
 }
 impl Default for IndexBuilder {
     fn default() -> Self {
@@ -122,6 +124,7 @@ impl IndexBuilder {
             index_settings: IndexSettings::default(),
             tokenizer_manager: TokenizerManager::default(),
             fast_field_tokenizer_manager: TokenizerManager::default(),
+            similarity_manager: SimilarityManager::default(),
         }
     }
 
@@ -151,6 +154,12 @@ impl IndexBuilder {
         self
     }
 
+    /// Set the similarities.
+    pub fn similarities(mut self, similarities: SimilarityManager) -> Self {
+        self.similarity_manager = similarities;
+        self
+    }
+
     /// Creates a new index using the [`RamDirectory`].
     ///
     /// The index will be allocated in anonymous memory.
@@ -224,6 +233,7 @@ impl IndexBuilder {
         }
         let mut index = Index::open(dir)?;
         index.set_tokenizers(self.tokenizer_manager.clone());
+        index.set_similarities(self.similarity_manager.clone());
         if index.schema() == self.get_expect_schema()? {
             Ok(index)
         } else {
@@ -276,6 +286,7 @@ impl IndexBuilder {
         let mut index = Index::open_from_metas(directory, &metas, SegmentMetaInventory::default());
         index.set_tokenizers(self.tokenizer_manager);
         index.set_fast_field_tokenizers(self.fast_field_tokenizer_manager);
+        index.set_similarities(self.similarity_manager);
         Ok(index)
     }
 }
@@ -289,6 +300,7 @@ pub struct Index {
     executor: Arc<Executor>,
     tokenizers: TokenizerManager,
     fast_field_tokenizers: TokenizerManager,
+    similarities: SimilarityManager,
     inventory: SegmentMetaInventory,
 }
 
@@ -408,6 +420,7 @@ impl Index {
             schema,
             tokenizers: TokenizerManager::default(),
             fast_field_tokenizers: TokenizerManager::default(),
+            similarities: SimilarityManager::default(),
             executor: Arc::new(Executor::single_thread()),
             inventory,
         }
@@ -433,6 +446,34 @@ impl Index {
         &self.fast_field_tokenizers
     }
 
+    /// Setter for the similarity manager.
+    pub fn set_similarities(&mut self, similarities: SimilarityManager) {
+        self.similarities = similarities;
+    }
+
+    /// Accessor for the similarity manager.
+    pub fn similarities(&self) -> &SimilarityManager {
+        &self.similarities
+    }
+
+    /// Get the similarity associated with a specific field.
+    ///
+    /// Fields that are not text fields are scored with the `bm25` similarity.
+    pub fn similarity_for_field(&self, field: Field) -> crate::Result<Arc<dyn Similarity>> {
+        let field_entry = self.schema.get_field_entry(field);
+        let indexing_options_opt = match field_entry.field_type() {
+            FieldType::JsonObject(options) => options.get_text_indexing_options(),
+            FieldType::Str(options) => options.get_indexing_options(),
+            _ => None,
+        };
+        let similarity_name = indexing_options_opt
+            .map(|indexing_options| indexing_options.similarity())
+            .unwrap_or("bm25");
+        self.similarities.get(similarity_name).ok_or_else(|| {
+            TantivyError::InvalidArgument(format!("No Similarity found for field {field_entry:?}"))
+        })
+    }
+
     /// Get the tokenizer associated with a specific field.
     pub fn tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
         let field_entry = self.schema.get_field_entry(field);
@@ -18,6 +18,29 @@ struct DeleteMeta {
     opstamp: Opstamp,
 }
 
+/// Returns the relative path of a component of the segment `segment_id`,
+/// with deletes up to `delete_opstamp`.
+pub(crate) fn segment_component_path(
+    segment_id: SegmentId,
+    delete_opstamp: Option<Opstamp>,
+    component: SegmentComponent,
+) -> PathBuf {
+    let mut path = segment_id.uuid_string();
+    path.push_str(&match component {
+        SegmentComponent::Postings => ".idx".to_string(),
+        SegmentComponent::Positions => ".pos".to_string(),
+        SegmentComponent::Terms => ".term".to_string(),
+        SegmentComponent::Store => ".store".to_string(),
+        SegmentComponent::TempStore => ".store.temp".to_string(),
+        SegmentComponent::FastFields => ".fast".to_string(),
+        SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
+        SegmentComponent::Delete => format!(".{}.del", delete_opstamp.unwrap_or(0)),
+        SegmentComponent::Vectors => ".vec".to_string(),
+        SegmentComponent::TermVectors => ".tv".to_string(),
+    });
+    PathBuf::from(path)
+}
+
 #[derive(Clone, Default)]
 pub struct SegmentMetaInventory {
     inventory: Inventory<InnerSegmentMeta>,
@@ -132,18 +155,7 @@ impl SegmentMeta {
     /// It just joins the segment id with the extension
     /// associated with a segment component.
     pub fn relative_path(&self, component: SegmentComponent) -> PathBuf {
-        let mut path = self.id().uuid_string();
-        path.push_str(&match component {
-            SegmentComponent::Postings => ".idx".to_string(),
-            SegmentComponent::Positions => ".pos".to_string(),
-            SegmentComponent::Terms => ".term".to_string(),
-            SegmentComponent::Store => ".store".to_string(),
-            SegmentComponent::TempStore => ".store.temp".to_string(),
-            SegmentComponent::FastFields => ".fast".to_string(),
-            SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
-            SegmentComponent::Delete => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
-        });
-        PathBuf::from(path)
+        segment_component_path(self.id(), self.delete_opstamp(), component)
     }
 
     /// Return the highest doc id + 1
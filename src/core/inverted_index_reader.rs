@@ -138,20 +138,26 @@ impl InvertedIndexReader {
         let option = option.downgrade(self.record_option);
 
         let block_postings = self.read_block_postings_from_terminfo(term_info, option)?;
-        let position_reader = {
-            if option.has_positions() {
-                let positions_data = self
-                    .positions_file_slice
-                    .read_bytes_slice(term_info.positions_range.clone())?;
-                let position_reader = PositionReader::open(positions_data)?;
-                Some(position_reader)
-            } else {
-                None
+        let mut position_reader = None;
+        let mut offset_reader = None;
+        if option.has_positions() {
+            let mut positions_data = self
+                .positions_file_slice
+                .read_bytes_slice(term_info.positions_range.clone())?;
+            if self.record_option.has_offsets() {
+                let (offsets_data, remaining_positions_data) =
+                    PositionReader::split_offsets(positions_data)?;
+                if option.has_offsets() {
+                    offset_reader = Some(PositionReader::open(offsets_data)?);
+                }
+                positions_data = remaining_positions_data;
             }
-        };
+            position_reader = Some(PositionReader::open(positions_data)?);
+        }
         Ok(SegmentPostings::from_block_postings(
             block_postings,
             position_reader,
+            offset_reader,
         ))
     }
 
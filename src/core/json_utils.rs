@@ -152,6 +152,8 @@ fn index_json_value(
                     ctx,
                     indexing_position,
                 );
+                // Offsets are computed as if the values of a path were joined with a space.
+                indexing_position.end_offset += text.len() as u32 + 1;
             }
             TextOrDateTime::DateTime(dt) => {
                 json_term_writer.set_fast_value(DateTime::from_utc(dt));
@@ -5,7 +5,7 @@ use std::{fmt, io};
 use crate::collector::Collector;
 use crate::core::{Executor, SegmentReader};
 use crate::query::{Bm25StatisticsProvider, EnableScoring, Query};
-use crate::reader::multi_parts_statistics::MultiPartsStatistics;
+use crate::reader::multi_parts_statistics::{GlobalStatistics, MultiPartsStatistics};
 use crate::schema::{Document, Schema, Term};
 use crate::space_usage::SearcherSpaceUsage;
 use crate::store::{CacheStats, StoreReader};
@@ -126,19 +126,29 @@ impl Searcher {
 
     /// Return the overall number of documents containing
     /// the given term.
-    pub fn doc_freq(&self, term: &Term) -> crate::Result<u64> {    
-        let multi_parts_statistics = self.multi_parts_statistics.as_ref().as_ref();
-        if multi_parts_statistics.is_none() {
-            let mut total_doc_freq = 0;
-            for segment_reader in &self.inner.segment_readers {
-                let inverted_index = segment_reader.inverted_index(term.field())?;
-                let doc_freq = inverted_index.doc_freq(term)?;
-                total_doc_freq += u64::from(doc_freq);
+    ///
+    /// Once multi parts statistics are installed, returns the document frequency of the term
+    /// in all of the parts. Terms whose document frequency was not collected fall back to
+    /// their document frequency in the segments of this searcher.
+    pub fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        if let Some(multi_parts_statistics) = self.multi_parts_statistics.as_ref() {
+            if let Some(&doc_freq) = multi_parts_statistics.doc_freq_map.get(term) {
+                return Ok(doc_freq);
             }
-            Ok(total_doc_freq)
-        } else {
-            Ok(u64::from(multi_parts_statistics.unwrap().doc_freq(term)))
         }
+        self.local_doc_freq(term)
+    }
+
+    /// Returns the number of documents of the segments of this searcher containing the given
+    /// term, ignoring the installed multi parts statistics.
+    pub(crate) fn local_doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        let mut total_doc_freq = 0;
+        for segment_reader in &self.inner.segment_readers {
+            let inverted_index = segment_reader.inverted_index(term.field())?;
+            let doc_freq = inverted_index.doc_freq(term)?;
+            total_doc_freq += u64::from(doc_freq);
+        }
+        Ok(total_doc_freq)
     }
 
     /// Update statistics for multi parts.
@@ -217,6 +227,84 @@ impl Searcher {
         self.search_with_executor(query, collector, executor, enabled_scoring)
     }
 
+    /// First phase of a distributed search: collects the statistics of this searcher
+    /// required to score `query`.
+    ///
+    /// The statistics of all the searchers (or shards) taking part in the search can then
+    /// be shipped with [`MultiPartsStatistics::to_bytes`], merged with
+    /// [`MultiPartsStatistics::merge`] and handed to the second phase,
+    /// [`search_with_global_statistics(...)`](Searcher::search_with_global_statistics).
+    pub fn collect_statistics(&self, query: &dyn Query) -> crate::Result<MultiPartsStatistics> {
+        MultiPartsStatistics::for_query(self, query)
+    }
+
+    /// Second phase of a distributed search: runs `query` on this searcher,
+    /// scoring documents with the merged `global_statistics` of all of the shards.
+    ///
+    /// Unlike [`update_multi_parts_statistics`](Searcher::update_multi_parts_statistics),
+    /// the searcher is left untouched.
+    ///
+    /// Terms whose document frequency was not collected, e.g. the terms of a
+    /// [`MoreLikeThisQuery`](crate::query::MoreLikeThisQuery), are scored with their document
+    /// frequency in this searcher.
+    ///
+    /// The fruits returned by every shard can be merged with [`Collector::merge_fruits`].
+    /// Keep in mind that the [`DocAddress`]es of the merged fruits are relative to the
+    /// shard they come from.
+    ///
+    /// ```rust
+    /// use tantivy::collector::{Collector, TopDocs};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::reader::multi_parts_statistics::MultiPartsStatistics;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    /// # fn test() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let schema = schema_builder.build();
+    /// let mut searchers = Vec::new();
+    /// for title_text in ["The Diary of Muadib", "The Diary of a Young Girl"] {
+    ///     let index = Index::create_in_ram(schema.clone());
+    ///     let mut index_writer = index.writer(15_000_000)?;
+    ///     index_writer.add_document(doc!(title => title_text))?;
+    ///     index_writer.commit()?;
+    ///     searchers.push(index.reader()?.searcher());
+    /// }
+    /// let query = QueryParser::for_index(searchers[0].index(), vec![title])
+    ///     .parse_query("diary girl")?;
+    ///
+    /// // Phase one: gather the statistics of every shard.
+    /// let mut global_statistics = MultiPartsStatistics::default();
+    /// for searcher in &searchers {
+    ///     global_statistics.merge(&searcher.collect_statistics(query.as_ref())?);
+    /// }
+    /// // Phase two: score every shard with the global statistics.
+    /// let collector = TopDocs::with_limit(2);
+    /// let fruits = searchers
+    ///     .iter()
+    ///     .map(|searcher| {
+    ///         searcher.search_with_global_statistics(query.as_ref(), &collector, &global_statistics)
+    ///     })
+    ///     .collect::<tantivy::Result<Vec<_>>>()?;
+    /// let top_docs = collector.merge_fruits(fruits)?;
+    /// assert_eq!(top_docs.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// # assert!(test().is_ok());
+    /// ```
+    pub fn search_with_global_statistics<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        global_statistics: &MultiPartsStatistics,
+    ) -> crate::Result<C::Fruit> {
+        let statistics_provider = GlobalStatistics {
+            statistics: global_statistics,
+            searcher: self,
+        };
+        self.search_with_statistics_provider(query, collector, &statistics_provider)
+    }
+
     /// Same as [`search(...)`](Searcher::search) but multithreaded.
     ///
     /// The current implementation is rather naive :
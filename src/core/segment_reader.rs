@@ -1,16 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::{fmt, io};
 
+use crate::core::index_meta::segment_component_path;
 use crate::core::{InvertedIndexReader, Segment, SegmentComponent, SegmentId};
 use crate::directory::{CompositeFile, FileSlice};
 use crate::error::DataCorruption;
 use crate::fastfield::{intersect_alive_bitsets, AliveBitSet, FacetReader, FastFieldReaders};
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders};
-use crate::schema::{Field, IndexRecordOption, Schema, Type};
+use crate::schema::{Field, FieldType, IndexRecordOption, Schema, Type};
 use crate::space_usage::SegmentSpaceUsage;
 use crate::store::StoreReader;
+use crate::term_vector::{TermVector, TermVectorReaders};
 use crate::termdict::TermDictionary;
+use crate::tokenizer::TokenizerManager;
+use crate::vector::VectorReaders;
 use crate::{DocId, Opstamp};
 
 /// Entry point to access all of the datastructures of the `Segment`
@@ -38,10 +43,13 @@ pub struct SegmentReader {
     positions_composite: CompositeFile,
     fast_fields_readers: FastFieldReaders,
     fieldnorm_readers: FieldNormReaders,
+    vector_readers: VectorReaders,
+    term_vector_readers: TermVectorReaders,
 
     store_file: FileSlice,
     alive_bitset_opt: Option<AliveBitSet>,
     schema: Schema,
+    tokenizers: TokenizerManager,
 }
 
 impl SegmentReader {
@@ -62,6 +70,11 @@ impl SegmentReader {
         &self.schema
     }
 
+    /// Returns the tokenizer manager of the index this segment belongs to.
+    pub(crate) fn tokenizers(&self) -> &TokenizerManager {
+        &self.tokenizers
+    }
+
     /// Return the number of documents that have been
     /// deleted in the segment.
     pub fn num_deleted_docs(&self) -> DocId {
@@ -126,6 +139,41 @@ impl SegmentReader {
         &self.fieldnorm_readers
     }
 
+    /// Accessor to the segment's vectors, used for nearest neighbor search.
+    pub fn vector_readers(&self) -> &VectorReaders {
+        &self.vector_readers
+    }
+
+    /// Accessor to the segment's term vectors.
+    pub fn term_vector_readers(&self) -> &TermVectorReaders {
+        &self.term_vector_readers
+    }
+
+    /// Returns the term vector of a document for the given field: the terms of the
+    /// document, with their frequency, and their positions and offsets if the field
+    /// records them.
+    ///
+    /// Returns an error if the field does not have term vectors, as defined by
+    /// [`TextOptions::set_term_vectors`](crate::schema::TextOptions::set_term_vectors).
+    /// Documents indexed before term vectors were enabled have an empty term vector.
+    pub fn term_vector(&self, doc: DocId, field: Field) -> crate::Result<TermVector> {
+        let field_entry = self.schema.get_field_entry(field);
+        match field_entry.field_type() {
+            FieldType::Str(text_options) if text_options.has_term_vectors() => {}
+            _ => {
+                return Err(crate::TantivyError::SchemaError(format!(
+                    "Field {:?} does not have term vectors",
+                    field_entry.name()
+                )));
+            }
+        }
+        if let Some(term_vector_reader) = self.term_vector_readers.get_field(field)? {
+            term_vector_reader.term_vector(doc)
+        } else {
+            Ok(TermVector::default())
+        }
+    }
+
     /// Accessor to the segment's [`StoreReader`](crate::store::StoreReader).
     ///
     /// `cache_num_blocks` sets the number of decompressed blocks to be cached in an LRU.
@@ -168,6 +216,14 @@ impl SegmentReader {
         let fast_fields_readers = FastFieldReaders::open(fast_fields_data, schema.clone())?;
         let fieldnorm_data = segment.open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
+        let vector_readers = VectorReaders::open(
+            segment.open_read(SegmentComponent::Vectors).ok(),
+            schema.clone(),
+        )?;
+        let term_vector_readers = TermVectorReaders::open(
+            segment.open_read(SegmentComponent::TermVectors).ok(),
+            schema.clone(),
+        )?;
 
         let original_bitset = if segment.meta().has_deletes() {
             let alive_doc_file_slice = segment.open_read(SegmentComponent::Delete)?;
@@ -193,12 +249,15 @@ impl SegmentReader {
             postings_composite,
             fast_fields_readers,
             fieldnorm_readers,
+            vector_readers,
+            term_vector_readers,
             segment_id: segment.id(),
             delete_opstamp: segment.meta().delete_opstamp(),
             store_file,
             alive_bitset_opt,
             positions_composite,
             schema,
+            tokenizers: segment.index().tokenizers().clone(),
         })
     }
 
@@ -290,6 +349,17 @@ impl SegmentReader {
         self.delete_opstamp
     }
 
+    /// Returns the list of files the segment reader may read from.
+    ///
+    /// Note: Some of the returned files may not exist.
+    pub(crate) fn list_files(&self) -> HashSet<PathBuf> {
+        SegmentComponent::iterator()
+            .map(|component| {
+                segment_component_path(self.segment_id, self.delete_opstamp, *component)
+            })
+            .collect()
+    }
+
     /// Returns the bitset representing the alive `DocId`s.
     pub fn alive_bitset(&self) -> Option<&AliveBitSet> {
         self.alive_bitset_opt.as_ref()
@@ -322,6 +392,8 @@ impl SegmentReader {
             self.fast_fields_readers.space_usage(self.schema())?,
             self.fieldnorm_readers.space_usage(),
             self.get_store_reader(0)?.space_usage(),
+            self.vector_readers.space_usage(),
+            self.term_vector_readers.space_usage(),
             self.alive_bitset_opt
                 .as_ref()
                 .map(AliveBitSet::space_usage)
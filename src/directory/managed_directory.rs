@@ -1,8 +1,9 @@
 use std::collections::HashSet;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, RwLock, RwLockWriteGuard};
-use std::{io, result};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use std::time::Instant;
+use std::{fmt, io, result};
 
 use crc32fast::Hasher;
 
@@ -14,7 +15,7 @@ use crate::directory::{
     WatchHandle, WritePtr, META_LOCK,
 };
 use crate::error::DataCorruption;
-use crate::Directory;
+use crate::{Directory, Inventory, TrackedObject};
 
 /// Returns true if the file is "managed".
 /// Non-managed file are not subject to garbage collection.
@@ -40,6 +41,7 @@ fn is_managed(path: &Path) -> bool {
 pub struct ManagedDirectory {
     directory: Box<dyn Directory>,
     meta_informations: Arc<RwLock<MetaInformation>>,
+    file_protections: FileProtections,
 }
 
 #[derive(Debug, Default)]
@@ -47,6 +49,49 @@ struct MetaInformation {
     managed_paths: HashSet<PathBuf>,
 }
 
+/// Set of files that must not be garbage collected until a deadline.
+struct ProtectedFiles {
+    files: HashSet<PathBuf>,
+    expires_at: Mutex<Instant>,
+}
+
+impl ProtectedFiles {
+    fn is_expired(&self, now: Instant) -> bool {
+        *self.expires_at.lock().unwrap() <= now
+    }
+}
+
+/// Files protected from garbage collection, shared by all of the clones
+/// of a `ManagedDirectory`.
+#[derive(Clone, Default)]
+struct FileProtections(Inventory<ProtectedFiles>);
+
+impl fmt::Debug for FileProtections {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FileProtections({})", self.0.len())
+    }
+}
+
+/// Guard protecting a set of files from garbage collection.
+///
+/// The files are protected until the guard is dropped or its deadline
+/// is reached, whichever comes first.
+pub(crate) struct FileProtection {
+    protected_files: TrackedObject<ProtectedFiles>,
+}
+
+impl FileProtection {
+    /// Moves the deadline of the protection.
+    pub fn set_expires_at(&self, expires_at: Instant) {
+        *self.protected_files.expires_at.lock().unwrap() = expires_at;
+    }
+
+    /// Returns true if the deadline of the protection has been reached.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.protected_files.is_expired(now)
+    }
+}
+
 /// Saves the file containing the list of existing files
 /// that were created by tantivy.
 fn save_managed_paths(
@@ -77,11 +122,13 @@ impl ManagedDirectory {
                     meta_informations: Arc::new(RwLock::new(MetaInformation {
                         managed_paths: managed_files,
                     })),
+                    file_protections: FileProtections::default(),
                 })
             }
             Err(OpenReadError::FileDoesNotExist(_)) => Ok(ManagedDirectory {
                 directory,
                 meta_informations: Arc::default(),
+                file_protections: FileProtections::default(),
             }),
             io_err @ Err(OpenReadError::IoError { .. }) => Err(io_err.err().unwrap().into()),
             Err(OpenReadError::IncompatibleIndex(incompatibility)) => {
@@ -99,6 +146,9 @@ impl ManagedDirectory {
     ///
     /// * `living_files` - List of files that are still used by the index.
     ///
+    /// Files protected with [`ManagedDirectory::protect_files`] are kept as well,
+    /// until their protection expires.
+    ///
     /// The use a callback ensures that the list of living_files is computed
     /// while we hold the lock on meta.
     ///
@@ -138,8 +188,21 @@ impl ManagedDirectory {
             match self.acquire_lock(&META_LOCK) {
                 Ok(_meta_lock) => {
                     let living_files = get_living_files();
+                    let now = Instant::now();
+                    let protected_files: Vec<TrackedObject<ProtectedFiles>> = self
+                        .file_protections
+                        .0
+                        .list()
+                        .into_iter()
+                        .filter(|protected_files| !protected_files.is_expired(now))
+                        .collect();
+                    let is_protected = |path: &PathBuf| {
+                        protected_files
+                            .iter()
+                            .any(|protected_files| protected_files.files.contains(path))
+                    };
                     for managed_path in &meta_informations_rlock.managed_paths {
-                        if !living_files.contains(managed_path) {
+                        if !living_files.contains(managed_path) && !is_protected(managed_path) {
                             files_to_delete.push(managed_path.clone());
                         }
                     }
@@ -199,6 +262,22 @@ impl ManagedDirectory {
         })
     }
 
+    /// Protects files from garbage collection until the returned guard is dropped,
+    /// or `expires_at` is reached.
+    ///
+    /// The protection is shared by all of the clones of this directory.
+    pub(crate) fn protect_files(
+        &self,
+        files: HashSet<PathBuf>,
+        expires_at: Instant,
+    ) -> FileProtection {
+        let protected_files = self.file_protections.0.track(ProtectedFiles {
+            files,
+            expires_at: Mutex::new(expires_at),
+        });
+        FileProtection { protected_files }
+    }
+
     /// Registers a file as managed
     ///
     /// This method must be called before the file is
@@ -330,6 +409,7 @@ impl Clone for ManagedDirectory {
         ManagedDirectory {
             directory: self.directory.box_clone(),
             meta_informations: Arc::clone(&self.meta_informations),
+            file_protections: self.file_protections.clone(),
         }
     }
 }
@@ -11,6 +11,7 @@ use crate::directory::error::{
     Incompatibility, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
 };
 use crate::fastfield::FastFieldNotAvailableError;
+use crate::reader::multi_parts_statistics::MultiPartsStatisticsError;
 use crate::{query, schema};
 
 /// Represents a `DataCorruption` error.
@@ -57,6 +58,9 @@ pub enum TantivyError {
     /// Error when handling aggregations.
     #[error(transparent)]
     AggregationError(#[from] AggregationError),
+    /// The statistics installed on the searcher are missing some information.
+    #[error(transparent)]
+    MultiPartsStatisticsError(#[from] MultiPartsStatisticsError),
     /// Failed to open the directory.
     #[error("Failed to open the directory: '{0:?}'")]
     OpenDirectoryError(#[from] OpenDirectoryError),
@@ -10,6 +10,7 @@ use common::ByteCount;
 
 use crate::core::json_utils::encode_column_name;
 use crate::directory::FileSlice;
+use crate::schema::term::JSON_PATH_SEGMENT_SEP_STR;
 use crate::schema::{Field, FieldEntry, FieldType, Schema};
 use crate::space_usage::{FieldUsage, PerFieldSpaceUsage};
 use crate::TantivyError;
@@ -234,6 +235,46 @@ impl FastFieldReaders {
         Ok(dynamic_column_handle_opt)
     }
 
+    /// Returns the handles of all of the columns of a field, whatever their type.
+    pub(crate) fn dynamic_column_handles(
+        &self,
+        field_name: &str,
+    ) -> crate::Result<Vec<DynamicColumnHandle>> {
+        let Some(resolved_field_name) = self.resolve_field(field_name)? else {
+            return Ok(Vec::new());
+        };
+        Ok(self.columnar.read_columns(&resolved_field_name)?)
+    }
+
+    /// Returns the handles of all of the columns of a field, or of a json path.
+    ///
+    /// For a json field, the columns of the sub-paths are included: the columns of
+    /// `attributes.color.rgb` are part of the columns of `attributes.color`.
+    pub(crate) fn dynamic_column_handles_with_subpaths(
+        &self,
+        field_name: &str,
+    ) -> crate::Result<Vec<DynamicColumnHandle>> {
+        let resolved_field_name = if self.schema.get_field(field_name).is_ok() {
+            // The root of a json field is not resolved as a column, but it is the prefix of all
+            // of its columns.
+            field_name.to_string()
+        } else if let Some(resolved_field_name) = self.resolve_field(field_name)? {
+            resolved_field_name
+        } else {
+            return Ok(Vec::new());
+        };
+        let subpath_prefix = format!("{resolved_field_name}{JSON_PATH_SEGMENT_SEP_STR}");
+        let column_handles = self
+            .columnar
+            .iter_columns()?
+            .filter(|(column_name, _)| {
+                *column_name == resolved_field_name || column_name.starts_with(&subpath_prefix)
+            })
+            .map(|(_, column_handle)| column_handle)
+            .collect();
+        Ok(column_handles)
+    }
+
     #[doc(hidden)]
     pub async fn list_dynamic_column_handles(
         &self,
@@ -218,6 +218,15 @@ impl FastFieldsWriter {
                         self.columnar_writer
                             .record_ip_addr(doc_id, field_name.as_str(), *ip_addr);
                     }
+                    // Vector fields are never fast fields.
+                    Value::Vector(_) => {}
+                    Value::GeoPoint(geo_point) => {
+                        self.columnar_writer.record_numerical(
+                            doc_id,
+                            field_name.as_str(),
+                            NumericalValue::from(geo_point.to_u64()),
+                        );
+                    }
                 }
             }
         }
@@ -96,6 +96,12 @@ impl FieldNormReader {
         ReaderImplEnum::FromData(data).into()
     }
 
+    /// Returns true if the fieldnorm is the same for all documents, e.g. for a field
+    /// indexed without fieldnorms.
+    pub(crate) fn is_constant(&self) -> bool {
+        matches!(self.0, ReaderImplEnum::Const { .. })
+    }
+
     /// Returns the number of documents in this segment.
     pub fn num_docs(&self) -> u32 {
         match &self.0 {
@@ -20,7 +20,8 @@ use crate::indexer::operation::DeleteOperation;
 use crate::indexer::stamper::Stamper;
 use crate::indexer::{MergePolicy, SegmentEntry, SegmentWriter};
 use crate::query::{EnableScoring, Query, TermQuery};
-use crate::schema::{Document, IndexRecordOption, Term};
+use crate::schema::{Document, FieldType, IndexRecordOption, Term};
+use crate::vector::validate_document_vectors;
 use crate::{FutureResult, Opstamp};
 
 // Size of the margin for the `memory_arena`. A segment is closed when the remaining memory
@@ -75,6 +76,9 @@ pub struct IndexWriter {
 
     stamper: Stamper,
     committed_opstamp: Opstamp,
+
+    // Documents are only validated if the schema has vector fields.
+    has_vector_fields: bool,
 }
 
 fn compute_deleted_bitset(
@@ -290,6 +294,11 @@ impl IndexWriter {
 
         let stamper = Stamper::new(current_opstamp);
 
+        let has_vector_fields = index
+            .schema()
+            .fields()
+            .any(|(_, field_entry)| matches!(field_entry.field_type(), FieldType::Vector(_)));
+
         let segment_updater =
             SegmentUpdater::create(index.clone(), stamper.clone(), &delete_queue.cursor())?;
 
@@ -312,6 +321,8 @@ impl IndexWriter {
             stamper,
 
             worker_id: 0,
+
+            has_vector_fields,
         };
         index_writer.start_workers()?;
         Ok(index_writer)
@@ -707,7 +718,10 @@ impl IndexWriter {
     /// The opstamp is an increasing `u64` that can
     /// be used by the client to align commits with its own
     /// document queue.
+    ///
+    /// Returns an error if the vectors of the document don't match the schema.
     pub fn add_document(&self, document: Document) -> crate::Result<Opstamp> {
+        self.validate_document(&document)?;
         let opstamp = self.stamper.stamp();
         self.send_add_documents_batch(smallvec![AddOperation { opstamp, document }])?;
         Ok(opstamp)
@@ -742,6 +756,9 @@ impl IndexWriter {
     /// Like adds and deletes (see `IndexWriter.add_document` and
     /// `IndexWriter.delete_term`), the changes made by calling `run` will be
     /// visible to readers only after calling `commit()`.
+    ///
+    /// Returns an error without running any operation if the vectors of a document don't match
+    /// the schema.
     pub fn run<I>(&self, user_operations: I) -> crate::Result<Opstamp>
     where
         I: IntoIterator<Item = UserOperation>,
@@ -755,6 +772,8 @@ impl IndexWriter {
         let (batch_opstamp, stamps) = self.get_batch_opstamps(count);
 
         let mut adds = AddBatch::default();
+        // The deletes are only queued once all the documents are validated.
+        let mut deletes = Vec::new();
 
         for (user_op, opstamp) in user_operations_it.zip(stamps) {
             match user_op {
@@ -766,18 +785,31 @@ impl IndexWriter {
                         opstamp,
                         target: weight,
                     };
-                    self.delete_queue.push(delete_operation);
+                    deletes.push(delete_operation);
                 }
                 UserOperation::Add(document) => {
+                    self.validate_document(&document)?;
                     let add_operation = AddOperation { opstamp, document };
                     adds.push(add_operation);
                 }
             }
         }
+        for delete_operation in deletes {
+            self.delete_queue.push(delete_operation);
+        }
         self.send_add_documents_batch(adds)?;
         Ok(batch_opstamp)
     }
 
+    /// Checks the vectors of a document before it is sent to the indexing workers, which can't
+    /// report errors.
+    fn validate_document(&self, document: &Document) -> crate::Result<()> {
+        if self.has_vector_fields {
+            validate_document_vectors(&self.index.schema(), document)?;
+        }
+        Ok(())
+    }
+
     fn send_add_documents_batch(&self, add_ops: AddBatch) -> crate::Result<()> {
         if self.index_writer_status.is_alive() && self.operation_sender.send(add_ops).is_ok() {
             Ok(())
@@ -808,7 +840,7 @@ mod tests {
     use proptest::strategy::Strategy;
 
     use super::super::operation::UserOperation;
-    use crate::collector::TopDocs;
+    use crate::collector::{Count, TopDocs};
     use crate::directory::error::LockError;
     use crate::error::*;
     use crate::indexer::index_writer::MEMORY_BUDGET_NUM_BYTES_MIN;
@@ -816,7 +848,8 @@ mod tests {
     use crate::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
     use crate::schema::{
         self, Facet, FacetOptions, IndexRecordOption, IpAddrOptions, NumericOptions, Schema,
-        TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, STRING, TEXT,
+        TextFieldIndexing, TextOptions, VectorMetric, VectorOptions, FAST, INDEXED, STORED, STRING,
+        TEXT,
     };
     use crate::store::DOCSTORE_CACHE_CAPACITY;
     use crate::{
@@ -2541,6 +2574,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_invalid_vector_keeps_index_writer_usable() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let idfield = schema_builder.add_text_field("id", STRING);
+        let embedding = schema_builder
+            .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Euclidean));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(idfield => "1", embedding => vec![1.0f32, 2.0]))?;
+        // Wrong dimension.
+        assert!(index_writer
+            .add_document(doc!(idfield => "2", embedding => vec![1.0f32]))
+            .is_err());
+        // Two vectors.
+        assert!(index_writer
+            .add_document(doc!(
+                idfield => "3",
+                embedding => vec![1.0f32, 2.0],
+                embedding => vec![3.0f32, 4.0]
+            ))
+            .is_err());
+        // Not a vector.
+        assert!(index_writer
+            .add_document(doc!(idfield => "4", embedding => "text"))
+            .is_err());
+        // None of the operations are run, including the delete.
+        assert!(index_writer
+            .run(vec![
+                UserOperation::Delete(Term::from_field_text(idfield, "1")),
+                UserOperation::Add(doc!(idfield => "5", embedding => vec![1.0f32, 2.0])),
+                UserOperation::Add(doc!(idfield => "6", embedding => vec![1.0f32])),
+            ])
+            .is_err());
+        index_writer.add_document(doc!(idfield => "7", embedding => vec![3.0f32, 4.0]))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        let query_1 = TermQuery::new(
+            Term::from_field_text(idfield, "1"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&query_1, &Count)?, 1);
+        Ok(())
+    }
+
     #[test]
     fn test_bug_1617_3() {
         assert!(test_operation_strategy(
@@ -4,7 +4,7 @@ use columnar::{
     ColumnType, ColumnValues, ColumnarReader, MergeRowOrder, RowAddr, ShuffleMergeOrder,
     StackMergeOrder,
 };
-use common::ReadOnlyBitSet;
+use common::{OwnedBytes, ReadOnlyBitSet};
 use itertools::Itertools;
 use measure_time::debug_time;
 
@@ -19,7 +19,9 @@ use crate::indexer::SegmentSerializer;
 use crate::postings::{InvertedIndexSerializer, Postings, SegmentPostings};
 use crate::schema::{value_type_to_column_type, Field, FieldType, Schema};
 use crate::store::StoreWriter;
+use crate::term_vector::{TermVectorReader, TermVectorsSerializer};
 use crate::termdict::{TermMerger, TermOrdinal};
+use crate::vector::{VectorReader, VectorsSerializer};
 use crate::{
     DocAddress, DocId, IndexSettings, IndexSortByField, InvertedIndexReader, Order,
     SegmentComponent, SegmentOrdinal,
@@ -255,6 +257,77 @@ impl IndexMerger {
         Ok(())
     }
 
+    fn write_vectors(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        for (field, field_entry) in self.schema.fields() {
+            let vector_options = if let FieldType::Vector(vector_options) = field_entry.field_type()
+            {
+                vector_options
+            } else {
+                continue;
+            };
+            let vector_readers: Vec<Option<Arc<VectorReader>>> = self
+                .readers
+                .iter()
+                .map(|reader| reader.vector_readers().get_field(field))
+                .collect::<Result<_, _>>()?;
+            let mut doc_ids = Vec::new();
+            let mut vectors = Vec::new();
+            for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+                // Vectors are already normalized, they are copied as is.
+                if let Some(vector) = vector_readers[old_doc_addr.segment_ord as usize]
+                    .as_ref()
+                    .and_then(|vector_reader| vector_reader.vector(old_doc_addr.doc_id))
+                {
+                    doc_ids.push(new_doc_id as DocId);
+                    vectors.extend(vector);
+                }
+            }
+            vectors_serializer.serialize_field(field, vector_options, &doc_ids, &vectors)?;
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+
+    fn write_term_vectors(
+        &self,
+        mut term_vectors_serializer: TermVectorsSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        // Term vector of a document without terms, for segments written before term vectors
+        // were enabled.
+        let empty_term_vector: &[u8] = &[0u8];
+        for (field, field_entry) in self.schema.fields() {
+            match field_entry.field_type() {
+                FieldType::Str(text_options) if text_options.has_term_vectors() => {}
+                _ => continue,
+            }
+            let term_vector_readers: Vec<Option<TermVectorReader>> = self
+                .readers
+                .iter()
+                .map(|reader| reader.term_vector_readers().get_field(field))
+                .collect::<Result<_, _>>()?;
+            // The serialized term vectors are copied as is.
+            let docs =
+                doc_id_mapping
+                    .iter_old_doc_addrs()
+                    .map(|old_doc_addr| {
+                        match &term_vector_readers[old_doc_addr.segment_ord as usize] {
+                            Some(term_vector_reader) => {
+                                term_vector_reader.term_vector_bytes(old_doc_addr.doc_id)
+                            }
+                            None => Ok(OwnedBytes::new(empty_term_vector)),
+                        }
+                    });
+            term_vectors_serializer.serialize_field(field, docs)?;
+        }
+        term_vectors_serializer.close()?;
+        Ok(())
+    }
+
     fn write_fast_fields(
         &self,
         fast_field_wrt: &mut WritePtr,
@@ -453,6 +526,7 @@ impl IndexMerger {
     ) -> crate::Result<()> {
         debug_time!("write-postings-for-field");
         let mut positions_buffer: Vec<u32> = Vec::with_capacity(1_000);
+        let mut offsets_buffer: Vec<(u32, u32)> = Vec::new();
         let mut delta_computer = DeltaComputer::new();
 
         let mut max_term_ords: Vec<TermOrdinal> = Vec::new();
@@ -569,6 +643,7 @@ impl IndexMerger {
                         // there is at least one document.
                         let term_freq = segment_postings.term_freq();
                         segment_postings.positions(&mut positions_buffer);
+                        segment_postings.offsets(&mut offsets_buffer);
                         // if doc_id_mapping exists, the doc_ids are reordered, they are
                         // not just stacked. The field serializer expects monotonically increasing
                         // doc_ids, so we collect and sort them first, before writing.
@@ -581,10 +656,16 @@ impl IndexMerger {
                                 remapped_doc_id,
                                 term_freq,
                                 positions_buffer.to_vec(),
+                                offsets_buffer.to_vec(),
                             ));
                         } else {
                             let delta_positions = delta_computer.compute_delta(&positions_buffer);
-                            field_serializer.write_doc(remapped_doc_id, term_freq, delta_positions);
+                            field_serializer.write_doc_with_offsets(
+                                remapped_doc_id,
+                                term_freq,
+                                delta_positions,
+                                &offsets_buffer,
+                            );
                         }
                     }
 
@@ -592,11 +673,16 @@ impl IndexMerger {
                 }
             }
             if !doc_id_mapping.is_trivial() {
-                doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _)| doc_id);
+                doc_id_and_positions.sort_unstable_by_key(|&(doc_id, _, _, _)| doc_id);
 
-                for (doc_id, term_freq, positions) in &doc_id_and_positions {
+                for (doc_id, term_freq, positions, offsets) in &doc_id_and_positions {
                     let delta_positions = delta_computer.compute_delta(positions);
-                    field_serializer.write_doc(*doc_id, *term_freq, delta_positions);
+                    field_serializer.write_doc_with_offsets(
+                        *doc_id,
+                        *term_freq,
+                        delta_positions,
+                        offsets,
+                    );
                 }
                 doc_id_and_positions.clear();
             }
@@ -730,6 +816,16 @@ impl IndexMerger {
             &doc_id_mapping,
         )?;
 
+        debug!("write-vectors");
+        if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+            self.write_vectors(vectors_serializer, &doc_id_mapping)?;
+        }
+
+        debug!("write-term-vectors");
+        if let Some(term_vectors_serializer) = serializer.extract_term_vectors_serializer() {
+            self.write_term_vectors(term_vectors_serializer, &doc_id_mapping)?;
+        }
+
         debug!("write-storagefields");
         self.write_storable_fields(serializer.get_store_writer(), &doc_id_mapping)?;
         debug!("write-fastfields");
@@ -4,7 +4,10 @@ use crate::core::{Segment, SegmentComponent};
 use crate::directory::WritePtr;
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::postings::InvertedIndexSerializer;
+use crate::schema::FieldType;
 use crate::store::StoreWriter;
+use crate::term_vector::TermVectorsSerializer;
+use crate::vector::VectorsSerializer;
 
 /// Segment serializer is in charge of laying out on disk
 /// the data accumulated and sorted by the `SegmentWriter`.
@@ -14,6 +17,8 @@ pub struct SegmentSerializer {
     fast_field_write: WritePtr,
     fieldnorms_serializer: Option<FieldNormsSerializer>,
     postings_serializer: InvertedIndexSerializer,
+    vectors_serializer: Option<VectorsSerializer>,
+    term_vectors_serializer: Option<TermVectorsSerializer>,
 }
 
 impl SegmentSerializer {
@@ -53,6 +58,33 @@ impl SegmentSerializer {
         let fieldnorms_write = segment.open_write(SegmentComponent::FieldNorms)?;
         let fieldnorms_serializer = FieldNormsSerializer::from_write(fieldnorms_write)?;
 
+        // The vectors file is only written if the schema has vector fields.
+        let schema = segment.schema();
+        let vectors_serializer = if schema
+            .fields()
+            .any(|(_, field_entry)| matches!(field_entry.field_type(), FieldType::Vector(_)))
+        {
+            let vectors_write = segment.open_write(SegmentComponent::Vectors)?;
+            Some(VectorsSerializer::from_write(vectors_write)?)
+        } else {
+            None
+        };
+
+        // Likewise, the term vectors file is only written if some fields have term vectors.
+        let has_term_vectors = |field_type: &FieldType| match field_type {
+            FieldType::Str(text_options) => text_options.has_term_vectors(),
+            _ => false,
+        };
+        let term_vectors_serializer = if schema
+            .fields()
+            .any(|(_, field_entry)| has_term_vectors(field_entry.field_type()))
+        {
+            let term_vectors_write = segment.open_write(SegmentComponent::TermVectors)?;
+            Some(TermVectorsSerializer::from_write(term_vectors_write)?)
+        } else {
+            None
+        };
+
         let postings_serializer = InvertedIndexSerializer::open(&mut segment)?;
         Ok(SegmentSerializer {
             segment,
@@ -60,6 +92,8 @@ impl SegmentSerializer {
             fast_field_write,
             fieldnorms_serializer: Some(fieldnorms_serializer),
             postings_serializer,
+            vectors_serializer,
+            term_vectors_serializer,
         })
     }
 
@@ -93,6 +127,20 @@ impl SegmentSerializer {
         self.fieldnorms_serializer.take()
     }
 
+    /// Extract the vectors serializer.
+    ///
+    /// Note the vectors serializer can only be extracted once.
+    pub fn extract_vectors_serializer(&mut self) -> Option<VectorsSerializer> {
+        self.vectors_serializer.take()
+    }
+
+    /// Extract the term vectors serializer.
+    ///
+    /// Note the term vectors serializer can only be extracted once.
+    pub fn extract_term_vectors_serializer(&mut self) -> Option<TermVectorsSerializer> {
+        self.term_vectors_serializer.take()
+    }
+
     /// Accessor to the `StoreWriter`.
     pub fn get_store_writer(&mut self) -> &mut StoreWriter {
         &mut self.store_writer
@@ -103,6 +151,12 @@ impl SegmentSerializer {
         if let Some(fieldnorms_serializer) = self.extract_fieldnorms_serializer() {
             fieldnorms_serializer.close()?;
         }
+        if let Some(vectors_serializer) = self.extract_vectors_serializer() {
+            vectors_serializer.close()?;
+        }
+        if let Some(term_vectors_serializer) = self.extract_term_vectors_serializer() {
+            term_vectors_serializer.close()?;
+        }
         self.fast_field_write.terminate()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
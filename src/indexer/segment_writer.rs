@@ -13,9 +13,14 @@ use crate::postings::{
     compute_table_memory_size, serialize_postings, IndexingContext, IndexingPosition,
     PerFieldPostingsWriter, PostingsWriter,
 };
-use crate::schema::{FieldEntry, FieldType, Schema, Term, Value, DATE_TIME_PRECISION_INDEXED};
+use crate::schema::{
+    FieldEntry, FieldType, Schema, Term, Value, DATE_TIME_PRECISION_INDEXED,
+    INDEXED_GEOHASH_PRECISION,
+};
 use crate::store::{StoreReader, StoreWriter};
+use crate::term_vector::TermVectorsWriter;
 use crate::tokenizer::{FacetTokenizer, PreTokenizedStream, TextAnalyzer, Tokenizer};
+use crate::vector::VectorsWriter;
 use crate::{DocId, Document, Opstamp, SegmentComponent, TantivyError};
 
 /// Computes the initial size of the hash table.
@@ -65,6 +70,8 @@ pub struct SegmentWriter {
     pub(crate) segment_serializer: SegmentSerializer,
     pub(crate) fast_field_writers: FastFieldsWriter,
     pub(crate) fieldnorms_writer: FieldNormsWriter,
+    pub(crate) vectors_writer: VectorsWriter,
+    pub(crate) term_vectors_writer: TermVectorsWriter,
     pub(crate) doc_opstamps: Vec<Opstamp>,
     per_field_text_analyzers: Vec<TextAnalyzer>,
     term_buffer: Term,
@@ -118,6 +125,8 @@ impl SegmentWriter {
             ctx: IndexingContext::new(table_size),
             per_field_postings_writers,
             fieldnorms_writer: FieldNormsWriter::for_schema(&schema),
+            vectors_writer: VectorsWriter::for_schema(&schema),
+            term_vectors_writer: TermVectorsWriter::for_schema(&schema),
             segment_serializer,
             fast_field_writers: FastFieldsWriter::from_schema_and_tokenizer_manager(
                 &schema,
@@ -150,6 +159,8 @@ impl SegmentWriter {
             self.ctx,
             self.fast_field_writers,
             &self.fieldnorms_writer,
+            &self.vectors_writer,
+            &self.term_vectors_writer,
             self.segment_serializer,
             mapping.as_ref(),
         )?;
@@ -161,6 +172,8 @@ impl SegmentWriter {
         self.ctx.mem_usage()
             + self.fieldnorms_writer.mem_usage()
             + self.fast_field_writers.mem_usage()
+            + self.vectors_writer.mem_usage()
+            + self.term_vectors_writer.mem_usage()
             + self.segment_serializer.mem_usage()
     }
 
@@ -210,14 +223,15 @@ impl SegmentWriter {
                 FieldType::Str(_) => {
                     let mut indexing_position = IndexingPosition::default();
                     for value in values {
-                        let mut token_stream = match value {
-                            Value::PreTokStr(tok_str) => {
-                                BoxTokenStream::new(PreTokenizedStream::from(tok_str.clone()))
-                            }
+                        let (mut token_stream, text_len) = match value {
+                            Value::PreTokStr(tok_str) => (
+                                BoxTokenStream::new(PreTokenizedStream::from(tok_str.clone())),
+                                tok_str.text.len(),
+                            ),
                             Value::Str(ref text) => {
                                 let text_analyzer =
                                     &mut self.per_field_text_analyzers[field.field_id() as usize];
-                                text_analyzer.token_stream(text)
+                                (text_analyzer.token_stream(text), text.len())
                             }
                             _ => {
                                 continue;
@@ -225,13 +239,21 @@ impl SegmentWriter {
                         };
 
                         assert!(term_buffer.is_empty());
+                        let mut token_stream = self.term_vectors_writer.token_stream(
+                            field,
+                            &mut *token_stream,
+                            indexing_position.end_position,
+                            indexing_position.end_offset,
+                        );
                         postings_writer.index_text(
                             doc_id,
-                            &mut *token_stream,
+                            &mut token_stream,
                             term_buffer,
                             ctx,
                             &mut indexing_position,
                         );
+                        // Offsets are computed as if the values were joined with a space.
+                        indexing_position.end_offset += text_len as u32 + 1;
                     }
                     if field_entry.has_fieldnorms() {
                         self.fieldnorms_writer
@@ -338,6 +360,18 @@ impl SegmentWriter {
                         self.fieldnorms_writer.record(doc_id, field, num_vals);
                     }
                 }
+                // Vectors are recorded by the `VectorsWriter`.
+                FieldType::Vector(_) => {}
+                FieldType::GeoPoint(_) => {
+                    for value in values {
+                        let geo_point = value.as_geo_point().ok_or_else(make_schema_error)?;
+                        let geohash = geo_point.geohash(INDEXED_GEOHASH_PRECISION);
+                        for precision in 1..=INDEXED_GEOHASH_PRECISION {
+                            term_buffer.set_bytes(&geohash.as_bytes()[..precision]);
+                            postings_writer.subscribe(doc_id, 0u32, term_buffer, ctx);
+                        }
+                    }
+                }
             }
         }
         Ok(())
@@ -348,9 +382,11 @@ impl SegmentWriter {
     /// As a user, you should rather use `IndexWriter`'s add_document.
     pub fn add_document(&mut self, add_operation: AddOperation) -> crate::Result<()> {
         let AddOperation { document, opstamp } = add_operation;
+        self.vectors_writer.add_document(self.max_doc, &document)?;
         self.doc_opstamps.push(opstamp);
         self.fast_field_writers.add_document(&document)?;
         self.index_document(&document)?;
+        self.term_vectors_writer.end_document()?;
         let doc_writer = self.segment_serializer.get_store_writer();
         doc_writer.store(&document, &self.schema)?;
         self.max_doc += 1;
@@ -383,11 +419,14 @@ impl SegmentWriter {
 /// to the `SegmentSerializer`.
 ///
 /// `doc_id_map` is used to map to the new doc_id order.
+#[allow(clippy::too_many_arguments)]
 fn remap_and_write(
     per_field_postings_writers: &PerFieldPostingsWriter,
     ctx: IndexingContext,
     fast_field_writers: FastFieldsWriter,
     fieldnorms_writer: &FieldNormsWriter,
+    vectors_writer: &VectorsWriter,
+    term_vectors_writer: &TermVectorsWriter,
     mut serializer: SegmentSerializer,
     doc_id_map: Option<&DocIdMapping>,
 ) -> crate::Result<()> {
@@ -408,6 +447,14 @@ fn remap_and_write(
     )?;
     debug!("fastfield-serialize");
     fast_field_writers.serialize(serializer.get_fast_field_write(), doc_id_map)?;
+    if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+        debug!("vectors-serialize");
+        vectors_writer.serialize(vectors_serializer, doc_id_map)?;
+    }
+    if let Some(term_vectors_serializer) = serializer.extract_term_vectors_serializer() {
+        debug!("term-vectors-serialize");
+        term_vectors_writer.serialize(term_vectors_serializer, doc_id_map)?;
+    }
 
     // finalize temp docstore and create version, which reflects the doc_id_map
     if let Some(doc_id_map) = doc_id_map {
@@ -456,14 +503,16 @@ mod tests {
     use crate::postings::TermInfo;
     use crate::query::PhraseQuery;
     use crate::schema::{
-        IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Type, STORED, STRING, TEXT,
+        IndexRecordOption, JsonObjectOptions, Schema, TextFieldIndexing, TextOptions, Type, STORED,
+        STRING, TEXT,
     };
     use crate::store::{Compressor, StoreReader, StoreWriter};
     use crate::time::format_description::well_known::Rfc3339;
     use crate::time::OffsetDateTime;
     use crate::tokenizer::{PreTokenizedString, Token};
     use crate::{
-        DateTime, Directory, DocAddress, DocSet, Document, Index, Postings, Term, TERMINATED,
+        DateTime, Directory, DocAddress, DocSet, Document, Index, Postings, SegmentComponent, Term,
+        TERMINATED,
     };
 
     #[test]
@@ -715,6 +764,56 @@ mod tests {
         assert_eq!(postings.advance(), TERMINATED);
     }
 
+    #[test]
+    fn test_json_tokenized_with_offsets() {
+        let mut schema_builder = Schema::builder();
+        let json_options: JsonObjectOptions = JsonObjectOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+        );
+        let json_field = schema_builder.add_json_field("json", json_options);
+        let schema = schema_builder.build();
+        let json_val: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(r#"{"mykey": ["token a", "b token"], "other": "token"}"#).unwrap();
+        let doc = doc!(json_field=>json_val);
+        let index = Index::create_in_ram(schema);
+        let mut writer = index.writer_for_tests().unwrap();
+        writer.add_document(doc).unwrap();
+        writer.commit().unwrap();
+        let reader = index.reader().unwrap();
+        let searcher = reader.searcher();
+        let segment_reader = searcher.segment_reader(0u32);
+        let inv_index = segment_reader.inverted_index(json_field).unwrap();
+        let mut term = Term::with_type_and_field(Type::Json, json_field);
+        let mut json_term_writer = JsonTermWriter::wrap(&mut term, false);
+        json_term_writer.push_path_segment("mykey");
+        json_term_writer.set_str("token");
+        let mut postings = inv_index
+            .read_postings(
+                json_term_writer.term(),
+                IndexRecordOption::WithFreqsAndPositionsAndOffsets,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(postings.doc(), 0);
+        let mut offsets = Vec::new();
+        postings.offsets(&mut offsets);
+        // The offsets of the second value start after the first one and a space.
+        assert_eq!(&offsets[..], &[(0, 5), (10, 15)]);
+        json_term_writer.pop_path_segment();
+        json_term_writer.push_path_segment("other");
+        json_term_writer.set_str("token");
+        let mut postings = inv_index
+            .read_postings(
+                json_term_writer.term(),
+                IndexRecordOption::WithFreqsAndPositionsAndOffsets,
+            )
+            .unwrap()
+            .unwrap();
+        postings.offsets(&mut offsets);
+        assert_eq!(&offsets[..], &[(0, 5)]);
+    }
+
     #[test]
     fn test_json_raw_no_position() {
         let mut schema_builder = Schema::builder();
@@ -939,4 +1038,26 @@ mod tests {
             "Schema error: 'Error getting tokenizer for field: title'"
         );
     }
+
+    #[test]
+    fn test_no_vectors_files_without_vector_fields() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "world"))?;
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 1);
+        let directory = index.directory();
+        assert!(directory.exists(&segment_metas[0].relative_path(SegmentComponent::Store))?);
+        assert!(!directory.exists(&segment_metas[0].relative_path(SegmentComponent::Vectors))?);
+        assert!(!directory.exists(&segment_metas[0].relative_path(SegmentComponent::TermVectors))?);
+        Ok(())
+    }
 }
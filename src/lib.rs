@@ -156,14 +156,16 @@ pub mod query;
 pub mod schema;
 pub mod space_usage;
 pub mod store;
+pub mod term_vector;
 pub mod termdict;
+pub mod vector;
 
 /// TODO: Try not expose tantivy reader mod entirely
 pub mod reader;
 
-pub use self::reader::{IndexReader, IndexReaderBuilder, ReloadPolicy, Warmer};
+pub use self::reader::{IndexReader, IndexReaderBuilder, PointInTimeId, ReloadPolicy, Warmer};
 mod snippet;
-pub use self::snippet::{Snippet, SnippetGenerator};
+pub use self::snippet::{FragmentBoundary, Highlighter, Snippet, SnippetGenerator};
 
 mod docset;
 use std::fmt;
@@ -291,7 +293,7 @@ impl DocAddress {
 ///
 /// The id used for the segment is actually an ordinal
 /// in the list of `Segment`s held by a `Searcher`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct DocAddress {
     /// The segment ordinal id that identifies the segment
     /// hosting the document in the `Searcher` it is called from.
@@ -27,6 +27,14 @@
 //! * *VIntPosDeltas* := *VIntPosDelta*^(*P* % 128).
 //!
 //! The skip widths encoded separately makes it easy and fast to rapidly skip over n positions.
+//!
+//! If the field also records offsets, each occurrence of a term has two offset values:
+//! the delta between its start offset and the start offset of the previous occurrence within
+//! the same document, and the length of the occurrence in bytes. These values are encoded
+//! like the positions delta, and prepended to the positions of the term:
+//! * *PositionsWithOffsets* := *NumOffsetsBytes* *Offsets* *Positions*
+//! * *NumOffsetsBytes* := number of bytes of *Offsets*, encoded as a variable byte integer.
+//! * *Offsets* := the offsets values, with the same layout as *Positions*.
 mod reader;
 mod serializer;
 
@@ -129,6 +137,31 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_positions_with_offsets() -> crate::Result<()> {
+        let position_deltas: Vec<u32> = (0..300).collect();
+        let offset_deltas: Vec<u32> = (0..600).map(|i| i * 3).collect();
+        let mut positions_buffer = vec![];
+        let mut serializer = PositionSerializer::with_offsets(&mut positions_buffer);
+        serializer.write_positions_delta(&position_deltas[..100]);
+        serializer.write_offsets_delta(&offset_deltas[..200]);
+        serializer.write_positions_delta(&position_deltas[100..]);
+        serializer.write_offsets_delta(&offset_deltas[200..]);
+        serializer.close_term()?;
+        serializer.close()?;
+        let (offsets_data, positions_data) =
+            PositionReader::split_offsets(OwnedBytes::new(positions_buffer))?;
+        let mut position_reader = PositionReader::open(positions_data)?;
+        let mut offset_reader = PositionReader::open(offsets_data)?;
+        let mut positions = vec![0u32; 300];
+        position_reader.read(0, &mut positions[..]);
+        assert_eq!(positions, position_deltas);
+        let mut offsets = vec![0u32; 400];
+        offset_reader.read(200, &mut offsets[..]);
+        assert_eq!(&offsets[..], &offset_deltas[200..]);
+        Ok(())
+    }
+
     #[test]
     fn test_position_read_with_offset() -> crate::Result<()> {
         let position_deltas: Vec<u32> = (0..1000).collect();
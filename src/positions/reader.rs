@@ -54,6 +54,15 @@ impl PositionReader {
         })
     }
 
+    /// Splits the data of a term of a field recording offsets into its offsets data
+    /// and its positions data.
+    ///
+    /// Both can then be opened with [`PositionReader::open`].
+    pub fn split_offsets(mut positions_data: OwnedBytes) -> io::Result<(OwnedBytes, OwnedBytes)> {
+        let num_offsets_bytes = VInt::deserialize(&mut positions_data)?.0 as usize;
+        Ok(positions_data.split(num_offsets_bytes))
+    }
+
     fn reset(&mut self) {
         self.positions = self.original_positions.clone();
         self.bit_widths = self.original_bit_widths.clone();
@@ -8,13 +8,16 @@ use crate::postings::compression::{BlockEncoder, VIntEncoder};
 /// The PositionSerializer is in charge of serializing all of the positions
 /// of all of the terms of a given field.
 ///
+/// If the field records offsets, the offsets of a term are serialized right
+/// before its positions.
+///
 /// It is valid to call write_position_delta more than once per term.
 pub struct PositionSerializer<W: io::Write> {
     block_encoder: BlockEncoder,
     positions_wrt: CountingWriter<W>,
-    positions_buffer: Vec<u8>,
-    block: Vec<u32>,
-    bit_widths: Vec<u8>,
+    positions: DeltaStream,
+    offsets_opt: Option<DeltaStream>,
+    offsets_buffer: Vec<u8>,
 }
 
 impl<W: io::Write> PositionSerializer<W> {
@@ -23,9 +26,18 @@ impl<W: io::Write> PositionSerializer<W> {
         PositionSerializer {
             block_encoder: BlockEncoder::new(),
             positions_wrt: CountingWriter::wrap(positions_wrt),
-            positions_buffer: Vec::with_capacity(128_000),
-            block: Vec::with_capacity(128),
-            bit_widths: Vec::new(),
+            positions: DeltaStream::with_capacity(128_000),
+            offsets_opt: None,
+            offsets_buffer: Vec::new(),
+        }
+    }
+
+    /// Creates a new PositionSerializer writing positions and offsets
+    /// into the given positions_wrt.
+    pub fn with_offsets(positions_wrt: W) -> PositionSerializer<W> {
+        PositionSerializer {
+            offsets_opt: Some(DeltaStream::with_capacity(128_000)),
+            ..PositionSerializer::new(positions_wrt)
         }
     }
 
@@ -39,54 +51,101 @@ impl<W: io::Write> PositionSerializer<W> {
         self.positions_wrt.written_bytes()
     }
 
+    /// Writes all of the given positions delta.
+    pub fn write_positions_delta(&mut self, positions_delta: &[u32]) {
+        self.positions
+            .write(&mut self.block_encoder, positions_delta);
+    }
+
+    /// Writes all of the given offsets delta.
+    ///
+    /// Offsets delta are ignored if the serializer was not created
+    /// with [`PositionSerializer::with_offsets`].
+    pub fn write_offsets_delta(&mut self, offsets_delta: &[u32]) {
+        if let Some(offsets) = self.offsets_opt.as_mut() {
+            offsets.write(&mut self.block_encoder, offsets_delta);
+        }
+    }
+
+    /// Close the positions for the current term.
+    pub fn close_term(&mut self) -> io::Result<()> {
+        if let Some(offsets) = self.offsets_opt.as_mut() {
+            self.offsets_buffer.clear();
+            offsets.close_term(&mut self.block_encoder, &mut self.offsets_buffer)?;
+            VInt(self.offsets_buffer.len() as u64).serialize(&mut self.positions_wrt)?;
+            self.positions_wrt.write_all(&self.offsets_buffer)?;
+        }
+        self.positions
+            .close_term(&mut self.block_encoder, &mut self.positions_wrt)
+    }
+
+    /// Close the positions for this term and flushes the data.
+    pub fn close(mut self) -> io::Result<()> {
+        self.positions_wrt.flush()
+    }
+}
+
+/// Buffers the deltas of a term, and encodes them in blocks.
+struct DeltaStream {
+    buffer: Vec<u8>,
+    block: Vec<u32>,
+    bit_widths: Vec<u8>,
+}
+
+impl DeltaStream {
+    fn with_capacity(capacity: usize) -> DeltaStream {
+        DeltaStream {
+            buffer: Vec::with_capacity(capacity),
+            block: Vec::with_capacity(COMPRESSION_BLOCK_SIZE),
+            bit_widths: Vec::new(),
+        }
+    }
+
     fn remaining_block_len(&self) -> usize {
         COMPRESSION_BLOCK_SIZE - self.block.len()
     }
 
-    /// Writes all of the given positions delta.
-    pub fn write_positions_delta(&mut self, mut positions_delta: &[u32]) {
-        while !positions_delta.is_empty() {
+    fn write(&mut self, block_encoder: &mut BlockEncoder, mut deltas: &[u32]) {
+        while !deltas.is_empty() {
             let remaining_block_len = self.remaining_block_len();
-            let num_to_write = remaining_block_len.min(positions_delta.len());
-            self.block.extend(&positions_delta[..num_to_write]);
-            positions_delta = &positions_delta[num_to_write..];
+            let num_to_write = remaining_block_len.min(deltas.len());
+            self.block.extend(&deltas[..num_to_write]);
+            deltas = &deltas[num_to_write..];
             if self.remaining_block_len() == 0 {
-                self.flush_block();
+                self.flush_block(block_encoder);
             }
         }
     }
 
-    fn flush_block(&mut self) {
-        // encode the positions in the block
+    fn flush_block(&mut self, block_encoder: &mut BlockEncoder) {
+        // encode the deltas in the block
         if self.block.is_empty() {
             return;
         }
         if self.block.len() == COMPRESSION_BLOCK_SIZE {
             let (bit_width, block_encoded): (u8, &[u8]) =
-                self.block_encoder.compress_block_unsorted(&self.block[..]);
+                block_encoder.compress_block_unsorted(&self.block[..]);
             self.bit_widths.push(bit_width);
-            self.positions_buffer.extend(block_encoded);
+            self.buffer.extend(block_encoded);
         } else {
             debug_assert!(self.block.len() < COMPRESSION_BLOCK_SIZE);
-            let block_vint_encoded = self.block_encoder.compress_vint_unsorted(&self.block[..]);
-            self.positions_buffer.extend_from_slice(block_vint_encoded);
+            let block_vint_encoded = block_encoder.compress_vint_unsorted(&self.block[..]);
+            self.buffer.extend_from_slice(block_vint_encoded);
         }
         self.block.clear();
     }
 
-    /// Close the positions for the current term.
-    pub fn close_term(&mut self) -> io::Result<()> {
-        self.flush_block();
-        VInt(self.bit_widths.len() as u64).serialize(&mut self.positions_wrt)?;
-        self.positions_wrt.write_all(&self.bit_widths[..])?;
-        self.positions_wrt.write_all(&self.positions_buffer)?;
+    fn close_term<W: io::Write>(
+        &mut self,
+        block_encoder: &mut BlockEncoder,
+        wrt: &mut W,
+    ) -> io::Result<()> {
+        self.flush_block(block_encoder);
+        VInt(self.bit_widths.len() as u64).serialize(wrt)?;
+        wrt.write_all(&self.bit_widths[..])?;
+        wrt.write_all(&self.buffer)?;
         self.bit_widths.clear();
-        self.positions_buffer.clear();
+        self.buffer.clear();
         Ok(())
     }
-
-    /// Close the positions for this term and flushes the data.
-    pub fn close(mut self) -> io::Result<()> {
-        self.positions_wrt.flush()
-    }
 }
@@ -6,7 +6,7 @@ use crate::directory::{FileSlice, OwnedBytes};
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::compression::{BlockDecoder, VIntDecoder, COMPRESSION_BLOCK_SIZE};
 use crate::postings::{BlockInfo, FreqReadingOption, SkipReader};
-use crate::query::Bm25Weight;
+use crate::query::SimilarityWeight;
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, TERMINATED};
 
@@ -116,15 +116,15 @@ impl BlockSegmentPostings {
     /// after having called `.shallow_advance(..)`.
     ///
     /// See `TermScorer::block_max_score(..)` for more information.
-    pub fn block_max_score(
+    pub fn block_max_score<TSimilarityWeight: SimilarityWeight + ?Sized>(
         &mut self,
         fieldnorm_reader: &FieldNormReader,
-        bm25_weight: &Bm25Weight,
+        similarity_weight: &TSimilarityWeight,
     ) -> Score {
         if let Some(score) = self.block_max_score_cache {
             return score;
         }
-        if let Some(skip_reader_max_score) = self.skip_reader.block_max_score(bm25_weight) {
+        if let Some(skip_reader_max_score) = self.skip_reader.block_max_score(similarity_weight) {
             // if we are on a full block, the skip reader should have the block max information
             // for us
             self.block_max_score_cache = Some(skip_reader_max_score);
@@ -135,19 +135,19 @@ impl BlockSegmentPostings {
         if self.block_is_loaded() {
             let docs = self.doc_decoder.output_array().iter().cloned();
             let freqs = self.freq_decoder.output_array().iter().cloned();
-            let bm25_scores = docs.zip(freqs).map(|(doc, term_freq)| {
+            let scores = docs.zip(freqs).map(|(doc, term_freq)| {
                 let fieldnorm_id = fieldnorm_reader.fieldnorm_id(doc);
-                bm25_weight.score(fieldnorm_id, term_freq)
+                similarity_weight.score(fieldnorm_id, term_freq)
             });
-            let block_max_score = max_score(bm25_scores).unwrap_or(0.0);
+            let block_max_score = max_score(scores).unwrap_or(0.0);
             self.block_max_score_cache = Some(block_max_score);
             return block_max_score;
         }
-        // We do not have access to any good block max value. We return bm25_weight.max_score()
-        // as it is a valid upperbound.
+        // We do not have access to any good block max value. We return
+        // similarity_weight.max_score() as it is a valid upperbound.
         //
         // We do not cache it however, so that it gets computed when once block is loaded.
-        bm25_weight.max_score()
+        similarity_weight.max_score()
     }
 
     pub(crate) fn freq_reading_option(&self) -> FreqReadingOption {
@@ -418,7 +418,7 @@ mod tests {
         doc_ids.push(130);
         {
             let block_segments = build_block_postings(&doc_ids)?;
-            let mut docset = SegmentPostings::from_block_postings(block_segments, None);
+            let mut docset = SegmentPostings::from_block_postings(block_segments, None, None);
             assert_eq!(docset.seek(128), 129);
             assert_eq!(docset.doc(), 129);
             assert_eq!(docset.advance(), 130);
@@ -427,7 +427,7 @@ mod tests {
         }
         {
             let block_segments = build_block_postings(&doc_ids).unwrap();
-            let mut docset = SegmentPostings::from_block_postings(block_segments, None);
+            let mut docset = SegmentPostings::from_block_postings(block_segments, None, None);
             assert_eq!(docset.seek(129), 129);
             assert_eq!(docset.doc(), 129);
             assert_eq!(docset.advance(), 130);
@@ -436,7 +436,7 @@ mod tests {
         }
         {
             let block_segments = build_block_postings(&doc_ids)?;
-            let mut docset = SegmentPostings::from_block_postings(block_segments, None);
+            let mut docset = SegmentPostings::from_block_postings(block_segments, None, None);
             assert_eq!(docset.doc(), 0);
             assert_eq!(docset.seek(131), TERMINATED);
             assert_eq!(docset.doc(), TERMINATED);
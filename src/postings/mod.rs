@@ -46,13 +46,13 @@ pub mod tests {
     use crate::docset::{DocSet, TERMINATED};
     use crate::fieldnorm::FieldNormReader;
     use crate::indexer::operation::AddOperation;
-    use crate::indexer::SegmentWriter;
+    use crate::indexer::{NoMergePolicy, SegmentWriter};
     use crate::query::Scorer;
     use crate::schema::{
         Field, IndexRecordOption, Schema, Term, TextFieldIndexing, TextOptions, INDEXED, TEXT,
     };
     use crate::tokenizer::{SimpleTokenizer, MAX_TOKEN_LEN};
-    use crate::{DocId, HasLen, Score};
+    use crate::{DocId, HasLen, Score, Searcher};
 
     #[test]
     pub fn test_position_write() -> crate::Result<()> {
@@ -149,6 +149,83 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_offsets() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for i in 0..300 {
+            if i % 100 == 7 {
+                index_writer.add_document(doc!(text => "abc de abc", text => "x  abc"))?;
+            } else {
+                index_writer.add_document(doc!(text => "abc abc"))?;
+            }
+            if i == 150 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+
+        let check_offsets = |searcher: &Searcher| -> crate::Result<()> {
+            let term = Term::from_field_text(text, "abc");
+            let mut positions = Vec::new();
+            let mut offsets = Vec::new();
+            let mut num_docs = 0;
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader.inverted_index(text)?;
+                let mut postings = inverted_index
+                    .read_postings(&term, IndexRecordOption::WithFreqsAndPositionsAndOffsets)?
+                    .unwrap();
+                assert!(postings.has_offsets());
+                while postings.doc() != TERMINATED {
+                    postings.positions(&mut positions);
+                    postings.offsets(&mut offsets);
+                    if postings.term_freq() == 3 {
+                        assert_eq!(&positions[..], &[0, 2, 5]);
+                        // Offsets of the second value start after a space.
+                        assert_eq!(&offsets[..], &[(0, 3), (7, 10), (14, 17)]);
+                    } else {
+                        assert_eq!(&positions[..], &[0, 1]);
+                        assert_eq!(&offsets[..], &[(0, 3), (4, 7)]);
+                    }
+                    num_docs += 1;
+                    postings.advance();
+                }
+                // Offsets are not decoded if they are not requested.
+                let mut postings = inverted_index
+                    .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)?
+                    .unwrap();
+                assert!(!postings.has_offsets());
+                postings.positions(&mut positions);
+                postings.offsets(&mut offsets);
+                assert_eq!(&positions[..], &[0, 1]);
+                assert!(offsets.is_empty());
+            }
+            assert_eq!(num_docs, 300);
+            Ok(())
+        };
+
+        let reader = index.reader()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        check_offsets(&searcher)?;
+
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        check_offsets(&searcher)?;
+        Ok(())
+    }
+
     #[test]
     pub fn test_index_max_length_token() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
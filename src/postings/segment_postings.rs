@@ -17,6 +17,9 @@ pub struct SegmentPostings {
     pub(crate) block_cursor: BlockSegmentPostings,
     cur: usize,
     position_reader: Option<PositionReader>,
+    offset_reader: Option<PositionReader>,
+    /// Buffer the offset deltas are decoded into, reused across documents.
+    offset_deltas: Vec<u32>,
 }
 
 impl SegmentPostings {
@@ -26,6 +29,8 @@ impl SegmentPostings {
             block_cursor: BlockSegmentPostings::empty(),
             cur: 0,
             position_reader: None,
+            offset_reader: None,
+            offset_deltas: Vec::new(),
         }
     }
 
@@ -86,7 +91,7 @@ impl SegmentPostings {
             IndexRecordOption::Basic,
         )
         .unwrap();
-        SegmentPostings::from_block_postings(block_segment_postings, None)
+        SegmentPostings::from_block_postings(block_segment_postings, None, None)
     }
 
     /// Helper functions to create `SegmentPostings` for tests.
@@ -134,7 +139,7 @@ impl SegmentPostings {
             IndexRecordOption::WithFreqs,
         )
         .unwrap();
-        SegmentPostings::from_block_postings(block_segment_postings, None)
+        SegmentPostings::from_block_postings(block_segment_postings, None, None)
     }
 
     /// Reads a Segment postings from an &[u8]
@@ -145,11 +150,50 @@ impl SegmentPostings {
     pub(crate) fn from_block_postings(
         segment_block_postings: BlockSegmentPostings,
         position_reader: Option<PositionReader>,
+        offset_reader: Option<PositionReader>,
     ) -> SegmentPostings {
         SegmentPostings {
             block_cursor: segment_block_postings,
             cur: 0, // cursor within the block
             position_reader,
+            offset_reader,
+            offset_deltas: Vec::new(),
+        }
+    }
+
+    /// Returns true if the offsets of the term occurrences are available.
+    ///
+    /// Offsets are only available if the field was indexed with
+    /// [`IndexRecordOption::WithFreqsAndPositionsAndOffsets`](crate::schema::IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+    /// and if they were requested when reading the postings.
+    pub fn has_offsets(&self) -> bool {
+        self.offset_reader.is_some()
+    }
+
+    /// Fills `output` with the `(offset_from, offset_to)` byte ranges of the
+    /// occurrences of the term in the current document, in the same order as
+    /// the positions.
+    ///
+    /// Offsets are relative to the text of the field, as if the values of
+    /// a multivalued field were joined with a single space.
+    ///
+    /// If the offsets are not available, `output` is left empty.
+    pub fn offsets(&mut self, output: &mut Vec<(u32, u32)>) {
+        output.clear();
+        let term_freq = self.term_freq() as usize;
+        if let Some(offset_reader) = self.offset_reader.as_mut() {
+            let read_offset = self.block_cursor.position_offset()
+                + (self.block_cursor.freqs()[..self.cur]
+                    .iter()
+                    .cloned()
+                    .sum::<u32>() as u64);
+            self.offset_deltas.resize(term_freq * 2, 0u32);
+            offset_reader.read(read_offset * 2, &mut self.offset_deltas[..]);
+            let mut offset_from = 0u32;
+            for delta in self.offset_deltas.chunks_exact(2) {
+                offset_from = offset_from.wrapping_add(delta[0]);
+                output.push((offset_from, offset_from.wrapping_add(delta[1])));
+            }
         }
     }
 }
@@ -106,6 +106,7 @@ pub struct FieldSerializer<'a> {
     term_dictionary_builder: TermDictionaryBuilder<&'a mut CountingWriter<WritePtr>>,
     postings_serializer: PostingsSerializer<&'a mut CountingWriter<WritePtr>>,
     positions_serializer_opt: Option<PositionSerializer<&'a mut CountingWriter<WritePtr>>>,
+    offsets_delta: Vec<u32>,
     current_term_info: TermInfo,
     term_open: bool,
 }
@@ -134,7 +135,9 @@ impl<'a> FieldSerializer<'a> {
             index_record_option,
             fieldnorm_reader,
         );
-        let positions_serializer_opt = if index_record_option.has_positions() {
+        let positions_serializer_opt = if index_record_option.has_offsets() {
+            Some(PositionSerializer::with_offsets(positions_write))
+        } else if index_record_option.has_positions() {
             Some(PositionSerializer::new(positions_write))
         } else {
             None
@@ -144,6 +147,7 @@ impl<'a> FieldSerializer<'a> {
             term_dictionary_builder,
             postings_serializer,
             positions_serializer_opt,
+            offsets_delta: Vec::new(),
             current_term_info: TermInfo::default(),
             term_open: false,
         })
@@ -191,11 +195,41 @@ impl<'a> FieldSerializer<'a> {
     /// Term frequencies and positions may be ignored by the serializer depending
     /// on the configuration of the field in the `Schema`.
     pub fn write_doc(&mut self, doc_id: DocId, term_freq: u32, position_deltas: &[u32]) {
+        self.write_doc_with_offsets(doc_id, term_freq, position_deltas, &[]);
+    }
+
+    /// Serialize the information that a document contains for the current term:
+    /// its term frequency, the position deltas, and the offsets of each occurrence.
+    ///
+    /// Contrary to positions, offsets are not delta-encoded: each occurrence comes
+    /// with its `(offset_from, offset_to)` byte range.
+    ///
+    /// Offsets are ignored by the serializer if the field does not record them.
+    pub fn write_doc_with_offsets(
+        &mut self,
+        doc_id: DocId,
+        term_freq: u32,
+        position_deltas: &[u32],
+        offsets: &[(u32, u32)],
+    ) {
         self.current_term_info.doc_freq += 1;
         self.postings_serializer.write_doc(doc_id, term_freq);
         if let Some(ref mut positions_serializer) = self.positions_serializer_opt.as_mut() {
             assert_eq!(term_freq as usize, position_deltas.len());
             positions_serializer.write_positions_delta(position_deltas);
+            if self.postings_serializer.mode.has_offsets() {
+                assert_eq!(term_freq as usize, offsets.len());
+                self.offsets_delta.clear();
+                let mut prev_offset_from = 0u32;
+                for &(offset_from, offset_to) in offsets {
+                    // Tokens are not guaranteed to be sorted by offsets.
+                    self.offsets_delta
+                        .push(offset_from.wrapping_sub(prev_offset_from));
+                    self.offsets_delta.push(offset_to.wrapping_sub(offset_from));
+                    prev_offset_from = offset_from;
+                }
+                positions_serializer.write_offsets_delta(&self.offsets_delta);
+            }
         }
     }
 
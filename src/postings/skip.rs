@@ -2,7 +2,7 @@ use std::convert::TryInto;
 
 use crate::directory::OwnedBytes;
 use crate::postings::compression::{compressed_block_size, COMPRESSION_BLOCK_SIZE};
-use crate::query::Bm25Weight;
+use crate::query::SimilarityWeight;
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, TERMINATED};
 
@@ -144,13 +144,18 @@ impl SkipReader {
     //
     // The block max score is available for all full bitpacked block,
     // but no available for the last VInt encoded incomplete block.
-    pub fn block_max_score(&self, bm25_weight: &Bm25Weight) -> Option<Score> {
+    pub fn block_max_score<TSimilarityWeight: SimilarityWeight + ?Sized>(
+        &self,
+        similarity_weight: &TSimilarityWeight,
+    ) -> Option<Score> {
         match self.block_info {
             BlockInfo::BitPacked {
                 block_wand_fieldnorm_id,
                 block_wand_term_freq,
                 ..
-            } => Some(bm25_weight.score(block_wand_fieldnorm_id, block_wand_term_freq)),
+            } => Some(
+                similarity_weight.block_max_score(block_wand_fieldnorm_id, block_wand_term_freq),
+            ),
             BlockInfo::VInt { .. } => None,
         }
     }
@@ -197,7 +202,8 @@ impl SkipReader {
                     block_wand_term_freq,
                 };
             }
-            IndexRecordOption::WithFreqsAndPositions => {
+            IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsAndPositionsAndOffsets => {
                 let tf_num_bits = bytes[5];
                 let tf_sum = read_u32(&bytes[6..10]);
                 let block_wand_fieldnorm_id = bytes[10];
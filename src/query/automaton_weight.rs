@@ -14,6 +14,7 @@ use crate::{DocId, Score, TantivyError};
 pub struct AutomatonWeight<A> {
     field: Field,
     automaton: Arc<A>,
+    max_expansions: Option<u32>,
 }
 
 impl<A> AutomatonWeight<A>
@@ -26,9 +27,19 @@ where
         AutomatonWeight {
             field,
             automaton: automaton.into(),
+            max_expansions: None,
         }
     }
 
+    /// Limits the number of terms matched by the automaton in each segment.
+    ///
+    /// Building the scorer of a segment where the automaton matches more than
+    /// `max_expansions` terms returns an error.
+    pub fn limit_expansions(mut self, max_expansions: u32) -> AutomatonWeight<A> {
+        self.max_expansions = Some(max_expansions);
+        self
+    }
+
     fn automaton_stream<'a>(
         &'a self,
         term_dict: &'a TermDictionary,
@@ -50,7 +61,18 @@ where
         let inverted_index = reader.inverted_index(self.field)?;
         let term_dict = inverted_index.terms();
         let mut term_stream = self.automaton_stream(term_dict)?;
+        let mut num_expansions = 0u32;
         while term_stream.advance() {
+            if let Some(max_expansions) = self.max_expansions {
+                if num_expansions == max_expansions {
+                    return Err(TantivyError::InvalidArgument(format!(
+                        "The query matches more than {max_expansions} terms of field {:?} in a \
+                         segment",
+                        self.field
+                    )));
+                }
+            }
+            num_expansions += 1;
             let term_info = term_stream.value();
             let mut block_segment_postings = inverted_index
                 .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)?;
@@ -168,4 +190,23 @@ mod tests {
         assert_eq!(scorer.score(), 1.32);
         Ok(())
     }
+
+    #[test]
+    fn test_automaton_weight_limit_expansions() -> crate::Result<()> {
+        let index = create_index()?;
+        let field = index.schema().get_field("title").unwrap();
+        let reader = index.reader()?;
+        let searcher = reader.searcher();
+        // "abc" and "abcd" match.
+        let automaton_weight = AutomatonWeight::new(field, PrefixedByA).limit_expansions(1);
+        assert!(automaton_weight
+            .scorer(searcher.segment_reader(0u32), 1.0)
+            .is_err());
+        let automaton_weight = AutomatonWeight::new(field, PrefixedByA).limit_expansions(2);
+        let mut scorer = automaton_weight.scorer(searcher.segment_reader(0u32), 1.0)?;
+        assert_eq!(scorer.doc(), 0u32);
+        assert_eq!(scorer.advance(), 2u32);
+        assert_eq!(scorer.advance(), TERMINATED);
+        Ok(())
+    }
 }
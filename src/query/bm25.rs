@@ -1,7 +1,9 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 use crate::fieldnorm::FieldNormReader;
-use crate::query::Explanation;
+use crate::query::{Explanation, Similarity, SimilarityStatistics, SimilarityWeight};
 use crate::reader::multi_parts_statistics::MultiPartsStatistics;
 use crate::schema::Field;
 use crate::{Score, Searcher, Term};
@@ -69,15 +71,11 @@ pub(crate) fn idf(doc_freq: u64, doc_count: u64) -> Score {
     (1.0 + x).ln()
 }
 
-fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score) -> Score {
-    K1 * (1.0 - B + B * fieldnorm as Score / average_fieldnorm)
-}
-
-fn compute_tf_cache(average_fieldnorm: Score) -> [Score; 256] {
+fn compute_tf_cache(k1: Score, b: Score, average_fieldnorm: Score) -> [Score; 256] {
     let mut cache: [Score; 256] = [0.0; 256];
     for (fieldnorm_id, cache_mut) in cache.iter_mut().enumerate() {
         let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id as u8);
-        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm);
+        *cache_mut = k1 * (1.0 - b + b * fieldnorm as Score / average_fieldnorm);
     }
     cache
 }
@@ -88,13 +86,125 @@ pub struct Bm25Params {
     pub avg_fieldnorm: Score,
 }
 
+/// The Okapi BM25 [`Similarity`].
+///
+/// This is the similarity used by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bm25Similarity {
+    k1: Score,
+    b: Score,
+}
+
+impl Default for Bm25Similarity {
+    fn default() -> Self {
+        Bm25Similarity { k1: K1, b: B }
+    }
+}
+
+impl Bm25Similarity {
+    /// Creates a BM25 similarity.
+    ///
+    /// - `k1` controls the term frequency saturation (default `1.2`).
+    /// - `b` controls how much the field length normalizes the term frequency (default `0.75`).
+    pub fn new(k1: Score, b: Score) -> Bm25Similarity {
+        assert!(k1 >= 0.0, "k1 must be positive, got {k1}");
+        assert!((0.0..=1.0).contains(&b), "b must be within [0, 1], got {b}");
+        Bm25Similarity { k1, b }
+    }
+
+    /// Builds the [`Bm25Weight`] associated with the given statistics.
+    pub fn bm25_weight(&self, statistics: &SimilarityStatistics) -> Bm25Weight {
+        let idf_explain = bm25_idf_explain(statistics);
+        Bm25Weight::with_params(idf_explain, statistics.average_fieldnorm(), self.k1, self.b)
+    }
+}
+
+impl Similarity for Bm25Similarity {
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight> {
+        Arc::new(self.bm25_weight(statistics))
+    }
+}
+
+/// The BM25+ [`Similarity`].
+///
+/// BM25+ adds a constant `delta` to the term frequency component of BM25, so that
+/// matching a term in a very long field is never scored lower than not matching it at all.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bm25PlusSimilarity {
+    k1: Score,
+    b: Score,
+    delta: Score,
+}
+
+impl Default for Bm25PlusSimilarity {
+    fn default() -> Self {
+        Bm25PlusSimilarity {
+            k1: K1,
+            b: B,
+            delta: 1.0,
+        }
+    }
+}
+
+impl Bm25PlusSimilarity {
+    /// Creates a BM25+ similarity.
+    ///
+    /// `k1` and `b` have the same meaning as in [`Bm25Similarity::new`]. `delta` is the lower
+    /// bound of the term frequency component (default `1.0`).
+    pub fn new(k1: Score, b: Score, delta: Score) -> Bm25PlusSimilarity {
+        assert!(k1 >= 0.0, "k1 must be positive, got {k1}");
+        assert!((0.0..=1.0).contains(&b), "b must be within [0, 1], got {b}");
+        assert!(delta >= 0.0, "delta must be positive, got {delta}");
+        Bm25PlusSimilarity { k1, b, delta }
+    }
+}
+
+impl Similarity for Bm25PlusSimilarity {
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight> {
+        let idf_explain = bm25_idf_explain(statistics);
+        let bm25_weight =
+            Bm25Weight::with_params(idf_explain, statistics.average_fieldnorm(), self.k1, self.b);
+        Arc::new(bm25_weight.with_delta(self.delta))
+    }
+}
+
+fn bm25_idf_explain(statistics: &SimilarityStatistics) -> Explanation {
+    let total_num_docs = statistics.total_num_docs();
+    if let [term_doc_freq] = statistics.doc_freqs() {
+        one_term_idf_explain(*term_doc_freq, total_num_docs)
+    } else {
+        let idf_sum: Score = statistics
+            .doc_freqs()
+            .iter()
+            .map(|&term_doc_freq| idf(term_doc_freq, total_num_docs))
+            .sum();
+        Explanation::new("idf", idf_sum)
+    }
+}
+
+fn one_term_idf_explain(term_doc_freq: u64, total_num_docs: u64) -> Explanation {
+    let idf = idf(term_doc_freq, total_num_docs);
+    let mut idf_explain =
+        Explanation::new("idf, computed as log(1 + (N - n + 0.5) / (n + 0.5))", idf);
+    idf_explain.add_const(
+        "n, number of docs containing this term",
+        term_doc_freq as Score,
+    );
+    idf_explain.add_const("N, total number of docs", total_num_docs as Score);
+    idf_explain
+}
+
 /// A struct used for computing BM25 scores.
 #[derive(Clone)]
 pub struct Bm25Weight {
     idf_explain: Explanation,
     weight: Score,
+    delta_weight: Score,
     cache: [Score; 256],
     average_fieldnorm: Score,
+    k1: Score,
+    b: Score,
+    delta: Score,
 }
 
 impl Bm25Weight {
@@ -103,8 +213,12 @@ impl Bm25Weight {
         Bm25Weight {
             idf_explain: self.idf_explain.clone(),
             weight: self.weight * boost,
+            delta_weight: self.delta_weight * boost,
             cache: self.cache,
             average_fieldnorm: self.average_fieldnorm,
+            k1: self.k1,
+            b: self.b,
+            delta: self.delta,
         }
     }
 
@@ -113,46 +227,8 @@ impl Bm25Weight {
         statistics: &dyn Bm25StatisticsProvider,
         terms: &[Term],
     ) -> crate::Result<Bm25Weight> {
-        assert!(!terms.is_empty(), "Bm25 requires at least one term");
-        let field = terms[0].field();
-        for term in &terms[1..] {
-            assert_eq!(
-                term.field(),
-                field,
-                "All terms must belong to the same field."
-            );
-        }
-
-        // For one part calculation.
-        let mut total_num_tokens = statistics.total_num_tokens(field)?;
-        let mut total_num_docs = statistics.total_num_docs()?;
-        let mut average_fieldnorm = total_num_tokens as Score / total_num_docs as Score;
-        
-        // For multi parts calculation.
-        let multi_parts_statistics_option = statistics.get_multi_parts_statistics();
-        if multi_parts_statistics_option.is_some() {
-            let multi_parts_statistics = multi_parts_statistics_option.unwrap();
-            total_num_tokens = multi_parts_statistics.total_num_tokens(&field);
-            total_num_docs = multi_parts_statistics.total_num_docs();
-            average_fieldnorm = total_num_tokens as Score / total_num_docs as Score;
-        }
-
-        if terms.len() == 1 {
-            let term_doc_freq = statistics.doc_freq(&terms[0])?;
-            Ok(Bm25Weight::for_one_term(
-                term_doc_freq,
-                total_num_docs,
-                average_fieldnorm,
-            ))
-        } else {
-            let mut idf_sum: Score = 0.0;
-            for term in terms {
-                let term_doc_freq = statistics.doc_freq(term)?;
-                idf_sum += idf(term_doc_freq, total_num_docs);
-            }
-            let idf_explain = Explanation::new("idf", idf_sum);
-            Ok(Bm25Weight::new(idf_explain, average_fieldnorm))
-        }
+        let statistics = SimilarityStatistics::for_terms(statistics, terms)?;
+        Ok(Bm25Similarity::default().bm25_weight(&statistics))
     }
 
     /// Construct a [Bm25Weight] for a single term.
@@ -161,31 +237,43 @@ impl Bm25Weight {
         total_num_docs: u64,
         avg_fieldnorm: Score,
     ) -> Bm25Weight {
-        let idf = idf(term_doc_freq, total_num_docs);
-        let mut idf_explain =
-            Explanation::new("idf, computed as log(1 + (N - n + 0.5) / (n + 0.5))", idf);
-        idf_explain.add_const(
-            "n, number of docs containing this term",
-            term_doc_freq as Score,
-        );
-        idf_explain.add_const("N, total number of docs", total_num_docs as Score);
+        let idf_explain = one_term_idf_explain(term_doc_freq, total_num_docs);
         Bm25Weight::new(idf_explain, avg_fieldnorm)
     }
 
     pub(crate) fn new(idf_explain: Explanation, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf_explain.value() * (1.0 + K1);
+        Bm25Weight::with_params(idf_explain, average_fieldnorm, K1, B)
+    }
+
+    fn with_params(
+        idf_explain: Explanation,
+        average_fieldnorm: Score,
+        k1: Score,
+        b: Score,
+    ) -> Bm25Weight {
+        let weight = idf_explain.value() * (1.0 + k1);
         Bm25Weight {
             idf_explain,
             weight,
-            cache: compute_tf_cache(average_fieldnorm),
+            delta_weight: 0.0,
+            cache: compute_tf_cache(k1, b, average_fieldnorm),
             average_fieldnorm,
+            k1,
+            b,
+            delta: 0.0,
         }
     }
 
+    fn with_delta(mut self, delta: Score) -> Bm25Weight {
+        self.delta = delta;
+        self.delta_weight = self.idf_explain.value() * delta;
+        self
+    }
+
     /// Compute the BM25 score of a single document.
     #[inline]
     pub fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
-        self.weight * self.tf_factor(fieldnorm_id, term_freq)
+        self.weight * self.tf_factor(fieldnorm_id, term_freq) + self.delta_weight
     }
 
     /// Compute the maximum possible BM25 score given this weight.
@@ -216,8 +304,8 @@ impl Bm25Weight {
         );
 
         tf_explanation.add_const("freq, occurrences of term within document", term_freq);
-        tf_explanation.add_const("k1, term saturation parameter", K1);
-        tf_explanation.add_const("b, length normalization parameter", B);
+        tf_explanation.add_const("k1, term saturation parameter", self.k1);
+        tf_explanation.add_const("b, length normalization parameter", self.b);
         tf_explanation.add_const(
             "dl, length of field",
             FieldNormReader::id_to_fieldnorm(fieldnorm_id) as Score,
@@ -225,13 +313,49 @@ impl Bm25Weight {
         tf_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
 
         let mut explanation = Explanation::new("TermQuery, product of...", score);
-        explanation.add_detail(Explanation::new("(K1+1)", K1 + 1.0));
+        explanation.add_detail(Explanation::new("(K1+1)", self.k1 + 1.0));
         explanation.add_detail(self.idf_explain.clone());
         explanation.add_detail(tf_explanation);
+        if self.delta > 0.0 {
+            explanation.add_const("delta, BM25+ lower bound of the tf component", self.delta);
+        }
         explanation
     }
 }
 
+impl SimilarityWeight for Bm25Weight {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        Bm25Weight::score(self, fieldnorm_id, term_freq)
+    }
+
+    fn max_score(&self) -> Score {
+        Bm25Weight::max_score(self)
+    }
+
+    // The block wand information stored at indexing time is precisely the
+    // `(fieldnorm_id, term_freq)` pair maximizing the BM25 score with the default
+    // parameters. With other parameters, another pair of the block may score higher.
+    fn block_max_score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        if self.k1 == K1 && self.b == B {
+            Bm25Weight::score(self, fieldnorm_id, term_freq)
+        } else {
+            Bm25Weight::max_score(self)
+        }
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        Bm25Weight::explain(self, fieldnorm_id, term_freq)
+    }
+
+    fn boost_by(&self, boost: Score) -> Arc<dyn SimilarityWeight> {
+        Arc::new(Bm25Weight::boost_by(self, boost))
+    }
+
+    fn as_bm25(&self) -> Option<&Bm25Weight> {
+        Some(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
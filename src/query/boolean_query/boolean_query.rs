@@ -1,5 +1,7 @@
 use super::boolean_weight::BooleanWeight;
-use crate::query::{EnableScoring, Occur, Query, SumWithCoordsCombiner, TermQuery, Weight};
+use crate::query::{
+    EnableScoring, MatchPattern, Occur, Query, SumWithCoordsCombiner, TermQuery, Weight,
+};
 use crate::schema::{IndexRecordOption, Term};
 
 /// The boolean query returns a set of documents
@@ -161,6 +163,14 @@ impl Query for BooleanQuery {
         }
     }
 
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        for (occur, subquery) in &self.subqueries {
+            if *occur != Occur::MustNot {
+                subquery.match_patterns(visitor);
+            }
+        }
+    }
+
 }
 
 impl BooleanQuery {
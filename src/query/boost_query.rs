@@ -2,7 +2,7 @@ use std::fmt;
 
 use crate::docset::BUFFER_LEN;
 use crate::fastfield::AliveBitSet;
-use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::query::{EnableScoring, Explanation, MatchPattern, Query, Scorer, Weight};
 use crate::{DocId, DocSet, Score, SegmentReader, Term};
 
 /// `BoostQuery` is a wrapper over a query used to boost its score.
@@ -51,6 +51,10 @@ impl Query for BoostQuery {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.query.query_terms(visitor)
     }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.query.match_patterns(visitor);
+    }
 }
 
 /// Weight associated to the BoostQuery.
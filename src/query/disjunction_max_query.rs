@@ -1,4 +1,6 @@
-use crate::query::{BooleanWeight, DisjunctionMaxCombiner, EnableScoring, Occur, Query, Weight};
+use crate::query::{
+    BooleanWeight, DisjunctionMaxCombiner, EnableScoring, MatchPattern, Occur, Query, Weight,
+};
 use crate::{Score, Term};
 
 /// The disjunction max query returns documents matching one or more wrapped queries,
@@ -108,6 +110,12 @@ impl Query for DisjunctionMaxQuery {
             disjunct.query_terms(visitor);
         }
     }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        for disjunct in &self.disjuncts {
+            disjunct.match_patterns(visitor);
+        }
+    }
 }
 
 impl DisjunctionMaxQuery {
@@ -0,0 +1,359 @@
+use columnar::ColumnIndex;
+use common::BitSet;
+
+use crate::core::json_utils::JsonTermWriter;
+use crate::core::SegmentReader;
+use crate::error::TantivyError;
+use crate::query::explanation::does_not_match;
+use crate::query::{BitSetDocSet, ConstScorer, EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::term::{JSON_END_OF_PATH, JSON_PATH_SEGMENT_SEP};
+use crate::schema::{Field, FieldType, IndexRecordOption, Term};
+use crate::{DocId, Score};
+
+/// An Exists Query matches all of the documents having a value in a field.
+///
+/// Documents missing a value can be matched by excluding the `ExistsQuery` from an
+/// [`AllQuery`](crate::query::AllQuery), in a [`BooleanQuery`](crate::query::BooleanQuery).
+///
+/// The field name can point to a path in a json field, such as `attributes.color`. A json
+/// path has a value if it, or one of its sub-paths, has a value: `attributes` matches
+/// `{"attributes": {"color": "red"}}`.
+///
+/// The field needs to be either fast or indexed.
+/// - For a fast field, the documents are read from the column index.
+/// - For an indexed field, the documents are read from the fieldnorms if available, or from the
+///   posting lists of all of the terms of the field otherwise.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::{AllQuery, BooleanQuery, ExistsQuery, Occur, Query};
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let year = schema_builder.add_u64_field("year", FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer = index.writer(15_000_000)?;
+///     index_writer.add_document(doc!(
+///         title => "The Name of the Wind",
+///         year => 2007u64,
+///     ))?;
+///     index_writer.add_document(doc!(
+///         title => "The Diary of Muadib",
+///     ))?;
+///     index_writer.commit()?;
+/// }
+///
+/// let reader = index.reader()?;
+/// let searcher = reader.searcher();
+///
+/// let query = ExistsQuery::new("year".to_string());
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// let missing_query = BooleanQuery::new(vec![
+///     (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+///     (Occur::MustNot, Box::new(query)),
+/// ]);
+/// assert_eq!(searcher.search(&missing_query, &Count)?, 1);
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExistsQuery {
+    field_name: String,
+}
+
+impl ExistsQuery {
+    /// Creates a new `ExistsQuery` for the given field name, or json path.
+    pub fn new(field_name: String) -> ExistsQuery {
+        ExistsQuery { field_name }
+    }
+
+    /// The name of the field, or the json path, targeted by the query.
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+}
+
+impl Query for ExistsQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let schema = enable_scoring.schema();
+        let (field, json_path) = schema
+            .find_field(&self.field_name)
+            .ok_or_else(|| TantivyError::FieldNotFound(self.field_name.clone()))?;
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.is_fast() {
+            return Ok(Box::new(ExistsWeight::FastField {
+                field_name: self.field_name.clone(),
+            }));
+        }
+        if !field_entry.is_indexed() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is neither fast nor indexed",
+                field_entry.name()
+            )));
+        }
+        let weight = match field_entry.field_type() {
+            FieldType::JsonObject(json_options) => {
+                let mut term = Term::with_capacity(json_path.len());
+                let json_term_writer = JsonTermWriter::from_field_and_json_path(
+                    field,
+                    json_path,
+                    json_options.is_expand_dots_enabled(),
+                    &mut term,
+                );
+                // The path is followed by a separator, unless it is empty.
+                let mut path = json_term_writer.term().serialized_value_bytes().to_vec();
+                path.pop();
+                ExistsWeight::Postings {
+                    field,
+                    json_path_opt: Some(path).filter(|path| !path.is_empty()),
+                }
+            }
+            _ => ExistsWeight::Fieldnorms { field },
+        };
+        Ok(Box::new(weight))
+    }
+}
+
+/// Weight of an [`ExistsQuery`], depending on the way its field is indexed.
+enum ExistsWeight {
+    FastField {
+        field_name: String,
+    },
+    Fieldnorms {
+        field: Field,
+    },
+    Postings {
+        field: Field,
+        json_path_opt: Option<Vec<u8>>,
+    },
+}
+
+impl ExistsWeight {
+    fn doc_bitset(&self, reader: &SegmentReader) -> crate::Result<BitSet> {
+        let max_doc = reader.max_doc();
+        let mut doc_bitset = BitSet::with_max_value(max_doc);
+        match self {
+            ExistsWeight::FastField { field_name } => {
+                let column_handles = reader
+                    .fast_fields()
+                    .dynamic_column_handles_with_subpaths(field_name)?;
+                for column_handle in column_handles {
+                    let column = column_handle.open()?;
+                    insert_docs_with_value(column.column_index(), max_doc, &mut doc_bitset);
+                }
+            }
+            ExistsWeight::Fieldnorms { field } => {
+                if let Some(fieldnorm_reader) = reader.fieldnorms_readers().get_field(*field)? {
+                    for doc in 0..max_doc {
+                        if fieldnorm_reader.fieldnorm_id(doc) != 0 {
+                            doc_bitset.insert(doc);
+                        }
+                    }
+                } else {
+                    insert_docs_with_terms(reader, *field, None, &mut doc_bitset)?;
+                }
+            }
+            ExistsWeight::Postings {
+                field,
+                json_path_opt,
+            } => {
+                insert_docs_with_terms(reader, *field, json_path_opt.as_deref(), &mut doc_bitset)?;
+            }
+        }
+        Ok(doc_bitset)
+    }
+}
+
+fn insert_docs_with_value(column_index: &ColumnIndex, max_doc: DocId, doc_bitset: &mut BitSet) {
+    match column_index {
+        ColumnIndex::Empty { .. } => {}
+        ColumnIndex::Full => {
+            for doc in 0..max_doc {
+                doc_bitset.insert(doc);
+            }
+        }
+        ColumnIndex::Optional(optional_index) => {
+            for doc in optional_index.iter_rows() {
+                doc_bitset.insert(doc);
+            }
+        }
+        ColumnIndex::Multivalued(_) => {
+            for doc in 0..max_doc {
+                if column_index.has_value(doc) {
+                    doc_bitset.insert(doc);
+                }
+            }
+        }
+    }
+}
+
+/// Inserts the documents containing any term of the field, or any term of the json path
+/// and of its sub-paths.
+fn insert_docs_with_terms(
+    reader: &SegmentReader,
+    field: Field,
+    json_path_opt: Option<&[u8]>,
+    doc_bitset: &mut BitSet,
+) -> crate::Result<()> {
+    let inverted_index = reader.inverted_index(field)?;
+    let term_dict = inverted_index.terms();
+    let mut term_stream = if let Some(json_path) = json_path_opt {
+        // The terms of the path and of its sub-paths start with the path, followed either by
+        // the end of path marker `\0`, or by a path separator `\1`.
+        let mut lower_bound = json_path.to_vec();
+        lower_bound.push(JSON_END_OF_PATH);
+        let mut upper_bound = json_path.to_vec();
+        upper_bound.push(JSON_PATH_SEGMENT_SEP + 1);
+        term_dict
+            .range()
+            .ge(&lower_bound)
+            .lt(&upper_bound)
+            .into_stream()?
+    } else {
+        term_dict.stream()?
+    };
+    while term_stream.advance() {
+        let mut block_segment_postings = inverted_index
+            .read_block_postings_from_terminfo(term_stream.value(), IndexRecordOption::Basic)?;
+        loop {
+            let docs = block_segment_postings.docs();
+            if docs.is_empty() {
+                break;
+            }
+            for &doc in docs {
+                doc_bitset.insert(doc);
+            }
+            block_segment_postings.advance();
+        }
+    }
+    Ok(())
+}
+
+impl Weight for ExistsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let doc_bitset = BitSetDocSet::from(self.doc_bitset(reader)?);
+        Ok(Box::new(ConstScorer::new(doc_bitset, boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("ExistsQuery", 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExistsQuery;
+    use crate::collector::Count;
+    use crate::query::{AllQuery, BooleanQuery, Occur, Query};
+    use crate::schema::{
+        NumericOptions, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, STRING, TEXT,
+    };
+    use crate::{Index, IndexWriter, Searcher, TantivyError};
+
+    fn count_exists(searcher: &Searcher, field_name: &str) -> crate::Result<usize> {
+        searcher.search(&ExistsQuery::new(field_name.to_string()), &Count)
+    }
+
+    #[test]
+    fn test_exists_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let fast = schema_builder.add_u64_field("fast", FAST);
+        let multi_fast = schema_builder.add_text_field("multi_fast", STRING | FAST);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let without_fieldnorms = schema_builder.add_text_field(
+            "without_fieldnorms",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("default")
+                    .set_fieldnorms(false),
+            ),
+        );
+        let indexed =
+            schema_builder.add_i64_field("indexed", NumericOptions::default().set_indexed());
+        schema_builder.add_u64_field("stored", STORED);
+        schema_builder.add_f64_field("numeric", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            fast => 1u64,
+            multi_fast => "a",
+            multi_fast => "b",
+            text => "hello",
+        ))?;
+        index_writer.add_document(doc!(
+            fast => 2u64,
+            without_fieldnorms => "hello",
+            indexed => -1i64,
+        ))?;
+        index_writer.add_document(doc!(multi_fast => "c", text => ""))?;
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(count_exists(&searcher, "fast")?, 2);
+        assert_eq!(count_exists(&searcher, "multi_fast")?, 2);
+        assert_eq!(count_exists(&searcher, "text")?, 1);
+        assert_eq!(count_exists(&searcher, "without_fieldnorms")?, 1);
+        assert_eq!(count_exists(&searcher, "indexed")?, 1);
+        assert_eq!(count_exists(&searcher, "numeric")?, 0);
+        assert!(matches!(
+            count_exists(&searcher, "stored"),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert!(matches!(
+            count_exists(&searcher, "missing"),
+            Err(TantivyError::FieldNotFound(_))
+        ));
+        let missing_fast_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+            (
+                Occur::MustNot,
+                Box::new(ExistsQuery::new("fast".to_string())),
+            ),
+        ]);
+        assert_eq!(searcher.search(&missing_fast_query, &Count)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_query_json() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_json_field("indexed", TEXT);
+        schema_builder.add_json_field("fast", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let schema = index.schema();
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for json in [
+            r#"{"color": "red", "size": {"width": 3}}"#,
+            r#"{"color": "blue", "colors": ["blue"]}"#,
+            r#"{"size": {"height": 2}}"#,
+            r#"{}"#,
+        ] {
+            let doc =
+                schema.parse_document(&format!(r#"{{"indexed": {json}, "fast": {json}}}"#))?;
+            index_writer.add_document(doc)?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        for field_name in ["indexed", "fast"] {
+            let count = |path: &str| count_exists(&searcher, &format!("{field_name}{path}"));
+            assert_eq!(count("")?, 3);
+            assert_eq!(count(".color")?, 2);
+            assert_eq!(count(".colors")?, 1);
+            assert_eq!(count(".size")?, 2);
+            assert_eq!(count(".size.width")?, 1);
+            assert_eq!(count(".size.depth")?, 0);
+            assert_eq!(count(".missing")?, 0);
+        }
+        Ok(())
+    }
+}
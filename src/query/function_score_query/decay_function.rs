@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use super::score_function::NumericalColumn;
+use super::{ScoreFunction, SegmentScoreFunction};
+use crate::{DateTime, DocId, Score, SegmentReader};
+
+/// Shape of the curve of a [`DecayFunction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecayKind {
+    /// Normal decay: `decay ^ ((distance / scale) ^ 2)`.
+    Gauss,
+    /// Exponential decay: `decay ^ (distance / scale)`.
+    Exp,
+    /// Linear decay: `max(0, 1 - (1 - decay) * distance / scale)`.
+    Linear,
+}
+
+/// Scores documents depending on the distance between the value of a numerical fast field, and
+/// an origin, such as the current date.
+///
+/// The score is `1` at the origin, and decreases with the distance to the origin, following the
+/// curve of a [`DecayKind`]. It is `decay`, 0.5 by default, at a distance of `scale`.
+/// An `offset` can be set so that the documents within `offset` of the origin are not penalized:
+/// the distance used in the formula is then `max(0, |value - origin| - offset)`.
+///
+/// For a multivalued field, the value closest to the origin is used. The score of a document
+/// without any value is `1`.
+#[derive(Clone, Debug)]
+pub struct DecayFunction {
+    kind: DecayKind,
+    field: String,
+    origin: f64,
+    scale: f64,
+    offset: f64,
+    decay: f64,
+}
+
+impl DecayFunction {
+    /// Creates a `DecayFunction` over the values of a numerical fast field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is not strictly positive.
+    pub fn new(kind: DecayKind, field: String, origin: f64, scale: f64) -> DecayFunction {
+        assert!(
+            scale > 0.0,
+            "The scale of a decay function must be positive"
+        );
+        DecayFunction {
+            kind,
+            field,
+            origin,
+            scale,
+            offset: 0.0,
+            decay: 0.5,
+        }
+    }
+
+    /// Creates a `DecayFunction` over the values of a date fast field.
+    ///
+    /// Distances between dates are expressed in nanoseconds, including the
+    /// [`offset`](DecayFunction::with_offset).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is zero.
+    pub fn for_date(
+        kind: DecayKind,
+        field: String,
+        origin: DateTime,
+        scale: Duration,
+    ) -> DecayFunction {
+        let origin = origin.into_timestamp_nanos() as f64;
+        DecayFunction::new(kind, field, origin, scale.as_nanos() as f64)
+    }
+
+    /// Sets the distance to the origin within which documents are not penalized.
+    pub fn with_offset(mut self, offset: f64) -> DecayFunction {
+        self.offset = offset.max(0.0);
+        self
+    }
+
+    /// Sets the score of the documents at a distance `scale` from the origin, 0.5 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `decay` is not strictly between 0 and 1.
+    pub fn with_decay(mut self, decay: f64) -> DecayFunction {
+        assert!(
+            decay > 0.0 && decay < 1.0,
+            "The decay of a decay function must be in ]0, 1["
+        );
+        self.decay = decay;
+        self
+    }
+
+    fn score(&self, value: f64) -> Score {
+        let distance = ((value - self.origin).abs() - self.offset).max(0.0);
+        let normalized_distance = distance / self.scale;
+        let score = match self.kind {
+            DecayKind::Gauss => self.decay.powf(normalized_distance * normalized_distance),
+            DecayKind::Exp => self.decay.powf(normalized_distance),
+            DecayKind::Linear => (1.0 - (1.0 - self.decay) * normalized_distance).max(0.0),
+        };
+        score as Score
+    }
+}
+
+impl ScoreFunction for DecayFunction {
+    fn segment_function(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Box<dyn SegmentScoreFunction>> {
+        Ok(Box::new(SegmentDecayFunction {
+            column_opt: NumericalColumn::open(segment_reader, &self.field)?,
+            function: self.clone(),
+        }))
+    }
+}
+
+struct SegmentDecayFunction {
+    column_opt: Option<NumericalColumn>,
+    function: DecayFunction,
+}
+
+impl SegmentScoreFunction for SegmentDecayFunction {
+    fn score(&mut self, doc: DocId) -> Score {
+        let column = if let Some(column) = self.column_opt.as_ref() {
+            column
+        } else {
+            return 1.0;
+        };
+        column
+            .values_for_doc(doc)
+            .map(|value| self.function.score(value))
+            .reduce(Score::max)
+            .unwrap_or(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DecayFunction, DecayKind};
+    use crate::{assert_nearly_equals, DateTime};
+
+    #[test]
+    fn test_decay_function_score() {
+        let gauss = DecayFunction::new(DecayKind::Gauss, "price".to_string(), 10.0, 5.0);
+        assert_nearly_equals!(gauss.score(10.0), 1.0);
+        assert_nearly_equals!(gauss.score(15.0), 0.5);
+        assert_nearly_equals!(gauss.score(0.0), 0.0625);
+        let exp = DecayFunction::new(DecayKind::Exp, "price".to_string(), 10.0, 5.0);
+        assert_nearly_equals!(exp.score(5.0), 0.5);
+        assert_nearly_equals!(exp.score(20.0), 0.25);
+        let linear = DecayFunction::new(DecayKind::Linear, "price".to_string(), 10.0, 5.0)
+            .with_offset(1.0)
+            .with_decay(0.2);
+        assert_nearly_equals!(linear.score(11.0), 1.0);
+        assert_nearly_equals!(linear.score(16.0), 0.2);
+        assert_nearly_equals!(linear.score(4.0), 0.2);
+        assert_nearly_equals!(linear.score(100.0), 0.0);
+    }
+
+    #[test]
+    fn test_decay_function_for_date() {
+        let now = DateTime::from_timestamp_secs(1_700_000_000);
+        let one_day = Duration::from_secs(24 * 3600);
+        let exp = DecayFunction::for_date(DecayKind::Exp, "date".to_string(), now, one_day);
+        let two_days_ago = DateTime::from_timestamp_secs(1_700_000_000 - 2 * 24 * 3600);
+        assert_nearly_equals!(exp.score(two_days_ago.into_timestamp_nanos() as f64), 0.25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decay_function_invalid_decay() {
+        DecayFunction::new(DecayKind::Exp, "price".to_string(), 0.0, 1.0).with_decay(1.0);
+    }
+}
@@ -0,0 +1,139 @@
+use super::score_function::NumericalColumn;
+use super::{ScoreFunction, SegmentScoreFunction};
+use crate::{DocId, Score, SegmentReader};
+
+/// Modifier applied to the value of a [`FieldValueFactor`], once multiplied by its factor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldValueModifier {
+    /// The value is used as is.
+    None,
+    /// `log10(1 + value)`
+    Log1p,
+    /// `ln(1 + value)`
+    Ln1p,
+    /// `sqrt(value)`
+    Sqrt,
+    /// `value * value`
+    Square,
+    /// `1 / value`
+    Reciprocal,
+}
+
+impl FieldValueModifier {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            FieldValueModifier::None => value,
+            FieldValueModifier::Log1p => value.ln_1p() / std::f64::consts::LN_10,
+            FieldValueModifier::Ln1p => value.ln_1p(),
+            FieldValueModifier::Sqrt => value.sqrt(),
+            FieldValueModifier::Square => value * value,
+            FieldValueModifier::Reciprocal => value.recip(),
+        }
+    }
+}
+
+/// Scores documents with the value of a numerical fast field, such as a number of likes.
+///
+/// The score is `modifier(factor * value)`. It defaults to `value`.
+///
+/// For a multivalued field, the first value of the document is used. The score of a document
+/// without any value is computed from the [`missing`](FieldValueFactor::with_missing) value if
+/// there is one, and is `1` otherwise. Negative or undefined scores are replaced by `0`.
+#[derive(Clone, Debug)]
+pub struct FieldValueFactor {
+    field: String,
+    factor: f64,
+    modifier: FieldValueModifier,
+    missing: Option<f64>,
+}
+
+impl FieldValueFactor {
+    /// Creates a `FieldValueFactor` over the values of a numerical or date fast field.
+    pub fn new(field: String) -> FieldValueFactor {
+        FieldValueFactor {
+            field,
+            factor: 1.0,
+            modifier: FieldValueModifier::None,
+            missing: None,
+        }
+    }
+
+    /// Sets the factor by which the value is multiplied.
+    pub fn with_factor(mut self, factor: f64) -> FieldValueFactor {
+        self.factor = factor;
+        self
+    }
+
+    /// Sets the modifier applied to the value, once multiplied by the factor.
+    pub fn with_modifier(mut self, modifier: FieldValueModifier) -> FieldValueFactor {
+        self.modifier = modifier;
+        self
+    }
+
+    /// Sets the value used for the documents without any value.
+    pub fn with_missing(mut self, missing: f64) -> FieldValueFactor {
+        self.missing = Some(missing);
+        self
+    }
+
+    fn score(&self, value_opt: Option<f64>) -> Score {
+        let value = if let Some(value) = value_opt.or(self.missing) {
+            value
+        } else {
+            return 1.0;
+        };
+        // `f64::max` ignores NaN.
+        self.modifier.apply(self.factor * value).max(0.0) as Score
+    }
+}
+
+impl ScoreFunction for FieldValueFactor {
+    fn segment_function(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Box<dyn SegmentScoreFunction>> {
+        Ok(Box::new(SegmentFieldValueFactor {
+            column_opt: NumericalColumn::open(segment_reader, &self.field)?,
+            function: self.clone(),
+        }))
+    }
+}
+
+struct SegmentFieldValueFactor {
+    column_opt: Option<NumericalColumn>,
+    function: FieldValueFactor,
+}
+
+impl SegmentScoreFunction for SegmentFieldValueFactor {
+    fn score(&mut self, doc: DocId) -> Score {
+        let value_opt = self
+            .column_opt
+            .as_ref()
+            .and_then(|column| column.values_for_doc(doc).next());
+        self.function.score(value_opt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldValueFactor, FieldValueModifier};
+    use crate::assert_nearly_equals;
+
+    #[test]
+    fn test_field_value_factor_score() {
+        let function = FieldValueFactor::new("likes".to_string());
+        assert_nearly_equals!(function.score(Some(3.0)), 3.0);
+        assert_nearly_equals!(function.score(None), 1.0);
+        assert_nearly_equals!(function.score(Some(-3.0)), 0.0);
+        let function = function
+            .with_factor(3.0)
+            .with_modifier(FieldValueModifier::Log1p)
+            .with_missing(0.0);
+        assert_nearly_equals!(function.score(Some(3.0)), 1.0);
+        assert_nearly_equals!(function.score(None), 0.0);
+        assert_nearly_equals!(function.score(Some(-1.0)), 0.0);
+        let function = FieldValueFactor::new("likes".to_string())
+            .with_modifier(FieldValueModifier::Reciprocal);
+        assert_nearly_equals!(function.score(Some(4.0)), 0.25);
+    }
+}
@@ -0,0 +1,399 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::{ScoreFunction, SegmentScoreFunction};
+use crate::fastfield::AliveBitSet;
+use crate::query::{EnableScoring, Explanation, MatchPattern, Query, Scorer, Weight};
+use crate::{DocId, DocSet, Score, SegmentReader, Term};
+
+/// Defines how the score of the query wrapped in a [`FunctionScoreQuery`] is combined with the
+/// score of its functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreMode {
+    /// The query score is multiplied by the function score.
+    Multiply,
+    /// The function score is added to the query score.
+    Sum,
+    /// The query score is replaced by the function score.
+    Replace,
+    /// The maximum of the query score and of the function score is used.
+    Max,
+}
+
+impl ScoreMode {
+    fn combine(self, query_score: Score, function_score: Score) -> Score {
+        match self {
+            ScoreMode::Multiply => query_score * function_score,
+            ScoreMode::Sum => query_score + function_score,
+            ScoreMode::Replace => function_score,
+            ScoreMode::Max => query_score.max(function_score),
+        }
+    }
+}
+
+/// `FunctionScoreQuery` is a wrapper over a query, combining its score with the score computed
+/// by some [`ScoreFunction`]s, typically from the value of fast fields.
+///
+/// The document set matched by the `FunctionScoreQuery` is strictly the same as the underlying
+/// query. The function score of a document is the product of the scores of the functions, `1`
+/// if there are none. It is combined with the score of the underlying query according to the
+/// [`ScoreMode`], [`ScoreMode::Multiply`] by default.
+///
+/// Unlike a collector tweaking the scores, a `FunctionScoreQuery` can be nested in other
+/// queries, such as a [`BooleanQuery`](crate::query::BooleanQuery).
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{FieldValueFactor, FieldValueModifier, FunctionScoreQuery, TermQuery};
+/// use tantivy::schema::{IndexRecordOption, Schema, FAST, TEXT};
+/// use tantivy::{doc, DocAddress, Index, Term};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let likes = schema_builder.add_u64_field("likes", FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+///     index_writer.add_document(doc!(
+///         title => "The Name of the Wind",
+///         likes => 10u64,
+///     ))?;
+///     index_writer.add_document(doc!(
+///         title => "The Wind-Up Bird Chronicle",
+///         likes => 1000u64,
+///     ))?;
+///     index_writer.commit()?;
+/// }
+///
+/// let reader = index.reader()?;
+/// let searcher = reader.searcher();
+///
+/// let query = TermQuery::new(
+///     Term::from_field_text(title, "wind"),
+///     IndexRecordOption::Basic,
+/// );
+/// let popularity = FieldValueFactor::new("likes".to_string())
+///     .with_modifier(FieldValueModifier::Log1p);
+/// let query = FunctionScoreQuery::new(Box::new(query), vec![Box::new(popularity)]);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+/// assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct FunctionScoreQuery {
+    query: Box<dyn Query>,
+    functions: Vec<Arc<dyn ScoreFunction>>,
+    score_mode: ScoreMode,
+}
+
+impl FunctionScoreQuery {
+    /// Creates a new `FunctionScoreQuery`.
+    pub fn new(
+        query: Box<dyn Query>,
+        functions: Vec<Box<dyn ScoreFunction>>,
+    ) -> FunctionScoreQuery {
+        FunctionScoreQuery {
+            query,
+            functions: functions.into_iter().map(Arc::from).collect(),
+            score_mode: ScoreMode::Multiply,
+        }
+    }
+
+    /// Sets the way the score of the query is combined with the score of the functions.
+    pub fn set_score_mode(&mut self, score_mode: ScoreMode) {
+        self.score_mode = score_mode;
+    }
+}
+
+impl Clone for FunctionScoreQuery {
+    fn clone(&self) -> Self {
+        FunctionScoreQuery {
+            query: self.query.box_clone(),
+            functions: self.functions.clone(),
+            score_mode: self.score_mode,
+        }
+    }
+}
+
+impl fmt::Debug for FunctionScoreQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "FunctionScore(query={:?}, functions={:?}, score_mode={:?})",
+            self.query, self.functions, self.score_mode
+        )
+    }
+}
+
+impl Query for FunctionScoreQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let weight = self.query.weight(enable_scoring)?;
+        if !enable_scoring.is_scoring_enabled() {
+            return Ok(weight);
+        }
+        Ok(Box::new(FunctionScoreWeight {
+            weight,
+            functions: self.functions.clone(),
+            score_mode: self.score_mode,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.query.match_patterns(visitor);
+    }
+}
+
+/// Weight associated to the `FunctionScoreQuery`.
+struct FunctionScoreWeight {
+    weight: Box<dyn Weight>,
+    functions: Vec<Arc<dyn ScoreFunction>>,
+    score_mode: ScoreMode,
+}
+
+impl FunctionScoreWeight {
+    fn segment_functions(
+        &self,
+        reader: &SegmentReader,
+    ) -> crate::Result<Vec<Box<dyn SegmentScoreFunction>>> {
+        self.functions
+            .iter()
+            .map(|function| function.segment_function(reader))
+            .collect()
+    }
+}
+
+impl Weight for FunctionScoreWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(Box::new(FunctionScorer {
+            scorer: self.weight.scorer(reader, boost)?,
+            functions: self.segment_functions(reader)?,
+            score_mode: self.score_mode,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let query_explanation = self.weight.explain(reader, doc)?;
+        let function_scores: Vec<Score> = self
+            .segment_functions(reader)?
+            .iter_mut()
+            .map(|segment_function| segment_function.score(doc))
+            .collect();
+        let function_score: Score = function_scores.iter().product();
+        let mut function_explanation = Explanation::new("Product of the functions", function_score);
+        for (function, score) in self.functions.iter().zip(function_scores) {
+            function_explanation.add_const(format!("{function:?}"), score);
+        }
+        let score = self
+            .score_mode
+            .combine(query_explanation.value(), function_score);
+        let mut explanation = Explanation::new(
+            format!("FunctionScore ({:?}) of ...", self.score_mode),
+            score,
+        );
+        explanation.add_detail(query_explanation);
+        explanation.add_detail(function_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+struct FunctionScorer {
+    scorer: Box<dyn Scorer>,
+    functions: Vec<Box<dyn SegmentScoreFunction>>,
+    score_mode: ScoreMode,
+}
+
+impl DocSet for FunctionScorer {
+    fn advance(&mut self) -> DocId {
+        self.scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
+        self.scorer.count(alive_bitset)
+    }
+
+    fn count_including_deleted(&mut self) -> u32 {
+        self.scorer.count_including_deleted()
+    }
+}
+
+impl Scorer for FunctionScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.scorer.doc();
+        let function_score: Score = self
+            .functions
+            .iter_mut()
+            .map(|function| function.score(doc))
+            .product();
+        let query_score = if self.score_mode == ScoreMode::Replace {
+            0.0
+        } else {
+            self.scorer.score()
+        };
+        self.score_mode.combine(query_score, function_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FunctionScoreQuery, ScoreMode};
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{
+        BooleanQuery, ConstScoreQuery, DecayFunction, DecayKind, FieldValueFactor, Occur, Query,
+        RandomScore, ScoreFunction, TermQuery,
+    };
+    use crate::schema::{Field, IndexRecordOption, Schema, FAST, TEXT};
+    use crate::{assert_nearly_equals, DocAddress, Index, IndexWriter, Term};
+
+    fn create_index() -> crate::Result<(Index, Field)> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let likes = schema_builder.add_u64_field("likes", FAST);
+        let price = schema_builder.add_f64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a b", likes => 1u64, price => 10.0))?;
+        index_writer.add_document(doc!(text => "a", likes => 4u64, price => 20.0))?;
+        index_writer.add_document(doc!(text => "a c", price => 15.0))?;
+        index_writer.add_document(doc!(text => "b"))?;
+        index_writer.commit()?;
+        Ok((index, text))
+    }
+
+    fn function_score_query(
+        text: Field,
+        functions: Vec<Box<dyn ScoreFunction>>,
+        score_mode: ScoreMode,
+    ) -> FunctionScoreQuery {
+        let term_query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+        let const_score_query = ConstScoreQuery::new(Box::new(term_query), 1.0);
+        let mut query = FunctionScoreQuery::new(Box::new(const_score_query), functions);
+        query.set_score_mode(score_mode);
+        query
+    }
+
+    #[test]
+    fn test_function_score_query_modes() -> crate::Result<()> {
+        let (index, text) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let scores = |score_mode: ScoreMode| -> crate::Result<Vec<f32>> {
+            let likes = FieldValueFactor::new("likes".to_string()).with_factor(2.0);
+            let query = function_score_query(text, vec![Box::new(likes)], score_mode);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(4))?;
+            assert_eq!(top_docs.len(), 3);
+            let mut scores: Vec<f32> = top_docs.into_iter().map(|(score, _)| score).collect();
+            scores.sort_by(|left, right| left.partial_cmp(right).unwrap());
+            Ok(scores)
+        };
+        // the query scores all the documents with `1`, the document without likes has a function
+        // score of `1`.
+        assert_eq!(scores(ScoreMode::Multiply)?, vec![1.0, 2.0, 8.0]);
+        assert_eq!(scores(ScoreMode::Sum)?, vec![2.0, 3.0, 9.0]);
+        assert_eq!(scores(ScoreMode::Replace)?, vec![1.0, 2.0, 8.0]);
+        assert_eq!(scores(ScoreMode::Max)?, vec![1.0, 2.0, 8.0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_score_query_product_of_functions() -> crate::Result<()> {
+        let (index, text) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let likes = FieldValueFactor::new("likes".to_string());
+        let price = DecayFunction::new(DecayKind::Linear, "price".to_string(), 10.0, 10.0);
+        let query = function_score_query(
+            text,
+            vec![Box::new(likes), Box::new(price)],
+            ScoreMode::Replace,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(4))?;
+        let scores: Vec<f32> = top_docs.iter().map(|(score, _)| *score).collect();
+        assert_eq!(scores, vec![2.0, 1.0, 0.75]);
+        let explanation = query.explain(&searcher, top_docs[0].1)?;
+        assert_nearly_equals!(explanation.value(), 2.0);
+        let explanation_json = explanation.to_pretty_json();
+        assert!(explanation_json.contains("Product of the functions"));
+        assert!(explanation_json.contains("DecayFunction"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_score_query_nested() -> crate::Result<()> {
+        let (index, text) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let likes = FieldValueFactor::new("likes".to_string()).with_missing(0.0);
+        let query = function_score_query(text, vec![Box::new(likes)], ScoreMode::Replace);
+        let boolean_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(query) as Box<dyn Query>),
+            (
+                Occur::MustNot,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(text, "c"),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+        ]);
+        assert_eq!(searcher.search(&boolean_query, &Count)?, 2);
+        let top_docs = searcher.search(&boolean_query, &TopDocs::with_limit(4))?;
+        let scores: Vec<f32> = top_docs.iter().map(|(score, _)| *score).collect();
+        assert_eq!(scores, vec![4.0, 1.0]);
+        let explanation = boolean_query.explain(&searcher, top_docs[0].1)?;
+        assert_nearly_equals!(explanation.value(), 4.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_score_query_random_score() -> crate::Result<()> {
+        let (index, text) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let random_top_docs = |seed: u64| -> crate::Result<Vec<(f32, DocAddress)>> {
+            let query = function_score_query(
+                text,
+                vec![Box::new(RandomScore::new(seed))],
+                ScoreMode::Replace,
+            );
+            searcher.search(&query, &TopDocs::with_limit(4))
+        };
+        let top_docs = random_top_docs(42)?;
+        assert_eq!(top_docs.len(), 3);
+        for (score, _) in &top_docs {
+            assert!((0.0..=1.0).contains(score));
+        }
+        assert_eq!(random_top_docs(42)?, top_docs);
+        assert_ne!(random_top_docs(43)?, top_docs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_score_query_missing_field() -> crate::Result<()> {
+        let (index, text) = create_index()?;
+        let searcher = index.reader()?.searcher();
+        let missing = FieldValueFactor::new("missing".to_string());
+        let query = function_score_query(text, vec![Box::new(missing)], ScoreMode::Multiply);
+        assert!(searcher.search(&query, &TopDocs::with_limit(4)).is_err());
+        // the functions are not evaluated when scoring is disabled.
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+mod decay_function;
+mod field_value_factor;
+mod function_score_query;
+mod random_score;
+mod score_function;
+
+pub use self::decay_function::{DecayFunction, DecayKind};
+pub use self::field_value_factor::{FieldValueFactor, FieldValueModifier};
+pub use self::function_score_query::{FunctionScoreQuery, ScoreMode};
+pub use self::random_score::RandomScore;
+pub use self::score_function::{ScoreFunction, SegmentScoreFunction};
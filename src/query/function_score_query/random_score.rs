@@ -0,0 +1,52 @@
+use super::{ScoreFunction, SegmentScoreFunction};
+use crate::{DocId, Score, SegmentReader};
+
+/// Scores documents with a pseudo-random number in `[0, 1]`, derived from a seed.
+///
+/// For a given seed, the score of a document is stable for as long as its segment is not
+/// merged, so that results can be paginated consistently. Changing the seed shuffles the
+/// scores.
+#[derive(Clone, Debug)]
+pub struct RandomScore {
+    seed: u64,
+}
+
+impl RandomScore {
+    /// Creates a `RandomScore` from a seed.
+    pub fn new(seed: u64) -> RandomScore {
+        RandomScore { seed }
+    }
+}
+
+impl ScoreFunction for RandomScore {
+    fn segment_function(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Box<dyn SegmentScoreFunction>> {
+        let segment_id = segment_reader.segment_id().uuid_string();
+        let segment_hash = murmurhash32::murmurhash2(segment_id.as_bytes());
+        Ok(Box::new(SegmentRandomScore {
+            seed: self.seed ^ (u64::from(segment_hash) << 32),
+        }))
+    }
+}
+
+struct SegmentRandomScore {
+    seed: u64,
+}
+
+impl SegmentScoreFunction for SegmentRandomScore {
+    fn score(&mut self, doc: DocId) -> Score {
+        let hash = splitmix64(self.seed ^ u64::from(doc));
+        // The 24 high bits are exactly representable by a `f32`.
+        (hash >> 40) as Score / ((1u64 << 24) - 1) as Score
+    }
+}
+
+/// Mixes the bits of a `u64`, as the `SplitMix64` generator does.
+fn splitmix64(val: u64) -> u64 {
+    let mut z = val.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+use columnar::{Column, ColumnType};
+
+use crate::aggregation::f64_from_fastfield_u64;
+use crate::{DocId, Score, SegmentReader, TantivyError};
+
+/// A function computing a score for the documents of a segment, combined with the score of
+/// the query wrapped in a [`FunctionScoreQuery`](super::FunctionScoreQuery).
+///
+/// Its `Debug` representation is used to describe the function in the
+/// [`Explanation`](crate::query::Explanation) of a score.
+pub trait ScoreFunction: fmt::Debug + Send + Sync + 'static {
+    /// Returns the function computing the scores of the documents of a given segment.
+    fn segment_function(
+        &self,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Box<dyn SegmentScoreFunction>>;
+}
+
+/// The [`ScoreFunction`] of a specific segment.
+pub trait SegmentScoreFunction: Send + 'static {
+    /// Returns the score of a document.
+    ///
+    /// Documents are not necessarily passed in an increasing order.
+    fn score(&mut self, doc: DocId) -> Score;
+}
+
+/// The numerical values of a fast field, as `f64`.
+///
+/// Dates are expressed as a number of nanoseconds since the Unix epoch.
+pub(crate) struct NumericalColumn {
+    column: Column<u64>,
+    column_type: ColumnType,
+}
+
+impl NumericalColumn {
+    /// Opens the numerical fast field `field_name` of a segment.
+    ///
+    /// Returns `None` if no document of the segment has a numerical value in this field.
+    pub fn open(
+        segment_reader: &SegmentReader,
+        field_name: &str,
+    ) -> crate::Result<Option<NumericalColumn>> {
+        if segment_reader.schema().find_field(field_name).is_none() {
+            return Err(TantivyError::FieldNotFound(field_name.to_string()));
+        }
+        let numerical_types = [
+            ColumnType::U64,
+            ColumnType::I64,
+            ColumnType::F64,
+            ColumnType::DateTime,
+        ];
+        let column_opt = segment_reader
+            .fast_fields()
+            .u64_lenient_for_type(Some(&numerical_types), field_name)?
+            .map(|(column, column_type)| NumericalColumn {
+                column,
+                column_type,
+            });
+        Ok(column_opt)
+    }
+
+    pub fn values_for_doc(&self, doc: DocId) -> impl Iterator<Item = f64> + '_ {
+        self.column
+            .values_for_doc(doc)
+            .map(|val| f64_from_fastfield_u64(val, &self.column_type))
+    }
+}
@@ -0,0 +1,172 @@
+use super::geo_weight::{GeoShape, GeoWeight};
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, GeoPoint};
+
+/// `GeoBoundingBoxQuery` matches the documents having a point of a geo point field
+/// within a bounding box.
+///
+/// The box is given by its top left and bottom right corners. If the longitude of the top
+/// left corner is greater than the one of the bottom right corner, the box crosses the
+/// antimeridian.
+///
+/// The field must be a fast field. If it is also indexed, only the documents located in the
+/// geohash cells covering the box are checked. All of the matching documents get a
+/// constant score of `1.0`.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::GeoBoundingBoxQuery;
+/// use tantivy::schema::{GeoPoint, Schema, FAST, INDEXED};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let location = schema_builder.add_geo_point_field("location", FAST | INDEXED);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+/// index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+/// index_writer.add_document(doc!(location => GeoPoint::new(40.7128, -74.0060)))?; // New York
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let europe = GeoBoundingBoxQuery::new(
+///     location,
+///     GeoPoint::new(60.0, -10.0),
+///     GeoPoint::new(35.0, 30.0),
+/// );
+/// assert_eq!(searcher.search(&europe, &Count)?, 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct GeoBoundingBoxQuery {
+    field: Field,
+    top_left: GeoPoint,
+    bottom_right: GeoPoint,
+}
+
+impl GeoBoundingBoxQuery {
+    /// Creates a query matching the points within the box defined by its top left and
+    /// bottom right corners.
+    pub fn new(field: Field, top_left: GeoPoint, bottom_right: GeoPoint) -> GeoBoundingBoxQuery {
+        GeoBoundingBoxQuery {
+            field,
+            top_left,
+            bottom_right,
+        }
+    }
+}
+
+impl Query for GeoBoundingBoxQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let shape = GeoShape::BoundingBox {
+            min_lat: self.bottom_right.lat,
+            max_lat: self.top_left.lat,
+            min_lon: self.top_left.lon,
+            max_lon: self.bottom_right.lon,
+        };
+        Ok(Box::new(GeoWeight::for_shape(
+            self.field,
+            shape,
+            enable_scoring,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoBoundingBoxQuery;
+    use crate::collector::{Count, DocSetCollector};
+    use crate::query::{Query, QueryParser};
+    use crate::schema::{GeoPoint, Schema, FAST, INDEXED, STORED};
+    use crate::{DocAddress, Index};
+
+    fn create_index(indexed: bool) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let location = if indexed {
+            schema_builder.add_geo_point_field("location", FAST | INDEXED)
+        } else {
+            schema_builder.add_geo_point_field("location", FAST)
+        };
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        // Tokyo
+        index_writer.add_document(doc!(location => GeoPoint::new(35.6762, 139.6503)))?;
+        // Fiji
+        index_writer.add_document(doc!(location => GeoPoint::new(-17.7134, 178.0650)))?;
+        // Samoa
+        index_writer.add_document(doc!(location => GeoPoint::new(-13.7590, -172.1046)))?;
+        // Paris and Sydney
+        index_writer.add_document(doc!(
+            location => GeoPoint::new(48.8566, 2.3522),
+            location => GeoPoint::new(-33.8688, 151.2093),
+        ))?;
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    fn search_docs(index: &Index, query: &dyn Query) -> crate::Result<Vec<u32>> {
+        let searcher = index.reader()?.searcher();
+        let docs = searcher.search(query, &DocSetCollector)?;
+        let mut docs: Vec<u32> = docs
+            .into_iter()
+            .map(|DocAddress { doc_id, .. }| doc_id)
+            .collect();
+        docs.sort_unstable();
+        Ok(docs)
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query() -> crate::Result<()> {
+        for indexed in [false, true] {
+            let index = create_index(indexed)?;
+            let location = index.schema().get_field("location").unwrap();
+            let pacific = GeoBoundingBoxQuery::new(
+                location,
+                GeoPoint::new(40.0, 130.0),
+                GeoPoint::new(-40.0, 180.0),
+            );
+            assert_eq!(search_docs(&index, &pacific)?, vec![0, 1, 3]);
+            let europe = GeoBoundingBoxQuery::new(
+                location,
+                GeoPoint::new(60.0, -10.0),
+                GeoPoint::new(35.0, 30.0),
+            );
+            assert_eq!(search_docs(&index, &europe)?, vec![3]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query_antimeridian() -> crate::Result<()> {
+        for indexed in [false, true] {
+            let index = create_index(indexed)?;
+            let location = index.schema().get_field("location").unwrap();
+            let query = GeoBoundingBoxQuery::new(
+                location,
+                GeoPoint::new(0.0, 170.0),
+                GeoPoint::new(-20.0, -170.0),
+            );
+            assert_eq!(search_docs(&index, &query)?, vec![1, 2]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_query_requires_fast_geo_point_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let stored = schema_builder.add_geo_point_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query =
+            GeoBoundingBoxQuery::new(stored, GeoPoint::new(1.0, 0.0), GeoPoint::new(0.0, 1.0));
+        assert!(searcher.search(&query, &Count).is_err());
+        let query_parser = QueryParser::for_index(&index, vec![stored]);
+        assert!(query_parser.parse_query("stored:abc").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use super::geo_weight::{GeoShape, GeoWeight};
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, GeoPoint};
+
+/// `GeoDistanceQuery` matches the documents having a point of a geo point field
+/// within a given distance of a center point.
+///
+/// Distances are great-circle distances, in meters, computed with the haversine formula.
+///
+/// The field must be a fast field. If it is also indexed, only the documents located in the
+/// geohash cells covering the circle are checked. All of the matching documents get a
+/// constant score of `1.0`. To rank documents by distance, use
+/// [`TopDocs::order_by_geo_distance`](crate::collector::TopDocs::order_by_geo_distance).
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::GeoDistanceQuery;
+/// use tantivy::schema::{GeoPoint, Schema, FAST};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let location = schema_builder.add_geo_point_field("location", FAST);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+/// index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let versailles = GeoPoint::new(48.8049, 2.1204);
+/// let query = GeoDistanceQuery::new(location, versailles, 50_000.0);
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct GeoDistanceQuery {
+    field: Field,
+    center: GeoPoint,
+    distance: f64,
+}
+
+impl GeoDistanceQuery {
+    /// Creates a query matching the points within `distance` meters of `center`.
+    pub fn new(field: Field, center: GeoPoint, distance: f64) -> GeoDistanceQuery {
+        GeoDistanceQuery {
+            field,
+            center,
+            distance,
+        }
+    }
+}
+
+impl Query for GeoDistanceQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let shape = GeoShape::Distance {
+            center: self.center,
+            radius: self.distance,
+        };
+        Ok(Box::new(GeoWeight::for_shape(
+            self.field,
+            shape,
+            enable_scoring,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoDistanceQuery;
+    use crate::collector::Count;
+    use crate::query::Query;
+    use crate::schema::{GeoPoint, Schema, FAST, INDEXED};
+    use crate::Index;
+
+    #[test]
+    fn test_geo_distance_query() -> crate::Result<()> {
+        for indexed in [false, true] {
+            let mut schema_builder = Schema::builder();
+            let location = if indexed {
+                schema_builder.add_geo_point_field("location", FAST | INDEXED)
+            } else {
+                schema_builder.add_geo_point_field("location", FAST)
+            };
+            let index = Index::create_in_ram(schema_builder.build());
+            let mut index_writer = index.writer_for_tests()?;
+            // Points along the equator, every ~111km, around the antimeridian.
+            for lon in [177.0, 178.0, 179.0, 180.0, -179.0, -178.0, -177.0] {
+                index_writer.add_document(doc!(location => GeoPoint::new(0.0, lon)))?;
+            }
+            index_writer.commit()?;
+            let searcher = index.reader()?.searcher();
+            let count = |center: GeoPoint, distance: f64| {
+                searcher.search(&GeoDistanceQuery::new(location, center, distance), &Count)
+            };
+            assert_eq!(count(GeoPoint::new(0.0, 180.0), 1_000.0)?, 1);
+            assert_eq!(count(GeoPoint::new(0.0, 179.5), 120_000.0)?, 2);
+            assert_eq!(count(GeoPoint::new(0.0, 179.5), 170_000.0)?, 4);
+            assert_eq!(count(GeoPoint::new(0.0, -179.5), 250_000.0)?, 4);
+            assert_eq!(count(GeoPoint::new(0.0, 0.0), 1_000_000.0)?, 0);
+            assert_eq!(count(GeoPoint::new(90.0, 0.0), 10_010_000.0)?, 7);
+
+            let query = GeoDistanceQuery::new(location, GeoPoint::new(0.0, 178.0), 10.0);
+            let weight = query.weight(crate::query::EnableScoring::disabled_from_searcher(
+                &searcher,
+            ))?;
+            let segment_reader = searcher.segment_reader(0);
+            assert!(weight.explain(segment_reader, 1).is_ok());
+            assert!(weight.explain(segment_reader, 0).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use super::geo_weight::{GeoShape, GeoWeight};
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, GeoPoint};
+
+/// `GeoPolygonQuery` matches the documents having a point of a geo point field
+/// inside a polygon.
+///
+/// The polygon is given by its vertices, and is closed automatically. Its edges are straight
+/// lines in the latitude/longitude plane. Each edge goes the shortest way between its vertices:
+/// an edge from longitude `170` to longitude `-170` crosses the antimeridian.
+///
+/// The field must be a fast field. If it is also indexed, only the documents located in the
+/// geohash cells covering the bounding box of the polygon are checked. All of the matching
+/// documents get a constant score of `1.0`.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::GeoPolygonQuery;
+/// use tantivy::schema::{GeoPoint, Schema, FAST, INDEXED};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let location = schema_builder.add_geo_point_field("location", FAST | INDEXED);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(location => GeoPoint::new(48.8566, 2.3522)))?; // Paris
+/// index_writer.add_document(doc!(location => GeoPoint::new(51.5074, -0.1278)))?; // London
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let france = GeoPolygonQuery::new(
+///     location,
+///     vec![
+///         GeoPoint::new(51.0, 2.5),
+///         GeoPoint::new(43.0, 8.0),
+///         GeoPoint::new(42.5, -1.8),
+///         GeoPoint::new(48.5, -5.0),
+///     ],
+/// );
+/// assert_eq!(searcher.search(&france, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct GeoPolygonQuery {
+    field: Field,
+    vertices: Vec<GeoPoint>,
+}
+
+impl GeoPolygonQuery {
+    /// Creates a query matching the points inside the polygon defined by `vertices`.
+    ///
+    /// A polygon with less than 3 vertices matches no document.
+    pub fn new(field: Field, vertices: Vec<GeoPoint>) -> GeoPolygonQuery {
+        GeoPolygonQuery { field, vertices }
+    }
+}
+
+impl Query for GeoPolygonQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let vertices = if self.vertices.len() >= 3 {
+            self.vertices.clone()
+        } else {
+            Vec::new()
+        };
+        Ok(Box::new(GeoWeight::for_shape(
+            self.field,
+            GeoShape::polygon(vertices),
+            enable_scoring,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoPolygonQuery;
+    use crate::collector::Count;
+    use crate::schema::{GeoPoint, Schema, FAST, INDEXED};
+    use crate::Index;
+
+    #[test]
+    fn test_geo_polygon_query() -> crate::Result<()> {
+        for indexed in [false, true] {
+            let mut schema_builder = Schema::builder();
+            let location = if indexed {
+                schema_builder.add_geo_point_field("location", FAST | INDEXED)
+            } else {
+                schema_builder.add_geo_point_field("location", FAST)
+            };
+            let index = Index::create_in_ram(schema_builder.build());
+            let mut index_writer = index.writer_for_tests()?;
+            for lat in 0..10 {
+                for lon in 0..10 {
+                    index_writer.add_document(
+                        doc!(location => GeoPoint::new(lat as f64 + 0.5, lon as f64 + 0.5)),
+                    )?;
+                }
+            }
+            index_writer.commit()?;
+            let searcher = index.reader()?.searcher();
+            let count = |vertices: Vec<(f64, f64)>| {
+                let vertices = vertices
+                    .into_iter()
+                    .map(|(lat, lon)| GeoPoint::new(lat, lon))
+                    .collect();
+                searcher.search(&GeoPolygonQuery::new(location, vertices), &Count)
+            };
+            // Lower left triangle, including the points on the diagonal.
+            assert_eq!(count(vec![(0.0, 0.0), (10.1, 0.0), (0.0, 10.1)])?, 55);
+            // Concave "L" shape.
+            assert_eq!(
+                count(vec![
+                    (0.0, 0.0),
+                    (10.0, 0.0),
+                    (10.0, 2.0),
+                    (2.0, 2.0),
+                    (2.0, 10.0),
+                    (0.0, 10.0),
+                ])?,
+                36
+            );
+            assert_eq!(count(vec![(0.0, 0.0), (10.0, 10.0)])?, 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_geo_polygon_query_antimeridian() -> crate::Result<()> {
+        for indexed in [false, true] {
+            let mut schema_builder = Schema::builder();
+            let location = if indexed {
+                schema_builder.add_geo_point_field("location", FAST | INDEXED)
+            } else {
+                schema_builder.add_geo_point_field("location", FAST)
+            };
+            let index = Index::create_in_ram(schema_builder.build());
+            let mut index_writer = index.writer_for_tests()?;
+            for lon in [-179.5, -175.0, -160.0, 0.0, 160.0, 175.0, 179.5] {
+                index_writer.add_document(doc!(location => GeoPoint::new(0.0, lon)))?;
+            }
+            index_writer.commit()?;
+            let searcher = index.reader()?.searcher();
+            let count = |vertices: Vec<(f64, f64)>| {
+                let vertices = vertices
+                    .into_iter()
+                    .map(|(lat, lon)| GeoPoint::new(lat, lon))
+                    .collect();
+                searcher.search(&GeoPolygonQuery::new(location, vertices), &Count)
+            };
+            assert_eq!(
+                count(vec![
+                    (-10.0, 170.0),
+                    (10.0, 170.0),
+                    (10.0, -170.0),
+                    (-10.0, -170.0)
+                ])?,
+                4
+            );
+            assert_eq!(
+                count(vec![
+                    (-10.0, -170.0),
+                    (10.0, -170.0),
+                    (10.0, 170.0),
+                    (-10.0, 170.0)
+                ])?,
+                4
+            );
+            // Same polygon, starting on the other side of the antimeridian.
+            assert_eq!(
+                count(vec![
+                    (10.0, -170.0),
+                    (-10.0, -170.0),
+                    (-10.0, 170.0),
+                    (10.0, 170.0)
+                ])?,
+                4
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use columnar::Column;
+use common::BitSet;
+
+use crate::query::explanation::does_not_match;
+use crate::query::{
+    BitSetDocSet, ConstScorer, EmptyScorer, EnableScoring, Explanation, Scorer, Weight,
+};
+use crate::schema::{
+    geohash_cover, geohash_cover_len, Field, FieldType, GeoPoint, IndexRecordOption, Term,
+    INDEXED_GEOHASH_PRECISION,
+};
+use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED};
+
+/// Maximum number of geohash cells whose postings are read to find the candidate documents
+/// of a geo query.
+const MAX_COVERING_CELLS: u64 = 256;
+
+/// An area of the earth searched by a geo query.
+#[derive(Clone, Debug)]
+pub(crate) enum GeoShape {
+    /// Points whose latitude is within `[min_lat, max_lat]` and whose longitude is within
+    /// `[min_lon, max_lon]`. If `min_lon > max_lon`, the box crosses the antimeridian.
+    BoundingBox {
+        min_lat: f64,
+        max_lat: f64,
+        min_lon: f64,
+        max_lon: f64,
+    },
+    /// Points within `radius` meters of `center`.
+    Distance { center: GeoPoint, radius: f64 },
+    /// Points inside the polygon. The longitudes of the vertices are unwrapped, see
+    /// [`GeoShape::polygon`].
+    Polygon(Vec<GeoPoint>),
+}
+
+/// A box given as `(min_lat, max_lat, min_lon, max_lon)`, not crossing the antimeridian.
+type LatLonBox = (f64, f64, f64, f64);
+
+impl GeoShape {
+    /// Creates a polygon shape.
+    ///
+    /// Each edge is taken as the shortest one between its vertices, so an edge spanning more
+    /// than 180 degrees of longitude crosses the antimeridian instead. The longitudes of the
+    /// vertices are shifted by multiples of 360 degrees to make the edges continuous, and can end
+    /// up outside of `[-180, 180]`.
+    pub fn polygon(vertices: Vec<GeoPoint>) -> GeoShape {
+        let mut unwrapped: Vec<GeoPoint> = Vec::with_capacity(vertices.len());
+        for mut vertex in vertices {
+            if let Some(previous) = unwrapped.last() {
+                vertex.lon -= 360.0 * ((vertex.lon - previous.lon) / 360.0).round();
+            }
+            unwrapped.push(vertex);
+        }
+        GeoShape::Polygon(unwrapped)
+    }
+
+    /// Returns true if the point belongs to the shape.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        match self {
+            GeoShape::BoundingBox {
+                min_lat,
+                max_lat,
+                min_lon,
+                max_lon,
+            } => {
+                let lon_matches = if min_lon <= max_lon {
+                    *min_lon <= point.lon && point.lon <= *max_lon
+                } else {
+                    *min_lon <= point.lon || point.lon <= *max_lon
+                };
+                *min_lat <= point.lat && point.lat <= *max_lat && lon_matches
+            }
+            GeoShape::Distance { center, radius } => center.distance(point) <= *radius,
+            GeoShape::Polygon(vertices) => {
+                // The longitudes of the vertices may have been shifted by 360 degrees.
+                [point.lon, point.lon - 360.0, point.lon + 360.0]
+                    .into_iter()
+                    .any(|lon| polygon_contains(vertices, point.lat, lon))
+            }
+        }
+    }
+
+    /// Returns boxes, not crossing the antimeridian, covering the shape.
+    fn covering_boxes(&self) -> Vec<LatLonBox> {
+        match *self {
+            GeoShape::BoundingBox {
+                min_lat,
+                max_lat,
+                min_lon,
+                max_lon,
+            } => {
+                if min_lon <= max_lon {
+                    vec![(min_lat, max_lat, min_lon, max_lon)]
+                } else {
+                    vec![
+                        (min_lat, max_lat, min_lon, 180.0),
+                        (min_lat, max_lat, -180.0, max_lon),
+                    ]
+                }
+            }
+            GeoShape::Distance { center, radius } => {
+                let lat_delta = GeoPoint::lat_delta(radius);
+                let min_lat = center.lat - lat_delta;
+                let max_lat = center.lat + lat_delta;
+                if min_lat <= -90.0 || max_lat >= 90.0 {
+                    // The circle contains a pole.
+                    return vec![(min_lat.max(-90.0), max_lat.min(90.0), -180.0, 180.0)];
+                }
+                let sin_lon_delta = lat_delta.to_radians().sin() / center.lat.to_radians().cos();
+                if sin_lon_delta >= 1.0 {
+                    return vec![(min_lat, max_lat, -180.0, 180.0)];
+                }
+                let lon_delta = sin_lon_delta.asin().to_degrees();
+                let min_lon = center.lon - lon_delta;
+                let max_lon = center.lon + lon_delta;
+                if min_lon < -180.0 {
+                    vec![
+                        (min_lat, max_lat, min_lon + 360.0, 180.0),
+                        (min_lat, max_lat, -180.0, max_lon),
+                    ]
+                } else if max_lon > 180.0 {
+                    vec![
+                        (min_lat, max_lat, min_lon, 180.0),
+                        (min_lat, max_lat, -180.0, max_lon - 360.0),
+                    ]
+                } else {
+                    vec![(min_lat, max_lat, min_lon, max_lon)]
+                }
+            }
+            GeoShape::Polygon(ref vertices) => {
+                if vertices.is_empty() {
+                    return Vec::new();
+                }
+                let (mut min_lat, mut max_lat) = (f64::MAX, f64::MIN);
+                let (mut min_lon, mut max_lon) = (f64::MAX, f64::MIN);
+                for vertex in vertices {
+                    min_lat = min_lat.min(vertex.lat);
+                    max_lat = max_lat.max(vertex.lat);
+                    min_lon = min_lon.min(vertex.lon);
+                    max_lon = max_lon.max(vertex.lon);
+                }
+                if max_lon - min_lon >= 360.0 {
+                    // The polygon goes around a pole.
+                    return vec![(min_lat, max_lat, -180.0, 180.0)];
+                }
+                if min_lon < -180.0 {
+                    min_lon += 360.0;
+                    max_lon += 360.0;
+                }
+                if max_lon > 180.0 {
+                    vec![
+                        (min_lat, max_lat, min_lon, 180.0),
+                        (min_lat, max_lat, -180.0, max_lon - 360.0),
+                    ]
+                } else {
+                    vec![(min_lat, max_lat, min_lon, max_lon)]
+                }
+            }
+        }
+    }
+
+    /// Returns the geohashes of the cells covering the shape, picking the finest
+    /// precision for which there are at most `MAX_COVERING_CELLS` cells.
+    fn covering_geohashes(&self) -> Vec<String> {
+        let boxes = self.covering_boxes();
+        let num_cells = |precision: usize| -> u64 {
+            boxes
+                .iter()
+                .map(|&(min_lat, max_lat, min_lon, max_lon)| {
+                    geohash_cover_len(min_lat, max_lat, min_lon, max_lon, precision)
+                })
+                .sum()
+        };
+        let precision = (2..=INDEXED_GEOHASH_PRECISION)
+            .take_while(|&precision| num_cells(precision) <= MAX_COVERING_CELLS)
+            .last()
+            .unwrap_or(1);
+        boxes
+            .iter()
+            .flat_map(|&(min_lat, max_lat, min_lon, max_lon)| {
+                geohash_cover(min_lat, max_lat, min_lon, max_lon, precision)
+            })
+            .collect()
+    }
+}
+
+/// Returns true if the point is inside the polygon, using ray casting on the plane defined by
+/// the latitude and the longitude.
+fn polygon_contains(vertices: &[GeoPoint], lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match vertices.last() {
+        Some(previous) => previous,
+        None => return false,
+    };
+    for vertex in vertices {
+        if (vertex.lat > lat) != (previous.lat > lat)
+            && lon
+                < (previous.lon - vertex.lon) * (lat - vertex.lat) / (previous.lat - vertex.lat)
+                    + vertex.lon
+        {
+            inside = !inside;
+        }
+        previous = vertex;
+    }
+    inside
+}
+
+/// Weight shared by the geo queries.
+///
+/// Documents are matched against the exact coordinates stored in the fast field. If the field
+/// is indexed, only the documents located in the geohash cells covering the shape are checked.
+pub(crate) struct GeoWeight {
+    field: Field,
+    shape: GeoShape,
+}
+
+impl GeoWeight {
+    /// Creates the weight, checking that the field is a fast geo point field.
+    pub fn for_shape(
+        field: Field,
+        shape: GeoShape,
+        enable_scoring: EnableScoring<'_>,
+    ) -> crate::Result<GeoWeight> {
+        let field_entry = enable_scoring.schema().get_field_entry(field);
+        match field_entry.field_type() {
+            FieldType::GeoPoint(geo_point_options) if geo_point_options.is_fast() => {
+                Ok(GeoWeight { field, shape })
+            }
+            FieldType::GeoPoint(_) => Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field",
+                field_entry.name()
+            ))),
+            _ => Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a geo point field",
+                field_entry.name()
+            ))),
+        }
+    }
+
+    fn column(&self, reader: &SegmentReader) -> crate::Result<Option<Column<u64>>> {
+        let field_name = reader.schema().get_field_name(self.field);
+        reader.fast_fields().column_opt::<u64>(field_name)
+    }
+
+    fn matches(&self, column: &Column<u64>, doc: DocId) -> bool {
+        column
+            .values_for_doc(doc)
+            .any(|val| self.shape.contains(&GeoPoint::from_u64(val)))
+    }
+
+    /// Returns the documents located in the geohash cells covering the shape.
+    fn candidates(&self, reader: &SegmentReader) -> crate::Result<BitSet> {
+        let mut candidates = BitSet::with_max_value(reader.max_doc());
+        let inverted_index = reader.inverted_index(self.field)?;
+        for geohash in self.shape.covering_geohashes() {
+            let term = Term::from_field_geohash(self.field, &geohash);
+            let term_info = if let Some(term_info) = inverted_index.get_term_info(&term)? {
+                term_info
+            } else {
+                continue;
+            };
+            let mut block_segment_postings = inverted_index
+                .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)?;
+            loop {
+                let docs = block_segment_postings.docs();
+                if docs.is_empty() {
+                    break;
+                }
+                for &doc in docs {
+                    candidates.insert(doc);
+                }
+                block_segment_postings.advance();
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+impl Weight for GeoWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let column = if let Some(column) = self.column(reader)? {
+            column
+        } else {
+            return Ok(Box::new(EmptyScorer));
+        };
+        let max_doc = reader.max_doc();
+        let mut doc_bitset = BitSet::with_max_value(max_doc);
+        if reader.schema().get_field_entry(self.field).is_indexed() {
+            let mut candidates = BitSetDocSet::from(self.candidates(reader)?);
+            let mut doc = candidates.doc();
+            while doc != TERMINATED {
+                if self.matches(&column, doc) {
+                    doc_bitset.insert(doc);
+                }
+                doc = candidates.advance();
+            }
+        } else {
+            for doc in 0..max_doc {
+                if self.matches(&column, doc) {
+                    doc_bitset.insert(doc);
+                }
+            }
+        }
+        let doc_bitset = BitSetDocSet::from(doc_bitset);
+        Ok(Box::new(ConstScorer::new(doc_bitset, boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let column = self.column(reader)?.ok_or_else(|| does_not_match(doc))?;
+        if !self.matches(&column, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("GeoQuery", 1.0))
+    }
+}
@@ -0,0 +1,8 @@
+mod geo_bounding_box_query;
+mod geo_distance_query;
+mod geo_polygon_query;
+mod geo_weight;
+
+pub use self::geo_bounding_box_query::GeoBoundingBoxQuery;
+pub use self::geo_distance_query::GeoDistanceQuery;
+pub use self::geo_polygon_query::GeoPolygonQuery;
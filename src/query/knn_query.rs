@@ -0,0 +1,411 @@
+use crate::query::explanation::does_not_match;
+use crate::query::{EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED};
+
+/// `KnnQuery` matches the `k` documents whose vector is the closest to
+/// the query vector, according to the metric of the vector field.
+///
+/// The nearest neighbors are searched in the HNSW graph of each segment, so the
+/// result is approximate: raising `ef_search` improves the recall at the expense
+/// of speed. Deleted documents are skipped during the search.
+///
+/// The score of a document is the similarity between its vector and the query vector,
+/// as defined by the [`VectorMetric`](crate::schema::VectorMetric) of the field.
+///
+/// Each segment contributes its own `k` nearest neighbors, so collecting the top `k`
+/// documents of the query yields the `k` nearest neighbors of the index.
+/// When combined with other queries, for instance in a
+/// [`BooleanQuery`](crate::query::BooleanQuery), the other clauses act as a post-filter: fewer than
+/// `k` documents may match.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::KnnQuery;
+/// use tantivy::schema::{Schema, VectorMetric, VectorOptions};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let embedding = schema_builder
+///     .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Euclidean));
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(embedding => vec![0.0f32, 0.0]))?;
+/// index_writer.add_document(doc!(embedding => vec![1.0f32, 1.0]))?;
+/// index_writer.add_document(doc!(embedding => vec![5.0f32, 5.0]))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = KnnQuery::new(embedding, vec![1.0, 2.0], 2);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].1.doc_id, 1);
+/// assert_eq!(top_docs[0].0, 0.5);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct KnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: Option<usize>,
+}
+
+impl KnnQuery {
+    /// Creates a query matching the `k` nearest neighbors of `vector` in the given field.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> KnnQuery {
+        KnnQuery {
+            field,
+            vector,
+            k,
+            ef_search: None,
+        }
+    }
+
+    /// Sets the size of the candidate list used during the search.
+    ///
+    /// It defaults to `k`, and values lower than `k` are ignored.
+    #[must_use]
+    pub fn with_ef_search(mut self, ef_search: usize) -> KnnQuery {
+        self.ef_search = Some(ef_search);
+        self
+    }
+
+    /// Returns the number of neighbors searched.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+impl Query for KnnQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let field_entry = enable_scoring.schema().get_field_entry(self.field);
+        let vector_options = if let FieldType::Vector(vector_options) = field_entry.field_type() {
+            vector_options
+        } else {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a vector field",
+                field_entry.name()
+            )));
+        };
+        if vector_options.dims() != self.vector.len() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Expected a query vector of dimension {} for field {:?}, got {}",
+                vector_options.dims(),
+                field_entry.name(),
+                self.vector.len()
+            )));
+        }
+        Ok(Box::new(KnnWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            ef_search: self.ef_search.unwrap_or(self.k).max(self.k),
+        }))
+    }
+}
+
+struct KnnWeight {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: usize,
+}
+
+impl KnnWeight {
+    fn knn_scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Option<KnnScorer>> {
+        let vector_reader =
+            if let Some(vector_reader) = reader.vector_readers().get_field(self.field)? {
+                vector_reader
+            } else {
+                return Ok(None);
+            };
+        let mut neighbors =
+            vector_reader.search(&self.vector, self.k, self.ef_search, reader.alive_bitset());
+        neighbors.sort_unstable_by_key(|(doc, _)| *doc);
+        Ok(Some(KnnScorer::new(neighbors, boost)))
+    }
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if let Some(knn_scorer) = self.knn_scorer(reader, boost)? {
+            Ok(Box::new(knn_scorer))
+        } else {
+            Ok(Box::new(EmptyScorer))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self
+            .knn_scorer(reader, 1.0)?
+            .ok_or_else(|| does_not_match(doc))?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation = Explanation::new("KnnQuery, vector similarity", scorer.score());
+        explanation.add_context(format!(
+            "Document is among the {} nearest neighbors in its segment",
+            self.k
+        ));
+        Ok(explanation)
+    }
+}
+
+/// Scorer over the precomputed nearest neighbors of a segment, sorted by doc id.
+struct KnnScorer {
+    neighbors: Vec<(DocId, Score)>,
+    cursor: usize,
+    boost: Score,
+}
+
+impl KnnScorer {
+    fn new(neighbors: Vec<(DocId, Score)>, boost: Score) -> KnnScorer {
+        KnnScorer {
+            neighbors,
+            cursor: 0,
+            boost,
+        }
+    }
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.neighbors.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.neighbors
+            .get(self.cursor)
+            .map(|(doc, _)| *doc)
+            .unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.neighbors.len() as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.neighbors
+            .get(self.cursor)
+            .map(|(_, score)| *score * self.boost)
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use super::KnnQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{
+        IndexRecordOption, Schema, Value, VectorMetric, VectorOptions, FAST, INDEXED, STORED,
+        STRING,
+    };
+    use crate::{DocAddress, Index, IndexSettings, IndexSortByField, IndexWriter, Order, Term};
+
+    fn random_vectors(num_vectors: usize, dims: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..num_vectors)
+            .map(|_| (0..dims).map(|_| rng.gen_range(-1.0f32..1.0f32)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_knn_query_across_merge_and_deletes() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED | FAST | STORED);
+        let parity = schema_builder.add_text_field("parity", STRING);
+        let embedding = schema_builder.add_vector_field(
+            "embedding",
+            VectorOptions::new(4, VectorMetric::Cosine) | STORED,
+        );
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let vectors = random_vectors(200, 4, 7);
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for (i, vector) in vectors.iter().enumerate() {
+            let mut doc = doc!(id => i as u64, parity => if i % 2 == 0 { "even" } else { "odd" });
+            // Some documents have no vector.
+            if i % 10 != 9 {
+                doc.add_vector(embedding, vector.clone());
+            }
+            index_writer.add_document(doc)?;
+            if i % 50 == 49 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.delete_term(Term::from_field_u64(id, 0));
+        index_writer.commit()?;
+
+        let query_vector = vectors[0].clone();
+        let check = |index: &Index| -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            let query = KnnQuery::new(embedding, query_vector.clone(), 5).with_ef_search(50);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(5))?;
+            assert_eq!(top_docs.len(), 5);
+            let ids: Vec<u64> = top_docs
+                .iter()
+                .map(|(_, doc_address)| {
+                    let doc = searcher.doc(*doc_address).unwrap();
+                    doc.get_first(id).unwrap().as_u64().unwrap()
+                })
+                .collect();
+            assert!(!ids.contains(&0));
+            assert!(ids.iter().all(|id| id % 10 != 9));
+            assert!(top_docs.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+            assert!(top_docs.iter().all(|(score, _)| *score <= 1.0));
+            // the stored vector is returned untouched
+            let doc = searcher.doc(top_docs[0].1)?;
+            let stored_id = doc.get_first(id).unwrap().as_u64().unwrap() as usize;
+            assert_eq!(
+                doc.get_first(embedding).and_then(Value::as_vector),
+                Some(&vectors[stored_id][..])
+            );
+            let explanation = query.explain(&searcher, top_docs[0].1)?;
+            assert_eq!(explanation.value(), top_docs[0].0);
+
+            let filtered_query = BooleanQuery::new(vec![
+                (Occur::Must, Box::new(query.clone()) as Box<dyn Query>),
+                (
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(parity, "odd"),
+                        IndexRecordOption::Basic,
+                    )),
+                ),
+            ]);
+            let num_odd = searcher.search(&filtered_query, &Count)?;
+            assert!(num_odd <= 5 * searcher.segment_readers().len());
+            Ok(())
+        };
+        check(&index)?;
+        let segment_ids = index.searchable_segment_ids()?;
+        assert!(segment_ids.len() > 1);
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(index.searchable_segment_ids()?.len(), 1);
+        check(&index)?;
+
+        let searcher = index.reader()?.searcher();
+        let vector_reader = searcher
+            .segment_reader(0)
+            .vector_readers()
+            .get_field(embedding)?;
+        // 180 vectors, minus the deleted one.
+        assert_eq!(vector_reader.unwrap().num_vectors(), 179);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_exact_on_small_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder
+            .add_vector_field("embedding", VectorOptions::new(3, VectorMetric::DotProduct));
+        let index = Index::create_in_ram(schema_builder.build());
+        let vectors = random_vectors(100, 3, 11);
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for vector in &vectors {
+            index_writer.add_document(doc!(embedding => vector.clone()))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_vector = vec![0.5, -0.25, 1.0];
+        let mut expected: Vec<(f32, u32)> = vectors
+            .iter()
+            .enumerate()
+            .map(|(doc, vector)| {
+                let score: f32 = vector.iter().zip(&query_vector).map(|(l, r)| l * r).sum();
+                (score, doc as u32)
+            })
+            .collect();
+        expected.sort_by(|left, right| right.0.total_cmp(&left.0));
+        let top_docs = searcher.search(
+            &KnnQuery::new(embedding, query_vector, 10).with_ef_search(100),
+            &TopDocs::with_limit(10),
+        )?;
+        let expected_docs: Vec<DocAddress> = expected[..10]
+            .iter()
+            .map(|(_, doc)| DocAddress::new(0, *doc))
+            .collect();
+        let found_docs: Vec<DocAddress> = top_docs.iter().map(|(_, doc)| *doc).collect();
+        assert_eq!(found_docs, expected_docs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_sorted_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let rank = schema_builder.add_u64_field("rank", FAST | STORED);
+        let embedding = schema_builder
+            .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Euclidean));
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "rank".to_string(),
+                    order: Order::Desc,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(rank => i, embedding => vec![i as f32, 0.0f32]))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let top_docs = searcher.search(
+            &KnnQuery::new(embedding, vec![3.0, 0.0], 1),
+            &TopDocs::with_limit(1),
+        )?;
+        let doc = searcher.doc(top_docs[0].1)?;
+        assert_eq!(doc.get_first(rank).unwrap().as_u64(), Some(3));
+        assert_eq!(top_docs[0].0, 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_errors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", STRING);
+        let embedding = schema_builder
+            .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Cosine));
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "no vector"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(
+            searcher.search(&KnnQuery::new(embedding, vec![1.0, 0.0], 3), &Count)?,
+            0
+        );
+        assert!(searcher
+            .search(&KnnQuery::new(embedding, vec![1.0], 3), &Count)
+            .is_err());
+        assert!(searcher
+            .search(&KnnQuery::new(title, vec![1.0, 0.0], 3), &Count)
+            .is_err());
+        let doc = schema
+            .parse_document(r#"{"embedding": [1.0, 2.0]}"#)
+            .unwrap();
+        assert_eq!(
+            doc.get_first(embedding).and_then(Value::as_vector),
+            Some(&[1.0f32, 2.0][..])
+        );
+        assert!(schema.parse_document(r#"{"embedding": [1.0]}"#).is_err());
+        Ok(())
+    }
+}
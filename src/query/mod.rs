@@ -8,9 +8,13 @@ mod const_score_query;
 mod disjunction_max_query;
 mod empty_query;
 mod exclude;
+mod exists_query;
 mod explanation;
+mod function_score_query;
 mod fuzzy_query;
+mod geo_query;
 mod intersection;
+mod knn_query;
 mod more_like_this;
 mod phrase_prefix_query;
 mod phrase_query;
@@ -21,9 +25,12 @@ mod regex_query;
 mod reqopt_scorer;
 mod scorer;
 mod set_query;
+mod similarity;
+mod span_query;
 mod term_query;
 mod union;
 mod weight;
+mod wildcard_query;
 
 #[cfg(test)]
 mod vec_docset;
@@ -34,22 +41,29 @@ pub use query_grammar::Occur;
 pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::automaton_weight::AutomatonWeight;
 pub use self::bitset::BitSetDocSet;
-pub use self::bm25::{Bm25StatisticsProvider, Bm25Weight};
+pub use self::bm25::{Bm25PlusSimilarity, Bm25Similarity, Bm25StatisticsProvider, Bm25Weight};
 pub use self::boolean_query::{BooleanQuery, BooleanWeight};
 pub use self::boost_query::{BoostQuery, BoostWeight};
 pub use self::const_score_query::{ConstScoreQuery, ConstScorer};
 pub use self::disjunction_max_query::DisjunctionMaxQuery;
 pub use self::empty_query::{EmptyQuery, EmptyScorer, EmptyWeight};
 pub use self::exclude::Exclude;
+pub use self::exists_query::ExistsQuery;
 pub use self::explanation::Explanation;
+pub use self::function_score_query::{
+    DecayFunction, DecayKind, FieldValueFactor, FieldValueModifier, FunctionScoreQuery,
+    RandomScore, ScoreFunction, ScoreMode, SegmentScoreFunction,
+};
 #[cfg(test)]
 pub(crate) use self::fuzzy_query::DfaWrapper;
 pub use self::fuzzy_query::FuzzyTermQuery;
+pub use self::geo_query::{GeoBoundingBoxQuery, GeoDistanceQuery, GeoPolygonQuery};
 pub use self::intersection::{intersect_scorers, Intersection};
+pub use self::knn_query::KnnQuery;
 pub use self::more_like_this::{MoreLikeThisQuery, MoreLikeThisQueryBuilder};
 pub use self::phrase_prefix_query::PhrasePrefixQuery;
 pub use self::phrase_query::PhraseQuery;
-pub use self::query::{EnableScoring, Query, QueryClone};
+pub use self::query::{EnableScoring, MatchPattern, Query, QueryClone};
 pub use self::query_parser::{QueryParser, QueryParserError};
 pub use self::range_query::{FastFieldRangeWeight, IPFastFieldRangeWeight, RangeQuery};
 pub use self::regex_query::RegexQuery;
@@ -59,11 +73,20 @@ pub use self::score_combiner::{
 };
 pub use self::scorer::Scorer;
 pub use self::set_query::TermSetQuery;
+pub use self::similarity::{
+    DfrSimilarity, LmDirichletSimilarity, Similarity, SimilarityManager, SimilarityQuery,
+    SimilarityStatistics, SimilarityWeight, TfIdfSimilarity,
+};
+pub use self::span_query::{
+    Span, SpanContainingQuery, SpanFirstQuery, SpanNearQuery, SpanNotQuery, SpanOrQuery, SpanQuery,
+    SpanTermQuery, Spans,
+};
 pub use self::term_query::TermQuery;
 pub use self::union::Union;
 #[cfg(test)]
 pub use self::vec_docset::VecDocSet;
 pub use self::weight::Weight;
+pub use self::wildcard_query::WildcardQuery;
 
 #[cfg(test)]
 mod tests {
@@ -89,6 +89,18 @@ impl MoreLikeThis {
         Ok(query)
     }
 
+    /// Creates a [`BooleanQuery`] using a document address to collect
+    /// the terms of its fields with term vectors.
+    pub fn query_with_document_term_vectors(
+        &self,
+        searcher: &Searcher,
+        doc_address: DocAddress,
+    ) -> Result<BooleanQuery> {
+        let score_terms = self.retrieve_terms_from_term_vectors(searcher, doc_address)?;
+        let query = self.create_query(score_terms);
+        Ok(query)
+    }
+
     /// Creates a [`BooleanQuery`] using a set of field values.
     pub fn query_with_document_fields(
         &self,
@@ -140,6 +152,39 @@ impl MoreLikeThis {
         self.retrieve_terms_from_doc_fields(searcher, &field_to_values)
     }
 
+    /// Finds terms for a more-like-this query, using the term vectors of the document.
+    /// doc_address is the address of document from which to find terms.
+    fn retrieve_terms_from_term_vectors(
+        &self,
+        searcher: &Searcher,
+        doc_address: DocAddress,
+    ) -> Result<Vec<ScoreTerm>> {
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+        let mut term_frequencies = HashMap::new();
+        let mut has_term_vectors = false;
+        for (field, field_entry) in searcher.schema().fields() {
+            match field_entry.field_type() {
+                FieldType::Str(text_options) if text_options.has_term_vectors() => {}
+                _ => continue,
+            }
+            has_term_vectors = true;
+            let term_vector = segment_reader.term_vector(doc_address.doc_id, field)?;
+            for entry in term_vector.entries() {
+                if !self.is_noise_word(entry.text().to_string()) {
+                    *term_frequencies.entry(entry.term().clone()).or_insert(0) +=
+                        entry.term_freq() as usize;
+                }
+            }
+        }
+        if !has_term_vectors {
+            return Err(TantivyError::InvalidArgument(
+                "Cannot create more like this query from term vectors: no field has term vectors"
+                    .to_string(),
+            ));
+        }
+        self.create_score_term(searcher, term_frequencies)
+    }
+
     /// Finds terms for a more-like-this query.
     /// field_to_field_values is a mapping from field to possible values of that field.
     fn retrieve_terms_from_doc_fields(
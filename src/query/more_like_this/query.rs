@@ -31,6 +31,7 @@ pub struct MoreLikeThisQuery {
 #[derive(Debug, PartialEq, Clone)]
 enum TargetDocument {
     DocumentAdress(DocAddress),
+    TermVectors(DocAddress),
     DocumentFields(Vec<(Field, Vec<Value>)>),
 }
 
@@ -55,6 +56,10 @@ impl Query for MoreLikeThisQuery {
                 .mlt
                 .query_with_document(searcher, *doc_address)?
                 .weight(enable_scoring),
+            TargetDocument::TermVectors(doc_address) => self
+                .mlt
+                .query_with_document_term_vectors(searcher, *doc_address)?
+                .weight(enable_scoring),
             TargetDocument::DocumentFields(doc_fields) => self
                 .mlt
                 .query_with_document_fields(searcher, doc_fields)?
@@ -160,6 +165,20 @@ impl MoreLikeThisQueryBuilder {
         }
     }
 
+    /// Sets the document address
+    /// Returns the constructed [`MoreLikeThisQuery`]
+    ///
+    /// Contrary to [`MoreLikeThisQueryBuilder::with_document`], the terms of the document
+    /// are read from the term vectors of its fields, instead of tokenizing its stored values.
+    /// Only the fields with term vectors are considered, see
+    /// [`TextOptions::set_term_vectors`](crate::schema::TextOptions::set_term_vectors).
+    pub fn with_document_term_vectors(self, doc_address: DocAddress) -> MoreLikeThisQuery {
+        MoreLikeThisQuery {
+            mlt: self.mlt,
+            target: TargetDocument::TermVectors(doc_address),
+        }
+    }
+
     /// Sets the document fields
     /// Returns the constructed [`MoreLikeThisQuery`]
     ///
@@ -280,4 +299,50 @@ mod tests {
         assert_eq!(doc_ids, vec![3, 4]);
         Ok(())
     }
+
+    #[test]
+    fn test_more_like_this_query_with_term_vectors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let body = schema_builder.add_text_field("body", (TEXT | STORED).set_term_vectors());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(body => "the old man and the sea"))?;
+        index_writer.add_document(doc!(body => "an old man sailing on the sea"))?;
+        index_writer.add_document(doc!(body => "send this message to alice"))?;
+        index_writer.add_document(doc!(body => "a lady was riding and old bike"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let builder = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(1)
+            .with_min_term_frequency(1)
+            .with_min_word_length(2)
+            .with_stop_words(vec!["old".to_string()]);
+        let query = builder
+            .clone()
+            .with_document_term_vectors(DocAddress::new(0, 0));
+        assert_eq!(
+            query.target,
+            TargetDocument::TermVectors(DocAddress::new(0, 0))
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(5))?;
+        let stored_query = builder.with_document(DocAddress::new(0, 0));
+        assert_eq!(
+            top_docs,
+            searcher.search(&stored_query, &TopDocs::with_limit(5))?
+        );
+        let mut doc_ids: Vec<_> = top_docs.iter().map(|item| item.1.doc_id).collect();
+        doc_ids.sort_unstable();
+        assert_eq!(doc_ids, vec![0, 1, 3]);
+
+        // None of the fields has term vectors.
+        let index = create_test_index()?;
+        let query = MoreLikeThisQuery::builder().with_document_term_vectors(DocAddress::new(0, 0));
+        assert!(index
+            .reader()?
+            .searcher()
+            .search(&query, &TopDocs::with_limit(5))
+            .is_err());
+        Ok(())
+    }
 }
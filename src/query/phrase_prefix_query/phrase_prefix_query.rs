@@ -1,8 +1,7 @@
 use std::ops::Bound;
 
 use super::{prefix_end, PhrasePrefixWeight};
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, Query, RangeQuery, Weight};
+use crate::query::{EnableScoring, MatchPattern, Query, RangeQuery, Weight};
 use crate::schema::{Field, IndexRecordOption, Term};
 
 const DEFAULT_MAX_EXPANSIONS: u32 = 50;
@@ -110,16 +109,11 @@ impl PhrasePrefixQuery {
             )));
         }
         let terms = self.phrase_terms();
-        let bm25_weight_opt = match enable_scoring {
-            EnableScoring::Enabled { searcher, .. } => {
-                Some(Bm25Weight::for_terms(searcher, &terms)?)
-            }
-            EnableScoring::Disabled { .. } => None,
-        };
+        let similarity_weight_opt = enable_scoring.similarity_weight(&terms)?;
         let weight = PhrasePrefixWeight::new(
             self.phrase_terms.clone(),
             self.prefix.clone(),
-            bm25_weight_opt,
+            similarity_weight_opt,
             self.max_expansions,
         );
         Ok(Some(weight))
@@ -164,4 +158,12 @@ impl Query for PhrasePrefixQuery {
             visitor(term, true);
         }
     }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        visitor(MatchPattern::PhrasePrefix {
+            terms: &self.phrase_terms,
+            prefix: &self.prefix,
+            max_expansions: self.max_expansions,
+        });
+    }
 }
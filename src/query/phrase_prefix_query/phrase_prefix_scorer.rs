@@ -1,9 +1,10 @@
+use std::sync::Arc;
+
 use crate::docset::{DocSet, TERMINATED};
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::Postings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::phrase_query::{intersection_count, PhraseScorer};
-use crate::query::Scorer;
+use crate::query::{Scorer, SimilarityWeight};
 use crate::{DocId, Score};
 
 enum PhraseKind<TPostings: Postings> {
@@ -103,7 +104,7 @@ impl<TPostings: Postings> PhrasePrefixScorer<TPostings> {
     // If similarity_weight is None, then scoring is disabled.
     pub fn new(
         mut term_postings: Vec<(usize, TPostings)>,
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
         fieldnorm_reader: FieldNormReader,
         suffixes: Vec<TPostings>,
         suffix_pos: usize,
@@ -1,17 +1,18 @@
+use std::sync::Arc;
+
 use super::{prefix_end, PhrasePrefixScorer};
 use crate::core::SegmentReader;
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::SegmentPostings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
-use crate::query::{EmptyScorer, Explanation, Scorer, Weight};
+use crate::query::{EmptyScorer, Explanation, Scorer, SimilarityWeight, Weight};
 use crate::schema::{IndexRecordOption, Term};
 use crate::{DocId, DocSet, Score};
 
 pub struct PhrasePrefixWeight {
     phrase_terms: Vec<(usize, Term)>,
     prefix: (usize, Term),
-    similarity_weight_opt: Option<Bm25Weight>,
+    similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
     max_expansions: u32,
 }
 
@@ -21,7 +22,7 @@ impl PhrasePrefixWeight {
     pub fn new(
         phrase_terms: Vec<(usize, Term)>,
         prefix: (usize, Term),
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
         max_expansions: u32,
     ) -> PhrasePrefixWeight {
         PhrasePrefixWeight {
@@ -1,6 +1,5 @@
 use super::PhraseWeight;
-use crate::query::bm25::Bm25Weight;
-use crate::query::{EnableScoring, Query, Weight};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
 use crate::schema::{Field, IndexRecordOption, Term};
 
 /// `PhraseQuery` matches a specific sequence of words.
@@ -117,14 +116,8 @@ impl PhraseQuery {
             )));
         }
         let terms = self.phrase_terms();
-        let bm25_weight_opt = match enable_scoring {
-            EnableScoring::Enabled {
-                statistics_provider,
-                ..
-            } => Some(Bm25Weight::for_terms(statistics_provider, &terms)?),
-            EnableScoring::Disabled { .. } => None,
-        };
-        let mut weight = PhraseWeight::new(self.phrase_terms.clone(), bm25_weight_opt);
+        let similarity_weight_opt = enable_scoring.similarity_weight(&terms)?;
+        let mut weight = PhraseWeight::new(self.phrase_terms.clone(), similarity_weight_opt);
         if self.slop > 0 {
             weight.slop(self.slop);
         }
@@ -146,4 +139,11 @@ impl Query for PhraseQuery {
             visitor(term, true);
         }
     }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        visitor(MatchPattern::Phrase {
+            terms: &self.phrase_terms,
+            slop: self.slop,
+        });
+    }
 }
@@ -1,10 +1,10 @@
 use std::cmp::Ordering;
+use std::sync::Arc;
 
 use crate::docset::{DocSet, TERMINATED};
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::Postings;
-use crate::query::bm25::Bm25Weight;
-use crate::query::{Intersection, Scorer};
+use crate::query::{Intersection, Scorer, SimilarityWeight};
 use crate::{DocId, Score};
 
 struct PostingsWithOffset<TPostings> {
@@ -50,7 +50,7 @@ pub struct PhraseScorer<TPostings: Postings> {
     right_positions: Vec<u32>,
     phrase_count: u32,
     fieldnorm_reader: FieldNormReader,
-    similarity_weight_opt: Option<Bm25Weight>,
+    similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
     slop: u32,
     left_slops: Vec<u8>,
     positions_buffer: Vec<u32>,
@@ -348,7 +348,7 @@ impl<TPostings: Postings> PhraseScorer<TPostings> {
     // If similarity_weight is None, then scoring is disabled.
     pub fn new(
         term_postings: Vec<(usize, TPostings)>,
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
         fieldnorm_reader: FieldNormReader,
         slop: u32,
     ) -> PhraseScorer<TPostings> {
@@ -363,7 +363,7 @@ impl<TPostings: Postings> PhraseScorer<TPostings> {
 
     pub(crate) fn new_with_offset(
         term_postings_with_offset: Vec<(usize, TPostings)>,
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
         fieldnorm_reader: FieldNormReader,
         slop: u32,
         offset: usize,
@@ -1,16 +1,17 @@
+use std::sync::Arc;
+
 use super::PhraseScorer;
 use crate::core::SegmentReader;
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::SegmentPostings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
-use crate::query::{EmptyScorer, Explanation, Scorer, Weight};
+use crate::query::{EmptyScorer, Explanation, Scorer, SimilarityWeight, Weight};
 use crate::schema::{IndexRecordOption, Term};
 use crate::{DocId, DocSet, Score};
 
 pub struct PhraseWeight {
     phrase_terms: Vec<(usize, Term)>,
-    similarity_weight_opt: Option<Bm25Weight>,
+    similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
     slop: u32,
 }
 
@@ -19,7 +20,7 @@ impl PhraseWeight {
     /// If `similarity_weight_opt` is None, then scoring is disabled
     pub fn new(
         phrase_terms: Vec<(usize, Term)>,
-        similarity_weight_opt: Option<Bm25Weight>,
+        similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
     ) -> PhraseWeight {
         let slop = 0;
         PhraseWeight {
@@ -1,8 +1,10 @@
 use std::fmt;
+use std::sync::Arc;
 
 use downcast_rs::impl_downcast;
 
 use super::bm25::Bm25StatisticsProvider;
+use super::similarity::{Similarity, SimilarityStatistics, SimilarityWeight};
 use super::Weight;
 use crate::core::searcher::Searcher;
 use crate::query::Explanation;
@@ -22,6 +24,11 @@ pub enum EnableScoring<'a> {
         /// Normally this should be the [Searcher], but you can specify a custom
         /// one to adjust the statistics.
         statistics_provider: &'a dyn Bm25StatisticsProvider,
+
+        /// A [Similarity] overriding the similarities configured in the schema.
+        ///
+        /// See [`SimilarityQuery`](crate::query::SimilarityQuery).
+        similarity_opt: Option<&'a dyn Similarity>,
     },
     /// Pass this to disable scoring.
     /// This can improve performance.
@@ -39,6 +46,7 @@ impl<'a> EnableScoring<'a> {
         EnableScoring::Enabled {
             searcher,
             statistics_provider: searcher,
+            similarity_opt: None,
         }
     }
 
@@ -50,6 +58,7 @@ impl<'a> EnableScoring<'a> {
         EnableScoring::Enabled {
             statistics_provider,
             searcher,
+            similarity_opt: None,
         }
     }
 
@@ -89,6 +98,56 @@ impl<'a> EnableScoring<'a> {
     pub fn is_scoring_enabled(&self) -> bool {
         matches!(self, EnableScoring::Enabled { .. })
     }
+
+    /// Overrides the similarities configured in the schema.
+    ///
+    /// This has no effect if scoring is disabled.
+    #[must_use]
+    pub fn with_similarity(self, similarity: &'a dyn Similarity) -> EnableScoring<'a> {
+        match self {
+            EnableScoring::Enabled {
+                searcher,
+                statistics_provider,
+                ..
+            } => EnableScoring::Enabled {
+                searcher,
+                statistics_provider,
+                similarity_opt: Some(similarity),
+            },
+            disabled @ EnableScoring::Disabled { .. } => disabled,
+        }
+    }
+
+    /// Builds the [`SimilarityWeight`] used to score a group of terms of a same field.
+    ///
+    /// The similarity is the one overriding the schema if any, and otherwise the
+    /// similarity configured for the field in the schema.
+    ///
+    /// Returns `None` if scoring is disabled.
+    pub(crate) fn similarity_weight(
+        &self,
+        terms: &[Term],
+    ) -> crate::Result<Option<Arc<dyn SimilarityWeight>>> {
+        let (searcher, statistics_provider, similarity_opt) = match *self {
+            EnableScoring::Enabled {
+                searcher,
+                statistics_provider,
+                similarity_opt,
+            } => (searcher, statistics_provider, similarity_opt),
+            EnableScoring::Disabled { .. } => return Ok(None),
+        };
+        let statistics = SimilarityStatistics::for_terms(statistics_provider, terms)?;
+        let similarity_weight = if let Some(similarity) = similarity_opt {
+            similarity.weight(&statistics)
+        } else {
+            let field = terms[0].field();
+            searcher
+                .index()
+                .similarity_for_field(field)?
+                .weight(&statistics)
+        };
+        Ok(Some(similarity_weight))
+    }
 }
 
 /// The `Query` trait defines a set of documents and a scoring method
@@ -160,6 +219,41 @@ pub trait Query: QueryClone + Send + Sync + downcast_rs::Downcast + fmt::Debug {
     /// Note that there can be multiple instances of any given term
     /// in a query and deduplication must be handled by the visitor.
     fn query_terms<'a>(&'a self, _visitor: &mut dyn FnMut(&'a Term, bool)) {}
+
+    /// Extract the patterns the query matches in the text of a document, such as
+    /// terms and phrases, and pass them to the given closure.
+    ///
+    /// This is used to highlight the matches of a query. By default, each term
+    /// extracted by [`Query::query_terms`] is passed as a [`MatchPattern::Term`].
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.query_terms(&mut |term, _| visitor(MatchPattern::Term(term)));
+    }
+}
+
+/// Pattern matched by a query in the text of a document.
+///
+/// See [`Query::match_patterns`].
+#[derive(Clone, Copy, Debug)]
+pub enum MatchPattern<'a> {
+    /// A single term.
+    Term(&'a Term),
+    /// A sequence of terms, associated with their offset within the phrase.
+    Phrase {
+        /// The terms of the phrase and their offsets.
+        terms: &'a [(usize, Term)],
+        /// The number of positions each term may be moved by.
+        slop: u32,
+    },
+    /// A sequence of terms followed by a prefix, associated with their offset within
+    /// the phrase.
+    PhrasePrefix {
+        /// The terms of the phrase and their offsets, without the prefix.
+        terms: &'a [(usize, Term)],
+        /// The prefix ending the phrase, and its offset.
+        prefix: &'a (usize, Term),
+        /// The maximum number of terms the prefix expands to.
+        max_expansions: u32,
+    },
 }
 
 /// Implements `box_clone`.
@@ -188,6 +282,10 @@ impl Query for Box<dyn Query> {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.as_ref().query_terms(visitor);
     }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.as_ref().match_patterns(visitor);
+    }
 }
 
 impl QueryClone for Box<dyn Query> {
@@ -1,5 +1,8 @@
 use std::fmt;
 use std::ops::Bound;
+use std::sync::Arc;
+
+use tantivy_fst::Regex;
 
 use crate::query::Occur;
 use crate::schema::{Field, Term, Type};
@@ -24,6 +27,27 @@ pub enum LogicalLiteral {
         value_type: Type,
         elements: Vec<Term>,
     },
+    Near {
+        operands: Vec<Vec<Term>>,
+        distance: u32,
+    },
+    Wildcard {
+        field: Field,
+        pattern: String,
+        max_expansions: Option<u32>,
+    },
+    Regex {
+        field: Field,
+        pattern: String,
+        regex: Arc<Regex>,
+    },
+    Fuzzy {
+        term: Term,
+        distance: u8,
+    },
+    Exists {
+        field_name: String,
+    },
     All,
 }
 
@@ -122,6 +146,22 @@ impl fmt::Debug for LogicalLiteral {
                 }
                 write!(formatter, "]")
             }
+            LogicalLiteral::Near {
+                ref operands,
+                distance,
+            } => write!(formatter, "NEAR/{distance}({operands:?})"),
+            LogicalLiteral::Wildcard {
+                field, ref pattern, ..
+            } => write!(formatter, "Wildcard({field:?}, {pattern:?})"),
+            LogicalLiteral::Regex {
+                field, ref pattern, ..
+            } => write!(formatter, "Regex({field:?}, {pattern:?})"),
+            LogicalLiteral::Fuzzy { ref term, distance } => {
+                write!(formatter, "{term:?}~{distance}")
+            }
+            LogicalLiteral::Exists { ref field_name } => {
+                write!(formatter, "Exists({field_name:?})")
+            }
             LogicalLiteral::All => write!(formatter, "*"),
         }
     }
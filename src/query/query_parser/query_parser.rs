@@ -2,12 +2,14 @@ use std::net::{AddrParseError, IpAddr};
 use std::num::{ParseFloatError, ParseIntError};
 use std::ops::Bound;
 use std::str::{FromStr, ParseBoolError};
+use std::sync::Arc;
 
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
 use itertools::Itertools;
 use query_grammar::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 use rustc_hash::FxHashMap;
+use tantivy_fst::Regex;
 
 use super::logical_ast::*;
 use crate::core::json_utils::{
@@ -15,9 +17,11 @@ use crate::core::json_utils::{
 };
 use crate::core::Index;
 use crate::query::range_query::{is_type_valid_for_fastfield_range_query, RangeQuery};
+use crate::query::wildcard_query::has_leading_wildcard;
 use crate::query::{
-    AllQuery, BooleanQuery, BoostQuery, EmptyQuery, FuzzyTermQuery, Occur, PhrasePrefixQuery,
-    PhraseQuery, Query, TermQuery, TermSetQuery,
+    AllQuery, BooleanQuery, BoostQuery, EmptyQuery, ExistsQuery, FuzzyTermQuery, Occur,
+    PhrasePrefixQuery, PhraseQuery, Query, RegexQuery, SpanNearQuery, SpanQuery, SpanTermQuery,
+    TermQuery, TermSetQuery, WildcardQuery,
 };
 use crate::schema::{
     Facet, FacetParseError, Field, FieldType, IndexRecordOption, IntoIpv6Addr, JsonObjectOptions,
@@ -104,8 +108,14 @@ pub enum QueryParserError {
     /// The format for the ip field is invalid.
     #[error("The ip field is malformed: {0}")]
     IpFormatError(#[from] AddrParseError),
+    /// The query contains a regex which is invalid, or which compiles to a too large automaton.
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(String),
 }
 
+/// Maximum edit distance of a fuzzy term. Larger distances build huge automata.
+const MAX_FUZZY_DISTANCE: u8 = 2;
+
 /// Recursively remove empty clause from the AST
 ///
 /// Returns `None` if and only if the `logical_ast` ended up being empty.
@@ -184,6 +194,10 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 ///
 /// * all docs query: A plain `*` will match all documents in the index.
 ///
+/// * exists query: `title:*`, or `_exists_:title`, will match all documents having a value in the
+///   `title` field. The field can be a json path, and must be either indexed or fast. Documents
+///   missing a value can be searched with `* -title:*`.
+///
 /// Parts of the queries can be boosted by appending `^boostfactor`.
 /// For instance, `"SRE"^2.0 OR devops^0.4` will boost documents containing `SRE` instead of
 /// devops. Negative boosts are not allowed.
@@ -198,6 +212,26 @@ fn trim_ast(logical_ast: LogicalAst) -> Option<LogicalAst> {
 /// Phrase terms support the `~` slop operator which allows to set the phrase's matching
 /// distance in words. `"big wolf"~1` will return documents containing the phrase `"big bad wolf"`.
 ///
+/// Terms can be searched close to each other, in any order, with the `NEAR` operator.
+/// `title:rust NEAR/3 compiler` will return documents where `rust` and `compiler` are separated
+/// by at most 3 words in the title field. Without an explicit distance, as in `rust NEAR
+/// compiler`, the distance defaults to 10. Quoted operands are matched as exact phrases, and
+/// operators can be chained as long as they use the same distance, e.g. `a NEAR/2 b NEAR/2 c`.
+/// The targeted fields must be text fields with positions indexed, JSON fields are not supported.
+/// Operands which produce no token, e.g. because they are only made of stop words, are rejected.
+/// As `NEAR` and `NEAR/N` are operators, searching for them as a word requires quoting them,
+/// e.g. `"NEAR"`.
+///
+/// Terms containing the wildcards `*` (any sequence of characters) or `?` (exactly one character)
+/// are searched with a [`WildcardQuery`], e.g. `title:te?t*`. Wildcards can be escaped with a
+/// `\`. Patterns starting with a wildcard are rejected, unless allowed with
+/// [`QueryParser::set_allow_leading_wildcard`].
+///
+/// Terms can be searched with a regex delimited by slashes, e.g. `country:/jap[ao]n/`, or with a
+/// maximum number of edits by appending a `~` and an edit distance, e.g. `japon~1`. The distance
+/// defaults to 2 and cannot exceed 2. Unlike the other terms, regexes are matched as is against
+/// the terms of the index, without going through the tokenizer.
+///
 /// Phrase terms also support the `*` prefix operator which switches the phrase's matching
 /// to consider all documents which contain the last term as a prefix, e.g. `"big bad wo"*` will
 /// match `"big bad wolf"`.
@@ -209,6 +243,8 @@ pub struct QueryParser {
     tokenizer_manager: TokenizerManager,
     boost: FxHashMap<Field, Score>,
     fuzzy: FxHashMap<Field, Fuzzy>,
+    allow_leading_wildcard: bool,
+    wildcard_max_expansions: Option<u32>,
 }
 
 #[derive(Clone)]
@@ -263,6 +299,8 @@ impl QueryParser {
             conjunction_by_default: false,
             boost: Default::default(),
             fuzzy: Default::default(),
+            allow_leading_wildcard: false,
+            wildcard_max_expansions: None,
         }
     }
 
@@ -324,6 +362,20 @@ impl QueryParser {
         );
     }
 
+    /// Allows wildcard patterns starting with a `*` or a `?`, such as `*ing`.
+    ///
+    /// These patterns need to scan the whole term dictionary, and are rejected by default.
+    pub fn set_allow_leading_wildcard(&mut self, allow_leading_wildcard: bool) {
+        self.allow_leading_wildcard = allow_leading_wildcard;
+    }
+
+    /// Sets the maximum number of terms to which a wildcard pattern will expand.
+    ///
+    /// See [`WildcardQuery::set_max_expansions`].
+    pub fn set_wildcard_max_expansions(&mut self, max_expansions: u32) {
+        self.wildcard_max_expansions = Some(max_expansions);
+    }
+
     /// Parse a query
     ///
     /// Note that `parse_query` returns an error if the input
@@ -509,6 +561,13 @@ impl QueryParser {
                 let ip_v6 = IpAddr::from_str(phrase)?.into_ipv6_addr();
                 Ok(Term::from_field_ip_addr(field, ip_v6))
             }
+            FieldType::Vector(_) => Err(QueryParserError::FieldNotIndexed(
+                field_entry.name().to_string(),
+            )),
+            FieldType::GeoPoint(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "Range queries are not supported on the geo point field {:?}",
+                field_entry.name()
+            ))),
         }
     }
 
@@ -609,7 +668,191 @@ impl QueryParser {
                 let term = Term::from_field_ip_addr(field, ip_v6);
                 Ok(vec![LogicalLiteral::Term(term)])
             }
+            // Vector fields are not indexed, this was checked above.
+            FieldType::Vector(_) => Err(QueryParserError::FieldNotIndexed(field_name.to_string())),
+            FieldType::GeoPoint(_) => Err(QueryParserError::UnsupportedQuery(format!(
+                "The geo point field {field_name:?} can only be searched with geo queries"
+            ))),
+        }
+    }
+
+    /// Returns the indexing options of a text field, for the queries which are only supported
+    /// on text fields.
+    fn text_field_indexing(
+        &self,
+        field: Field,
+        json_path: &str,
+        query_name: &str,
+    ) -> Result<&TextFieldIndexing, QueryParserError> {
+        let field_entry = self.schema.get_field_entry(field);
+        let field_type = field_entry.field_type();
+        let field_name = field_entry.name();
+        if !field_type.is_indexed() {
+            return Err(QueryParserError::FieldNotIndexed(field_name.to_string()));
+        }
+        if field_type.value_type() != Type::Json && !json_path.is_empty() {
+            return Err(QueryParserError::FieldDoesNotExist(format!(
+                "{field_name}.{json_path}"
+            )));
+        }
+        if let FieldType::Str(ref str_options) = *field_type {
+            str_options
+                .get_indexing_options()
+                .ok_or_else(|| QueryParserError::FieldNotIndexed(field_name.to_string()))
+        } else {
+            Err(QueryParserError::UnsupportedQuery(format!(
+                "{query_name} queries are only supported on text fields, {field_name:?} is not one"
+            )))
+        }
+    }
+
+    fn text_analyzer(
+        &self,
+        field: Field,
+        indexing_options: &TextFieldIndexing,
+    ) -> Result<TextAnalyzer, QueryParserError> {
+        self.tokenizer_manager
+            .get(indexing_options.tokenizer())
+            .ok_or_else(|| QueryParserError::UnknownTokenizer {
+                field: self.schema.get_field_name(field).to_string(),
+                tokenizer: indexing_options.tokenizer().to_string(),
+            })
+    }
+
+    fn compute_logical_ast_for_near(
+        &self,
+        field: Field,
+        json_path: &str,
+        terms: &[String],
+        distance: u32,
+    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+        let indexing_options = self.text_field_indexing(field, json_path, "NEAR")?;
+        if !indexing_options.index_option().has_positions() {
+            return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
+                self.schema.get_field_name(field).to_string(),
+            ));
+        }
+        let mut text_analyzer = self.text_analyzer(field, indexing_options)?;
+        let mut operands = Vec::with_capacity(terms.len());
+        for text in terms {
+            let mut operand = Vec::new();
+            let mut token_stream = text_analyzer.token_stream(text);
+            token_stream.process(&mut |token| {
+                operand.push(Term::from_field_text(field, &token.text));
+            });
+            if operand.is_empty() {
+                return Err(QueryParserError::UnsupportedQuery(format!(
+                    "NEAR operand {text:?} does not contain any token"
+                )));
+            }
+            operands.push(operand);
+        }
+        Ok(Some(LogicalLiteral::Near { operands, distance }))
+    }
+
+    fn compute_logical_ast_for_wildcard(
+        &self,
+        field: Field,
+        json_path: &str,
+        pattern: &str,
+    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+        let indexing_options = self.text_field_indexing(field, json_path, "Wildcard")?;
+        if !self.allow_leading_wildcard && has_leading_wildcard(pattern) {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "Leading wildcards are not allowed in {pattern:?}"
+            )));
+        }
+        let mut text_analyzer = self.text_analyzer(field, indexing_options)?;
+        Ok(Some(LogicalLiteral::Wildcard {
+            field,
+            pattern: normalize_wildcard_pattern(pattern, &mut text_analyzer),
+            max_expansions: self.wildcard_max_expansions,
+        }))
+    }
+
+    fn compute_logical_ast_for_regex(
+        &self,
+        field: Field,
+        json_path: &str,
+        pattern: &str,
+    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+        self.text_field_indexing(field, json_path, "Regex")?;
+        // The automaton is built here, so that patterns which are invalid or which exceed the
+        // automaton size limit are reported as parsing errors.
+        let regex = Regex::new(pattern)
+            .map_err(|err| QueryParserError::InvalidRegex(format!("{pattern:?}: {err}")))?;
+        Ok(Some(LogicalLiteral::Regex {
+            field,
+            pattern: pattern.to_string(),
+            regex: Arc::new(regex),
+        }))
+    }
+
+    fn compute_logical_ast_for_fuzzy(
+        &self,
+        field: Field,
+        json_path: &str,
+        text: &str,
+        distance: u8,
+    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+        let indexing_options = self.text_field_indexing(field, json_path, "Fuzzy")?;
+        if distance > MAX_FUZZY_DISTANCE {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "The edit distance of {text:?} is {distance}, the maximum is {MAX_FUZZY_DISTANCE}"
+            )));
+        }
+        let mut text_analyzer = self.text_analyzer(field, indexing_options)?;
+        let mut terms = Vec::new();
+        let mut token_stream = text_analyzer.token_stream(text);
+        token_stream.process(&mut |token| {
+            terms.push(Term::from_field_text(field, &token.text));
+        });
+        if terms.len() > 1 {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "The fuzzy term {text:?} is split into several terms by the tokenizer"
+            )));
+        }
+        Ok(terms
+            .pop()
+            .map(|term| LogicalLiteral::Fuzzy { term, distance }))
+    }
+
+    /// Computes the logical AST of a leaf, on the field it targets or on the default fields.
+    fn compute_logical_ast_for_fields(
+        &self,
+        full_path_opt: Option<&str>,
+        mut compute_literal: impl FnMut(Field, &str) -> Result<Option<LogicalLiteral>, QueryParserError>,
+    ) -> (Option<LogicalAst>, Vec<QueryParserError>) {
+        let field_paths: Vec<(Field, &str)> = if let Some(full_path) = full_path_opt {
+            vec![try_tuple!(self.split_full_path(full_path).ok_or_else(
+                || QueryParserError::FieldDoesNotExist(full_path.to_string())
+            ))]
+        } else if self.default_fields.is_empty() {
+            return (None, vec![QueryParserError::NoDefaultFieldDeclared]);
+        } else {
+            self.default_fields
+                .iter()
+                .map(|default_field| (*default_field, ""))
+                .collect()
+        };
+        let mut asts: Vec<LogicalAst> = Vec::new();
+        let mut errors: Vec<QueryParserError> = Vec::new();
+        for (field, json_path) in field_paths {
+            match compute_literal(field, json_path) {
+                Ok(Some(literal)) => {
+                    let boost = self.field_boost(field);
+                    asts.push(LogicalAst::Leaf(Box::new(literal)).boost(boost));
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(e),
+            }
         }
+        let result_ast: LogicalAst = if asts.len() == 1 {
+            asts.into_iter().next().unwrap()
+        } else {
+            LogicalAst::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
+        };
+        (Some(result_ast), errors)
     }
 
     fn default_occur(&self) -> Occur {
@@ -847,6 +1090,56 @@ impl QueryParser {
                 }));
                 (Some(logical_ast), errors)
             }
+            UserInputLeaf::Near {
+                field: full_field_opt,
+                terms,
+                distance,
+            } => self.compute_logical_ast_for_fields(
+                full_field_opt.as_deref(),
+                |field, json_path| {
+                    self.compute_logical_ast_for_near(field, json_path, &terms, distance)
+                },
+            ),
+            UserInputLeaf::Wildcard {
+                field: full_field_opt,
+                pattern,
+            } => self.compute_logical_ast_for_fields(
+                full_field_opt.as_deref(),
+                |field, json_path| {
+                    self.compute_logical_ast_for_wildcard(field, json_path, &pattern)
+                },
+            ),
+            UserInputLeaf::Regex {
+                field: full_field_opt,
+                pattern,
+            } => self
+                .compute_logical_ast_for_fields(full_field_opt.as_deref(), |field, json_path| {
+                    self.compute_logical_ast_for_regex(field, json_path, &pattern)
+                }),
+            UserInputLeaf::Fuzzy {
+                field: full_field_opt,
+                term,
+                distance,
+            } => self.compute_logical_ast_for_fields(
+                full_field_opt.as_deref(),
+                |field, json_path| {
+                    self.compute_logical_ast_for_fuzzy(field, json_path, &term, distance)
+                },
+            ),
+            UserInputLeaf::Exists { field: full_path } => {
+                let (field, _) = try_tuple!(self
+                    .split_full_path(&full_path)
+                    .ok_or_else(|| QueryParserError::FieldDoesNotExist(full_path.clone())));
+                let field_entry = self.schema.get_field_entry(field);
+                if !field_entry.is_indexed() && !field_entry.is_fast() {
+                    let field_name = field_entry.name().to_string();
+                    return (None, vec![QueryParserError::FieldNotIndexed(field_name)]);
+                }
+                let logical_ast = LogicalAst::Leaf(Box::new(LogicalLiteral::Exists {
+                    field_name: full_path,
+                }));
+                (Some(logical_ast), Vec::new())
+            }
         }
     }
 }
@@ -895,10 +1188,81 @@ fn convert_literal_to_query(
             field, value_type, &lower, &upper,
         )),
         LogicalLiteral::Set { elements, .. } => Box::new(TermSetQuery::new(elements)),
+        LogicalLiteral::Near { operands, distance } => {
+            let span_term =
+                |term: Term| -> Box<dyn SpanQuery> { Box::new(SpanTermQuery::new(term)) };
+            let clauses: Vec<Box<dyn SpanQuery>> = operands
+                .into_iter()
+                .map(|mut operand| {
+                    if operand.len() == 1 {
+                        span_term(operand.pop().unwrap())
+                    } else {
+                        // a quoted operand is matched as an exact phrase.
+                        let phrase = operand.into_iter().map(span_term).collect();
+                        Box::new(SpanNearQuery::new(phrase, 0, true))
+                    }
+                })
+                .collect();
+            Box::new(SpanNearQuery::new(clauses, distance, false))
+        }
+        LogicalLiteral::Wildcard {
+            field,
+            pattern,
+            max_expansions,
+        } => {
+            let mut wildcard_query = WildcardQuery::new(&pattern, field);
+            if let Some(max_expansions) = max_expansions {
+                wildcard_query.set_max_expansions(max_expansions);
+            }
+            Box::new(wildcard_query)
+        }
+        LogicalLiteral::Regex { field, regex, .. } => {
+            Box::new(RegexQuery::from_regex(regex, field))
+        }
+        LogicalLiteral::Fuzzy { term, distance } => {
+            Box::new(FuzzyTermQuery::new(term, distance, true))
+        }
+        LogicalLiteral::Exists { field_name } => Box::new(ExistsQuery::new(field_name)),
         LogicalLiteral::All => Box::new(AllQuery),
     }
 }
 
+/// Normalizes the parts of a wildcard pattern between wildcards with the text analyzer of the
+/// field, so that `Foo*` matches `foobar` on a field lowercasing its tokens.
+///
+/// The parts producing several tokens, or none, are kept as is.
+fn normalize_wildcard_pattern(pattern: &str, text_analyzer: &mut TextAnalyzer) -> String {
+    fn push_normalized(text: &str, text_analyzer: &mut TextAnalyzer, output: &mut String) {
+        let mut tokens = Vec::new();
+        let mut token_stream = text_analyzer.token_stream(text);
+        token_stream.process(&mut |token| tokens.push(token.text.clone()));
+        let normalized = if tokens.len() == 1 { &tokens[0] } else { text };
+        for c in normalized.chars() {
+            if matches!(c, '*' | '?' | '\\') {
+                output.push('\\');
+            }
+            output.push(c);
+        }
+    }
+
+    let mut normalized_pattern = String::with_capacity(pattern.len());
+    let mut text = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => {
+                push_normalized(&text, text_analyzer, &mut normalized_pattern);
+                text.clear();
+                normalized_pattern.push(c);
+            }
+            '\\' => text.push(chars.next().unwrap_or('\\')),
+            _ => text.push(c),
+        }
+    }
+    push_normalized(&text, text_analyzer, &mut normalized_pattern);
+    normalized_pattern
+}
+
 fn generate_literals_for_str(
     field_name: &str,
     field: Field,
@@ -1021,7 +1385,8 @@ mod test {
 
     use super::super::logical_ast::*;
     use super::{QueryParser, QueryParserError};
-    use crate::query::Query;
+    use crate::collector::Count;
+    use crate::query::{Query, WildcardQuery};
     use crate::schema::{
         FacetOptions, Field, IndexRecordOption, Schema, Term, TextFieldIndexing, TextOptions, FAST,
         INDEXED, STORED, STRING, TEXT,
@@ -1835,6 +2200,242 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_near() {
+        test_parse_query_to_logical_ast_helper(
+            "title:a NEAR/3 b",
+            r#"NEAR/3([[Term(field=0, type=Str, "a")], [Term(field=0, type=Str, "b")]])"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "a NEAR \"b c\"",
+            r#"(NEAR/10([[Term(field=0, type=Str, "a")], [Term(field=0, type=Str, "b"), Term(field=0, type=Str, "c")]]) NEAR/10([[Term(field=1, type=Str, "a")], [Term(field=1, type=Str, "b"), Term(field=1, type=Str, "c")]]))"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("with_stop_words:the NEAR/1 a", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        assert_eq!(
+            parse_query_to_logical_ast("nottokenized:a NEAR b", false).unwrap_err(),
+            QueryParserError::FieldDoesNotHavePositionsIndexed("nottokenized".to_string())
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("signed:1 NEAR 2", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("json.a:b NEAR c", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+    }
+
+    #[test]
+    pub fn test_near_search() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "rust is a systems programming language"))?;
+        index_writer.add_document(doc!(title => "the language of the rust compiler"))?;
+        index_writer.add_document(doc!(title => "a language, and far far away some rust"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("rust NEAR/2 language"), 1);
+        assert_eq!(count("language NEAR/4 rust"), 2);
+        assert_eq!(count("rust NEAR language"), 3);
+        assert_eq!(count("\"rust compiler\" NEAR/2 language"), 1);
+        assert_eq!(count("\"compiler rust\" NEAR/2 language"), 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_wildcard() {
+        test_parse_query_to_logical_ast_helper(
+            "title:Fo?o*",
+            r#"Wildcard(Field(0), "fo?o*")"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "te*t",
+            r#"(Wildcard(Field(0), "te*t") Wildcard(Field(1), "te*t"))"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            r"title:A\*B*",
+            r#"Wildcard(Field(0), "A\\*B*")"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:*ing", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("signed:1*", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        let mut query_parser = make_query_parser();
+        query_parser.set_allow_leading_wildcard(true);
+        let query = query_parser.parse_query("title:*ing").unwrap();
+        let wildcard_query = query.downcast_ref::<WildcardQuery>().unwrap();
+        assert_eq!(wildcard_query.pattern(), "*ing");
+    }
+
+    #[test]
+    pub fn test_wildcard_search() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "Test driven development"))?;
+        index_writer.add_document(doc!(title => "a text about tests"))?;
+        index_writer.add_document(doc!(title => "toast"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let mut query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query_parser: &QueryParser, query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count(&query_parser, "Te?t"), 2);
+        assert_eq!(count(&query_parser, "te*t"), 2);
+        assert_eq!(count(&query_parser, "t*t"), 3);
+        assert_eq!(count(&query_parser, "t*t -toast"), 2);
+        query_parser.set_wildcard_max_expansions(1);
+        // "test", "text" and "toast" match.
+        let query = query_parser.parse_query("t*t")?;
+        assert!(searcher.search(&query, &Count).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_regex() {
+        test_parse_query_to_logical_ast_helper(
+            "title:/jap[ao]n/",
+            r#"Regex(Field(0), "jap[ao]n")"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "/a.*/",
+            r#"(Regex(Field(0), "a.*") Regex(Field(1), "a.*"))"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:/jap[ao/", false),
+            Err(QueryParserError::InvalidRegex(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:/a{1000}{1000}/", false),
+            Err(QueryParserError::InvalidRegex(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("signed:/1.*/", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+    }
+
+    #[test]
+    pub fn test_fuzzy() {
+        test_parse_query_to_logical_ast_helper(
+            "title:Japon~1",
+            r#"Term(field=0, type=Str, "japon")~1"#,
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:japon~",
+            r#"Term(field=0, type=Str, "japon")~2"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:japon~3", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("title:ja-pon~1", false),
+            Err(QueryParserError::UnsupportedQuery(_))
+        );
+        let query_parser = make_query_parser();
+        let query = query_parser.parse_query("title:japon~1").unwrap();
+        assert_eq!(
+            format!("{query:?}"),
+            r#"FuzzyTermQuery { term: Term(field=0, type=Str, "japon"), distance: 1, transposition_cost_one: true, prefix: false }"#
+        );
+    }
+
+    #[test]
+    pub fn test_regex_and_fuzzy_search() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let country = schema_builder.add_text_field("country", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(country => "Japan"))?;
+        index_writer.add_document(doc!(country => "Korea"))?;
+        index_writer.add_document(doc!(country => "Kenya"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![country]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("/k.*a/"), 2);
+        assert_eq!(count("/jap[ao]n/ /kore./"), 2);
+        assert_eq!(count("japon~1"), 1);
+        assert_eq!(count("corea~1"), 1);
+        assert_eq!(count("kenia~2 -/k.r.*/"), 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_exists() {
+        test_parse_query_to_logical_ast_helper("title:*", r#"Exists("title")"#, false);
+        test_parse_query_to_logical_ast_helper("_exists_:u64_ff", r#"Exists("u64_ff")"#, false);
+        test_parse_query_to_logical_ast_helper(
+            "json.a.b:* -signed:*",
+            r#"(Exists("json.a.b") -Exists("signed"))"#,
+            false,
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("missing:*", false),
+            Err(QueryParserError::FieldDoesNotExist(_))
+        );
+        assert_matches!(
+            parse_query_to_logical_ast("_exists_:notindexed_text", false),
+            Err(QueryParserError::FieldNotIndexed(_))
+        );
+    }
+
+    #[test]
+    pub fn test_exists_search() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let year = schema_builder.add_u64_field("year", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "dune", year => 1965u64))?;
+        index_writer.add_document(doc!(title => "hyperion"))?;
+        index_writer.add_document(doc!(year => 1989u64))?;
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("title:*"), 2);
+        assert_eq!(count("_exists_:year"), 2);
+        assert_eq!(count("title:* AND year:*"), 1);
+        assert_eq!(count("* -year:*"), 2);
+        assert_eq!(count("* -title:* -_exists_:year"), 1);
+        Ok(())
+    }
+
     #[test]
     pub fn test_term_set_query() {
         test_parse_query_to_logical_ast_helper(
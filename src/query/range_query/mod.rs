@@ -16,7 +16,7 @@ pub(crate) fn is_type_valid_for_fastfield_range_query(typ: Type) -> bool {
     match typ {
         Type::U64 | Type::I64 | Type::F64 | Type::Bool | Type::Date => true,
         Type::IpAddr => true,
-        Type::Str | Type::Facet | Type::Bytes | Type::Json => false,
+        Type::Str | Type::Facet | Type::Bytes | Type::Json | Type::Vector | Type::GeoPoint => false,
     }
 }
 
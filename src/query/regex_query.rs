@@ -11,8 +11,8 @@ use crate::schema::Field;
 /// containing a specific term that matches
 /// a regex pattern.
 ///
-/// Wildcard queries (e.g. ho*se) can be achieved
-/// by converting them to their regex counterparts.
+/// Wildcard queries (e.g. ho*se) are better expressed
+/// with a [`WildcardQuery`](crate::query::WildcardQuery).
 ///
 /// ```rust
 /// use tantivy::collector::Count;
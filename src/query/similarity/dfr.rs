@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use super::{field_length, Similarity, SimilarityStatistics, SimilarityWeight};
+use crate::query::Explanation;
+use crate::Score;
+
+/// Divergence from randomness [`Similarity`].
+///
+/// This implements the `GL2` model: the geometric basic model `G`, the Laplace
+/// after effect `L` and the `H2` term frequency normalization.
+///
+/// - The term frequency is first normalized as `tfn = freq * log2(1 + c * avgdl / dl)`.
+/// - With `λ = F / (N + F)`, the score is then `(A + tfn * B) / (1 + tfn)` where `A = log2(1 + λ)`
+///   and `B = log2((1 + λ) / λ)`.
+///
+/// tantivy does not record the total number of occurrences of a term in the
+/// collection, so `F` is estimated as `n + 1` where `n` is the number of documents
+/// containing the term.
+///
+/// For a phrase, the scores of its terms are summed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DfrSimilarity {
+    c: Score,
+}
+
+impl Default for DfrSimilarity {
+    fn default() -> Self {
+        DfrSimilarity { c: 1.0 }
+    }
+}
+
+impl DfrSimilarity {
+    /// Creates a DFR similarity with the given `H2` normalization parameter `c`
+    /// (default `1.0`).
+    pub fn new(c: Score) -> DfrSimilarity {
+        assert!(c > 0.0, "c must be strictly positive, got {c}");
+        DfrSimilarity { c }
+    }
+}
+
+impl Similarity for DfrSimilarity {
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight> {
+        let total_num_docs = statistics.total_num_docs() as Score;
+        let terms = statistics
+            .doc_freqs()
+            .iter()
+            .map(|&doc_freq| {
+                let collection_freq = doc_freq as Score + 1.0;
+                let lambda = collection_freq / (total_num_docs + collection_freq);
+                DfrTerm {
+                    a: (1.0 + lambda).log2(),
+                    b: ((1.0 + lambda) / lambda).log2(),
+                }
+            })
+            .collect();
+        Arc::new(DfrWeight {
+            c: self.c,
+            average_fieldnorm: statistics.average_fieldnorm(),
+            terms,
+            boost: 1.0,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct DfrTerm {
+    a: Score,
+    b: Score,
+}
+
+impl DfrTerm {
+    fn score(&self, tfn: Score) -> Score {
+        (self.a + tfn * self.b) / (1.0 + tfn)
+    }
+}
+
+#[derive(Clone)]
+struct DfrWeight {
+    c: Score,
+    average_fieldnorm: Score,
+    terms: Vec<DfrTerm>,
+    boost: Score,
+}
+
+impl DfrWeight {
+    fn normalized_term_freq(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        term_freq as Score
+            * (1.0 + self.c * self.average_fieldnorm / field_length(fieldnorm_id)).log2()
+    }
+}
+
+impl SimilarityWeight for DfrWeight {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let tfn = self.normalized_term_freq(fieldnorm_id, term_freq);
+        let score: Score = self.terms.iter().map(|term| term.score(tfn)).sum();
+        self.boost * score
+    }
+
+    // As `A < B`, the score of a term grows with `tfn` and converges to `B`.
+    fn max_score(&self) -> Score {
+        let max_score: Score = self.terms.iter().map(|term| term.b).sum();
+        self.boost * max_score
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let score = self.score(fieldnorm_id, term_freq);
+        let tfn = self.normalized_term_freq(fieldnorm_id, term_freq);
+        let mut explanation = Explanation::new("DFR GL2, sum of...", score);
+        if self.boost != 1.0 {
+            explanation.add_const("boost", self.boost);
+        }
+        for term in &self.terms {
+            let mut term_explanation =
+                Explanation::new("(A + tfn * B) / (1 + tfn)", term.score(tfn));
+            let mut tfn_explanation =
+                Explanation::new("tfn, computed as freq * log2(1 + c * avgdl / dl)", tfn);
+            tfn_explanation.add_const(
+                "freq, occurrences of term within document",
+                term_freq as Score,
+            );
+            tfn_explanation.add_const("c, normalization parameter", self.c);
+            tfn_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
+            tfn_explanation.add_const("dl, length of field", field_length(fieldnorm_id));
+            term_explanation.add_detail(tfn_explanation);
+            term_explanation.add_const("A, log2(1 + λ)", term.a);
+            term_explanation.add_const("B, log2((1 + λ) / λ)", term.b);
+            explanation.add_detail(term_explanation);
+        }
+        explanation
+    }
+
+    fn boost_by(&self, boost: Score) -> Arc<dyn SimilarityWeight> {
+        Arc::new(DfrWeight {
+            boost: self.boost * boost,
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_nearly_equals;
+    use crate::fieldnorm::FieldNormReader;
+
+    #[test]
+    fn test_dfr_score() {
+        let statistics = SimilarityStatistics::new(7, 70, vec![0]);
+        let weight = DfrSimilarity::default().weight(&statistics);
+        // λ = 1 / 8, A = log2(9 / 8), B = log2(9)
+        // tfn = 1 * log2(1 + 10 / 10) = 1
+        let fieldnorm_id = FieldNormReader::fieldnorm_to_id(10);
+        let expected = ((9.0f32 / 8.0).log2() + 9.0f32.log2()) / 2.0;
+        assert_nearly_equals!(weight.score(fieldnorm_id, 1), expected);
+        assert_nearly_equals!(weight.max_score(), 9.0f32.log2());
+    }
+}
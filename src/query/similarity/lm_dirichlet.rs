@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use super::{Similarity, SimilarityStatistics, SimilarityWeight};
+use crate::fieldnorm::FieldNormReader;
+use crate::query::Explanation;
+use crate::Score;
+
+/// Language model [`Similarity`] with Bayesian smoothing using Dirichlet priors.
+///
+/// The score of a term is computed as
+/// `ln(1 + freq / (mu * p)) + ln(mu / (dl + mu))`, clamped at 0,
+/// where `p` is the probability of the term in the collection.
+///
+/// tantivy does not record the total number of occurrences of a term in the
+/// collection, so `p` is estimated as `(n + 1) / (T + 1)` where `n` is the
+/// number of documents containing the term and `T` the number of tokens in the field.
+///
+/// For a phrase, the scores of its terms are summed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LmDirichletSimilarity {
+    mu: Score,
+}
+
+impl Default for LmDirichletSimilarity {
+    fn default() -> Self {
+        LmDirichletSimilarity { mu: 2000.0 }
+    }
+}
+
+impl LmDirichletSimilarity {
+    /// Creates a LM-Dirichlet similarity with the given smoothing parameter `mu`
+    /// (default `2000`).
+    pub fn new(mu: Score) -> LmDirichletSimilarity {
+        assert!(mu > 0.0, "mu must be strictly positive, got {mu}");
+        LmDirichletSimilarity { mu }
+    }
+}
+
+impl Similarity for LmDirichletSimilarity {
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight> {
+        let total_num_tokens = statistics.total_num_tokens() as Score;
+        let collection_probabilities = statistics
+            .doc_freqs()
+            .iter()
+            .map(|&doc_freq| (doc_freq as Score + 1.0) / (total_num_tokens + 1.0))
+            .collect();
+        Arc::new(LmDirichletWeight {
+            mu: self.mu,
+            collection_probabilities,
+            boost: 1.0,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct LmDirichletWeight {
+    mu: Score,
+    collection_probabilities: Vec<Score>,
+    boost: Score,
+}
+
+impl LmDirichletWeight {
+    fn term_score(&self, collection_probability: Score, fieldnorm: u32, term_freq: u32) -> Score {
+        let score = (1.0 + term_freq as Score / (self.mu * collection_probability)).ln()
+            + (self.mu / (fieldnorm as Score + self.mu)).ln();
+        score.max(0.0)
+    }
+}
+
+impl SimilarityWeight for LmDirichletWeight {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id);
+        let score: Score = self
+            .collection_probabilities
+            .iter()
+            .map(|&collection_probability| {
+                self.term_score(collection_probability, fieldnorm, term_freq)
+            })
+            .sum();
+        self.boost * score
+    }
+
+    // The score grows with the term frequency and decreases with the field length.
+    fn max_score(&self) -> Score {
+        self.score(0u8, u32::MAX)
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let score = self.score(fieldnorm_id, term_freq);
+        let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id);
+        let mut explanation = Explanation::new("LM-Dirichlet, sum of...", score);
+        if self.boost != 1.0 {
+            explanation.add_const("boost", self.boost);
+        }
+        for &collection_probability in &self.collection_probabilities {
+            let mut term_explanation = Explanation::new(
+                "max(0, ln(1 + freq / (mu * p)) + ln(mu / (dl + mu)))",
+                self.term_score(collection_probability, fieldnorm, term_freq),
+            );
+            term_explanation.add_const(
+                "freq, occurrences of term within document",
+                term_freq as Score,
+            );
+            term_explanation.add_const("mu, smoothing parameter", self.mu);
+            term_explanation.add_const(
+                "p, probability of term in collection",
+                collection_probability,
+            );
+            term_explanation.add_const("dl, length of field", fieldnorm as Score);
+            explanation.add_detail(term_explanation);
+        }
+        explanation
+    }
+
+    fn boost_by(&self, boost: Score) -> Arc<dyn SimilarityWeight> {
+        Arc::new(LmDirichletWeight {
+            boost: self.boost * boost,
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_nearly_equals;
+
+    #[test]
+    fn test_lm_dirichlet_score() {
+        let statistics = SimilarityStatistics::new(10, 99, vec![4]);
+        let weight = LmDirichletSimilarity::new(100.0).weight(&statistics);
+        let fieldnorm_id = FieldNormReader::fieldnorm_to_id(10);
+        let expected = (1.0f32 + 2.0 / (100.0 * 0.05)).ln() + (100.0f32 / 110.0).ln();
+        assert_nearly_equals!(weight.score(fieldnorm_id, 2), expected);
+        // Rare occurrences in long documents are clamped to 0.
+        let long_fieldnorm_id = FieldNormReader::fieldnorm_to_id(10_000);
+        assert_eq!(weight.score(long_fieldnorm_id, 1), 0.0);
+    }
+}
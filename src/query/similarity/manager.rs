@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::{DfrSimilarity, LmDirichletSimilarity, Similarity, TfIdfSimilarity};
+use crate::query::{Bm25PlusSimilarity, Bm25Similarity};
+
+/// The similarity manager serves as a store for
+/// all of the similarities that can be referenced by the schema.
+///
+/// By default, it is populated with the following similarities.
+///
+///  * `bm25` : BM25 with `k1 = 1.2` and `b = 0.75`. This is the similarity used by default.
+///  * `bm25plus` : BM25+ with `k1 = 1.2`, `b = 0.75` and `delta = 1.0`.
+///  * `tfidf` : classic TF-IDF.
+///  * `lm_dirichlet` : language model with Dirichlet smoothing, with `mu = 2000`.
+///  * `dfr` : divergence from randomness `GL2` model, with `c = 1.0`.
+///
+/// Registering a similarity under an existing name replaces it. This makes it possible
+/// to tune the parameters of a built-in similarity without changing the schema.
+#[derive(Clone)]
+pub struct SimilarityManager {
+    similarities: Arc<RwLock<HashMap<String, Arc<dyn Similarity>>>>,
+}
+
+impl SimilarityManager {
+    /// Creates an empty similarity manager.
+    pub fn new() -> Self {
+        Self {
+            similarities: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a new similarity associated with a given name.
+    pub fn register<S: Similarity>(&self, similarity_name: &str, similarity: S) {
+        self.similarities
+            .write()
+            .expect("Acquiring the lock should never fail")
+            .insert(similarity_name.to_string(), Arc::new(similarity));
+    }
+
+    /// Accessing a similarity given its name.
+    pub fn get(&self, similarity_name: &str) -> Option<Arc<dyn Similarity>> {
+        self.similarities
+            .read()
+            .expect("Acquiring the lock should never fail")
+            .get(similarity_name)
+            .cloned()
+    }
+}
+
+impl Default for SimilarityManager {
+    /// Creates a `SimilarityManager` prepopulated with
+    /// the built-in similarities of `tantivy`.
+    fn default() -> SimilarityManager {
+        let manager = SimilarityManager::new();
+        manager.register("bm25", Bm25Similarity::default());
+        manager.register("bm25plus", Bm25PlusSimilarity::default());
+        manager.register("tfidf", TfIdfSimilarity);
+        manager.register("lm_dirichlet", LmDirichletSimilarity::default());
+        manager.register("dfr", DfrSimilarity::default());
+        manager
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::TopDocs;
+    use crate::query::{Bm25Similarity, QueryParser, SimilarityManager};
+    use crate::schema::{Schema, TextFieldIndexing, TextOptions, TEXT};
+    use crate::{Index, Searcher};
+
+    fn top_score(searcher: &Searcher, index: &Index, query: &str) -> crate::Result<f32> {
+        let query_parser = QueryParser::for_index(index, Vec::new());
+        let query = query_parser.parse_query(query)?;
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+        Ok(top_docs[0].0)
+    }
+
+    #[test]
+    fn test_similarity_for_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let bm25_text = schema_builder.add_text_field("bm25_text", TEXT);
+        let tfidf_text = schema_builder.add_text_field(
+            "tfidf_text",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_index_option(crate::schema::IndexRecordOption::WithFreqsAndPositions)
+                    .set_similarity("tfidf"),
+            ),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(bm25_text => "a b c", tfidf_text => "a b c"))?;
+        index_writer.add_document(doc!(bm25_text => "d", tfidf_text => "d"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(
+            format!("{:?}", index.similarity_for_field(tfidf_text)?),
+            "TfIdfSimilarity"
+        );
+        let bm25_score = top_score(&searcher, &index, "bm25_text:a")?;
+        let tfidf_score = top_score(&searcher, &index, "tfidf_text:a")?;
+        assert_ne!(bm25_score, tfidf_score);
+        // idf = 1 + ln(3 / 2), dl = 3
+        let idf = 1.0 + (3.0f32 / 2.0).ln();
+        crate::assert_nearly_equals!(tfidf_score, idf * idf / 3.0f32.sqrt());
+        // The phrase query goes through the similarity too.
+        let phrase_score = top_score(&searcher, &index, "tfidf_text:\"a b\"")?;
+        crate::assert_nearly_equals!(phrase_score, 4.0 * idf * idf / 3.0f32.sqrt());
+
+        // Registering a similarity under the default name retunes BM25.
+        index
+            .similarities()
+            .register("bm25", Bm25Similarity::new(2.0, 0.0));
+        let tuned_bm25_score = top_score(&searcher, &index, "bm25_text:a")?;
+        // With `k1 = 2` and `b = 0`, a single occurrence scores `idf * 3 * 1 / (1 + 2)`.
+        assert_ne!(tuned_bm25_score, bm25_score);
+        crate::assert_nearly_equals!(tuned_bm25_score, crate::query::bm25::idf(1, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_similarity() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field(
+            "text",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_similarity("unknown")),
+        );
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .similarities(SimilarityManager::new())
+            .create_in_ram()?;
+        assert!(index.similarity_for_field(text).is_err());
+        let searcher = index.reader()?.searcher();
+        assert!(top_score(&searcher, &index, "text:a").is_err());
+        Ok(())
+    }
+}
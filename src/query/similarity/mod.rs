@@ -0,0 +1,269 @@
+mod dfr;
+mod lm_dirichlet;
+mod manager;
+mod similarity_query;
+mod tfidf;
+
+use std::fmt;
+use std::sync::Arc;
+
+pub use self::dfr::DfrSimilarity;
+pub use self::lm_dirichlet::LmDirichletSimilarity;
+pub use self::manager::SimilarityManager;
+pub use self::similarity_query::SimilarityQuery;
+pub use self::tfidf::TfIdfSimilarity;
+use crate::query::{Bm25StatisticsProvider, Bm25Weight, Explanation};
+use crate::{Score, Term};
+
+/// Statistics about a group of terms belonging to the same field,
+/// as required to build a [`SimilarityWeight`].
+///
+/// The group is either a single term or the terms of a phrase.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimilarityStatistics {
+    total_num_docs: u64,
+    total_num_tokens: u64,
+    doc_freqs: Vec<u64>,
+}
+
+impl SimilarityStatistics {
+    /// Creates a new `SimilarityStatistics`.
+    ///
+    /// - `total_num_docs`: number of documents in the collection.
+    /// - `total_num_tokens`: number of tokens in the field across the collection.
+    /// - `doc_freqs`: number of documents containing each of the terms.
+    pub fn new(
+        total_num_docs: u64,
+        total_num_tokens: u64,
+        doc_freqs: Vec<u64>,
+    ) -> SimilarityStatistics {
+        assert!(
+            !doc_freqs.is_empty(),
+            "Similarity statistics require at least one term"
+        );
+        SimilarityStatistics {
+            total_num_docs,
+            total_num_tokens,
+            doc_freqs,
+        }
+    }
+
+    /// Gathers the statistics of the given terms from a [`Bm25StatisticsProvider`].
+    ///
+    /// If the provider exposes multi parts statistics, the collection-wide figures
+    /// are taken from there.
+    pub fn for_terms(
+        statistics: &dyn Bm25StatisticsProvider,
+        terms: &[Term],
+    ) -> crate::Result<SimilarityStatistics> {
+        assert!(!terms.is_empty(), "Similarity requires at least one term");
+        let field = terms[0].field();
+        for term in &terms[1..] {
+            assert_eq!(
+                term.field(),
+                field,
+                "All terms must belong to the same field."
+            );
+        }
+        let (total_num_tokens, total_num_docs) =
+            if let Some(multi_parts_statistics) = statistics.get_multi_parts_statistics() {
+                (
+                    multi_parts_statistics.total_num_tokens(&field)?,
+                    multi_parts_statistics.total_num_docs(),
+                )
+            } else {
+                (
+                    statistics.total_num_tokens(field)?,
+                    statistics.total_num_docs()?,
+                )
+            };
+        let doc_freqs = terms
+            .iter()
+            .map(|term| statistics.doc_freq(term))
+            .collect::<crate::Result<Vec<u64>>>()?;
+        Ok(SimilarityStatistics::new(
+            total_num_docs,
+            total_num_tokens,
+            doc_freqs,
+        ))
+    }
+
+    /// Returns the number of documents in the collection.
+    pub fn total_num_docs(&self) -> u64 {
+        self.total_num_docs
+    }
+
+    /// Returns the number of tokens in the field across the collection.
+    pub fn total_num_tokens(&self) -> u64 {
+        self.total_num_tokens
+    }
+
+    /// Returns the document frequency of each of the terms.
+    pub fn doc_freqs(&self) -> &[u64] {
+        &self.doc_freqs
+    }
+
+    /// Returns the average number of tokens in the field.
+    pub fn average_fieldnorm(&self) -> Score {
+        self.total_num_tokens as Score / self.total_num_docs as Score
+    }
+}
+
+/// A `Similarity` defines how documents matching a group of terms get scored.
+///
+/// A `Similarity` is a recipe: given the statistics of a group of terms, it produces a
+/// [`SimilarityWeight`] that computes the score of a document from its field length
+/// (its fieldnorm) and the number of occurrences of the terms in it.
+///
+/// The similarity used for a field is configured in the schema through
+/// [`TextFieldIndexing::set_similarity`](crate::schema::TextFieldIndexing::set_similarity),
+/// and resolved by name in the [`SimilarityManager`] of the [`Index`](crate::Index).
+/// It can also be overridden for a given query using a [`SimilarityQuery`].
+pub trait Similarity: fmt::Debug + Send + Sync + 'static {
+    /// Builds the [`SimilarityWeight`] used to score the documents matching
+    /// the terms described by `statistics`.
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight>;
+}
+
+/// Computes the score of a document for a given group of terms.
+///
+/// Scores depend only on the fieldnorm id of the document and on the
+/// number of occurrences of the terms within the field.
+pub trait SimilarityWeight: Send + Sync + 'static {
+    /// Computes the score of a document.
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score;
+
+    /// Returns an upper bound of the score of any document.
+    ///
+    /// This bound is used by block-WAND to skip documents that cannot make it
+    /// to the top K. A lower value than the actual maximum would make search
+    /// return wrong results.
+    ///
+    /// The bound may rely on the term frequency being at most the number of tokens
+    /// of the field.
+    fn max_score(&self) -> Score;
+
+    /// Returns an upper bound of the score of any document of a field without fieldnorms.
+    ///
+    /// All of the documents of such a field get the fieldnorm id of a field of length 1,
+    /// whatever their term frequency. Defaults to [`SimilarityWeight::max_score`].
+    fn max_score_without_fieldnorms(&self) -> Score {
+        self.max_score()
+    }
+
+    /// Returns an upper bound of the score of the documents of a block of postings.
+    ///
+    /// The `(fieldnorm_id, term_freq)` pair is the one recorded at indexing time
+    /// in the skip information of the block, chosen as the one maximizing the
+    /// default BM25 score. As this pair is not necessarily the one maximizing
+    /// other similarities, the default implementation ignores it and returns
+    /// [`SimilarityWeight::max_score`].
+    fn block_max_score(&self, _fieldnorm_id: u8, _term_freq: u32) -> Score {
+        self.max_score()
+    }
+
+    /// Produces an [`Explanation`] of the score of a document.
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation;
+
+    /// Returns a copy of this weight, with scores multiplied by `boost`.
+    fn boost_by(&self, boost: Score) -> Arc<dyn SimilarityWeight>;
+
+    /// Returns this weight as a [`Bm25Weight`], if it is one.
+    ///
+    /// Term scorers use it to score documents with BM25, the default similarity, without a
+    /// virtual call per document.
+    fn as_bm25(&self) -> Option<&Bm25Weight> {
+        None
+    }
+}
+
+/// Returns the field length associated with a fieldnorm id.
+///
+/// Empty fields have a fieldnorm of 0. The length is clamped to 1 so that
+/// it can safely be used as a divisor.
+pub(crate) fn field_length(fieldnorm_id: u8) -> Score {
+    crate::fieldnorm::FieldNormReader::id_to_fieldnorm(fieldnorm_id).max(1) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::{Bm25PlusSimilarity, Bm25Similarity};
+
+    fn statistics() -> SimilarityStatistics {
+        SimilarityStatistics::new(1_000, 20_000, vec![10])
+    }
+
+    fn check_max_score(similarity: &dyn Similarity) {
+        let weight = similarity.weight(&statistics());
+        let max_score = weight.max_score();
+        for fieldnorm_id in 0..=255u8 {
+            // A term cannot appear more often than the field length.
+            let fieldnorm = crate::fieldnorm::FieldNormReader::id_to_fieldnorm(fieldnorm_id);
+            for term_freq in [1u32, 2, 5, 10, 100, 10_000, 1 << 30] {
+                if term_freq > fieldnorm.max(1) {
+                    break;
+                }
+                let score = weight.score(fieldnorm_id, term_freq);
+                assert!(score >= 0.0, "{similarity:?}: negative score {score}");
+                assert!(
+                    score <= max_score * (1.0 + 1e-6),
+                    "{similarity:?}: score {score} exceeds max score {max_score}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_similarities_max_score_is_an_upper_bound() {
+        check_max_score(&Bm25Similarity::default());
+        check_max_score(&Bm25Similarity::new(2.0, 0.3));
+        check_max_score(&Bm25PlusSimilarity::default());
+        check_max_score(&TfIdfSimilarity);
+        check_max_score(&LmDirichletSimilarity::default());
+        check_max_score(&DfrSimilarity::default());
+    }
+
+    fn check_monotonicity(similarity: &dyn Similarity) {
+        let weight = similarity.weight(&statistics());
+        assert!(weight.score(20, 3) > weight.score(20, 1), "{similarity:?}");
+        assert!(weight.score(10, 3) > weight.score(60, 3), "{similarity:?}");
+    }
+
+    #[test]
+    fn test_similarities_monotonicity() {
+        check_monotonicity(&Bm25Similarity::default());
+        check_monotonicity(&Bm25PlusSimilarity::default());
+        check_monotonicity(&TfIdfSimilarity);
+        check_monotonicity(&LmDirichletSimilarity::default());
+        check_monotonicity(&DfrSimilarity::default());
+    }
+
+    fn check_boost_and_explain(similarity: &dyn Similarity) {
+        let weight = similarity.weight(&statistics());
+        let boosted_weight = weight.boost_by(2.0);
+        crate::assert_nearly_equals!(boosted_weight.score(12, 3), 2.0 * weight.score(12, 3));
+        crate::assert_nearly_equals!(boosted_weight.max_score(), 2.0 * weight.max_score());
+        crate::assert_nearly_equals!(
+            boosted_weight.explain(12, 3).value(),
+            boosted_weight.score(12, 3)
+        );
+    }
+
+    #[test]
+    fn test_similarities_boost_and_explain() {
+        check_boost_and_explain(&Bm25Similarity::default());
+        check_boost_and_explain(&Bm25PlusSimilarity::default());
+        check_boost_and_explain(&TfIdfSimilarity);
+        check_boost_and_explain(&LmDirichletSimilarity::default());
+        check_boost_and_explain(&DfrSimilarity::default());
+    }
+
+    #[test]
+    fn test_bm25_plus_delta() {
+        let bm25 = Bm25Similarity::default().weight(&statistics());
+        let bm25_plus = Bm25PlusSimilarity::new(1.2, 0.75, 1.0).weight(&statistics());
+        let idf = crate::query::bm25::idf(10, 1_000);
+        crate::assert_nearly_equals!(bm25_plus.score(12, 3), bm25.score(12, 3) + idf);
+    }
+}
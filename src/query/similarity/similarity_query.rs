@@ -0,0 +1,193 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::Similarity;
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::Term;
+
+/// `SimilarityQuery` is a wrapper over a query used to score it with a given [`Similarity`].
+///
+/// The document set matched by the `SimilarityQuery` is strictly the same as the underlying
+/// query. The terms and phrases of the underlying query are scored using `similarity`,
+/// regardless of the similarities configured in the schema.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{QueryParser, SimilarityQuery, TfIdfSimilarity};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer = index.writer(15_000_000)?;
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+///     index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+///     index_writer.commit()?;
+/// }
+/// let searcher = index.reader()?.searcher();
+/// let query_parser = QueryParser::for_index(&index, vec![title]);
+/// let query = SimilarityQuery::new(query_parser.parse_query("diary girl")?, TfIdfSimilarity);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+/// assert_eq!(top_docs.len(), 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+pub struct SimilarityQuery {
+    query: Box<dyn Query>,
+    similarity: Arc<dyn Similarity>,
+}
+
+impl SimilarityQuery {
+    /// Builds a similarity query.
+    pub fn new<S: Similarity>(query: Box<dyn Query>, similarity: S) -> SimilarityQuery {
+        SimilarityQuery {
+            query,
+            similarity: Arc::new(similarity),
+        }
+    }
+}
+
+impl Clone for SimilarityQuery {
+    fn clone(&self) -> Self {
+        SimilarityQuery {
+            query: self.query.box_clone(),
+            similarity: self.similarity.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for SimilarityQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Similarity(query={:?}, similarity={:?})",
+            self.query, self.similarity
+        )
+    }
+}
+
+impl Query for SimilarityQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        self.query
+            .weight(enable_scoring.with_similarity(self.similarity.as_ref()))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.query.match_patterns(visitor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimilarityQuery;
+    use crate::collector::TopDocs;
+    use crate::query::{Query, TermQuery, TfIdfSimilarity};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{assert_nearly_equals, DocAddress, Index, Term};
+
+    #[test]
+    fn test_similarity_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a b"))?;
+        index_writer.add_document(doc!(text => "a a a a"))?;
+        index_writer.add_document(doc!(text => "c"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let query = SimilarityQuery::new(Box::new(term_query), TfIdfSimilarity);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(3))?;
+        assert_eq!(top_docs.len(), 2);
+        // idf = 1 + ln(4 / 3)
+        let idf = 1.0 + (4.0f32 / 3.0).ln();
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 1));
+        assert_nearly_equals!(top_docs[0].0, idf * idf * 2.0 / 2.0);
+        assert_eq!(top_docs[1].1, DocAddress::new(0, 0));
+        assert_nearly_equals!(top_docs[1].0, idf * idf / 2.0f32.sqrt());
+        let explanation = query.explain(&searcher, DocAddress::new(0, 1))?;
+        assert_nearly_equals!(explanation.value(), top_docs[0].0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_query_block_wand() -> crate::Result<()> {
+        use rand::prelude::SliceRandom;
+        use rand::{Rng, SeedableRng};
+
+        use crate::query::{
+            Bm25PlusSimilarity, Bm25Similarity, DfrSimilarity, EnableScoring,
+            LmDirichletSimilarity, QueryParser,
+        };
+        use crate::{DocId, Score};
+
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let words = ["a", "b", "c", "d", "e"];
+        for _ in 0..1_000 {
+            let num_words = rng.gen_range(1..30);
+            let doc_text: Vec<&str> = (0..num_words)
+                .map(|_| *words.choose(&mut rng).unwrap())
+                .collect();
+            index_writer.add_document(doc!(text => doc_text.join(" ")))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text]);
+        let queries = vec![
+            SimilarityQuery::new(query_parser.parse_query("a b")?, TfIdfSimilarity),
+            SimilarityQuery::new(
+                query_parser.parse_query("a b")?,
+                Bm25PlusSimilarity::default(),
+            ),
+            SimilarityQuery::new(
+                query_parser.parse_query("a b")?,
+                LmDirichletSimilarity::default(),
+            ),
+            SimilarityQuery::new(query_parser.parse_query("a b")?, DfrSimilarity::default()),
+            // The block-max information stored in the index assumes the default BM25
+            // parameters, and can't be used without length normalization.
+            SimilarityQuery::new(
+                query_parser.parse_query("a b")?,
+                Bm25Similarity::new(1.2, 0.0),
+            ),
+            SimilarityQuery::new(
+                query_parser.parse_query("a b")?,
+                Bm25PlusSimilarity::new(2.0, 0.0, 1.0),
+            ),
+        ];
+        for query in queries {
+            // `TopDocs` relies on block-WAND.
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+            let mut all_scores: Vec<(Score, DocId)> = Vec::new();
+            weight.for_each(searcher.segment_reader(0), &mut |doc, score| {
+                all_scores.push((score, doc))
+            })?;
+            all_scores.sort_by(|left, right| right.0.partial_cmp(&left.0).unwrap());
+            let expected_scores: Vec<Score> = all_scores
+                .iter()
+                .take(10)
+                .map(|(score, _)| *score)
+                .collect();
+            let scores: Vec<Score> = top_docs.iter().map(|(score, _)| *score).collect();
+            assert_eq!(scores, expected_scores, "{query:?}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+
+use super::{field_length, Similarity, SimilarityStatistics, SimilarityWeight};
+use crate::fieldnorm::FieldNormReader;
+use crate::query::Explanation;
+use crate::Score;
+
+/// The classic TF-IDF [`Similarity`], as defined by Lucene's `ClassicSimilarity`.
+///
+/// The score is computed as `idf² * sqrt(freq) / sqrt(dl)` with
+/// `idf = 1 + ln((N + 1) / (n + 1))`.
+///
+/// For a phrase, the idf is the sum of the idf of its terms.
+///
+/// # Pruning
+///
+/// The score grows with the term frequency without saturating, but a term can't occur more
+/// often than there are tokens in the field: as fieldnorms round the field length down,
+/// `sqrt(freq) / sqrt(dl)` is at most about 1.5. This bounds the score for the block-WAND
+/// pruning of top-K queries. The index only records, for
+/// each block of postings, the document maximizing BM25, so the same bound is used for every
+/// block. Fields indexed without fieldnorms are not pruned.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TfIdfSimilarity;
+
+/// Highest ratio between a term frequency and the field length of its fieldnorm id.
+///
+/// The term frequency is at most the number of tokens of the field, but fieldnorms round the
+/// number of tokens down.
+static MAX_TERM_FREQ_TO_FIELD_LENGTH: Lazy<Score> = Lazy::new(|| {
+    (0..=u8::MAX)
+        .map(|fieldnorm_id| {
+            let max_num_tokens = if fieldnorm_id == u8::MAX {
+                u32::MAX
+            } else {
+                FieldNormReader::id_to_fieldnorm(fieldnorm_id + 1) - 1
+            };
+            max_num_tokens as Score / field_length(fieldnorm_id)
+        })
+        .fold(0.0, Score::max)
+});
+
+fn idf(doc_freq: u64, total_num_docs: u64) -> Score {
+    1.0 + ((total_num_docs as Score + 1.0) / (doc_freq as Score + 1.0)).ln()
+}
+
+impl Similarity for TfIdfSimilarity {
+    fn weight(&self, statistics: &SimilarityStatistics) -> Arc<dyn SimilarityWeight> {
+        let total_num_docs = statistics.total_num_docs();
+        let idf_explain = if let [doc_freq] = statistics.doc_freqs() {
+            let mut idf_explain = Explanation::new(
+                "idf, computed as 1 + ln((N + 1) / (n + 1))",
+                idf(*doc_freq, total_num_docs),
+            );
+            idf_explain.add_const("n, number of docs containing this term", *doc_freq as Score);
+            idf_explain.add_const("N, total number of docs", total_num_docs as Score);
+            idf_explain
+        } else {
+            let idf_sum = statistics
+                .doc_freqs()
+                .iter()
+                .map(|&doc_freq| idf(doc_freq, total_num_docs))
+                .sum();
+            Explanation::new("idf", idf_sum)
+        };
+        Arc::new(TfIdfWeight {
+            idf_explain,
+            boost: 1.0,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct TfIdfWeight {
+    idf_explain: Explanation,
+    boost: Score,
+}
+
+impl SimilarityWeight for TfIdfWeight {
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        let idf = self.idf_explain.value();
+        self.boost * idf * idf * (term_freq as Score).sqrt() / field_length(fieldnorm_id).sqrt()
+    }
+
+    fn max_score(&self) -> Score {
+        let idf = self.idf_explain.value();
+        self.boost * idf * idf * MAX_TERM_FREQ_TO_FIELD_LENGTH.sqrt()
+    }
+
+    // All the documents have a field length of 1, the term frequency is not bounded.
+    fn max_score_without_fieldnorms(&self) -> Score {
+        self.score(0u8, u32::MAX)
+    }
+
+    fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        let score = self.score(fieldnorm_id, term_freq);
+        let mut explanation = Explanation::new("TF-IDF, product of...", score);
+        if self.boost != 1.0 {
+            explanation.add_const("boost", self.boost);
+        }
+        explanation.add_detail(self.idf_explain.clone());
+        explanation.add_detail(self.idf_explain.clone());
+        let mut tf_explanation =
+            Explanation::new("tf, computed as sqrt(freq)", (term_freq as Score).sqrt());
+        tf_explanation.add_const(
+            "freq, occurrences of term within document",
+            term_freq as Score,
+        );
+        explanation.add_detail(tf_explanation);
+        let field_length = field_length(fieldnorm_id);
+        let mut norm_explanation =
+            Explanation::new("norm, computed as 1 / sqrt(dl)", 1.0 / field_length.sqrt());
+        norm_explanation.add_const("dl, length of field", field_length);
+        explanation.add_detail(norm_explanation);
+        explanation
+    }
+
+    fn boost_by(&self, boost: Score) -> Arc<dyn SimilarityWeight> {
+        Arc::new(TfIdfWeight {
+            idf_explain: self.idf_explain.clone(),
+            boost: self.boost * boost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_nearly_equals;
+    use crate::fieldnorm::FieldNormReader;
+
+    #[test]
+    fn test_tfidf_score() {
+        let statistics = SimilarityStatistics::new(9, 100, vec![2]);
+        let weight = TfIdfSimilarity.weight(&statistics);
+        let fieldnorm_id = FieldNormReader::fieldnorm_to_id(4);
+        let idf = 1.0 + (10.0f32 / 3.0).ln();
+        assert_nearly_equals!(weight.score(fieldnorm_id, 9), idf * idf * 3.0 / 2.0);
+    }
+
+    #[test]
+    fn test_tfidf_max_score() {
+        let statistics = SimilarityStatistics::new(9, 100, vec![2]);
+        let weight = TfIdfSimilarity.weight(&statistics);
+        let idf = 1.0 + (10.0f32 / 3.0).ln();
+        assert!(weight.max_score() < idf * idf * 1.5);
+        assert!(weight.max_score() < weight.max_score_without_fieldnorms());
+        // The last fieldnorm id covers the longest fields.
+        assert!(weight.max_score() * (1.0 + 1e-6) >= weight.score(u8::MAX, u32::MAX));
+    }
+
+    #[test]
+    fn test_tfidf_block_wand() -> crate::Result<()> {
+        use rand::prelude::SliceRandom;
+        use rand::{Rng, SeedableRng};
+
+        use crate::collector::TopDocs;
+        use crate::query::{EnableScoring, Query, QueryParser, SimilarityQuery};
+        use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, TEXT};
+        use crate::{DocId, Index};
+
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let text_without_fieldnorms = schema_builder.add_text_field(
+            "text_without_fieldnorms",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_fieldnorms(false)
+                    .set_index_option(IndexRecordOption::WithFreqs),
+            ),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let words = ["a", "b", "c"];
+        for _ in 0..2_000 {
+            // A few long documents repeat the same term.
+            let num_words = if rng.gen_bool(0.01) {
+                300
+            } else {
+                rng.gen_range(1..20)
+            };
+            let doc_text: Vec<&str> = (0..num_words)
+                .map(|_| *words.choose(&mut rng).unwrap())
+                .collect();
+            let doc_text = doc_text.join(" ");
+            index_writer.add_document(doc!(
+                text => doc_text.clone(),
+                text_without_fieldnorms => doc_text,
+            ))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        for field in [text, text_without_fieldnorms] {
+            let query_parser = QueryParser::for_index(&index, vec![field]);
+            let query = SimilarityQuery::new(query_parser.parse_query("a b")?, TfIdfSimilarity);
+            // `TopDocs` prunes documents with block-WAND.
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+            let mut all_scores: Vec<(Score, DocId)> = Vec::new();
+            weight.for_each(searcher.segment_reader(0), &mut |doc, score| {
+                all_scores.push((score, doc))
+            })?;
+            all_scores.sort_by(|left, right| right.0.partial_cmp(&left.0).unwrap());
+            let expected_scores: Vec<Score> = all_scores
+                .iter()
+                .take(10)
+                .map(|(score, _)| *score)
+                .collect();
+            let scores: Vec<Score> = top_docs.iter().map(|(score, _)| *score).collect();
+            assert_eq!(scores, expected_scores);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+mod span_containing_query;
+mod span_first_query;
+mod span_near_query;
+mod span_not_query;
+mod span_or_query;
+#[allow(clippy::module_inception)]
+mod span_query;
+mod span_term_query;
+mod span_weight;
+mod spans;
+
+pub use self::span_containing_query::SpanContainingQuery;
+pub use self::span_first_query::SpanFirstQuery;
+pub use self::span_near_query::SpanNearQuery;
+pub use self::span_not_query::SpanNotQuery;
+pub use self::span_or_query::SpanOrQuery;
+pub use self::span_query::SpanQuery;
+pub use self::span_term_query::SpanTermQuery;
+pub(crate) use self::span_weight::SpanWeight;
+pub use self::spans::{Span, Spans};
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::collector::{Count, TopDocs};
+    use crate::core::Index;
+    use crate::query::{EnableScoring, Query};
+    use crate::schema::{Schema, Term, STRING, TEXT};
+    use crate::{assert_nearly_equals, DocAddress};
+
+    pub fn create_index(texts: &[&'static str]) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            for &text in texts {
+                index_writer.add_document(doc!(text_field=>text))?;
+            }
+            index_writer.commit()?;
+        }
+        Ok(index)
+    }
+
+    pub fn span_term(index: &Index, text: &str) -> Box<dyn SpanQuery> {
+        let text_field = index.schema().get_field("text").unwrap();
+        Box::new(SpanTermQuery::new(Term::from_field_text(text_field, text)))
+    }
+
+    #[test]
+    fn test_span_queries_combined() -> crate::Result<()> {
+        let index = create_index(&[
+            "a x x b y",
+            "a x x c d",
+            "c a",
+            "a x x x x x x b",
+            "b a d",
+            "d d x a c",
+        ])?;
+        // "a" within 2 positions of "b" or "c", not followed by "d", in the first 4 positions.
+        let b_or_c = SpanOrQuery::new(vec![span_term(&index, "b"), span_term(&index, "c")]);
+        let near = SpanNearQuery::new(vec![span_term(&index, "a"), Box::new(b_or_c)], 2, false);
+        let not_followed_by_d =
+            SpanNotQuery::new_with_distances(Box::new(near), span_term(&index, "d"), 0, 1);
+        let query = SpanFirstQuery::new(Box::new(not_followed_by_d), 4);
+        let searcher = index.reader()?.searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let mut docs: Vec<DocAddress> = top_docs.into_iter().map(|(_, doc)| doc).collect();
+        docs.sort();
+        assert_eq!(docs, vec![DocAddress::new(0, 0), DocAddress::new(0, 2)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_query_scoring_and_explain() -> crate::Result<()> {
+        let index = create_index(&["a b x x a b", "a b x x x x", "b a"])?;
+        let query = SpanNearQuery::new(
+            vec![span_term(&index, "a"), span_term(&index, "b")],
+            0,
+            true,
+        );
+        let searcher = index.reader()?.searcher();
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 2);
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 0));
+        assert!(top_docs[0].0 > top_docs[1].0);
+        let explanation = query.explain(&searcher, DocAddress::new(0, 0))?;
+        assert_nearly_equals!(explanation.value(), top_docs[0].0);
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+        let weight = query.weight(EnableScoring::disabled_from_searcher(&searcher))?;
+        let mut scorer = weight.scorer(searcher.segment_reader(0), 1.0)?;
+        assert_eq!(scorer.score(), 1.0);
+        assert_eq!(query.count(&searcher)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_query_requires_positions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = SpanTermQuery::new(Term::from_field_text(id, "a"));
+        let err = searcher.search(&query, &Count).unwrap_err();
+        assert!(matches!(err, crate::TantivyError::SchemaError(_)));
+        Ok(())
+    }
+}
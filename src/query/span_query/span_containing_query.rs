@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use super::spans::{CombinedSpans, SpanCombiner, SpansIntersection};
+use super::{Span, SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::SegmentReader;
+
+/// `SpanContainingQuery` matches the spans of a `big` query containing at least one span of
+/// a `little` query.
+///
+/// For instance, a `SpanNearQuery` of `a` and `b` with a slop of 5 containing `c` matches
+/// the passages where `a` and `b` are close to each other and `c` is between them.
+#[derive(Clone, Debug)]
+pub struct SpanContainingQuery {
+    big: Arc<dyn SpanQuery>,
+    little: Arc<dyn SpanQuery>,
+}
+
+impl SpanContainingQuery {
+    /// Creates a new `SpanContainingQuery`.
+    ///
+    /// Both queries must target the same field.
+    pub fn new(big: Box<dyn SpanQuery>, little: Box<dyn SpanQuery>) -> SpanContainingQuery {
+        assert!(
+            big.field() == little.field(),
+            "The clauses of a span containing query must target the same field"
+        );
+        SpanContainingQuery {
+            big: Arc::from(big),
+            little: Arc::from(little),
+        }
+    }
+}
+
+impl SpanQuery for SpanContainingQuery {
+    fn field(&self) -> Field {
+        self.big.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let big = self.big.spans(reader)?;
+        let little = self.little.spans(reader)?;
+        let (big, little) = if let Some(big_and_little) = big.zip(little) {
+            big_and_little
+        } else {
+            return Ok(None);
+        };
+        let combiner = ContainingCombiner {
+            big_spans: Vec::new(),
+            little_spans: Vec::new(),
+        };
+        let spans = CombinedSpans::new(SpansIntersection::new(vec![big, little]), combiner);
+        Ok(Some(Box::new(spans)))
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        self.big.term_sequences()
+    }
+}
+
+impl Query for SpanContainingQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.big.query_terms(visitor);
+        self.little.query_terms(visitor);
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.big.match_patterns(visitor);
+    }
+}
+
+struct ContainingCombiner {
+    big_spans: Vec<Span>,
+    little_spans: Vec<Span>,
+}
+
+impl SpanCombiner<SpansIntersection> for ContainingCombiner {
+    fn combine(&mut self, candidates: &mut SpansIntersection, output: &mut Vec<Span>) {
+        let (big, little) = candidates.children_mut().split_at_mut(1);
+        self.big_spans.clear();
+        big[0].spans(&mut self.big_spans);
+        self.little_spans.clear();
+        little[0].spans(&mut self.little_spans);
+        let little_spans = &self.little_spans;
+        output.extend(self.big_spans.iter().filter(|big_span| {
+            little_spans
+                .iter()
+                .any(|little_span| big_span.contains(little_span))
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanContainingQuery;
+    use crate::collector::Count;
+    use crate::query::span_query::tests::{create_index, span_term};
+    use crate::query::SpanNearQuery;
+
+    #[test]
+    fn test_span_containing_query() -> crate::Result<()> {
+        let index = create_index(&["a c b", "a b c", "c a b", "a x x c b", "a b"])?;
+        let searcher = index.reader()?.searcher();
+        let a_near_b = || {
+            Box::new(SpanNearQuery::new(
+                vec![span_term(&index, "a"), span_term(&index, "b")],
+                3,
+                true,
+            ))
+        };
+        let query = SpanContainingQuery::new(a_near_b(), span_term(&index, "c"));
+        assert_eq!(searcher.search(&query, &Count)?, 2);
+        let query = SpanContainingQuery::new(a_near_b(), span_term(&index, "z"));
+        assert_eq!(searcher.search(&query, &Count)?, 0);
+        Ok(())
+    }
+}
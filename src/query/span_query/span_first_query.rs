@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use super::spans::{CombinedSpans, SpanCombiner};
+use super::{Span, SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::SegmentReader;
+
+/// `SpanFirstQuery` matches the spans of a query ending within the first `end` positions
+/// of the field.
+#[derive(Clone, Debug)]
+pub struct SpanFirstQuery {
+    query: Arc<dyn SpanQuery>,
+    end: u32,
+}
+
+impl SpanFirstQuery {
+    /// Creates a new `SpanFirstQuery`.
+    pub fn new(query: Box<dyn SpanQuery>, end: u32) -> SpanFirstQuery {
+        SpanFirstQuery {
+            query: Arc::from(query),
+            end,
+        }
+    }
+
+    /// Maximum end position of the spans.
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+}
+
+impl SpanQuery for SpanFirstQuery {
+    fn field(&self) -> Field {
+        self.query.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let spans_opt = self.query.spans(reader)?;
+        Ok(spans_opt.map(|spans| {
+            let combiner = FirstCombiner {
+                end: self.end,
+                spans: Vec::new(),
+            };
+            Box::new(CombinedSpans::new(spans, combiner)) as Box<dyn Spans>
+        }))
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        self.query.term_sequences()
+    }
+}
+
+impl Query for SpanFirstQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.query.match_patterns(visitor);
+    }
+}
+
+struct FirstCombiner {
+    end: u32,
+    spans: Vec<Span>,
+}
+
+impl SpanCombiner<Box<dyn Spans>> for FirstCombiner {
+    fn combine(&mut self, candidates: &mut Box<dyn Spans>, output: &mut Vec<Span>) {
+        self.spans.clear();
+        candidates.spans(&mut self.spans);
+        let end = self.end;
+        output.extend(self.spans.iter().filter(|span| span.end <= end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanFirstQuery;
+    use crate::collector::Count;
+    use crate::query::span_query::tests::{create_index, span_term};
+    use crate::query::SpanNearQuery;
+
+    #[test]
+    fn test_span_first_query() -> crate::Result<()> {
+        let index = create_index(&["a b c", "b a c", "c c a", "c c c a b"])?;
+        let searcher = index.reader()?.searcher();
+        let query = SpanFirstQuery::new(span_term(&index, "a"), 1);
+        assert_eq!(searcher.search(&query, &Count)?, 1);
+        let query = SpanFirstQuery::new(span_term(&index, "a"), 3);
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        let a_b = SpanNearQuery::new(
+            vec![span_term(&index, "a"), span_term(&index, "b")],
+            0,
+            true,
+        );
+        let query = SpanFirstQuery::new(Box::new(a_b), 4);
+        assert_eq!(searcher.search(&query, &Count)?, 1);
+        Ok(())
+    }
+}
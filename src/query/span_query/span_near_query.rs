@@ -0,0 +1,395 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+use once_cell::sync::OnceCell;
+
+use super::spans::{CombinedSpans, SpanCombiner, SpansIntersection};
+use super::{Span, SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::SegmentReader;
+
+/// `SpanNearQuery` matches the spans of its clauses when they are close to each other.
+///
+/// The slop is the maximum number of positions between the spans of the clauses. With a slop
+/// of 0, the spans must be adjacent.
+///
+/// If the query is ordered, the spans of the clauses must appear in the order of the clauses
+/// and must not overlap: an ordered `SpanNearQuery` over terms with a slop of 0 matches the
+/// same documents as a [`PhraseQuery`](crate::query::PhraseQuery). If the query is unordered,
+/// the spans of the clauses can appear in any order.
+///
+/// Each match is a span going from the start of the first span of the clauses to the end of
+/// the last one.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::{SpanNearQuery, SpanOrQuery, SpanQuery, SpanTermQuery};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc!(body => "the patent covers a rotating blade assembly"))?;
+/// index_writer.add_document(doc!(body => "a blade that is rotating"))?;
+/// index_writer.add_document(doc!(body => "rotating parts and a very long and sharp blade"))?;
+/// index_writer.commit()?;
+///
+/// let span_term = |text: &str| -> Box<dyn SpanQuery> {
+///     Box::new(SpanTermQuery::new(Term::from_field_text(body, text)))
+/// };
+/// // "rotating" followed by "blade" or "knife", with at most 2 positions in between.
+/// let query = SpanNearQuery::new(
+///     vec![
+///         span_term("rotating"),
+///         Box::new(SpanOrQuery::new(vec![span_term("blade"), span_term("knife")])),
+///     ],
+///     2,
+///     true,
+/// );
+/// let searcher = index.reader()?.searcher();
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpanNearQuery {
+    field: Field,
+    clauses: Vec<Arc<dyn SpanQuery>>,
+    slop: u32,
+    in_order: bool,
+    /// Computed on the first call to `match_patterns`, as it is only needed for highlighting.
+    highlight_phrases: OnceCell<Vec<Vec<(usize, Term)>>>,
+}
+
+/// Maximum number of phrases a `SpanNearQuery` is highlighted with. Above it, the terms of the
+/// query are highlighted on their own.
+const MAX_HIGHLIGHT_PHRASES: usize = 64;
+
+/// Returns all the concatenations of one term sequence of each clause, or `None` if there are
+/// more than `MAX_HIGHLIGHT_PHRASES` of them.
+fn concatenate_term_sequences<'a>(
+    clause_term_sequences: impl Iterator<Item = &'a Vec<Vec<Term>>>,
+) -> Option<Vec<Vec<Term>>> {
+    let mut concatenations: Vec<Vec<Term>> = vec![Vec::new()];
+    for term_sequences in clause_term_sequences {
+        concatenations = concatenations
+            .iter()
+            .cartesian_product(term_sequences)
+            .map(|(prefix, term_sequence)| prefix.iter().chain(term_sequence).cloned().collect())
+            .collect();
+        if concatenations.len() > MAX_HIGHLIGHT_PHRASES {
+            return None;
+        }
+    }
+    Some(concatenations)
+}
+
+/// Returns the phrases matching the clauses in the order of the clauses, or in any order if the
+/// query is unordered. Returns an empty `Vec` if the clauses can't be highlighted as phrases.
+fn highlight_phrases(clauses: &[Arc<dyn SpanQuery>], in_order: bool) -> Vec<Vec<(usize, Term)>> {
+    let clause_term_sequences: Vec<Vec<Vec<Term>>> = if let Some(term_sequences) = clauses
+        .iter()
+        .map(|clause| clause.term_sequences())
+        .collect()
+    {
+        term_sequences
+    } else {
+        return Vec::new();
+    };
+    let clause_orders: Vec<Vec<usize>> = if in_order {
+        vec![(0..clauses.len()).collect()]
+    } else {
+        (0..clauses.len())
+            .permutations(clauses.len())
+            .take(MAX_HIGHLIGHT_PHRASES + 1)
+            .collect()
+    };
+    let mut phrases = Vec::new();
+    for clause_order in clause_orders {
+        let concatenations =
+            concatenate_term_sequences(clause_order.iter().map(|&ord| &clause_term_sequences[ord]));
+        match concatenations {
+            Some(concatenations)
+                if phrases.len() + concatenations.len() <= MAX_HIGHLIGHT_PHRASES =>
+            {
+                phrases.extend(concatenations);
+            }
+            _ => return Vec::new(),
+        }
+    }
+    phrases
+        .into_iter()
+        .map(|phrase| phrase.into_iter().enumerate().collect())
+        .collect()
+}
+
+impl SpanNearQuery {
+    /// Creates a new `SpanNearQuery`.
+    ///
+    /// There must be at least one clause, and all the clauses must target the same field.
+    pub fn new(clauses: Vec<Box<dyn SpanQuery>>, slop: u32, in_order: bool) -> SpanNearQuery {
+        assert!(
+            !clauses.is_empty(),
+            "A span near query is required to have at least one clause."
+        );
+        let field = clauses[0].field();
+        assert!(
+            clauses.iter().all(|clause| clause.field() == field),
+            "All clauses of a span near query must target the same field"
+        );
+        SpanNearQuery {
+            field,
+            clauses: clauses.into_iter().map(Arc::from).collect(),
+            slop,
+            in_order,
+            highlight_phrases: OnceCell::new(),
+        }
+    }
+
+    /// Maximum number of positions between the spans of the clauses.
+    pub fn slop(&self) -> u32 {
+        self.slop
+    }
+
+    /// Returns true if the clauses have to match in order.
+    pub fn is_in_order(&self) -> bool {
+        self.in_order
+    }
+}
+
+impl SpanQuery for SpanNearQuery {
+    fn field(&self) -> Field {
+        self.field
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let mut clause_spans = Vec::with_capacity(self.clauses.len());
+        for clause in &self.clauses {
+            if let Some(spans) = clause.spans(reader)? {
+                clause_spans.push(spans);
+            } else {
+                return Ok(None);
+            }
+        }
+        let combiner = NearCombiner {
+            slop: self.slop,
+            in_order: self.in_order,
+            clause_spans: vec![Vec::new(); clause_spans.len()],
+        };
+        let spans = CombinedSpans::new(SpansIntersection::new(clause_spans), combiner);
+        Ok(Some(Box::new(spans)))
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        if !self.in_order || self.slop > 0 {
+            return None;
+        }
+        concatenate_term_sequences(
+            self.clauses
+                .iter()
+                .map(|clause| clause.term_sequences())
+                .collect::<Option<Vec<_>>>()?
+                .iter(),
+        )
+    }
+}
+
+impl Query for SpanNearQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+
+    /// The query is highlighted as phrases with the slop of the query, one per order of the
+    /// clauses it can match, so that a term far from the other clauses is not highlighted.
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        let highlight_phrases = self
+            .highlight_phrases
+            .get_or_init(|| highlight_phrases(&self.clauses, self.in_order));
+        if highlight_phrases.is_empty() {
+            self.query_terms(&mut |term, _| visitor(MatchPattern::Term(term)));
+            return;
+        }
+        for phrase in highlight_phrases {
+            visitor(MatchPattern::Phrase {
+                terms: phrase,
+                slop: self.slop,
+            });
+        }
+    }
+}
+
+struct NearCombiner {
+    slop: u32,
+    in_order: bool,
+    clause_spans: Vec<Vec<Span>>,
+}
+
+impl NearCombiner {
+    /// For each span of the first clause, picks in each following clause the span ending first
+    /// among the spans starting after the end of the span of the previous clause.
+    fn combine_ordered(&self, output: &mut Vec<Span>) {
+        let (first_clause_spans, other_clause_spans) = self.clause_spans.split_first().unwrap();
+        'first_span: for first_span in first_clause_spans {
+            let mut previous_end = first_span.end;
+            let mut gaps = 0u32;
+            for spans in other_clause_spans {
+                let idx = spans.partition_point(|span| span.start < previous_end);
+                let next_span =
+                    if let Some(next_span) = spans[idx..].iter().min_by_key(|span| span.end) {
+                        next_span
+                    } else {
+                        continue 'first_span;
+                    };
+                gaps += next_span.start - previous_end;
+                if gaps > self.slop {
+                    continue 'first_span;
+                }
+                previous_end = next_span.end;
+            }
+            output.push(Span::new(first_span.start, previous_end));
+        }
+    }
+
+    /// Goes through the spans of all the clauses by increasing start position, keeping the
+    /// current span of each clause, and checks for each of them whether the current spans
+    /// are close enough.
+    fn combine_unordered(&self, output: &mut Vec<Span>) {
+        let mut current_idx = vec![0usize; self.clause_spans.len()];
+        loop {
+            let mut min_clause = 0;
+            let mut max_end = 0u32;
+            let mut total_width = 0u64;
+            for (clause, spans) in self.clause_spans.iter().enumerate() {
+                let span = spans[current_idx[clause]];
+                if span < self.clause_spans[min_clause][current_idx[min_clause]] {
+                    min_clause = clause;
+                }
+                max_end = max_end.max(span.end);
+                total_width += span.width() as u64;
+            }
+            let min_start = self.clause_spans[min_clause][current_idx[min_clause]].start;
+            let match_width = (max_end - min_start) as u64;
+            if match_width <= total_width + self.slop as u64 {
+                output.push(Span::new(min_start, max_end));
+            }
+            current_idx[min_clause] += 1;
+            if current_idx[min_clause] == self.clause_spans[min_clause].len() {
+                return;
+            }
+        }
+    }
+}
+
+impl SpanCombiner<SpansIntersection> for NearCombiner {
+    fn combine(&mut self, candidates: &mut SpansIntersection, output: &mut Vec<Span>) {
+        for (spans, clause_spans) in candidates
+            .children_mut()
+            .iter_mut()
+            .zip(self.clause_spans.iter_mut())
+        {
+            clause_spans.clear();
+            spans.spans(clause_spans);
+        }
+        if self.in_order {
+            self.combine_ordered(output);
+        } else {
+            self.combine_unordered(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanNearQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::span_query::tests::{create_index, span_term};
+    use crate::query::span_query::SpanQuery;
+    use crate::query::{PhraseQuery, Query, SpanTermQuery};
+    use crate::schema::{Schema, Term, TEXT};
+    use crate::Index;
+
+    fn count(index: &Index, query: &dyn Query) -> crate::Result<usize> {
+        index.reader()?.searcher().search(query, &Count)
+    }
+
+    fn near(index: &Index, texts: &[&str], slop: u32, in_order: bool) -> SpanNearQuery {
+        let clauses: Vec<Box<dyn SpanQuery>> = texts
+            .iter()
+            .map(|text| span_term(index, text) as Box<dyn SpanQuery>)
+            .collect();
+        SpanNearQuery::new(clauses, slop, in_order)
+    }
+
+    #[test]
+    fn test_span_near_query_ordered() -> crate::Result<()> {
+        let index = create_index(&["a b c", "a x b", "a x x b", "b a", "c a b"])?;
+        assert_eq!(count(&index, &near(&index, &["a", "b"], 0, true))?, 2);
+        assert_eq!(count(&index, &near(&index, &["a", "b"], 1, true))?, 3);
+        assert_eq!(count(&index, &near(&index, &["a", "b"], 2, true))?, 4);
+        assert_eq!(count(&index, &near(&index, &["a", "b", "c"], 0, true))?, 1);
+        assert_eq!(count(&index, &near(&index, &["b", "a"], 0, true))?, 1);
+        assert_eq!(count(&index, &near(&index, &["b", "a"], 5, true))?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_near_query_ordered_same_as_phrase() -> crate::Result<()> {
+        let index = create_index(&["a b c a b", "a c b", "b a b a"])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ]);
+        let span_near_query = near(&index, &["a", "b"], 0, true);
+        let searcher = index.reader()?.searcher();
+        let phrase_docs = searcher.search(&phrase_query, &TopDocs::with_limit(10))?;
+        let span_docs = searcher.search(&span_near_query, &TopDocs::with_limit(10))?;
+        assert_eq!(phrase_docs, span_docs);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_near_query_unordered() -> crate::Result<()> {
+        let index = create_index(&["a b", "b a", "b x x a", "a", "b x a x c"])?;
+        assert_eq!(count(&index, &near(&index, &["a", "b"], 0, false))?, 2);
+        assert_eq!(count(&index, &near(&index, &["a", "b"], 2, false))?, 4);
+        assert_eq!(count(&index, &near(&index, &["c", "b", "a"], 2, false))?, 1);
+        assert_eq!(count(&index, &near(&index, &["c", "b", "a"], 1, false))?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_span_near_query_nested() -> crate::Result<()> {
+        let index = create_index(&["a b x c d", "a b x x x c d", "c d a b"])?;
+        let inner = |texts: &[&str]| Box::new(near(&index, texts, 0, true)) as Box<dyn SpanQuery>;
+        let query = SpanNearQuery::new(vec![inner(&["a", "b"]), inner(&["c", "d"])], 1, true);
+        assert_eq!(count(&index, &query)?, 1);
+        let query = SpanNearQuery::new(vec![inner(&["a", "b"]), inner(&["c", "d"])], 0, false);
+        assert_eq!(count(&index, &query)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "same field")]
+    fn test_span_near_query_different_fields() {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let clauses: Vec<Box<dyn SpanQuery>> = vec![
+            Box::new(SpanTermQuery::new(Term::from_field_text(title, "a"))),
+            Box::new(SpanTermQuery::new(Term::from_field_text(body, "a"))),
+        ];
+        SpanNearQuery::new(clauses, 0, true);
+    }
+}
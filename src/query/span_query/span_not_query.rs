@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use super::spans::{CombinedSpans, SpanCombiner};
+use super::{Span, SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::{DocSet, SegmentReader};
+
+/// `SpanNotQuery` matches the spans of a query which are not close to the spans of
+/// another query.
+///
+/// A span of the `include` query is removed if a span of the `exclude` query overlaps it,
+/// or starts less than `post` positions after its end, or ends less than `pre` positions
+/// before its start. For instance, "`a` not followed by `b`" is expressed with `pre = 0` and
+/// `post = 1`.
+///
+/// Only the terms of the `include` query are used to score the documents.
+#[derive(Clone, Debug)]
+pub struct SpanNotQuery {
+    include: Arc<dyn SpanQuery>,
+    exclude: Arc<dyn SpanQuery>,
+    pre: u32,
+    post: u32,
+}
+
+impl SpanNotQuery {
+    /// Creates a new `SpanNotQuery`, removing the spans of `include` overlapping a span of
+    /// `exclude`.
+    ///
+    /// Both queries must target the same field.
+    pub fn new(include: Box<dyn SpanQuery>, exclude: Box<dyn SpanQuery>) -> SpanNotQuery {
+        SpanNotQuery::new_with_distances(include, exclude, 0, 0)
+    }
+
+    /// Creates a new `SpanNotQuery`, removing the spans of `include` which are less than `pre`
+    /// positions after or `post` positions before a span of `exclude`.
+    ///
+    /// Both queries must target the same field.
+    pub fn new_with_distances(
+        include: Box<dyn SpanQuery>,
+        exclude: Box<dyn SpanQuery>,
+        pre: u32,
+        post: u32,
+    ) -> SpanNotQuery {
+        assert!(
+            include.field() == exclude.field(),
+            "The clauses of a span not query must target the same field"
+        );
+        SpanNotQuery {
+            include: Arc::from(include),
+            exclude: Arc::from(exclude),
+            pre,
+            post,
+        }
+    }
+}
+
+impl SpanQuery for SpanNotQuery {
+    fn field(&self) -> Field {
+        self.include.field()
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let include = if let Some(include) = self.include.spans(reader)? {
+            include
+        } else {
+            return Ok(None);
+        };
+        let exclude_opt = self.exclude.spans(reader)?;
+        if exclude_opt.is_none() {
+            return Ok(Some(include));
+        }
+        let combiner = NotCombiner {
+            exclude_opt,
+            pre: self.pre,
+            post: self.post,
+            include_spans: Vec::new(),
+            exclude_spans: Vec::new(),
+        };
+        Ok(Some(Box::new(CombinedSpans::new(include, combiner))))
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        self.include.term_sequences()
+    }
+}
+
+impl Query for SpanNotQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.include.query_terms(visitor);
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        self.include.match_patterns(visitor);
+    }
+}
+
+struct NotCombiner {
+    exclude_opt: Option<Box<dyn Spans>>,
+    pre: u32,
+    post: u32,
+    include_spans: Vec<Span>,
+    exclude_spans: Vec<Span>,
+}
+
+impl SpanCombiner<Box<dyn Spans>> for NotCombiner {
+    fn combine(&mut self, include: &mut Box<dyn Spans>, output: &mut Vec<Span>) {
+        let doc = include.doc();
+        self.include_spans.clear();
+        include.spans(&mut self.include_spans);
+        self.exclude_spans.clear();
+        if let Some(exclude) = self.exclude_opt.as_mut() {
+            if exclude.doc() < doc {
+                exclude.seek(doc);
+            }
+            if exclude.doc() == doc {
+                exclude.spans(&mut self.exclude_spans);
+            }
+        }
+        let (pre, post) = (self.pre, self.post);
+        let exclude_spans = &self.exclude_spans;
+        output.extend(self.include_spans.iter().filter(|include_span| {
+            !exclude_spans.iter().any(|exclude_span| {
+                exclude_span.end + pre > include_span.start
+                    && exclude_span.start < include_span.end + post
+            })
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanNotQuery;
+    use crate::collector::Count;
+    use crate::query::span_query::tests::{create_index, span_term};
+    use crate::query::SpanNearQuery;
+
+    #[test]
+    fn test_span_not_query() -> crate::Result<()> {
+        let index = create_index(&["a b", "a c", "b a", "a c b", "a b a", "c"])?;
+        let searcher = index.reader()?.searcher();
+        // "a" not followed by "b"
+        let query =
+            SpanNotQuery::new_with_distances(span_term(&index, "a"), span_term(&index, "b"), 0, 1);
+        assert_eq!(searcher.search(&query, &Count)?, 4);
+        // "a" not within 2 positions of "b"
+        let query =
+            SpanNotQuery::new_with_distances(span_term(&index, "a"), span_term(&index, "b"), 2, 2);
+        assert_eq!(searcher.search(&query, &Count)?, 1);
+        // "a" not overlapping "a b"
+        let a_b = SpanNearQuery::new(
+            vec![span_term(&index, "a"), span_term(&index, "b")],
+            0,
+            true,
+        );
+        let query = SpanNotQuery::new(span_term(&index, "a"), Box::new(a_b));
+        assert_eq!(searcher.search(&query, &Count)?, 4);
+        let query = SpanNotQuery::new(span_term(&index, "a"), span_term(&index, "z"));
+        assert_eq!(searcher.search(&query, &Count)?, 5);
+        Ok(())
+    }
+}
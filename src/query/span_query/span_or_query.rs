@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use super::spans::{CombinedSpans, SpanCombiner, SpansUnion};
+use super::{Span, SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, MatchPattern, Query, Weight};
+use crate::schema::{Field, Term};
+use crate::SegmentReader;
+
+/// `SpanOrQuery` matches the spans of any of its clauses.
+#[derive(Clone, Debug)]
+pub struct SpanOrQuery {
+    field: Field,
+    clauses: Vec<Arc<dyn SpanQuery>>,
+}
+
+impl SpanOrQuery {
+    /// Creates a new `SpanOrQuery`.
+    ///
+    /// There must be at least one clause, and all the clauses must target the same field.
+    pub fn new(clauses: Vec<Box<dyn SpanQuery>>) -> SpanOrQuery {
+        assert!(
+            !clauses.is_empty(),
+            "A span or query is required to have at least one clause."
+        );
+        let field = clauses[0].field();
+        assert!(
+            clauses.iter().all(|clause| clause.field() == field),
+            "All clauses of a span or query must target the same field"
+        );
+        SpanOrQuery {
+            field,
+            clauses: clauses.into_iter().map(Arc::from).collect(),
+        }
+    }
+}
+
+impl SpanQuery for SpanOrQuery {
+    fn field(&self) -> Field {
+        self.field
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let mut clause_spans = Vec::with_capacity(self.clauses.len());
+        for clause in &self.clauses {
+            if let Some(spans) = clause.spans(reader)? {
+                clause_spans.push(spans);
+            }
+        }
+        if clause_spans.is_empty() {
+            return Ok(None);
+        }
+        let spans = CombinedSpans::new(SpansUnion::new(clause_spans), OrCombiner);
+        Ok(Some(Box::new(spans)))
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        let mut term_sequences = Vec::new();
+        for clause in &self.clauses {
+            term_sequences.extend(clause.term_sequences()?);
+        }
+        Some(term_sequences)
+    }
+}
+
+impl Query for SpanOrQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for clause in &self.clauses {
+            clause.query_terms(visitor);
+        }
+    }
+
+    fn match_patterns<'a>(&'a self, visitor: &mut dyn FnMut(MatchPattern<'a>)) {
+        for clause in &self.clauses {
+            clause.match_patterns(visitor);
+        }
+    }
+}
+
+struct OrCombiner;
+
+impl SpanCombiner<SpansUnion> for OrCombiner {
+    fn combine(&mut self, candidates: &mut SpansUnion, output: &mut Vec<Span>) {
+        candidates.spans_of_current_doc(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanOrQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::span_query::tests::{create_index, span_term};
+    use crate::query::span_query::SpanQuery;
+    use crate::DocAddress;
+
+    #[test]
+    fn test_span_or_query() -> crate::Result<()> {
+        let index = create_index(&["a", "b", "c", "a b", "a b a"])?;
+        let clauses: Vec<Box<dyn SpanQuery>> = vec![
+            span_term(&index, "a"),
+            span_term(&index, "b"),
+            span_term(&index, "z"),
+        ];
+        let query = SpanOrQuery::new(clauses);
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.search(&query, &Count)?, 4);
+        // The document with the most spans comes first.
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 4));
+        let missing = SpanOrQuery::new(vec![span_term(&index, "z")]);
+        assert_eq!(searcher.search(&missing, &Count)?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use super::Spans;
+use crate::query::Query;
+use crate::schema::{Field, Term};
+use crate::SegmentReader;
+
+/// A query matching spans of positions in a field.
+///
+/// Span queries can be combined with each other to express proximity constraints, e.g.
+/// "`a` within 5 positions of `b` or `c`, not followed by `d`":
+/// [`SpanTermQuery`](super::SpanTermQuery) matches the positions of a term, and
+/// [`SpanNearQuery`](super::SpanNearQuery), [`SpanOrQuery`](super::SpanOrQuery),
+/// [`SpanNotQuery`](super::SpanNotQuery), [`SpanFirstQuery`](super::SpanFirstQuery) and
+/// [`SpanContainingQuery`](super::SpanContainingQuery) combine the spans of other span queries.
+///
+/// All the span queries of a combination must target the same field, which is required to
+/// have positions indexed.
+///
+/// A document matches a span query if it contains at least one span. Its score is computed
+/// by the similarity of the field over the terms of the query, using the number of spans
+/// in the document as term frequency, the same way [`PhraseQuery`](crate::query::PhraseQuery)
+/// uses the number of occurrences of the phrase.
+pub trait SpanQuery: Query {
+    /// The field targeted by the query.
+    fn field(&self) -> Field;
+
+    /// Returns the spans of the query in a segment.
+    ///
+    /// Returns `None` if the query cannot match any document of the segment.
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>>;
+
+    /// Returns the sequences of consecutive terms the spans of the query can be made of, used to
+    /// highlight its matches.
+    ///
+    /// Returns `None` if the spans are not sequences of consecutive terms, e.g. for a
+    /// [`SpanNearQuery`](super::SpanNearQuery) with a slop.
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        None
+    }
+}
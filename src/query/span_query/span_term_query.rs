@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use super::spans::TermSpans;
+use super::{SpanQuery, SpanWeight, Spans};
+use crate::query::{EnableScoring, Query, Weight};
+use crate::schema::{Field, IndexRecordOption, Term};
+use crate::SegmentReader;
+
+/// `SpanTermQuery` matches the positions of a term.
+///
+/// On its own, it matches the same documents as a [`TermQuery`](crate::query::TermQuery).
+/// It is meant to be combined into other [span queries](super::SpanQuery).
+#[derive(Clone, Debug)]
+pub struct SpanTermQuery {
+    term: Term,
+}
+
+impl SpanTermQuery {
+    /// Creates a new `SpanTermQuery`.
+    pub fn new(term: Term) -> SpanTermQuery {
+        SpanTermQuery { term }
+    }
+
+    /// The term matched by the query.
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+}
+
+impl SpanQuery for SpanTermQuery {
+    fn field(&self) -> Field {
+        self.term.field()
+    }
+
+    fn term_sequences(&self) -> Option<Vec<Vec<Term>>> {
+        Some(vec![vec![self.term.clone()]])
+    }
+
+    fn spans(&self, reader: &SegmentReader) -> crate::Result<Option<Box<dyn Spans>>> {
+        let postings_opt = reader
+            .inverted_index(self.term.field())?
+            .read_postings(&self.term, IndexRecordOption::WithFreqsAndPositions)?;
+        Ok(postings_opt.map(|postings| Box::new(TermSpans::new(postings)) as Box<dyn Spans>))
+    }
+}
+
+impl Query for SpanTermQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let span_weight = SpanWeight::new(Arc::new(self.clone()), enable_scoring)?;
+        Ok(Box::new(span_weight))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        visitor(&self.term, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanTermQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::span_query::tests::create_index;
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Term};
+
+    #[test]
+    fn test_span_term_query_scores_like_term_query() -> crate::Result<()> {
+        let index = create_index(&["a b c", "a a b", "b c d"])?;
+        let text_field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let term = Term::from_field_text(text_field, "a");
+        let span_query = SpanTermQuery::new(term.clone());
+        let term_query = TermQuery::new(term, IndexRecordOption::WithFreqs);
+        assert_eq!(searcher.search(&span_query, &Count)?, 2);
+        let span_top_docs = searcher.search(&span_query, &TopDocs::with_limit(3))?;
+        let term_top_docs = searcher.search(&term_query, &TopDocs::with_limit(3))?;
+        assert_eq!(span_top_docs, term_top_docs);
+        let missing_term = Term::from_field_text(text_field, "z");
+        assert_eq!(
+            searcher.search(&SpanTermQuery::new(missing_term), &Count)?,
+            0
+        );
+        Ok(())
+    }
+}
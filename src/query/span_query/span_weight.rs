@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use super::{Span, SpanQuery, Spans};
+use crate::core::SegmentReader;
+use crate::fieldnorm::FieldNormReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{EmptyScorer, EnableScoring, Explanation, Scorer, SimilarityWeight, Weight};
+use crate::schema::IndexRecordOption;
+use crate::{DocId, DocSet, Score};
+
+/// Weight shared by the span queries.
+pub(crate) struct SpanWeight {
+    query: Arc<dyn SpanQuery>,
+    similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
+}
+
+impl SpanWeight {
+    /// Creates the weight of a span query, checking that its field has positions indexed.
+    pub fn new(
+        query: Arc<dyn SpanQuery>,
+        enable_scoring: EnableScoring<'_>,
+    ) -> crate::Result<SpanWeight> {
+        let field_entry = enable_scoring.schema().get_field_entry(query.field());
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(IndexRecordOption::has_positions)
+            .unwrap_or(false);
+        if !has_positions {
+            let field_name = field_entry.name();
+            return Err(crate::TantivyError::SchemaError(format!(
+                "Applied span query on field {field_name:?}, which does not have positions indexed"
+            )));
+        }
+        let mut terms = Vec::new();
+        query.query_terms(&mut |term, _| terms.push(term.clone()));
+        let similarity_weight_opt = enable_scoring.similarity_weight(&terms)?;
+        Ok(SpanWeight {
+            query,
+            similarity_weight_opt,
+        })
+    }
+
+    fn fieldnorm_reader(&self, reader: &SegmentReader) -> crate::Result<FieldNormReader> {
+        if self.similarity_weight_opt.is_some() {
+            if let Some(fieldnorm_reader) =
+                reader.fieldnorms_readers().get_field(self.query.field())?
+            {
+                return Ok(fieldnorm_reader);
+            }
+        }
+        Ok(FieldNormReader::constant(reader.max_doc(), 1))
+    }
+
+    fn span_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Option<SpanScorer>> {
+        let spans = if let Some(spans) = self.query.spans(reader)? {
+            spans
+        } else {
+            return Ok(None);
+        };
+        let similarity_weight_opt = self
+            .similarity_weight_opt
+            .as_ref()
+            .map(|similarity_weight| similarity_weight.boost_by(boost));
+        Ok(Some(SpanScorer {
+            spans,
+            fieldnorm_reader: self.fieldnorm_reader(reader)?,
+            similarity_weight_opt,
+            spans_buffer: Vec::new(),
+        }))
+    }
+}
+
+impl Weight for SpanWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        if let Some(scorer) = self.span_scorer(reader, boost)? {
+            Ok(Box::new(scorer))
+        } else {
+            Ok(Box::new(EmptyScorer))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut scorer = self
+            .span_scorer(reader, 1.0)?
+            .ok_or_else(|| does_not_match(doc))?;
+        if scorer.seek(doc) != doc {
+            return Err(does_not_match(doc));
+        }
+        let fieldnorm_id = scorer.fieldnorm_reader.fieldnorm_id(doc);
+        let span_count = scorer.span_count();
+        let mut explanation = Explanation::new("Span Scorer", scorer.score());
+        if let Some(similarity_weight) = self.similarity_weight_opt.as_ref() {
+            explanation.add_detail(similarity_weight.explain(fieldnorm_id, span_count));
+        }
+        Ok(explanation)
+    }
+}
+
+/// Scores the documents matching a span query, using their number of spans as term
+/// frequency.
+pub(crate) struct SpanScorer {
+    spans: Box<dyn Spans>,
+    fieldnorm_reader: FieldNormReader,
+    similarity_weight_opt: Option<Arc<dyn SimilarityWeight>>,
+    spans_buffer: Vec<Span>,
+}
+
+impl SpanScorer {
+    /// Returns the number of spans in the current document.
+    pub fn span_count(&mut self) -> u32 {
+        self.spans_buffer.clear();
+        self.spans.spans(&mut self.spans_buffer);
+        self.spans_buffer.len() as u32
+    }
+}
+
+impl DocSet for SpanScorer {
+    fn advance(&mut self) -> DocId {
+        self.spans.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.spans.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.spans.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.spans.size_hint()
+    }
+}
+
+impl Scorer for SpanScorer {
+    fn score(&mut self) -> Score {
+        if self.similarity_weight_opt.is_none() {
+            return 1.0f32;
+        }
+        let doc = self.doc();
+        let fieldnorm_id = self.fieldnorm_reader.fieldnorm_id(doc);
+        let span_count = self.span_count();
+        self.similarity_weight_opt
+            .as_ref()
+            .map(|similarity_weight| similarity_weight.score(fieldnorm_id, span_count))
+            .unwrap_or(1.0f32)
+    }
+}
@@ -0,0 +1,310 @@
+use crate::docset::{DocSet, TERMINATED};
+use crate::postings::{Postings, SegmentPostings};
+use crate::DocId;
+
+/// A span of positions `[start, end)` in a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    /// Position of the first token of the span.
+    pub start: u32,
+    /// Position following the last token of the span.
+    pub end: u32,
+}
+
+impl Span {
+    /// Creates a new span.
+    pub fn new(start: u32, end: u32) -> Span {
+        Span { start, end }
+    }
+
+    /// Number of positions in the span.
+    pub fn width(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// Returns true if `other` is included in the span.
+    pub fn contains(&self, other: &Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// The documents matching a [`SpanQuery`](super::SpanQuery), along with their spans.
+///
+/// Like other `DocSet`s, `Spans` are positioned on their first document when created.
+/// Every document of the `DocSet` has at least one span.
+pub trait Spans: DocSet {
+    /// Appends the spans of the current document to `output`.
+    ///
+    /// Spans are sorted by start position, then by end position, and are not repeated.
+    fn spans(&mut self, output: &mut Vec<Span>);
+}
+
+impl<TSpans: Spans + ?Sized> Spans for Box<TSpans> {
+    fn spans(&mut self, output: &mut Vec<Span>) {
+        self.as_mut().spans(output)
+    }
+}
+
+/// The spans of single term, one for each of its positions.
+pub(crate) struct TermSpans {
+    postings: SegmentPostings,
+    positions: Vec<u32>,
+}
+
+impl TermSpans {
+    pub fn new(postings: SegmentPostings) -> TermSpans {
+        TermSpans {
+            postings,
+            positions: Vec::new(),
+        }
+    }
+}
+
+impl DocSet for TermSpans {
+    fn advance(&mut self) -> DocId {
+        self.postings.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.postings.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.postings.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings.size_hint()
+    }
+}
+
+impl Spans for TermSpans {
+    fn spans(&mut self, output: &mut Vec<Span>) {
+        self.postings.positions(&mut self.positions);
+        output.extend(
+            self.positions
+                .iter()
+                .map(|&position| Span::new(position, position + 1)),
+        );
+    }
+}
+
+/// Computes the spans of a composite span query, on the documents of a candidate `DocSet`.
+pub(crate) trait SpanCombiner<TCandidates>: Send {
+    /// Appends the spans of the current document of `candidates` to `output`.
+    ///
+    /// Spans do not need to be sorted or deduplicated.
+    fn combine(&mut self, candidates: &mut TCandidates, output: &mut Vec<Span>);
+}
+
+/// Spans built by a [`SpanCombiner`], skipping the candidates for which it does not
+/// produce any span.
+pub(crate) struct CombinedSpans<TCandidates, TCombiner> {
+    candidates: TCandidates,
+    combiner: TCombiner,
+    spans: Vec<Span>,
+}
+
+impl<TCandidates, TCombiner> CombinedSpans<TCandidates, TCombiner>
+where
+    TCandidates: DocSet,
+    TCombiner: SpanCombiner<TCandidates>,
+{
+    pub fn new(candidates: TCandidates, combiner: TCombiner) -> Self {
+        let mut combined_spans = CombinedSpans {
+            candidates,
+            combiner,
+            spans: Vec::new(),
+        };
+        if combined_spans.doc() != TERMINATED && !combined_spans.compute_spans() {
+            combined_spans.advance();
+        }
+        combined_spans
+    }
+
+    fn compute_spans(&mut self) -> bool {
+        self.spans.clear();
+        self.combiner.combine(&mut self.candidates, &mut self.spans);
+        self.spans.sort_unstable();
+        self.spans.dedup();
+        !self.spans.is_empty()
+    }
+}
+
+impl<TCandidates, TCombiner> DocSet for CombinedSpans<TCandidates, TCombiner>
+where
+    TCandidates: DocSet,
+    TCombiner: SpanCombiner<TCandidates>,
+{
+    fn advance(&mut self) -> DocId {
+        loop {
+            let doc = self.candidates.advance();
+            if doc == TERMINATED || self.compute_spans() {
+                return doc;
+            }
+        }
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        debug_assert!(target >= self.doc());
+        let doc = self.candidates.seek(target);
+        if doc == TERMINATED || self.compute_spans() {
+            return doc;
+        }
+        self.advance()
+    }
+
+    fn doc(&self) -> DocId {
+        self.candidates.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.candidates.size_hint()
+    }
+}
+
+impl<TCandidates, TCombiner> Spans for CombinedSpans<TCandidates, TCombiner>
+where
+    TCandidates: DocSet,
+    TCombiner: SpanCombiner<TCandidates>,
+{
+    fn spans(&mut self, output: &mut Vec<Span>) {
+        output.extend_from_slice(&self.spans);
+    }
+}
+
+/// The documents matched by all of the sub-spans.
+pub(crate) struct SpansIntersection {
+    children: Vec<Box<dyn Spans>>,
+    doc: DocId,
+}
+
+impl SpansIntersection {
+    pub fn new(children: Vec<Box<dyn Spans>>) -> SpansIntersection {
+        assert!(!children.is_empty());
+        let target = children.iter().map(|child| child.doc()).max().unwrap();
+        let mut intersection = SpansIntersection { children, doc: 0 };
+        intersection.doc = intersection.align(target);
+        intersection
+    }
+
+    /// Seeks all the children to the first document they have in common, greater than or
+    /// equal to `target`.
+    fn align(&mut self, mut target: DocId) -> DocId {
+        'align: loop {
+            if target == TERMINATED {
+                return TERMINATED;
+            }
+            for child in &mut self.children {
+                let doc = child.seek(target);
+                if doc > target {
+                    target = doc;
+                    continue 'align;
+                }
+            }
+            return target;
+        }
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Box<dyn Spans>] {
+        &mut self.children
+    }
+}
+
+impl DocSet for SpansIntersection {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        let target = self.children[0].advance();
+        self.doc = self.align(target);
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.doc = self.align(target);
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.children
+            .iter()
+            .map(|child| child.size_hint())
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// The documents matched by any of the sub-spans.
+pub(crate) struct SpansUnion {
+    children: Vec<Box<dyn Spans>>,
+    doc: DocId,
+}
+
+impl SpansUnion {
+    pub fn new(children: Vec<Box<dyn Spans>>) -> SpansUnion {
+        let mut union = SpansUnion { children, doc: 0 };
+        union.doc = union.min_doc();
+        union
+    }
+
+    fn min_doc(&self) -> DocId {
+        self.children
+            .iter()
+            .map(|child| child.doc())
+            .min()
+            .unwrap_or(TERMINATED)
+    }
+
+    /// Appends the spans of the children positioned on the current document.
+    pub fn spans_of_current_doc(&mut self, output: &mut Vec<Span>) {
+        let doc = self.doc;
+        for child in &mut self.children {
+            if child.doc() == doc {
+                child.spans(output);
+            }
+        }
+    }
+}
+
+impl DocSet for SpansUnion {
+    fn advance(&mut self) -> DocId {
+        let doc = self.doc;
+        if doc == TERMINATED {
+            return TERMINATED;
+        }
+        for child in &mut self.children {
+            if child.doc() == doc {
+                child.advance();
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        for child in &mut self.children {
+            if child.doc() < target {
+                child.seek(target);
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.children
+            .iter()
+            .map(|child| child.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
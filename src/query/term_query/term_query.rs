@@ -1,4 +1,5 @@
 use std::fmt;
+use std::sync::Arc;
 
 use super::term_weight::TermWeight;
 use crate::query::bm25::Bm25Weight;
@@ -95,15 +96,14 @@ impl TermQuery {
             let error_msg = format!("Field {:?} is not indexed.", field_entry.name());
             return Err(crate::TantivyError::SchemaError(error_msg));
         }
-        let bm25_weight = match enable_scoring {
-            EnableScoring::Enabled {
-                statistics_provider,
-                ..
-            } => Bm25Weight::for_terms(statistics_provider, &[self.term.clone()])?,
-            EnableScoring::Disabled { .. } => {
-                Bm25Weight::new(Explanation::new("<no score>".to_string(), 1.0f32), 1.0f32)
-            }
-        };
+        let similarity_weight = enable_scoring
+            .similarity_weight(&[self.term.clone()])?
+            .unwrap_or_else(|| {
+                Arc::new(Bm25Weight::new(
+                    Explanation::new("<no score>".to_string(), 1.0f32),
+                    1.0f32,
+                ))
+            });
         let scoring_enabled = enable_scoring.is_scoring_enabled();
         let index_record_option = if scoring_enabled {
             self.index_record_option
@@ -114,7 +114,7 @@ impl TermQuery {
         Ok(TermWeight::new(
             self.term.clone(),
             index_record_option,
-            bm25_weight,
+            similarity_weight,
             scoring_enabled,
         ))
     }
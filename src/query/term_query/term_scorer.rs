@@ -1,23 +1,59 @@
+use std::sync::Arc;
+
 use crate::docset::DocSet;
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::{FreqReadingOption, Postings, SegmentPostings};
-use crate::query::bm25::Bm25Weight;
-use crate::query::{Explanation, Scorer};
-use crate::{DocId, Score};
+use crate::query::{Bm25Weight, Explanation, Scorer, SimilarityWeight};
+use crate::{DocId, Score, TERMINATED};
+
+/// The weight of a [`TermScorer`].
+///
+/// BM25 is the default similarity, and is kept concrete so that scoring a document doesn't
+/// require a virtual call.
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)] // The BM25 weight is read without an indirection.
+enum TermScorerWeight {
+    Bm25(Bm25Weight),
+    Custom(Arc<dyn SimilarityWeight>),
+}
+
+impl TermScorerWeight {
+    fn as_similarity_weight(&self) -> &dyn SimilarityWeight {
+        match self {
+            TermScorerWeight::Bm25(bm25_weight) => bm25_weight,
+            TermScorerWeight::Custom(similarity_weight) => similarity_weight.as_ref(),
+        }
+    }
+
+    #[inline]
+    fn score(&self, fieldnorm_id: u8, term_freq: u32) -> Score {
+        match self {
+            TermScorerWeight::Bm25(bm25_weight) => bm25_weight.score(fieldnorm_id, term_freq),
+            TermScorerWeight::Custom(similarity_weight) => {
+                similarity_weight.score(fieldnorm_id, term_freq)
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct TermScorer {
     postings: SegmentPostings,
     fieldnorm_reader: FieldNormReader,
-    similarity_weight: Bm25Weight,
+    similarity_weight: TermScorerWeight,
 }
 
 impl TermScorer {
     pub fn new(
         postings: SegmentPostings,
         fieldnorm_reader: FieldNormReader,
-        similarity_weight: Bm25Weight,
+        similarity_weight: Arc<dyn SimilarityWeight>,
     ) -> TermScorer {
+        let similarity_weight = if let Some(bm25_weight) = similarity_weight.as_bm25() {
+            TermScorerWeight::Bm25(bm25_weight.clone())
+        } else {
+            TermScorerWeight::Custom(similarity_weight)
+        };
         TermScorer {
             postings,
             fieldnorm_reader,
@@ -33,7 +69,7 @@ impl TermScorer {
     pub fn create_for_test(
         doc_and_tfs: &[(DocId, u32)],
         fieldnorms: &[u32],
-        similarity_weight: Bm25Weight,
+        similarity_weight: impl SimilarityWeight,
     ) -> TermScorer {
         assert!(!doc_and_tfs.is_empty());
         assert!(
@@ -47,7 +83,11 @@ impl TermScorer {
         let segment_postings =
             SegmentPostings::create_from_docs_and_tfs(doc_and_tfs, Some(fieldnorms));
         let fieldnorm_reader = FieldNormReader::for_test(fieldnorms);
-        TermScorer::new(segment_postings, fieldnorm_reader, similarity_weight)
+        TermScorer::new(
+            segment_postings,
+            fieldnorm_reader,
+            Arc::new(similarity_weight),
+        )
     }
 
     /// See `FreqReadingOption`.
@@ -69,9 +109,19 @@ impl TermScorer {
     ///
     /// (The result is on the other hand guaranteed to be correct if there is only one segment).
     pub fn block_max_score(&mut self) -> Score {
-        self.postings
-            .block_cursor
-            .block_max_score(&self.fieldnorm_reader, &self.similarity_weight)
+        let block_cursor = &mut self.postings.block_cursor;
+        match &self.similarity_weight {
+            TermScorerWeight::Bm25(bm25_weight) => {
+                block_cursor.block_max_score(&self.fieldnorm_reader, bm25_weight)
+            }
+            // Without fieldnorms, the term frequency isn't bounded by the field length.
+            TermScorerWeight::Custom(similarity_weight) if self.fieldnorm_reader.is_constant() => {
+                similarity_weight.max_score_without_fieldnorms()
+            }
+            TermScorerWeight::Custom(similarity_weight) => {
+                block_cursor.block_max_score(&self.fieldnorm_reader, similarity_weight.as_ref())
+            }
+        }
     }
 
     pub fn term_freq(&self) -> u32 {
@@ -85,11 +135,38 @@ impl TermScorer {
     pub fn explain(&self) -> Explanation {
         let fieldnorm_id = self.fieldnorm_id();
         let term_freq = self.term_freq();
-        self.similarity_weight.explain(fieldnorm_id, term_freq)
+        self.similarity_weight
+            .as_similarity_weight()
+            .explain(fieldnorm_id, term_freq)
     }
 
     pub fn max_score(&self) -> Score {
-        self.similarity_weight.max_score()
+        let similarity_weight = self.similarity_weight.as_similarity_weight();
+        if self.fieldnorm_reader.is_constant() {
+            similarity_weight.max_score_without_fieldnorms()
+        } else {
+            similarity_weight.max_score()
+        }
+    }
+
+    /// Calls `callback` with all the remaining documents and their scores.
+    ///
+    /// The similarity is resolved once, rather than for every document.
+    pub(crate) fn for_each(&mut self, callback: &mut dyn FnMut(DocId, Score)) {
+        match &self.similarity_weight {
+            TermScorerWeight::Bm25(bm25_weight) => for_each_with_weight(
+                &mut self.postings,
+                &self.fieldnorm_reader,
+                bm25_weight,
+                callback,
+            ),
+            TermScorerWeight::Custom(similarity_weight) => for_each_with_weight(
+                &mut self.postings,
+                &self.fieldnorm_reader,
+                similarity_weight.as_ref(),
+                callback,
+            ),
+        }
     }
 
     pub fn last_doc_in_block(&self) -> DocId {
@@ -97,6 +174,23 @@ impl TermScorer {
     }
 }
 
+fn for_each_with_weight<TSimilarityWeight: SimilarityWeight + ?Sized>(
+    postings: &mut SegmentPostings,
+    fieldnorm_reader: &FieldNormReader,
+    similarity_weight: &TSimilarityWeight,
+    callback: &mut dyn FnMut(DocId, Score),
+) {
+    let mut doc = postings.doc();
+    while doc != TERMINATED {
+        let fieldnorm_id = fieldnorm_reader.fieldnorm_id(doc);
+        callback(
+            doc,
+            similarity_weight.score(fieldnorm_id, postings.term_freq()),
+        );
+        doc = postings.advance();
+    }
+}
+
 impl DocSet for TermScorer {
     fn advance(&mut self) -> DocId {
         self.postings.advance()
@@ -125,12 +219,19 @@ impl Scorer for TermScorer {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use proptest::prelude::*;
 
+    use crate::fieldnorm::FieldNormReader;
     use crate::merge_policy::NoMergePolicy;
     use crate::postings::compression::COMPRESSION_BLOCK_SIZE;
+    use crate::postings::SegmentPostings;
     use crate::query::term_query::TermScorer;
-    use crate::query::{Bm25Weight, EnableScoring, Scorer, TermQuery};
+    use crate::query::{
+        Bm25Weight, EnableScoring, Scorer, Similarity, SimilarityStatistics, SimilarityWeight,
+        TermQuery, TfIdfSimilarity,
+    };
     use crate::schema::{IndexRecordOption, Schema, TEXT};
     use crate::{
         assert_nearly_equals, DocId, DocSet, Index, Score, Searcher, SegmentId, Term, TERMINATED,
@@ -162,6 +263,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_term_scorer_for_each() {
+        let doc_and_tfs = [(2, 3), (3, 12), (7, 8)];
+        let fieldnorms = [0, 0, 10, 12, 0, 0, 0, 100];
+        let statistics = SimilarityStatistics::new(6, 60, vec![3]);
+        let weights: [Arc<dyn SimilarityWeight>; 2] = [
+            Arc::new(Bm25Weight::for_one_term(3, 6, 10.0)),
+            TfIdfSimilarity.weight(&statistics),
+        ];
+        for weight in weights {
+            let new_scorer = || {
+                let postings =
+                    SegmentPostings::create_from_docs_and_tfs(&doc_and_tfs, Some(&fieldnorms));
+                let fieldnorm_reader = FieldNormReader::for_test(&fieldnorms);
+                TermScorer::new(postings, fieldnorm_reader, weight.clone())
+            };
+            let mut expected = Vec::new();
+            let mut term_scorer = new_scorer();
+            while term_scorer.doc() != TERMINATED {
+                expected.push((term_scorer.doc(), term_scorer.score()));
+                term_scorer.advance();
+            }
+            let mut docs_and_scores = Vec::new();
+            new_scorer().for_each(&mut |doc, score| docs_and_scores.push((doc, score)));
+            assert_eq!(docs_and_scores.len(), 3);
+            assert_eq!(docs_and_scores, expected);
+        }
+    }
+
     #[test]
     fn test_term_scorer_shallow_advance() -> crate::Result<()> {
         let bm25_weight = Bm25Weight::for_one_term(300, 1024, 10.0);
@@ -1,19 +1,20 @@
+use std::sync::Arc;
+
 use super::term_scorer::TermScorer;
 use crate::core::SegmentReader;
 use crate::docset::{DocSet, BUFFER_LEN};
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::SegmentPostings;
-use crate::query::bm25::Bm25Weight;
 use crate::query::explanation::does_not_match;
-use crate::query::weight::{for_each_docset_buffered, for_each_scorer};
-use crate::query::{Explanation, Scorer, Weight};
+use crate::query::weight::for_each_docset_buffered;
+use crate::query::{Explanation, Scorer, SimilarityWeight, Weight};
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, Term};
 
 pub struct TermWeight {
     term: Term,
     index_record_option: IndexRecordOption,
-    similarity_weight: Bm25Weight,
+    similarity_weight: Arc<dyn SimilarityWeight>,
     scoring_enabled: bool,
 }
 
@@ -52,7 +53,7 @@ impl Weight for TermWeight {
         callback: &mut dyn FnMut(DocId, Score),
     ) -> crate::Result<()> {
         let mut scorer = self.specialized_scorer(reader, 1.0)?;
-        for_each_scorer(&mut scorer, callback);
+        scorer.for_each(callback);
         Ok(())
     }
 
@@ -95,7 +96,7 @@ impl TermWeight {
     pub fn new(
         term: Term,
         index_record_option: IndexRecordOption,
-        similarity_weight: Bm25Weight,
+        similarity_weight: Arc<dyn SimilarityWeight>,
         scoring_enabled: bool,
     ) -> TermWeight {
         TermWeight {
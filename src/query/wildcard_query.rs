@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use tantivy_fst::Automaton;
+
+use crate::query::{AutomatonWeight, EnableScoring, Query, Weight};
+use crate::schema::Field;
+
+const DEFAULT_MAX_EXPANSIONS: u32 = 1_024;
+
+/// A Wildcard Query matches all of the documents
+/// containing a term matching a wildcard pattern.
+///
+/// In the pattern, `*` matches any sequence of characters, including the empty one,
+/// and `?` matches exactly one character. The other characters are matched as is:
+/// a `*`, `?` or `\` can be matched literally by escaping it with a `\`.
+///
+/// The pattern is matched against the terms of the dictionary, after tokenization:
+/// `Diar*` will not match anything on a field lowercasing its tokens.
+///
+/// The query expands into at most
+/// [`max_expansions`](WildcardQuery::set_max_expansions) terms per segment, 1024 by
+/// default: the search returns an error if the pattern matches more terms. Patterns starting with a
+/// wildcard need to go through the whole term dictionary, and can be expensive.
+///
+/// ```rust
+/// use tantivy::collector::Count;
+/// use tantivy::query::WildcardQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// {
+///     let mut index_writer = index.writer(15_000_000)?;
+///     index_writer.add_document(doc!(
+///         title => "The Name of the Wind",
+///     ))?;
+///     index_writer.add_document(doc!(
+///         title => "The Diary of Muadib",
+///     ))?;
+///     index_writer.add_document(doc!(
+///         title => "A Dairy Cow",
+///     ))?;
+///     index_writer.add_document(doc!(
+///         title => "The Diary of a Young Girl",
+///     ))?;
+///     index_writer.commit()?;
+/// }
+///
+/// let reader = index.reader()?;
+/// let searcher = reader.searcher();
+///
+/// let query = WildcardQuery::new("d??ry", title);
+/// assert_eq!(searcher.search(&query, &Count)?, 3);
+/// let query = WildcardQuery::new("w*", title);
+/// assert_eq!(searcher.search(&query, &Count)?, 1);
+/// Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct WildcardQuery {
+    pattern: String,
+    field: Field,
+    automaton: Arc<WildcardAutomaton>,
+    max_expansions: u32,
+}
+
+impl WildcardQuery {
+    /// Creates a new WildcardQuery from a given pattern.
+    pub fn new(pattern: &str, field: Field) -> WildcardQuery {
+        WildcardQuery {
+            pattern: pattern.to_string(),
+            field,
+            automaton: Arc::new(WildcardAutomaton::new(pattern)),
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    /// Maximum number of terms to which the pattern will expand, in each segment.
+    ///
+    /// Searching a segment where the pattern matches more terms returns an error, rather than
+    /// silently ignoring some of the terms.
+    pub fn set_max_expansions(&mut self, value: u32) {
+        self.max_expansions = value;
+    }
+
+    /// The [`Field`] this `WildcardQuery` is targeting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// The wildcard pattern.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn specialized_weight(&self) -> AutomatonWeight<WildcardAutomaton> {
+        AutomatonWeight::new(self.field, self.automaton.clone())
+            .limit_expansions(self.max_expansions)
+    }
+}
+
+impl Query for WildcardQuery {
+    fn weight(&self, _enabled_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        Ok(Box::new(self.specialized_weight()))
+    }
+}
+
+/// Returns true if the pattern starts with a `*` or a `?` wildcard.
+pub(crate) fn has_leading_wildcard(pattern: &str) -> bool {
+    pattern.starts_with(['*', '?'])
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WildcardToken {
+    /// A byte of the pattern, matched as is.
+    Byte(u8),
+    /// `?`, matching exactly one character.
+    AnyChar,
+    /// `*`, matching any sequence of characters.
+    AnyString,
+}
+
+/// Automaton matching the terms of a wildcard pattern.
+///
+/// Its states are the sorted set of the positions reached in the pattern. A position
+/// is the ordinal of the next token to match, along with the number of bytes left to
+/// complete the character matched by a `?`.
+#[derive(Debug)]
+struct WildcardAutomaton {
+    tokens: Vec<WildcardToken>,
+}
+
+impl WildcardAutomaton {
+    fn new(pattern: &str) -> WildcardAutomaton {
+        let mut tokens = Vec::new();
+        let mut bytes = pattern.bytes();
+        while let Some(byte) = bytes.next() {
+            let token = match byte {
+                b'*' if tokens.last() == Some(&WildcardToken::AnyString) => continue,
+                b'*' => WildcardToken::AnyString,
+                b'?' => WildcardToken::AnyChar,
+                // a trailing `\` is matched literally.
+                b'\\' => WildcardToken::Byte(bytes.next().unwrap_or(b'\\')),
+                _ => WildcardToken::Byte(byte),
+            };
+            tokens.push(token);
+        }
+        WildcardAutomaton { tokens }
+    }
+
+    /// Adds the position before the token `token_ord` to the state, along with the positions
+    /// reachable from it without consuming any byte.
+    fn push_position(&self, state: &mut Vec<(usize, u8)>, mut token_ord: usize) {
+        loop {
+            state.push((token_ord, 0));
+            if self.tokens.get(token_ord) != Some(&WildcardToken::AnyString) {
+                return;
+            }
+            // `*` can match the empty string.
+            token_ord += 1;
+        }
+    }
+}
+
+/// Number of continuation bytes following the first byte of an UTF-8 encoded character,
+/// or `None` if `byte` is a continuation byte.
+fn num_continuation_bytes(byte: u8) -> Option<u8> {
+    match byte {
+        0x00..=0x7F => Some(0),
+        0x80..=0xBF => None,
+        0xC0..=0xDF => Some(1),
+        0xE0..=0xEF => Some(2),
+        0xF0..=0xFF => Some(3),
+    }
+}
+
+impl Automaton for WildcardAutomaton {
+    type State = Vec<(usize, u8)>;
+
+    fn start(&self) -> Self::State {
+        let mut state = Vec::new();
+        self.push_position(&mut state, 0);
+        state
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        state.contains(&(self.tokens.len(), 0))
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        !state.is_empty()
+    }
+
+    fn will_always_match(&self, state: &Self::State) -> bool {
+        self.tokens.last() == Some(&WildcardToken::AnyString)
+            && state.contains(&(self.tokens.len() - 1, 0))
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        let mut next_state = Vec::with_capacity(state.len());
+        for &(token_ord, remaining_bytes) in state {
+            if remaining_bytes == 1 {
+                self.push_position(&mut next_state, token_ord + 1);
+                continue;
+            }
+            if remaining_bytes > 1 {
+                next_state.push((token_ord, remaining_bytes - 1));
+                continue;
+            }
+            match self.tokens.get(token_ord) {
+                Some(WildcardToken::Byte(expected)) if *expected == byte => {
+                    self.push_position(&mut next_state, token_ord + 1);
+                }
+                // `?` must start on a character boundary.
+                Some(WildcardToken::AnyChar) => match num_continuation_bytes(byte) {
+                    Some(0) => self.push_position(&mut next_state, token_ord + 1),
+                    Some(num_bytes) => next_state.push((token_ord, num_bytes)),
+                    None => {}
+                },
+                Some(WildcardToken::AnyString) => self.push_position(&mut next_state, token_ord),
+                _ => {}
+            }
+        }
+        next_state.sort_unstable();
+        next_state.dedup();
+        next_state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tantivy_fst::Automaton;
+
+    use super::{WildcardAutomaton, WildcardQuery};
+    use crate::collector::{Count, TopDocs};
+    use crate::schema::{Field, Schema, STRING};
+    use crate::{assert_nearly_equals, Index, IndexReader};
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        let automaton = WildcardAutomaton::new(pattern);
+        let mut state = automaton.start();
+        for &byte in text.as_bytes() {
+            state = automaton.accept(&state, byte);
+        }
+        automaton.is_match(&state)
+    }
+
+    #[test]
+    fn test_wildcard_automaton() {
+        assert!(matches("abc", "abc"));
+        assert!(!matches("abc", "abcd"));
+        assert!(matches("ab*", "ab"));
+        assert!(matches("ab*", "abcd"));
+        assert!(!matches("ab*", "a"));
+        assert!(matches("*cd", "abcd"));
+        assert!(matches("a*d", "abcd"));
+        assert!(matches("a**d", "ad"));
+        assert!(!matches("a*d", "abcde"));
+        assert!(matches("a*b*a", "abba"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(!matches("a?c", "abbc"));
+        assert!(matches("?", "é"));
+        assert!(matches("?", "€"));
+        assert!(!matches("??", "€"));
+        assert!(!matches("*??", "€"));
+        assert!(matches("*??", "a€"));
+        assert!(matches("caf?", "café"));
+        assert!(matches(r"a\*c", "a*c"));
+        assert!(!matches(r"a\*c", "abc"));
+        assert!(matches(r"a\?", "a?"));
+        assert!(matches(r"a\\*", r"a\bc"));
+        assert!(matches("a\\", "a\\"));
+        assert!(matches("*", ""));
+    }
+
+    fn build_test_index() -> crate::Result<(IndexReader, Field)> {
+        let mut schema_builder = Schema::builder();
+        let country_field = schema_builder.add_text_field("country", STRING);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            for country in ["japan", "jamaica", "korea", "kenya", "jordan"] {
+                index_writer.add_document(doc!(country_field => country))?;
+            }
+            index_writer.commit()?;
+        }
+        let reader = index.reader()?;
+        Ok((reader, country_field))
+    }
+
+    #[test]
+    fn test_wildcard_query() -> crate::Result<()> {
+        let (reader, field) = build_test_index()?;
+        let searcher = reader.searcher();
+        let count = |pattern: &str| searcher.search(&WildcardQuery::new(pattern, field), &Count);
+        assert_eq!(count("ja*")?, 2);
+        assert_eq!(count("j*a*")?, 3);
+        assert_eq!(count("*a")?, 3);
+        assert_eq!(count("k?n?a")?, 1);
+        assert_eq!(count("?????")?, 3);
+        assert_eq!(count("*")?, 5);
+        assert_eq!(count("japan")?, 1);
+        assert_eq!(count("z*")?, 0);
+        let top_docs =
+            searcher.search(&WildcardQuery::new("ko*", field), &TopDocs::with_limit(2))?;
+        assert_eq!(top_docs.len(), 1);
+        assert_nearly_equals!(top_docs[0].0, 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard_query_max_expansions() -> crate::Result<()> {
+        let (reader, field) = build_test_index()?;
+        let searcher = reader.searcher();
+        let mut query = WildcardQuery::new("j*", field);
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        query.set_max_expansions(3);
+        assert_eq!(searcher.search(&query, &Count)?, 3);
+        query.set_max_expansions(2);
+        let err = searcher.search(&query, &Count).unwrap_err();
+        assert!(
+            err.to_string().contains("matches more than 2 terms"),
+            "{err}"
+        );
+        Ok(())
+    }
+}
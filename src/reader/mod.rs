@@ -1,3 +1,4 @@
+mod point_in_time;
 mod warming;
 /// Store for multi parts statistics info.
 pub mod multi_parts_statistics;
@@ -5,10 +6,13 @@ pub mod multi_parts_statistics;
 use std::convert::TryInto;
 use std::sync::atomic::AtomicU64;
 use std::sync::{atomic, Arc, Weak};
+use std::time::Duration;
 
 use arc_swap::ArcSwap;
+pub use point_in_time::PointInTimeId;
 pub use warming::Warmer;
 
+use self::point_in_time::PointInTimeRegistry;
 use self::warming::WarmingState;
 use crate::core::searcher::{SearcherGeneration, SearcherInner};
 use crate::directory::{Directory, WatchCallback, WatchHandle, META_LOCK};
@@ -162,6 +166,7 @@ struct InnerIndexReader {
     searcher: arc_swap::ArcSwap<SearcherInner>,
     searcher_generation_counter: Arc<AtomicU64>,
     searcher_generation_inventory: Inventory<SearcherGeneration>,
+    points_in_time: PointInTimeRegistry,
 }
 
 impl InnerIndexReader {
@@ -189,6 +194,7 @@ impl InnerIndexReader {
             searcher: ArcSwap::from(searcher),
             searcher_generation_counter,
             searcher_generation_inventory,
+            points_in_time: PointInTimeRegistry::default(),
         })
     }
     /// Opens the freshest segments [`SegmentReader`].
@@ -254,6 +260,7 @@ impl InnerIndexReader {
         )?;
 
         self.searcher.store(searcher);
+        self.points_in_time.purge_expired();
 
         Ok(())
     }
@@ -304,4 +311,35 @@ impl IndexReader {
     pub fn searcher(&self) -> Searcher {
         self.inner.searcher()
     }
+
+    /// Opens a point in time on the current searcher, and returns its id.
+    ///
+    /// The searcher of a point in time, returned by [`IndexReader::point_in_time`],
+    /// keeps searching the same snapshot of the index regardless of reloads, commits
+    /// and merges. This makes it possible to paginate consistently through results
+    /// across several requests.
+    ///
+    /// The files of its segments are protected from garbage collection by the
+    /// [`IndexWriter`](crate::IndexWriter) of the same `Index`, until the point in time
+    /// is closed or expires. It expires once it has not been accessed for `keep_alive`.
+    pub fn open_point_in_time(&self, keep_alive: Duration) -> PointInTimeId {
+        self.inner
+            .points_in_time
+            .open(self.inner.searcher(), keep_alive)
+    }
+
+    /// Returns the searcher of a point in time, or `None` if the point in time
+    /// was closed or has expired.
+    ///
+    /// Accessing a point in time extends its keep-alive.
+    pub fn point_in_time(&self, point_in_time_id: PointInTimeId) -> Option<Searcher> {
+        self.inner.points_in_time.get(point_in_time_id)
+    }
+
+    /// Closes a point in time, releasing its searcher and the files of its segments.
+    ///
+    /// Returns `false` if the point in time was already closed or has expired.
+    pub fn close_point_in_time(&self, point_in_time_id: PointInTimeId) -> bool {
+        self.inner.points_in_time.close(point_in_time_id)
+    }
 }
@@ -1,12 +1,80 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
 
-use crate::{schema::Field, Term};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use common::{BinarySerializable, VInt};
+use thiserror::Error;
+
+use crate::query::{Bm25StatisticsProvider, Query};
+use crate::schema::{Field, Type};
+use crate::{Searcher, Term};
+
+/// Version of the binary format written by [`MultiPartsStatistics::to_bytes`].
+const MULTI_PARTS_STATISTICS_FORMAT_VERSION: u8 = 1;
+
+/// Error returned when the statistics required for scoring are missing.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MultiPartsStatisticsError {
+    /// The number of tokens of the field was not collected.
+    #[error("No token count was collected for field {0:?}")]
+    MissingField(Field),
+    /// The document frequency of the term was not collected.
+    #[error("No document frequency was collected for term {0:?}")]
+    MissingTerm(Term),
+}
 
 /// A structure for storing statistics related to multiple parts of a document collection.
-/// 
-/// MyScale creates a separate tantivy index for each data part of a table. 
-/// MultiPartsStatistics allows MyScale to accurately calculate bm25 scores across multiple data parts.
-#[derive(Debug, Clone)]
+///
+/// MyScale creates a separate tantivy index for each data part of a table.
+/// MultiPartsStatistics allows MyScale to accurately calculate bm25 scores across multiple data
+/// parts.
+///
+/// The statistics of a part are collected with [`MultiPartsStatistics::for_query`] (or
+/// [`MultiPartsStatistics::from_searcher`]), shipped with [`MultiPartsStatistics::to_bytes`] or
+/// serde, combined with [`MultiPartsStatistics::merge`], and finally installed on every searcher
+/// with [`Searcher::update_multi_parts_statistics`].
+///
+/// ```rust
+/// use tantivy::query::QueryParser;
+/// use tantivy::reader::multi_parts_statistics::MultiPartsStatistics;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index};
+/// # fn test() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let part_1 = Index::create_in_ram(schema.clone());
+/// let part_2 = Index::create_in_ram(schema);
+/// let mut index_writer = part_1.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+/// index_writer.commit()?;
+/// let mut index_writer = part_2.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+/// index_writer.commit()?;
+///
+/// let query = QueryParser::for_index(&part_1, vec![title]).parse_query("diary")?;
+/// let mut statistics = MultiPartsStatistics::default();
+/// for part in [&part_1, &part_2] {
+///     let searcher = part.reader()?.searcher();
+///     let part_statistics = MultiPartsStatistics::for_query(&searcher, query.as_ref())?;
+///     let bytes = part_statistics.to_bytes();
+///     statistics.merge(&MultiPartsStatistics::from_bytes(&bytes)?);
+/// }
+/// assert_eq!(statistics.total_num_docs(), 2);
+/// assert_eq!(statistics.total_num_tokens(&title)?, 10);
+///
+/// let mut searcher = part_1.reader()?.searcher();
+/// searcher.update_multi_parts_statistics(statistics)?;
+/// assert_eq!(searcher.doc_freq(&tantivy::Term::from_field_text(title, "diary"))?, 2);
+/// # Ok(())
+/// # }
+/// # assert!(test().is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(into = "SerializedMultiPartsStatistics")]
+#[serde(try_from = "SerializedMultiPartsStatistics")]
 pub struct MultiPartsStatistics {
     /// Maps each term to its document frequency (number of documents containing the term)
     pub doc_freq_map: HashMap<Term, u64>,
@@ -15,6 +83,7 @@ pub struct MultiPartsStatistics {
     /// Records total number of documents in the collection.
     pub total_num_docs: u64,
 }
+
 impl MultiPartsStatistics {
     /// Constructs a new `MultiPartsStatistics`.
     ///
@@ -25,7 +94,11 @@ impl MultiPartsStatistics {
     ///
     /// # Returns
     /// A new instance of `MultiPartsStatistics`.
-    pub fn new(doc_freq_map: HashMap<Term, u64>, total_num_tokens: HashMap<Field, u64>, total_num_docs: u64) -> Self {
+    pub fn new(
+        doc_freq_map: HashMap<Term, u64>,
+        total_num_tokens: HashMap<Field, u64>,
+        total_num_docs: u64,
+    ) -> Self {
         Self {
             doc_freq_map,
             total_num_tokens,
@@ -33,6 +106,90 @@ impl MultiPartsStatistics {
         }
     }
 
+    /// Collects the statistics of the data part held by `searcher`.
+    ///
+    /// The document frequencies of `terms` are collected, as well as the number
+    /// of tokens of each of the fields of `terms`.
+    ///
+    /// The statistics are always computed from the segments of the searcher:
+    /// statistics previously installed with [`Searcher::update_multi_parts_statistics`]
+    /// are ignored.
+    pub fn from_searcher<'a>(
+        searcher: &Searcher,
+        terms: impl IntoIterator<Item = &'a Term>,
+    ) -> crate::Result<Self> {
+        let terms: BTreeSet<&Term> = terms.into_iter().collect();
+        let fields: BTreeSet<Field> = terms.iter().map(|term| term.field()).collect();
+        let mut statistics = MultiPartsStatistics {
+            total_num_docs: searcher
+                .segment_readers()
+                .iter()
+                .map(|segment_reader| u64::from(segment_reader.max_doc()))
+                .sum(),
+            ..Default::default()
+        };
+        for field in fields {
+            let mut total_num_tokens = 0u64;
+            for segment_reader in searcher.segment_readers() {
+                total_num_tokens += segment_reader.inverted_index(field)?.total_num_tokens();
+            }
+            statistics.total_num_tokens.insert(field, total_num_tokens);
+        }
+        for term in terms {
+            let doc_freq = searcher.local_doc_freq(term)?;
+            statistics.doc_freq_map.insert(term.clone(), doc_freq);
+        }
+        Ok(statistics)
+    }
+
+    /// Collects the statistics of the data part held by `searcher`, restricted
+    /// to the terms reported by [`Query::query_terms`].
+    ///
+    /// Queries expanding their terms at search time do not report them. Fuzzy, regex,
+    /// wildcard and exists queries give constant scores and need no statistics. The terms of
+    /// other expanding queries (e.g. [`MoreLikeThisQuery`](crate::query::MoreLikeThisQuery))
+    /// fall back to the document frequencies of the part they are scored on, but their fields
+    /// must have been collected: otherwise, scoring fails with
+    /// [`MultiPartsStatisticsError::MissingField`].
+    pub fn for_query(searcher: &Searcher, query: &dyn Query) -> crate::Result<Self> {
+        let mut terms: BTreeSet<&Term> = BTreeSet::new();
+        query.query_terms(&mut |term, _| {
+            terms.insert(term);
+        });
+        Self::from_searcher(searcher, terms)
+    }
+
+    /// Merges the statistics of another set of parts into `self`.
+    ///
+    /// Document frequencies, token counts and document counts are summed, so merging
+    /// is associative and commutative: parts can be merged in any order or grouping.
+    pub fn merge(&mut self, other: &MultiPartsStatistics) {
+        for (term, doc_freq) in &other.doc_freq_map {
+            *self.doc_freq_map.entry(term.clone()).or_insert(0) += doc_freq;
+        }
+        for (field, num_tokens) in &other.total_num_tokens {
+            *self.total_num_tokens.entry(*field).or_insert(0) += num_tokens;
+        }
+        self.total_num_docs += other.total_num_docs;
+    }
+
+    /// Serializes the statistics into a compact binary format.
+    ///
+    /// The output does not depend on the iteration order of the underlying maps,
+    /// so identical statistics always serialize to identical bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)
+            .expect("Writing to a Vec should never fail");
+        buffer
+    }
+
+    /// Deserializes statistics written by [`MultiPartsStatistics::to_bytes`].
+    pub fn from_bytes(mut bytes: &[u8]) -> crate::Result<Self> {
+        let statistics = Self::deserialize(&mut bytes)?;
+        Ok(statistics)
+    }
+
     /// Returns the document frequency of a specific term.
     ///
     /// # Parameters
@@ -41,8 +198,7 @@ impl MultiPartsStatistics {
     /// # Returns
     /// The document frequency of the given term. Returns `0` if the term is not found.
     pub fn doc_freq(&self, term: &Term) -> u64 {
-        let res = self.doc_freq_map.get(term);
-        res.unwrap_or(&0).clone()
+        self.doc_freq_map.get(term).copied().unwrap_or(0)
     }
 
     /// Retrieves the total number of tokens for a specified field.
@@ -50,19 +206,17 @@ impl MultiPartsStatistics {
     /// # Parameters
     /// - `field`: A reference to the field for which the token count is needed.
     ///
-    /// # Panics
-    /// This method will panic if the field does not exist in the map.
+    /// # Errors
+    /// Returns [`MultiPartsStatisticsError::MissingField`] if the token count of the field was
+    /// not collected.
     ///
     /// # Returns
     /// The total number of tokens for the field.
-    pub fn total_num_tokens(&self, field: &Field) -> u64 {
-        let nums = self.total_num_tokens.get(field);
-        // assert!(nums.is_some(), "Bm25 requires total_num_tokens not none for field:{:?}", field);
-        if nums.is_some() {
-            nums.unwrap().clone()
-        } else {
-            0u64
-        }
+    pub fn total_num_tokens(&self, field: &Field) -> Result<u64, MultiPartsStatisticsError> {
+        self.total_num_tokens
+            .get(field)
+            .copied()
+            .ok_or(MultiPartsStatisticsError::MissingField(*field))
     }
 
     /// Returns the total number of documents in the collection.
@@ -72,5 +226,626 @@ impl MultiPartsStatistics {
     pub fn total_num_docs(&self) -> u64 {
         self.total_num_docs
     }
+
+    fn sorted_num_tokens(&self) -> Vec<(Field, u64)> {
+        let mut total_num_tokens: Vec<(Field, u64)> = self
+            .total_num_tokens
+            .iter()
+            .map(|(field, num_tokens)| (*field, *num_tokens))
+            .collect();
+        total_num_tokens.sort_unstable();
+        total_num_tokens
+    }
+
+    fn sorted_doc_freqs(&self) -> Vec<(&Term, u64)> {
+        let mut doc_freqs: Vec<(&Term, u64)> = self
+            .doc_freq_map
+            .iter()
+            .map(|(term, doc_freq)| (term, *doc_freq))
+            .collect();
+        doc_freqs.sort_unstable();
+        doc_freqs
+    }
 }
 
+/// Scores queries with the merged statistics of all of the parts only.
+///
+/// Scoring a term whose document frequency was not collected fails with
+/// [`MultiPartsStatisticsError::MissingTerm`]. Use
+/// [`Searcher::search_with_global_statistics`] to fall back to the statistics of the searcher.
+impl Bm25StatisticsProvider for MultiPartsStatistics {
+    fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        Ok(MultiPartsStatistics::total_num_tokens(self, &field)?)
+    }
+
+    fn total_num_docs(&self) -> crate::Result<u64> {
+        Ok(self.total_num_docs)
+    }
+
+    fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        let doc_freq = self
+            .doc_freq_map
+            .get(term)
+            .copied()
+            .ok_or_else(|| MultiPartsStatisticsError::MissingTerm(term.clone()))?;
+        Ok(doc_freq)
+    }
+
+    // `self` already answers with the statistics of all of the parts.
+    fn get_multi_parts_statistics(&self) -> Option<MultiPartsStatistics> {
+        None
+    }
+}
+
+/// The merged statistics of all of the parts, used by
+/// [`Searcher::search_with_global_statistics`].
+///
+/// The terms whose document frequency was not collected, e.g. the terms of expanding
+/// queries, are scored with their document frequency in `searcher`.
+pub(crate) struct GlobalStatistics<'a> {
+    pub statistics: &'a MultiPartsStatistics,
+    pub searcher: &'a Searcher,
+}
+
+impl Bm25StatisticsProvider for GlobalStatistics<'_> {
+    fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        Ok(self.statistics.total_num_tokens(&field)?)
+    }
+
+    fn total_num_docs(&self) -> crate::Result<u64> {
+        Ok(self.statistics.total_num_docs)
+    }
+
+    fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        if let Some(&doc_freq) = self.statistics.doc_freq_map.get(term) {
+            return Ok(doc_freq);
+        }
+        self.searcher.local_doc_freq(term)
+    }
+
+    fn get_multi_parts_statistics(&self) -> Option<MultiPartsStatistics> {
+        None
+    }
+}
+
+/// Checks that the serialized value of a term starts with a valid type code.
+fn term_from_value_bytes(field: Field, value_bytes: &[u8]) -> Result<Term, String> {
+    let type_code = *value_bytes
+        .first()
+        .ok_or_else(|| "Term value is empty".to_string())?;
+    if Type::from_code(type_code).is_none() {
+        return Err(format!("Invalid term type code {type_code}"));
+    }
+    let mut term_bytes = Vec::with_capacity(4 + value_bytes.len());
+    term_bytes.extend_from_slice(&field.field_id().to_be_bytes());
+    term_bytes.extend_from_slice(value_bytes);
+    Ok(Term::wrap(term_bytes))
+}
+
+/// Binary format:
+/// `[version: u8][num docs: u64][num fields: vint]([field: u32][num tokens: u64])*`
+/// `[num terms: vint]([field: u32][term value: bytes][doc freq: u64])*`
+///
+/// Fields and terms are sorted.
+impl BinarySerializable for MultiPartsStatistics {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        MULTI_PARTS_STATISTICS_FORMAT_VERSION.serialize(writer)?;
+        self.total_num_docs.serialize(writer)?;
+        let total_num_tokens = self.sorted_num_tokens();
+        VInt(total_num_tokens.len() as u64).serialize(writer)?;
+        for (field, num_tokens) in total_num_tokens {
+            field.serialize(writer)?;
+            num_tokens.serialize(writer)?;
+        }
+        let doc_freqs = self.sorted_doc_freqs();
+        VInt(doc_freqs.len() as u64).serialize(writer)?;
+        for (term, doc_freq) in doc_freqs {
+            term.field().serialize(writer)?;
+            let value = term.value();
+            let value_bytes = value.as_serialized();
+            VInt(value_bytes.len() as u64).serialize(writer)?;
+            writer.write_all(value_bytes)?;
+            doc_freq.serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let version = u8::deserialize(reader)?;
+        if version != MULTI_PARTS_STATISTICS_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported multi parts statistics format version {version}"),
+            ));
+        }
+        let total_num_docs = u64::deserialize(reader)?;
+        let num_fields = VInt::deserialize(reader)?.0;
+        let mut total_num_tokens = HashMap::new();
+        for _ in 0..num_fields {
+            let field = Field::deserialize(reader)?;
+            let num_tokens = u64::deserialize(reader)?;
+            total_num_tokens.insert(field, num_tokens);
+        }
+        let num_terms = VInt::deserialize(reader)?.0;
+        let mut doc_freq_map = HashMap::new();
+        for _ in 0..num_terms {
+            let field = Field::deserialize(reader)?;
+            let num_bytes = VInt::deserialize(reader)?.0;
+            // The length is not trusted to size the buffer, the payload may be corrupted.
+            let mut value_bytes = Vec::new();
+            reader.take(num_bytes).read_to_end(&mut value_bytes)?;
+            if value_bytes.len() as u64 != num_bytes {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "Term value of {num_bytes} bytes is truncated to {} bytes",
+                        value_bytes.len()
+                    ),
+                ));
+            }
+            let term = term_from_value_bytes(field, &value_bytes)
+                .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+            let doc_freq = u64::deserialize(reader)?;
+            doc_freq_map.insert(term, doc_freq);
+        }
+        Ok(MultiPartsStatistics {
+            doc_freq_map,
+            total_num_tokens,
+            total_num_docs,
+        })
+    }
+}
+
+/// JSON representation of [`MultiPartsStatistics`].
+///
+/// Term values are base64 encoded, and entries are sorted.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedMultiPartsStatistics {
+    total_num_docs: u64,
+    total_num_tokens: Vec<SerializedNumTokens>,
+    doc_freqs: Vec<SerializedDocFreq>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedNumTokens {
+    field: Field,
+    num_tokens: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedDocFreq {
+    field: Field,
+    value: String,
+    doc_freq: u64,
+}
+
+impl From<MultiPartsStatistics> for SerializedMultiPartsStatistics {
+    fn from(statistics: MultiPartsStatistics) -> Self {
+        SerializedMultiPartsStatistics {
+            total_num_docs: statistics.total_num_docs,
+            total_num_tokens: statistics
+                .sorted_num_tokens()
+                .into_iter()
+                .map(|(field, num_tokens)| SerializedNumTokens { field, num_tokens })
+                .collect(),
+            doc_freqs: statistics
+                .sorted_doc_freqs()
+                .into_iter()
+                .map(|(term, doc_freq)| SerializedDocFreq {
+                    field: term.field(),
+                    value: BASE64.encode(term.value().as_serialized()),
+                    doc_freq,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SerializedMultiPartsStatistics> for MultiPartsStatistics {
+    type Error = String;
+
+    fn try_from(serialized: SerializedMultiPartsStatistics) -> Result<Self, String> {
+        let mut doc_freq_map = HashMap::new();
+        for serialized_doc_freq in serialized.doc_freqs {
+            let value_bytes = BASE64
+                .decode(&serialized_doc_freq.value)
+                .map_err(|err| format!("Invalid base64 term value: {err}"))?;
+            let term = term_from_value_bytes(serialized_doc_freq.field, &value_bytes)?;
+            doc_freq_map.insert(term, serialized_doc_freq.doc_freq);
+        }
+        let total_num_tokens = serialized
+            .total_num_tokens
+            .into_iter()
+            .map(|num_tokens| (num_tokens.field, num_tokens.num_tokens))
+            .collect();
+        Ok(MultiPartsStatistics {
+            doc_freq_map,
+            total_num_tokens,
+            total_num_docs: serialized.total_num_docs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{BinarySerializable, VInt};
+
+    use super::{MultiPartsStatistics, MultiPartsStatisticsError};
+    use crate::collector::TopDocs;
+    use crate::query::{Query, QueryParser, TermQuery};
+    use crate::schema::{Field, IndexRecordOption, Schema, INDEXED, STORED, TEXT};
+    use crate::{assert_nearly_equals, Index, Score, Searcher, Term};
+
+    fn create_part(texts: &[&str]) -> crate::Result<Searcher> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        schema_builder.add_u64_field("num", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for (i, doc_text) in texts.iter().enumerate() {
+            index_writer.add_document(doc!(text => *doc_text))?;
+            // Create several segments.
+            if i % 2 == 1 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.commit()?;
+        Ok(index.reader()?.searcher())
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_for_query() -> crate::Result<()> {
+        let searcher = create_part(&["a b", "a c c", "d"])?;
+        let schema = searcher.schema();
+        let text = schema.get_field("text").unwrap();
+        let num = schema.get_field("num").unwrap();
+        let query = QueryParser::for_index(searcher.index(), vec![text]).parse_query("a c")?;
+        let statistics = MultiPartsStatistics::for_query(&searcher, query.as_ref())?;
+        assert_eq!(statistics.total_num_docs(), 3);
+        assert_eq!(statistics.total_num_tokens(&text), Ok(6));
+        assert_eq!(
+            statistics.total_num_tokens(&num),
+            Err(MultiPartsStatisticsError::MissingField(num))
+        );
+        assert_eq!(statistics.doc_freq_map.len(), 2);
+        assert_eq!(statistics.doc_freq(&Term::from_field_text(text, "a")), 2);
+        assert_eq!(statistics.doc_freq(&Term::from_field_text(text, "c")), 1);
+        assert_eq!(statistics.doc_freq(&Term::from_field_text(text, "b")), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_ignore_installed_statistics() -> crate::Result<()> {
+        let mut searcher = create_part(&["a b"])?;
+        let text = searcher.schema().get_field("text").unwrap();
+        let term = Term::from_field_text(text, "a");
+        let local_statistics = MultiPartsStatistics::from_searcher(&searcher, [&term])?;
+        let mut global_statistics = local_statistics.clone();
+        global_statistics.merge(&local_statistics);
+        searcher.update_multi_parts_statistics(global_statistics)?;
+        assert_eq!(searcher.doc_freq(&term)?, 2);
+        assert_eq!(
+            MultiPartsStatistics::from_searcher(&searcher, [&term])?,
+            local_statistics
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_merge_is_associative() -> crate::Result<()> {
+        let parts = [
+            create_part(&["a b", "a"])?,
+            create_part(&["b c", "c c", "a"])?,
+            create_part(&["c"])?,
+        ];
+        let text = parts[0].schema().get_field("text").unwrap();
+        let terms = [
+            Term::from_field_text(text, "a"),
+            Term::from_field_text(text, "b"),
+            Term::from_field_text(text, "c"),
+        ];
+        let statistics: Vec<MultiPartsStatistics> = parts
+            .iter()
+            .map(|part| MultiPartsStatistics::from_searcher(part, &terms))
+            .collect::<crate::Result<_>>()?;
+        let mut left = statistics[0].clone();
+        left.merge(&statistics[1]);
+        left.merge(&statistics[2]);
+        let mut right = statistics[1].clone();
+        right.merge(&statistics[2]);
+        let mut right_merged = statistics[0].clone();
+        right_merged.merge(&right);
+        assert_eq!(left, right_merged);
+        let mut merged = MultiPartsStatistics::default();
+        for part_statistics in statistics.iter().rev() {
+            merged.merge(part_statistics);
+        }
+        assert_eq!(left, merged);
+        assert_eq!(merged.total_num_docs(), 6);
+        assert_eq!(merged.total_num_tokens(&text), Ok(9));
+        assert_eq!(merged.doc_freq(&terms[0]), 3);
+        assert_eq!(merged.doc_freq(&terms[1]), 2);
+        assert_eq!(merged.doc_freq(&terms[2]), 3);
+        Ok(())
+    }
+
+    /// Returns the score of each document matching the query, by document text.
+    fn scores_by_text(
+        searcher: &Searcher,
+        query: &dyn Query,
+    ) -> crate::Result<Vec<(String, Score)>> {
+        let text = searcher.schema().get_field("text").unwrap();
+        let mut scores = Vec::new();
+        for (score, doc_address) in searcher.search(query, &TopDocs::with_limit(10))? {
+            let doc = searcher.doc(doc_address)?;
+            let doc_text = doc
+                .get_first(text)
+                .and_then(|value| value.as_text())
+                .unwrap();
+            scores.push((doc_text.to_string(), score));
+        }
+        scores.sort_by(|left, right| left.0.cmp(&right.0));
+        Ok(scores)
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_scores() -> crate::Result<()> {
+        let part_1 = create_part(&["a b", "b", "b b"])?;
+        let part_2 = create_part(&["a", "c", "c d"])?;
+        let union = create_part(&["a b", "b", "b b", "a", "c", "c d"])?;
+        let text = union.schema().get_field("text").unwrap();
+        let query = QueryParser::for_index(union.index(), vec![text]).parse_query("a b")?;
+        let mut statistics = MultiPartsStatistics::for_query(&part_1, query.as_ref())?;
+        statistics.merge(&MultiPartsStatistics::for_query(&part_2, query.as_ref())?);
+        let mut searcher = part_1.clone();
+        searcher.update_multi_parts_statistics(statistics)?;
+
+        let local_scores = scores_by_text(&part_1, query.as_ref())?;
+        let scores = scores_by_text(&searcher, query.as_ref())?;
+        let union_scores: Vec<(String, Score)> = scores_by_text(&union, query.as_ref())?
+            .into_iter()
+            .filter(|(doc_text, _)| scores.iter().any(|(text, _)| text == doc_text))
+            .collect();
+        assert_eq!(scores.len(), 3);
+        assert_eq!(union_scores.len(), 3);
+        for ((doc_text, score), (union_doc_text, union_score)) in scores.iter().zip(&union_scores) {
+            assert_eq!(doc_text, union_doc_text);
+            assert_nearly_equals!(*score, *union_score);
+        }
+        // The statistics of the other part change the scores.
+        assert!(local_scores
+            .iter()
+            .zip(&scores)
+            .all(|((_, local_score), (_, score))| local_score != score));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_missing_field_error() -> crate::Result<()> {
+        let mut searcher = create_part(&["a b"])?;
+        let text = searcher.schema().get_field("text").unwrap();
+        searcher.update_multi_parts_statistics(MultiPartsStatistics::default())?;
+        let query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let err = searcher
+            .search(&query, &TopDocs::with_limit(1))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TantivyError::MultiPartsStatisticsError(
+                MultiPartsStatisticsError::MissingField(field)
+            ) if field == text
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_phase_search() -> crate::Result<()> {
+        use crate::collector::{Collector, Count, TopDocs};
+
+        let shards = [
+            create_part(&["a b", "b", "b b c"])?,
+            create_part(&["a", "c", "c d"])?,
+            create_part(&["b d", "d d d"])?,
+        ];
+        let union = create_part(&["a b", "b", "b b c", "a", "c", "c d", "b d", "d d d"])?;
+        let text = union.schema().get_field("text").unwrap();
+        let query = QueryParser::for_index(union.index(), vec![text]).parse_query("b d")?;
+        let mut global_statistics = MultiPartsStatistics::default();
+        for shard in &shards {
+            let statistics = shard.collect_statistics(query.as_ref())?;
+            global_statistics.merge(&MultiPartsStatistics::from_bytes(&statistics.to_bytes())?);
+        }
+        let collector = (TopDocs::with_limit(10), Count);
+        let fruits = shards
+            .iter()
+            .map(|shard| {
+                shard.search_with_global_statistics(query.as_ref(), &collector, &global_statistics)
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        let (top_docs, count) = collector.merge_fruits(fruits)?;
+        let (expected_top_docs, expected_count) = union.search(query.as_ref(), &collector)?;
+        assert_eq!(count, expected_count);
+        let scores: Vec<Score> = top_docs.iter().map(|(score, _)| *score).collect();
+        let expected_scores: Vec<Score> =
+            expected_top_docs.iter().map(|(score, _)| *score).collect();
+        assert_eq!(scores, expected_scores);
+        // The shards are left untouched.
+        assert!(shards[0].get_multi_parts_statistics().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_phase_search_missing_field() -> crate::Result<()> {
+        let searcher = create_part(&["a b"])?;
+        let text = searcher.schema().get_field("text").unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let err = searcher
+            .search_with_global_statistics(
+                &query,
+                &TopDocs::with_limit(1),
+                &MultiPartsStatistics::default(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TantivyError::MultiPartsStatisticsError(
+                MultiPartsStatisticsError::MissingField(field)
+            ) if field == text
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_two_phase_search_expanding_query() -> crate::Result<()> {
+        use crate::query::{MoreLikeThisQuery, RegexQuery};
+        use crate::schema::Value;
+
+        let shards = [
+            create_part(&["a b", "b c"])?,
+            create_part(&["c", "c d", "a"])?,
+        ];
+        let text = shards[0].schema().get_field("text").unwrap();
+        // The terms of a more like this query are only known once it is expanded on a shard.
+        let query = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(1)
+            .with_min_term_frequency(1)
+            .with_document_fields(vec![(text, vec![Value::Str("b c".to_string())])]);
+        let mut global_statistics = MultiPartsStatistics::default();
+        for shard in &shards {
+            global_statistics.merge(&shard.collect_statistics(&query)?);
+        }
+        assert!(global_statistics.doc_freq_map.is_empty());
+        let err = shards[0]
+            .search_with_global_statistics(&query, &TopDocs::with_limit(10), &global_statistics)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TantivyError::MultiPartsStatisticsError(
+                MultiPartsStatisticsError::MissingField(field)
+            ) if field == text
+        ));
+
+        // Once the field is collected, the terms that were not collected are scored with their
+        // document frequency in the shard.
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "b"),
+            IndexRecordOption::WithFreqs,
+        );
+        let mut global_statistics = MultiPartsStatistics::default();
+        for shard in &shards {
+            global_statistics.merge(&shard.collect_statistics(&term_query)?);
+        }
+        let top_docs = shards[0].search_with_global_statistics(
+            &query,
+            &TopDocs::with_limit(10),
+            &global_statistics,
+        )?;
+        assert_eq!(top_docs.len(), 2);
+        let mut searcher = shards[0].clone();
+        searcher.update_multi_parts_statistics(global_statistics.clone())?;
+        assert_eq!(searcher.search(&query, &TopDocs::with_limit(10))?, top_docs);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text, "b"))?, 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text, "c"))?, 1);
+
+        // Without a searcher to fall back to, uncollected terms are an error.
+        let err = shards[0]
+            .search_with_statistics_provider(&query, &TopDocs::with_limit(10), &global_statistics)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TantivyError::MultiPartsStatisticsError(MultiPartsStatisticsError::MissingTerm(
+                _
+            ))
+        ));
+
+        // Regex queries give constant scores and don't need any statistics.
+        let regex_query = RegexQuery::from_pattern("c.*", text)?;
+        let top_docs = shards[1].search_with_global_statistics(
+            &regex_query,
+            &TopDocs::with_limit(10),
+            &MultiPartsStatistics::default(),
+        )?;
+        assert_eq!(top_docs.len(), 2);
+        Ok(())
+    }
+
+    fn statistics_for_serialization() -> MultiPartsStatistics {
+        let mut statistics = MultiPartsStatistics::default();
+        let text = crate::schema::Field::from_field_id(0);
+        let num = crate::schema::Field::from_field_id(1);
+        for (i, word) in ["hello", "world", "", "é"].iter().enumerate() {
+            statistics
+                .doc_freq_map
+                .insert(Term::from_field_text(text, word), i as u64);
+        }
+        statistics
+            .doc_freq_map
+            .insert(Term::from_field_u64(num, 42), 7);
+        statistics.total_num_tokens.insert(text, 120);
+        statistics.total_num_tokens.insert(num, 3);
+        statistics.total_num_docs = 11;
+        statistics
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_binary_serialization() -> crate::Result<()> {
+        let statistics = statistics_for_serialization();
+        let bytes = statistics.to_bytes();
+        assert_eq!(MultiPartsStatistics::from_bytes(&bytes)?, statistics);
+        // The output is independent from the map iteration order.
+        let mut rebuilt = MultiPartsStatistics::default();
+        rebuilt.merge(&statistics);
+        assert_eq!(rebuilt.to_bytes(), bytes);
+        assert!(MultiPartsStatistics::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut invalid_version = bytes.clone();
+        invalid_version[0] = 0;
+        assert!(MultiPartsStatistics::from_bytes(&invalid_version).is_err());
+        // A corrupted term length does not allocate the announced number of bytes.
+        let mut huge_term = Vec::new();
+        1u8.serialize(&mut huge_term)?;
+        0u64.serialize(&mut huge_term)?;
+        VInt(0).serialize(&mut huge_term)?;
+        VInt(1).serialize(&mut huge_term)?;
+        Field::from_field_id(0).serialize(&mut huge_term)?;
+        VInt(1 << 50).serialize(&mut huge_term)?;
+        huge_term.extend_from_slice(b"abc");
+        let err = MultiPartsStatistics::from_bytes(&huge_term).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{err}");
+        let mut buffer = Vec::new();
+        MultiPartsStatistics::default().serialize(&mut buffer)?;
+        assert_eq!(
+            MultiPartsStatistics::deserialize(&mut &buffer[..])?,
+            MultiPartsStatistics::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_parts_statistics_json_serialization() {
+        let statistics = statistics_for_serialization();
+        let json = serde_json::to_string(&statistics).unwrap();
+        let deserialized: MultiPartsStatistics = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, statistics);
+        let mut small_statistics = MultiPartsStatistics::default();
+        small_statistics.doc_freq_map.insert(
+            Term::from_field_text(crate::schema::Field::from_field_id(0), "a"),
+            2,
+        );
+        small_statistics
+            .total_num_tokens
+            .insert(crate::schema::Field::from_field_id(0), 5);
+        small_statistics.total_num_docs = 3;
+        assert_eq!(
+            serde_json::to_string(&small_statistics).unwrap(),
+            r#"{"total_num_docs":3,"total_num_tokens":[{"field":0,"num_tokens":5}],"doc_freqs":[{"field":0,"value":"c2E=","doc_freq":2}]}"#
+        );
+        let invalid_type = r#"{"total_num_docs":3,"total_num_tokens":[],"doc_freqs":[{"field":0,"value":"eGE=","doc_freq":2}]}"#;
+        assert!(serde_json::from_str::<MultiPartsStatistics>(invalid_type).is_err());
+    }
+}
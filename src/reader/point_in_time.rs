@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::directory::FileProtection;
+use crate::Searcher;
+
+/// Identifies a point in time opened with
+/// [`IndexReader::open_point_in_time`](super::IndexReader::open_point_in_time).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PointInTimeId(u64);
+
+impl PointInTimeId {
+    /// Returns the id as a `u64`, e.g. to send it to a client.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for PointInTimeId {
+    fn from(id: u64) -> PointInTimeId {
+        PointInTimeId(id)
+    }
+}
+
+impl fmt::Display for PointInTimeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+struct PointInTime {
+    searcher: Searcher,
+    keep_alive: Duration,
+    file_protection: FileProtection,
+}
+
+/// Registry of the points in time opened on an `IndexReader`.
+///
+/// Expired points in time are purged lazily, whenever the registry is accessed.
+/// Their files are not protected from garbage collection anymore, even before
+/// they are purged.
+#[derive(Default)]
+pub(crate) struct PointInTimeRegistry {
+    id_counter: AtomicU64,
+    points_in_time: Mutex<HashMap<PointInTimeId, PointInTime>>,
+}
+
+impl PointInTimeRegistry {
+    pub fn open(&self, searcher: Searcher, keep_alive: Duration) -> PointInTimeId {
+        let files: HashSet<PathBuf> = searcher
+            .segment_readers()
+            .iter()
+            .flat_map(|segment_reader| segment_reader.list_files())
+            .collect();
+        let now = Instant::now();
+        let file_protection = searcher
+            .index()
+            .directory()
+            .protect_files(files, now + keep_alive);
+        let id = PointInTimeId(self.id_counter.fetch_add(1, Ordering::Relaxed));
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        purge_expired(&mut points_in_time, now);
+        points_in_time.insert(
+            id,
+            PointInTime {
+                searcher,
+                keep_alive,
+                file_protection,
+            },
+        );
+        id
+    }
+
+    pub fn get(&self, id: PointInTimeId) -> Option<Searcher> {
+        let now = Instant::now();
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        purge_expired(&mut points_in_time, now);
+        let point_in_time = points_in_time.get(&id)?;
+        point_in_time
+            .file_protection
+            .set_expires_at(now + point_in_time.keep_alive);
+        Some(point_in_time.searcher.clone())
+    }
+
+    pub fn close(&self, id: PointInTimeId) -> bool {
+        let mut points_in_time = self.points_in_time.lock().unwrap();
+        purge_expired(&mut points_in_time, Instant::now());
+        points_in_time.remove(&id).is_some()
+    }
+
+    pub fn purge_expired(&self) {
+        purge_expired(&mut self.points_in_time.lock().unwrap(), Instant::now());
+    }
+}
+
+fn purge_expired(points_in_time: &mut HashMap<PointInTimeId, PointInTime>, now: Instant) {
+    points_in_time.retain(|_, point_in_time| !point_in_time.file_protection.is_expired(now));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crate::collector::Count;
+    use crate::directory::Directory;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{Index, IndexWriter, ReloadPolicy, Searcher};
+
+    fn segment_files(searcher: &Searcher) -> HashSet<PathBuf> {
+        searcher
+            .segment_readers()
+            .iter()
+            .flat_map(|segment_reader| segment_reader.list_files())
+            .filter(|path| searcher.index().directory().exists(path).unwrap())
+            .collect()
+    }
+
+    fn create_index() -> crate::Result<(Index, IndexWriter)> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.add_document(doc!(text => "b"))?;
+        index_writer.commit()?;
+        Ok((index, index_writer))
+    }
+
+    #[test]
+    fn test_point_in_time_survives_reload_and_gc() -> crate::Result<()> {
+        let (index, mut index_writer) = create_index()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let pit_id = reader.open_point_in_time(Duration::from_secs(3600));
+        let pit_files = segment_files(&reader.point_in_time(pit_id).unwrap());
+        assert!(!pit_files.is_empty());
+
+        index_writer.delete_all_documents()?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().search(&AllQuery, &Count)?, 0);
+        index_writer.garbage_collect_files().wait()?;
+
+        let pit_searcher = reader.point_in_time(pit_id).unwrap();
+        assert_eq!(pit_searcher.search(&AllQuery, &Count)?, 2);
+        assert_eq!(segment_files(&pit_searcher), pit_files);
+
+        assert!(reader.close_point_in_time(pit_id));
+        assert!(!reader.close_point_in_time(pit_id));
+        assert!(reader.point_in_time(pit_id).is_none());
+        index_writer.garbage_collect_files().wait()?;
+        assert!(segment_files(&pit_searcher).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_in_time_expires() -> crate::Result<()> {
+        let (index, mut index_writer) = create_index()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let pit_id = reader.open_point_in_time(Duration::ZERO);
+        let searcher = reader.searcher();
+        assert!(!segment_files(&searcher).is_empty());
+
+        index_writer.delete_all_documents()?;
+        index_writer.commit()?;
+        // The point in time has expired but it has not been purged yet:
+        // its files are not protected anymore.
+        index_writer.garbage_collect_files().wait()?;
+        assert!(segment_files(&searcher).is_empty());
+        assert!(reader.point_in_time(pit_id).is_none());
+        assert!(!reader.close_point_in_time(pit_id));
+        Ok(())
+    }
+}
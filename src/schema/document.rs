@@ -103,6 +103,16 @@ impl Document {
         self.add_field_value(field, value);
     }
 
+    /// Add a vector field
+    pub fn add_vector(&mut self, field: Field, vector: Vec<f32>) {
+        self.add_field_value(field, Value::Vector(vector));
+    }
+
+    /// Add a geo point field
+    pub fn add_geo_point(&mut self, field: Field, geo_point: GeoPoint) {
+        self.add_field_value(field, geo_point);
+    }
+
     /// Add a i64 field
     pub fn add_i64(&mut self, field: Field, value: i64) {
         self.add_field_value(field, value);
@@ -3,8 +3,8 @@ use serde::{Deserialize, Serialize};
 use super::ip_options::IpAddrOptions;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::{
-    is_valid_field_name, DateOptions, FacetOptions, FieldType, JsonObjectOptions, NumericOptions,
-    TextOptions,
+    is_valid_field_name, DateOptions, FacetOptions, FieldType, GeoPointOptions, JsonObjectOptions,
+    NumericOptions, TextOptions, VectorOptions,
 };
 
 /// A `FieldEntry` represents a field and its configuration.
@@ -81,6 +81,16 @@ impl FieldEntry {
         Self::new(field_name, FieldType::JsonObject(json_object_options))
     }
 
+    /// Creates a field entry for a vector field
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        Self::new(field_name, FieldType::Vector(vector_options))
+    }
+
+    /// Creates a field entry for a geo point field
+    pub fn new_geo_point(field_name: String, geo_point_options: GeoPointOptions) -> FieldEntry {
+        Self::new(field_name, FieldType::GeoPoint(geo_point_options))
+    }
+
     /// Returns the name of the field
     pub fn name(&self) -> &str {
         &self.name
@@ -121,6 +131,8 @@ impl FieldEntry {
             FieldType::Bytes(ref options) => options.is_stored(),
             FieldType::JsonObject(ref options) => options.is_stored(),
             FieldType::IpAddr(ref options) => options.is_stored(),
+            FieldType::Vector(ref options) => options.is_stored(),
+            FieldType::GeoPoint(ref options) => options.is_stored(),
         }
     }
 }
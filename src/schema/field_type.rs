@@ -7,13 +7,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use thiserror::Error;
 
+use super::geo_point_options::GeoPointOptions;
 use super::ip_options::IpAddrOptions;
 use super::IntoIpv6Addr;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::facet_options::FacetOptions;
 use crate::schema::{
-    DateOptions, Facet, IndexRecordOption, JsonObjectOptions, NumericOptions, TextFieldIndexing,
-    TextOptions, Value,
+    DateOptions, Facet, GeoPoint, IndexRecordOption, JsonObjectOptions, NumericOptions,
+    TextFieldIndexing, TextOptions, Value, VectorOptions,
 };
 use crate::time::format_description::well_known::Rfc3339;
 use crate::time::OffsetDateTime;
@@ -70,9 +71,13 @@ pub enum Type {
     Json = b'j',
     /// IpAddr
     IpAddr = b'p',
+    /// Dense vector of `f32`
+    Vector = b'v',
+    /// `tantivy::schema::GeoPoint`
+    GeoPoint = b'g',
 }
 
-const ALL_TYPES: [Type; 10] = [
+const ALL_TYPES: [Type; 12] = [
     Type::Str,
     Type::U64,
     Type::I64,
@@ -83,6 +88,8 @@ const ALL_TYPES: [Type; 10] = [
     Type::Bytes,
     Type::Json,
     Type::IpAddr,
+    Type::Vector,
+    Type::GeoPoint,
 ];
 
 impl Type {
@@ -110,6 +117,8 @@ impl Type {
             Type::Bytes => "Bytes",
             Type::Json => "Json",
             Type::IpAddr => "IpAddr",
+            Type::Vector => "Vector",
+            Type::GeoPoint => "GeoPoint",
         }
     }
 
@@ -127,6 +136,8 @@ impl Type {
             b'b' => Some(Type::Bytes),
             b'j' => Some(Type::Json),
             b'p' => Some(Type::IpAddr),
+            b'v' => Some(Type::Vector),
+            b'g' => Some(Type::GeoPoint),
             _ => None,
         }
     }
@@ -159,6 +170,10 @@ pub enum FieldType {
     JsonObject(JsonObjectOptions),
     /// IpAddr field
     IpAddr(IpAddrOptions),
+    /// Dense vector field
+    Vector(VectorOptions),
+    /// Geo point field
+    GeoPoint(GeoPointOptions),
 }
 
 impl FieldType {
@@ -175,6 +190,8 @@ impl FieldType {
             FieldType::Bytes(_) => Type::Bytes,
             FieldType::JsonObject(_) => Type::Json,
             FieldType::IpAddr(_) => Type::IpAddr,
+            FieldType::Vector(_) => Type::Vector,
+            FieldType::GeoPoint(_) => Type::GeoPoint,
         }
     }
 
@@ -201,9 +218,17 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.is_indexed(),
             FieldType::JsonObject(ref json_object_options) => json_object_options.is_indexed(),
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_indexed(),
+            // Vectors are not part of the inverted index.
+            FieldType::Vector(_) => false,
+            FieldType::GeoPoint(ref geo_point_options) => geo_point_options.is_indexed(),
         }
     }
 
+    /// Returns true if this is a vector field.
+    pub fn is_vector(&self) -> bool {
+        matches!(self, FieldType::Vector(_))
+    }
+
     /// Returns the index record option for the field.
     ///
     /// If the field is not indexed, returns `None`.
@@ -238,6 +263,8 @@ impl FieldType {
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_fast(),
             FieldType::Facet(_) => true,
             FieldType::JsonObject(ref json_object_options) => json_object_options.is_fast(),
+            FieldType::Vector(_) => false,
+            FieldType::GeoPoint(ref geo_point_options) => geo_point_options.is_fast(),
         }
     }
 
@@ -257,6 +284,7 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.fieldnorms(),
             FieldType::JsonObject(ref _json_object_options) => false,
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.fieldnorms(),
+            FieldType::Vector(_) | FieldType::GeoPoint(_) => false,
         }
     }
 
@@ -308,6 +336,14 @@ impl FieldType {
                     None
                 }
             }
+            FieldType::Vector(_) => None,
+            FieldType::GeoPoint(ref geo_point_options) => {
+                if geo_point_options.is_indexed() {
+                    Some(IndexRecordOption::Basic)
+                } else {
+                    None
+                }
+            }
         }
     }
 
@@ -408,6 +444,17 @@ impl FieldType {
 
                         Ok(Value::IpAddr(ip_addr.into_ipv6_addr()))
                     }
+                    FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                        expected: "an array of numbers",
+                        json: JsonValue::String(field_text),
+                    }),
+                    FieldType::GeoPoint(_) => match GeoPoint::parse(&field_text) {
+                        Some(geo_point) => Ok(Value::GeoPoint(geo_point)),
+                        None => Err(ValueParsingError::TypeError {
+                            expected: "a geo point as \"lat,lon\"",
+                            json: JsonValue::String(field_text),
+                        }),
+                    },
                 }
             }
             JsonValue::Number(field_val_num) => match self {
@@ -467,6 +514,14 @@ impl FieldType {
                     expected: "a string with an ip addr",
                     json: JsonValue::Number(field_val_num),
                 }),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                    expected: "an array of numbers",
+                    json: JsonValue::Number(field_val_num),
+                }),
+                FieldType::GeoPoint(_) => Err(ValueParsingError::TypeError {
+                    expected: "a geo point",
+                    json: JsonValue::Number(field_val_num),
+                }),
             },
             JsonValue::Object(json_map) => match self {
                 FieldType::Str(_) => {
@@ -482,6 +537,15 @@ impl FieldType {
                     }
                 }
                 FieldType::JsonObject(_) => Ok(Value::JsonObject(json_map)),
+                FieldType::GeoPoint(_) => {
+                    match serde_json::from_value::<GeoPoint>(JsonValue::Object(json_map.clone())) {
+                        Ok(geo_point) if geo_point.is_valid() => Ok(Value::GeoPoint(geo_point)),
+                        _ => Err(ValueParsingError::TypeError {
+                            expected: "a geo point as {\"lat\": .., \"lon\": ..}",
+                            json: JsonValue::Object(json_map),
+                        }),
+                    }
+                }
                 _ => Err(ValueParsingError::TypeError {
                     expected: self.value_type().name(),
                     json: JsonValue::Object(json_map),
@@ -504,6 +568,45 @@ impl FieldType {
                     json: JsonValue::Bool(json_bool_val),
                 }),
             },
+            JsonValue::Array(json_items) => match self {
+                FieldType::Vector(vector_options) => {
+                    let vector: Option<Vec<f32>> = json_items
+                        .iter()
+                        .map(|json_item| json_item.as_f64().map(|val| val as f32))
+                        .collect();
+                    match vector {
+                        Some(vector) if vector.len() == vector_options.dims() => {
+                            Ok(Value::Vector(vector))
+                        }
+                        _ => Err(ValueParsingError::TypeError {
+                            expected: "an array of numbers with the dimension of the field",
+                            json: JsonValue::Array(json_items),
+                        }),
+                    }
+                }
+                // Following the GeoJSON convention, arrays are `[lon, lat]`.
+                FieldType::GeoPoint(_) => {
+                    let geo_point = match json_items.as_slice() {
+                        [lon, lat] => lat
+                            .as_f64()
+                            .zip(lon.as_f64())
+                            .map(|(lat, lon)| GeoPoint::new(lat, lon))
+                            .filter(GeoPoint::is_valid),
+                        _ => None,
+                    };
+                    match geo_point {
+                        Some(geo_point) => Ok(Value::GeoPoint(geo_point)),
+                        None => Err(ValueParsingError::TypeError {
+                            expected: "a geo point as [lon, lat]",
+                            json: JsonValue::Array(json_items),
+                        }),
+                    }
+                }
+                _ => Err(ValueParsingError::TypeError {
+                    expected: self.value_type().name(),
+                    json: JsonValue::Array(json_items),
+                }),
+            },
             // Could also just filter them
             JsonValue::Null => match self {
                 FieldType::Str(opt) => {
@@ -521,10 +624,6 @@ impl FieldType {
                     json: JsonValue::Null,
                 }),
             },
-            _ => Err(ValueParsingError::TypeError {
-                expected: self.value_type().name(),
-                json: json.clone(),
-            }),
         }
     }
 }
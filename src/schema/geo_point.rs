@@ -0,0 +1,293 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Mean radius of the earth, in meters.
+const EARTH_RADIUS_IN_METERS: f64 = 6_371_008.8;
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Maximum precision (in characters) of a geohash.
+pub const MAX_GEOHASH_PRECISION: usize = 12;
+
+/// Precision of the geohash cells indexed for each point of an indexed geo point field.
+///
+/// A point is indexed in its enclosing cell for each precision from 1 to this value. At
+/// precision 8, a cell covers about 38m x 19m.
+pub(crate) const INDEXED_GEOHASH_PRECISION: usize = 8;
+
+/// A point on the earth, given by its latitude and longitude in degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    /// Latitude, in `[-90, 90]`.
+    pub lat: f64,
+    /// Longitude, in `[-180, 180]`.
+    pub lon: f64,
+}
+
+impl fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.lat, self.lon)
+    }
+}
+
+fn encode_coordinate(val: f64, max: f64) -> u32 {
+    ((val + max) / (2.0 * max) * u32::MAX as f64).round() as u32
+}
+
+fn decode_coordinate(val: u32, max: f64) -> f64 {
+    val as f64 / u32::MAX as f64 * (2.0 * max) - max
+}
+
+/// Number of bits used for the longitude and the latitude in a geohash of the given
+/// precision. The bits are interleaved, starting with the longitude.
+fn num_geohash_bits(precision: usize) -> (u32, u32) {
+    let num_bits = 5 * precision as u32;
+    ((num_bits + 1) / 2, num_bits / 2)
+}
+
+/// Returns the index of the cell containing `val`, when splitting `[-max, max]` into
+/// `2^num_bits` cells.
+fn cell_index(val: f64, max: f64, num_bits: u32) -> u64 {
+    let num_cells = 1u64 << num_bits;
+    let index = ((val + max) / (2.0 * max) * num_cells as f64).floor();
+    (index.max(0.0) as u64).min(num_cells - 1)
+}
+
+/// Interleaves the bits of the longitude and latitude indexes of a cell, starting with
+/// the longitude.
+fn interleave(lon_index: u64, lat_index: u64, precision: usize) -> u64 {
+    let (lon_bits, lat_bits) = num_geohash_bits(precision);
+    let mut interleaved = 0u64;
+    for i in 0..lon_bits {
+        let bit = (lon_index >> (lon_bits - 1 - i)) & 1;
+        interleaved |= bit << (5 * precision as u32 - 1 - 2 * i);
+    }
+    for i in 0..lat_bits {
+        let bit = (lat_index >> (lat_bits - 1 - i)) & 1;
+        interleaved |= bit << (5 * precision as u32 - 2 - 2 * i);
+    }
+    interleaved
+}
+
+/// Encodes the interleaved bits of a cell, as returned by [`GeoPoint::geohash_cell`], into
+/// a geohash.
+pub(crate) fn geohash_from_cell_bits(interleaved: u64, precision: usize) -> String {
+    (0..precision)
+        .map(|char_ord| {
+            let shift = 5 * (precision - 1 - char_ord);
+            GEOHASH_ALPHABET[((interleaved >> shift) & 31) as usize] as char
+        })
+        .collect()
+}
+
+/// Builds the geohash of the cell given by its longitude and latitude indexes.
+fn geohash_from_cell(lon_index: u64, lat_index: u64, precision: usize) -> String {
+    geohash_from_cell_bits(interleave(lon_index, lat_index, precision), precision)
+}
+
+/// Returns the geohashes of the cells of the given precision intersecting the
+/// `[min_lat, max_lat] x [min_lon, max_lon]` box.
+pub(crate) fn geohash_cover(
+    min_lat: f64,
+    max_lat: f64,
+    min_lon: f64,
+    max_lon: f64,
+    precision: usize,
+) -> Vec<String> {
+    let (lon_bits, lat_bits) = num_geohash_bits(precision);
+    let lat_range = cell_index(min_lat, 90.0, lat_bits)..=cell_index(max_lat, 90.0, lat_bits);
+    let lon_range = cell_index(min_lon, 180.0, lon_bits)..=cell_index(max_lon, 180.0, lon_bits);
+    lat_range
+        .flat_map(|lat_index| {
+            lon_range
+                .clone()
+                .map(move |lon_index| geohash_from_cell(lon_index, lat_index, precision))
+        })
+        .collect()
+}
+
+/// Returns the number of cells of the given precision intersecting the
+/// `[min_lat, max_lat] x [min_lon, max_lon]` box.
+pub(crate) fn geohash_cover_len(
+    min_lat: f64,
+    max_lat: f64,
+    min_lon: f64,
+    max_lon: f64,
+    precision: usize,
+) -> u64 {
+    let (lon_bits, lat_bits) = num_geohash_bits(precision);
+    let num_lat_cells =
+        cell_index(max_lat, 90.0, lat_bits) - cell_index(min_lat, 90.0, lat_bits) + 1;
+    let num_lon_cells =
+        cell_index(max_lon, 180.0, lon_bits) - cell_index(min_lon, 180.0, lon_bits) + 1;
+    num_lat_cells * num_lon_cells
+}
+
+impl GeoPoint {
+    /// Creates a new point.
+    pub fn new(lat: f64, lon: f64) -> GeoPoint {
+        GeoPoint { lat, lon }
+    }
+
+    /// Returns true if the latitude is within `[-90, 90]` and the longitude within
+    /// `[-180, 180]`.
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lon)
+    }
+
+    /// Encodes the point as a `u64`, as stored in fast fields.
+    ///
+    /// The latitude is stored in the 32 most significant bits, the longitude in the
+    /// 32 least significant bits. The precision is below a centimeter.
+    pub fn to_u64(&self) -> u64 {
+        (u64::from(encode_coordinate(self.lat, 90.0)) << 32)
+            | u64::from(encode_coordinate(self.lon, 180.0))
+    }
+
+    /// Decodes a point encoded with [`GeoPoint::to_u64`].
+    pub fn from_u64(val: u64) -> GeoPoint {
+        GeoPoint {
+            lat: decode_coordinate((val >> 32) as u32, 90.0),
+            lon: decode_coordinate(val as u32, 180.0),
+        }
+    }
+
+    /// Returns the great-circle distance to another point, in meters,
+    /// using the haversine formula.
+    pub fn distance(&self, other: &GeoPoint) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let half_delta_lat = (lat2 - lat1) / 2.0;
+        let half_delta_lon = (other.lon - self.lon).to_radians() / 2.0;
+        let h =
+            half_delta_lat.sin().powi(2) + lat1.cos() * lat2.cos() * half_delta_lon.sin().powi(2);
+        2.0 * EARTH_RADIUS_IN_METERS * h.sqrt().min(1.0).asin()
+    }
+
+    /// Returns the geohash of the point, with `precision` characters.
+    ///
+    /// # Panics
+    /// Panics if `precision` is not within `[1, 12]`.
+    pub fn geohash(&self, precision: usize) -> String {
+        assert!(
+            (1..=MAX_GEOHASH_PRECISION).contains(&precision),
+            "Geohash precision must be within [1, {MAX_GEOHASH_PRECISION}]"
+        );
+        geohash_from_cell_bits(self.geohash_cell(precision), precision)
+    }
+
+    /// Returns the cell of the given precision containing the point, as the interleaved
+    /// bits of its geohash.
+    pub(crate) fn geohash_cell(&self, precision: usize) -> u64 {
+        let (lon_bits, lat_bits) = num_geohash_bits(precision);
+        interleave(
+            cell_index(self.lon, 180.0, lon_bits),
+            cell_index(self.lat, 90.0, lat_bits),
+            precision,
+        )
+    }
+
+    /// Parses a point given as `"lat,lon"`.
+    pub(crate) fn parse(text: &str) -> Option<GeoPoint> {
+        let (lat, lon) = text.split_once(',')?;
+        let point = GeoPoint::new(lat.trim().parse().ok()?, lon.trim().parse().ok()?);
+        Some(point).filter(GeoPoint::is_valid)
+    }
+
+    /// Returns the latitude delta, in degrees, corresponding to `distance` meters.
+    pub(crate) fn lat_delta(distance: f64) -> f64 {
+        (distance / EARTH_RADIUS_IN_METERS).to_degrees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{geohash_cover, GeoPoint};
+    use crate::schema::{Schema, FAST, STORED};
+
+    #[test]
+    fn test_geo_point_encoding() {
+        for point in [
+            GeoPoint::new(0.0, 0.0),
+            GeoPoint::new(-90.0, -180.0),
+            GeoPoint::new(90.0, 180.0),
+            GeoPoint::new(48.8566, 2.3522),
+        ] {
+            let decoded = GeoPoint::from_u64(point.to_u64());
+            assert!((decoded.lat - point.lat).abs() < 1e-7);
+            assert!((decoded.lon - point.lon).abs() < 1e-7);
+        }
+        assert!(GeoPoint::new(1.0, 0.0).to_u64() > GeoPoint::new(0.0, 100.0).to_u64());
+    }
+
+    #[test]
+    fn test_geo_point_distance() {
+        let paris = GeoPoint::new(48.8566, 2.3522);
+        let london = GeoPoint::new(51.5074, -0.1278);
+        let distance = paris.distance(&london);
+        assert!((distance - 343_500.0).abs() < 1_000.0, "{distance}");
+        assert_eq!(paris.distance(&paris), 0.0);
+    }
+
+    #[test]
+    fn test_geohash() {
+        assert_eq!(GeoPoint::new(57.64911, 10.40744).geohash(11), "u4pruydqqvj");
+        assert_eq!(GeoPoint::new(48.8566, 2.3522).geohash(5), "u09tv");
+        assert_eq!(GeoPoint::new(-90.0, -180.0).geohash(1), "0");
+        assert_eq!(GeoPoint::new(90.0, 180.0).geohash(1), "z");
+        let cover = geohash_cover(48.8, 48.9, 2.3, 2.4, 5);
+        assert!(cover.contains(&"u09tv".to_string()));
+        assert!(cover.len() < 16);
+    }
+
+    #[test]
+    fn test_geo_point_from_json() {
+        let mut schema_builder = Schema::builder();
+        let location = schema_builder.add_geo_point_field("location", STORED | FAST);
+        let schema = schema_builder.build();
+        let doc = schema
+            .parse_document(
+                r#"{"location": [{"lat": 48.5, "lon": 2.5}, "-10.5,20.0", [-170.0, 80.0]]}"#,
+            )
+            .unwrap();
+        let points: Vec<GeoPoint> = doc
+            .get_all(location)
+            .flat_map(|value| value.as_geo_point())
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                GeoPoint::new(48.5, 2.5),
+                GeoPoint::new(-10.5, 20.0),
+                GeoPoint::new(80.0, -170.0),
+            ]
+        );
+        let doc = schema
+            .parse_document(r#"{"location": [2.5, 48.5]}"#)
+            .unwrap();
+        assert_eq!(
+            doc.get_first(location).unwrap().as_geo_point(),
+            Some(GeoPoint::new(48.5, 2.5))
+        );
+        assert!(schema
+            .parse_document(r#"{"location": {"lat": 100.0, "lon": 0.0}}"#)
+            .is_err());
+        assert!(schema.parse_document(r#"{"location": 1.0}"#).is_err());
+        let field_entry = serde_json::to_string(schema.get_field_entry(location)).unwrap();
+        assert_eq!(
+            field_entry,
+            r#"{"name":"location","type":"geo_point","options":{"fast":true,"stored":true,"indexed":false}}"#
+        );
+    }
+
+    #[test]
+    fn test_geo_point_parse() {
+        assert_eq!(
+            GeoPoint::parse("48.8566, 2.3522"),
+            Some(GeoPoint::new(48.8566, 2.3522))
+        );
+        assert_eq!(GeoPoint::parse("98.0,2.0"), None);
+        assert_eq!(GeoPoint::parse("a,b"), None);
+    }
+}
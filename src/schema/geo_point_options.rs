@@ -0,0 +1,121 @@
+use std::ops::BitOr;
+
+use serde::{Deserialize, Serialize};
+
+use super::flags::{FastFlag, IndexedFlag, SchemaFlagList, StoredFlag};
+
+/// Define how a geo point field should be handled by tantivy.
+///
+/// Geo queries check the exact coordinates of the points using the fast field, which is
+/// therefore required to search a geo point field. Indexing the field is optional: it
+/// makes geo queries faster by only checking the points located in the geohash cells
+/// covering the searched area.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct GeoPointOptions {
+    fast: bool,
+    stored: bool,
+    indexed: bool,
+}
+
+impl GeoPointOptions {
+    /// Returns true iff the value is a fast field.
+    pub fn is_fast(&self) -> bool {
+        self.fast
+    }
+
+    /// Returns `true` if the geo point should be stored in the doc store.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Returns true iff the value is indexed.
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// Sets the field as stored
+    #[must_use]
+    pub fn set_stored(mut self) -> Self {
+        self.stored = true;
+        self
+    }
+
+    /// Set the field as indexed.
+    ///
+    /// Setting a geo point as indexed will generate a posting list for each
+    /// geohash cell containing the point, up to a precision of 8 characters.
+    #[must_use]
+    pub fn set_indexed(mut self) -> Self {
+        self.indexed = true;
+        self
+    }
+
+    /// Set the field as a fast field.
+    ///
+    /// This is required for the field to be searchable.
+    #[must_use]
+    pub fn set_fast(mut self) -> Self {
+        self.fast = true;
+        self
+    }
+}
+
+impl From<()> for GeoPointOptions {
+    fn from(_: ()) -> GeoPointOptions {
+        GeoPointOptions::default()
+    }
+}
+
+impl From<FastFlag> for GeoPointOptions {
+    fn from(_: FastFlag) -> Self {
+        GeoPointOptions {
+            indexed: false,
+            stored: false,
+            fast: true,
+        }
+    }
+}
+
+impl From<StoredFlag> for GeoPointOptions {
+    fn from(_: StoredFlag) -> Self {
+        GeoPointOptions {
+            indexed: false,
+            stored: true,
+            fast: false,
+        }
+    }
+}
+
+impl From<IndexedFlag> for GeoPointOptions {
+    fn from(_: IndexedFlag) -> Self {
+        GeoPointOptions {
+            indexed: true,
+            stored: false,
+            fast: false,
+        }
+    }
+}
+
+impl<T: Into<GeoPointOptions>> BitOr<T> for GeoPointOptions {
+    type Output = GeoPointOptions;
+
+    fn bitor(self, other: T) -> GeoPointOptions {
+        let other = other.into();
+        GeoPointOptions {
+            indexed: self.indexed | other.indexed,
+            stored: self.stored | other.stored,
+            fast: self.fast | other.fast,
+        }
+    }
+}
+
+impl<Head, Tail> From<SchemaFlagList<Head, Tail>> for GeoPointOptions
+where
+    Head: Clone,
+    Tail: Clone,
+    Self: BitOr<Output = Self> + From<Head> + From<Tail>,
+{
+    fn from(head_tail: SchemaFlagList<Head, Tail>) -> Self {
+        Self::from(head_tail.head) | Self::from(head_tail.tail)
+    }
+}
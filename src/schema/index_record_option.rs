@@ -26,6 +26,13 @@ pub enum IndexRecordOption {
     /// Positions are required to run a [`PhraseQuery`](crate::query::PhraseQuery).
     #[serde(rename = "position")]
     WithFreqsAndPositions,
+    /// records the document id, the term frequency, the positions of
+    /// the occurrences in the document, and the byte offsets of these occurrences
+    /// in the original text.
+    /// Offsets make it possible to highlight matches without re-tokenizing the text.
+    /// (See [`SegmentPostings::offsets()`](crate::postings::SegmentPostings::offsets))
+    #[serde(rename = "offsets")]
+    WithFreqsAndPositionsAndOffsets,
 }
 
 impl IndexRecordOption {
@@ -34,7 +41,9 @@ impl IndexRecordOption {
     pub fn has_freq(self) -> bool {
         match self {
             IndexRecordOption::Basic => false,
-            IndexRecordOption::WithFreqs | IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqs
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
         }
     }
 
@@ -43,20 +52,25 @@ impl IndexRecordOption {
     pub fn has_positions(self) -> bool {
         match self {
             IndexRecordOption::Basic | IndexRecordOption::WithFreqs => false,
-            IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
+        }
+    }
+
+    /// Returns true if this option include encoding
+    /// term offsets.
+    pub fn has_offsets(self) -> bool {
+        match self {
+            IndexRecordOption::Basic
+            | IndexRecordOption::WithFreqs
+            | IndexRecordOption::WithFreqsAndPositions => false,
+            IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
         }
     }
 
     /// Downgrades to the next level if provided `IndexRecordOption` is unavailable.
     pub fn downgrade(&self, other: IndexRecordOption) -> IndexRecordOption {
-        use IndexRecordOption::*;
-
-        match (other, self) {
-            (WithFreqsAndPositions, WithFreqsAndPositions) => WithFreqsAndPositions,
-            (WithFreqs, WithFreqs) => WithFreqs,
-            (WithFreqsAndPositions, WithFreqs) => WithFreqs,
-            (WithFreqs, WithFreqsAndPositions) => WithFreqs,
-            _ => Basic,
-        }
+        // Each option includes all of the information of the options before it.
+        (*self).min(other)
     }
 }
@@ -8,7 +8,7 @@
 //! - the field name (may contain any characted, can't start with a `-` and can't be empty. Some
 //!   characters may require escaping when using the query parser).
 //! - the type of the field (currently `text`, `u64`, `i64`, `f64`, `bool`, `date`, `IpAddr`,
-//!   facets, bytes and json are supported)
+//!   facets, bytes, json and dense vectors are supported)
 //! - how the field should be indexed / stored.
 //!
 //! This very last point is critical as it will enable / disable some of the functionality
@@ -120,6 +120,8 @@ mod bytes_options;
 mod date_time_options;
 mod field;
 mod flags;
+mod geo_point;
+mod geo_point_options;
 mod index_record_option;
 mod ip_options;
 mod json_object_options;
@@ -127,6 +129,7 @@ mod named_field_document;
 mod numeric_options;
 mod text_options;
 mod value;
+mod vector_options;
 
 use columnar::ColumnType;
 
@@ -143,6 +146,11 @@ pub use self::field_entry::FieldEntry;
 pub use self::field_type::{FieldType, Type};
 pub use self::field_value::FieldValue;
 pub use self::flags::{COERCE, FAST, INDEXED, STORED};
+pub(crate) use self::geo_point::{
+    geohash_cover, geohash_cover_len, geohash_from_cell_bits, INDEXED_GEOHASH_PRECISION,
+};
+pub use self::geo_point::{GeoPoint, MAX_GEOHASH_PRECISION};
+pub use self::geo_point_options::GeoPointOptions;
 pub use self::index_record_option::IndexRecordOption;
 pub use self::ip_options::{IntoIpv6Addr, IpAddrOptions};
 pub use self::json_object_options::JsonObjectOptions;
@@ -154,6 +162,7 @@ pub use self::schema::{DocParsingError, Schema, SchemaBuilder};
 pub use self::term::{Term, ValueBytes, JSON_END_OF_PATH};
 pub use self::text_options::{TextFieldIndexing, TextOptions, STRING, TEXT};
 pub use self::value::Value;
+pub use self::vector_options::{VectorMetric, VectorOptions};
 
 /// Validator for a potential `field_name`.
 /// Returns true if the name can be use for a field name.
@@ -176,6 +185,8 @@ pub(crate) fn value_type_to_column_type(typ: Type) -> Option<ColumnType> {
         Type::Bytes => Some(ColumnType::Bytes),
         Type::IpAddr => Some(ColumnType::IpAddr),
         Type::Json => None,
+        Type::Vector => None,
+        Type::GeoPoint => Some(ColumnType::U64),
     }
 }
 
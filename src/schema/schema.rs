@@ -142,6 +142,38 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a dense vector field.
+    /// Returns the associated field handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when field already exists.
+    pub fn add_vector_field(
+        &mut self,
+        field_name_str: &str,
+        field_options: VectorOptions,
+    ) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_vector(field_name, field_options);
+        self.add_field(field_entry)
+    }
+
+    /// Adds a geo point field.
+    /// Returns the associated field handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when field already exists.
+    pub fn add_geo_point_field<T: Into<GeoPointOptions>>(
+        &mut self,
+        field_name_str: &str,
+        field_options: T,
+    ) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_geo_point(field_name, field_options.into());
+        self.add_field(field_entry)
+    }
+
     /// Adds a new text field.
     /// Returns the associated field handle
     ///
@@ -369,6 +401,21 @@ impl Schema {
                 let field_entry = self.get_field_entry(field);
                 let field_type = field_entry.field_type();
                 match json_value {
+                    // A vector is given as an array of numbers,
+                    // several vectors as an array of arrays.
+                    // Similarly, a geo point can be given as a `[lon, lat]` array.
+                    JsonValue::Array(json_items)
+                        if (matches!(field_type, FieldType::Vector(_))
+                            && !json_items.iter().all(JsonValue::is_array))
+                            || (matches!(field_type, FieldType::GeoPoint(_))
+                                && !json_items.is_empty()
+                                && json_items.iter().all(JsonValue::is_number)) =>
+                    {
+                        let value = field_type
+                            .value_from_json(JsonValue::Array(json_items))
+                            .map_err(|e| DocParsingError::ValueError(field_name.clone(), e))?;
+                        doc.add_field_value(field, value);
+                    }
                     JsonValue::Array(json_items) => {
                         for json_item in json_items {
                             let value = field_type
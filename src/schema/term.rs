@@ -124,6 +124,13 @@ impl Term {
         Term::with_bytes_and_field_and_payload(Type::Bytes, field, bytes)
     }
 
+    /// Builds a term given a field, and the geohash of a cell.
+    ///
+    /// Indexed geo point fields have a term for each of the cells containing the point.
+    pub fn from_field_geohash(field: Field, geohash: &str) -> Term {
+        Term::with_bytes_and_field_and_payload(Type::GeoPoint, field, geohash.as_bytes())
+    }
+
     /// Removes the value_bytes and set the field and type code.
     pub(crate) fn clear_with_field_and_type(&mut self, typ: Type, field: Field) {
         self.truncate_value_bytes(0);
@@ -388,6 +395,16 @@ where B: AsRef<[u8]>
         Some(self.value_bytes())
     }
 
+    /// Returns the geohash of the cell associated with the term.
+    ///
+    /// Returns `None` if the field is not of geo point type.
+    pub fn as_geohash(&self) -> Option<&str> {
+        if self.typ() != Type::GeoPoint {
+            return None;
+        }
+        str::from_utf8(self.value_bytes()).ok()
+    }
+
     /// Returns a `Ipv6Addr` value from the term.
     pub fn as_ip_addr(&self) -> Option<Ipv6Addr> {
         if self.typ() != Type::IpAddr {
@@ -478,6 +495,11 @@ where B: AsRef<[u8]>
             Type::IpAddr => {
                 write_opt(f, self.as_ip_addr())?;
             }
+            // Vectors are never indexed as terms.
+            Type::Vector => {}
+            Type::GeoPoint => {
+                write_opt(f, self.as_geohash())?;
+            }
         }
         Ok(())
     }
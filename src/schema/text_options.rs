@@ -21,6 +21,9 @@ pub struct TextOptions {
     #[serde(skip_serializing_if = "is_false")]
     /// coerce values into string if they are not of type string
     coerce: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    term_vectors: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -105,6 +108,13 @@ impl TextOptions {
         self.coerce
     }
 
+    /// Returns true if the term vector of each document is to be stored.
+    ///
+    /// Term vectors are only stored for indexed fields.
+    pub fn has_term_vectors(&self) -> bool {
+        self.term_vectors && self.indexing.is_some()
+    }
+
     /// Set the field as a fast field.
     ///
     /// Fast fields are designed for random access.
@@ -147,6 +157,18 @@ impl TextOptions {
         self
     }
 
+    /// Stores the term vector of each document: its terms, with their frequency,
+    /// and their positions and offsets if the field records them.
+    ///
+    /// Term vectors can be read with
+    /// [`SegmentReader::term_vector()`](crate::SegmentReader::term_vector).
+    /// They are only stored if the field is indexed.
+    #[must_use]
+    pub fn set_term_vectors(mut self) -> TextOptions {
+        self.term_vectors = true;
+        self
+    }
+
     /// Sets the field as indexed, with the specific indexing options.
     #[must_use]
     pub fn set_indexing_options(mut self, indexing: TextFieldIndexing) -> TextOptions {
@@ -180,6 +202,32 @@ impl TokenizerName {
     }
 }
 
+#[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
+pub(crate) struct SimilarityName(Cow<'static, str>);
+
+const DEFAULT_SIMILARITY_NAME: &str = "bm25";
+
+impl Default for SimilarityName {
+    fn default() -> Self {
+        SimilarityName::from_static(DEFAULT_SIMILARITY_NAME)
+    }
+}
+
+impl SimilarityName {
+    pub const fn from_static(name: &'static str) -> Self {
+        SimilarityName(Cow::Borrowed(name))
+    }
+    pub(crate) fn from_name(name: &str) -> Self {
+        SimilarityName(Cow::Owned(name.to_string()))
+    }
+    pub(crate) fn name(&self) -> &str {
+        &self.0
+    }
+    fn is_default(&self) -> bool {
+        self.name() == DEFAULT_SIMILARITY_NAME
+    }
+}
+
 /// Configuration defining indexing for a text field.
 ///
 /// It defines
@@ -189,6 +237,8 @@ impl TokenizerName {
 /// - The name of the `Tokenizer` that should be used to process the field.
 /// - Flag indicating, if fieldnorms should be stored (See [fieldnorm](crate::fieldnorm)). Defaults
 ///   to `true`.
+/// - The name of the [`Similarity`](crate::query::Similarity) used to score the field. Defaults to
+///   `bm25`.
 #[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
 pub struct TextFieldIndexing {
     #[serde(default)]
@@ -197,6 +247,9 @@ pub struct TextFieldIndexing {
     fieldnorms: bool,
     #[serde(default)]
     tokenizer: TokenizerName,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "SimilarityName::is_default")]
+    similarity: SimilarityName,
 }
 
 pub(crate) fn default_fieldnorms() -> bool {
@@ -209,6 +262,7 @@ impl Default for TextFieldIndexing {
             tokenizer: TokenizerName::default(),
             record: IndexRecordOption::default(),
             fieldnorms: default_fieldnorms(),
+            similarity: SimilarityName::default(),
         }
     }
 }
@@ -226,6 +280,21 @@ impl TextFieldIndexing {
         self.tokenizer.name()
     }
 
+    /// Sets the similarity used to score the field.
+    ///
+    /// The similarity must be registered in the
+    /// [`SimilarityManager`](crate::query::SimilarityManager) of the index.
+    #[must_use]
+    pub fn set_similarity(mut self, similarity_name: &str) -> TextFieldIndexing {
+        self.similarity = SimilarityName::from_name(similarity_name);
+        self
+    }
+
+    /// Returns the name of the similarity used to score the field.
+    pub fn similarity(&self) -> &str {
+        self.similarity.name()
+    }
+
     /// Sets fieldnorms
     #[must_use]
     pub fn set_fieldnorms(mut self, fieldnorms: bool) -> TextFieldIndexing {
@@ -261,10 +330,12 @@ pub const STRING: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(NO_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::Basic,
+        similarity: SimilarityName::from_static(DEFAULT_SIMILARITY_NAME),
     }),
     stored: false,
     fast: FastFieldTextOptions::IsEnabled(false),
     coerce: false,
+    term_vectors: false,
 };
 
 /// The field will be tokenized and indexed.
@@ -273,10 +344,12 @@ pub const TEXT: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(DEFAULT_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::WithFreqsAndPositions,
+        similarity: SimilarityName::from_static(DEFAULT_SIMILARITY_NAME),
     }),
     stored: false,
     coerce: false,
     fast: FastFieldTextOptions::IsEnabled(false),
+    term_vectors: false,
 };
 
 impl<T: Into<TextOptions>> BitOr<T> for TextOptions {
@@ -289,6 +362,7 @@ impl<T: Into<TextOptions>> BitOr<T> for TextOptions {
             stored: self.stored | other.stored,
             fast: self.fast | other.fast,
             coerce: self.coerce | other.coerce,
+            term_vectors: self.term_vectors | other.term_vectors,
         }
     }
 }
@@ -306,6 +380,7 @@ impl From<StoredFlag> for TextOptions {
             stored: true,
             fast: FastFieldTextOptions::default(),
             coerce: false,
+            term_vectors: false,
         }
     }
 }
@@ -317,6 +392,7 @@ impl From<CoerceFlag> for TextOptions {
             stored: false,
             fast: FastFieldTextOptions::default(),
             coerce: true,
+            term_vectors: false,
         }
     }
 }
@@ -328,6 +404,7 @@ impl From<FastFlag> for TextOptions {
             stored: false,
             fast: FastFieldTextOptions::IsEnabled(true),
             coerce: false,
+            term_vectors: false,
         }
     }
 }
@@ -365,6 +442,10 @@ mod tests {
 
     #[test]
     fn test_cmp_index_record_option() {
+        assert!(
+            IndexRecordOption::WithFreqsAndPositionsAndOffsets
+                > IndexRecordOption::WithFreqsAndPositions
+        );
         assert!(IndexRecordOption::WithFreqsAndPositions > IndexRecordOption::WithFreqs);
         assert!(IndexRecordOption::WithFreqs > IndexRecordOption::Basic);
     }
@@ -389,6 +470,20 @@ mod tests {
         assert_eq!(options3.indexing, None);
     }
 
+    #[test]
+    fn serde_similarity() {
+        let indexing = TextFieldIndexing::default();
+        assert_eq!(indexing.similarity(), "bm25");
+        assert!(!serde_json::to_string(&indexing)
+            .unwrap()
+            .contains("similarity"));
+        let indexing = indexing.set_similarity("tfidf");
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""similarity":"tfidf""#));
+        let indexing_deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(indexing_deser, indexing);
+    }
+
     #[test]
     fn serde_fast_field_tokenizer() {
         let json = r#" {
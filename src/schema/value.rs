@@ -7,7 +7,7 @@ use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Map;
 
-use crate::schema::Facet;
+use crate::schema::{Facet, GeoPoint};
 use crate::tokenizer::PreTokenizedString;
 use crate::DateTime;
 
@@ -37,6 +37,10 @@ pub enum Value {
     JsonObject(serde_json::Map<String, serde_json::Value>),
     /// IpV6 Address. Internally there is no IpV4, it needs to be converted to `Ipv6Addr`.
     IpAddr(Ipv6Addr),
+    /// Dense vector of `f32`
+    Vector(Vec<f32>),
+    /// Geo point
+    GeoPoint(GeoPoint),
 }
 
 impl Eq for Value {}
@@ -63,6 +67,8 @@ impl Serialize for Value {
                     obj.serialize(serializer)
                 }
             }
+            Value::Vector(ref vector) => vector.serialize(serializer),
+            Value::GeoPoint(ref geo_point) => geo_point.serialize(serializer),
         }
     }
 }
@@ -215,6 +221,28 @@ impl Value {
         }
     }
 
+    /// Returns the vector, provided the value is of the `Vector` type.
+    ///
+    /// Returns `None` if the value is not of type `Vector`.
+    pub fn as_vector(&self) -> Option<&[f32]> {
+        if let Value::Vector(vector) = self {
+            Some(vector)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the geo point, provided the value is of the `GeoPoint` type.
+    ///
+    /// Returns `None` if the value is not of type `GeoPoint`.
+    pub fn as_geo_point(&self) -> Option<GeoPoint> {
+        if let Value::GeoPoint(geo_point) = self {
+            Some(*geo_point)
+        } else {
+            None
+        }
+    }
+
     /// Returns the ip addr, provided the value is of the `Ip` type.
     /// (Returns None if the value is not of the `Ip` type)
     pub fn as_ip_addr(&self) -> Option<Ipv6Addr> {
@@ -292,6 +320,18 @@ impl From<Vec<u8>> for Value {
     }
 }
 
+impl From<Vec<f32>> for Value {
+    fn from(vector: Vec<f32>) -> Value {
+        Value::Vector(vector)
+    }
+}
+
+impl From<GeoPoint> for Value {
+    fn from(geo_point: GeoPoint) -> Value {
+        Value::GeoPoint(geo_point)
+    }
+}
+
 impl From<PreTokenizedString> for Value {
     fn from(pretokenized_string: PreTokenizedString) -> Value {
         Value::PreTokStr(pretokenized_string)
@@ -323,7 +363,7 @@ mod binary_serialize {
     use common::{f64_to_u64, u64_to_f64, BinarySerializable};
 
     use super::Value;
-    use crate::schema::Facet;
+    use crate::schema::{Facet, GeoPoint};
     use crate::tokenizer::PreTokenizedString;
     use crate::DateTime;
 
@@ -338,6 +378,8 @@ mod binary_serialize {
     const JSON_OBJ_CODE: u8 = 8;
     const BOOL_CODE: u8 = 9;
     const IP_CODE: u8 = 10;
+    const VECTOR_CODE: u8 = 11;
+    const GEO_POINT_CODE: u8 = 12;
 
     // extended types
 
@@ -400,6 +442,15 @@ mod binary_serialize {
                     IP_CODE.serialize(writer)?;
                     ip.to_u128().serialize(writer)
                 }
+                Value::Vector(ref vector) => {
+                    VECTOR_CODE.serialize(writer)?;
+                    vector.serialize(writer)
+                }
+                Value::GeoPoint(ref geo_point) => {
+                    GEO_POINT_CODE.serialize(writer)?;
+                    geo_point.lat.serialize(writer)?;
+                    geo_point.lon.serialize(writer)
+                }
             }
         }
 
@@ -473,6 +524,12 @@ mod binary_serialize {
                     let value = u128::deserialize(reader)?;
                     Ok(Value::IpAddr(Ipv6Addr::from_u128(value)))
                 }
+                VECTOR_CODE => Ok(Value::Vector(Vec::<f32>::deserialize(reader)?)),
+                GEO_POINT_CODE => {
+                    let lat = f64::deserialize(reader)?;
+                    let lon = f64::deserialize(reader)?;
+                    Ok(Value::GeoPoint(GeoPoint::new(lat, lon)))
+                }
 
                 _ => Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -0,0 +1,158 @@
+use std::ops::BitOr;
+
+use serde::{Deserialize, Serialize};
+
+use super::flags::{SchemaFlagList, StoredFlag};
+
+/// Function used to compare vectors.
+///
+/// Whatever the metric, a higher score means a closer vector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorMetric {
+    /// Cosine similarity. Vectors are normalized at indexing time.
+    ///
+    /// The score is `(1 + cos) / 2`, in `[0, 1]`.
+    #[default]
+    Cosine,
+    /// Dot product. The score is the raw dot product.
+    DotProduct,
+    /// Euclidean distance.
+    ///
+    /// The score is `1 / (1 + d²)`, in `(0, 1]`.
+    Euclidean,
+}
+
+const DEFAULT_MAX_CONNECTIONS: usize = 16;
+const DEFAULT_EF_CONSTRUCTION: usize = 100;
+
+fn default_max_connections() -> usize {
+    DEFAULT_MAX_CONNECTIONS
+}
+
+fn default_ef_construction() -> usize {
+    DEFAULT_EF_CONSTRUCTION
+}
+
+/// Define how a dense vector field should be handled by tantivy.
+///
+/// Each document can hold at most one vector of exactly `dims` dimensions per vector field.
+/// Vectors are indexed in a [HNSW graph](crate::vector) built for each segment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dims: usize,
+    #[serde(default)]
+    metric: VectorMetric,
+    #[serde(default)]
+    stored: bool,
+    #[serde(default = "default_max_connections")]
+    max_connections: usize,
+    #[serde(default = "default_ef_construction")]
+    ef_construction: usize,
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field with `dims` dimensions.
+    ///
+    /// # Panics
+    /// Panics if `dims` is 0.
+    pub fn new(dims: usize, metric: VectorMetric) -> VectorOptions {
+        assert!(dims > 0, "A vector field requires at least one dimension");
+        VectorOptions {
+            dims,
+            metric,
+            stored: false,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            ef_construction: DEFAULT_EF_CONSTRUCTION,
+        }
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Returns the metric used to compare vectors.
+    pub fn metric(&self) -> VectorMetric {
+        self.metric
+    }
+
+    /// Returns true if the vector is stored.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Returns the maximum number of neighbors of a node in the upper layers of the graph.
+    ///
+    /// The bottom layer allows twice as many neighbors.
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// Returns the size of the candidate list used when building the graph.
+    pub fn ef_construction(&self) -> usize {
+        self.ef_construction
+    }
+
+    /// Set the field as stored.
+    ///
+    /// Only the fields that are set as *stored* are
+    /// persisted into the Tantivy's store.
+    #[must_use]
+    pub fn set_stored(mut self) -> VectorOptions {
+        self.stored = true;
+        self
+    }
+
+    /// Sets the maximum number of neighbors of a node in the graph (default `16`).
+    ///
+    /// Higher values improve recall at the expense of indexing time and memory.
+    #[must_use]
+    pub fn set_max_connections(mut self, max_connections: usize) -> VectorOptions {
+        assert!(
+            max_connections > 0,
+            "max_connections must be strictly positive"
+        );
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Sets the size of the candidate list used when building the graph (default `100`).
+    ///
+    /// Higher values improve recall at the expense of indexing time.
+    #[must_use]
+    pub fn set_ef_construction(mut self, ef_construction: usize) -> VectorOptions {
+        assert!(
+            ef_construction > 0,
+            "ef_construction must be strictly positive"
+        );
+        self.ef_construction = ef_construction;
+        self
+    }
+}
+
+impl BitOr<SchemaFlagList<StoredFlag, ()>> for VectorOptions {
+    type Output = VectorOptions;
+
+    fn bitor(self, _: SchemaFlagList<StoredFlag, ()>) -> VectorOptions {
+        self.set_stored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::STORED;
+
+    #[test]
+    fn test_vector_options_serde() {
+        let options = VectorOptions::new(3, VectorMetric::DotProduct) | STORED;
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dims":3,"metric":"dot_product","stored":true,"max_connections":16,"ef_construction":100}"#
+        );
+        let deserialized: VectorOptions = serde_json::from_str(r#"{"dims":3}"#).unwrap();
+        assert_eq!(deserialized, VectorOptions::new(3, VectorMetric::Cosine));
+    }
+}
@@ -0,0 +1,979 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use super::{Snippet, DEFAULT_MAX_NUM_CHARS};
+use crate::postings::{Postings, TermInfo};
+use crate::query::{MatchPattern, Query};
+use crate::schema::{Field, IndexRecordOption, Value};
+use crate::tokenizer::{TextAnalyzer, Token};
+use crate::{DocAddress, DocId, DocSet, Document, Score, Searcher, SegmentReader, Term};
+
+const DEFAULT_NUM_FRAGMENTS: usize = 3;
+
+/// Defines where the fragments returned by a [`Highlighter`] start and stop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FragmentBoundary {
+    /// Fragments are sequences of tokens of at most `max_num_chars`.
+    #[default]
+    Chars,
+    /// Fragments are sentences.
+    ///
+    /// Sentences longer than `max_num_chars` are shortened to a sequence of tokens
+    /// of at most `max_num_chars`.
+    Sentence,
+}
+
+/// Term of a [`Pattern`], as it appears in the token stream.
+#[derive(Debug, PartialEq)]
+struct PatternTerm {
+    offset: usize,
+    text: String,
+    /// Set if the term is a prefix, to the terms it expands to.
+    expansions: Option<BTreeSet<String>>,
+    score: Score,
+}
+
+impl PatternTerm {
+    fn new(
+        searcher: &Searcher,
+        offset: usize,
+        term: &Term,
+        prefix_max_expansions: Option<u32>,
+    ) -> crate::Result<Option<Self>> {
+        let text = if let Some(text) = term.value().as_str() {
+            text.to_string()
+        } else {
+            return Ok(None);
+        };
+        let expansions = if let Some(max_expansions) = prefix_max_expansions {
+            Some(expand_prefix(
+                searcher,
+                term.field(),
+                &text,
+                max_expansions,
+            )?)
+        } else {
+            None
+        };
+        let doc_freq = searcher.doc_freq(term)?;
+        Ok(Some(PatternTerm {
+            offset,
+            text,
+            expansions,
+            score: 1.0 / (1.0 + doc_freq as Score),
+        }))
+    }
+
+    fn matches(&self, token: &Token) -> bool {
+        if let Some(expansions) = &self.expansions {
+            expansions.contains(&token.text)
+        } else {
+            token.text == self.text
+        }
+    }
+}
+
+/// Returns the terms a prefix expands to, i.e. in each segment the first `max_expansions`
+/// terms starting with the prefix, as in the query.
+fn expand_prefix(
+    searcher: &Searcher,
+    field: Field,
+    prefix: &str,
+    max_expansions: u32,
+) -> crate::Result<BTreeSet<String>> {
+    let mut expansions = BTreeSet::new();
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let mut stream = inverted_index
+            .terms()
+            .range()
+            .ge(prefix.as_bytes())
+            .into_stream()?;
+        let mut num_expansions = 0;
+        while num_expansions < max_expansions
+            && stream.advance()
+            && stream.key().starts_with(prefix.as_bytes())
+        {
+            num_expansions += 1;
+            if let Ok(term_text) = std::str::from_utf8(stream.key()) {
+                expansions.insert(term_text.to_string());
+            }
+        }
+    }
+    Ok(expansions)
+}
+
+/// Sequence of terms to highlight, a single term being a phrase of one term.
+#[derive(Debug, PartialEq)]
+struct Pattern {
+    terms: Vec<PatternTerm>,
+    slop: usize,
+}
+
+impl Pattern {
+    fn for_field(
+        searcher: &Searcher,
+        match_pattern: MatchPattern<'_>,
+        field: Field,
+    ) -> crate::Result<Option<Pattern>> {
+        let (terms, prefix_opt, slop) = match match_pattern {
+            MatchPattern::Term(term) => {
+                let pattern_term_opt = if term.field() == field {
+                    PatternTerm::new(searcher, 0, term, None)?
+                } else {
+                    None
+                };
+                return Ok(pattern_term_opt.map(|pattern_term| Pattern {
+                    terms: vec![pattern_term],
+                    slop: 0,
+                }));
+            }
+            MatchPattern::Phrase { terms, slop } => (terms, None, slop),
+            MatchPattern::PhrasePrefix {
+                terms,
+                prefix,
+                max_expansions,
+            } => (terms, Some((prefix, max_expansions)), 0),
+        };
+        let mut pattern_terms = Vec::with_capacity(terms.len() + 1);
+        let all_terms = terms
+            .iter()
+            .map(|term| (term, None))
+            .chain(prefix_opt.map(|(prefix, max_expansions)| (prefix, Some(max_expansions))));
+        for ((offset, term), prefix_max_expansions) in all_terms {
+            if term.field() != field {
+                return Ok(None);
+            }
+            if let Some(pattern_term) =
+                PatternTerm::new(searcher, *offset, term, prefix_max_expansions)?
+            {
+                pattern_terms.push(pattern_term);
+            } else {
+                return Ok(None);
+            }
+        }
+        if pattern_terms.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Pattern {
+            terms: pattern_terms,
+            slop: slop as usize,
+        }))
+    }
+
+    /// Returns the tokens matching the pattern, for each occurrence of the pattern.
+    fn find_matches(
+        &self,
+        tokens: &[Token],
+        tokens_by_position: &BTreeMap<usize, Vec<usize>>,
+    ) -> Vec<Match> {
+        let first_term = &self.terms[0];
+        let mut matches = Vec::new();
+        'tokens: for (token_ord, token) in tokens.iter().enumerate() {
+            if !first_term.matches(token) {
+                continue;
+            }
+            let mut matched_tokens = vec![token_ord];
+            for term in &self.terms[1..] {
+                let expected_position = token.position + term.offset - first_term.offset;
+                let closest_token_opt = tokens_by_position
+                    .range(
+                        expected_position.saturating_sub(self.slop)..=expected_position + self.slop,
+                    )
+                    .flat_map(|(position, token_ords)| {
+                        let distance = if *position > expected_position {
+                            position - expected_position
+                        } else {
+                            expected_position - position
+                        };
+                        token_ords
+                            .iter()
+                            .map(move |token_ord| (distance, *token_ord))
+                    })
+                    .filter(|(_, token_ord)| {
+                        term.matches(&tokens[*token_ord]) && !matched_tokens.contains(token_ord)
+                    })
+                    .min();
+                if let Some((_, token_ord)) = closest_token_opt {
+                    matched_tokens.push(token_ord);
+                } else {
+                    continue 'tokens;
+                }
+            }
+            let mut highlighted: Vec<Range<usize>> = matched_tokens
+                .iter()
+                .map(|&token_ord| tokens[token_ord].offset_from..tokens[token_ord].offset_to)
+                .collect();
+            highlighted.sort_by_key(|range| (range.start, range.end));
+            matches.push(Match {
+                range: highlighted[0].start
+                    ..highlighted.iter().map(|range| range.end).max().unwrap(),
+                highlighted,
+                score: self.terms.iter().map(|term| term.score).sum(),
+            });
+        }
+        matches
+    }
+}
+
+/// Occurrence of a pattern in a text.
+struct Match {
+    range: Range<usize>,
+    highlighted: Vec<Range<usize>>,
+    score: Score,
+}
+
+/// Candidate fragment of a text, with the matches it contains.
+struct Fragment {
+    range: Range<usize>,
+    highlighted: Vec<Range<usize>>,
+    score: Score,
+}
+
+impl Fragment {
+    fn new(range: Range<usize>, matches: &[&Match]) -> Fragment {
+        let matches_in_range = matches
+            .iter()
+            .filter(|m| range.start <= m.range.start && m.range.end <= range.end);
+        let mut highlighted: Vec<Range<usize>> = Vec::new();
+        let mut score = 0.0;
+        for m in matches_in_range {
+            highlighted.extend(m.highlighted.iter().cloned());
+            score += m.score;
+        }
+        highlighted.sort_by_key(|range| (range.start, range.end));
+        highlighted.dedup();
+        Fragment {
+            range,
+            highlighted,
+            score,
+        }
+    }
+
+    fn to_snippet(&self, text: &str) -> Snippet {
+        let highlighted = self
+            .highlighted
+            .iter()
+            .map(|range| range.start - self.range.start..range.end - self.range.start)
+            .collect();
+        Snippet::new(&text[self.range.clone()], highlighted)
+    }
+}
+
+struct FieldHighlighter {
+    field: Field,
+    tokenizer: TextAnalyzer,
+    patterns: Vec<Pattern>,
+    has_offsets: bool,
+}
+
+impl FieldHighlighter {
+    /// Returns the tokens of each text matching a term of the patterns, as recorded
+    /// in the postings of the document.
+    ///
+    /// The field must be indexed with offsets.
+    fn indexed_tokens(
+        &self,
+        segment_reader: &SegmentReader,
+        doc: DocId,
+        texts: &[&str],
+    ) -> crate::Result<Vec<Vec<Token>>> {
+        let inverted_index = segment_reader.inverted_index(self.field)?;
+        let mut term_texts: BTreeSet<String> = BTreeSet::new();
+        for pattern_term in self.patterns.iter().flat_map(|pattern| &pattern.terms) {
+            if let Some(expansions) = &pattern_term.expansions {
+                term_texts.extend(expansions.iter().cloned());
+            } else {
+                term_texts.insert(pattern_term.text.clone());
+            }
+        }
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut positions: Vec<u32> = Vec::new();
+        let mut offsets: Vec<(u32, u32)> = Vec::new();
+        for term_text in term_texts {
+            let term = Term::from_field_text(self.field, &term_text);
+            let term_info: TermInfo =
+                if let Some(term_info) = inverted_index.get_term_info(&term)? {
+                    term_info
+                } else {
+                    continue;
+                };
+            let mut postings = inverted_index.read_postings_from_terminfo(
+                &term_info,
+                IndexRecordOption::WithFreqsAndPositionsAndOffsets,
+            )?;
+            if postings.seek(doc) != doc {
+                continue;
+            }
+            postings.positions(&mut positions);
+            postings.offsets(&mut offsets);
+            tokens.extend(positions.iter().zip(offsets.iter()).map(
+                |(&position, &(offset_from, offset_to))| Token {
+                    offset_from: offset_from as usize,
+                    offset_to: offset_to as usize,
+                    position: position as usize,
+                    text: term_text.clone(),
+                    position_length: 1,
+                },
+            ));
+        }
+        tokens.sort_by_key(|token| (token.offset_from, token.position));
+        // Offsets were recorded as if the texts were joined with a space.
+        let mut tokens_per_text = Vec::with_capacity(texts.len());
+        let mut text_start = 0;
+        for text in texts {
+            let text_end = text_start + text.len();
+            let text_tokens: Vec<Token> = tokens
+                .iter()
+                .filter(|token| text_start <= token.offset_from && token.offset_to <= text_end)
+                .map(|token| Token {
+                    offset_from: token.offset_from - text_start,
+                    offset_to: token.offset_to - text_start,
+                    ..token.clone()
+                })
+                .collect();
+            tokens_per_text.push(text_tokens);
+            text_start = text_end + 1;
+        }
+        Ok(tokens_per_text)
+    }
+}
+
+/// `Highlighter` returns the best fragments of the text of one or several fields
+/// that match a query.
+///
+/// Unlike [`SnippetGenerator`](super::SnippetGenerator), only the words matching the
+/// query as a whole are highlighted: the words of a phrase query are only highlighted
+/// where they form the phrase, and the words of a phrase prefix query where they
+/// start with the prefix. Words of the negated clauses of a
+/// [`BooleanQuery`](crate::query::BooleanQuery) are not highlighted.
+///
+/// The text is tokenized again with the tokenizer of each field, unless the field
+/// is indexed with [`IndexRecordOption::WithFreqsAndPositionsAndOffsets`] and highlighted
+/// with [`Highlighter::highlight_doc`]: the matches are then read from the postings.
+///
+/// # Example
+///
+/// ```rust
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, FragmentBoundary, Highlighter, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let schema = schema_builder.build();
+/// let index = Index::create_in_ram(schema);
+/// let doc = doc!(
+///     title => "The Old Man and the Sea",
+///     body => "He was an old man who fished alone. The man was old. The sea was calm.",
+/// );
+/// let mut index_writer = index.writer_with_num_threads(1, 15_000_000)?;
+/// index_writer.add_document(doc.clone())?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title, body]).parse_query("\"old man\" sea")?;
+/// let mut highlighter = Highlighter::create(&searcher, &query, &[title, body])?;
+/// highlighter.set_fragment_boundary(FragmentBoundary::Sentence);
+/// let highlights = highlighter.highlight(&doc);
+///
+/// let title_html: Vec<String> = highlights[0].1.iter().map(|snippet| snippet.to_html()).collect();
+/// assert_eq!(title_html, vec!["The <b>Old</b> <b>Man</b> and the <b>Sea</b>"]);
+/// let body_html: Vec<String> = highlights[1].1.iter().map(|snippet| snippet.to_html()).collect();
+/// assert_eq!(
+///     body_html,
+///     vec!["He was an <b>old</b> <b>man</b> who fished alone.", "The <b>sea</b> was calm."]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct Highlighter {
+    fields: Vec<FieldHighlighter>,
+    max_num_chars: usize,
+    num_fragments: usize,
+    fragment_boundary: FragmentBoundary,
+}
+
+impl Highlighter {
+    /// Creates a highlighter for the matches of `query` in `fields`.
+    pub fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        fields: &[Field],
+    ) -> crate::Result<Highlighter> {
+        let mut match_patterns = Vec::new();
+        query.match_patterns(&mut |match_pattern| match_patterns.push(match_pattern));
+        let mut field_highlighters = Vec::with_capacity(fields.len());
+        for &field in fields {
+            let mut patterns: Vec<Pattern> = Vec::new();
+            for &match_pattern in &match_patterns {
+                if let Some(pattern) = Pattern::for_field(searcher, match_pattern, field)? {
+                    if !patterns.contains(&pattern) {
+                        patterns.push(pattern);
+                    }
+                }
+            }
+            let has_offsets = searcher
+                .schema()
+                .get_field_entry(field)
+                .field_type()
+                .get_index_record_option()
+                .map(IndexRecordOption::has_offsets)
+                .unwrap_or(false);
+            field_highlighters.push(FieldHighlighter {
+                field,
+                tokenizer: searcher.index().tokenizer_for_field(field)?,
+                patterns,
+                has_offsets,
+            });
+        }
+        Ok(Highlighter {
+            fields: field_highlighters,
+            max_num_chars: DEFAULT_MAX_NUM_CHARS,
+            num_fragments: DEFAULT_NUM_FRAGMENTS,
+            fragment_boundary: FragmentBoundary::default(),
+        })
+    }
+
+    /// Sets the maximum number of chars of a fragment.
+    pub fn set_max_num_chars(&mut self, max_num_chars: usize) {
+        self.max_num_chars = max_num_chars;
+    }
+
+    /// Sets the maximum number of fragments returned for each field.
+    pub fn set_num_fragments(&mut self, num_fragments: usize) {
+        self.num_fragments = num_fragments;
+    }
+
+    /// Sets where fragments start and stop.
+    pub fn set_fragment_boundary(&mut self, fragment_boundary: FragmentBoundary) {
+        self.fragment_boundary = fragment_boundary;
+    }
+
+    /// Highlights the fields of a `Document`.
+    ///
+    /// Returns the best fragments of each field, in the order the fields were given to
+    /// [`Highlighter::create`]. The fragments are sorted by decreasing score, and do not
+    /// overlap. A field without any match has no fragments.
+    pub fn highlight(&self, doc: &Document) -> Vec<(Field, Vec<Snippet>)> {
+        self.fields
+            .iter()
+            .map(|field_highlighter| {
+                let texts: Vec<&str> = doc
+                    .get_all(field_highlighter.field)
+                    .flat_map(Value::as_text)
+                    .collect();
+                (
+                    field_highlighter.field,
+                    self.highlight_texts(field_highlighter, &texts),
+                )
+            })
+            .collect()
+    }
+
+    /// Highlights the fields of the stored document at `doc_address`.
+    ///
+    /// Returns the same fragments as [`Highlighter::highlight`], but the fields indexed with
+    /// offsets are not tokenized again: the matches are read from the postings of the
+    /// document.
+    pub fn highlight_doc(
+        &self,
+        searcher: &Searcher,
+        doc_address: DocAddress,
+    ) -> crate::Result<Vec<(Field, Vec<Snippet>)>> {
+        let doc = searcher.doc(doc_address)?;
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+        let mut highlights = Vec::with_capacity(self.fields.len());
+        for field_highlighter in &self.fields {
+            let texts: Vec<&str> = doc
+                .get_all(field_highlighter.field)
+                .flat_map(Value::as_text)
+                .collect();
+            let snippets = if field_highlighter.has_offsets
+                && !field_highlighter.patterns.is_empty()
+            {
+                let tokens_per_text =
+                    field_highlighter.indexed_tokens(segment_reader, doc_address.doc_id, &texts)?;
+                self.highlight_tokens(field_highlighter, &texts, tokens_per_text)
+            } else {
+                self.highlight_texts(field_highlighter, &texts)
+            };
+            highlights.push((field_highlighter.field, snippets));
+        }
+        Ok(highlights)
+    }
+
+    /// Highlights a text, as a value of `field`.
+    ///
+    /// Returns no fragments if `field` was not given to [`Highlighter::create`].
+    pub fn highlight_text(&self, field: Field, text: &str) -> Vec<Snippet> {
+        self.fields
+            .iter()
+            .find(|field_highlighter| field_highlighter.field == field)
+            .map(|field_highlighter| self.highlight_texts(field_highlighter, &[text]))
+            .unwrap_or_default()
+    }
+
+    fn highlight_texts(
+        &self,
+        field_highlighter: &FieldHighlighter,
+        texts: &[&str],
+    ) -> Vec<Snippet> {
+        if field_highlighter.patterns.is_empty() {
+            return Vec::new();
+        }
+        let tokens_per_text = texts
+            .iter()
+            .map(|text| tokenize(&mut field_highlighter.tokenizer.clone(), text))
+            .collect();
+        self.highlight_tokens(field_highlighter, texts, tokens_per_text)
+    }
+
+    fn highlight_tokens(
+        &self,
+        field_highlighter: &FieldHighlighter,
+        texts: &[&str],
+        tokens_per_text: Vec<Vec<Token>>,
+    ) -> Vec<Snippet> {
+        let mut fragments: Vec<(usize, Fragment)> = Vec::new();
+        for (text_ord, (text, tokens)) in texts.iter().zip(tokens_per_text).enumerate() {
+            let mut tokens_by_position: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for (token_ord, token) in tokens.iter().enumerate() {
+                tokens_by_position
+                    .entry(token.position)
+                    .or_default()
+                    .push(token_ord);
+            }
+            let mut matches: Vec<Match> = field_highlighter
+                .patterns
+                .iter()
+                .flat_map(|pattern| pattern.find_matches(&tokens, &tokens_by_position))
+                .collect();
+            if matches.is_empty() {
+                continue;
+            }
+            matches.sort_by_key(|m| (m.range.start, m.range.end));
+            let boundaries = match self.fragment_boundary {
+                FragmentBoundary::Chars => vec![trim(text, 0..text.len())],
+                FragmentBoundary::Sentence => sentence_ranges(text),
+            };
+            for boundary in boundaries {
+                fragments.extend(
+                    fragments_within(boundary, &tokens, &matches, self.max_num_chars)
+                        .into_iter()
+                        .map(|fragment| (text_ord, fragment)),
+                );
+            }
+        }
+        select_fragments(fragments, self.num_fragments)
+            .into_iter()
+            .map(|(text_ord, fragment)| fragment.to_snippet(texts[text_ord]))
+            .collect()
+    }
+}
+
+fn tokenize(tokenizer: &mut TextAnalyzer, text: &str) -> Vec<Token> {
+    let mut token_stream = tokenizer.token_stream(text);
+    let mut tokens = Vec::new();
+    while let Some(token) = token_stream.next() {
+        tokens.push(token.clone());
+    }
+    tokens
+}
+
+/// Returns the ranges of the sentences of a text, without surrounding whitespaces.
+///
+/// A sentence ends with a `.`, `!` or `?` followed by a whitespace, or with an empty line.
+fn sentence_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut sentence_ranges = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let next_char_opt = chars.peek().map(|(_, next_char)| *next_char);
+        let is_sentence_end = match c {
+            '.' | '!' | '?' => next_char_opt.map(char::is_whitespace).unwrap_or(false),
+            '\n' => next_char_opt == Some('\n'),
+            _ => false,
+        };
+        if is_sentence_end {
+            let end = offset + c.len_utf8();
+            sentence_ranges.push(start..end);
+            start = end;
+        }
+    }
+    sentence_ranges.push(start..text.len());
+    sentence_ranges
+        .into_iter()
+        .map(|range| trim(text, range))
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Removes the leading and trailing whitespaces of a range of a text.
+fn trim(text: &str, range: Range<usize>) -> Range<usize> {
+    let trimmed_start = text[range.clone()].trim_start();
+    let start = range.end - trimmed_start.len();
+    start..start + trimmed_start.trim_end().len()
+}
+
+/// Returns the candidate fragments within `boundary`.
+///
+/// If `boundary` is too long, fragments start at each match and include as many
+/// of the following tokens as possible.
+fn fragments_within(
+    boundary: Range<usize>,
+    tokens: &[Token],
+    matches: &[Match],
+    max_num_chars: usize,
+) -> Vec<Fragment> {
+    let matches: Vec<&Match> = matches
+        .iter()
+        .filter(|m| boundary.start <= m.range.start && m.range.start < boundary.end)
+        .collect();
+    if matches.is_empty() {
+        return Vec::new();
+    }
+    // A phrase may span several sentences.
+    let boundary_end = matches
+        .iter()
+        .map(|m| m.range.end)
+        .fold(boundary.end, usize::max);
+    if boundary_end - boundary.start <= max_num_chars {
+        return vec![Fragment::new(boundary.start..boundary_end, &matches)];
+    }
+    let mut fragments = Vec::new();
+    for (match_ord, m) in matches.iter().enumerate() {
+        let start = m.range.start;
+        let mut end = matches[match_ord..]
+            .iter()
+            .map(|next_match| next_match.range.end)
+            .take_while(|&next_end| next_end - start <= max_num_chars)
+            .fold(m.range.end, usize::max);
+        // Fills the remaining budget with the following tokens, and then with the previous
+        // tokens.
+        end = tokens
+            .iter()
+            .filter(|token| token.offset_from >= start)
+            .map(|token| token.offset_to)
+            .take_while(|&token_end| {
+                token_end - start <= max_num_chars && token_end <= boundary_end
+            })
+            .fold(end, usize::max);
+        let start = tokens
+            .iter()
+            .map(|token| token.offset_from)
+            .filter(|&token_start| {
+                boundary.start <= token_start
+                    && token_start <= start
+                    && end - token_start <= max_num_chars
+            })
+            .min()
+            .unwrap_or(start);
+        fragments.push(Fragment::new(start..end, &matches));
+    }
+    fragments
+}
+
+/// Selects the `num_fragments` best non-overlapping fragments.
+///
+/// Fragments are identified by the ordinal of their text, and sorted by decreasing score.
+fn select_fragments(
+    mut fragments: Vec<(usize, Fragment)>,
+    num_fragments: usize,
+) -> Vec<(usize, Fragment)> {
+    fragments.sort_by(|(left_text_ord, left), (right_text_ord, right)| {
+        right
+            .score
+            .partial_cmp(&left.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                (left_text_ord, left.range.start).cmp(&(right_text_ord, right.range.start))
+            })
+    });
+    let mut selected_fragments: Vec<(usize, Fragment)> = Vec::with_capacity(num_fragments);
+    for (text_ord, fragment) in fragments {
+        if selected_fragments.len() == num_fragments {
+            break;
+        }
+        let overlaps = selected_fragments
+            .iter()
+            .any(|(selected_text_ord, selected)| {
+                *selected_text_ord == text_ord
+                    && selected.range.start < fragment.range.end
+                    && fragment.range.start < selected.range.end
+            });
+        if !overlaps {
+            selected_fragments.push((text_ord, fragment));
+        }
+    }
+    selected_fragments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sentence_ranges, FragmentBoundary, Highlighter};
+    use crate::query::{BooleanQuery, Occur, PhrasePrefixQuery, Query, QueryParser, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, STORED, TEXT};
+    use crate::snippet::Snippet;
+    use crate::{DocAddress, Index, Term};
+
+    const TEST_TEXT: &str = "The quick brown fox jumps over the lazy dog. A brown dog is not a \
+                             quick fox. The fox and the dog are friends.";
+
+    fn highlight_html(
+        index: &Index,
+        query: &dyn Query,
+        text: &str,
+        configure: impl Fn(&mut Highlighter),
+    ) -> crate::Result<Vec<String>> {
+        let field = index.schema().get_field("text").unwrap();
+        let searcher = index.reader()?.searcher();
+        let mut highlighter = Highlighter::create(&searcher, query, &[field])?;
+        configure(&mut highlighter);
+        Ok(highlighter
+            .highlight_text(field, text)
+            .iter()
+            .map(|snippet| snippet.to_html())
+            .collect())
+    }
+
+    fn create_index() -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => TEST_TEXT))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    #[test]
+    fn test_highlighter_phrase() -> crate::Result<()> {
+        let index = create_index()?;
+        let field = index.schema().get_field("text").unwrap();
+        let query = QueryParser::for_index(&index, vec![field]).parse_query("\"quick fox\"")?;
+        // "quick" and "fox" are only highlighted where they form the phrase.
+        assert_eq!(
+            highlight_html(&index, &query, TEST_TEXT, |_| {})?,
+            vec![
+                "The quick brown fox jumps over the lazy dog. A brown dog is not a <b>quick</b> \
+                 <b>fox</b>. The fox and the dog are friends."
+            ]
+        );
+        let query = QueryParser::for_index(&index, vec![field]).parse_query("\"quick fox\"~1")?;
+        assert_eq!(
+            highlight_html(&index, &query, TEST_TEXT, |highlighter| {
+                highlighter.set_fragment_boundary(FragmentBoundary::Sentence)
+            })?,
+            vec![
+                "The <b>quick</b> brown <b>fox</b> jumps over the lazy dog.",
+                "A brown dog is not a <b>quick</b> <b>fox</b>."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlighter_near() -> crate::Result<()> {
+        let index = create_index()?;
+        let field = index.schema().get_field("text").unwrap();
+        let query_parser = QueryParser::for_index(&index, vec![field]);
+        // "fox" and "dog" are only highlighted where they are near each other, in any order.
+        let query = query_parser.parse_query("dog NEAR/2 fox")?;
+        assert_eq!(
+            highlight_html(&index, &query, TEST_TEXT, |highlighter| {
+                highlighter.set_fragment_boundary(FragmentBoundary::Sentence)
+            })?,
+            vec!["The <b>fox</b> and the <b>dog</b> are friends."]
+        );
+        let query = query_parser.parse_query("\"the lazy\" NEAR/1 dog")?;
+        assert_eq!(
+            highlight_html(&index, &query, TEST_TEXT, |_| {})?,
+            vec![
+                "The quick brown fox jumps over <b>the</b> <b>lazy</b> <b>dog</b>. A brown dog is \
+                 not a quick fox. The fox and the dog are friends."
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlighter_fragments() -> crate::Result<()> {
+        let index = create_index()?;
+        let field = index.schema().get_field("text").unwrap();
+        let query = QueryParser::for_index(&index, vec![field]).parse_query("fox friends")?;
+        let sentences = highlight_html(&index, &query, TEST_TEXT, |highlighter| {
+            highlighter.set_fragment_boundary(FragmentBoundary::Sentence);
+            highlighter.set_num_fragments(2);
+        })?;
+        assert_eq!(
+            sentences,
+            vec![
+                "The <b>fox</b> and the dog are <b>friends</b>.",
+                "The quick brown <b>fox</b> jumps over the lazy dog."
+            ]
+        );
+        // The best fragment contains both occurrences of "fox", the fragments overlapping it
+        // are discarded.
+        let fragments = highlight_html(&index, &query, TEST_TEXT, |highlighter| {
+            highlighter.set_max_num_chars(20);
+        })?;
+        assert_eq!(
+            fragments,
+            vec![
+                "<b>fox</b>. The <b>fox</b> and the",
+                "<b>fox</b> jumps over the"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlighter_prefix_and_must_not() -> crate::Result<()> {
+        let index = create_index()?;
+        let field = index.schema().get_field("text").unwrap();
+        let prefix_query = PhrasePrefixQuery::new(vec![
+            Term::from_field_text(field, "lazy"),
+            Term::from_field_text(field, "d"),
+        ]);
+        let not_fox = TermQuery::new(
+            Term::from_field_text(field, "fox"),
+            IndexRecordOption::Basic,
+        );
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, Box::new(prefix_query)),
+            (Occur::MustNot, Box::new(not_fox)),
+        ]);
+        assert_eq!(
+            highlight_html(&index, &query, TEST_TEXT, |highlighter| {
+                highlighter.set_fragment_boundary(FragmentBoundary::Sentence)
+            })?,
+            vec!["The quick brown fox jumps over the <b>lazy</b> <b>dog</b>."]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_highlighter_several_fields() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let other = schema_builder.add_text_field("other", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let doc = doc!(
+            title => "Lazy dogs",
+            body => "Nothing here.",
+            body => "Dogs are lazy. Cats are lazier.",
+            other => "lazy",
+        );
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc.clone())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![title, body]).parse_query("lazy")?;
+        let mut highlighter = Highlighter::create(&searcher, &query, &[title, body, other])?;
+        highlighter.set_fragment_boundary(FragmentBoundary::Sentence);
+        let highlights: Vec<_> = highlighter
+            .highlight(&doc)
+            .into_iter()
+            .map(|(field, snippets)| {
+                let html: Vec<String> = snippets.iter().map(|snippet| snippet.to_html()).collect();
+                (field, html)
+            })
+            .collect();
+        assert_eq!(
+            highlights,
+            vec![
+                (title, vec!["<b>Lazy</b> dogs".to_string()]),
+                (body, vec!["Dogs are <b>lazy</b>.".to_string()]),
+                (other, vec![]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sentence_ranges() {
+        let text = " One. Two!Three?  Four\n\nFive.\nSix ";
+        let sentences: Vec<&str> = sentence_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect();
+        assert_eq!(
+            sentences,
+            vec!["One.", "Two!Three?", "Four", "Five.", "Six"]
+        );
+    }
+
+    #[test]
+    fn test_highlighter_with_offsets() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("en_stem")
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+            )
+            .set_stored();
+        let text = schema_builder.add_text_field("text", text_options);
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            title => "Dogs",
+            text => "The dogs were running.",
+            text => "A dog runs fast.",
+        ))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let phrase_prefix_query = PhrasePrefixQuery::new(vec![
+            Term::from_field_text(text, "dog"),
+            Term::from_field_text(text, "ru"),
+        ]);
+        let query_parser = QueryParser::for_index(&index, vec![text, title]);
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, query_parser.parse_query("dogs")?),
+            (Occur::Should, Box::new(phrase_prefix_query)),
+        ]);
+        let highlighter = Highlighter::create(&searcher, &query, &[text, title])?;
+        let doc_address = DocAddress::new(0, 0);
+        let highlights = highlighter.highlight_doc(&searcher, doc_address)?;
+        let to_html = |highlights: &[(_, Vec<Snippet>)]| -> Vec<Vec<String>> {
+            highlights
+                .iter()
+                .map(|(_, snippets)| snippets.iter().map(Snippet::to_html).collect())
+                .collect()
+        };
+        assert_eq!(
+            to_html(&highlights),
+            vec![
+                vec![
+                    "A <b>dog</b> <b>runs</b> fast.".to_string(),
+                    "The <b>dogs</b> were running.".to_string(),
+                ],
+                vec!["<b>Dogs</b>".to_string()],
+            ]
+        );
+        // The stored offsets give the same fragments as tokenizing the text again.
+        let doc = searcher.doc(doc_address)?;
+        assert_eq!(to_html(&highlights), to_html(&highlighter.highlight(&doc)));
+
+        // The prefix expands to at most as many terms as in the query.
+        let mut prefix_query = PhrasePrefixQuery::new(vec![Term::from_field_text(text, "ru")]);
+        prefix_query.set_max_expansions(1);
+        let highlighter = Highlighter::create(&searcher, &prefix_query, &[text])?;
+        let snippets = to_html(&highlighter.highlight_doc(&searcher, doc_address)?).concat();
+        assert!(snippets
+            .iter()
+            .any(|snippet| snippet.contains("<b>running</b>")));
+        assert!(!snippets
+            .iter()
+            .any(|snippet| snippet.contains("<b>runs</b>")));
+        // Tokenizing the text again only highlights the same terms.
+        assert_eq!(snippets, to_html(&highlighter.highlight(&doc)).concat());
+        Ok(())
+    }
+}
@@ -9,6 +9,9 @@ use crate::schema::{Field, Value};
 use crate::tokenizer::{TextAnalyzer, Token};
 use crate::{Document, Score, Searcher, Term};
 
+mod highlighter;
+pub use self::highlighter::{FragmentBoundary, Highlighter};
+
 const DEFAULT_MAX_NUM_CHARS: usize = 150;
 
 const DEFAULT_SNIPPET_PREFIX: &str = "<b>";
@@ -72,6 +72,9 @@ pub struct SegmentSpaceUsage {
 
     store: StoreSpaceUsage,
 
+    vectors: PerFieldSpaceUsage,
+    term_vectors: PerFieldSpaceUsage,
+
     deletes: ByteCount,
 
     total: ByteCount,
@@ -87,6 +90,8 @@ impl SegmentSpaceUsage {
         fast_fields: PerFieldSpaceUsage,
         fieldnorms: PerFieldSpaceUsage,
         store: StoreSpaceUsage,
+        vectors: PerFieldSpaceUsage,
+        term_vectors: PerFieldSpaceUsage,
         deletes: ByteCount,
     ) -> SegmentSpaceUsage {
         let total = termdict.total()
@@ -95,6 +100,8 @@ impl SegmentSpaceUsage {
             + fast_fields.total()
             + fieldnorms.total()
             + store.total()
+            + vectors.total()
+            + term_vectors.total()
             + deletes;
         SegmentSpaceUsage {
             num_docs,
@@ -104,6 +111,8 @@ impl SegmentSpaceUsage {
             fast_fields,
             fieldnorms,
             store,
+            vectors,
+            term_vectors,
             deletes,
             total,
         }
@@ -125,6 +134,8 @@ impl SegmentSpaceUsage {
             SegmentComponent::Store => ComponentSpaceUsage::Store(self.store().clone()),
             SegmentComponent::TempStore => ComponentSpaceUsage::Store(self.store().clone()),
             Delete => Basic(self.deletes()),
+            Vectors => PerField(self.vectors().clone()),
+            TermVectors => PerField(self.term_vectors().clone()),
         }
     }
 
@@ -163,6 +174,16 @@ impl SegmentSpaceUsage {
         &self.store
     }
 
+    /// Space usage for vectors
+    pub fn vectors(&self) -> &PerFieldSpaceUsage {
+        &self.vectors
+    }
+
+    /// Space usage for term vectors
+    pub fn term_vectors(&self) -> &PerFieldSpaceUsage {
+        &self.term_vectors
+    }
+
     /// Space usage for document deletions
     pub fn deletes(&self) -> ByteCount {
         self.deletes
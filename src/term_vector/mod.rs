@@ -0,0 +1,311 @@
+//! Term vectors, the terms of each document for a given text field.
+//!
+//! Term vectors are enabled per field with
+//! [`TextOptions::set_term_vectors`](crate::schema::TextOptions::set_term_vectors), and read
+//! with [`SegmentReader::term_vector`](crate::SegmentReader::term_vector).
+//!
+//! They are kept in the `.tv` file of each segment. For each field, the file contains the
+//! term vector of every document of the segment, one after the other, followed by the
+//! offset of each of them and the number of documents.
+//!
+//! The term vector of a document is encoded as the number of terms, followed for each term,
+//! in increasing order, by
+//! - the length and the bytes of its text,
+//! - its term frequency,
+//! - the delta-encoded positions of its occurrences, if the field records positions,
+//! - the delta-encoded start offset and the length of its occurrences, if the field records
+//!   offsets.
+mod reader;
+mod serializer;
+mod writer;
+
+use std::io;
+
+use common::{BinarySerializable, VInt};
+
+pub use self::reader::{TermVectorReader, TermVectorReaders};
+pub use self::serializer::TermVectorsSerializer;
+pub(crate) use self::writer::TermVectorsWriter;
+use crate::schema::{Field, FieldEntry, FieldType, IndexRecordOption, Term};
+
+/// Returns the record option of the field if it has term vectors.
+fn term_vector_record_option(field_entry: &FieldEntry) -> Option<IndexRecordOption> {
+    match field_entry.field_type() {
+        FieldType::Str(text_options) if text_options.has_term_vectors() => text_options
+            .get_indexing_options()
+            .map(|indexing_options| indexing_options.index_option()),
+        _ => None,
+    }
+}
+
+/// Occurrence of a term in a document: its position and its offsets.
+#[derive(Clone, Copy, Debug)]
+struct Occurrence {
+    position: u32,
+    offset_from: u32,
+    offset_to: u32,
+}
+
+fn serialize_term_vector<'a>(
+    terms: impl ExactSizeIterator<Item = (&'a str, &'a [Occurrence])>,
+    record_option: IndexRecordOption,
+    output: &mut Vec<u8>,
+) -> io::Result<()> {
+    VInt(terms.len() as u64).serialize(output)?;
+    for (text, occurrences) in terms {
+        VInt(text.len() as u64).serialize(output)?;
+        output.extend_from_slice(text.as_bytes());
+        VInt(occurrences.len() as u64).serialize(output)?;
+        if record_option.has_positions() {
+            let mut previous_position = 0;
+            for occurrence in occurrences {
+                VInt(u64::from(occurrence.position - previous_position)).serialize(output)?;
+                previous_position = occurrence.position;
+            }
+        }
+        if record_option.has_offsets() {
+            let mut previous_offset_from = 0;
+            for occurrence in occurrences {
+                // Tokens are not guaranteed to be sorted by offsets.
+                let delta = occurrence.offset_from.wrapping_sub(previous_offset_from);
+                let len = occurrence.offset_to.wrapping_sub(occurrence.offset_from);
+                VInt(u64::from(delta)).serialize(output)?;
+                VInt(u64::from(len)).serialize(output)?;
+                previous_offset_from = occurrence.offset_from;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn deserialize_term_vector(
+    field: Field,
+    mut data: &[u8],
+    record_option: IndexRecordOption,
+) -> io::Result<TermVector> {
+    let read_u32 =
+        |data: &mut &[u8]| -> io::Result<u32> { Ok(VInt::deserialize(data)?.val() as u32) };
+    let num_terms = read_u32(&mut data)? as usize;
+    let mut entries = Vec::with_capacity(num_terms);
+    for _ in 0..num_terms {
+        let text_len = read_u32(&mut data)? as usize;
+        if data.len() < text_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Term vector is truncated",
+            ));
+        }
+        let text = std::str::from_utf8(&data[..text_len])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let term = Term::from_field_text(field, text);
+        data = &data[text_len..];
+        let term_freq = read_u32(&mut data)?;
+        let mut positions = Vec::new();
+        if record_option.has_positions() {
+            let mut position = 0;
+            for _ in 0..term_freq {
+                position += read_u32(&mut data)?;
+                positions.push(position);
+            }
+        }
+        let mut offsets = Vec::new();
+        if record_option.has_offsets() {
+            let mut offset_from = 0u32;
+            for _ in 0..term_freq {
+                offset_from = offset_from.wrapping_add(read_u32(&mut data)?);
+                let len = read_u32(&mut data)?;
+                offsets.push((offset_from, offset_from.wrapping_add(len)));
+            }
+        }
+        entries.push(TermVectorEntry {
+            term,
+            term_freq,
+            positions,
+            offsets,
+        });
+    }
+    Ok(TermVector { entries })
+}
+
+/// The terms of a document for a given field, sorted by term.
+///
+/// Positions and offsets are only available if the field records them, as defined by its
+/// [`IndexRecordOption`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermVector {
+    entries: Vec<TermVectorEntry>,
+}
+
+impl TermVector {
+    /// Returns the entries of the term vector, sorted by term.
+    pub fn entries(&self) -> &[TermVectorEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry of the term with the given text, if the document contains it.
+    pub fn get(&self, text: &str) -> Option<&TermVectorEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.term.serialized_value_bytes().cmp(text.as_bytes()))
+            .ok()
+            .map(|ord| &self.entries[ord])
+    }
+
+    /// Returns the number of distinct terms of the document.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the document has no term in this field.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A term of a [`TermVector`], with its occurrences in the document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermVectorEntry {
+    term: Term,
+    term_freq: u32,
+    positions: Vec<u32>,
+    offsets: Vec<(u32, u32)>,
+}
+
+impl TermVectorEntry {
+    /// Returns the term.
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+
+    /// Returns the text of the term.
+    pub fn text(&self) -> &str {
+        // The text was checked to be valid utf-8 when the term vector was read.
+        std::str::from_utf8(self.term.serialized_value_bytes()).unwrap_or_default()
+    }
+
+    /// Returns the number of occurrences of the term in the document.
+    pub fn term_freq(&self) -> u32 {
+        self.term_freq
+    }
+
+    /// Returns the positions of the occurrences of the term, or an empty slice if the field
+    /// does not record positions.
+    pub fn positions(&self) -> &[u32] {
+        &self.positions
+    }
+
+    /// Returns the byte offsets of the occurrences of the term, or an empty slice if the field
+    /// does not record offsets.
+    ///
+    /// As for the postings, the offsets of multivalued fields are computed as if the values
+    /// were joined with a space.
+    pub fn offsets(&self) -> &[(u32, u32)] {
+        &self.offsets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::TermVector;
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, STRING, TEXT,
+    };
+    use crate::{Index, IndexSettings, IndexSortByField, Order, Searcher};
+
+    fn term_vectors_by_id(
+        searcher: &Searcher,
+        fields: &[Field],
+    ) -> crate::Result<HashMap<u64, Vec<TermVector>>> {
+        let mut term_vectors_by_id = HashMap::new();
+        for segment_reader in searcher.segment_readers() {
+            let id_column = segment_reader.fast_fields().u64("id")?;
+            for doc in segment_reader.doc_ids_alive() {
+                let term_vectors = fields
+                    .iter()
+                    .map(|&field| segment_reader.term_vector(doc, field))
+                    .collect::<crate::Result<Vec<_>>>()?;
+                term_vectors_by_id.insert(id_column.first(doc).unwrap(), term_vectors);
+            }
+        }
+        Ok(term_vectors_by_id)
+    }
+
+    #[test]
+    fn test_term_vectors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", FAST);
+        let body_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+            )
+            .set_term_vectors();
+        let body = schema_builder.add_text_field("body", body_options);
+        let tags = schema_builder.add_text_field("tags", STRING.set_term_vectors());
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(IndexSettings {
+                sort_by_field: Some(IndexSortByField {
+                    field: "id".to_string(),
+                    order: Order::Desc,
+                }),
+                ..Default::default()
+            })
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(
+            id => 1u64,
+            body => "Hello happy world, hello",
+            tags => "a",
+            title => "title"
+        ))?;
+        index_writer.add_document(doc!(id => 2u64, body => "one two", body => "two"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(id => 3u64, title => "title"))?;
+        index_writer.add_document(doc!(id => 4u64, body => "happy", tags => "b"))?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        assert!(searcher.segment_reader(0).term_vector(0, title).is_err());
+        let term_vectors = term_vectors_by_id(&searcher, &[body, tags])?;
+
+        let body_1 = &term_vectors[&1][0];
+        let texts: Vec<&str> = body_1.entries().iter().map(|entry| entry.text()).collect();
+        assert_eq!(texts, vec!["happy", "hello", "world"]);
+        let hello = body_1.get("hello").unwrap();
+        assert_eq!(hello.term(), &crate::Term::from_field_text(body, "hello"));
+        assert_eq!(hello.term_freq(), 2);
+        assert_eq!(hello.positions(), &[0, 3]);
+        assert_eq!(hello.offsets(), &[(0, 5), (19, 24)]);
+        assert!(body_1.get("unknown").is_none());
+
+        // The values of a multivalued field are separated by a position gap, and their
+        // offsets are computed as if the values were joined with a space.
+        let two = term_vectors[&2][0].get("two").unwrap();
+        assert_eq!(two.positions(), &[1, 3]);
+        assert_eq!(two.offsets(), &[(4, 7), (8, 11)]);
+
+        // Positions and offsets are only kept if the field records them.
+        let tag = term_vectors[&1][1].get("a").unwrap();
+        assert_eq!(tag.term_freq(), 1);
+        assert!(tag.positions().is_empty());
+        assert!(tag.offsets().is_empty());
+
+        assert!(term_vectors[&2][1].is_empty());
+        assert!(term_vectors[&3][0].is_empty());
+        assert_eq!(term_vectors[&4][0].len(), 1);
+
+        index_writer
+            .merge(&index.searchable_segment_ids()?)
+            .wait()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(term_vectors_by_id(&searcher, &[body, tags])?, term_vectors);
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use common::{BinarySerializable, HasLen, OwnedBytes};
+
+use super::{deserialize_term_vector, term_vector_record_option, TermVector};
+use crate::directory::{CompositeFile, FileSlice};
+use crate::error::DataCorruption;
+use crate::schema::{Field, IndexRecordOption, Schema};
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::DocId;
+
+/// Reader for the term vectors of all of the fields with term vectors of a segment.
+#[derive(Clone)]
+pub struct TermVectorReaders {
+    data: Arc<CompositeFile>,
+    schema: Schema,
+}
+
+impl TermVectorReaders {
+    /// Creates a term vector reader.
+    ///
+    /// Segments written before term vectors existed do not have a term vector file. Such
+    /// segments are opened with an empty file.
+    pub fn open(file: Option<FileSlice>, schema: Schema) -> crate::Result<TermVectorReaders> {
+        let data = if let Some(file) = file {
+            CompositeFile::open(&file)?
+        } else {
+            CompositeFile::empty()
+        };
+        Ok(TermVectorReaders {
+            data: Arc::new(data),
+            schema,
+        })
+    }
+
+    /// Returns the `TermVectorReader` for a specific field.
+    ///
+    /// Returns `None` if the field does not have term vectors, or if the segment was
+    /// written before term vectors were enabled for this field.
+    pub fn get_field(&self, field: Field) -> crate::Result<Option<TermVectorReader>> {
+        let record_option = if let Some(record_option) =
+            term_vector_record_option(self.schema.get_field_entry(field))
+        {
+            record_option
+        } else {
+            return Ok(None);
+        };
+        if let Some(file) = self.data.open_read(field) {
+            Ok(Some(TermVectorReader::open(field, file, record_option)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Return a break down of the space usage per field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.data.space_usage()
+    }
+}
+
+/// Reads the term vectors of a given field of a segment.
+pub struct TermVectorReader {
+    field: Field,
+    record_option: IndexRecordOption,
+    data: FileSlice,
+    doc_offsets: OwnedBytes,
+}
+
+impl TermVectorReader {
+    fn open(
+        field: Field,
+        file: FileSlice,
+        record_option: IndexRecordOption,
+    ) -> crate::Result<TermVectorReader> {
+        let truncated = || DataCorruption::comment_only("Term vector file is truncated");
+        if file.len() < 4 {
+            return Err(truncated().into());
+        }
+        let (file, num_docs_data) = file.split_from_end(4);
+        let num_docs = u32::deserialize(&mut num_docs_data.read_bytes()?.as_slice())? as usize;
+        let doc_offsets_len = (num_docs + 1) * 8;
+        if file.len() < doc_offsets_len {
+            return Err(truncated().into());
+        }
+        let (data, doc_offsets) = file.split_from_end(doc_offsets_len);
+        Ok(TermVectorReader {
+            field,
+            record_option,
+            data,
+            doc_offsets: doc_offsets.read_bytes()?,
+        })
+    }
+
+    /// Returns the number of documents in the segment, including deleted documents.
+    pub fn num_docs(&self) -> u32 {
+        (self.doc_offsets.len() / 8 - 1) as u32
+    }
+
+    /// Returns the serialized term vector of a document.
+    pub(crate) fn term_vector_bytes(&self, doc: DocId) -> crate::Result<OwnedBytes> {
+        if doc >= self.num_docs() {
+            return Err(crate::TantivyError::InvalidArgument(format!(
+                "Document {doc} is out of range"
+            )));
+        }
+        let mut doc_offsets = &self.doc_offsets.as_slice()[doc as usize * 8..];
+        let start = u64::deserialize(&mut doc_offsets)? as usize;
+        let end = u64::deserialize(&mut doc_offsets)? as usize;
+        if start > end || end > self.data.len() {
+            return Err(DataCorruption::comment_only("Invalid term vector offsets").into());
+        }
+        Ok(self.data.read_bytes_slice(start..end)?)
+    }
+
+    /// Returns the term vector of a document.
+    pub fn term_vector(&self, doc: DocId) -> crate::Result<TermVector> {
+        let bytes = self.term_vector_bytes(doc)?;
+        Ok(deserialize_term_vector(
+            self.field,
+            bytes.as_slice(),
+            self.record_option,
+        )?)
+    }
+}
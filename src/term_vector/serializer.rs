@@ -0,0 +1,53 @@
+use std::io;
+use std::io::Write;
+
+use common::BinarySerializable;
+
+use crate::directory::{CompositeWrite, WritePtr};
+use crate::schema::Field;
+
+/// The term vectors serializer is in charge of
+/// the serialization of the term vectors for all fields with term vectors.
+pub struct TermVectorsSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl TermVectorsSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<TermVectorsSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(TermVectorsSerializer { composite_write })
+    }
+
+    /// Serialize the term vectors of the given field.
+    ///
+    /// `docs` yields the serialized term vector of each document of the segment, in doc id
+    /// order.
+    pub(crate) fn serialize_field<D: AsRef<[u8]>>(
+        &mut self,
+        field: Field,
+        docs: impl Iterator<Item = crate::Result<D>>,
+    ) -> crate::Result<()> {
+        let write = self.composite_write.for_field(field);
+        let mut doc_offsets: Vec<u64> = vec![0];
+        let mut offset = 0u64;
+        for doc in docs {
+            let doc = doc?;
+            write.write_all(doc.as_ref())?;
+            offset += doc.as_ref().len() as u64;
+            doc_offsets.push(offset);
+        }
+        for &doc_offset in &doc_offsets {
+            doc_offset.serialize(write)?;
+        }
+        ((doc_offsets.len() - 1) as u32).serialize(write)?;
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use super::{serialize_term_vector, term_vector_record_option, Occurrence, TermVectorsSerializer};
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::{Field, IndexRecordOption, Schema};
+use crate::tokenizer::{Token, TokenStream, MAX_TOKEN_LEN};
+
+struct TermVectorFieldWriter {
+    record_option: IndexRecordOption,
+    /// Occurrences of the terms of the document being indexed.
+    occurrences: BTreeMap<String, Vec<Occurrence>>,
+    /// Serialized term vectors of the documents already indexed.
+    data: Vec<u8>,
+    /// Start of the term vector of each document in `data`.
+    doc_offsets: Vec<usize>,
+}
+
+impl TermVectorFieldWriter {
+    fn record(&mut self, token: &Token, base_position: u32, base_offset: u32) {
+        let occurrence = Occurrence {
+            position: base_position + token.position as u32,
+            offset_from: base_offset + token.offset_from as u32,
+            offset_to: base_offset + token.offset_to as u32,
+        };
+        if let Some(occurrences) = self.occurrences.get_mut(&token.text) {
+            occurrences.push(occurrence);
+        } else {
+            self.occurrences
+                .insert(token.text.clone(), vec![occurrence]);
+        }
+    }
+
+    fn mem_usage(&self) -> usize {
+        let occurrences_mem_usage: usize = self
+            .occurrences
+            .iter()
+            .map(|(text, occurrences)| {
+                std::mem::size_of::<(String, Vec<Occurrence>)>()
+                    + text.capacity()
+                    + occurrences.capacity() * std::mem::size_of::<Occurrence>()
+            })
+            .sum();
+        occurrences_mem_usage
+            + self.data.capacity()
+            + self.doc_offsets.capacity() * std::mem::size_of::<usize>()
+    }
+
+    fn doc_bytes(&self, doc_ord: usize) -> &[u8] {
+        let end = self
+            .doc_offsets
+            .get(doc_ord + 1)
+            .copied()
+            .unwrap_or(self.data.len());
+        &self.data[self.doc_offsets[doc_ord]..end]
+    }
+}
+
+/// The `TermVectorsWriter` is in charge of buffering the term vectors of each document
+/// for each field with term vectors.
+pub(crate) struct TermVectorsWriter {
+    field_writers: Vec<Option<TermVectorFieldWriter>>,
+}
+
+impl TermVectorsWriter {
+    /// Initialize with state for tracking the fields with term vectors
+    /// specified in the schema.
+    pub fn for_schema(schema: &Schema) -> TermVectorsWriter {
+        let field_writers = schema
+            .fields()
+            .map(|(_, field_entry)| {
+                term_vector_record_option(field_entry).map(|record_option| TermVectorFieldWriter {
+                    record_option,
+                    occurrences: BTreeMap::new(),
+                    data: Vec::new(),
+                    doc_offsets: Vec::new(),
+                })
+            })
+            .collect();
+        TermVectorsWriter { field_writers }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.field_writers
+            .iter()
+            .flatten()
+            .map(TermVectorFieldWriter::mem_usage)
+            .sum()
+    }
+
+    /// Wraps the token stream of a value of `field`, so that its tokens are recorded in the
+    /// term vector of the document being indexed, if the field has term vectors.
+    ///
+    /// `base_position` and `base_offset` are the position and the offset of the value
+    /// within the field, as tracked while indexing the postings.
+    pub fn token_stream<'a>(
+        &'a mut self,
+        field: Field,
+        token_stream: &'a mut dyn TokenStream,
+        base_position: u32,
+        base_offset: u32,
+    ) -> TermVectorTokenStream<'a> {
+        TermVectorTokenStream {
+            token_stream,
+            field_writer: self
+                .field_writers
+                .get_mut(field.field_id() as usize)
+                .and_then(Option::as_mut),
+            base_position,
+            base_offset,
+        }
+    }
+
+    /// Serializes the term vectors recorded for the document being indexed.
+    ///
+    /// This must be called once for each document, in doc id order.
+    pub fn end_document(&mut self) -> io::Result<()> {
+        for field_writer in self.field_writers.iter_mut().flatten() {
+            field_writer.doc_offsets.push(field_writer.data.len());
+            serialize_term_vector(
+                field_writer
+                    .occurrences
+                    .iter()
+                    .map(|(text, occurrences)| (text.as_str(), &occurrences[..])),
+                field_writer.record_option,
+                &mut field_writer.data,
+            )?;
+            field_writer.occurrences.clear();
+        }
+        Ok(())
+    }
+
+    /// Serialize the term vectors of all fields.
+    pub fn serialize(
+        &self,
+        mut term_vectors_serializer: TermVectorsSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> crate::Result<()> {
+        for (field_id, field_writer) in self.field_writers.iter().enumerate() {
+            let field_writer = if let Some(field_writer) = field_writer {
+                field_writer
+            } else {
+                continue;
+            };
+            let field = Field::from_field_id(field_id as u32);
+            if let Some(doc_id_map) = doc_id_map {
+                let docs = doc_id_map
+                    .iter_old_doc_ids()
+                    .map(|old_doc_id| Ok(field_writer.doc_bytes(old_doc_id as usize)));
+                term_vectors_serializer.serialize_field(field, docs)?;
+            } else {
+                let docs = (0..field_writer.doc_offsets.len())
+                    .map(|doc_ord| Ok(field_writer.doc_bytes(doc_ord)));
+                term_vectors_serializer.serialize_field(field, docs)?;
+            }
+        }
+        term_vectors_serializer.close()?;
+        Ok(())
+    }
+}
+
+/// Token stream recording the tokens it emits in the term vector of a document.
+///
+/// Tokens that are too long to be indexed are not recorded either.
+pub(crate) struct TermVectorTokenStream<'a> {
+    token_stream: &'a mut dyn TokenStream,
+    field_writer: Option<&'a mut TermVectorFieldWriter>,
+    base_position: u32,
+    base_offset: u32,
+}
+
+impl<'a> TokenStream for TermVectorTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.token_stream.advance() {
+            return false;
+        }
+        if let Some(field_writer) = self.field_writer.as_mut() {
+            let token = self.token_stream.token();
+            if token.text.len() <= MAX_TOKEN_LEN {
+                field_writer.record(token, self.base_position, self.base_offset);
+            }
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.token_stream.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.token_stream.token_mut()
+    }
+}
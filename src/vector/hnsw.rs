@@ -0,0 +1,547 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, Read, Write};
+
+use common::{BinarySerializable, BitSet, VInt};
+
+use super::distance;
+use crate::schema::VectorMetric;
+
+/// Highest layer a node can be assigned to.
+const MAX_LEVEL: usize = 16;
+
+/// Number of bytes of a serialized `f32`.
+const F32_NUM_BYTES: usize = 4;
+
+/// Serializes vectors the way [`FlatVectors`] reads them, as little endian `f32`s.
+pub(crate) fn vectors_to_bytes(vectors: &[f32]) -> Vec<u8> {
+    vectors.iter().flat_map(|val| val.to_le_bytes()).collect()
+}
+
+/// A set of vectors of the same dimension, addressed by their ordinal.
+///
+/// Vectors are read from their serialized form, so that a segment can be searched without
+/// decoding all of its vectors first.
+#[derive(Clone, Copy)]
+pub(crate) struct FlatVectors<'a> {
+    dims: usize,
+    data: &'a [u8],
+    metric: VectorMetric,
+}
+
+impl<'a> FlatVectors<'a> {
+    pub fn new(dims: usize, data: &'a [u8], metric: VectorMetric) -> FlatVectors<'a> {
+        assert_eq!(data.len() % (dims * F32_NUM_BYTES), 0);
+        FlatVectors { dims, data, metric }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / (self.dims * F32_NUM_BYTES)
+    }
+
+    pub fn get(&self, ord: u32) -> impl Iterator<Item = f32> + 'a {
+        let num_bytes = self.dims * F32_NUM_BYTES;
+        let start = ord as usize * num_bytes;
+        self.data[start..start + num_bytes]
+            .chunks_exact(F32_NUM_BYTES)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn distance_to(&self, query: &[f32], ord: u32) -> f32 {
+        distance(self.metric, query.iter().copied(), self.get(ord))
+    }
+
+    fn distance_between(&self, left_ord: u32, right_ord: u32) -> f32 {
+        distance(self.metric, self.get(left_ord), self.get(right_ord))
+    }
+}
+
+/// A candidate node, ordered by its distance to the query.
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    distance: f32,
+    ord: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.ord.cmp(&other.ord))
+    }
+}
+
+/// Hierarchical Navigable Small World graph.
+///
+/// Nodes are identified by the ordinal of their vector. `neighbors[ord][layer]` lists
+/// the neighbors of the node `ord` in the layer `layer`.
+///
+/// The layer of each node is derived from its ordinal, so building the graph of a given
+/// set of vectors is deterministic.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HnswGraph {
+    entry_point: Option<u32>,
+    neighbors: Vec<Vec<Vec<u32>>>,
+}
+
+/// Returns the layer of the node `ord`, following an exponentially decaying distribution.
+fn node_level(ord: u32, level_multiplier: f64) -> usize {
+    // splitmix64
+    let mut z = (ord as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // uniform in (0, 1]
+    let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    ((-uniform.ln() * level_multiplier) as usize).min(MAX_LEVEL)
+}
+
+impl HnswGraph {
+    /// Builds the graph of the given vectors.
+    ///
+    /// Nodes have at most `max_connections` neighbors, twice as many in the bottom layer.
+    pub fn build(
+        vectors: FlatVectors,
+        max_connections: usize,
+        ef_construction: usize,
+    ) -> HnswGraph {
+        let level_multiplier = 1.0 / (max_connections.max(2) as f64).ln();
+        let mut graph = HnswGraph {
+            entry_point: None,
+            neighbors: Vec::with_capacity(vectors.len()),
+        };
+        for ord in 0..vectors.len() as u32 {
+            let level = node_level(ord, level_multiplier);
+            graph.insert(vectors, ord, level, max_connections, ef_construction);
+        }
+        graph
+    }
+
+    fn max_level(&self) -> usize {
+        self.entry_point
+            .map(|entry_point| self.neighbors[entry_point as usize].len() - 1)
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of nodes of the graph.
+    pub fn num_nodes(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    /// Checks that searching the graph only visits existing nodes and layers.
+    fn validate(&self) -> io::Result<()> {
+        match self.entry_point {
+            Some(entry_point) if entry_point as usize >= self.neighbors.len() => {
+                return Err(invalid_graph("invalid entry point"));
+            }
+            None if !self.neighbors.is_empty() => {
+                return Err(invalid_graph("missing entry point"));
+            }
+            _ => {}
+        }
+        for layers in &self.neighbors {
+            for (layer, neighbors) in layers.iter().enumerate() {
+                if neighbors
+                    .iter()
+                    .any(|&neighbor| self.neighbors[neighbor as usize].len() <= layer)
+                {
+                    return Err(invalid_graph("neighbor missing from its layer"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(
+        &mut self,
+        vectors: FlatVectors,
+        ord: u32,
+        level: usize,
+        max_connections: usize,
+        ef_construction: usize,
+    ) {
+        self.neighbors.push(vec![Vec::new(); level + 1]);
+        let entry_point = if let Some(entry_point) = self.entry_point {
+            entry_point
+        } else {
+            self.entry_point = Some(ord);
+            return;
+        };
+        let query: &[f32] = &vectors.get(ord).collect::<Vec<f32>>();
+        let max_level = self.max_level();
+        let mut entry_points = vec![Candidate {
+            distance: vectors.distance_to(query, entry_point),
+            ord: entry_point,
+        }];
+        for layer in (level + 1..=max_level).rev() {
+            entry_points = self.search_layer(vectors, query, &entry_points, 1, layer, |_| true);
+        }
+        for layer in (0..=level.min(max_level)).rev() {
+            let candidates = self.search_layer(
+                vectors,
+                query,
+                &entry_points,
+                ef_construction,
+                layer,
+                |_| true,
+            );
+            let max_neighbors = max_neighbors(max_connections, layer);
+            let selected = select_neighbors(vectors, &candidates, max_neighbors);
+            for neighbor in &selected {
+                let neighbor_ord = neighbor.ord;
+                let neighbor_neighbors = &mut self.neighbors[neighbor_ord as usize][layer];
+                neighbor_neighbors.push(ord);
+                if neighbor_neighbors.len() > max_neighbors {
+                    let mut neighbor_candidates: Vec<Candidate> = neighbor_neighbors
+                        .iter()
+                        .map(|&other_ord| Candidate {
+                            distance: vectors.distance_between(neighbor_ord, other_ord),
+                            ord: other_ord,
+                        })
+                        .collect();
+                    neighbor_candidates.sort_unstable();
+                    *neighbor_neighbors =
+                        select_neighbors(vectors, &neighbor_candidates, max_neighbors)
+                            .iter()
+                            .map(|candidate| candidate.ord)
+                            .collect();
+                }
+            }
+            self.neighbors[ord as usize][layer] =
+                selected.iter().map(|candidate| candidate.ord).collect();
+            entry_points = candidates;
+        }
+        if level > max_level {
+            self.entry_point = Some(ord);
+        }
+    }
+
+    /// Greedy search of the `ef` closest nodes in a layer.
+    ///
+    /// Only the nodes accepted by `filter` are returned, but all of the nodes are used
+    /// to navigate the graph. The result is sorted by increasing distance.
+    fn search_layer(
+        &self,
+        vectors: FlatVectors,
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<Candidate> {
+        let mut visited = BitSet::with_max_value(self.neighbors.len() as u32);
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+        for &entry_point in entry_points {
+            if visited.contains(entry_point.ord) {
+                continue;
+            }
+            visited.insert(entry_point.ord);
+            candidates.push(Reverse(entry_point));
+            if filter(entry_point.ord) {
+                results.push(entry_point);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+        while let Some(Reverse(candidate)) = candidates.pop() {
+            if results.len() >= ef {
+                if let Some(furthest) = results.peek() {
+                    if candidate.distance > furthest.distance {
+                        break;
+                    }
+                }
+            }
+            for &neighbor_ord in &self.neighbors[candidate.ord as usize][layer] {
+                if visited.contains(neighbor_ord) {
+                    continue;
+                }
+                visited.insert(neighbor_ord);
+                let neighbor = Candidate {
+                    distance: vectors.distance_to(query, neighbor_ord),
+                    ord: neighbor_ord,
+                };
+                let is_competitive = results.len() < ef
+                    || results
+                        .peek()
+                        .map(|furthest| neighbor.distance < furthest.distance)
+                        .unwrap_or(true);
+                if is_competitive {
+                    candidates.push(Reverse(neighbor));
+                    if filter(neighbor_ord) {
+                        results.push(neighbor);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Returns the (at most) `ef` nodes closest to `query` accepted by `filter`,
+    /// as `(distance, ord)` pairs sorted by increasing distance.
+    pub fn search(
+        &self,
+        vectors: FlatVectors,
+        query: &[f32],
+        ef: usize,
+        filter: impl Fn(u32) -> bool,
+    ) -> Vec<(f32, u32)> {
+        let entry_point = if let Some(entry_point) = self.entry_point {
+            entry_point
+        } else {
+            return Vec::new();
+        };
+        let mut entry_points = vec![Candidate {
+            distance: vectors.distance_to(query, entry_point),
+            ord: entry_point,
+        }];
+        for layer in (1..=self.max_level()).rev() {
+            entry_points = self.search_layer(vectors, query, &entry_points, 1, layer, |_| true);
+        }
+        self.search_layer(vectors, query, &entry_points, ef.max(1), 0, filter)
+            .into_iter()
+            .map(|candidate| (candidate.distance, candidate.ord))
+            .collect()
+    }
+}
+
+fn max_neighbors(max_connections: usize, layer: usize) -> usize {
+    if layer == 0 {
+        2 * max_connections
+    } else {
+        max_connections
+    }
+}
+
+/// Selects the neighbors of a node among `candidates`, sorted by increasing distance.
+///
+/// A candidate is preferred if it is closer to the node than to any of the
+/// already selected neighbors, which keeps the graph navigable across clusters.
+/// The remaining slots are filled with the closest candidates.
+fn select_neighbors(
+    vectors: FlatVectors,
+    candidates: &[Candidate],
+    max_neighbors: usize,
+) -> Vec<Candidate> {
+    let mut selected: Vec<Candidate> = Vec::with_capacity(max_neighbors);
+    let mut pruned: Vec<Candidate> = Vec::new();
+    for &candidate in candidates {
+        if selected.len() >= max_neighbors {
+            break;
+        }
+        let is_diverse = selected.iter().all(|selected_candidate| {
+            vectors.distance_between(candidate.ord, selected_candidate.ord) > candidate.distance
+        });
+        if is_diverse {
+            selected.push(candidate);
+        } else {
+            pruned.push(candidate);
+        }
+    }
+    let num_missing = max_neighbors.saturating_sub(selected.len());
+    selected.extend(pruned.into_iter().take(num_missing));
+    selected
+}
+
+impl BinarySerializable for HnswGraph {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.entry_point.unwrap_or(u32::MAX).serialize(writer)?;
+        VInt(self.neighbors.len() as u64).serialize(writer)?;
+        for layers in &self.neighbors {
+            VInt(layers.len() as u64).serialize(writer)?;
+            for neighbors in layers {
+                VInt(neighbors.len() as u64).serialize(writer)?;
+                for &neighbor in neighbors {
+                    VInt(neighbor as u64).serialize(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let entry_point = Some(u32::deserialize(reader)?).filter(|&ord| ord != u32::MAX);
+        let num_nodes = VInt::deserialize(reader)?.val();
+        if num_nodes > u32::MAX as u64 {
+            return Err(invalid_graph("too many nodes"));
+        }
+        // The lengths are not trusted to size the buffers, the file may be corrupted.
+        let mut neighbors = Vec::new();
+        for _ in 0..num_nodes {
+            let num_layers = VInt::deserialize(reader)?.val();
+            if num_layers == 0 || num_layers > MAX_LEVEL as u64 + 1 {
+                return Err(invalid_graph("invalid number of layers"));
+            }
+            let mut layers = Vec::with_capacity(num_layers as usize);
+            for _ in 0..num_layers {
+                let num_neighbors = VInt::deserialize(reader)?.val();
+                let mut layer = Vec::new();
+                for _ in 0..num_neighbors {
+                    let neighbor = VInt::deserialize(reader)?.val();
+                    if neighbor >= num_nodes {
+                        return Err(invalid_graph("invalid node"));
+                    }
+                    layer.push(neighbor as u32);
+                }
+                layers.push(layer);
+            }
+            neighbors.push(layers);
+        }
+        let graph = HnswGraph {
+            entry_point,
+            neighbors,
+        };
+        graph.validate()?;
+        Ok(graph)
+    }
+}
+
+fn invalid_graph(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid HNSW graph: {reason}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{BinarySerializable, VInt};
+    use rand::{Rng, SeedableRng};
+
+    use super::{vectors_to_bytes, FlatVectors, HnswGraph};
+    use crate::schema::VectorMetric;
+    use crate::vector::distance;
+
+    fn random_vectors(num_vectors: usize, dims: usize, seed: u64) -> Vec<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..num_vectors * dims)
+            .map(|_| rng.gen_range(-1.0f32..1.0f32))
+            .collect()
+    }
+
+    fn exact_search(vectors: FlatVectors, query: &[f32], k: usize) -> Vec<u32> {
+        let mut distances: Vec<(f32, u32)> = (0..vectors.len() as u32)
+            .map(|ord| {
+                (
+                    distance(
+                        VectorMetric::Euclidean,
+                        query.iter().copied(),
+                        vectors.get(ord),
+                    ),
+                    ord,
+                )
+            })
+            .collect();
+        distances.sort_by(|left, right| left.0.total_cmp(&right.0));
+        distances.into_iter().take(k).map(|(_, ord)| ord).collect()
+    }
+
+    #[test]
+    fn test_hnsw_recall() {
+        let dims = 8;
+        let data = vectors_to_bytes(&random_vectors(1_000, dims, 1));
+        let vectors = FlatVectors::new(dims, &data, VectorMetric::Euclidean);
+        let graph = HnswGraph::build(vectors, 16, 100);
+        let queries = random_vectors(50, dims, 2);
+        let mut num_found = 0;
+        for query in queries.chunks(dims) {
+            let expected = exact_search(vectors, query, 10);
+            let found: Vec<u32> = graph
+                .search(vectors, query, 50, |_| true)
+                .into_iter()
+                .take(10)
+                .map(|(_, ord)| ord)
+                .collect();
+            num_found += expected.iter().filter(|ord| found.contains(ord)).count();
+        }
+        // Recall@10 over 50 queries.
+        assert!(num_found >= 450, "recall too low: {num_found} / 500");
+    }
+
+    #[test]
+    fn test_hnsw_filter_and_serialization() {
+        let dims = 4;
+        let data = random_vectors(300, dims, 3);
+        let data_bytes = vectors_to_bytes(&data);
+        let vectors = FlatVectors::new(dims, &data_bytes, VectorMetric::Euclidean);
+        let graph = HnswGraph::build(vectors, 8, 50);
+        let query = &data[10 * dims..11 * dims];
+        let results = graph.search(vectors, query, 20, |ord| ord % 2 == 1);
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|(_, ord)| ord % 2 == 1));
+        assert!(results.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        let exact_search_results = graph.search(vectors, query, 1, |_| true);
+        assert_eq!(exact_search_results, vec![(0.0, 10)]);
+
+        let mut buffer = Vec::new();
+        graph.serialize(&mut buffer).unwrap();
+        assert_eq!(HnswGraph::deserialize(&mut &buffer[..]).unwrap(), graph);
+        assert_eq!(HnswGraph::build(vectors, 8, 50), graph);
+    }
+
+    #[test]
+    fn test_hnsw_empty() {
+        let vectors = FlatVectors::new(3, &[], VectorMetric::Cosine);
+        let graph = HnswGraph::build(vectors, 8, 50);
+        assert!(graph
+            .search(vectors, &[1.0, 0.0, 0.0], 10, |_| true)
+            .is_empty());
+        let mut buffer = Vec::new();
+        graph.serialize(&mut buffer).unwrap();
+        assert_eq!(HnswGraph::deserialize(&mut &buffer[..]).unwrap(), graph);
+    }
+
+    fn deserialize_graph(entry_point: u32, nodes: &[&[&[u64]]]) -> std::io::Result<HnswGraph> {
+        let mut buffer = Vec::new();
+        entry_point.serialize(&mut buffer)?;
+        VInt(nodes.len() as u64).serialize(&mut buffer)?;
+        for layers in nodes {
+            VInt(layers.len() as u64).serialize(&mut buffer)?;
+            for neighbors in layers.iter() {
+                VInt(neighbors.len() as u64).serialize(&mut buffer)?;
+                for &neighbor in neighbors.iter() {
+                    VInt(neighbor).serialize(&mut buffer)?;
+                }
+            }
+        }
+        HnswGraph::deserialize(&mut &buffer[..])
+    }
+
+    #[test]
+    fn test_hnsw_deserialize_corrupted() {
+        assert!(deserialize_graph(0, &[&[&[1]], &[&[0]]]).is_ok());
+        // A huge number of nodes does not allocate them upfront.
+        let mut buffer = Vec::new();
+        0u32.serialize(&mut buffer).unwrap();
+        VInt(1 << 31).serialize(&mut buffer).unwrap();
+        assert!(HnswGraph::deserialize(&mut &buffer[..]).is_err());
+        // The entry point is not a node.
+        assert!(deserialize_graph(2, &[&[&[1]], &[&[0]]]).is_err());
+        // The graph has nodes but no entry point.
+        assert!(deserialize_graph(u32::MAX, &[&[&[]]]).is_err());
+        // A node without layers.
+        assert!(deserialize_graph(0, &[&[&[1]], &[]]).is_err());
+        // A neighbor which is not a node.
+        assert!(deserialize_graph(0, &[&[&[1]], &[&[2]]]).is_err());
+        // A neighbor in a layer it is not part of.
+        assert!(deserialize_graph(0, &[&[&[1], &[1]], &[&[0]]]).is_err());
+    }
+}
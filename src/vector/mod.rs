@@ -0,0 +1,129 @@
+//! Dense vector fields and approximate nearest neighbor search.
+//!
+//! Vectors are kept in the `.vec` file of each segment, along with a
+//! [HNSW](https://arxiv.org/abs/1603.09320) graph built when the segment is
+//! serialized. Merging segments rebuilds the graph from the vectors of the alive
+//! documents.
+//!
+//! For each vector field, the file contains the number of dimensions, the sorted
+//! list of documents having a vector, the vectors themselves (normalized for
+//! [`VectorMetric::Cosine`]) and the graph.
+//!
+//! Vector fields are searched with the [`KnnQuery`](crate::query::KnnQuery).
+mod hnsw;
+mod reader;
+mod serializer;
+mod writer;
+
+pub use self::reader::{VectorReader, VectorReaders};
+pub use self::serializer::VectorsSerializer;
+pub(crate) use self::writer::{validate_document_vectors, VectorsWriter};
+use crate::schema::VectorMetric;
+use crate::Score;
+
+fn dot_product(left: impl IntoIterator<Item = f32>, right: impl IntoIterator<Item = f32>) -> f32 {
+    left.into_iter()
+        .zip(right)
+        .map(|(left, right)| left * right)
+        .sum()
+}
+
+/// Normalizes the vector if the metric requires it.
+pub(crate) fn prepare_vector(metric: VectorMetric, vector: &mut [f32]) {
+    if metric == VectorMetric::Cosine {
+        let norm = dot_product(vector.iter().copied(), vector.iter().copied()).sqrt();
+        if norm > 0.0 {
+            for val in vector.iter_mut() {
+                *val /= norm;
+            }
+        }
+    }
+}
+
+/// Distance between two prepared vectors. Lower is closer.
+pub(crate) fn distance(
+    metric: VectorMetric,
+    left: impl IntoIterator<Item = f32>,
+    right: impl IntoIterator<Item = f32>,
+) -> f32 {
+    match metric {
+        VectorMetric::Cosine | VectorMetric::DotProduct => -dot_product(left, right),
+        VectorMetric::Euclidean => left
+            .into_iter()
+            .zip(right)
+            .map(|(left, right)| (left - right) * (left - right))
+            .sum(),
+    }
+}
+
+/// Converts a distance into a score. Higher is closer.
+pub(crate) fn distance_to_score(metric: VectorMetric, distance: f32) -> Score {
+    match metric {
+        VectorMetric::Cosine => ((1.0 - distance) / 2.0).clamp(0.0, 1.0),
+        VectorMetric::DotProduct => -distance,
+        VectorMetric::Euclidean => 1.0 / (1.0 + distance),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, distance_to_score, prepare_vector, VectorReaders};
+    use crate::schema::{Schema, VectorMetric, VectorOptions};
+    use crate::{Index, IndexWriter, SegmentComponent, TantivyError};
+
+    #[test]
+    fn test_vector_scores() {
+        let mut left = vec![3.0, 4.0];
+        let mut right = vec![-6.0, -8.0];
+        prepare_vector(VectorMetric::Cosine, &mut left);
+        prepare_vector(VectorMetric::Cosine, &mut right);
+        assert_eq!(left, vec![0.6, 0.8]);
+        let cosine_distance = distance(VectorMetric::Cosine, left, right);
+        assert!(distance_to_score(VectorMetric::Cosine, cosine_distance).abs() < 1e-6);
+        assert_eq!(
+            distance_to_score(
+                VectorMetric::DotProduct,
+                distance(VectorMetric::DotProduct, [1.0, 2.0], [3.0, 4.0])
+            ),
+            11.0
+        );
+        assert_eq!(
+            distance_to_score(
+                VectorMetric::Euclidean,
+                distance(VectorMetric::Euclidean, [1.0, 2.0], [2.0, 2.0])
+            ),
+            0.5
+        );
+    }
+
+    #[test]
+    fn test_vector_reader_dims_mismatch() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder
+            .add_vector_field("embedding", VectorOptions::new(3, VectorMetric::Euclidean));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(embedding => vec![1.0f32, 2.0, 3.0]))?;
+        index_writer.commit()?;
+        let segment = &index.searchable_segments()?[0];
+
+        let mut schema_builder = Schema::builder();
+        schema_builder
+            .add_vector_field("embedding", VectorOptions::new(2, VectorMetric::Euclidean));
+        let vector_readers = VectorReaders::open(
+            Some(segment.open_read(SegmentComponent::Vectors)?),
+            schema_builder.build(),
+        )?;
+        assert!(matches!(
+            vector_readers.get_field(embedding),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        assert!(VectorReaders::open(
+            Some(segment.open_read(SegmentComponent::Vectors)?),
+            index.schema(),
+        )?
+        .get_field(embedding)?
+        .is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use common::{BinarySerializable, FixedSize, OwnedBytes};
+
+use super::hnsw::{FlatVectors, HnswGraph};
+use super::{distance, distance_to_score, prepare_vector};
+use crate::directory::{CompositeFile, FileSlice};
+use crate::error::DataCorruption;
+use crate::fastfield::AliveBitSet;
+use crate::schema::{Field, FieldType, Schema, VectorMetric};
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::{DocId, Score};
+
+/// Reader for the vectors of all of the vector fields of a segment.
+///
+/// The graph of a field is decoded the first time it is accessed, and kept in
+/// memory afterwards. Vectors are read from the file when needed.
+#[derive(Clone)]
+pub struct VectorReaders {
+    data: Arc<CompositeFile>,
+    schema: Schema,
+    cache: Arc<RwLock<HashMap<Field, Arc<VectorReader>>>>,
+}
+
+impl VectorReaders {
+    /// Creates a vector reader.
+    ///
+    /// Segments written before vector fields existed do not have a vector file. Such
+    /// segments are opened with an empty file.
+    pub fn open(file: Option<FileSlice>, schema: Schema) -> crate::Result<VectorReaders> {
+        let data = if let Some(file) = file {
+            CompositeFile::open(&file)?
+        } else {
+            CompositeFile::empty()
+        };
+        Ok(VectorReaders {
+            data: Arc::new(data),
+            schema,
+            cache: Default::default(),
+        })
+    }
+
+    /// Returns the `VectorReader` for a specific field.
+    ///
+    /// Returns `None` if the field is not a vector field or if no document of the
+    /// segment has a vector in this field.
+    pub fn get_field(&self, field: Field) -> crate::Result<Option<Arc<VectorReader>>> {
+        if let Some(vector_reader) = self
+            .cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&field)
+        {
+            return Ok(Some(Arc::clone(vector_reader)));
+        }
+        let vector_options = match self.schema.get_field_entry(field).field_type() {
+            FieldType::Vector(vector_options) => vector_options,
+            _ => return Ok(None),
+        };
+        let file = if let Some(file) = self.data.open_read(field) {
+            file
+        } else {
+            return Ok(None);
+        };
+        let vector_reader = Arc::new(VectorReader::open(
+            file,
+            vector_options.dims(),
+            vector_options.metric(),
+        )?);
+        self.cache
+            .write()
+            .expect("Field reader cache lock poisoned. This should never happen.")
+            .insert(field, Arc::clone(&vector_reader));
+        Ok(Some(vector_reader))
+    }
+
+    /// Return a break down of the space usage per field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.data.space_usage()
+    }
+}
+
+/// Reads the vectors of a given field of a segment, and searches their
+/// nearest neighbors.
+pub struct VectorReader {
+    metric: VectorMetric,
+    dims: usize,
+    num_vectors: usize,
+    doc_ids: OwnedBytes,
+    vectors: OwnedBytes,
+    graph: HnswGraph,
+}
+
+impl VectorReader {
+    fn open(
+        file: FileSlice,
+        expected_dims: usize,
+        metric: VectorMetric,
+    ) -> crate::Result<VectorReader> {
+        let mut bytes = file.read_bytes()?;
+        if bytes.len() < 8 {
+            return Err(DataCorruption::comment_only("Vector file is truncated").into());
+        }
+        let dims = bytes.read_u32() as usize;
+        if dims != expected_dims {
+            return Err(DataCorruption::comment_only(format!(
+                "Vector file has {dims} dimensions, the schema expects {expected_dims}"
+            ))
+            .into());
+        }
+        let num_vectors = bytes.read_u32() as usize;
+        let num_doc_id_bytes = num_vectors * DocId::SIZE_IN_BYTES;
+        let num_vector_bytes = num_vectors * dims * f32::SIZE_IN_BYTES;
+        if dims == 0 || bytes.len() < num_doc_id_bytes + num_vector_bytes {
+            return Err(DataCorruption::comment_only("Vector file is truncated").into());
+        }
+        let (doc_ids, bytes) = bytes.split(num_doc_id_bytes);
+        let (vectors, graph_bytes) = bytes.split(num_vector_bytes);
+        let graph = HnswGraph::deserialize(&mut graph_bytes.as_slice())
+            .map_err(DataCorruption::comment_only)?;
+        if graph.num_nodes() != num_vectors {
+            return Err(DataCorruption::comment_only(format!(
+                "HNSW graph has {} nodes for {num_vectors} vectors",
+                graph.num_nodes()
+            ))
+            .into());
+        }
+        Ok(VectorReader {
+            metric,
+            dims,
+            num_vectors,
+            doc_ids,
+            vectors,
+            graph,
+        })
+    }
+
+    fn flat_vectors(&self) -> FlatVectors<'_> {
+        FlatVectors::new(self.dims, self.vectors.as_slice(), self.metric)
+    }
+
+    fn doc_id(&self, ord: usize) -> DocId {
+        let start = ord * DocId::SIZE_IN_BYTES;
+        let bytes = &self.doc_ids.as_slice()[start..start + DocId::SIZE_IN_BYTES];
+        DocId::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    /// Returns the ordinal of the vector of a document, if it has one.
+    fn ord(&self, doc: DocId) -> Option<u32> {
+        let (mut start, mut end) = (0, self.num_vectors);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            match self.doc_id(mid).cmp(&doc) {
+                Ordering::Less => start = mid + 1,
+                Ordering::Greater => end = mid,
+                Ordering::Equal => return Some(mid as u32),
+            }
+        }
+        None
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Returns the metric used to compare vectors.
+    pub fn metric(&self) -> VectorMetric {
+        self.metric
+    }
+
+    /// Returns the number of documents having a vector, including deleted documents.
+    pub fn num_vectors(&self) -> usize {
+        self.num_vectors
+    }
+
+    /// Returns the sorted documents having a vector, including deleted documents.
+    pub fn doc_ids(&self) -> impl Iterator<Item = DocId> + '_ {
+        (0..self.num_vectors).map(|ord| self.doc_id(ord))
+    }
+
+    /// Returns the values of the vector of a document, if it has one.
+    ///
+    /// With [`VectorMetric::Cosine`], the returned vector is normalized.
+    pub fn vector(&self, doc: DocId) -> Option<impl Iterator<Item = f32> + '_> {
+        let ord = self.ord(doc)?;
+        Some(self.flat_vectors().get(ord))
+    }
+
+    /// Computes the exact score of a document for the given query vector.
+    ///
+    /// Returns `None` if the document has no vector.
+    pub fn score(&self, doc: DocId, query: &[f32]) -> Option<Score> {
+        assert_eq!(
+            query.len(),
+            self.dims,
+            "Query vector has the wrong dimension"
+        );
+        let mut query = query.to_vec();
+        prepare_vector(self.metric, &mut query);
+        let vector = self.vector(doc)?;
+        Some(distance_to_score(
+            self.metric,
+            distance(self.metric, query, vector),
+        ))
+    }
+
+    /// Searches the (approximate) `k` nearest neighbors of the query vector.
+    ///
+    /// `ef` is the size of the candidate list: higher values improve recall at the
+    /// expense of speed. It cannot be lower than `k`. Documents rejected by the
+    /// alive bitset are skipped during the search, so up to `k` alive documents are returned.
+    ///
+    /// The result is sorted by decreasing score.
+    ///
+    /// # Panics
+    /// Panics if the query vector does not have the dimension of the field.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef: usize,
+        alive_bitset: Option<&AliveBitSet>,
+    ) -> Vec<(DocId, Score)> {
+        assert_eq!(
+            query.len(),
+            self.dims,
+            "Query vector has the wrong dimension"
+        );
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut query = query.to_vec();
+        prepare_vector(self.metric, &mut query);
+        let is_alive = |ord: u32| {
+            alive_bitset
+                .map(|alive_bitset| alive_bitset.is_alive(self.doc_id(ord as usize)))
+                .unwrap_or(true)
+        };
+        let mut results = self
+            .graph
+            .search(self.flat_vectors(), &query, ef.max(k), is_alive);
+        results.truncate(k);
+        results
+            .into_iter()
+            .map(|(distance, ord)| {
+                (
+                    self.doc_id(ord as usize),
+                    distance_to_score(self.metric, distance),
+                )
+            })
+            .collect()
+    }
+}
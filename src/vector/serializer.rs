@@ -0,0 +1,60 @@
+use std::io;
+use std::io::Write;
+
+use common::BinarySerializable;
+
+use super::hnsw::{vectors_to_bytes, FlatVectors, HnswGraph};
+use crate::directory::{CompositeWrite, WritePtr};
+use crate::schema::{Field, VectorOptions};
+use crate::DocId;
+
+/// The vectors serializer is in charge of
+/// the serialization of the vectors and their graph for all vector fields.
+pub struct VectorsSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl VectorsSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<VectorsSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(VectorsSerializer { composite_write })
+    }
+
+    /// Serialize the vectors of the given field and build their graph.
+    ///
+    /// `doc_ids` must be strictly increasing, and `vectors` holds the vector of each of these
+    /// documents, one after the other, already normalized if the metric requires it.
+    pub(crate) fn serialize_field(
+        &mut self,
+        field: Field,
+        options: &VectorOptions,
+        doc_ids: &[DocId],
+        vectors: &[f32],
+    ) -> io::Result<()> {
+        debug_assert!(doc_ids.windows(2).all(|pair| pair[0] < pair[1]));
+        debug_assert_eq!(doc_ids.len() * options.dims(), vectors.len());
+        let vector_bytes = vectors_to_bytes(vectors);
+        let graph = HnswGraph::build(
+            FlatVectors::new(options.dims(), &vector_bytes, options.metric()),
+            options.max_connections(),
+            options.ef_construction(),
+        );
+        let write = self.composite_write.for_field(field);
+        (options.dims() as u32).serialize(write)?;
+        (doc_ids.len() as u32).serialize(write)?;
+        for &doc_id in doc_ids {
+            doc_id.serialize(write)?;
+        }
+        write.write_all(&vector_bytes)?;
+        graph.serialize(write)?;
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use std::{io, iter};
+
+use super::{prepare_vector, VectorsSerializer};
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::{Field, FieldType, Schema, Value, VectorOptions};
+use crate::{DocId, Document, TantivyError};
+
+struct VectorFieldWriter {
+    options: VectorOptions,
+    doc_ids: Vec<DocId>,
+    vectors: Vec<f32>,
+}
+
+/// Returns the vectors of a document, by field.
+///
+/// `vector_options` returns the options of the vector fields. Each vector field accepts at most
+/// one vector, of the dimension defined in its options.
+pub(crate) fn document_vectors<'a, 'b>(
+    doc: &'a Document,
+    vector_options: impl Fn(Field) -> Option<&'b VectorOptions>,
+) -> crate::Result<Vec<(Field, &'a [f32])>> {
+    let mut vector_values: Vec<(Field, &[f32])> = Vec::new();
+    for field_value in doc.field_values() {
+        let options = if let Some(options) = vector_options(field_value.field()) {
+            options
+        } else {
+            continue;
+        };
+        let vector = match field_value.value() {
+            Value::Vector(vector) => vector,
+            _ => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Expected a vector for field {:?}",
+                    field_value.field()
+                )))
+            }
+        };
+        if vector.len() != options.dims() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Expected a vector of dimension {} for field {:?}, got {}",
+                options.dims(),
+                field_value.field(),
+                vector.len()
+            )));
+        }
+        if vector_values
+            .iter()
+            .any(|(field, _)| *field == field_value.field())
+        {
+            return Err(TantivyError::InvalidArgument(format!(
+                "A document can only have one vector for field {:?}",
+                field_value.field()
+            )));
+        }
+        vector_values.push((field_value.field(), vector));
+    }
+    Ok(vector_values)
+}
+
+/// Checks the vectors of a document against the vector fields of the schema, see
+/// [`document_vectors`].
+pub(crate) fn validate_document_vectors(schema: &Schema, doc: &Document) -> crate::Result<()> {
+    document_vectors(doc, |field| {
+        match schema.get_field_entry(field).field_type() {
+            FieldType::Vector(options) => Some(options),
+            _ => None,
+        }
+    })
+    .map(|_| ())
+}
+
+/// The `VectorsWriter` is in charge of buffering the vectors of each document
+/// for each vector field.
+pub(crate) struct VectorsWriter {
+    field_writers: Vec<Option<VectorFieldWriter>>,
+}
+
+impl VectorsWriter {
+    /// Initialize with state for tracking the vector fields
+    /// specified in the schema.
+    pub fn for_schema(schema: &Schema) -> VectorsWriter {
+        let mut field_writers: Vec<Option<VectorFieldWriter>> = iter::repeat_with(|| None)
+            .take(schema.num_fields())
+            .collect();
+        for (field, field_entry) in schema.fields() {
+            if let FieldType::Vector(options) = field_entry.field_type() {
+                field_writers[field.field_id() as usize] = Some(VectorFieldWriter {
+                    options: options.clone(),
+                    doc_ids: Vec::new(),
+                    vectors: Vec::new(),
+                });
+            }
+        }
+        VectorsWriter { field_writers }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.field_writers
+            .iter()
+            .flatten()
+            .map(|field_writer| {
+                field_writer.doc_ids.capacity() * std::mem::size_of::<DocId>()
+                    + field_writer.vectors.capacity() * std::mem::size_of::<f32>()
+            })
+            .sum()
+    }
+
+    /// Records the vectors of a document.
+    ///
+    /// The document is validated before anything is recorded, see [`document_vectors`].
+    pub fn add_document(&mut self, doc_id: DocId, doc: &Document) -> crate::Result<()> {
+        let vector_values = document_vectors(doc, |field| {
+            self.field_writers
+                .get(field.field_id() as usize)
+                .and_then(Option::as_ref)
+                .map(|field_writer| &field_writer.options)
+        })?;
+        for (field, vector) in vector_values {
+            if let Some(field_writer) = self.field_writers[field.field_id() as usize].as_mut() {
+                let start = field_writer.vectors.len();
+                field_writer.doc_ids.push(doc_id);
+                field_writer.vectors.extend_from_slice(vector);
+                prepare_vector(
+                    field_writer.options.metric(),
+                    &mut field_writer.vectors[start..],
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the vectors of all fields and build their graphs.
+    pub fn serialize(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> io::Result<()> {
+        for (field_id, field_writer) in self.field_writers.iter().enumerate() {
+            let field_writer = if let Some(field_writer) = field_writer {
+                field_writer
+            } else {
+                continue;
+            };
+            let field = Field::from_field_id(field_id as u32);
+            if let Some(doc_id_map) = doc_id_map {
+                let dims = field_writer.options.dims();
+                let mut new_doc_ords: Vec<(DocId, usize)> = field_writer
+                    .doc_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(ord, &doc_id)| (doc_id_map.get_new_doc_id(doc_id), ord))
+                    .collect();
+                new_doc_ords.sort_unstable();
+                let doc_ids: Vec<DocId> = new_doc_ords.iter().map(|(doc_id, _)| *doc_id).collect();
+                let mut vectors = Vec::with_capacity(field_writer.vectors.len());
+                for (_, ord) in new_doc_ords {
+                    vectors.extend_from_slice(&field_writer.vectors[ord * dims..(ord + 1) * dims]);
+                }
+                vectors_serializer.serialize_field(
+                    field,
+                    &field_writer.options,
+                    &doc_ids,
+                    &vectors,
+                )?;
+            } else {
+                vectors_serializer.serialize_field(
+                    field,
+                    &field_writer.options,
+                    &field_writer.doc_ids,
+                    &field_writer.vectors,
+                )?;
+            }
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+}